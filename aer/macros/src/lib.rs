@@ -42,10 +42,5 @@ mod subcommand;
 
 #[proc_macro]
 pub fn subcommand(input: TokenStream) -> TokenStream {
-    subcommand::subcommand(input, true)
-}
-
-#[proc_macro]
-pub fn subcommand_for_dev_only(input: TokenStream) -> TokenStream {
-    subcommand::subcommand(input, false)
+    subcommand::subcommand(input)
 }
\ No newline at end of file
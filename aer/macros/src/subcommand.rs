@@ -95,7 +95,7 @@ impl Parse for FlagInput {
     }
 }
 
-pub fn subcommand(input: TokenStream, panic_on_issue: bool) -> TokenStream {
+pub fn subcommand(input: TokenStream) -> TokenStream {
     let SubcommandInput { file_path, module, service_name, commands } =
         parse_macro_input!(input);
 
@@ -113,9 +113,12 @@ pub fn subcommand(input: TokenStream, panic_on_issue: bool) -> TokenStream {
         .find(|s| matches!(s.name(), n if service_name == n))
         .expect("failed to find gRPC service");
 
+    // Client streaming requests can't be built from command line flags,
+    // so they are left to hand written commands (e.g., `aer cp`).
     let commands: Vec<_> = service
         .method
         .iter()
+        .filter(|m| !m.client_streaming())
         .map(|m| {
             let method_name = m.name();
 
@@ -143,38 +146,35 @@ pub fn subcommand(input: TokenStream, panic_on_issue: bool) -> TokenStream {
                     fields: vec![],
                 }
             } else {
-                let fields: Vec<_> = resolve_fields(
-                    file_path_span,
-                    &proto,
-                    input_type_message,
-                    panic_on_issue,
-                )
-                .into_iter()
-                .map(|mut f| {
-                    let attribute = command
-                        .map(|c| {
-                            c.flags.as_ref().map(|flags| {
-                                flags.iter().find_map(|flag| {
-                                    if f.get_resolved_field_ident() == flag.name
-                                    {
-                                        Some(&flag.attribute)
-                                    } else {
-                                        None
-                                    }
+                let fields: Vec<_> =
+                    resolve_fields(file_path_span, &proto, input_type_message)
+                        .into_iter()
+                        .map(|mut f| {
+                            let attribute = command
+                                .map(|c| {
+                                    c.flags.as_ref().map(|flags| {
+                                        flags.iter().find_map(|flag| {
+                                            if f.get_resolved_field_ident()
+                                                == flag.name
+                                            {
+                                                Some(&flag.attribute)
+                                            } else {
+                                                None
+                                            }
+                                        })
+                                    })
                                 })
-                            })
+                                .unwrap_or(None)
+                                .unwrap_or(None)
+                                .map_or_else(
+                                    || quote! { #[arg(long)] },
+                                    |t| quote! { #[arg(#t)]},
+                                );
+
+                            f.attribute = attribute;
+                            f
                         })
-                        .unwrap_or(None)
-                        .unwrap_or(None)
-                        .map_or_else(
-                            || quote! { #[arg(long)] },
-                            |t| quote! { #[arg(#t)]},
-                        );
-
-                    f.attribute = attribute;
-                    f
-                })
-                .collect();
+                        .collect();
 
                 Command {
                     module: &module,
@@ -196,14 +196,16 @@ pub fn subcommand(input: TokenStream, panic_on_issue: bool) -> TokenStream {
         }
     };
 
-    let impls =
-        commands.into_iter().map(|c| c.into_impl(&proto, panic_on_issue));
+    let impls = commands.into_iter().map(|c| c.into_impl(&proto));
 
     let expanded = quote! {
         #command_variants
 
         impl #command_ident {
-            pub async fn execute(self) -> ::anyhow::Result<()> {
+            pub async fn execute(
+                self,
+                output: crate::output::OutputFormat,
+            ) -> ::anyhow::Result<()> {
                 match self {
                     #(#impls)*
                 }
@@ -247,7 +249,6 @@ impl Command<'_> {
     fn into_impl(
         self,
         proto: &ParsedAndTypechecked,
-        panic_on_issue: bool,
     ) -> proc_macro2::TokenStream {
         let Self { module, service_name, method, fields } = self;
 
@@ -257,7 +258,7 @@ impl Command<'_> {
             fields.iter().map(|f| f.get_resolved_field_ident()).collect();
 
         // Mapping is hard. Let's just "write" the code.
-        let mapping = write_mapping(module, proto, method, panic_on_issue);
+        let mapping = write_mapping(module, proto, method);
         let mapping =
             proc_macro2::TokenStream::from_str(&mapping).expect("mapping");
 
@@ -283,10 +284,10 @@ impl Command<'_> {
                 todo!("client streaming")
             }
             (false, true) => quote! {
-                crate::execute_server_streaming!(::client::#module::#client_mod::#client_ident::#function, req);
+                crate::execute_server_streaming!(::client::#module::#client_mod::#client_ident::#function, req, output);
             },
             _ => quote! {
-                let _ = crate::execute!(::client::#module::#client_mod::#client_ident::#function, req);
+                let _ = crate::execute!(::client::#module::#client_mod::#client_ident::#function, req, output);
             },
         };
 
@@ -311,7 +312,7 @@ enum FieldType {
 }
 
 impl FieldType {
-    fn resolve(field: &FieldDescriptorProto, panic_on_issue: bool) -> Self {
+    fn resolve(field: &FieldDescriptorProto) -> Self {
         let is_repeated =
             matches!(field.label, Some(l) if l == LABEL_REPEATED.into());
 
@@ -319,9 +320,6 @@ impl FieldType {
             if is_repeated {
                 let name = field.type_name();
                 if name.ends_with("Entry") {
-                    if panic_on_issue {
-                        panic!("Map not supported by the macro. To generate code that is close to correct, use the `subcommand_for_dev_only` macro. The code will have compilation errors, but you can expand the macro and save some typing");
-                    }
                     Self::Map
                 } else {
                     Self::VecMessage
@@ -372,7 +370,6 @@ fn resolve_fields<'a>(
     span: Span,
     proto: &'a ParsedAndTypechecked,
     message: &'a DescriptorProto,
-    panic_on_issue: bool,
 ) -> Vec<ResolvedField> {
     message
         .field
@@ -380,13 +377,17 @@ fn resolve_fields<'a>(
         .flat_map(|f| {
             let field_ident = Ident::new(f.name(), span);
 
-            match FieldType::resolve(f, panic_on_issue) {
-                FieldType::Primitive | FieldType::VecPrimitive => {
+            match FieldType::resolve(f) {
+                field_type @ (FieldType::Primitive
+                | FieldType::VecPrimitive) => {
                     let type_ident =
                         proto_reader::helpers::to_rust_type(f.type_(), span);
 
+                    // Repeated fields are flags that may be given many times
                     let type_ident = if f.proto3_optional() {
                         quote! { Option<#type_ident> }
+                    } else if let FieldType::VecPrimitive = field_type {
+                        quote! { Vec<#type_ident> }
                     } else {
                         quote! { #type_ident }
                     };
@@ -397,7 +398,7 @@ fn resolve_fields<'a>(
                         type_ident,
                     }]
                 }
-                FieldType::Message | FieldType::VecMessage => {
+                FieldType::Message => {
                     let message = proto_reader::helpers::find_message(
                         proto,
                         proto_reader::helpers::to_unqualified_type(
@@ -411,7 +412,7 @@ fn resolve_fields<'a>(
                         )
                     });
 
-                    resolve_fields(span, proto, message, panic_on_issue)
+                    resolve_fields(span, proto, message)
                         .into_iter()
                         .map(|mut f| {
                             f.field_ident.push_front(field_ident.clone());
@@ -419,7 +420,7 @@ fn resolve_fields<'a>(
                         })
                         .collect()
                 }
-                FieldType::Map => {
+                FieldType::Map | FieldType::VecMessage => {
                     vec![]
                 }
             }
@@ -431,25 +432,12 @@ fn write_mapping(
     module: &Path,
     proto: &ParsedAndTypechecked,
     method: &MethodDescriptorProto,
-    panic_on_issue: bool,
 ) -> String {
     fn write_value_from_field(
         command_field_parts: &mut VecDeque<String>,
         mapping: &mut String,
-        field: &FieldDescriptorProto,
-        panic_on_issue: bool,
     ) {
-        let field_type = FieldType::resolve(field, panic_on_issue);
-        if let FieldType::VecPrimitive = field_type {
-            mapping.push_str("vec![");
-        }
-
         mapping.push_str(&command_field_parts.iter().join("_"));
-
-        if let FieldType::VecPrimitive = field_type {
-            mapping.push(']');
-        }
-
         mapping.push(',');
     }
 
@@ -459,17 +447,8 @@ fn write_mapping(
         command_field_parts: &mut VecDeque<String>,
         mapping: &mut String,
         field: &FieldDescriptorProto,
-        panic_on_issue: bool,
     ) {
-        let field_type = FieldType::resolve(field, panic_on_issue);
-        match field_type {
-            FieldType::VecMessage => {
-                mapping.push_str("vec![");
-            }
-            _ => {
-                mapping.push_str("Some(");
-            }
-        };
+        mapping.push_str("Some(");
 
         let field_type_name =
             proto_reader::helpers::to_unqualified_type(field.type_name());
@@ -483,24 +462,10 @@ fn write_mapping(
                 .expect("failed to find message for field");
 
         for field in &field_type_message.field {
-            write_field(
-                module_path,
-                proto,
-                command_field_parts,
-                mapping,
-                field,
-                panic_on_issue,
-            )
+            write_field(module_path, proto, command_field_parts, mapping, field)
         }
 
-        match field_type {
-            FieldType::VecMessage => {
-                mapping.push_str("}],");
-            }
-            _ => {
-                mapping.push_str("}),");
-            }
-        };
+        mapping.push_str("}),");
     }
 
     fn write_field(
@@ -509,11 +474,15 @@ fn write_mapping(
         command_field_parts: &mut VecDeque<String>,
         mapping: &mut String,
         field: &FieldDescriptorProto,
-        panic_on_issue: bool,
     ) {
-        let field_type = FieldType::resolve(field, panic_on_issue);
+        let field_type = FieldType::resolve(field);
         match field_type {
-            FieldType::Map => {}
+            // Maps, and lists of messages, have no flags, so they are sent
+            // empty
+            FieldType::Map | FieldType::VecMessage => {
+                mapping.push_str(field.name());
+                mapping.push_str(": Default::default(),");
+            }
             _ => {
                 let name = field.name();
 
@@ -530,28 +499,22 @@ fn write_mapping(
 
         match field_type {
             FieldType::Primitive | FieldType::VecPrimitive => {
-                write_value_from_field(
-                    command_field_parts,
-                    mapping,
-                    field,
-                    panic_on_issue,
-                );
+                write_value_from_field(command_field_parts, mapping);
             }
-            FieldType::Message | FieldType::VecMessage => {
+            FieldType::Message => {
                 write_value_from_type(
                     module_path,
                     proto,
                     command_field_parts,
                     mapping,
                     field,
-                    panic_on_issue,
                 );
             }
-            FieldType::Map => {}
+            FieldType::Map | FieldType::VecMessage => {}
         }
 
         match field_type {
-            FieldType::Map => {}
+            FieldType::Map | FieldType::VecMessage => {}
             _ => {
                 let _ = command_field_parts.pop_back();
             }
@@ -582,7 +545,6 @@ fn write_mapping(
             &mut command_field_parts,
             &mut mapping,
             field,
-            panic_on_issue,
         );
     }
 
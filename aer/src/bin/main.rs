@@ -43,25 +43,44 @@
 \* -------------------------------------------------------------------------- */
 
 use aer::{
-    discovery::DiscoveryServiceCommands, grpc::HealthCommands,
-    observe::ObserveServiceCommands, runtime::CellServiceCommands,
+    completions::{CompletionsCommand, COMPLETE_VAR},
+    cri::PodCommands,
+    discovery::DiscoveryServiceCommands,
+    grpc::HealthCommands,
+    observe::ObserveCommands,
+    output::OutputFormat,
+    runtime::{CellCommands, CpCommand, RunCommand},
 };
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::env::CompleteEnv;
 
 #[derive(Debug, Parser)]
 #[command(name = "aer")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// How to print responses, and errors to stderr
+    #[arg(long, short, global = true, value_enum, default_value_t)]
+    output: OutputFormat,
+
+    /// Send the command to this node, as registered with the discovery
+    /// service of the auraed of the config, rather than to that auraed
+    #[arg(long, global = true)]
+    node: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-    #[command(arg_required_else_help = true)]
+    #[command(arg_required_else_help = true, alias = "cells")]
     Cell {
         #[command(subcommand)]
-        command: CellServiceCommands,
+        command: CellCommands,
     },
+    #[command(arg_required_else_help = true, hide = true)]
+    Completions(CompletionsCommand),
+    #[command(arg_required_else_help = true)]
+    Cp(CpCommand),
     #[command(arg_required_else_help = true)]
     Discovery {
         #[command(subcommand)]
@@ -75,20 +94,42 @@ enum Commands {
     #[command(arg_required_else_help = true)]
     Observe {
         #[command(subcommand)]
-        command: ObserveServiceCommands,
+        command: ObserveCommands,
+    },
+    #[command(arg_required_else_help = true, alias = "pods")]
+    Pod {
+        #[command(subcommand)]
+        command: PodCommands,
     },
+    #[command(arg_required_else_help = true)]
+    Run(RunCommand),
+}
+
+fn main() {
+    // Prints the completions and exits, if called by the shell to complete
+    CompleteEnv::with_factory(Cli::command).var(COMPLETE_VAR).complete();
+
+    run();
 }
 
 #[tokio::main]
-async fn main() {
-    let args = Cli::parse();
+async fn run() {
+    let Cli { command, output, node } = Cli::parse();
+    if let Some(node) = node {
+        aer::set_node(node);
+    }
 
-    if let Err(e) = match args.command {
-        Commands::Cell { command } => command.execute().await,
-        Commands::Discovery { command } => command.execute().await,
-        Commands::Health { command } => command.execute().await,
-        Commands::Observe { command } => command.execute().await,
+    if let Err(e) = match command {
+        Commands::Cell { command } => command.execute(output).await,
+        Commands::Completions(command) => command.execute(output).await,
+        Commands::Cp(command) => command.execute(output).await,
+        Commands::Discovery { command } => command.execute(output).await,
+        Commands::Health { command } => command.execute(output).await,
+        Commands::Observe { command } => command.execute(output).await,
+        Commands::Pod { command } => command.execute(output).await,
+        Commands::Run(command) => command.execute(output).await,
     } {
-        eprintln!("{e:#?}");
+        output.print_error(&e);
+        std::process::exit(1);
     }
-}
\ No newline at end of file
+}
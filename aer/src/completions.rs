@@ -0,0 +1,141 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Shell completions of aer, generated from its commands.
+//!
+//! The shell calls aer back with the command line being completed, so the
+//! names of the cells and pod sandboxes are completed by listing them, if
+//! auraed can be reached in time. Nothing is completed otherwise.
+
+use crate::output::OutputFormat;
+use anyhow::anyhow;
+use clap_complete::{
+    engine::{ArgValueCandidates, CompletionCandidate},
+    env::Shells,
+};
+use client::{
+    cells::cell_service::CellServiceClient,
+    cri::runtime_service::RuntimeServiceClient, Client, RetryPolicy,
+};
+use proto::{
+    cells::{CellGraphNode, CellServiceListRequest},
+    cri::ListPodSandboxRequest,
+};
+use std::future::Future;
+use std::time::Duration;
+
+/// The environment variable the shell sets to ask aer for completions.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// How long names are looked up for, before completing without them.
+const LOOKUP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Print the script which sets up the completions of aer for a shell.
+///
+/// Example: `source <(aer completions bash)`
+#[derive(Debug, clap::Args)]
+pub struct CompletionsCommand {
+    shell: Shell,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl CompletionsCommand {
+    pub async fn execute(self, _output: OutputFormat) -> anyhow::Result<()> {
+        let name = match self.shell {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        };
+        let shells = Shells::builtins();
+        let completer = shells
+            .completer(name)
+            .ok_or_else(|| anyhow!("completions for {name} are unsupported"))?;
+
+        completer.write_registration(
+            COMPLETE_VAR,
+            "aer",
+            "aer",
+            "aer",
+            &mut std::io::stdout(),
+        )?;
+        Ok(())
+    }
+}
+
+/// Completes the names of the cells, nested cells included.
+pub(crate) fn cell_names() -> ArgValueCandidates {
+    ArgValueCandidates::new(|| {
+        candidates(|client| async move {
+            let cells = client.list(CellServiceListRequest {}).await?;
+            let mut names = vec![];
+            collect_cell_names(&cells.into_inner().cells, &mut names);
+            Ok(names)
+        })
+    })
+}
+
+/// Completes the ids of the pod sandboxes.
+pub(crate) fn pod_names() -> ArgValueCandidates {
+    ArgValueCandidates::new(|| {
+        candidates(|client| async move {
+            let pods = client
+                .list_pod_sandbox(ListPodSandboxRequest { filter: None })
+                .await?;
+            Ok(pods.into_inner().items.into_iter().map(|pod| pod.id).collect())
+        })
+    })
+}
+
+fn collect_cell_names(cells: &[CellGraphNode], names: &mut Vec<String>) {
+    for node in cells {
+        if let Some(cell) = &node.cell {
+            names.push(cell.name.clone());
+        }
+        collect_cell_names(&node.children, names);
+    }
+}
+
+/// The names listed by `list` with a new client, or none if auraed could not
+/// be reached in time. Called as aer completes, outside of any runtime.
+fn candidates<F, Fut>(list: F) -> Vec<CompletionCandidate>
+where
+    F: FnOnce(Client) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<String>>>,
+{
+    let Ok(runtime) =
+        tokio::runtime::Builder::new_current_thread().enable_all().build()
+    else {
+        return vec![];
+    };
+
+    runtime
+        .block_on(async {
+            let client = Client::default()
+                .await?
+                .with_timeout(LOOKUP_TIMEOUT)
+                .with_retry_policy(RetryPolicy::none());
+            tokio::time::timeout(LOOKUP_TIMEOUT, list(client)).await?
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
@@ -44,10 +44,10 @@
 
 // TODO: The macro `macros::subcommand` is unable to produce valid code.
 //
-// Using `macros::subcommand_for_dev_only` should create invalid code,
-// without panicking, expanding the results and copy/pasting saves time
+// Using `macros::subcommand` creates invalid code, but expanding the
+// results and copy/pasting saves time
 
-// macros::subcommand_for_dev_only!(
+// macros::subcommand!(
 //     "../api/kubernetes/cri/v1/release-1.26.proto",
 //     kubernetes::cri,
 //     ImageService,
@@ -0,0 +1,51 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::output::OutputFormat;
+use client::cri::{
+    labels::LabelSelector, runtime_service::RuntimeServiceClient,
+};
+use proto::cri::{ListPodSandboxRequest, PodSandboxFilter};
+
+/// List the pod sandboxes, optionally only those whose labels match a
+/// selector.
+///
+/// Example: `aer pod list --selector 'app=web,tier!=db'`
+#[derive(Debug, clap::Args)]
+pub struct ListCommand {
+    /// The labels to select pod sandboxes by: comma-separated key=value
+    /// and key!=value terms, all of which must match
+    #[arg(long, short = 'l')]
+    selector: Option<LabelSelector>,
+}
+
+impl ListCommand {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        // Selected by auraed, which is sent the selector as the label
+        // selector of the filter
+        let req = ListPodSandboxRequest {
+            filter: self.selector.map(|selector| PodSandboxFilter {
+                label_selector: selector.to_filter(),
+                ..Default::default()
+            }),
+        };
+        let _ = crate::execute!(
+            RuntimeServiceClient::list_pod_sandbox,
+            req,
+            output
+        );
+        Ok(())
+    }
+}
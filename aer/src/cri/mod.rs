@@ -42,5 +42,28 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
+use crate::output::OutputFormat;
+pub use list::ListCommand;
+pub use wait::WaitCommand;
+
 pub mod image_service;
-pub mod pod_service;
\ No newline at end of file
+mod list;
+pub mod pod_service;
+mod wait;
+
+/// The commands for pod sandboxes.
+#[derive(Debug, clap::Subcommand)]
+pub enum PodCommands {
+    List(ListCommand),
+    #[command(arg_required_else_help = true)]
+    Wait(WaitCommand),
+}
+
+impl PodCommands {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::List(command) => command.execute(output).await,
+            Self::Wait(command) => command.execute(output).await,
+        }
+    }
+}
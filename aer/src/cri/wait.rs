@@ -0,0 +1,106 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::{
+    duration::parse_duration,
+    output::OutputFormat,
+    wait::{self, stream_ended, WaitFor},
+};
+use client::{cri::runtime_service::RuntimeServiceClient, Client};
+use futures_util::StreamExt;
+use proto::cri::{
+    ContainerEventType, ContainerState, GetEventsRequest,
+    PodSandboxStatusRequest,
+};
+use std::time::Duration;
+use tonic::{Code, Status};
+
+/// Wait for a pod sandbox to run or stop, exiting non-zero on timeout.
+///
+/// A pod sandbox which was removed is stopped.
+///
+/// Example: `aer pod wait mypod --for running --timeout 30s`
+#[derive(Debug, clap::Args)]
+pub struct WaitCommand {
+    /// The id of the pod sandbox
+    #[arg(add = crate::completions::pod_names())]
+    pod_sandbox_id: String,
+
+    /// The state to wait for
+    #[arg(long = "for", value_enum, default_value_t)]
+    wait_for: WaitFor,
+
+    /// How long to wait for, e.g. 30s, 10m, 2h or 1d. Forever by default
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+}
+
+impl WaitCommand {
+    pub async fn execute(self, _output: OutputFormat) -> anyhow::Result<()> {
+        let client = crate::client().await?;
+        let what = format!("pod '{}'", self.pod_sandbox_id);
+        wait::wait(&what, self.timeout, || self.wait(&client)).await
+    }
+
+    /// Watches the pod sandbox until it reaches the state waited for.
+    async fn wait(&self, client: &Client) -> Result<(), Status> {
+        // Watch for the pod sandbox to start or stop before looking at it
+        let mut events = client
+            .get_container_events(GetEventsRequest {})
+            .await?
+            .into_inner();
+
+        if self.running(client).await? == (self.wait_for == WaitFor::Running) {
+            return Ok(());
+        }
+
+        while let Some(res) = events.next().await {
+            let event = res?;
+            if event.container_id != self.pod_sandbox_id {
+                continue;
+            }
+            let running = match event.container_event_type() {
+                ContainerEventType::ContainerStartedEvent => true,
+                ContainerEventType::ContainerStoppedEvent
+                | ContainerEventType::ContainerDeletedEvent => false,
+                ContainerEventType::ContainerCreatedEvent => continue,
+            };
+            if running == (self.wait_for == WaitFor::Running) {
+                return Ok(());
+            }
+        }
+
+        Err(stream_ended())
+    }
+
+    /// Whether the init container of the pod sandbox is running.
+    async fn running(&self, client: &Client) -> Result<bool, Status> {
+        match client
+            .pod_sandbox_status(PodSandboxStatusRequest {
+                pod_sandbox_id: self.pod_sandbox_id.clone(),
+                verbose: false,
+            })
+            .await
+        {
+            Ok(res) => {
+                Ok(res.into_inner().containers_statuses.iter().any(|status| {
+                    status.state() == ContainerState::ContainerRunning
+                }))
+            }
+            Err(e) if e.code() == Code::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
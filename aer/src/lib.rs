@@ -70,33 +70,64 @@ unused_results
 #![warn(clippy::unwrap_used)]
 // #![warn(missing_docs)] // TODO: We want the docs from the proto
 
+pub mod completions;
 pub mod cri;
 pub mod discovery;
+mod duration;
 pub mod grpc;
 pub mod observe;
+pub mod output;
 pub mod runtime;
+mod wait;
 
-/// Executes an rpc call with the default `Client` and prints the results.
+use client::{Client, ClientError};
+use std::sync::OnceLock;
+
+/// The node the commands are sent to, if not the default auraed.
+static NODE: OnceLock<String> = OnceLock::new();
+
+/// Sends the commands to the node `name`, as resolved by the discovery
+/// service, rather than to the default auraed. Only the first call has an
+/// effect.
+pub fn set_node(name: String) {
+    let _ = NODE.set(name);
+}
+
+/// The default `Client`, or that of the node set with [set_node].
+pub async fn client() -> Result<Client, ClientError> {
+    let client = Client::default().await?;
+    match NODE.get() {
+        Some(node) => client.with_node(node).await,
+        None => Ok(client),
+    }
+}
+
+/// Executes an rpc call with the `Client` of [client] and prints the
+/// results.
 #[macro_export]
 macro_rules! execute {
-    ($call:path, $req:ident) => {{
-        let client = ::client::Client::default().await?;
+    ($call:path, $req:ident, $output:ident) => {{
+        let client = $crate::client().await?;
         let res = $call(&client, $req).await?.into_inner();
-        println!("{res:#?}");
+        $output.print(&res)?;
         res
     }};
 }
 
-/// Executes an rpc call with the default `Client` and prints the results.
-/// For use with server streaming requests.
+/// Executes an rpc call with the `Client` of [client] and prints the
+/// results. For use with server streaming requests.
 /// The initial response will be printed, followed by printing the stream of messages.
 #[macro_export]
 macro_rules! execute_server_streaming {
-    ($call:path, $req:ident) => {{
-        let mut res = $crate::execute!($call, $req);
+    ($call:path, $req:ident, $output:ident) => {{
+        let client = $crate::client().await?;
+        let mut res = $call(&client, $req).await?.into_inner();
+        if $output == $crate::output::OutputFormat::Text {
+            println!("{res:#?}");
+        }
         while let Some(res) = futures_util::StreamExt::next(&mut res).await {
             let res = res?;
-            println!("{res:#?}");
+            $output.print_message(&res)?;
         }
     }};
-}
\ No newline at end of file
+}
@@ -0,0 +1,164 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::{duration::parse_duration, output::OutputFormat};
+use client::observe::observe_service::ObserveServiceClient;
+use futures_util::StreamExt;
+use proto::observe::{
+    GetSubProcessStreamRequest, GetSubProcessStreamResponse, LogChannelType,
+};
+use serde::Serialize;
+use std::io::{ErrorKind, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Print the output of an executable.
+///
+/// The output auraed retains is printed first, then new output as it is
+/// written if following.
+///
+/// Example: `aer observe logs mycell myexe --follow --since 10m`
+#[derive(Debug, clap::Args)]
+pub struct LogsCommand {
+    /// The cell the executable runs in
+    #[arg(add = crate::completions::cell_names())]
+    cell_name: String,
+
+    /// The name of the executable
+    executable_name: String,
+
+    /// Keep printing output as it is written, until the executable is stopped
+    #[arg(long, short)]
+    follow: bool,
+
+    /// Only print what the executable writes to stderr
+    #[arg(long)]
+    stderr_only: bool,
+
+    /// Only print output written within this long, e.g. 30s, 10m, 2h or 1d
+    #[arg(long, value_parser = parse_duration)]
+    since: Option<Duration>,
+
+    /// Prepend the name of the executable to each line
+    #[arg(long)]
+    prefix: bool,
+}
+
+/// A line of output, as printed with `--output json` or `--output yaml`.
+#[derive(Debug, Serialize)]
+struct LogLine<'a> {
+    cell_name: &'a str,
+    executable_name: &'a str,
+    channel: &'static str,
+    line: &'a str,
+    timestamp: i64,
+    /// The number of lines dropped before this one, because they were not
+    /// read fast enough.
+    dropped: u64,
+}
+
+impl LogsCommand {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        let since = match self.since {
+            None => 0,
+            Some(since) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+                now.saturating_sub(since).as_secs() as i64
+            }
+        };
+        let channel_type = if self.stderr_only {
+            LogChannelType::Stderr
+        } else {
+            LogChannelType::Unspecified
+        };
+
+        let client = crate::client().await?;
+        let mut stream = client
+            .get_sub_process_stream(GetSubProcessStreamRequest {
+                cell_name: Some(self.cell_name.clone()),
+                executable_name: self.executable_name.clone(),
+                channel_type: channel_type.into(),
+                since: Some(since),
+                no_follow: !self.follow,
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+
+        loop {
+            let res = tokio::select! {
+                res = stream.next() => res,
+                // Stop reading, rather than be killed mid line
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            };
+            let Some(res) = res else {
+                return Ok(());
+            };
+
+            match self.print(output, res?) {
+                // Whoever reads the output stopped, e.g. `head`
+                Err(e)
+                    if e.downcast_ref::<std::io::Error>()
+                        .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) =>
+                {
+                    return Ok(())
+                }
+                res => res?,
+            }
+        }
+    }
+
+    fn print(
+        &self,
+        output: OutputFormat,
+        res: GetSubProcessStreamResponse,
+    ) -> anyhow::Result<()> {
+        let channel_type = res.channel_type();
+        let Some(item) = res.item else {
+            return Ok(());
+        };
+
+        if output != OutputFormat::Text {
+            let line = LogLine {
+                cell_name: &self.cell_name,
+                executable_name: &self.executable_name,
+                channel: match channel_type {
+                    LogChannelType::Stderr => "stderr",
+                    _ => "stdout",
+                },
+                line: &item.line,
+                timestamp: item.timestamp,
+                dropped: res.dropped,
+            };
+            return output.print_message(&line);
+        }
+
+        if res.dropped > 0 {
+            eprintln!("... {} lines dropped", res.dropped);
+        }
+
+        let prefix = if self.prefix {
+            format!("[{}] ", self.executable_name)
+        } else {
+            String::new()
+        };
+        match channel_type {
+            LogChannelType::Stderr => {
+                writeln!(std::io::stderr().lock(), "{prefix}{}", item.line)?
+            }
+            _ => writeln!(std::io::stdout().lock(), "{prefix}{}", item.line)?,
+        }
+        Ok(())
+    }
+}
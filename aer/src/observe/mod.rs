@@ -42,6 +42,27 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
+use crate::output::OutputFormat;
+pub use logs::LogsCommand;
 pub use observe_service::ObserveServiceCommands;
 
-mod observe_service;
\ No newline at end of file
+mod logs;
+mod observe_service;
+
+/// The commands of the observe service, and those built on them.
+#[derive(Debug, clap::Subcommand)]
+pub enum ObserveCommands {
+    #[command(arg_required_else_help = true)]
+    Logs(LogsCommand),
+    #[command(flatten)]
+    Service(ObserveServiceCommands),
+}
+
+impl ObserveCommands {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::Logs(command) => command.execute(output).await,
+            Self::Service(command) => command.execute(output).await,
+        }
+    }
+}
\ No newline at end of file
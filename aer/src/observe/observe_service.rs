@@ -44,7 +44,23 @@
 
 macros::subcommand!("../api/v0/observe/observe.proto", observe, ObserveService,
     GetSubProcessStream {
-        process_id[required = true, alias = "pid"],
-        channel_type[default_value = "1"],  // default to stdout
+        cell_name[long],
+        executable_name[long, alias = "executable", default_value = ""],
+        process_id[long, alias = "pid", default_value = "0"],
+        channel_type[long, default_value = "0"],  // default to stdout and stderr
+    },
+    GetLogStream {
+        cell_name_prefix[long, alias = "cell", default_value = ""],
+        executable_name[long, alias = "executable", default_value = ""],
+        min_level[long, default_value = "0"],
+        line_regex[long, alias = "grep", default_value = ""],
+    },
+    WatchEvents {
+        kinds[long, alias = "kind"],  // default to all kinds
+        since[long],
+    },
+    SetLogLevel {
+        directives[required = true],
+        revert_after_ms[long, alias = "revert-after"],
     },
 );
\ No newline at end of file
@@ -0,0 +1,156 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! How commands print the responses of auraed. Besides the debug format for
+//! people to read, responses are printed as JSON or YAML, with the field
+//! names of the proto messages, for scripts to read.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use std::io::Write;
+
+/// How commands print their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// For people to read
+    #[default]
+    Text,
+    /// JSON, with one object per line for streams
+    Json,
+    /// YAML, with one document per message for streams
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Prints the response of a call.
+    pub fn print<T: Serialize + Debug>(self, res: &T) -> anyhow::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        match self {
+            Self::Text => writeln!(stdout, "{res:#?}")?,
+            Self::Json => writeln!(
+                stdout,
+                "{}",
+                serde_json::to_string_pretty(&to_value(res)?)?
+            )?,
+            Self::Yaml => {
+                write!(stdout, "{}", serde_yaml::to_string(&to_value(res)?)?)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints a message of a stream. Errors writing to stdout are
+    /// [std::io::Error]s, e.g. to stop when the reader is gone.
+    pub fn print_message<T: Serialize + Debug>(
+        self,
+        res: &T,
+    ) -> anyhow::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        match self {
+            Self::Text => writeln!(stdout, "{res:#?}")?,
+            Self::Json => {
+                writeln!(stdout, "{}", serde_json::to_string(&to_value(res)?)?)?
+            }
+            Self::Yaml => write!(
+                stdout,
+                "---\n{}",
+                serde_yaml::to_string(&to_value(res)?)?
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Prints the error a command failed with to stderr. An error status of
+    /// auraed is printed as its code and message.
+    pub fn print_error(self, e: &anyhow::Error) {
+        if self == Self::Text {
+            eprintln!("{e:#?}");
+            return;
+        }
+
+        let (code, message) = match e.downcast_ref::<tonic::Status>() {
+            Some(status) => (
+                snake_case(&format!("{:?}", status.code())).to_uppercase(),
+                status.message().to_string(),
+            ),
+            None => ("UNKNOWN".to_string(), message(e)),
+        };
+        let error = serde_json::json!({
+            "error": { "code": code, "message": message }
+        });
+
+        match self {
+            Self::Json => eprintln!("{error}"),
+            _ => eprint!(
+                "{}",
+                serde_yaml::to_string(&error)
+                    .unwrap_or_else(|_| format!("{message}\n"))
+            ),
+        }
+    }
+}
+
+/// The error and its causes, leaving out those already in the message of
+/// the error they caused.
+fn message(e: &anyhow::Error) -> String {
+    let mut message = e.to_string();
+    for cause in e.chain().skip(1) {
+        let cause = cause.to_string();
+        if !message.contains(&cause) {
+            message = format!("{message}: {cause}");
+        }
+    }
+    message
+}
+
+/// Serializes a message with the names its fields have in the proto, rather
+/// than the lowerCamelCase names of the proto JSON mapping. Only names are
+/// changed, so the proto messages must not have map fields, whose keys would
+/// be changed as well.
+fn to_value<T: Serialize>(res: &T) -> serde_json::Result<Value> {
+    fn rename(value: Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (snake_case(&key), rename(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(rename).collect())
+            }
+            value => value,
+        }
+    }
+
+    serde_json::to_value(res).map(rename)
+}
+
+/// "cellName" to "cell_name", and "NotFound" to "not_found".
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.char_indices() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
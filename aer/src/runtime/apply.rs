@@ -0,0 +1,70 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::output::OutputFormat;
+use anyhow::Context;
+use client::cells::cell_service::CellServiceClient;
+use proto::cells::{CellServiceImportRequest, CellsDocument};
+use std::{io::Read, path::PathBuf};
+
+/// Allocate the cells of a document from `aer cell export`, and start their
+/// executables, leaving those that already exist as they are. Prints the
+/// changes made.
+///
+/// Example: `aer cell apply cells.yaml --dry-run`
+#[derive(Debug, clap::Args)]
+pub struct ApplyCommand {
+    /// The document, as YAML or JSON. `-` reads it from stdin
+    file: PathBuf,
+
+    /// Print the changes that would be made, without making them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Free the cells not in the document, with the executables running in
+    /// them
+    #[arg(long)]
+    prune: bool,
+}
+
+impl ApplyCommand {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        let content = if self.file.as_os_str() == "-" {
+            let mut content = String::new();
+            let _ = std::io::stdin().read_to_string(&mut content)?;
+            content
+        } else {
+            std::fs::read_to_string(&self.file).with_context(|| {
+                format!("failed to read '{}'", self.file.display())
+            })?
+        };
+
+        // YAML is a superset of JSON, so either is read
+        let document: CellsDocument = serde_yaml::from_str(&content)
+            .with_context(|| {
+                format!("failed to parse '{}'", self.file.display())
+            })?;
+
+        let client = crate::client().await?;
+        let res = client
+            .import(CellServiceImportRequest {
+                document: Some(document),
+                dry_run: self.dry_run,
+                prune: self.prune,
+            })
+            .await?;
+        output.print(&res.into_inner())
+    }
+}
@@ -54,18 +54,70 @@ macros::subcommand!(
         cell_cpuset_mems[long, alias = "cpuset-mems"],
         cell_isolate_process[long, default_value = "false"],
         cell_isolate_network[long, default_value = "false"],
+        cell_isolate_uts[long, default_value = "false"],
+        cell_hostname[long, alias = "hostname"],
+        cell_reserved_ports[long, alias = "reserved-ports"],
+        cell_per_executable_accounting[long, default_value = "false"],
+        update[long, default_value = "false"],
+    },
+    Update {
+        cell_name[required = true, add = crate::completions::cell_names()],
+        cpu_weight[long],
+        cpu_max[long],
+        memory_max[long],
+        memory_low[long],
+        pids_max[long],
     },
     Free {
-        cell_name[required = true],
+        cell_name[required = true, add = crate::completions::cell_names()],
+        force[long, default_value = "false"],
+        recursive[long, default_value = "false"],
+        timeout_ms[long, default_value = "0"],
     },
     Start {
-        cell_name[required = true],
+        cell_name[required = true, add = crate::completions::cell_names()],
         executable_name[required = true],
         executable_command[required = true, long, aliases = ["command", "cmd"], short = 'c'],
         executable_description[long, aliases = ["description", "desc"], default_value = ""],
+        executable_forbid_daemonize[long, alias = "forbid-daemonize", default_value = "false"],
+        executable_disable_log_redaction[long, alias = "disable-log-redaction", default_value = "false"],
+        executable_seccomp_profile[long, alias = "seccomp-profile", default_value = ""],
+        executable_no_new_privs[long, alias = "no-new-privs", default_value = "false"],
+        executable_capabilities_drop[long, alias = "cap-drop"],
+        executable_capabilities_keep[long, alias = "cap-keep"],
+        executable_rootfs[long, alias = "rootfs", default_value = ""],
+        executable_output_limit_lines_per_second[long, alias = "output-lines-per-second", default_value = "0"],
+        executable_output_limit_bytes_per_second[long, alias = "output-bytes-per-second", default_value = "0"],
     },
     Stop {
-        cell_name[required = true],
+        cell_name[required = true, add = crate::completions::cell_names()],
+        executable_name[required = true],
+    },
+    Quarantine {
+        cell_name[required = true, add = crate::completions::cell_names()],
         executable_name[required = true],
     },
-);
\ No newline at end of file
+    Unquarantine {
+        cell_name[required = true, add = crate::completions::cell_names()],
+        executable_name[required = true],
+    },
+    ListExecutables {
+        cell_name[long, add = crate::completions::cell_names()],
+    },
+    ListPortReservations {},
+    Admission {},
+    Stats {
+        cell_name[required = true, add = crate::completions::cell_names()],
+    },
+    WatchOomEvents {
+        cell_name[long, default_value = "", add = crate::completions::cell_names()],
+    },
+    NetCheck {
+        cell_name[required = true, add = crate::completions::cell_names()],
+        target_address[required = true],
+        protocol[long, default_value = "1"], // default to tcp
+        timeout_ms[long, default_value = "0"],
+        count[long, default_value = "0"],
+        tls_server_name[long],
+    },
+);
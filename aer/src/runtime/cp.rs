@@ -0,0 +1,219 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::output::OutputFormat;
+use anyhow::{anyhow, bail, Context};
+use bytes::Bytes;
+use client::cells::cell_service::CellServiceClient;
+use futures_util::StreamExt;
+use proto::cells::{
+    cell_service_copy_from_response, cell_service_copy_into_request,
+    CellServiceCopyFromRequest, CellServiceCopyIntoRequest, CopyIntoHeader,
+};
+use std::{
+    fs,
+    io::{Cursor, Write},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const CELL_SCHEME: &str = "cell://";
+
+/// The size of the chunks streamed into a cell.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Either side of a copy.
+#[derive(Debug, Clone)]
+pub enum Location {
+    /// A path on the local filesystem.
+    Local(PathBuf),
+    /// A path in a cell, written as `cell://<cell_name>:<path>`.
+    /// An empty cell name refers to the auraed itself.
+    Cell { cell_name: Option<String>, path: String },
+}
+
+impl FromStr for Location {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(location) = s.strip_prefix(CELL_SCHEME) else {
+            return Ok(Self::Local(PathBuf::from(s)));
+        };
+
+        let Some((cell_name, path)) = location.split_once(':') else {
+            bail!("expected '{CELL_SCHEME}<cell_name>:<path>', got '{s}'");
+        };
+
+        Ok(Self::Cell {
+            cell_name: (!cell_name.is_empty()).then(|| cell_name.to_string()),
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Copy files and directories between the local filesystem and a cell.
+///
+/// Example: `aer cp cell://mycell:/var/log/app.log ./app.log`
+#[derive(Debug, clap::Args)]
+pub struct CpCommand {
+    /// A local path, or `cell://<cell_name>:<path>`
+    source: Location,
+
+    /// A local path, or `cell://<cell_name>:<path>`
+    destination: Location,
+
+    /// Preserve the permission bits of the copied file
+    #[arg(long, short)]
+    preserve: bool,
+
+    /// Owner of the files written into a cell.
+    /// The destination must be writable by this uid.
+    #[arg(long)]
+    uid: Option<u32>,
+
+    /// Group of the files written into a cell
+    #[arg(long)]
+    gid: Option<u32>,
+}
+
+impl CpCommand {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        match (&self.source, &self.destination) {
+            (Location::Local(source), Location::Cell { cell_name, path }) => {
+                self.copy_into(source, cell_name.clone(), path.clone(), output)
+                    .await
+            }
+            (Location::Cell { cell_name, path }, Location::Local(dest)) => {
+                self.copy_from(cell_name.clone(), path.clone(), dest).await
+            }
+            _ => bail!("exactly one of source and destination must be a cell"),
+        }
+    }
+
+    async fn copy_into(
+        &self,
+        source: &Path,
+        cell_name: Option<String>,
+        destination_path: String,
+        output: OutputFormat,
+    ) -> anyhow::Result<()> {
+        let metadata = fs::metadata(source).with_context(|| {
+            format!("failed to read '{}'", source.display())
+        })?;
+
+        let (archive, content) = if metadata.is_dir() {
+            let mut builder = tar::Builder::new(Vec::new());
+            builder.follow_symlinks(false);
+            builder.append_dir_all(".", source)?;
+            (true, builder.into_inner()?)
+        } else {
+            (false, fs::read(source)?)
+        };
+
+        let header = CopyIntoHeader {
+            cell_name,
+            destination_path,
+            archive,
+            mode: (self.preserve && !archive).then(|| metadata.mode() & 0o7777),
+            uid: self.uid,
+            gid: self.gid,
+            size: Some(content.len() as u64),
+        };
+
+        let mut messages = vec![CellServiceCopyIntoRequest {
+            payload: Some(cell_service_copy_into_request::Payload::Header(
+                header,
+            )),
+        }];
+        let content = Bytes::from(content);
+        messages.extend((0..content.len()).step_by(CHUNK_SIZE).map(|start| {
+            let end = content.len().min(start + CHUNK_SIZE);
+            CellServiceCopyIntoRequest {
+                payload: Some(cell_service_copy_into_request::Payload::Chunk(
+                    content.slice(start..end),
+                )),
+            }
+        }));
+
+        let client = crate::client().await?;
+        let res = client
+            .copy_into(futures_util::stream::iter(messages))
+            .await?
+            .into_inner();
+        output.print(&res)?;
+
+        Ok(())
+    }
+
+    async fn copy_from(
+        &self,
+        cell_name: Option<String>,
+        source_path: String,
+        destination: &Path,
+    ) -> anyhow::Result<()> {
+        let client = crate::client().await?;
+        let mut stream = client
+            .copy_from(CellServiceCopyFromRequest {
+                cell_name,
+                source_path: source_path.clone(),
+            })
+            .await?
+            .into_inner();
+
+        let Some(cell_service_copy_from_response::Payload::Header(header)) =
+            stream.next().await.transpose()?.and_then(|res| res.payload)
+        else {
+            return Err(anyhow!("copy stream did not start with a header"));
+        };
+
+        let mut content = Vec::with_capacity(header.size as usize);
+        while let Some(res) = stream.next().await {
+            if let Some(cell_service_copy_from_response::Payload::Chunk(
+                chunk,
+            )) = res?.payload
+            {
+                content.extend_from_slice(&chunk);
+            }
+        }
+
+        if header.archive {
+            fs::create_dir_all(destination)?;
+            tar::Archive::new(Cursor::new(content)).unpack(destination)?;
+            return Ok(());
+        }
+
+        // Like cp, copying into an existing directory keeps the file name
+        let destination = if destination.is_dir() {
+            let file_name = Path::new(&source_path)
+                .file_name()
+                .ok_or_else(|| anyhow!("'{source_path}' has no file name"))?;
+            destination.join(file_name)
+        } else {
+            destination.to_path_buf()
+        };
+
+        let mut file = fs::File::create(&destination)?;
+        file.write_all(&content)?;
+
+        if self.preserve {
+            file.set_permissions(PermissionsExt::from_mode(
+                header.mode & 0o7777,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
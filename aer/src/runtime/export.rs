@@ -0,0 +1,55 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::output::OutputFormat;
+use client::cells::cell_service::CellServiceClient;
+use proto::cells::CellServiceExportRequest;
+use std::io::Write;
+
+/// Export the allocated cells, with the executables running in them, as a
+/// document `aer cell apply` recreates them from. Printed as YAML, unless
+/// the output is JSON.
+///
+/// Secrets are redacted, and have to be filled in before the document is
+/// applied.
+///
+/// Example: `aer cell export > cells.yaml`
+#[derive(Debug, clap::Args)]
+pub struct ExportCommand {}
+
+impl ExportCommand {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        let client = crate::client().await?;
+        let document = client
+            .export(CellServiceExportRequest {})
+            .await?
+            .into_inner()
+            .document
+            .unwrap_or_default();
+
+        // With the field names of the proto JSON mapping, rather than those
+        // the responses are printed with, as the variables of cells are maps
+        let mut stdout = std::io::stdout().lock();
+        match output {
+            OutputFormat::Json => writeln!(
+                stdout,
+                "{}",
+                serde_json::to_string_pretty(&document)?
+            )?,
+            _ => write!(stdout, "{}", serde_yaml::to_string(&document)?)?,
+        }
+        Ok(())
+    }
+}
@@ -42,6 +42,40 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
+use crate::output::OutputFormat;
+pub use apply::ApplyCommand;
 pub use cell_service::CellServiceCommands;
+pub use cp::CpCommand;
+pub use export::ExportCommand;
+pub use run::RunCommand;
+pub use wait::WaitCommand;
 
-mod cell_service;
\ No newline at end of file
+mod apply;
+mod cell_service;
+mod cp;
+mod export;
+mod run;
+mod wait;
+
+/// The commands of the cell service, and those built on them.
+#[derive(Debug, clap::Subcommand)]
+pub enum CellCommands {
+    #[command(arg_required_else_help = true)]
+    Apply(ApplyCommand),
+    Export(ExportCommand),
+    #[command(arg_required_else_help = true)]
+    Wait(WaitCommand),
+    #[command(flatten)]
+    Service(CellServiceCommands),
+}
+
+impl CellCommands {
+    pub async fn execute(self, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::Apply(command) => command.execute(output).await,
+            Self::Export(command) => command.execute(output).await,
+            Self::Wait(command) => command.execute(output).await,
+            Self::Service(command) => command.execute(output).await,
+        }
+    }
+}
@@ -0,0 +1,329 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::output::OutputFormat;
+use client::{
+    cells::cell_service::CellServiceClient,
+    observe::observe_service::ObserveServiceClient, Client,
+};
+use futures_util::{Stream, StreamExt};
+use proto::{
+    cells::{
+        Cell, CellServiceAllocateRequest, CellServiceFreeRequest,
+        CellServiceStartRequest, CellServiceStopRequest, CpuController,
+        Executable, MemoryController,
+    },
+    observe::{
+        lifecycle_event::Kind, ExecutableExited, GetSubProcessStreamRequest,
+        GetSubProcessStreamResponse, LifecycleEventKind, LogChannelType,
+        WatchEventsRequest,
+    },
+};
+use std::io::Write;
+use std::path::Path;
+use std::pin::pin;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+/// How long the output of an executable that exited is read for, after the
+/// last line, before it is stopped.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The exit code of aer when interrupted, as for shells.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Run a command in a new cell, printing its output until it exits.
+///
+/// The cell is allocated, the command started in it, and once the command
+/// exits (or Ctrl-C is pressed) it is stopped and the cell freed. aer exits
+/// with the exit code of the command.
+///
+/// Example: `aer run --cell mycell --mem-max 100000000 -- ls -la /`
+#[derive(Debug, clap::Args)]
+pub struct RunCommand {
+    /// The name of the cell to allocate
+    #[arg(long)]
+    cell: String,
+
+    /// The weight of the cell's share of CPU time, from 1 to 10000
+    #[arg(long)]
+    cpu_weight: Option<u64>,
+
+    /// The most memory the cell may use, in bytes
+    #[arg(long)]
+    mem_max: Option<i64>,
+
+    /// The name of the executable. The name of the program by default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Don't free the cell once the command exits, to inspect it
+    #[arg(long)]
+    keep: bool,
+
+    /// The seccomp profile to run the command under: "default", "strict",
+    /// or the absolute path to an OCI seccomp profile
+    #[arg(long)]
+    seccomp_profile: Option<String>,
+
+    /// The command to run, and its arguments
+    #[arg(required = true, last = true)]
+    command: Vec<String>,
+}
+
+/// What was created, to clean up.
+#[derive(Debug, Default)]
+struct Created {
+    cell: bool,
+    executable: bool,
+}
+
+impl RunCommand {
+    /// Exits aer with the exit code of the command, unless running it fails.
+    pub async fn execute(self, _output: OutputFormat) -> anyhow::Result<()> {
+        let client = crate::client().await?;
+        let mut created = Created::default();
+
+        let res = tokio::select! {
+            res = self.run(&client, &mut created) => res,
+            _ = tokio::signal::ctrl_c() => Ok(INTERRUPTED_EXIT_CODE),
+        };
+        let cleanup = self.cleanup(&client, created).await;
+
+        let code = res?;
+        cleanup?;
+        std::process::exit(code)
+    }
+
+    /// Allocates the cell, starts the command and prints its output until it
+    /// exits. Returns the exit code for aer.
+    async fn run(
+        &self,
+        client: &Client,
+        created: &mut Created,
+    ) -> anyhow::Result<i32> {
+        let executable_name = self.executable_name();
+
+        // Watch for the command to exit before it is started
+        let events = client
+            .watch_events(WatchEventsRequest {
+                kinds: vec![LifecycleEventKind::ExecutableExited.into()],
+                since: None,
+            })
+            .await?
+            .into_inner()
+            .filter_map(|res| async move {
+                match res.map(|res| res.event) {
+                    Ok(Some(event)) => match event.kind {
+                        Some(Kind::ExecutableExited(exited)) => {
+                            Some(Ok((event.cell_name, exited)))
+                        }
+                        _ => None,
+                    },
+                    Ok(None) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            });
+        let mut events = pin!(events);
+
+        let _ = client
+            .allocate(CellServiceAllocateRequest {
+                cell: Some(Cell {
+                    name: self.cell.clone(),
+                    cpu: Some(CpuController {
+                        weight: self.cpu_weight,
+                        ..Default::default()
+                    }),
+                    memory: Some(MemoryController {
+                        max: self.mem_max,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                update: false,
+            })
+            .await?;
+        created.cell = true;
+
+        let _ = client
+            .start(CellServiceStartRequest {
+                cell_name: Some(self.cell.clone()),
+                executable: Some(Executable {
+                    name: executable_name.clone(),
+                    command: shell_command(&self.command),
+                    seccomp_profile: self
+                        .seccomp_profile
+                        .clone()
+                        .unwrap_or_default(),
+                    ..Default::default()
+                }),
+                uid: None,
+                gid: None,
+            })
+            .await?;
+        created.executable = true;
+
+        // The output written before subscribing is replayed. If the command
+        // exited and was stopped already, there is none to print.
+        let output = client
+            .get_sub_process_stream(GetSubProcessStreamRequest {
+                cell_name: Some(self.cell.clone()),
+                executable_name: executable_name.clone(),
+                since: Some(0),
+                ..Default::default()
+            })
+            .await;
+        let mut output = match output {
+            Ok(output) => Some(output.into_inner()),
+            Err(e) if e.code() == Code::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let exited = loop {
+            tokio::select! {
+                res = next(&mut output) => match res {
+                    Some(res) => print(res?)?,
+                    None => output = None,
+                },
+                res = events.next() => match res {
+                    Some(Ok((cell_name, exited)))
+                        if cell_name == self.cell
+                            && exited.executable_name == executable_name =>
+                    {
+                        break exited;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(Status::unavailable(
+                        "the stream of events ended before the command exited",
+                    ).into()),
+                },
+            }
+        };
+
+        // Print what the command wrote before exiting, which may still be
+        // on its way
+        while let Ok(Some(res)) =
+            tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, next(&mut output)).await
+        {
+            print(res?)?;
+        }
+
+        Ok(exit_code(&exited))
+    }
+
+    /// Stops the executable and frees the cell, if they were created. Both
+    /// are attempted even if the other fails.
+    async fn cleanup(
+        &self,
+        client: &Client,
+        created: Created,
+    ) -> anyhow::Result<()> {
+        let mut res = Ok(());
+
+        if created.executable {
+            if let Err(e) = client
+                .stop(CellServiceStopRequest {
+                    cell_name: Some(self.cell.clone()),
+                    executable_name: self.executable_name(),
+                    ..Default::default()
+                })
+                .await
+            {
+                eprintln!("failed to stop '{}': {e}", self.executable_name());
+                res = Err(e.into());
+            }
+        }
+
+        if created.cell && !self.keep {
+            if let Err(e) = client
+                .free(CellServiceFreeRequest {
+                    cell_name: self.cell.clone(),
+                    force: true,
+                    ..Default::default()
+                })
+                .await
+            {
+                eprintln!("failed to free cell '{}': {e}", self.cell);
+                res = Err(e.into());
+            }
+        }
+
+        res
+    }
+
+    fn executable_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.command
+                .first()
+                .and_then(|program| Path::new(program).file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "run".into())
+        })
+    }
+}
+
+/// The next message of `output`, or never if there is no output to read.
+async fn next<S>(output: &mut Option<S>) -> Option<S::Item>
+where
+    S: Stream + Unpin,
+{
+    match output {
+        Some(output) => output.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Prints a line of output where the command wrote it.
+fn print(res: GetSubProcessStreamResponse) -> std::io::Result<()> {
+    let channel_type = res.channel_type();
+    let Some(item) = res.item else {
+        return Ok(());
+    };
+
+    match channel_type {
+        LogChannelType::Stderr => writeln!(std::io::stderr(), "{}", item.line),
+        _ => writeln!(std::io::stdout(), "{}", item.line),
+    }
+}
+
+/// The exit code of a command, or 128 plus the signal that killed it, as
+/// for shells.
+fn exit_code(exited: &ExecutableExited) -> i32 {
+    match (exited.code, exited.signal) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => 128 + signal,
+        (None, None) => 1,
+    }
+}
+
+/// Quotes the arguments for the shell auraed runs commands with, so that
+/// they are passed on as they are.
+fn shell_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c)
+                });
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,126 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::{
+    duration::parse_duration,
+    output::OutputFormat,
+    wait::{self, stream_ended, WaitFor},
+};
+use client::{
+    cells::cell_service::CellServiceClient,
+    observe::observe_service::ObserveServiceClient, Client,
+};
+use futures_util::StreamExt;
+use proto::{
+    cells::CellServiceListExecutablesRequest,
+    observe::{lifecycle_event::Kind, LifecycleEventKind, WatchEventsRequest},
+};
+use std::time::Duration;
+use tonic::{Code, Status};
+
+/// Wait for an executable to start or stop, exiting non-zero on timeout.
+///
+/// Executables of a cell which no longer exists are stopped.
+///
+/// Example: `aer cell wait mycell myexe --timeout 30s`
+#[derive(Debug, clap::Args)]
+pub struct WaitCommand {
+    /// The cell the executable runs in
+    #[arg(add = crate::completions::cell_names())]
+    cell_name: String,
+
+    /// The name of the executable
+    executable_name: String,
+
+    /// The state to wait for
+    #[arg(long = "for", value_enum, default_value_t)]
+    wait_for: WaitFor,
+
+    /// How long to wait for, e.g. 30s, 10m, 2h or 1d. Forever by default
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+}
+
+impl WaitCommand {
+    pub async fn execute(self, _output: OutputFormat) -> anyhow::Result<()> {
+        let client = crate::client().await?;
+        let what =
+            format!("'{}' in cell '{}'", self.executable_name, self.cell_name);
+        wait::wait(&what, self.timeout, || self.wait(&client)).await
+    }
+
+    /// Watches the executable until it reaches the state waited for.
+    async fn wait(&self, client: &Client) -> Result<(), Status> {
+        // Watch for the executable to start or exit before looking at it
+        let mut events = client
+            .watch_events(WatchEventsRequest {
+                kinds: vec![
+                    LifecycleEventKind::ExecutableStarted.into(),
+                    LifecycleEventKind::ExecutableExited.into(),
+                ],
+                since: None,
+            })
+            .await?
+            .into_inner();
+
+        if self.running(client).await? == (self.wait_for == WaitFor::Running) {
+            return Ok(());
+        }
+
+        while let Some(res) = events.next().await {
+            let Some(event) = res?.event else {
+                continue;
+            };
+            if event.cell_name != self.cell_name {
+                continue;
+            }
+            let running = match event.kind {
+                Some(Kind::ExecutableStarted(started))
+                    if started.executable_name == self.executable_name =>
+                {
+                    true
+                }
+                Some(Kind::ExecutableExited(exited))
+                    if exited.executable_name == self.executable_name =>
+                {
+                    false
+                }
+                _ => continue,
+            };
+            if running == (self.wait_for == WaitFor::Running) {
+                return Ok(());
+            }
+        }
+
+        Err(stream_ended())
+    }
+
+    /// Whether the executable is running.
+    async fn running(&self, client: &Client) -> Result<bool, Status> {
+        match client
+            .list_executables(CellServiceListExecutablesRequest {
+                cell_name: Some(self.cell_name.clone()),
+            })
+            .await
+        {
+            Ok(res) => Ok(res
+                .into_inner()
+                .executable_names
+                .contains(&self.executable_name)),
+            Err(e) if e.code() == Code::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use anyhow::anyhow;
+use client::{Disconnected, RetryPolicy};
+use std::future::Future;
+use std::time::Duration;
+use tonic::Status;
+
+/// The state to wait for a workload to reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum WaitFor {
+    /// Until it runs
+    Running,
+    /// Until it no longer runs
+    #[default]
+    Stopped,
+}
+
+/// Waits for `what` by calling `wait` until it returns, for up to `timeout`
+/// if limited.
+///
+/// `wait` is called again after a backoff if it failed because the
+/// connection to auraed dropped, or the stream of events ended, so it should
+/// watch for events before looking at the current state, to miss none.
+pub(crate) async fn wait<F, Fut>(
+    what: &str,
+    timeout: Option<Duration>,
+    wait: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Status>>,
+{
+    match timeout {
+        Some(timeout) => {
+            tokio::time::timeout(timeout, retry(wait)).await.map_err(|_| {
+                anyhow!(
+                    "timed out after {}s waiting for {what}",
+                    timeout.as_secs()
+                )
+            })?
+        }
+        None => retry(wait).await,
+    }
+}
+
+/// Calls `wait` until it returns, or fails other than by disconnecting.
+async fn retry<F, Fut>(mut wait: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Status>>,
+{
+    let retry_policy = RetryPolicy::default();
+    let mut attempt = 0;
+    loop {
+        let Err(status) = wait().await else {
+            return Ok(());
+        };
+        let disconnected = Disconnected::try_from(status)?;
+        attempt += 1;
+        eprintln!("{disconnected}, retrying");
+        tokio::time::sleep(retry_policy.backoff(attempt)).await;
+    }
+}
+
+/// The error for a stream of events which ended, to watch again.
+pub(crate) fn stream_ended() -> Status {
+    Status::unavailable("the stream of events ended")
+}
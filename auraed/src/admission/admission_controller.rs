@@ -0,0 +1,302 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{
+    host_capacity, AdmissionConfig, AdmissionError, Resource, Resources,
+    Result, Workload,
+};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tracing::{info, warn};
+
+/// The resources committed by the cells and pods, shared by the services
+/// which allocate them.
+#[derive(Debug, Clone)]
+pub struct AdmissionController(Arc<Mutex<Inner>>);
+
+#[derive(Debug)]
+struct Inner {
+    config: AdmissionConfig,
+    read_capacity: fn() -> io::Result<Resources>,
+    /// The capacity last read. A resource whose capacity is unknown is not
+    /// admitted against.
+    capacity: Resources,
+    committed: HashMap<Workload, Resources>,
+}
+
+/// The totals of admission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdmissionInfo {
+    pub enforce: bool,
+    pub capacity: Resources,
+    /// The capacity times the overcommit ratios.
+    pub allowed: Resources,
+    /// The sum of the resources of all the workloads.
+    pub committed: Resources,
+    /// The number of workloads which commit resources.
+    pub workloads: usize,
+}
+
+impl AdmissionController {
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self::with_capacity(config, host_capacity::read)
+    }
+
+    fn with_capacity(
+        config: AdmissionConfig,
+        read_capacity: fn() -> io::Result<Resources>,
+    ) -> Self {
+        let capacity = read_capacity().unwrap_or_else(|e| {
+            warn!("failed to read the capacity of the host: {e}");
+            Resources::default()
+        });
+        info!(
+            "Admitting allocations against {} bytes of memory, {} millicpus and {} pids{}",
+            capacity.memory_bytes,
+            capacity.cpu_millis,
+            capacity.pids,
+            if config.enforce { "" } else { ", warning only" }
+        );
+
+        Self(Arc::new(Mutex::new(Inner {
+            config,
+            read_capacity,
+            capacity,
+            committed: HashMap::new(),
+        })))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.0.lock().expect("admission lock")
+    }
+
+    /// Commits `resources` for `workload`, in place of those it committed
+    /// before, unless it would commit more of a resource than allowed.
+    /// Only increases are rejected, so that an oversubscribed host can
+    /// still be relieved. If admission is not enforced, the increase is
+    /// warned of and committed.
+    pub fn admit(
+        &self,
+        workload: Workload,
+        resources: Resources,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        inner.refresh_capacity();
+
+        let previous =
+            inner.committed.get(&workload).copied().unwrap_or_default();
+        let committed = inner.committed_except(&workload);
+        let allowed = inner.allowed();
+
+        for resource in Resource::ALL {
+            let requested = resources.get(resource);
+            let capacity = inner.capacity.get(resource);
+            if requested <= previous.get(resource) || capacity == 0 {
+                continue;
+            }
+
+            let committed = committed.get(resource);
+            let allowed = allowed.get(resource);
+            if committed.saturating_add(requested) <= allowed {
+                continue;
+            }
+
+            let err = AdmissionError::Oversubscribed {
+                workload: workload.clone(),
+                resource,
+                requested,
+                committed,
+                allowed,
+                capacity,
+            };
+            if inner.config.enforce {
+                return Err(err);
+            }
+            warn!("{err}");
+        }
+
+        let _ = inner.committed.insert(workload, resources);
+        Ok(())
+    }
+
+    /// Commits `resources` for `workload` without admitting them, as for a
+    /// workload which already runs.
+    pub fn commit(&self, workload: Workload, resources: Resources) {
+        let _ = self.lock().committed.insert(workload, resources);
+    }
+
+    /// Commits what `workload` committed before it was admitted again, as
+    /// when the allocation or update it was admitted for failed.
+    pub fn restore(&self, workload: Workload, previous: Option<Resources>) {
+        match previous {
+            Some(resources) => self.commit(workload, resources),
+            None => self.release(&workload),
+        }
+    }
+
+    /// The resources `workload` commits, if any.
+    pub fn committed(&self, workload: &Workload) -> Option<Resources> {
+        self.lock().committed.get(workload).copied()
+    }
+
+    /// Releases the resources `workload` commits.
+    pub fn release(&self, workload: &Workload) {
+        let _ = self.lock().committed.remove(workload);
+    }
+
+    pub fn info(&self) -> AdmissionInfo {
+        let mut inner = self.lock();
+        inner.refresh_capacity();
+
+        AdmissionInfo {
+            enforce: inner.config.enforce,
+            capacity: inner.capacity,
+            allowed: inner.allowed(),
+            committed: inner
+                .committed
+                .values()
+                .fold(Resources::default(), |sum, x| sum.saturating_add(*x)),
+            workloads: inner.committed.len(),
+        }
+    }
+}
+
+impl Inner {
+    fn refresh_capacity(&mut self) {
+        let capacity = match (self.read_capacity)() {
+            Ok(capacity) => capacity,
+            Err(e) => {
+                warn!("failed to read the capacity of the host: {e}");
+                return;
+            }
+        };
+
+        if capacity != self.capacity {
+            info!(
+                "The capacity of the host changed to {} bytes of memory, {} millicpus and {} pids",
+                capacity.memory_bytes, capacity.cpu_millis, capacity.pids
+            );
+            self.capacity = capacity;
+        }
+    }
+
+    fn committed_except(&self, workload: &Workload) -> Resources {
+        self.committed
+            .iter()
+            .filter(|(other, _)| *other != workload)
+            .fold(Resources::default(), |sum, (_, x)| sum.saturating_add(*x))
+    }
+
+    fn allowed(&self) -> Resources {
+        let allowed = |resource: Resource| {
+            let capacity = u128::from(self.capacity.get(resource));
+            let overcommit = u128::from(self.config.overcommit(resource));
+            u64::try_from(capacity * overcommit / 100).unwrap_or(u64::MAX)
+        };
+
+        Resources {
+            memory_bytes: allowed(Resource::Memory),
+            cpu_millis: allowed(Resource::Cpu),
+            pids: allowed(Resource::Pids),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacity() -> io::Result<Resources> {
+        Ok(Resources { memory_bytes: 1000, cpu_millis: 2000, pids: 100 })
+    }
+
+    fn controller(enforce: bool) -> AdmissionController {
+        AdmissionController::with_capacity(
+            AdmissionConfig {
+                enabled: true,
+                enforce,
+                cpu_overcommit: 200,
+                ..Default::default()
+            },
+            capacity,
+        )
+    }
+
+    fn memory(memory_bytes: u64) -> Resources {
+        Resources { memory_bytes, ..Default::default() }
+    }
+
+    fn cell(name: &str) -> Workload {
+        Workload::Cell(name.into())
+    }
+
+    #[test]
+    fn test_rejects_oversubscribing_allocations() {
+        let admission = controller(true);
+        admission.admit(cell("a"), memory(600)).unwrap();
+
+        let err = admission.admit(cell("b"), memory(600)).unwrap_err();
+        let AdmissionError::Oversubscribed {
+            resource,
+            requested,
+            committed,
+            allowed,
+            ..
+        } = err;
+        assert_eq!(resource, Resource::Memory);
+        assert_eq!((requested, committed, allowed), (600, 600, 1000));
+        assert_eq!(admission.committed(&cell("b")), None);
+
+        admission.admit(cell("b"), memory(400)).unwrap();
+        // Cpus may be committed twice over
+        admission
+            .admit(
+                Workload::Pod("c".into()),
+                Resources { cpu_millis: 4000, ..Default::default() },
+            )
+            .unwrap();
+
+        let info = admission.info();
+        assert_eq!(
+            info.committed,
+            Resources { memory_bytes: 1000, cpu_millis: 4000, pids: 0 }
+        );
+        assert_eq!(info.allowed.cpu_millis, 4000);
+        assert_eq!(info.workloads, 3);
+    }
+
+    #[test]
+    fn test_only_increases_are_rejected() {
+        let admission = controller(true);
+        admission.commit(cell("a"), memory(2000));
+
+        // The workload replaces what it committed
+        admission.admit(cell("a"), memory(1500)).unwrap();
+        assert!(admission.admit(cell("a"), memory(1600)).is_err());
+
+        admission.release(&cell("a"));
+        admission.admit(cell("b"), memory(1000)).unwrap();
+    }
+
+    #[test]
+    fn test_warns_only_if_not_enforced() {
+        let admission = controller(false);
+        admission.admit(cell("a"), memory(5000)).unwrap();
+        assert_eq!(admission.info().committed.memory_bytes, 5000);
+    }
+}
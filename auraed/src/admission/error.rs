@@ -0,0 +1,54 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{Resource, Workload};
+use thiserror::Error;
+use tonic::Status;
+use tracing::error;
+
+pub type Result<T> = std::result::Result<T, AdmissionError>;
+
+#[derive(Debug, Error)]
+pub enum AdmissionError {
+    #[error(
+        "{workload} would commit {} {}, over the {allowed} allowed: {committed} are committed by other workloads, of {capacity} on the host",
+        committed.saturating_add(*requested),
+        resource.unit()
+    )]
+    Oversubscribed {
+        workload: Workload,
+        resource: Resource,
+        /// The amount the workload commits.
+        requested: u64,
+        /// The amount the other workloads commit.
+        committed: u64,
+        /// The amount which may be committed, the capacity times the
+        /// overcommit ratio.
+        allowed: u64,
+        capacity: u64,
+    },
+}
+
+impl From<AdmissionError> for Status {
+    fn from(err: AdmissionError) -> Self {
+        let msg = err.to_string();
+        error!("{msg}");
+        match err {
+            AdmissionError::Oversubscribed { .. } => {
+                Status::resource_exhausted(msg)
+            }
+        }
+    }
+}
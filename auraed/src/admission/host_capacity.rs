@@ -0,0 +1,97 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The capacity of the host, read again on each admission, so cpus and
+//! memory hotplugged since auraed started are accounted for.
+
+use super::Resources;
+use std::io;
+
+const MEMINFO: &str = "/proc/meminfo";
+const CPUS_ONLINE: &str = "/sys/devices/system/cpu/online";
+const PID_MAX: &str = "/proc/sys/kernel/pid_max";
+
+/// Reads the memory, online cpus and pids of the host.
+pub(super) fn read() -> io::Result<Resources> {
+    let meminfo = std::fs::read_to_string(MEMINFO)?;
+    let memory_bytes = mem_total(&meminfo).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no MemTotal in {MEMINFO}"),
+        )
+    })?;
+
+    let online = std::fs::read_to_string(CPUS_ONLINE)?;
+    let cpus = cpu_list_len(&online).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid cpu list in {CPUS_ONLINE}: '{}'", online.trim()),
+        )
+    })?;
+
+    let pids =
+        std::fs::read_to_string(PID_MAX)?.trim().parse().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{PID_MAX}: {e}"),
+            )
+        })?;
+
+    Ok(Resources { memory_bytes, cpu_millis: cpus * 1000, pids })
+}
+
+/// The bytes of `MemTotal` in the contents of `/proc/meminfo`.
+fn mem_total(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    kib.checked_mul(1024)
+}
+
+/// The number of cpus in a cpu list, such as `0-3,8-11,16`.
+fn cpu_list_len(list: &str) -> Option<u64> {
+    list.trim().split(',').try_fold(0, |len, range| {
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => {
+                (first.parse::<u64>().ok()?, last.parse().ok()?)
+            }
+            None => {
+                let cpu = range.parse().ok()?;
+                (cpu, cpu)
+            }
+        };
+
+        Some(len + last.checked_sub(first)? + 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mem_total() {
+        let meminfo = "MemTotal:       16318044 kB\nMemFree:         1024 kB\n";
+        assert_eq!(mem_total(meminfo), Some(16318044 * 1024));
+        assert_eq!(mem_total("MemFree: 1024 kB\n"), None);
+    }
+
+    #[test]
+    fn test_cpu_list_len() {
+        assert_eq!(cpu_list_len("0\n"), Some(1));
+        assert_eq!(cpu_list_len("0-3,8-11,16\n"), Some(9));
+        assert_eq!(cpu_list_len("3-1"), None);
+        assert_eq!(cpu_list_len(""), None);
+    }
+}
@@ -0,0 +1,151 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Admission of cells and pods against the capacity of the host.
+//!
+//! The memory, cpu and pids limits of the cells and pods are committed
+//! against the capacity of the host, and an allocation which would commit
+//! more than the capacity times the overcommit ratio of a resource is
+//! rejected, or only warned of if admission is not enforced.
+//!
+//! Unlimited resources are not committed, and nested cells are bounded by
+//! the limits of their parent, so only the top level cells are committed.
+
+pub use admission_controller::{AdmissionController, AdmissionInfo};
+pub use error::{AdmissionError, Result};
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+mod admission_controller;
+mod error;
+mod host_capacity;
+
+/// Whether and how allocations are admitted against the capacity of the
+/// host. Overcommit ratios are percents of the capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    /// Track the resources committed by cells and pods.
+    pub enabled: bool,
+    /// Reject the allocations which oversubscribe the host, instead of
+    /// only warning of them.
+    pub enforce: bool,
+    /// The percent of the memory of the host which may be committed.
+    pub memory_overcommit: u32,
+    /// The percent of the cpus of the host which may be committed.
+    pub cpu_overcommit: u32,
+    /// The percent of the pids of the host which may be committed.
+    pub pids_overcommit: u32,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            enforce: true,
+            memory_overcommit: 100,
+            cpu_overcommit: 100,
+            pids_overcommit: 100,
+        }
+    }
+}
+
+impl AdmissionConfig {
+    fn overcommit(&self, resource: Resource) -> u32 {
+        match resource {
+            Resource::Memory => self.memory_overcommit,
+            Resource::Cpu => self.cpu_overcommit,
+            Resource::Pids => self.pids_overcommit,
+        }
+    }
+}
+
+/// The resources admission commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Memory,
+    Cpu,
+    Pids,
+}
+
+impl Resource {
+    const ALL: [Resource; 3] =
+        [Resource::Memory, Resource::Cpu, Resource::Pids];
+
+    /// The unit amounts of the resource are counted in.
+    fn unit(&self) -> &'static str {
+        match self {
+            Resource::Memory => "bytes of memory",
+            Resource::Cpu => "millicpus",
+            Resource::Pids => "pids",
+        }
+    }
+}
+
+/// A cell or pod which commits resources.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Workload {
+    Cell(String),
+    Pod(String),
+}
+
+impl Display for Workload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Workload::Cell(name) => write!(f, "cell '{name}'"),
+            Workload::Pod(id) => write!(f, "pod '{id}'"),
+        }
+    }
+}
+
+/// Amounts of the resources admission commits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resources {
+    /// Bytes of memory, as `memory.max`.
+    pub memory_bytes: u64,
+    /// Thousandths of a cpu, as the quota of `cpu.max` over its period.
+    pub cpu_millis: u64,
+    /// Processes and threads, as `pids.max`.
+    pub pids: u64,
+}
+
+impl Resources {
+    fn get(&self, resource: Resource) -> u64 {
+        match resource {
+            Resource::Memory => self.memory_bytes,
+            Resource::Cpu => self.cpu_millis,
+            Resource::Pids => self.pids,
+        }
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self {
+            memory_bytes: self.memory_bytes.saturating_add(other.memory_bytes),
+            cpu_millis: self.cpu_millis.saturating_add(other.cpu_millis),
+            pids: self.pids.saturating_add(other.pids),
+        }
+    }
+
+    /// The thousandths of a cpu of a quota of `quota` microseconds every
+    /// `period` microseconds. A quota of 0 is no limit.
+    pub fn cpu_millis(quota: u64, period: u64) -> u64 {
+        if period == 0 {
+            return 0;
+        }
+
+        quota.saturating_mul(1000) / period
+    }
+}
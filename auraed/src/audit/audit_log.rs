@@ -0,0 +1,225 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::AuditRecord;
+use crate::blocking::{self, BlockingJob, Pool};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Where audit records are written, and how the file is rotated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// The file audit records are appended to, as JSON lines. Records are
+    /// not written to a file if [None].
+    pub path: Option<PathBuf>,
+    /// Flush each record to disk before the call it records returns.
+    pub fsync: bool,
+    /// The size in bytes past which the file is rotated.
+    pub max_bytes: u64,
+    /// The number of rotated files kept, as `<path>.1` (the most recent) to
+    /// `<path>.<max_files>`.
+    pub max_files: usize,
+    /// Publish records to the observe event stream as well.
+    pub publish: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            fsync: false,
+            max_bytes: 64 * 1024 * 1024,
+            max_files: 5,
+            publish: false,
+        }
+    }
+}
+
+/// The audit log file, rotated by size.
+#[derive(Debug, Clone)]
+pub(super) struct AuditLog(Arc<Mutex<AuditFile>>);
+
+#[derive(Debug)]
+struct AuditFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    fsync: bool,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl AuditLog {
+    /// Opens the file of `config` to append to, which must have a path.
+    pub fn open(config: &AuditConfig) -> io::Result<Self> {
+        let path = config.path.clone().expect("audit log path");
+        let file = open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self(Arc::new(Mutex::new(AuditFile {
+            path,
+            file,
+            size,
+            fsync: config.fsync,
+            max_bytes: config.max_bytes,
+            max_files: config.max_files,
+        }))))
+    }
+
+    pub async fn append(&self, record: &AuditRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        blocking::run(AppendJob { audit_file: self.0.clone(), line })
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+struct AppendJob {
+    audit_file: Arc<Mutex<AuditFile>>,
+    line: Vec<u8>,
+}
+
+impl BlockingJob for AppendJob {
+    type Output = io::Result<()>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        self.audit_file.lock().expect("audit file lock").append(&self.line)
+    }
+}
+
+impl AuditFile {
+    fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line)?;
+        self.size += len;
+
+        if self.fsync {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Shifts the rotated files by one, dropping the oldest, and starts a
+    /// new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                match std::fs::rename(
+                    rotated(&self.path, n),
+                    rotated(&self.path, n + 1),
+                ) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                        return Err(e)
+                    }
+                    _ => {}
+                }
+            }
+            std::fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+
+        self.file = open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).mode(0o600).open(path)
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(format!(".{n}"));
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::RequestSummary;
+    use std::time::Duration;
+
+    fn record(method: &'static str) -> AuditRecord {
+        AuditRecord::new(
+            "uid:0".into(),
+            method,
+            RequestSummary::default(),
+            None,
+            Duration::ZERO,
+        )
+    }
+
+    fn lines(path: &Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_append_rotates_by_size() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-audit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+
+        // The longest record of the test
+        let line_len =
+            serde_json::to_vec(&record("Allocate")).unwrap().len() + 1;
+        let log = AuditLog::open(&AuditConfig {
+            path: Some(path.clone()),
+            fsync: true,
+            // Two records per file
+            max_bytes: 2 * line_len as u64,
+            max_files: 2,
+            publish: false,
+        })
+        .unwrap();
+
+        for method in ["Start", "Stop", "Allocate", "Free", "Start", "Stop"] {
+            log.append(&record(method)).await.unwrap();
+        }
+
+        let methods = |path: &Path| -> Vec<String> {
+            lines(path)
+                .iter()
+                .map(|line| line["method"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(methods(&path), ["Start", "Stop"]);
+        assert_eq!(methods(&rotated(&path, 1)), ["Allocate", "Free"]);
+        assert_eq!(methods(&rotated(&path, 2)), ["Start", "Stop"]);
+        assert!(!rotated(&path, 3).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
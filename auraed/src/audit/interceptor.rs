@@ -0,0 +1,91 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::authz::Identity;
+use crate::peer::Peer;
+use std::time::Instant;
+use tonic::{Request, Status};
+
+/// The client of a call, and when the call was received, attached to the
+/// request by [interceptor].
+#[derive(Debug, Clone)]
+pub(super) struct CallStart {
+    pub identity: String,
+    pub started: Instant,
+}
+
+/// Identifies the client of each call of a service, for the call to be
+/// audited by its handler.
+pub(crate) fn interceptor(
+    mut request: Request<()>,
+) -> Result<Request<()>, Status> {
+    let call_start =
+        CallStart { identity: identity(&request), started: Instant::now() };
+    let _ = request.extensions_mut().insert(call_start);
+    Ok(request)
+}
+
+/// Returns the identity of the client, or the uid of the process connected
+/// to the unix socket of auraed without TLS.
+fn identity<T>(request: &Request<T>) -> String {
+    let identity = match request.extensions().get::<Identity>() {
+        Some(identity) => identity.clone(),
+        None => Identity::of_connection(request.extensions()),
+    };
+    if !identity.is_anonymous() {
+        return identity.to_string();
+    }
+
+    match Peer::of_request(request).and_then(|peer| peer.uid) {
+        Some(uid) => format!("uid:{uid}"),
+        None => String::from("unknown"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interceptor_identifies_unix_socket_peer() {
+        let mut request = Request::new(());
+        let _ = request
+            .extensions_mut()
+            .insert(Peer { uid: Some(1000), ..Default::default() });
+
+        let request = interceptor(request).unwrap();
+        let call_start = request.extensions().get::<CallStart>().unwrap();
+        assert_eq!(call_start.identity, "uid:1000");
+    }
+
+    #[test]
+    fn test_interceptor_identifies_authorized_client() {
+        let mut request = Request::new(());
+        let _ = request
+            .extensions_mut()
+            .insert(Identity::with_names(["operator", "operator.aurae.io"]));
+
+        let request = interceptor(request).unwrap();
+        let call_start = request.extensions().get::<CallStart>().unwrap();
+        assert_eq!(call_start.identity, "operator");
+    }
+
+    #[test]
+    fn test_interceptor_unknown_peer() {
+        let request = interceptor(Request::new(())).unwrap();
+        let call_start = request.extensions().get::<CallStart>().unwrap();
+        assert_eq!(call_start.identity, "unknown");
+    }
+}
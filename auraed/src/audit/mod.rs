@@ -0,0 +1,131 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! An append-only log of the calls that change workloads: who made them,
+//! what they asked for, and how they ended.
+//!
+//! The [interceptor] of a service identifies the client of each call, and
+//! the handlers of the audited calls record them with [Audit].
+
+pub use audit_log::AuditConfig;
+pub(crate) use interceptor::interceptor;
+pub(crate) use record::{AuditRecord, RequestSummary};
+
+use audit_log::AuditLog;
+use interceptor::CallStart;
+use once_cell::sync::OnceCell;
+use std::time::Instant;
+use tonic::{Request, Status};
+use tracing::warn;
+
+mod audit_log;
+mod interceptor;
+mod record;
+
+static AUDITOR: OnceCell<Auditor> = OnceCell::new();
+
+#[derive(Debug)]
+struct Auditor {
+    log: Option<AuditLog>,
+    publish: bool,
+}
+
+/// Opens the audit log, if one is configured. Calls are not audited before.
+pub(crate) fn init(config: &AuditConfig) -> std::io::Result<()> {
+    let log =
+        config.path.as_ref().map(|_| AuditLog::open(config)).transpose()?;
+
+    let auditor = Auditor { log, publish: config.publish };
+    if AUDITOR.set(auditor).is_err() {
+        warn!("audit log is already initialized, ignoring {config:?}");
+    }
+    Ok(())
+}
+
+/// A request for `message`, made by a handler as part of handling `request`,
+/// such as the allocates of an import. The call is audited as made by the
+/// client of `request`.
+pub(crate) fn derive_request<T, U>(
+    request: &Request<T>,
+    message: U,
+) -> Request<U> {
+    let mut derived = Request::new(message);
+    if let Some(call_start) = request.extensions().get::<CallStart>() {
+        let _ = derived.extensions_mut().insert(CallStart {
+            identity: call_start.identity.clone(),
+            started: Instant::now(),
+        });
+    }
+    derived
+}
+
+/// An audited call, recorded with [Audit::end] once it is handled.
+#[derive(Debug)]
+pub(crate) struct Audit {
+    identity: String,
+    method: &'static str,
+    summary: RequestSummary,
+    started: Instant,
+}
+
+impl Audit {
+    /// Starts auditing a call of `method`, made with `request`.
+    pub fn begin<T>(
+        request: &Request<T>,
+        method: &'static str,
+        summary: RequestSummary,
+    ) -> Self {
+        let (identity, started) = match request.extensions().get::<CallStart>()
+        {
+            Some(CallStart { identity, started }) => {
+                (identity.clone(), *started)
+            }
+            None => (String::from("unknown"), Instant::now()),
+        };
+
+        Self { identity, method, summary, started }
+    }
+
+    /// Records `command` as the command of the call, once it is resolved.
+    pub fn set_command(&mut self, command: String) {
+        self.summary.command = Some(command);
+    }
+
+    /// Records the call, which failed with `status` if any. Returns the
+    /// record if auraed is configured to publish it to the observe event
+    /// stream as well.
+    pub async fn end(self, status: Option<&Status>) -> Option<AuditRecord> {
+        let auditor = AUDITOR.get()?;
+        if auditor.log.is_none() && !auditor.publish {
+            return None;
+        }
+
+        let record = AuditRecord::new(
+            self.identity,
+            self.method,
+            self.summary,
+            status,
+            self.started.elapsed(),
+        );
+
+        if let Some(log) = &auditor.log {
+            if let Err(e) = log.append(&record).await {
+                warn!("failed to write audit record {record:?}: {e}");
+            }
+        }
+
+        auditor.publish.then_some(record)
+    }
+}
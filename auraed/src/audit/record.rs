@@ -0,0 +1,152 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::logging::redaction;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct AuditRecord {
+    /// When the call ended, in RFC 3339 format.
+    pub timestamp: String,
+    pub identity: String,
+    pub method: &'static str,
+    #[serde(flatten)]
+    pub summary: RequestSummary,
+    /// The name of the gRPC status code the call ended with.
+    pub code: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    pub latency_ms: u64,
+}
+
+/// The fields of a request that identify what it changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct RequestSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executable_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl AuditRecord {
+    /// Secrets auraed is configured to redact from logs are redacted from
+    /// the command and the status message.
+    pub fn new(
+        identity: String,
+        method: &'static str,
+        mut summary: RequestSummary,
+        status: Option<&Status>,
+        latency: Duration,
+    ) -> Self {
+        summary.command = summary
+            .command
+            .map(|command| redaction::redact(&command).into_owned());
+
+        let (code, message) = match status {
+            Some(status) => (
+                status.code(),
+                redaction::redact(status.message()).into_owned(),
+            ),
+            None => (Code::Ok, String::new()),
+        };
+
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            identity,
+            method,
+            summary,
+            code: format!("{code:?}"),
+            message,
+            latency_ms: latency.as_millis() as u64,
+        }
+    }
+}
+
+impl From<AuditRecord> for proto::observe::Audit {
+    fn from(value: AuditRecord) -> Self {
+        let AuditRecord {
+            timestamp: _,
+            identity,
+            method,
+            summary: RequestSummary { cell_name: _, executable_name, command },
+            code,
+            message,
+            latency_ms,
+        } = value;
+
+        Self {
+            identity,
+            method: method.into(),
+            executable_name: executable_name.unwrap_or_default(),
+            command: command.unwrap_or_default(),
+            code,
+            message,
+            latency_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_omits_absent_fields() {
+        let record = AuditRecord::new(
+            "uid:0".into(),
+            "Free",
+            RequestSummary {
+                cell_name: Some("ae-1".into()),
+                ..Default::default()
+            },
+            None,
+            Duration::from_millis(12),
+        );
+
+        let json: serde_json::Value = serde_json::to_value(&record).unwrap();
+        let object = json.as_object().unwrap();
+        assert_eq!(object["identity"], "uid:0");
+        assert_eq!(object["method"], "Free");
+        assert_eq!(object["cell_name"], "ae-1");
+        assert_eq!(object["code"], "Ok");
+        assert_eq!(object["latency_ms"], 12);
+        assert!(!object.contains_key("executable_name"));
+        assert!(!object.contains_key("command"));
+        assert!(!object.contains_key("message"));
+    }
+
+    #[test]
+    fn test_record_of_failed_call() {
+        let record = AuditRecord::new(
+            "ae-client".into(),
+            "Stop",
+            RequestSummary {
+                executable_name: Some("ae-exe".into()),
+                ..Default::default()
+            },
+            Some(&Status::not_found("executable 'ae-exe' not found")),
+            Duration::ZERO,
+        );
+
+        assert_eq!(record.code, "NotFound");
+        assert_eq!(record.message, "executable 'ae-exe' not found");
+    }
+}
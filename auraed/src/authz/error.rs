@@ -0,0 +1,27 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub(crate) type Result<T> = std::result::Result<T, AuthzError>;
+
+#[derive(Debug, Error)]
+pub(crate) enum AuthzError {
+    #[error("failed to read authorization policy '{}': {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid authorization policy '{}': {source}", path.display())]
+    Parse { path: PathBuf, source: toml::de::Error },
+}
@@ -0,0 +1,232 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::peer::Peer;
+use std::fmt::{Display, Formatter};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::Extensions;
+use x509_certificate::X509Certificate;
+
+/// The DER encoded object identifier of the subject alternative name
+/// extension, 2.5.29.17.
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// The context specific tags of the rfc822Name, dNSName and
+/// uniformResourceIdentifier choices of a GeneralName.
+const GENERAL_NAME_EMAIL: u8 = 0x81;
+const GENERAL_NAME_DNS: u8 = 0x82;
+const GENERAL_NAME_URI: u8 = 0x86;
+
+/// The names a client is known by, from the certificate it presented: its
+/// common name first, then its email, DNS and URI subject alternative
+/// names. Clients of a unix socket without TLS are known by the user and
+/// group of their process instead, as `uid:<uid>` and `gid:<gid>`. Other
+/// clients without a certificate have no name.
+///
+/// Attached to the extensions of each request by the authorization layer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Identity {
+    names: Vec<String>,
+}
+
+impl Identity {
+    /// Returns the identity of the client of the connection a request was
+    /// received on, with TLS over TCP or over a unix socket, or without TLS
+    /// over a unix socket.
+    pub fn of_connection(extensions: &Extensions) -> Self {
+        let certs = extensions
+            .get::<TlsConnectInfo<Peer>>()
+            .and_then(|info| info.peer_certs())
+            .or_else(|| {
+                extensions
+                    .get::<TlsConnectInfo<TcpConnectInfo>>()
+                    .and_then(|info| info.peer_certs())
+            });
+
+        if let Some(cert) = certs.as_ref().and_then(|certs| certs.first()) {
+            return Self::of_certificate(cert);
+        }
+
+        extensions.get::<Peer>().map(Self::of_peer).unwrap_or_default()
+    }
+
+    /// Returns the identity of a process connected to a unix socket.
+    pub fn of_peer(peer: &Peer) -> Self {
+        let names = peer
+            .uid
+            .map(|uid| format!("uid:{uid}"))
+            .into_iter()
+            .chain(peer.gid.map(|gid| format!("gid:{gid}")))
+            .collect();
+
+        Self { names }
+    }
+
+    /// Returns the identity of a DER encoded certificate.
+    pub fn of_certificate(der: &[u8]) -> Self {
+        let Ok(cert) = X509Certificate::from_der(der) else {
+            return Self::default();
+        };
+
+        let mut names: Vec<_> =
+            cert.subject_common_name().into_iter().collect();
+        for extension in cert.iter_extensions() {
+            if extension.id.as_ref() == SUBJECT_ALT_NAME_OID {
+                let value = extension.value.clone().into_bytes();
+                names.extend(general_names(&value).unwrap_or_default());
+            }
+        }
+
+        Self { names }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn with_names<const N: usize>(names: [&str; N]) -> Self {
+        Self { names: names.into_iter().map(String::from).collect() }
+    }
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.names.first() {
+            Some(name) => f.write_str(name),
+            None => f.write_str("anonymous"),
+        }
+    }
+}
+
+/// Returns the email, DNS and URI names of a DER encoded GeneralNames
+/// sequence, or [None] if it is malformed.
+fn general_names(der: &[u8]) -> Option<Vec<String>> {
+    let (0x30, mut names, _) = der_element(der)? else {
+        return None;
+    };
+
+    let mut found = vec![];
+    while !names.is_empty() {
+        let (tag, value, rest) = der_element(names)?;
+        if matches!(
+            tag,
+            GENERAL_NAME_EMAIL | GENERAL_NAME_DNS | GENERAL_NAME_URI
+        ) {
+            found.push(String::from_utf8(value.to_vec()).ok()?);
+        }
+        names = rest;
+    }
+
+    Some(found)
+}
+
+/// Splits the DER element at the start of `der` into its tag, its value,
+/// and the bytes after it.
+fn der_element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, der) = der.split_first()?;
+    let (&len, mut der) = der.split_first()?;
+
+    let len = if len < 0x80 {
+        len as usize
+    } else {
+        // The long form, with the length in the next (len & 0x7f) bytes
+        let len_bytes = (len & 0x7f) as usize;
+        if len_bytes > std::mem::size_of::<usize>() || der.len() < len_bytes {
+            return None;
+        }
+        let (len, rest) = der.split_at(len_bytes);
+        der = rest;
+        len.iter().fold(0, |len, byte| (len << 8) | *byte as usize)
+    };
+
+    if der.len() < len {
+        return None;
+    }
+    let (value, rest) = der.split_at(len);
+    Some((tag, value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a DER element with a short form length.
+    fn element(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut der = vec![tag, value.len() as u8];
+        der.extend_from_slice(value);
+        der
+    }
+
+    #[test]
+    fn test_general_names() {
+        let names = [
+            element(GENERAL_NAME_DNS, b"observer-1.aurae.io"),
+            // An iPAddress, which is skipped
+            element(0x87, &[127, 0, 0, 1]),
+            element(GENERAL_NAME_URI, b"spiffe://aurae.io/observer"),
+            element(GENERAL_NAME_EMAIL, b"ops@aurae.io"),
+        ]
+        .concat();
+
+        assert_eq!(
+            general_names(&element(0x30, &names)),
+            Some(vec![
+                String::from("observer-1.aurae.io"),
+                String::from("spiffe://aurae.io/observer"),
+                String::from("ops@aurae.io"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_general_names_long_form_length() {
+        let dns_name = "a".repeat(200);
+        let mut der = vec![0x30, 0x81, 203, GENERAL_NAME_DNS, 0x81, 200];
+        der.extend_from_slice(dns_name.as_bytes());
+
+        assert_eq!(general_names(&der), Some(vec![dns_name]));
+    }
+
+    #[test]
+    fn test_general_names_malformed() {
+        assert_eq!(general_names(&[0x30, 4, GENERAL_NAME_DNS, 8, b'a']), None);
+        assert_eq!(general_names(&element(0x31, &[])), None);
+        assert_eq!(general_names(&[]), None);
+    }
+
+    #[test]
+    fn test_anonymous_without_certificate() {
+        let identity = Identity::of_connection(&Extensions::new());
+        assert!(identity.is_anonymous());
+        assert_eq!(identity.to_string(), "anonymous");
+    }
+
+    #[test]
+    fn test_unix_socket_peer_without_tls() {
+        let peer =
+            Peer { uid: Some(1000), gid: Some(100), ..Default::default() };
+        let mut extensions = Extensions::new();
+        let _ = extensions.insert(peer);
+
+        let identity = Identity::of_connection(&extensions);
+        assert_eq!(identity.names(), ["uid:1000", "gid:100"]);
+        assert_eq!(identity.to_string(), "uid:1000");
+    }
+}
@@ -0,0 +1,211 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Coarse grained authorization of gRPC calls by client identity.
+//!
+//! The [AuthzLayer] wraps every service of auraed. It attaches the
+//! [Identity] of the client to each request and, if an [AuthzPolicy] is
+//! configured, denies the calls the policy does not allow.
+
+pub(crate) use error::AuthzError;
+pub(crate) use identity::Identity;
+pub(crate) use policy::AuthzPolicy;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use tokio::signal::unix::{signal, SignalKind};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::Status;
+use tower_layer::Layer;
+use tracing::{error, info, warn};
+
+mod error;
+mod identity;
+mod policy;
+
+/// Authorizes the calls of the services it wraps.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuthzLayer {
+    /// All calls are allowed if [None].
+    policy: Arc<RwLock<Option<AuthzPolicy>>>,
+}
+
+impl AuthzLayer {
+    /// Loads the policy at `path`, if any, and reloads it from there on
+    /// SIGHUP. A policy that fails to reload is kept until the next SIGHUP.
+    pub fn new(path: Option<&Path>) -> Result<Self, AuthzError> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let policy = AuthzPolicy::load(path)?;
+        info!("Loaded authorization policy from {}", path.display());
+
+        let layer = Self { policy: Arc::new(RwLock::new(Some(policy))) };
+        layer.reload_on_sighup(path.to_path_buf());
+        Ok(layer)
+    }
+
+    fn reload_on_sighup(&self, path: PathBuf) {
+        let Ok(mut sighup) = signal(SignalKind::hangup()) else {
+            warn!("failed to listen for SIGHUP, the authorization policy won't be reloaded");
+            return;
+        };
+
+        let policy = self.policy.clone();
+        let _ignored = tokio::spawn(async move {
+            while sighup.recv().await.is_some() {
+                match AuthzPolicy::load(&path) {
+                    Ok(reloaded) => {
+                        *policy.write().expect("authz policy lock") =
+                            Some(reloaded);
+                        info!(
+                            "Reloaded authorization policy from {}",
+                            path.display()
+                        );
+                    }
+                    Err(e) => error!("{e}, keeping the current policy"),
+                }
+            }
+        });
+    }
+}
+
+impl<S> Layer<S> for AuthzLayer {
+    type Service = Authz<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authz { inner, policy: self.policy.clone() }
+    }
+}
+
+/// A service wrapped by an [AuthzLayer].
+#[derive(Debug, Clone)]
+pub(crate) struct Authz<S> {
+    inner: S,
+    policy: Arc<RwLock<Option<AuthzPolicy>>>,
+}
+
+impl<S, B> Service<http::Request<B>> for Authz<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        let identity = Identity::of_connection(request.extensions());
+
+        let allowed =
+            self.policy.read().expect("authz policy lock").as_ref().is_none_or(
+                |policy| policy.allows(&identity, request.uri().path()),
+            );
+        if !allowed {
+            // The policy is not named, so as not to reveal it
+            let status = Status::permission_denied(format!(
+                "'{identity}' is not allowed to call {}",
+                request.uri().path()
+            ));
+            return Box::pin(std::future::ready(Ok(status.into_http())));
+        }
+
+        let _ = request.extensions_mut().insert(identity);
+
+        // The inner service was polled ready, not its clone
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    /// Responds with the identity attached to the request.
+    #[derive(Debug, Clone)]
+    struct Echo;
+
+    impl Service<http::Request<()>> for Echo {
+        type Response = http::Response<BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<()>) -> Self::Future {
+            let identity = request.extensions().get::<Identity>().cloned();
+            std::future::ready(Ok(
+                Status::ok(format!("{identity:?}")).into_http()
+            ))
+        }
+    }
+
+    fn request(path: &str) -> http::Request<()> {
+        http::Request::builder().uri(path).body(()).unwrap()
+    }
+
+    async fn code(layer: &AuthzLayer, path: &str) -> (Code, String) {
+        let response = layer.layer(Echo).call(request(path)).await.unwrap();
+        let status = Status::from_header_map(response.headers()).unwrap();
+        (status.code(), status.message().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_allows_all_without_policy() {
+        let (code, message) =
+            code(&AuthzLayer::default(), "/aurae.cells.v0.CellService/Free")
+                .await;
+        assert_eq!(code, Code::Ok);
+        assert_eq!(message, format!("{:?}", Some(Identity::default())));
+    }
+
+    #[tokio::test]
+    async fn test_denies_by_policy() {
+        let layer = AuthzLayer {
+            policy: Arc::new(RwLock::new(Some(AuthzPolicy::default()))),
+        };
+
+        let (code, message) =
+            code(&layer, "/aurae.cells.v0.CellService/Free").await;
+        assert_eq!(code, Code::PermissionDenied);
+        assert_eq!(
+            message,
+            "'anonymous' is not allowed to call /aurae.cells.v0.CellService/Free"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::error::{AuthzError, Result};
+use super::identity::Identity;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Maps client identities to the gRPC methods they may call.
+///
+/// Loaded from a TOML file of rules. The first rule with an `identity`
+/// pattern matching any name of the client decides which methods it may
+/// call. Calls of clients matching no rule are denied.
+///
+/// ```toml
+/// [[rule]]
+/// identity = "observer-*"
+/// allow = ["aurae.observe.v0.ObserveService", "grpc.health.v1.Health"]
+///
+/// [[rule]]
+/// identity = "operator"
+/// allow = ["*"]
+/// ```
+///
+/// An `allow` pattern without a `/` is matched against the full name of
+/// the service, one with a `/` against `<service>/<method>`. `*` matches
+/// any number of characters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AuthzPolicy {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    identity: String,
+    allow: Vec<String>,
+}
+
+impl AuthzPolicy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let policy = std::fs::read_to_string(path)
+            .map_err(|source| AuthzError::Read { path: path.into(), source })?;
+
+        toml::from_str(&policy)
+            .map_err(|source| AuthzError::Parse { path: path.into(), source })
+    }
+
+    /// Returns true if `identity` may call the method at `path`, the path
+    /// of a gRPC request, e.g. `/aurae.cells.v0.CellService/Allocate`.
+    pub fn allows(&self, identity: &Identity, path: &str) -> bool {
+        let method = path.trim_start_matches('/');
+        let service = method.split_once('/').map_or(method, |(s, _)| s);
+
+        let Some(rule) = self.rules.iter().find(|rule| {
+            identity.names().iter().any(|name| matches(&rule.identity, name))
+        }) else {
+            return false;
+        };
+
+        rule.allow.iter().any(|pattern| {
+            if pattern.contains('/') {
+                matches(pattern, method)
+            } else {
+                matches(pattern, service)
+            }
+        })
+    }
+}
+
+/// Returns true if `value` matches `pattern`, in which `*` matches any
+/// number of characters.
+fn matches(pattern: &str, value: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == value;
+    };
+    let Some(mut value) = value.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts: Vec<_> = rest.split('*').collect();
+    let suffix = parts.pop().expect("split yields at least one part");
+    for part in parts {
+        match value.find(part) {
+            Some(i) => value = &value[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    value.len() >= suffix.len() && value.ends_with(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOCATE: &str = "/aurae.cells.v0.CellService/Allocate";
+    const GET_LOG_STREAM: &str =
+        "/aurae.observe.v0.ObserveService/GetLogStream";
+
+    fn policy(policy: &str) -> AuthzPolicy {
+        toml::from_str(policy).unwrap()
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("observer-*", "observer-1"));
+        assert!(matches("*", ""));
+        assert!(matches("*.aurae.io", "observer.aurae.io"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXcYb"));
+        assert!(!matches("ab*ba", "aba"));
+        assert!(!matches("observer-*", "operator"));
+        assert!(!matches("operator", "operator-1"));
+    }
+
+    #[test]
+    fn test_allows_first_matching_rule() {
+        let policy = policy(
+            r#"
+            [[rule]]
+            identity = "observer-*"
+            allow = ["aurae.observe.v0.ObserveService"]
+
+            [[rule]]
+            identity = "*"
+            allow = ["aurae.cells.v0.CellService/Allocate"]
+            "#,
+        );
+
+        let observer = Identity::with_names(["observer-1"]);
+        assert!(policy.allows(&observer, GET_LOG_STREAM));
+        // The first matching rule decides, even if a later one would allow
+        assert!(!policy.allows(&observer, ALLOCATE));
+
+        let operator = Identity::with_names(["operator"]);
+        assert!(policy.allows(&operator, ALLOCATE));
+        assert!(!policy.allows(&operator, GET_LOG_STREAM));
+    }
+
+    #[test]
+    fn test_denies_anonymous_and_unmatched() {
+        let policy = policy(
+            r#"
+            [[rule]]
+            identity = "operator"
+            allow = ["*"]
+            "#,
+        );
+
+        assert!(!policy.allows(&Identity::default(), ALLOCATE));
+        assert!(!policy.allows(&Identity::with_names(["observer"]), ALLOCATE));
+        assert!(!AuthzPolicy::default()
+            .allows(&Identity::with_names(["operator"]), ALLOCATE));
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields() {
+        assert!(toml::from_str::<AuthzPolicy>(
+            r#"
+            [[rule]]
+            identity = "operator"
+            allow = ["*"]
+            deny = ["*"]
+            "#,
+        )
+        .is_err());
+    }
+}
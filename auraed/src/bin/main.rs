@@ -34,9 +34,14 @@
 )]
 #![warn(clippy::unwrap_used)]
 
-use auraed::{prep_oci_spec_for_spawn, run, AuraedRuntime};
+use auraed::{
+    prep_oci_spec_for_spawn, run, AdmissionConfig, AuditConfig, AuraedConfig,
+    BlockingPoolsConfig, DiscoveryConfig, DnsConfig, EventJournalConfig,
+    IdMapping, ListenerConfig, OutputLimit, RedactionRule, ShutdownConfig,
+    SocketPermissions,
+};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::{net::IpAddr, path::PathBuf, time::Duration};
 use tracing::{error, info};
 
 /// Default exit code for successful termination of auraed.
@@ -48,30 +53,68 @@ pub const EXIT_ERROR: i32 = 1;
 /// Command line options for auraed.
 ///
 /// Defines the configurable options which can be used to populate
-/// an AuraeRuntime structure.
+/// an AuraeRuntime structure. Each option overrides the config file, and
+/// may be set by an AURAED_* environment variable instead.
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct AuraedOptions {
+    /// The config file. Defaults to /etc/aurae/auraed.toml, if it exists
+    #[clap(long, env = "AURAED_CONFIG", value_parser)]
+    config: Option<PathBuf>,
+    /// Print the config auraed would run with, from the config file,
+    /// environment and flags, and exit
+    #[clap(long)]
+    validate_config: bool,
     /// The signed server certificate. Defaults to /etc/aurae/pki/_signed.server.crt
-    #[clap(long, value_parser)]
+    #[clap(long, env = "AURAED_SERVER_CRT", value_parser)]
     server_crt: Option<String>,
     /// The secret server key. Defaults to /etc/aurae/pki/server.key
-    #[clap(long, value_parser)]
+    #[clap(long, env = "AURAED_SERVER_KEY", value_parser)]
     server_key: Option<String>,
     /// The CA certificate. Defaults to /etc/aurae/pki/ca.crt
-    #[clap(long, value_parser)]
+    #[clap(long, env = "AURAED_CA_CRT", value_parser)]
     ca_crt: Option<String>,
     /// Aurae socket address.  Depending on context, this should be a file or a network address.
     /// Defaults to ${runtime_dir}/aurae.sock or [::1]:8080 respectively.
+    /// A file starting with '@' names a socket in the abstract namespace,
+    /// which leaves no node on the filesystem.
     ///
     /// Warning: This socket is created (by default) with user
     /// mode 0o766 which allows for unprivileged access to the
     /// auraed daemon which can in turn be used to execute privileged
     /// processes and commands. Access to the socket must be governed
     /// by an appropriate mTLS Authorization setting in order to maintain
-    /// a secure multi tenant system.
-    #[clap(short, long, value_parser)]
+    /// a secure multi tenant system. Sockets in the abstract namespace
+    /// can be dialed by any process in the network namespace of auraed.
+    #[clap(short, long, env = "AURAED_SOCKET", value_parser)]
     socket: Option<String>,
+    /// Another socket to serve on, next to --socket. May be repeated.
+    /// Either tcp:<address>, with mTLS, or
+    /// unix:<path>[,mode=<octal>][,owner=<uid>][,group=<gid>] without TLS,
+    /// where clients are identified as uid:<uid> and gid:<gid> of their
+    /// process to the authorization policy, which is then required. The
+    /// mode defaults to 600.
+    /// Sockets are separated by ';' in AURAED_LISTEN
+    #[clap(
+        long = "listen",
+        env = "AURAED_LISTEN",
+        value_delimiter = ';',
+        value_parser
+    )]
+    listen: Vec<ListenerConfig>,
+    /// Octal mode of the unix socket file. Defaults to 766
+    #[clap(
+        long,
+        env = "AURAED_SOCKET_MODE",
+        value_parser = parse_socket_mode
+    )]
+    socket_mode: Option<u32>,
+    /// User id to own the unix socket file. Defaults to the user of auraed
+    #[clap(long, env = "AURAED_SOCKET_OWNER", value_parser)]
+    socket_owner: Option<u32>,
+    /// Group id to own the unix socket file. Defaults to the group of auraed
+    #[clap(long, env = "AURAED_SOCKET_GROUP", value_parser)]
+    socket_group: Option<u32>,
     /// Aurae runtime path.  Defaults to /var/run/aurae.
     ///
     /// Here is where the auraed daemon will store artifacts such as
@@ -82,7 +125,7 @@ struct AuraedOptions {
     /// a consequence of runtime operations.
     ///
     /// All aspects of the auraed daemon should respect this value.
-    #[clap(short, long, value_parser)]
+    #[clap(short, long, env = "AURAED_RUNTIME_DIR", value_parser)]
     runtime_dir: Option<String>,
     /// Aurae library path. Defaults to /var/lib/aurae
     ///
@@ -92,14 +135,226 @@ struct AuraedOptions {
     ///
     /// All aspects of the auraed library and dependency artifacts
     /// should respect this value.
-    #[clap(short, long, value_parser)]
+    #[clap(short, long, env = "AURAED_LIBRARY_DIR", value_parser)]
     library_dir: Option<String>,
+    /// Number of concurrent jobs in the thread pool for filesystem heavy
+    /// work such as unpacking archives. Defaults to 8
+    #[clap(long, env = "AURAED_BLOCKING_IO_THREADS", value_parser)]
+    blocking_io_threads: Option<usize>,
+    /// Number of concurrent jobs in the thread pool for mount and container
+    /// setup. Defaults to 4
+    #[clap(long, env = "AURAED_BLOCKING_MOUNT_THREADS", value_parser)]
+    blocking_mount_threads: Option<usize>,
+    /// Number of concurrent jobs in the thread pool for cryptographic work.
+    /// Defaults to 2
+    #[clap(long, env = "AURAED_BLOCKING_CRYPTO_THREADS", value_parser)]
+    blocking_crypto_threads: Option<usize>,
+    /// Secret to redact from logs, as <name>=<regex>. Matches are replaced
+    /// with [REDACTED:<name>]. May be repeated. Rules are separated by
+    /// newlines in AURAED_REDACT
+    #[clap(
+        long = "redact",
+        env = "AURAED_REDACT",
+        value_delimiter = '\n',
+        value_parser
+    )]
+    redact: Vec<RedactionRule>,
+    /// File to append a JSON line to for every call that allocates, frees,
+    /// starts or stops a workload. Calls are not audited to a file if unset
+    #[clap(long, env = "AURAED_AUDIT_LOG", value_parser)]
+    audit_log: Option<String>,
+    /// Flush each audit record to disk before the call returns
+    #[clap(
+        long,
+        env = "AURAED_AUDIT_FSYNC",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    audit_fsync: Option<bool>,
+    /// Size in bytes past which the audit log is rotated. Defaults to 64MiB
+    #[clap(long, env = "AURAED_AUDIT_MAX_BYTES", value_parser)]
+    audit_max_bytes: Option<u64>,
+    /// Number of rotated audit logs kept. Defaults to 5
+    #[clap(long, env = "AURAED_AUDIT_MAX_FILES", value_parser)]
+    audit_max_files: Option<usize>,
+    /// Publish audit records to the Observe.WatchEvents stream as well
+    #[clap(
+        long,
+        env = "AURAED_AUDIT_EVENTS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    audit_events: Option<bool>,
+    /// TOML file of which client identities may call which services and
+    /// methods, reloaded on SIGHUP. Any client trusted by the CA may call
+    /// any method if unset
+    #[clap(long, env = "AURAED_AUTHZ_POLICY", value_parser)]
+    authz_policy: Option<String>,
+    /// Lines per second each output of an executable may write, unless the
+    /// executable sets its own limit. Lines over it are suppressed. Zero is
+    /// unlimited. Defaults to 10000
+    #[clap(long, env = "AURAED_OUTPUT_LINES_PER_SECOND", value_parser)]
+    output_lines_per_second: Option<u64>,
+    /// Bytes per second each output of an executable may write, unless the
+    /// executable sets its own limit. Zero is unlimited. Defaults to 10485760
+    #[clap(long, env = "AURAED_OUTPUT_BYTES_PER_SECOND", value_parser)]
+    output_bytes_per_second: Option<u64>,
+    /// Seconds the output of a stopped executable remains available to
+    /// late readers. Defaults to 5
+    #[clap(long, env = "AURAED_LOG_GRACE_PERIOD", value_parser)]
+    log_grace_period: Option<u64>,
+    /// Seconds the output of a stopped executable is read for, before it is
+    /// cut short, as when a process it left running holds it open.
+    /// Defaults to 3
+    #[clap(long, env = "AURAED_OUTPUT_DRAIN_TIMEOUT", value_parser)]
+    output_drain_timeout: Option<u64>,
+    /// Seconds between samples of the lifetime stats of the cells, which is
+    /// how stale they may be. Zero only samples them when an executable of
+    /// the cell is stopped. Defaults to 10
+    #[clap(long, env = "AURAED_LIFETIME_STATS_INTERVAL", value_parser)]
+    lifetime_stats_interval: Option<u64>,
+    /// Seconds executables and cells are given to exit after SIGTERM when
+    /// auraed shuts down, before they are killed. Defaults to 10
+    #[clap(long, env = "AURAED_SHUTDOWN_GRACE_PERIOD", value_parser)]
+    shutdown_grace_period: Option<u64>,
+    /// Seconds after which auraed exits while shutting down, even if a
+    /// workload could not be stopped. Defaults to 30
+    #[clap(long, env = "AURAED_SHUTDOWN_DEADLINE", value_parser)]
+    shutdown_deadline: Option<u64>,
+    /// Seconds a peer is remembered after it last registered with the
+    /// discovery service. Defaults to 30
+    #[clap(long, env = "AURAED_DISCOVERY_PEER_TTL", value_parser)]
+    discovery_peer_ttl: Option<u64>,
+    /// Save the peers registered with the discovery service to the runtime
+    /// directory, for a restarted auraed to remember them
+    #[clap(
+        long,
+        env = "AURAED_DISCOVERY_PERSIST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    discovery_persist: Option<bool>,
+    /// Journal the lifecycle events to the runtime directory, for clients
+    /// to replay them across a restart of auraed. Default true
+    #[clap(
+        long,
+        env = "AURAED_EVENTS_PERSIST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    events_persist: Option<bool>,
+    /// Bytes the lifecycle events journal is kept under. Defaults to 16 MiB
+    #[clap(long, env = "AURAED_EVENTS_MAX_BYTES", value_parser)]
+    events_max_bytes: Option<u64>,
+    /// Seconds the journaled lifecycle events are kept for. Defaults to
+    /// 86400
+    #[clap(long, env = "AURAED_EVENTS_MAX_AGE", value_parser)]
+    events_max_age: Option<u64>,
+    /// Commit the memory, cpu and pids limits of cells and pods against the
+    /// capacity of the host, rejecting the allocations which oversubscribe
+    /// it. Default false
+    #[clap(
+        long,
+        env = "AURAED_ADMISSION",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    admission: Option<bool>,
+    /// Reject the allocations which oversubscribe the host, rather than only
+    /// warning of them. Default true
+    #[clap(
+        long,
+        env = "AURAED_ADMISSION_ENFORCE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    admission_enforce: Option<bool>,
+    /// Percent of the memory of the host which may be committed. Defaults
+    /// to 100
+    #[clap(long, env = "AURAED_ADMISSION_MEMORY_OVERCOMMIT", value_parser)]
+    admission_memory_overcommit: Option<u32>,
+    /// Percent of the cpus of the host which may be committed. Defaults to
+    /// 100
+    #[clap(long, env = "AURAED_ADMISSION_CPU_OVERCOMMIT", value_parser)]
+    admission_cpu_overcommit: Option<u32>,
+    /// Percent of the pids of the host which may be committed. Defaults to
+    /// 100
+    #[clap(long, env = "AURAED_ADMISSION_PIDS_OVERCOMMIT", value_parser)]
+    admission_pids_overcommit: Option<u32>,
+    /// Nameserver of the pods which do not set their own. May be repeated.
+    /// Nameservers are separated by commas in AURAED_DNS_NAMESERVER
+    #[clap(
+        long = "dns-nameserver",
+        env = "AURAED_DNS_NAMESERVER",
+        value_delimiter = ',',
+        value_parser
+    )]
+    dns_nameservers: Vec<IpAddr>,
+    /// Search domain of the pods which do not set their own. May be
+    /// repeated. Domains are separated by commas in AURAED_DNS_SEARCH
+    #[clap(
+        long = "dns-search",
+        env = "AURAED_DNS_SEARCH",
+        value_delimiter = ',',
+        value_parser
+    )]
+    dns_searches: Vec<String>,
+    /// Resolver option of the pods which do not set their own, such as
+    /// ndots:2. May be repeated. Options are separated by commas in
+    /// AURAED_DNS_OPTION
+    #[clap(
+        long = "dns-option",
+        env = "AURAED_DNS_OPTION",
+        value_delimiter = ',',
+        value_parser
+    )]
+    dns_options: Vec<String>,
     /// Toggle verbosity. Default false
     #[clap(short, long, alias = "ritz")]
     verbose: bool,
     /// Run auraed as a nested instance of itself in an Aurae cell.
     #[clap(long)]
     nested: bool,
+    /// Reap the processes orphaned by their parent, which are re-parented
+    /// to auraed as a child subreaper. Always done when auraed is pid 1
+    #[clap(
+        long,
+        env = "AURAED_SUBREAPER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    subreaper: Option<bool>,
+    /// Start the executables in a user namespace, mapping its uids to those
+    /// of the host, as <container_id>:<host_id>:<size>. May be repeated, and
+    /// requires --gid-map. Mappings are separated by commas in
+    /// AURAED_UID_MAP
+    #[clap(
+        long = "uid-map",
+        env = "AURAED_UID_MAP",
+        value_delimiter = ',',
+        value_parser
+    )]
+    uid_map: Vec<IdMapping>,
+    /// The gids of the user namespace of --uid-map, mapped as it does
+    #[clap(
+        long = "gid-map",
+        env = "AURAED_GID_MAP",
+        value_delimiter = ',',
+        value_parser
+    )]
+    gid_map: Vec<IdMapping>,
+    /// Start each executable in a leaf cgroup of its own under this cgroup.
+    /// Passed by the auraed allocating a cell with per executable accounting
+    /// to its nested auraed
+    #[clap(long, hide = true, requires = "nested")]
+    executable_cgroups: Option<PathBuf>,
     // Subcommands for the project
     #[clap(subcommand)]
     subcmd: Option<SubCommands>,
@@ -113,69 +368,277 @@ enum SubCommands {
     },
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command line arguments into AuraedOptions
     let options = AuraedOptions::parse();
 
+    // The nested auraeds and executables we start are not configured as we
+    // are, unless we pass the settings on. Removed before the runtime
+    // starts its threads, as the environment must not change while another
+    // thread may read it.
+    if options.subcmd.is_none() {
+        for (name, _) in std::env::vars_os() {
+            if name.to_string_lossy().starts_with("AURAED_") {
+                std::env::remove_var(name);
+            }
+        }
+    }
+
+    let runtime =
+        tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+
     // Match on the subcommand and handle accordingly
-    let exit_code = match &options.subcmd {
-        Some(SubCommands::Spawn { output }) => {
-            handle_spawn_subcommand(output).await
+    let exit_code = runtime.block_on(async move {
+        match &options.subcmd {
+            Some(SubCommands::Spawn { output }) => {
+                handle_spawn_subcommand(output).await
+            }
+            None => handle_default(options).await,
         }
-        None => handle_default(options).await,
-    };
+    });
 
     std::process::exit(exit_code);
 }
 
 async fn handle_default(options: AuraedOptions) -> i32 {
+    // A nested auraed only reads a config file it is given
+    let config = match (&options.config, options.nested) {
+        (None, true) => Ok((AuraedConfig::default(), vec![])),
+        (path, _) => AuraedConfig::load(path.as_deref()),
+    };
+    let (config, unknown_keys) = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return EXIT_ERROR;
+        }
+    };
+    // Logging is not set up yet
+    for key in unknown_keys {
+        eprintln!("Ignoring unknown key '{key}' in the config");
+    }
+
+    let validate_config = options.validate_config;
+    let verbose = options.verbose;
+    let nested = options.nested;
+    let executable_cgroups = options.executable_cgroups.clone();
+    let config = apply_options(config, options);
+
+    if validate_config {
+        return match toml::to_string(&config) {
+            Ok(config) => {
+                print!("{config}");
+                EXIT_OKAY
+            }
+            Err(e) => {
+                eprintln!("Failed to print the config: {e}");
+                EXIT_ERROR
+            }
+        };
+    }
+
     info!("Starting Aurae Daemon Runtime");
     info!("Aurae Daemon is pid {}", std::process::id());
 
+    // Run the auraed daemon with the configured runtime
+    let (mut runtime, socket) = config.into_runtime();
+    runtime.executable_cgroups = executable_cgroups;
+    if let Err(e) = run(runtime, socket, verbose, nested).await {
+        error!("{:?}", e); // Log any errors that occur
+        EXIT_ERROR // Return error exit code
+    } else {
+        EXIT_OKAY // Return success exit code
+    }
+}
+
+/// Overrides the config with the options which were set.
+fn apply_options(config: AuraedConfig, options: AuraedOptions) -> AuraedConfig {
     // Destructure the options into individual variables
     let AuraedOptions {
+        config: _,
+        validate_config: _,
         server_crt,
         server_key,
         ca_crt,
         socket,
+        listen,
+        socket_mode,
+        socket_owner,
+        socket_group,
         runtime_dir,
         library_dir,
-        verbose,
-        nested,
+        blocking_io_threads,
+        blocking_mount_threads,
+        blocking_crypto_threads,
+        redact,
+        audit_log,
+        audit_fsync,
+        audit_max_bytes,
+        audit_max_files,
+        audit_events,
+        authz_policy,
+        output_lines_per_second,
+        output_bytes_per_second,
+        log_grace_period,
+        output_drain_timeout,
+        lifetime_stats_interval,
+        shutdown_grace_period,
+        shutdown_deadline,
+        discovery_peer_ttl,
+        discovery_persist,
+        events_persist,
+        events_max_bytes,
+        events_max_age,
+        admission,
+        admission_enforce,
+        admission_memory_overcommit,
+        admission_cpu_overcommit,
+        admission_pids_overcommit,
+        dns_nameservers,
+        dns_searches,
+        dns_options,
+        verbose: _,
+        nested: _,
+        subreaper,
+        uid_map,
+        gid_map,
+        executable_cgroups: _,
         subcmd: _,
     } = options;
 
-    // Destructure the default runtime into individual variables
-    let AuraedRuntime {
-        auraed: default_auraed,
-        ca_crt: default_ca_crt,
-        server_crt: default_server_crt,
-        server_key: default_server_key,
-        runtime_dir: default_runtime_dir,
-        library_dir: default_library_dir,
-    } = AuraedRuntime::default();
-
-    // Create a new runtime configuration, using provided options or defaults
-    let runtime = AuraedRuntime {
-        auraed: default_auraed,
-        ca_crt: ca_crt.map(PathBuf::from).unwrap_or(default_ca_crt),
-        server_crt: server_crt.map(PathBuf::from).unwrap_or(default_server_crt),
-        server_key: server_key.map(PathBuf::from).unwrap_or(default_server_key),
+    // Destructure the config into individual variables
+    let AuraedConfig {
+        ca_crt: config_ca_crt,
+        server_crt: config_server_crt,
+        server_key: config_server_key,
+        socket: config_socket,
+        listeners: config_listeners,
+        runtime_dir: config_runtime_dir,
+        library_dir: config_library_dir,
+        log_redaction: config_log_redaction,
+        output_limit: config_output_limit,
+        log_grace_period: config_log_grace_period,
+        output_drain_timeout: config_output_drain_timeout,
+        lifetime_stats_interval: config_lifetime_stats_interval,
+        authz_policy: config_authz_policy,
+        subreaper: config_subreaper,
+        uid_map: config_uid_map,
+        gid_map: config_gid_map,
+        socket_permissions: config_socket_permissions,
+        blocking_pools: config_blocking_pools,
+        audit: config_audit,
+        shutdown: config_shutdown,
+        discovery: config_discovery,
+        events: config_events,
+        admission: config_admission,
+        dns: config_dns,
+    } = config;
+
+    // Create a new configuration, using provided options or the config
+    AuraedConfig {
+        ca_crt: ca_crt.map(PathBuf::from).unwrap_or(config_ca_crt),
+        server_crt: server_crt.map(PathBuf::from).unwrap_or(config_server_crt),
+        server_key: server_key.map(PathBuf::from).unwrap_or(config_server_key),
+        socket: socket.or(config_socket),
+        listeners: if listen.is_empty() { config_listeners } else { listen },
         runtime_dir: runtime_dir
             .map(PathBuf::from)
-            .unwrap_or(default_runtime_dir),
+            .unwrap_or(config_runtime_dir),
         library_dir: library_dir
             .map(PathBuf::from)
-            .unwrap_or(default_library_dir),
-    };
-
-    // Run the auraed daemon with the configured runtime
-    if let Err(e) = run(runtime, socket, verbose, nested).await { 
-        error!("{:?}", e); // Log any errors that occur
-        EXIT_ERROR // Return error exit code
-    } else {
-        EXIT_OKAY // Return success exit code
+            .unwrap_or(config_library_dir),
+        log_redaction: if redact.is_empty() {
+            config_log_redaction
+        } else {
+            redact
+        },
+        output_limit: OutputLimit {
+            lines_per_second: output_lines_per_second
+                .unwrap_or(config_output_limit.lines_per_second),
+            bytes_per_second: output_bytes_per_second
+                .unwrap_or(config_output_limit.bytes_per_second),
+        },
+        log_grace_period: log_grace_period
+            .map(Duration::from_secs)
+            .unwrap_or(config_log_grace_period),
+        output_drain_timeout: output_drain_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(config_output_drain_timeout),
+        lifetime_stats_interval: lifetime_stats_interval
+            .map(Duration::from_secs)
+            .unwrap_or(config_lifetime_stats_interval),
+        authz_policy: authz_policy.map(PathBuf::from).or(config_authz_policy),
+        subreaper: subreaper.unwrap_or(config_subreaper),
+        uid_map: if uid_map.is_empty() { config_uid_map } else { uid_map },
+        gid_map: if gid_map.is_empty() { config_gid_map } else { gid_map },
+        socket_permissions: SocketPermissions {
+            mode: socket_mode.unwrap_or(config_socket_permissions.mode),
+            owner: socket_owner.or(config_socket_permissions.owner),
+            group: socket_group.or(config_socket_permissions.group),
+        },
+        blocking_pools: BlockingPoolsConfig {
+            io_heavy: blocking_io_threads
+                .unwrap_or(config_blocking_pools.io_heavy),
+            mount_ops: blocking_mount_threads
+                .unwrap_or(config_blocking_pools.mount_ops),
+            crypto: blocking_crypto_threads
+                .unwrap_or(config_blocking_pools.crypto),
+        },
+        audit: AuditConfig {
+            path: audit_log.map(PathBuf::from).or(config_audit.path),
+            fsync: audit_fsync.unwrap_or(config_audit.fsync),
+            max_bytes: audit_max_bytes.unwrap_or(config_audit.max_bytes),
+            max_files: audit_max_files.unwrap_or(config_audit.max_files),
+            publish: audit_events.unwrap_or(config_audit.publish),
+        },
+        shutdown: ShutdownConfig {
+            grace_period: shutdown_grace_period
+                .map(Duration::from_secs)
+                .unwrap_or(config_shutdown.grace_period),
+            deadline: shutdown_deadline
+                .map(Duration::from_secs)
+                .unwrap_or(config_shutdown.deadline),
+        },
+        discovery: DiscoveryConfig {
+            peer_ttl: discovery_peer_ttl
+                .map(Duration::from_secs)
+                .unwrap_or(config_discovery.peer_ttl),
+            persist: discovery_persist.unwrap_or(config_discovery.persist),
+        },
+        events: EventJournalConfig {
+            persist: events_persist.unwrap_or(config_events.persist),
+            max_bytes: events_max_bytes.unwrap_or(config_events.max_bytes),
+            max_age: events_max_age
+                .map(Duration::from_secs)
+                .unwrap_or(config_events.max_age),
+        },
+        admission: AdmissionConfig {
+            enabled: admission.unwrap_or(config_admission.enabled),
+            enforce: admission_enforce.unwrap_or(config_admission.enforce),
+            memory_overcommit: admission_memory_overcommit
+                .unwrap_or(config_admission.memory_overcommit),
+            cpu_overcommit: admission_cpu_overcommit
+                .unwrap_or(config_admission.cpu_overcommit),
+            pids_overcommit: admission_pids_overcommit
+                .unwrap_or(config_admission.pids_overcommit),
+        },
+        dns: DnsConfig {
+            nameservers: if dns_nameservers.is_empty() {
+                config_dns.nameservers
+            } else {
+                dns_nameservers
+            },
+            searches: if dns_searches.is_empty() {
+                config_dns.searches
+            } else {
+                dns_searches
+            },
+            options: if dns_options.is_empty() {
+                config_dns.options
+            } else {
+                dns_options
+            },
+        },
     }
 }
 
@@ -183,4 +646,9 @@ async fn handle_spawn_subcommand(output: &str) -> i32 {
     info!("Spawning Auraed OCI bundle: {}", output);
     prep_oci_spec_for_spawn(output); // Prepare the OCI spec for spawning
     EXIT_OKAY // Return success exit code
-}
\ No newline at end of file
+}
+
+/// Parses a file mode in octal, with or without a leading "0o".
+fn parse_socket_mode(mode: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+}
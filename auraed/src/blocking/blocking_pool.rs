@@ -0,0 +1,205 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{BlockingError, Result};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// A snapshot of the load of a [BlockingPool].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockingPoolStats {
+    /// Name of the pool.
+    pub name: &'static str,
+    /// The number of jobs that can run concurrently.
+    pub size: usize,
+    /// The number of jobs waiting for a free slot.
+    pub queued: usize,
+    /// The number of jobs currently running.
+    pub running: usize,
+}
+
+#[derive(Debug)]
+pub(super) struct BlockingPool {
+    name: &'static str,
+    size: usize,
+    permits: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    running: Arc<AtomicUsize>,
+    saturated: AtomicBool,
+}
+
+impl BlockingPool {
+    pub fn new(name: &'static str, size: usize) -> Self {
+        // a pool of size 0 would never run anything
+        let size = size.max(1);
+
+        Self {
+            name,
+            size,
+            permits: Arc::new(Semaphore::new(size)),
+            queued: Default::default(),
+            running: Default::default(),
+            saturated: AtomicBool::new(false),
+        }
+    }
+
+    /// Runs `f` on a blocking thread once the pool has a free slot.
+    pub async fn run<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let _queued = Counter::increment(&self.queued);
+
+                if !self.saturated.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "blocking pool '{}' is saturated: {:?}",
+                        self.name,
+                        self.stats()
+                    );
+                }
+
+                self.permits.clone().acquire_owned().await.map_err(|_| {
+                    BlockingError::PoolClosed { pool: self.name }
+                })?
+            }
+        };
+
+        let running = Counter::increment(&self.running);
+
+        // This is the one place that is allowed to spawn blocking tasks.
+        #[allow(clippy::disallowed_methods)]
+        let handle = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let _running = running;
+            f()
+        });
+
+        let res = handle.await;
+
+        if self.queued.load(Ordering::Relaxed) == 0
+            && self.saturated.swap(false, Ordering::Relaxed)
+        {
+            info!("blocking pool '{}' is no longer saturated", self.name);
+        }
+
+        res.map_err(|e| BlockingError::JobFailed { pool: self.name, source: e })
+    }
+
+    pub fn stats(&self) -> BlockingPoolStats {
+        BlockingPoolStats {
+            name: self.name,
+            size: self.size,
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Increments a counter, and decrements it again when dropped.
+struct Counter(Arc<AtomicUsize>);
+
+impl Counter {
+    fn increment(counter: &Arc<AtomicUsize>) -> Self {
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter.clone())
+    }
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        let _ = self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_jobs_above_pool_size_are_queued() {
+        let pool = Arc::new(BlockingPool::new("test", 2));
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    pool.run(|| std::thread::sleep(Duration::from_millis(200)))
+                        .await
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let stats = pool.stats();
+        assert_eq!(stats.running, 2);
+        assert_eq!(stats.queued, 3);
+
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.running, 0);
+        assert_eq!(stats.queued, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_saturated_pool_does_not_delay_the_runtime() {
+        let pool = Arc::new(BlockingPool::new("test", 2));
+
+        // Saturate the pool with jobs that block for much longer than
+        // the latency we accept from the runtime.
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    pool.run(|| std::thread::sleep(Duration::from_millis(500)))
+                        .await
+                })
+            })
+            .collect();
+
+        // Unrelated async work on the single runtime worker stays responsive
+        for _ in 0..10 {
+            let start = Instant::now();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert!(start.elapsed() < Duration::from_millis(100));
+        }
+
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_job_is_an_error() {
+        let pool = BlockingPool::new("test", 1);
+
+        let res = pool.run(|| panic!("job panicked")).await;
+        assert!(matches!(res, Err(BlockingError::JobFailed { .. })));
+
+        // the slot of the panicked job is released
+        assert_eq!(pool.run(|| 42).await.unwrap(), 42);
+    }
+}
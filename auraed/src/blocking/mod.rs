@@ -0,0 +1,137 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Managed thread pools for blocking, syscall heavy operations.
+//!
+//! Work such as unpacking archives, creating containers, or walking large
+//! directory trees blocks the thread it runs on. Running it on the async
+//! runtime's worker threads delays every other request served by those
+//! threads, so it is scheduled on one of the named pools instead.
+//!
+//! Each pool bounds how many of its jobs run at once. Jobs above that bound
+//! wait in the pool's queue, and a warning is logged when a pool saturates.
+
+pub use blocking_pool::BlockingPoolStats;
+pub use error::{BlockingError, Result};
+
+use blocking_pool::BlockingPool;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+mod blocking_pool;
+mod error;
+
+static BLOCKING_POOLS: OnceCell<BlockingPools> = OnceCell::new();
+
+/// The pools that blocking work can be scheduled on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pool {
+    /// Filesystem heavy work, like packing and unpacking archives.
+    IoHeavy,
+    /// Mount, namespace, and container setup.
+    MountOps,
+    /// Hashing, signing, and verification.
+    Crypto,
+}
+
+impl Pool {
+    fn name(&self) -> &'static str {
+        match self {
+            Pool::IoHeavy => "io-heavy",
+            Pool::MountOps => "mount-ops",
+            Pool::Crypto => "crypto",
+        }
+    }
+}
+
+/// The number of jobs each pool runs concurrently.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockingPoolsConfig {
+    /// Size of the [Pool::IoHeavy] pool.
+    pub io_heavy: usize,
+    /// Size of the [Pool::MountOps] pool.
+    pub mount_ops: usize,
+    /// Size of the [Pool::Crypto] pool.
+    pub crypto: usize,
+}
+
+impl Default for BlockingPoolsConfig {
+    fn default() -> Self {
+        Self { io_heavy: 8, mount_ops: 4, crypto: 2 }
+    }
+}
+
+#[derive(Debug)]
+struct BlockingPools {
+    io_heavy: BlockingPool,
+    mount_ops: BlockingPool,
+    crypto: BlockingPool,
+}
+
+impl BlockingPools {
+    fn new(config: &BlockingPoolsConfig) -> Self {
+        let BlockingPoolsConfig { io_heavy, mount_ops, crypto } = config;
+
+        Self {
+            io_heavy: BlockingPool::new(Pool::IoHeavy.name(), *io_heavy),
+            mount_ops: BlockingPool::new(Pool::MountOps.name(), *mount_ops),
+            crypto: BlockingPool::new(Pool::Crypto.name(), *crypto),
+        }
+    }
+
+    fn get(&self, pool: Pool) -> &BlockingPool {
+        match pool {
+            Pool::IoHeavy => &self.io_heavy,
+            Pool::MountOps => &self.mount_ops,
+            Pool::Crypto => &self.crypto,
+        }
+    }
+}
+
+/// Creates the pools. Must be called before the first job is run,
+/// otherwise the pools are created with the default configuration.
+pub(crate) fn init(config: &BlockingPoolsConfig) {
+    if BLOCKING_POOLS.set(BlockingPools::new(config)).is_err() {
+        warn!("blocking pools are already initialized, ignoring {config:?}");
+    }
+}
+
+fn pools() -> &'static BlockingPools {
+    BLOCKING_POOLS.get_or_init(|| BlockingPools::new(&Default::default()))
+}
+
+/// Returns the current load of every pool.
+pub(crate) fn stats() -> Vec<BlockingPoolStats> {
+    let BlockingPools { io_heavy, mount_ops, crypto } = pools();
+    vec![io_heavy.stats(), mount_ops.stats(), crypto.stats()]
+}
+
+/// A unit of blocking work, and the pool it runs on.
+pub(crate) trait BlockingJob: Send + 'static {
+    type Output: Send + 'static;
+
+    /// The pool the job is scheduled on.
+    const POOL: Pool;
+
+    /// Performs the work. Called on a thread of [BlockingJob::POOL].
+    fn run(self) -> Self::Output;
+}
+
+/// Runs a [BlockingJob] on its pool, waiting for a free slot if the pool is saturated.
+pub(crate) async fn run<J: BlockingJob>(job: J) -> Result<J::Output> {
+    pools().get(J::POOL).run(move || job.run()).await
+}
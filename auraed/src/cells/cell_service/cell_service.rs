@@ -14,101 +14,556 @@
 \* -------------------------------------------------------------------------- */
 
 use super::{
-    cells::{CellName, Cells, CellsCache},
+    cells::{
+        cgroups::{Cgroup, CgroupSettings, CgroupSpec, Limit, OomEvent},
+        Cell, CellAdoption, CellName, CellSpec, Cells, CellsCache,
+    },
+    copy::{self, CopyDestination, CopyError, CopyPath},
     error::CellsServiceError,
-    executables::Executables,
+    executables::{
+        start_order, substitute, wait_ready, Executable, ExecutableName,
+        Executables, ExecutablesError, KillMode, Reservation,
+        AURAE_RUNTIME_DIR_VARIABLE, CELL_NAME_VARIABLE,
+    },
+    export::{self, StartedExecutables},
+    net_check::{self, NetCheck, NetCheckReport},
+    state::{CellRecord, CellServiceState, ExecutableRecord, StateFile},
+    usage::{UsageSample, UsageSampler, UsageSource},
     validation::{
-        ValidatedCellServiceAllocateRequest, ValidatedCellServiceFreeRequest,
-        ValidatedCellServiceStartRequest, ValidatedCellServiceStopRequest,
+        ValidatedCell, ValidatedCellDefinition,
+        ValidatedCellServiceAllocateRequest,
+        ValidatedCellServiceCopyFromRequest, ValidatedCellServiceFreeRequest,
+        ValidatedCellServiceImportRequest,
+        ValidatedCellServiceListExecutablesRequest,
+        ValidatedCellServiceNetCheckRequest,
+        ValidatedCellServiceQuarantineRequest,
+        ValidatedCellServiceStartBatchRequest,
+        ValidatedCellServiceStartRequest, ValidatedCellServiceStatsRequest,
+        ValidatedCellServiceStopRequest,
+        ValidatedCellServiceUnquarantineRequest,
+        ValidatedCellServiceUpdateRequest,
+        ValidatedCellServiceWatchOomEventsRequest,
+        ValidatedCellServiceWatchUsageRequest, ValidatedCopyIntoHeader,
+        ValidatedExecutable, ValidatedUsageTarget,
     },
-    Result,
+    workload, Result,
+};
+use crate::{
+    admission::{AdmissionController, Resources, Workload},
+    audit::{derive_request, Audit, RequestSummary},
+    blocking::{self, BlockingJob, Pool},
+    cells::cell_service::cells::CellsError,
+    deadline::request_deadline,
+    logging::redaction,
+    observe::ObserveService,
+    AURAED_RUNTIME,
 };
-use crate::{cells::cell_service::cells::CellsError, observe::ObserveService};
-use ::validation::ValidatedType;
-use backoff::backoff::Backoff;
-use client::{cells::cell_service::CellServiceClient, Client, ClientError};
+use ::validation::{ValidatedField, ValidatedType};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use bytes::Bytes;
+use client::{
+    cells::cell_service::CellServiceClient, grpc::health::health::HealthClient,
+    AuraeSocket, Client, ClientError, Disconnected, RetryPolicy,
+};
+use nix::unistd::Pid;
 use proto::{
     cells::{
-        cell_service_server, Cell, CellGraphNode, CellServiceAllocateRequest,
-        CellServiceAllocateResponse, CellServiceFreeRequest,
-        CellServiceFreeResponse, CellServiceListRequest,
-        CellServiceListResponse, CellServiceStartRequest,
-        CellServiceStartResponse, CellServiceStopRequest,
-        CellServiceStopResponse, CpuController, CpusetController,
-        MemoryController,
+        cell_service_copy_from_response, cell_service_copy_into_request,
+        cell_service_server, executable_start_result::Outcome,
+        AdmissionResources, Cell, CellDefinition, CellGraphNode,
+        CellServiceAdmissionRequest, CellServiceAdmissionResponse,
+        CellServiceAllocateRequest, CellServiceAllocateResponse,
+        CellServiceCopyFromRequest, CellServiceCopyFromResponse,
+        CellServiceCopyIntoRequest, CellServiceCopyIntoResponse,
+        CellServiceExportRequest, CellServiceExportResponse,
+        CellServiceFreeRequest, CellServiceFreeResponse,
+        CellServiceImportRequest, CellServiceImportResponse,
+        CellServiceListExecutablesRequest, CellServiceListExecutablesResponse,
+        CellServiceListPortReservationsRequest,
+        CellServiceListPortReservationsResponse, CellServiceListRequest,
+        CellServiceListResponse, CellServiceNetCheckRequest,
+        CellServiceNetCheckResponse, CellServiceQuarantineRequest,
+        CellServiceQuarantineResponse, CellServiceStartBatchRequest,
+        CellServiceStartBatchResponse, CellServiceStartRequest,
+        CellServiceStartResponse, CellServiceStatsRequest,
+        CellServiceStatsResponse, CellServiceStopRequest,
+        CellServiceStopResponse, CellServiceUnquarantineRequest,
+        CellServiceUnquarantineResponse, CellServiceUpdateRequest,
+        CellServiceUpdateResponse, CellServiceWatchOomEventsRequest,
+        CellServiceWatchOomEventsResponse, CellServiceWatchUsageRequest,
+        CellServiceWatchUsageResponse, CellsDocument, CopyIntoHeader,
+        CpuController, CpuStats, CpusetController, DeviceRule,
+        ExecutableDefinition, ExecutableExit, ExecutableRestarts,
+        ExecutableStartResult, ExecutableStatus, IdMapping, ImportAction,
+        ImportChange, LifetimeStats, MemoryController, MemoryEvents,
+        MemoryStats, NestedAuraed, NestedAuraedHealth, NetCheckAttempt,
+        PidsStats, PortReservation, UsageTarget, UserNamespace,
+    },
+    grpc::health::{health_check_response::ServingStatus, HealthCheckRequest},
+    observe::{
+        lifecycle_event::Kind, CellAllocated, CellFreed, ExecutableExited,
+        ExecutableStarted, OomKill,
     },
-    observe::LogChannelType,
 };
-use std::os::unix::fs::MetadataExt;
-use std::time::Duration;
-use std::{process::ExitStatus, sync::Arc};
-use tokio::sync::Mutex;
-use tonic::{Code, Request, Response, Status};
+use std::collections::HashMap;
+use std::os::unix::{fs::MetadataExt, process::ExitStatusExt};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use std::{
+    process::ExitStatus,
+    sync::{Arc, Weak},
+};
+use tokio::sync::{broadcast, mpsc, Mutex, Semaphore};
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::{Code, Request, Response, Status, Streaming};
 use tracing::{info, trace, warn};
 
+/// Network checks generate traffic as the node, so only a few may run at
+/// once. Requests above the limit are rejected rather than queued.
+const MAX_CONCURRENT_NET_CHECKS: usize = 4;
+
+/// How long listing the cells waits for each nested auraed to report its
+/// health, before reporting it as unreachable.
+const NESTED_AURAED_HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
 /**
  * Macro to perform an operation within a cell.
  * It retries the operation with an exponential backoff strategy in case of connection errors.
  */
 macro_rules! do_in_cell {
     ($self:ident, $cell_name:ident, $function:ident, $request:ident) => {{
-        let mut cells = $self.cells.lock().await;
-
-        // Retrieve the client socket for the specified cell
-        let client_socket = cells
+        // Retrieve the client socket for the specified cell. The cells are
+        // not locked during the call, so that a slow call to one cell does
+        // not hold up the others.
+        let client_socket = $self
+            .cells
+            .lock()
+            .await
             .get(&$cell_name, |cell| cell.client_socket())
             .map_err(CellsServiceError::CellsError)?;
 
-        // Initialize the exponential backoff strategy for retrying the operation
-        let mut retry_strategy = backoff::ExponentialBackoffBuilder::new()
-            .with_initial_interval(Duration::from_millis(50)) // 1st retry in 50ms
-            .with_multiplier(10.0) // 10x the delay each attempt
-            .with_randomization_factor(0.5) // with a randomness of +/-50%
-            .with_max_interval(Duration::from_secs(3)) // but never delay more than 3s
-            .with_max_elapsed_time(Some(Duration::from_secs(20))) // or 20s total
-            .build();
-
-        // Attempt to create a new client with retries in case of connection errors
-        let client = loop {
-            match Client::new_no_tls(client_socket.clone()).await {
-                Ok(client) => break Ok(client),
-                e @ Err(ClientError::ConnectionError(_)) => {
-                    trace!("aurae client failed to connect: {e:?}");
-                    if let Some(delay) = retry_strategy.next_backoff() {
-                        trace!("retrying in {delay:?}");
-                        tokio::time::sleep(delay).await
-                    } else {
-                        break e
-                    }
-                }
-                e => break e
-            }
-        }.map_err(CellsServiceError::from)?;
+        // Connect to the nested auraed, retrying in case of connection errors
+        let mut retry_strategy = retry_strategy();
+        let client =
+            connect_to_cell(client_socket, &mut retry_strategy).await?;
 
         // Attempt the operation with the backoff strategy
-        backoff::future::retry(
-            retry_strategy,
-            || async {
-                match client.$function($request.clone()).await {
-                    Ok(res) => Ok(res),
-                    Err(e) if e.code() == Code::Unknown && e.message() == "transport error" => {
-                        Err(e)?;
-                        unreachable!();
-                    }
-                    Err(e) => Err(backoff::Error::Permanent(e))
+        backoff::future::retry(retry_strategy, || async {
+            match client.$function($request.clone()).await {
+                Ok(res) => Ok(res),
+                Err(e)
+                    if e.code() == Code::Unknown
+                        && e.message() == "transport error" =>
+                {
+                    Err(e)?;
+                    unreachable!();
                 }
-            },
-        )
+                Err(e) => Err(backoff::Error::Permanent(e)),
+            }
+        })
         .await
     }};
 }
 
+/// Frees a cell taken out of the cells, waiting until `deadline` for the
+/// processes left in it to exit. The cell is returned with the result, to
+/// be restored to the cells if it failed to free.
+struct FreeCell {
+    cell: Cell,
+    recursive: bool,
+    deadline: std::time::Instant,
+}
+
+impl BlockingJob for FreeCell {
+    type Output = (Cell, std::result::Result<bool, CellsError>);
+
+    const POOL: Pool = Pool::MountOps;
+
+    fn run(mut self) -> Self::Output {
+        // The time spent queued for the pool counts towards the deadline
+        let timeout =
+            self.deadline.saturating_duration_since(std::time::Instant::now());
+        let freed = self.cell.free(self.recursive, timeout);
+        (self.cell, freed)
+    }
+}
+
+/// Connects to the nested auraed of a cell and lists its running executables.
+/// Returns [None] if the nested auraed can not be reached, as it then no
+/// longer manages any executables.
+async fn running_executables(
+    cell_name: &CellName,
+    client_socket: AuraeSocket,
+) -> std::result::Result<Option<(Client, Vec<String>)>, Status> {
+    let client = match Client::new_no_tls(client_socket).await {
+        Ok(client) => client.with_retry_policy(RetryPolicy::none()),
+        Err(e) => {
+            warn!("failed to connect to the auraed of cell '{cell_name}': {e}");
+            return Ok(None);
+        }
+    };
+
+    let response = client
+        .list_executables(CellServiceListExecutablesRequest { cell_name: None })
+        .await;
+    let executable_names = match response.map_err(Disconnected::try_from) {
+        Ok(response) => response.into_inner().executable_names,
+        Err(Ok(e)) => {
+            warn!("failed to connect to the auraed of cell '{cell_name}': {e}");
+            return Ok(None);
+        }
+        Err(Err(e)) => return Err(e),
+    };
+
+    Ok(Some((client, executable_names)))
+}
+
+/// Stops an executable of a cell being force freed, along with the
+/// processes it started: those of its leaf cgroup when the cell has per
+/// executable accounting, and those of its process group otherwise.
+async fn force_stop(
+    client: &Client,
+    executable_name: String,
+) -> std::result::Result<(), Status> {
+    let mut kill_modes =
+        [proto::cells::KillMode::Cgroup, proto::cells::KillMode::ProcessGroup]
+            .into_iter()
+            .peekable();
+
+    while let Some(kill_mode) = kill_modes.next() {
+        let request = CellServiceStopRequest {
+            cell_name: None,
+            executable_name: executable_name.clone(),
+            kill_mode: kill_mode.into(),
+        };
+
+        match client.stop(request).await {
+            Ok(_) => return Ok(()),
+            // The executable exited, and was removed, since it was listed
+            Err(e) if e.code() == Code::NotFound => return Ok(()),
+            // The executable has no leaf cgroup of its own
+            Err(e)
+                if e.code() == Code::FailedPrecondition
+                    && kill_modes.peek().is_some() => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Asks the nested auraed listening on `socket` whether it is serving.
+async fn nested_auraed_health(socket: &str) -> NestedAuraedHealth {
+    let check = async {
+        let client = Client::new_no_tls(AuraeSocket::Path(socket.into()))
+            .await
+            .ok()?
+            .with_retry_policy(RetryPolicy::none());
+        let response = client
+            .check(HealthCheckRequest { service: String::new() })
+            .await
+            .ok()?;
+        Some(response.into_inner().status())
+    };
+
+    match tokio::time::timeout(NESTED_AURAED_HEALTH_TIMEOUT, check).await {
+        Ok(Some(ServingStatus::Serving)) => NestedAuraedHealth::Serving,
+        Ok(Some(_)) => NestedAuraedHealth::NotServing,
+        Ok(None) | Err(_) => NestedAuraedHealth::Unreachable,
+    }
+}
+
+/// The nested auraeds of `nodes` and all their descendants.
+fn nested_auraeds_mut(nodes: &mut [CellGraphNode]) -> Vec<&mut NestedAuraed> {
+    nodes
+        .iter_mut()
+        .flat_map(|CellGraphNode { nested_auraed, children, .. }| {
+            nested_auraed
+                .as_mut()
+                .into_iter()
+                .chain(nested_auraeds_mut(children))
+        })
+        .collect()
+}
+
+/// The ports reserved by the cells of `cells` and all their descendants.
+fn port_reservations(cells: &impl CellsCache) -> Vec<(u16, CellName)> {
+    let reservations = cells.get_all(|cell| {
+        let mut reservations: Vec<_> = cell
+            .spec()
+            .reserved_ports
+            .iter()
+            .map(|port| (*port, cell.name().clone()))
+            .collect();
+        reservations.extend(port_reservations(cell));
+
+        Ok(reservations)
+    });
+
+    reservations
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|x| x.ok())
+        .flatten()
+        .collect()
+}
+
+/// The resources a cell with `cgroup_spec` commits, with `pids`, which are
+/// only set by updates. Unlimited resources are not committed.
+fn cell_resources(cgroup_spec: &CgroupSpec, pids: u64) -> Resources {
+    let limit = |limit: Limit| u64::try_from(limit.into_inner()).unwrap_or(0);
+
+    let memory_bytes = cgroup_spec
+        .memory
+        .as_ref()
+        .and_then(|memory| memory.max)
+        .map(limit)
+        .unwrap_or_default();
+
+    // Like the kernel, the period defaults to 100ms
+    let cpu_millis = cgroup_spec
+        .cpu
+        .as_ref()
+        .and_then(|cpu| {
+            let max = limit(cpu.max?);
+            Some(Resources::cpu_millis(max, cpu.period.unwrap_or(100_000)))
+        })
+        .unwrap_or_default();
+
+    Resources { memory_bytes, cpu_millis, pids }
+}
+
+/// Records the cells of `cells` and all their descendants, parents first.
+fn cell_records(cells: &impl CellsCache) -> Vec<CellRecord> {
+    let records = cells.get_all(|cell| {
+        // Nested auraeds always listen on a unix socket
+        let AuraeSocket::Path(client_socket) = cell.client_socket()? else {
+            return Ok(vec![]);
+        };
+
+        let mut records = vec![CellRecord {
+            cell: cell.into(),
+            nested_auraed_pid: cell.nested_auraed_pid()?.as_raw(),
+            nested_auraed_start_time: Some(cell.nested_auraed_start_time()?),
+            client_socket,
+            executables: vec![],
+        }];
+        records.extend(cell_records(cell));
+
+        Ok(records)
+    });
+
+    records
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|x| x.ok())
+        .flatten()
+        .collect()
+}
+
+/// Answers an allocate of a cell that already exists. Succeeds if the cell
+/// has the requested spec, or if `update` and the changes to it could be
+/// applied in place, and fails with [CellsError::CellExistsWithDifferentSpec]
+/// otherwise.
+fn allocate_existing(
+    cells: &mut impl CellsCache,
+    cell_name: CellName,
+    cell_spec: CellSpec,
+    update: bool,
+) -> Result<CellServiceAllocateResponse> {
+    let (changes, cgroup_v2) = cells.get(&cell_name, |cell| {
+        Ok((
+            cell.spec().diff(&cell_spec),
+            cell.v2().expect("allocated cell returns `Some`"),
+        ))
+    })?;
+
+    if !changes.is_empty() {
+        if !update {
+            return Err(CellsError::CellExistsWithDifferentSpec {
+                cell_name,
+                changes,
+            }
+            .into());
+        }
+
+        cells.update(&cell_name, cell_spec)?;
+    }
+
+    Ok(CellServiceAllocateResponse {
+        cell_name: cell_name.to_string(),
+        cgroup_v2,
+        existed: true,
+    })
+}
+
+/// A change made, or that would be made, by an import.
+fn import_change(
+    action: ImportAction,
+    cell_name: &CellName,
+    executable_name: Option<&str>,
+    reason: String,
+) -> ImportChange {
+    ImportChange {
+        action: action.into(),
+        cell_name: cell_name.to_string(),
+        executable_name: executable_name.map(str::to_owned),
+        reason,
+    }
+}
+
+/// The indices of the executables of a batch, in the order to start them.
+fn batch_start_order(specs: &[ValidatedExecutable]) -> Result<Vec<usize>> {
+    let dependencies: Vec<_> = specs
+        .iter()
+        .map(|spec| (&spec.name, spec.depends_on.as_slice()))
+        .collect();
+    Ok(start_order(&dependencies)?)
+}
+
+/// Publishes the OOM kills in cells as lifecycle events.
+async fn publish_oom_kills(
+    observe_service: ObserveService,
+    mut oom_events: broadcast::Receiver<OomEvent>,
+) {
+    loop {
+        let event = match oom_events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("OOM event publisher lagged, skipped {skipped} events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        observe_service.publish_event(
+            event.cell_name.to_string(),
+            Kind::OomKill(OomKill { oom_kill_count: event.oom_kill_count }),
+        );
+    }
+}
+
+/// Samples the lifetime stats of the cells every `interval`, for as long as
+/// they are cached.
+async fn sample_lifetime_stats(cells: Weak<Mutex<Cells>>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        let _ = ticks.tick().await;
+        let Some(cells) = cells.upgrade() else {
+            break;
+        };
+        sample_cells(&*cells.lock().await);
+    }
+}
+
+/// Samples the lifetime stats of `cells`, and of their nested cells.
+fn sample_cells(cells: &impl CellsCache) {
+    let _ = cells.get_all(|cell| {
+        if let Err(e) = cell.sample_lifetime_stats() {
+            trace!("failed to sample cell '{}': {e}", cell.name());
+        }
+        sample_cells(cell);
+        Ok(())
+    });
+}
+
+/// The exponential backoff strategy used when calling into a cell.
+fn retry_strategy() -> ExponentialBackoff {
+    backoff::ExponentialBackoffBuilder::new()
+        .with_initial_interval(Duration::from_millis(50)) // 1st retry in 50ms
+        .with_multiplier(10.0) // 10x the delay each attempt
+        .with_randomization_factor(0.5) // with a randomness of +/-50%
+        .with_max_interval(Duration::from_secs(3)) // but never delay more than 3s
+        .with_max_elapsed_time(Some(Duration::from_secs(20))) // or 20s total
+        .build()
+}
+
+/// Creates a client for the nested auraed of a cell, once connected.
+/// Connection errors are retried, as the nested auraed may still be starting.
+async fn connect_to_cell(
+    client_socket: AuraeSocket,
+    retry_strategy: &mut ExponentialBackoff,
+) -> Result<Client> {
+    // The calls to the nested auraed are retried as in `retry_strategy`
+    let client = Client::new_no_tls(client_socket)
+        .await?
+        .with_retry_policy(RetryPolicy::none());
+
+    // The client connects on its first call
+    loop {
+        let res =
+            client.check(HealthCheckRequest { service: String::new() }).await;
+        match res.map_err(Disconnected::try_from) {
+            Err(Ok(e)) => {
+                trace!("aurae client failed to connect: {e:?}");
+                if let Some(delay) = retry_strategy.next_backoff() {
+                    trace!("retrying in {delay:?}");
+                    tokio::time::sleep(delay).await
+                } else {
+                    break Err(ClientError::from(e));
+                }
+            }
+            _ => break Ok(client),
+        }
+    }
+    .map_err(CellsServiceError::from)
+}
+
+/// Looks up the sockets of the nested auraeds of cells, for services that
+/// forward requests about the workloads of a cell to its auraed.
+#[derive(Debug, Clone)]
+pub struct CellSockets(Arc<Mutex<Cells>>);
+
+impl CellSockets {
+    /// Returns the socket of the auraed of the cell named `cell_name`.
+    pub async fn get(
+        &self,
+        cell_name: String,
+    ) -> std::result::Result<AuraeSocket, Status> {
+        let cell_name = CellName::validate(Some(cell_name), "cell_name", None)?;
+        let mut cells = self.0.lock().await;
+        Ok(cells
+            .get(&cell_name, |cell| cell.client_socket())
+            .map_err(CellsServiceError::CellsError)?)
+    }
+
+    /// Returns the name and the socket of the auraed of every allocated
+    /// cell, nested cells included.
+    pub async fn all(&self) -> Vec<(String, AuraeSocket)> {
+        let cells = self.0.lock().await;
+        cells
+            .get_all(|cell| cell.client_sockets_recursive())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sockets| sockets.ok())
+            .flatten()
+            .map(|(cell_name, socket)| (cell_name.to_string(), socket))
+            .collect()
+    }
+}
+
 /// CellService struct manages the lifecycle of cells and executables.
 #[derive(Debug, Clone)]
 pub struct CellService {
     cells: Arc<Mutex<Cells>>,
     executables: Arc<Mutex<Executables>>,
+    /// The executables started in the cells through this auraed, to be
+    /// exported. Locked after the cells, if both are.
+    started: Arc<Mutex<StartedExecutables>>,
     observe_service: ObserveService,
+    net_checks: Arc<Semaphore>,
+    state_file: Option<Arc<Mutex<StateFile>>>,
+    usage_sampler: UsageSampler,
+    admission: Option<AdmissionController>,
+    /// Runs in the auraed of a cell, reporting the processes its stopped
+    /// executables leave in it.
+    in_cell: bool,
 }
 
 impl CellService {
@@ -117,13 +572,292 @@ impl CellService {
     /// # Arguments
     /// * `observe_service` - An instance of ObserveService to manage log channels.
     pub fn new(observe_service: ObserveService) -> Self {
+        let cells = Cells::default();
+        let oom_events = cells.oom_events().subscribe();
+        let cells = Arc::new(Mutex::new(cells));
+        observe_service.set_cell_sockets(CellSockets(cells.clone()));
+
+        if tokio::runtime::Handle::try_current().is_ok() {
+            let _ignored = tokio::spawn(publish_oom_kills(
+                observe_service.clone(),
+                oom_events,
+            ));
+        }
+
         CellService {
-            cells: Default::default(),
+            cells,
             executables: Default::default(),
+            started: Default::default(),
             observe_service,
+            net_checks: Arc::new(Semaphore::new(MAX_CONCURRENT_NET_CHECKS)),
+            state_file: None,
+            usage_sampler: UsageSampler::default(),
+            admission: None,
+            in_cell: false,
+        }
+    }
+
+    /// Marks the service as that of the auraed of a cell, to report the
+    /// processes its stopped executables leave in the cell.
+    pub(crate) fn in_cell(mut self) -> Self {
+        self.in_cell = true;
+        self
+    }
+
+    /// Samples the lifetime stats of the cells every `interval`, on top of
+    /// when their executables are stopped. Zero only samples the latter.
+    pub(crate) fn with_lifetime_stats_interval(
+        self,
+        interval: Duration,
+    ) -> Self {
+        if !interval.is_zero() {
+            let _ignored = tokio::spawn(sample_lifetime_stats(
+                Arc::downgrade(&self.cells),
+                interval,
+            ));
+        }
+        self
+    }
+
+    /// Admits the cells against the capacity of the host, committing their
+    /// resources with those of the other workloads of `admission`. Must be
+    /// set before the state file, so adopted cells are committed.
+    pub(crate) fn with_admission(
+        mut self,
+        admission: AdmissionController,
+    ) -> Self {
+        self.admission = Some(admission);
+        self
+    }
+
+    /// The admission of the cell, unless it is nested, as nested cells are
+    /// bounded by the limits of their parent.
+    fn cell_admission(
+        &self,
+        cell_name: &CellName,
+    ) -> Option<(&AdmissionController, Workload)> {
+        let admission = self.admission.as_ref()?;
+        if !cell_name.is_child(None) {
+            return None;
+        }
+
+        Some((admission, Workload::Cell(cell_name.to_string())))
+    }
+
+    /// Adopts the cells and executables recorded in the state file at `path`
+    /// by a previous auraed, and keeps the file up to date from then on.
+    ///
+    /// Cells whose cgroup or nested auraed no longer exist are dropped.
+    /// Executables whose process no longer exists are kept as stopped.
+    pub(crate) async fn with_state_file(mut self, path: PathBuf) -> Self {
+        let state_file = StateFile::new(path);
+
+        match state_file.load().await {
+            Ok(Some(state)) => self.adopt(state).await,
+            Ok(None) => {}
+            Err(e) => warn!("failed to load cell service state: {e}"),
+        }
+
+        self.state_file = Some(Arc::new(Mutex::new(state_file)));
+        self.persist_state().await;
+        self
+    }
+
+    /// Rebuilds the caches from the state persisted by a previous auraed.
+    #[tracing::instrument(skip(self))]
+    async fn adopt(&self, state: CellServiceState) {
+        let CellServiceState {
+            cells: cell_records,
+            executables: executable_records,
+        } = state;
+
+        {
+            let mut cells = self.cells.lock().await;
+
+            // Parents are recorded before their children
+            for CellRecord {
+                cell,
+                nested_auraed_pid,
+                nested_auraed_start_time,
+                client_socket,
+                executables: started,
+            } in cell_records
+            {
+                let cell = match ValidatedCell::validate(cell, None) {
+                    Ok(cell) => cell,
+                    Err(e) => {
+                        warn!("skipping invalid cell in state file: {e}");
+                        continue;
+                    }
+                };
+
+                let cell_name = cell.name.clone();
+                let adoption = CellAdoption {
+                    spec: cell.into(),
+                    nested_auraed_pid: Pid::from_raw(nested_auraed_pid),
+                    nested_auraed_start_time,
+                    client_socket,
+                };
+                let resources = cell_resources(&adoption.spec.cgroup_spec, 0);
+
+                match cells.adopt(cell_name.clone(), adoption) {
+                    Ok(cell) => {
+                        info!("adopted cell '{cell_name}'");
+                        let mut started_executables = self.started.lock().await;
+                        for definition in started {
+                            started_executables
+                                .insert(cell_name.clone(), definition);
+                        }
+                        drop(started_executables);
+                        // Already running, so committed without admission
+                        if let Some((admission, workload)) =
+                            self.cell_admission(&cell_name)
+                        {
+                            admission.commit(workload, resources);
+                        }
+                        // Events published before the adoption were
+                        // already published by the previous auraed
+                        if let Ok(client_socket) = cell.client_socket() {
+                            self.observe_service.watch_cell_events(
+                                cell_name.to_string(),
+                                client_socket,
+                                None,
+                            );
+                        }
+                    }
+                    Err(e) => warn!("failed to adopt cell '{cell_name}': {e}"),
+                }
+            }
+        }
+
+        let mut executables = self.executables.lock().await;
+
+        for ExecutableRecord { name, description, command, pid, start_time } in
+            executable_records
+        {
+            let name = match ExecutableName::validate(Some(name), "name", None)
+            {
+                Ok(name) => name,
+                Err(e) => {
+                    warn!("skipping invalid executable in state file: {e}");
+                    continue;
+                }
+            };
+
+            let command = command.into_iter().map(OsString::from).collect();
+            let executable = match executables.adopt(
+                name.clone(),
+                description,
+                command,
+                pid.map(Pid::from_raw),
+                start_time,
+            ) {
+                Ok(executable) => executable,
+                Err(e) => {
+                    warn!("failed to adopt executable '{name}': {e}");
+                    continue;
+                }
+            };
+
+            self.observe_service
+                .notify_executable_registered(executable.name.to_string());
+        }
+    }
+
+    /// Writes the cells and executables to the state file, if there is one.
+    /// Must not be called while holding the cells or executables lock.
+    async fn persist_state(&self) {
+        let Some(state_file) = &self.state_file else {
+            return;
+        };
+
+        // Serializes writers, so the last snapshot taken is the one saved
+        let state_file = state_file.lock().await;
+
+        let state = {
+            let cells = self.cells.lock().await;
+            let executables = self.executables.lock().await;
+            let started = self.started.lock().await;
+
+            let mut records = cell_records(&*cells);
+            for record in &mut records {
+                record.executables = started
+                    .get(&CellName::from(record.cell.name.as_str()))
+                    .to_vec();
+            }
+
+            CellServiceState {
+                cells: records,
+                executables: executables
+                    .iter()
+                    .map(|executable| ExecutableRecord {
+                        name: executable.name.to_string(),
+                        description: executable.description.clone(),
+                        command: executable
+                            .command()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|arg| arg.to_string_lossy().into_owned())
+                            .collect(),
+                        pid: executable
+                            .pid()
+                            .ok()
+                            .flatten()
+                            .map(|pid| pid.as_raw()),
+                        start_time: executable.start_time(),
+                    })
+                    .collect(),
+            }
+        };
+
+        if let Err(e) = state_file.save(&state).await {
+            warn!("failed to persist cell service state: {e}");
         }
     }
 
+    /// Records an audited call, and publishes the record to the observe
+    /// event stream if auraed is configured to.
+    async fn end_audit<T>(
+        &self,
+        audit: Audit,
+        response: &std::result::Result<Response<T>, Status>,
+    ) {
+        let Some(record) = audit.end(response.as_ref().err()).await else {
+            return;
+        };
+
+        self.observe_service.publish_event(
+            record.summary.cell_name.clone().unwrap_or_default(),
+            Kind::Audit(record.into()),
+        );
+    }
+
+    /// Publishes that the executable started, and that it exited once it
+    /// is reaped.
+    fn publish_executable_lifecycle(&self, executable: &Executable, pid: i32) {
+        let executable_name = executable.name.to_string();
+        self.observe_service.publish_event(
+            String::new(),
+            Kind::ExecutableStarted(ExecutableStarted {
+                executable_name: executable_name.clone(),
+                pid,
+            }),
+        );
+
+        let observe_service = self.observe_service.clone();
+        let _ = executable.on_exit(move |exit_status| {
+            observe_service.publish_event(
+                String::new(),
+                Kind::ExecutableExited(ExecutableExited {
+                    executable_name,
+                    pid,
+                    code: exit_status.and_then(|status| status.code()),
+                    signal: exit_status.and_then(|status| status.signal()),
+                }),
+            );
+        });
+    }
+
     /// Allocates a new cell based on the provided request.
     ///
     /// # Arguments
@@ -136,20 +870,153 @@ impl CellService {
     async fn allocate(
         &self,
         request: ValidatedCellServiceAllocateRequest,
+        deadline: Option<Instant>,
     ) -> Result<CellServiceAllocateResponse> {
         // Initialize the cell
-        let ValidatedCellServiceAllocateRequest { cell } = request;
+        let ValidatedCellServiceAllocateRequest { cell, update } = request;
 
         let cell_name = cell.name.clone();
-        let cell_spec = cell.into();
+        let cell_spec: CellSpec = cell.into();
+
+        // The lock is held until the cell is allocated, or found to exist,
+        // so of concurrent allocates of the same cell, the later ones see
+        // the cell as existing.
+        let mut cells = match deadline {
+            Some(deadline) => {
+                tokio::time::timeout_at(deadline, self.cells.lock())
+                    .await
+                    .map_err(|_| CellsServiceError::DeadlineExceeded {
+                        cell_name: cell_name.clone(),
+                    })?
+            }
+            None => self.cells.lock().await,
+        };
 
-        let mut cells = self.cells.lock().await;
+        // Nothing is awaited from here on, so the cell is either allocated
+        // in full, or, if allocating fails, cleaned up by `cells.allocate`.
+
+        // Reservations are advisory: nothing stops a cell from binding a
+        // port it hasn't reserved, but two cells can't reserve the same one.
+        if let Some((port, owner)) =
+            port_reservations(&*cells).into_iter().find(|(port, owner)| {
+                *owner != cell_name && cell_spec.reserved_ports.contains(port)
+            })
+        {
+            return Err(
+                CellsError::PortReserved { cell_name, port, owner }.into()
+            );
+        }
+
+        // Admitted before the cell is allocated, and committed again as it
+        // was if the cell is not. An existing cell is only admitted again
+        // if it may be updated.
+        let admitted = match self.cell_admission(&cell_name) {
+            Some((admission, workload))
+                if update || cells.get(&cell_name, |_| Ok(())).is_err() =>
+            {
+                let previous = admission.committed(&workload);
+                let pids = previous.map(|x| x.pids).unwrap_or_default();
+                admission.admit(
+                    workload.clone(),
+                    cell_resources(&cell_spec.cgroup_spec, pids),
+                )?;
+                Some((admission, workload, previous))
+            }
+            _ => None,
+        };
+        let restore = || {
+            if let Some((admission, workload, previous)) = &admitted {
+                admission.restore(workload.clone(), *previous);
+            }
+        };
+
+        let cell = match cells.allocate(cell_name, cell_spec.clone()) {
+            Err(CellsError::CellExists { cell_name }) => {
+                return allocate_existing(
+                    &mut *cells,
+                    cell_name,
+                    cell_spec,
+                    update,
+                )
+                .inspect_err(|_| restore());
+            }
+            Err(e) => {
+                restore();
+                return Err(e.into());
+            }
+            Ok(cell) => cell,
+        };
 
-        let cell = cells.allocate(cell_name, cell_spec)?;
+        self.observe_service.publish_event(
+            cell.name().to_string(),
+            Kind::CellAllocated(CellAllocated {}),
+        );
+        // From the first event, as executables may be started in the cell
+        // before its auraed is connected to
+        if let Ok(client_socket) = cell.client_socket() {
+            self.observe_service.watch_cell_events(
+                cell.name().to_string(),
+                client_socket,
+                Some(0),
+            );
+        }
 
         Ok(CellServiceAllocateResponse {
             cell_name: cell.name().clone().to_string(),
             cgroup_v2: cell.v2().expect("allocated cell returns `Some`"),
+            existed: false,
+        })
+    }
+
+    /// Writes cgroup values to an existing cell.
+    ///
+    /// # Arguments
+    /// * `request` - A validated request with the values to write.
+    ///
+    /// # Returns
+    /// The `memory.events` of the cell counted while the values were written.
+    #[tracing::instrument(skip(self))]
+    async fn update(
+        &self,
+        request: ValidatedCellServiceUpdateRequest,
+    ) -> Result<CellServiceUpdateResponse> {
+        let cell_name = request.cell_name.clone();
+        let settings: CgroupSettings = request.into();
+
+        let mut cells = self.cells.lock().await;
+
+        // The cell commits what it does once the settings are written
+        let admitted = match self.cell_admission(&cell_name) {
+            Some((admission, workload)) => {
+                let mut cgroup_spec = cells.get(&cell_name, |cell| {
+                    Ok(cell.spec().cgroup_spec.clone())
+                })?;
+                settings.merge_into(&mut cgroup_spec);
+
+                let previous = admission.committed(&workload);
+                let pids = settings
+                    .pids_max
+                    .map(|max| u64::try_from(max.into_inner()).unwrap_or(0))
+                    .or(previous.map(|x| x.pids))
+                    .unwrap_or_default();
+                admission.admit(
+                    workload.clone(),
+                    cell_resources(&cgroup_spec, pids),
+                )?;
+                Some((admission, workload, previous))
+            }
+            None => None,
+        };
+
+        let memory_events =
+            cells.write_settings(&cell_name, &settings).inspect_err(|_| {
+                if let Some((admission, workload, previous)) = admitted {
+                    admission.restore(workload, previous);
+                }
+            })?;
+
+        Ok(CellServiceUpdateResponse {
+            memory_events: Some(memory_events.into()),
         })
     }
 
@@ -164,29 +1031,155 @@ impl CellService {
     async fn free(
         &self,
         request: ValidatedCellServiceFreeRequest,
-    ) -> Result<CellServiceFreeResponse> {
-        let ValidatedCellServiceFreeRequest { cell_name } = request;
+    ) -> std::result::Result<CellServiceFreeResponse, Status> {
+        let ValidatedCellServiceFreeRequest {
+            cell_name,
+            force,
+            recursive,
+            timeout_ms,
+        } = request;
 
-        info!("CellService: free() cell_name={cell_name:?}");
+        info!(
+            "CellService: free() cell_name={cell_name:?} force={force} recursive={recursive} timeout={timeout_ms:?}"
+        );
+
+        // The executables are cached by the nested auraed of each cell, so
+        // every cell in the tree being freed has to be asked for them.
+        let client_sockets = {
+            let mut cells = self.cells.lock().await;
+            cells
+                .get(&cell_name, |cell| {
+                    if !recursive {
+                        cell.check_no_nested_cells()?;
+                    }
+                    cell.client_sockets_recursive()
+                })
+                .map_err(CellsServiceError::CellsError)?
+        };
 
-        let mut cells = self.cells.lock().await;
+        let freed_cell_names: Vec<_> =
+            client_sockets.iter().map(|(name, _)| name.to_string()).collect();
+
+        for (nested_cell_name, client_socket) in client_sockets {
+            let Some((client, executable_names)) =
+                running_executables(&nested_cell_name, client_socket).await?
+            else {
+                continue;
+            };
+
+            if executable_names.is_empty() {
+                continue;
+            }
+
+            if !force {
+                return Err(CellsServiceError::CellsError(
+                    CellsError::CellHasRunningExecutables {
+                        cell_name: nested_cell_name,
+                        executable_names,
+                    },
+                )
+                .into());
+            }
+
+            for executable_name in executable_names {
+                force_stop(&client, executable_name).await?;
+            }
+        }
+
+        // Taken out of the cells to be freed, so that waiting for its
+        // processes to exit does not hold up the other cells
+        let cell = self
+            .cells
+            .lock()
+            .await
+            .take(&cell_name, recursive)
+            .map_err(CellsServiceError::CellsError)?;
+        let (cell, freed) = blocking::run(FreeCell {
+            cell,
+            recursive,
+            deadline: std::time::Instant::now() + timeout_ms,
+        })
+        .await
+        .map_err(CellsServiceError::BlockingError)?;
+        let escalated = match freed {
+            Ok(escalated) => escalated,
+            Err(e) => {
+                // Left allocated, to be freed again
+                let restored =
+                    self.cells.lock().await.restore(&cell_name, cell);
+                if let Err(restore_error) = restored {
+                    warn!(
+                        "failed to restore cell '{cell_name}': {restore_error}"
+                    );
+                }
+                return Err(CellsServiceError::CellsError(e).into());
+            }
+        };
+        if escalated {
+            warn!("Killed the processes left in cell '{cell_name}' after {timeout_ms:?}");
+        }
+        self.started.lock().await.remove_cell(&cell_name);
+
+        for cell_name in freed_cell_names {
+            if let Some(admission) = &self.admission {
+                admission.release(&Workload::Cell(cell_name.clone()));
+            }
+            self.observe_service
+                .publish_event(cell_name, Kind::CellFreed(CellFreed {}));
+        }
 
-        cells.free(&cell_name)?;
+        Ok(CellServiceFreeResponse { escalated })
+    }
 
-        Ok(CellServiceFreeResponse::default())
+    /// Checks that cells can be allocated. See [Cgroup::check_root_writable].
+    pub(crate) fn check_health(&self) -> std::io::Result<()> {
+        Cgroup::check_root_writable()
     }
 
     #[tracing::instrument(skip(self))]
-    pub(crate) async fn free_all(&self) -> Result<()> {
-        let mut cells = self.cells.lock().await;
+    pub(crate) async fn free_all(&self, grace: Duration) -> Result<()> {
+        // Taken out of the cells, to be freed at once on the blocking pool,
+        // all waiting for their processes until the same deadline
+        let taken: Vec<_> = {
+            let mut cells = self.cells.lock().await;
+            let cell_names: Vec<_> = cells
+                .get_all(|cell| Ok(cell.name().clone()))?
+                .into_iter()
+                .flatten()
+                .collect();
+            cell_names
+                .into_iter()
+                .filter_map(|cell_name| cells.take(&cell_name, true).ok())
+                .collect()
+        };
 
-        // Attempt to gracefully free all cells
-        cells.broadcast_free();
+        let deadline = std::time::Instant::now() + grace;
+        let freed = futures::future::join_all(taken.into_iter().map(|cell| {
+            blocking::run(FreeCell { cell, recursive: true, deadline })
+        }))
+        .await;
+
+        let mut cells = self.cells.lock().await;
+        for freed in freed {
+            match freed {
+                Ok((_, Ok(_escalated))) => {}
+                Ok((cell, Err(e))) => {
+                    let cell_name = cell.name().clone();
+                    warn!("failed to free cell '{cell_name}': {e}");
+                    if let Err(e) = cells.restore(&cell_name, cell) {
+                        warn!("failed to restore cell '{cell_name}': {e}");
+                    }
+                }
+                Err(e) => warn!("failed to free a cell: {e}"),
+            }
+        }
 
         // The cells that remain failed to shut down for some reason.
         // Forcefully kill any remaining cells that failed to shut down
         cells.broadcast_kill();
+        drop(cells);
 
+        self.persist_state().await;
         Ok(())
     }
 
@@ -212,45 +1205,38 @@ impl CellService {
         assert!(cell_name.is_none());
         info!("CellService: start() executable={:?}", executable);
 
-        let mut executables = self.executables.lock().await;
+        // The name is reserved, so that of concurrent starts of the same
+        // name, only the first starts
+        let executable_name = executable.name.clone();
+        let reservation =
+            Reservation::new(&self.executables, vec![executable_name.clone()])
+                .await
+                .map_err(CellsServiceError::ExecutablesError)?;
 
-        // Start the executable and handle any errors
-        let executable = executables
-            .start(executable, uid, gid)
-            .map_err(CellsServiceError::ExecutablesError)?;
+        // Started without holding the executables lock, so that a slow start
+        // does not hold up the other executables
+        let spawned = Executables::spawn(executable, uid, gid).await;
+
+        let mut executables = self.executables.lock().await;
+        reservation.release(&mut executables);
+        let executable = match spawned {
+            Ok(executable) => executables.insert(executable),
+            Err(e) => return Err(CellsServiceError::ExecutablesError(e).into()),
+        };
 
-        // Retrieve the process ID (PID) of the started executable
+        // Retrieve the process ID (PID) of the started executable, which
+        // may have exited already
         let pid = executable
-            .pid()
-            .map_err(CellsServiceError::Io)?
-            .expect("pid")
+            .started_pid()
+            .expect("started executables have a pid")
             .as_raw();
+        self.observe_service
+            .notify_executable_registered(executable_name.to_string());
 
-        // Register the stdout log channel for the executable's PID
-        if let Err(e) = self
-            .observe_service
-            .register_sub_process_channel(
-                pid,
-                LogChannelType::Stdout,
-                executable.stdout.clone(),
-            )
-            .await
-        {
-            warn!("failed to register stdout channel for pid {pid}: {e}");
-        }
-
-        // Register the stderr log channel for the executable's PID
-        if let Err(e) = self
-            .observe_service
-            .register_sub_process_channel(
-                pid,
-                LogChannelType::Stderr,
-                executable.stderr.clone(),
-            )
-            .await
-        {
-            warn!("failed to register stderr channel for pid {pid}: {e}");
-        }
+        // Published before the executables lock is released, so the
+        // executable can't be stopped before its exit is listened for
+        self.publish_executable_lifecycle(executable, pid);
+        drop(executables);
 
         let (self_uid, self_gid) =
             std::fs::metadata("/proc/self").map(|m| (m.uid(), m.gid()))?;
@@ -262,13 +1248,283 @@ impl CellService {
         }))
     }
 
+    /// Substitutes the variables of the cell in the command of an executable
+    /// to be started in it. The nested auraed of the cell starts the command
+    /// as resolved here, as commands started outside of a cell are not
+    /// templated.
+    async fn resolve_command(
+        &self,
+        cell_name: &CellName,
+        executable_name: &ExecutableName,
+        command: &str,
+    ) -> std::result::Result<String, Status> {
+        let mut variables = self
+            .cells
+            .lock()
+            .await
+            .get(cell_name, |cell| Ok(cell.spec().variables.clone()))
+            .map_err(CellsServiceError::CellsError)?;
+
+        let runtime_dir = &AURAED_RUNTIME.get().expect("runtime").runtime_dir;
+        let _ = variables
+            .insert(CELL_NAME_VARIABLE.to_string(), cell_name.to_string());
+        let _ = variables.insert(
+            AURAE_RUNTIME_DIR_VARIABLE.to_string(),
+            runtime_dir.display().to_string(),
+        );
+
+        substitute(command, &variables).map_err(|variables| {
+            CellsServiceError::ExecutablesError(
+                ExecutablesError::UnresolvedVariables {
+                    executable_name: executable_name.clone(),
+                    variables,
+                },
+            )
+            .into()
+        })
+    }
+
+    /// The uid and gid the executables started in a cell run as, unless
+    /// their start request sets them.
+    async fn default_ids(
+        &self,
+        cell_name: &CellName,
+    ) -> std::result::Result<(Option<u32>, Option<u32>), Status> {
+        let ids = self
+            .cells
+            .lock()
+            .await
+            .get(cell_name, |cell| {
+                Ok((cell.spec().default_uid, cell.spec().default_gid))
+            })
+            .map_err(CellsServiceError::CellsError)?;
+
+        Ok(ids)
+    }
+
     #[tracing::instrument(skip(self))]
     async fn start_in_cell(
         &self,
         cell_name: &CellName,
         request: CellServiceStartRequest,
     ) -> std::result::Result<Response<CellServiceStartResponse>, Status> {
-        do_in_cell!(self, cell_name, start, request)
+        let response = do_in_cell!(self, cell_name, start, request)?;
+
+        let executable = request.executable.as_ref().expect("executable");
+        self.account(cell_name, |cell| {
+            cell.record_start(&executable.name);
+            Ok(())
+        })
+        .await;
+        Ok(response)
+    }
+
+    /// Starts the executables of a batch in order, each once the executables
+    /// it depends on are ready. Their names are reserved first, so that the
+    /// batch fails as a whole if any is taken, and then they are started
+    /// without holding the executables lock.
+    #[tracing::instrument(skip(self))]
+    async fn start_batch(
+        &self,
+        request: ValidatedCellServiceStartBatchRequest,
+    ) -> std::result::Result<Response<CellServiceStartBatchResponse>, Status>
+    {
+        let ValidatedCellServiceStartBatchRequest {
+            cell_name,
+            executables: mut specs,
+            uid,
+            gid,
+            atomic,
+            ready_after_ms: ready_after,
+        } = request;
+
+        assert!(cell_name.is_none());
+        let names: Vec<_> =
+            specs.iter().map(|spec| spec.name.clone()).collect();
+        info!(
+            "CellService: start_batch() executables={names:?} atomic={atomic}"
+        );
+
+        let order = batch_start_order(&specs)?;
+        let dependencies: Vec<_> = specs
+            .iter_mut()
+            .map(|spec| std::mem::take(&mut spec.depends_on))
+            .collect();
+        let probes: Vec<_> =
+            specs.iter_mut().map(|spec| spec.readiness_probe.take()).collect();
+
+        // Released if the batch is cancelled while it waits for readiness
+        let reservation = Reservation::new(&self.executables, names.clone())
+            .await
+            .map_err(CellsServiceError::ExecutablesError)?;
+
+        let mut specs: Vec<_> = specs.into_iter().map(Some).collect();
+        let mut outcomes: Vec<Option<std::result::Result<Executable, String>>> =
+            names.iter().map(|_| None).collect();
+        // Whether each dependency is ready, waited for once
+        let mut readiness: HashMap<usize, std::result::Result<(), String>> =
+            HashMap::new();
+        let mut started = Vec::with_capacity(names.len());
+        let mut failed = None;
+        for i in order {
+            let spec = specs[i].take().expect("spec");
+            let name = spec.name.clone();
+            if atomic && failed.is_some() {
+                outcomes[i] = Some(Err(format!(
+                    "not started, as '{}' failed to start",
+                    failed.as_ref().expect("failed")
+                )));
+                continue;
+            }
+
+            let mut dependency_failed = None;
+            for dependency in &dependencies[i] {
+                let j = names
+                    .iter()
+                    .position(|x| x == dependency)
+                    .expect("dependency");
+                let ready = match readiness.get(&j).cloned() {
+                    Some(ready) => ready,
+                    None => {
+                        let ready = match &mut outcomes[j] {
+                            Some(Ok(executable)) => wait_ready(
+                                executable,
+                                probes[j].as_ref(),
+                                ready_after,
+                            )
+                            .await
+                            .map_err(|e| e.to_string()),
+                            _ => Err(String::from("did not start")),
+                        };
+                        let _ = readiness.insert(j, ready.clone());
+                        ready
+                    }
+                };
+                if let Err(reason) = ready {
+                    dependency_failed =
+                        Some(ExecutablesError::DependencyFailed {
+                            executable_name: name.clone(),
+                            dependency: dependency.clone(),
+                            reason,
+                        });
+                    break;
+                }
+            }
+            if let Some(e) = dependency_failed {
+                outcomes[i] = Some(Err(e.to_string()));
+                failed = failed.or(Some(name));
+                continue;
+            }
+
+            match Executables::spawn(spec, uid, gid).await {
+                Ok(executable) => {
+                    started.push(name.to_string());
+                    outcomes[i] = Some(Ok(executable));
+                }
+                Err(e) => {
+                    outcomes[i] = Some(Err(e.to_string()));
+                    failed = failed.or(Some(name));
+                }
+            }
+        }
+        let mut outcomes: Vec<_> =
+            outcomes.into_iter().map(|x| x.expect("outcome")).collect();
+
+        if let Some(failed) = failed.as_ref().filter(|_| atomic) {
+            for outcome in &mut outcomes {
+                let Ok(executable) = outcome else {
+                    continue;
+                };
+                if let Err(e) = executable.kill().await {
+                    warn!(
+                        "failed to roll back executable '{}': {e}",
+                        executable.name
+                    );
+                }
+                *outcome =
+                    Err(format!("rolled back, as '{failed}' failed to start"));
+            }
+        }
+
+        let mut executables = self.executables.lock().await;
+        reservation.release(&mut executables);
+
+        let mut results = Vec::with_capacity(outcomes.len());
+        for (name, outcome) in names.into_iter().zip(outcomes) {
+            let outcome = match outcome {
+                Ok(mut executable) => {
+                    let pid = executable
+                        .started_pid()
+                        .expect("started executables have a pid")
+                        .as_raw();
+                    // It may have exited while its dependents were started
+                    let outcome = if executable
+                        .is_running()
+                        .map_err(CellsServiceError::Io)?
+                    {
+                        Outcome::Pid(pid)
+                    } else {
+                        let exit_status = executable
+                            .exit_status()
+                            .map_err(CellsServiceError::Io)?;
+                        Outcome::Exited(ExecutableExit {
+                            pid,
+                            code: exit_status.and_then(|status| status.code()),
+                            signal: exit_status
+                                .and_then(|status| status.signal()),
+                        })
+                    };
+                    let executable = executables.insert(executable);
+                    self.observe_service.notify_executable_registered(
+                        executable.name.to_string(),
+                    );
+                    self.publish_executable_lifecycle(executable, pid);
+                    outcome
+                }
+                Err(e) => Outcome::Error(e),
+            };
+
+            results.push(ExecutableStartResult {
+                executable_name: name.to_string(),
+                outcome: Some(outcome),
+            });
+        }
+
+        Ok(Response::new(CellServiceStartBatchResponse {
+            results,
+            start_order: started,
+        }))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn start_batch_in_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceStartBatchRequest,
+    ) -> std::result::Result<Response<CellServiceStartBatchResponse>, Status>
+    {
+        let response = do_in_cell!(self, cell_name, start_batch, request)?;
+
+        let started: Vec<_> = response
+            .get_ref()
+            .results
+            .iter()
+            .filter(|result| {
+                matches!(
+                    result.outcome,
+                    Some(Outcome::Pid(_) | Outcome::Exited(_))
+                )
+            })
+            .map(|result| result.executable_name.as_str())
+            .collect();
+        self.account(cell_name, |cell| {
+            for executable_name in &started {
+                cell.record_start(executable_name);
+            }
+            Ok(())
+        })
+        .await;
+        Ok(response)
     }
 
     #[tracing::instrument(skip(self))]
@@ -283,46 +1539,69 @@ impl CellService {
         &self,
         request: ValidatedCellServiceStopRequest,
     ) -> std::result::Result<Response<CellServiceStopResponse>, Status> {
-        let ValidatedCellServiceStopRequest { cell_name, executable_name } =
-            request;
+        let ValidatedCellServiceStopRequest {
+            cell_name,
+            executable_name,
+            kill_mode,
+        } = request;
 
         assert!(cell_name.is_none());
         info!("CellService: stop() executable_name={:?}", executable_name,);
 
-        let mut executables = self.executables.lock().await;
+        let mut executable = {
+            let mut executables = self.executables.lock().await;
+            let executable = executables
+                .get(&executable_name)
+                .map_err(CellsServiceError::ExecutablesError)?;
+
+            // Only the leaf cgroup of an executable can be killed, as the
+            // nested auraed runs in the cgroup of its cell
+            if kill_mode == KillMode::Cgroup
+                && executable.cgroup_path().is_none()
+            {
+                return Err(CellsServiceError::ExecutablesError(
+                    ExecutablesError::KillModeRequiresCell { executable_name },
+                )
+                .into());
+            }
 
-        // Retrieve the process ID (PID) of the executable to be stopped
-        let pid = executables
-            .get(&executable_name)
-            .map_err(CellsServiceError::ExecutablesError)?
-            .pid()
-            .map_err(CellsServiceError::Io)?
-            .expect("pid")
-            .as_raw();
+            // Taken out of the cache, with its name reserved, so that it is
+            // stopped without holding the executables lock
+            executables
+                .take(&executable_name)
+                .map_err(CellsServiceError::ExecutablesError)?
+        };
 
         // Stop the executable and handle any errors
-        let _: ExitStatus = executables
-            .stop(&executable_name)
-            .await
-            .map_err(CellsServiceError::ExecutablesError)?;
-
-        // Remove the executable's logs from the observe service.
-        if let Err(e) = self
-            .observe_service
-            .unregister_sub_process_channel(pid, LogChannelType::Stdout)
-            .await
-        {
-            warn!("failed to unregister stdout channel for pid {pid}: {e}");
-        }
-        if let Err(e) = self
-            .observe_service
-            .unregister_sub_process_channel(pid, LogChannelType::Stderr)
-            .await
-        {
-            warn!("failed to unregister stderr channel for pid {pid}: {e}");
+        let stopped = executable.kill_with(kill_mode).await;
+        let output_truncated = executable.output_truncated();
+        let orphan_pids = {
+            let mut executables = self.executables.lock().await;
+            let _: Option<ExitStatus> = executables
+                .finish_stop(executable, stopped)
+                .map_err(CellsServiceError::ExecutablesError)?;
+
+            if self.in_cell {
+                let running = executables
+                    .iter()
+                    .filter_map(Executable::id)
+                    .map(str::to_owned)
+                    .collect();
+                workload::orphans(std::process::id() as i32, &running)
+            } else {
+                vec![]
+            }
+        };
+        if !orphan_pids.is_empty() {
+            warn!(
+                "executable '{executable_name}' left processes in the cell: {orphan_pids:?}"
+            );
         }
 
-        Ok(Response::new(CellServiceStopResponse::default()))
+        Ok(Response::new(CellServiceStopResponse {
+            output_truncated,
+            orphan_pids,
+        }))
     }
 
     #[tracing::instrument(skip(self))]
@@ -331,31 +1610,846 @@ impl CellService {
         cell_name: &CellName,
         request: CellServiceStopRequest,
     ) -> std::result::Result<Response<CellServiceStopResponse>, Status> {
-        do_in_cell!(self, cell_name, stop, request)
+        let response = do_in_cell!(self, cell_name, stop, request)?;
+        self.account(cell_name, |cell| cell.record_stop()).await;
+        Ok(response)
+    }
+
+    /// Updates the lifetime stats of a cell with `f`. A cell freed
+    /// meanwhile is left alone, and failing to sample it is only logged, as
+    /// the request it accounts for succeeded.
+    async fn account(
+        &self,
+        cell_name: &CellName,
+        f: impl Fn(&super::cells::Cell) -> super::cells::Result<()>,
+    ) {
+        if let Err(e) = self.cells.lock().await.get(cell_name, f) {
+            warn!("failed to account for cell '{cell_name}': {e}");
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn quarantine(
+        &self,
+        request: ValidatedCellServiceQuarantineRequest,
+    ) -> Result<CellServiceQuarantineResponse> {
+        let ValidatedCellServiceQuarantineRequest {
+            cell_name,
+            executable_name,
+        } = request;
+
+        assert!(cell_name.is_none());
+        info!("CellService: quarantine() executable_name={executable_name:?}");
+
+        let mut executables = self.executables.lock().await;
+        let _ = executables.quarantine(&executable_name)?;
+
+        Ok(CellServiceQuarantineResponse::default())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn quarantine_in_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceQuarantineRequest,
+    ) -> std::result::Result<Response<CellServiceQuarantineResponse>, Status>
+    {
+        do_in_cell!(self, cell_name, quarantine, request)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn unquarantine(
+        &self,
+        request: ValidatedCellServiceUnquarantineRequest,
+    ) -> Result<CellServiceUnquarantineResponse> {
+        let ValidatedCellServiceUnquarantineRequest {
+            cell_name,
+            executable_name,
+        } = request;
+
+        assert!(cell_name.is_none());
+        info!(
+            "CellService: unquarantine() executable_name={executable_name:?}"
+        );
+
+        let mut executables = self.executables.lock().await;
+        let _ = executables.unquarantine(&executable_name)?;
+
+        Ok(CellServiceUnquarantineResponse::default())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn unquarantine_in_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceUnquarantineRequest,
+    ) -> std::result::Result<Response<CellServiceUnquarantineResponse>, Status>
+    {
+        do_in_cell!(self, cell_name, unquarantine, request)
     }
 
     #[tracing::instrument(skip(self))]
-    pub(crate) async fn stop_all(&self) -> Result<()> {
+    pub(crate) async fn stop_all(&self, grace: Duration) -> Result<()> {
         let mut executables = self.executables.lock().await;
-        // Broadcast a stop signal to all executables
-        executables.broadcast_stop().await;
+        // Ask all executables to exit, and kill the ones that do not in time
+        executables.broadcast_terminate(grace).await;
+        drop(executables);
+
+        self.persist_state().await;
         Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    async fn list(&self) -> Result<CellServiceListResponse> {
-        let cells = self.cells.lock().await;
+    async fn list_executables(
+        &self,
+    ) -> Result<CellServiceListExecutablesResponse> {
+        let mut executables = self.executables.lock().await;
+
+        let running = executables.running();
+        let statuses = running
+            .iter()
+            .filter_map(|name| executables.get(name).ok())
+            .map(|executable| {
+                let (suppressed_stdout_lines, suppressed_stderr_lines) =
+                    executable.suppressed_lines();
+                let (cpu, memory) = executable
+                    .cgroup_stats()
+                    .map(|stats| (stats.cpu.into(), stats.memory.into()))
+                    .unzip();
+                ExecutableStatus {
+                    name: executable.name.to_string(),
+                    suppressed_stdout_lines,
+                    suppressed_stderr_lines,
+                    cpu,
+                    memory,
+                }
+            })
+            .collect();
+        let executable_names =
+            running.into_iter().map(|name| name.to_string()).collect();
 
-        // Retrieve all cells and convert them for returning
-        let cells = cells
-            .get_all(|x| x.try_into())
-            .expect("cells doesn't error")
+        let quarantined_executable_names = executables
+            .quarantined()
             .into_iter()
-            .filter_map(|x| x.ok())
+            .map(|name| name.to_string())
             .collect();
 
+        Ok(CellServiceListExecutablesResponse {
+            executable_names,
+            quarantined_executable_names,
+            executables: statuses,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_executables_in_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceListExecutablesRequest,
+    ) -> std::result::Result<Response<CellServiceListExecutablesResponse>, Status>
+    {
+        do_in_cell!(self, cell_name, list_executables, request)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list(&self) -> Result<CellServiceListResponse> {
+        let mut cells: Vec<CellGraphNode> = {
+            let cells = self.cells.lock().await;
+
+            // Retrieve all cells and convert them for returning
+            cells
+                .get_all(|x| x.try_into())
+                .expect("cells doesn't error")
+                .into_iter()
+                .filter_map(|x| x.ok())
+                .collect()
+        };
+
+        // The cells are unlocked first, so an unresponsive nested auraed
+        // doesn't hold up other requests.
+        let _ = futures::future::join_all(
+            nested_auraeds_mut(&mut cells).into_iter().map(
+                |nested_auraed| async move {
+                    nested_auraed.health =
+                        nested_auraed_health(&nested_auraed.socket).await
+                            as i32;
+                },
+            ),
+        )
+        .await;
+
         Ok(CellServiceListResponse { cells })
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_port_reservations(
+        &self,
+    ) -> Result<CellServiceListPortReservationsResponse> {
+        let mut reservations = {
+            let cells = self.cells.lock().await;
+            port_reservations(&*cells)
+        };
+        reservations.sort();
+
+        let reservations = reservations
+            .into_iter()
+            .map(|(port, cell_name)| PortReservation {
+                port: u32::from(port),
+                cell_name: cell_name.to_string(),
+            })
+            .collect();
+
+        Ok(CellServiceListPortReservationsResponse { reservations })
+    }
+
+    /// Reports the resources committed by the cells and pods.
+    fn admission(&self) -> Result<CellServiceAdmissionResponse> {
+        let admission = self
+            .admission
+            .as_ref()
+            .ok_or(CellsServiceError::AdmissionNotEnabled)?;
+        let info = admission.info();
+
+        Ok(CellServiceAdmissionResponse {
+            enforce: info.enforce,
+            capacity: Some(info.capacity.into()),
+            allowed: Some(info.allowed.into()),
+            committed: Some(info.committed.into()),
+            workloads: u32::try_from(info.workloads).unwrap_or(u32::MAX),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stats(
+        &self,
+        request: ValidatedCellServiceStatsRequest,
+    ) -> Result<CellServiceStatsResponse> {
+        let ValidatedCellServiceStatsRequest { cell_name } = request;
+
+        let mut cells = self.cells.lock().await;
+
+        let (stats, lifetime_stats) = cells.get(&cell_name, |cell| {
+            Ok((cell.stats()?, cell.lifetime_stats()))
+        })?;
+
+        Ok(CellServiceStatsResponse {
+            cell_name: cell_name.to_string(),
+            cpu: Some(stats.cpu.into()),
+            memory: Some(stats.memory.into()),
+            pids: Some(stats.pids.into()),
+            lifetime_stats: Some(lifetime_stats.into()),
+        })
+    }
+
+    /// Creates a client for the nested auraed of a cell.
+    /// Unlike [do_in_cell], the cells are not locked for the duration of the call,
+    /// which makes it suitable for long-running streaming requests.
+    async fn cell_client(&self, cell_name: &CellName) -> Result<Client> {
+        let client_socket = {
+            let mut cells = self.cells.lock().await;
+            cells.get(cell_name, |cell| cell.client_socket())?
+        };
+
+        connect_to_cell(client_socket, &mut retry_strategy()).await
+    }
+
+    #[tracing::instrument(skip(self, chunks))]
+    async fn copy_into(
+        &self,
+        destination: CopyDestination,
+        chunks: Streaming<CellServiceCopyIntoRequest>,
+    ) -> Result<CellServiceCopyIntoResponse> {
+        info!("CellService: copy_into() destination={destination:?}");
+
+        let chunks = chunks.map(|message| match message?.payload {
+            Some(cell_service_copy_into_request::Payload::Chunk(chunk)) => {
+                Ok(chunk)
+            }
+            Some(cell_service_copy_into_request::Payload::Header(_)) => {
+                Err(CopyError::UnexpectedHeader)
+            }
+            None => Ok(Bytes::new()),
+        });
+
+        let bytes_written = copy::copy_into(destination, chunks).await?;
+
+        Ok(CellServiceCopyIntoResponse { bytes_written })
+    }
+
+    /// Forwards the copy to the nested auraed of the cell, so that the files
+    /// are written from within the cell's mount namespace.
+    #[tracing::instrument(skip(self, chunks))]
+    async fn copy_into_cell(
+        &self,
+        cell_name: &CellName,
+        header: CopyIntoHeader,
+        chunks: Streaming<CellServiceCopyIntoRequest>,
+    ) -> std::result::Result<Response<CellServiceCopyIntoResponse>, Status>
+    {
+        let client = self.cell_client(cell_name).await?;
+
+        let header = CellServiceCopyIntoRequest {
+            payload: Some(cell_service_copy_into_request::Payload::Header(
+                header,
+            )),
+        };
+
+        // An error of the incoming stream fails the copy. A second header is
+        // forwarded in its place, which the nested auraed fails the copy on,
+        // removing what it wrote, rather than ending the stream as if the
+        // copy was complete.
+        let error = Arc::new(std::sync::Mutex::new(None));
+        let chunks = chunks.map_while({
+            let error = error.clone();
+            let mut failed = false;
+            move |message| {
+                if failed {
+                    return None;
+                }
+                Some(message.unwrap_or_else(|status| {
+                    failed = true;
+                    *error.lock().expect("copy error lock") = Some(status);
+                    CellServiceCopyIntoRequest {
+                        payload: Some(
+                            cell_service_copy_into_request::Payload::Header(
+                                CopyIntoHeader::default(),
+                            ),
+                        ),
+                    }
+                }))
+            }
+        });
+
+        let response =
+            client.copy_into(tokio_stream::once(header).chain(chunks)).await;
+        if let Some(status) = error.lock().expect("copy error lock").take() {
+            return Err(status);
+        }
+        response
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn copy_from(
+        &self,
+        source_path: CopyPath,
+    ) -> Result<
+        ReceiverStream<
+            std::result::Result<CellServiceCopyFromResponse, Status>,
+        >,
+    > {
+        info!("CellService: copy_from() source_path={source_path}");
+
+        let (header, mut source) = copy::copy_from(source_path).await?;
+
+        let (tx, rx) = mpsc::channel::<
+            std::result::Result<CellServiceCopyFromResponse, Status>,
+        >(4);
+
+        let _ignored = tokio::spawn(async move {
+            let mut payload =
+                Some(cell_service_copy_from_response::Payload::Header(header));
+
+            while let Some(next) = payload.take() {
+                let resp = CellServiceCopyFromResponse { payload: Some(next) };
+                if tx.send(Ok(resp)).await.is_err() {
+                    // receiver is gone
+                    return;
+                }
+
+                payload = match source.next_chunk().await {
+                    Ok(chunk) => chunk
+                        .map(cell_service_copy_from_response::Payload::Chunk),
+                    Err(e) => {
+                        let _ = tx
+                            .send(Err(CellsServiceError::from(e).into()))
+                            .await;
+                        return;
+                    }
+                };
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Forwards the copy to the nested auraed of the cell, so that the files
+    /// are read from within the cell's mount namespace.
+    #[tracing::instrument(skip(self))]
+    async fn copy_from_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceCopyFromRequest,
+    ) -> Result<
+        ReceiverStream<
+            std::result::Result<CellServiceCopyFromResponse, Status>,
+        >,
+    > {
+        let client = self.cell_client(cell_name).await?;
+
+        let mut stream = client
+            .copy_from(request)
+            .await
+            .map_err(CopyError::Stream)?
+            .into_inner();
+
+        let (tx, rx) = mpsc::channel::<
+            std::result::Result<CellServiceCopyFromResponse, Status>,
+        >(4);
+
+        let _ignored = tokio::spawn(async move {
+            while let Some(resp) = stream.next().await {
+                if tx.send(resp).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Streams the OOM kills of all cells, or of a cell and its nested cells.
+    /// Nested cells are allocated by this auraed, so their events are
+    /// reported here as well, under their own name.
+    #[tracing::instrument(skip(self))]
+    async fn watch_oom_events(
+        &self,
+        request: ValidatedCellServiceWatchOomEventsRequest,
+    ) -> Result<
+        ReceiverStream<
+            std::result::Result<CellServiceWatchOomEventsResponse, Status>,
+        >,
+    > {
+        let ValidatedCellServiceWatchOomEventsRequest { cell_name } = request;
+        // Including the events of the cells nested in it
+        let cell_name = cell_name.map(|x| (x.to_string(), format!("{x}/")));
+
+        let mut events = {
+            let cells = self.cells.lock().await;
+            cells.oom_events().subscribe()
+        };
+
+        let (tx, rx) = mpsc::channel::<
+            std::result::Result<CellServiceWatchOomEventsResponse, Status>,
+        >(4);
+
+        let _ignored = tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("OOM event watcher lagged, skipped {skipped} events");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                if let Some((cell_name, nested)) = &cell_name {
+                    let name = event.cell_name.to_string();
+                    if name != *cell_name && !name.starts_with(nested) {
+                        continue;
+                    }
+                }
+
+                let resp = CellServiceWatchOomEventsResponse {
+                    cell_name: event.cell_name.to_string(),
+                    oom_kill_count: event.oom_kill_count,
+                    timestamp: event.timestamp,
+                };
+
+                if tx.send(Ok(resp)).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        let stream_closer = self.observe_service.stream_closer();
+        Ok(stream_closer.until_closed(ReceiverStream::new(rx)))
+    }
+
+    /// Streams samples of the resource usage of cells and executables. The
+    /// executables of a cell are sampled by its auraed, the other targets by
+    /// the sampler shared by all the watches of this auraed.
+    #[tracing::instrument(skip(self))]
+    async fn watch_usage(
+        &self,
+        request: ValidatedCellServiceWatchUsageRequest,
+    ) -> std::result::Result<
+        ReceiverStream<
+            std::result::Result<CellServiceWatchUsageResponse, Status>,
+        >,
+        Status,
+    > {
+        let ValidatedCellServiceWatchUsageRequest { targets, interval_ms } =
+            request;
+
+        let mut sources = vec![];
+        let mut exited = vec![];
+        let mut cell_executables: HashMap<CellName, Vec<UsageTarget>> =
+            HashMap::new();
+        for ValidatedUsageTarget { cell_name, executable_name } in targets {
+            match (cell_name, executable_name) {
+                (Some(cell_name), None) => {
+                    let path = {
+                        let mut cells = self.cells.lock().await;
+                        cells
+                            .get(&cell_name, |cell| cell.stats_path())
+                            .map_err(CellsServiceError::from)?
+                    };
+                    sources.push(UsageSource::Cell { cell_name, path });
+                }
+                (Some(cell_name), Some(executable_name)) => {
+                    cell_executables.entry(cell_name).or_default().push(
+                        UsageTarget {
+                            cell_name: None,
+                            executable_name: Some(executable_name.to_string()),
+                        },
+                    );
+                }
+                (None, Some(executable_name)) => {
+                    let (pid, cgroup) = {
+                        let executables = self.executables.lock().await;
+                        let executable = executables
+                            .get(&executable_name)
+                            .map_err(CellsServiceError::ExecutablesError)?;
+                        (
+                            executable.pid().map_err(CellsServiceError::Io)?,
+                            executable.cgroup_path().map(Path::to_path_buf),
+                        )
+                    };
+                    match pid {
+                        Some(pid) => sources.push(UsageSource::Executable {
+                            executable_name,
+                            pid: pid.as_raw(),
+                            cgroup,
+                        }),
+                        // Adopted executables that had already exited
+                        None => exited.push(executable_name),
+                    }
+                }
+                // Rejected by the validation
+                (None, None) => {}
+            }
+        }
+
+        // Connect to the auraed of every cell before streaming anything
+        let mut cell_samples = vec![];
+        for (cell_name, targets) in cell_executables {
+            let client = self.cell_client(&cell_name).await?;
+            let samples = client
+                .watch_usage(CellServiceWatchUsageRequest {
+                    targets,
+                    interval_ms: interval_ms.as_millis() as u32,
+                })
+                .await?
+                .into_inner();
+            cell_samples.push((cell_name, samples));
+        }
+
+        let (tx, rx) = mpsc::channel::<
+            std::result::Result<CellServiceWatchUsageResponse, Status>,
+        >(16);
+
+        for executable_name in exited {
+            let sample = CellServiceWatchUsageResponse {
+                target: Some(UsageTarget {
+                    cell_name: None,
+                    executable_name: Some(executable_name.to_string()),
+                }),
+                gone: true,
+                ..Default::default()
+            };
+            let _ = tx.try_send(Ok(sample));
+        }
+
+        if !sources.is_empty() {
+            let mut watch = self.usage_sampler.watch(interval_ms, sources);
+            let tx = tx.clone();
+            let _ignored = tokio::spawn(async move {
+                // Stops watching as soon as the receiver is gone
+                while let Some(samples) = tokio::select! {
+                    samples = watch.next() => samples,
+                    _ = tx.closed() => None,
+                } {
+                    for sample in samples {
+                        if tx.send(Ok(sample.into())).await.is_err() {
+                            // receiver is gone
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        for (cell_name, mut samples) in cell_samples {
+            let tx = tx.clone();
+            let _ignored = tokio::spawn(async move {
+                while let Some(sample) = samples.next().await {
+                    let sample = sample.map(|mut sample| {
+                        if let Some(target) = &mut sample.target {
+                            target.cell_name = Some(cell_name.to_string());
+                        }
+                        sample
+                    });
+                    if tx.send(sample).await.is_err() {
+                        // receiver is gone
+                        break;
+                    }
+                }
+            });
+        }
+
+        let stream_closer = self.observe_service.stream_closer();
+        Ok(stream_closer.until_closed(ReceiverStream::new(rx)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn net_check(&self, check: NetCheck) -> Result<NetCheckReport> {
+        info!("CellService: net_check() check={check:?}");
+        Ok(net_check::net_check(check).await?)
+    }
+
+    /// Forwards the check to the nested auraed of the cell, so that it runs
+    /// from within the cell's network namespace.
+    #[tracing::instrument(skip(self))]
+    async fn net_check_in_cell(
+        &self,
+        cell_name: &CellName,
+        request: CellServiceNetCheckRequest,
+    ) -> std::result::Result<Response<CellServiceNetCheckResponse>, Status>
+    {
+        do_in_cell!(self, cell_name, net_check, request)
+    }
+
+    /// Exports the cells, parents first, with the executables started in
+    /// them through this auraed that are still running. The values matching
+    /// the log redaction rules of auraed are redacted.
+    #[tracing::instrument(skip(self))]
+    async fn export(
+        &self,
+    ) -> std::result::Result<CellServiceExportResponse, Status> {
+        let records = {
+            let cells = self.cells.lock().await;
+            cell_records(&*cells)
+        };
+
+        let mut definitions = Vec::with_capacity(records.len());
+        for CellRecord { cell, client_socket, .. } in records {
+            let cell_name = CellName::from(cell.name.as_str());
+            let running = running_executables(
+                &cell_name,
+                AuraeSocket::Path(client_socket),
+            )
+            .await?
+            .map(|(_, executable_names)| executable_names)
+            .unwrap_or_default();
+
+            let executables = self
+                .started
+                .lock()
+                .await
+                .get(&cell_name)
+                .iter()
+                .filter(|x| running.iter().any(|name| name == export::name(x)))
+                .cloned()
+                .collect();
+
+            let mut definition = CellDefinition {
+                cell: Some(cell),
+                executables,
+                redacted: vec![],
+            };
+            export::redact(&mut definition, redaction::redact);
+            definitions.push(definition);
+        }
+
+        Ok(CellServiceExportResponse {
+            document: Some(CellsDocument { cells: definitions }),
+        })
+    }
+
+    /// Allocates the cells of a document that are not allocated, and starts
+    /// their executables that are not running. The changes are made with the
+    /// handlers of Free, Allocate and Start, as if requested one by one by
+    /// the client of `request`, so that each is audited and persisted.
+    #[tracing::instrument(skip(self, request, validated))]
+    async fn import(
+        &self,
+        request: &Request<CellServiceImportRequest>,
+        validated: ValidatedCellServiceImportRequest,
+    ) -> std::result::Result<CellServiceImportResponse, Status> {
+        let ValidatedCellServiceImportRequest { document, dry_run, prune } =
+            validated;
+        info!(
+            "CellService: import() cells={} dry_run={dry_run} prune={prune}",
+            document.len()
+        );
+
+        let allocated: Vec<_> = {
+            let cells = self.cells.lock().await;
+            cell_records(&*cells)
+                .into_iter()
+                .map(|record| CellName::from(record.cell.name.as_str()))
+                .collect()
+        };
+
+        let mut changes = vec![];
+
+        // Pruned first, so that the cells of the document can take the
+        // ports and resources of the cells freed
+        if prune {
+            let kept = document.iter().map(|x| x.name.clone()).collect();
+            for cell_name in export::pruned(&allocated, &kept) {
+                let mut action = ImportAction::Free;
+                let mut reason = String::new();
+                if !dry_run {
+                    let free = derive_request(
+                        request,
+                        CellServiceFreeRequest {
+                            cell_name: cell_name.to_string(),
+                            force: true,
+                            recursive: true,
+                            timeout_ms: 0,
+                        },
+                    );
+                    if let Err(e) =
+                        cell_service_server::CellService::free(self, free).await
+                    {
+                        action = ImportAction::Failed;
+                        reason = e.message().to_string();
+                    }
+                }
+                changes.push(import_change(action, &cell_name, None, reason));
+            }
+        }
+
+        for ValidatedCellDefinition {
+            name: cell_name,
+            spec,
+            cell,
+            executables,
+        } in document
+        {
+            let running = if allocated.contains(&cell_name) {
+                let diff = self
+                    .cells
+                    .lock()
+                    .await
+                    .get(&cell_name, |x| Ok(x.spec().diff(&spec)));
+                let (action, reason) = match diff {
+                    Ok(spec_changes) if spec_changes.is_empty() => {
+                        (ImportAction::Skip, String::new())
+                    }
+                    Ok(spec_changes) => (
+                        ImportAction::Conflict,
+                        spec_changes
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    Err(e) => (ImportAction::Failed, e.to_string()),
+                };
+                changes.push(import_change(action, &cell_name, None, reason));
+                if action == ImportAction::Failed {
+                    continue;
+                }
+
+                let list = Request::new(CellServiceListExecutablesRequest {
+                    cell_name: Some(cell_name.to_string()),
+                });
+                cell_service_server::CellService::list_executables(self, list)
+                    .await
+                    .map(|res| res.into_inner().executable_names)
+            } else {
+                let mut action = ImportAction::Allocate;
+                let mut reason = String::new();
+                if !dry_run {
+                    let allocate = derive_request(
+                        request,
+                        CellServiceAllocateRequest {
+                            cell: Some(cell),
+                            update: false,
+                        },
+                    );
+                    if let Err(e) = cell_service_server::CellService::allocate(
+                        self, allocate,
+                    )
+                    .await
+                    {
+                        action = ImportAction::Failed;
+                        reason = e.message().to_string();
+                    }
+                }
+                changes.push(import_change(action, &cell_name, None, reason));
+                if action == ImportAction::Failed {
+                    continue;
+                }
+
+                Ok(vec![])
+            };
+
+            let started = self.started.lock().await.get(&cell_name).to_vec();
+            for definition in executables {
+                let executable_name = export::name(&definition).to_owned();
+                let (action, reason) = match &running {
+                    Err(e) => (ImportAction::Failed, e.message().to_string()),
+                    Ok(running) if running.contains(&executable_name) => {
+                        match started
+                            .iter()
+                            .find(|x| export::name(x) == executable_name)
+                        {
+                            Some(x) if *x == definition => {
+                                (ImportAction::Skip, String::new())
+                            }
+                            Some(_) => (
+                                ImportAction::Conflict,
+                                "running with a different spec".into(),
+                            ),
+                            None => (
+                                ImportAction::Conflict,
+                                "running, but not started through this auraed"
+                                    .into(),
+                            ),
+                        }
+                    }
+                    Ok(_) if dry_run => (ImportAction::Start, String::new()),
+                    Ok(_) => {
+                        let ExecutableDefinition { executable, uid, gid } =
+                            definition;
+                        let start = derive_request(
+                            request,
+                            CellServiceStartRequest {
+                                cell_name: Some(cell_name.to_string()),
+                                executable,
+                                uid,
+                                gid,
+                            },
+                        );
+                        match cell_service_server::CellService::start(
+                            self, start,
+                        )
+                        .await
+                        {
+                            Ok(_) => (ImportAction::Start, String::new()),
+                            Err(e) => {
+                                (ImportAction::Failed, e.message().to_string())
+                            }
+                        }
+                    }
+                };
+                changes.push(import_change(
+                    action,
+                    &cell_name,
+                    Some(&executable_name),
+                    reason,
+                ));
+            }
+        }
+
+        Ok(CellServiceImportResponse { changes })
+    }
 }
 
 impl TryFrom<&super::cells::Cell> for CellGraphNode {
@@ -371,33 +2465,101 @@ impl TryFrom<&super::cells::Cell> for CellGraphNode {
     fn try_from(
         value: &super::cells::Cell,
     ) -> std::result::Result<Self, Self::Error> {
+        // Retrieve and convert all child cells
+        let children = CellsCache::get_all(value, |x| x.try_into())?
+            .into_iter()
+            .filter_map(|x| x.ok())
+            .collect();
+
+        // The health is left unspecified, as checking it means calling into
+        // the nested auraed.
+        let socket = match value.client_socket()? {
+            AuraeSocket::Path(path) | AuraeSocket::Unix(path) => {
+                path.to_string_lossy().into_owned()
+            }
+            AuraeSocket::Addr(addr) => addr.to_string(),
+        };
+        let nested_auraed = NestedAuraed {
+            pid: value.nested_auraed_pid()?.as_raw(),
+            socket,
+            health: NestedAuraedHealth::Unspecified as i32,
+        };
+
+        Ok(Self {
+            cell: Some(value.into()),
+            children,
+            nested_auraed: Some(nested_auraed),
+            lifetime_stats: Some(value.lifetime_stats().into()),
+        })
+    }
+}
+
+impl From<&super::cells::Cell> for Cell {
+    fn from(value: &super::cells::Cell) -> Self {
         // Extract the name and specification of the cell
         let name = value.name();
         let spec = value.spec();
-        // Retrieve and convert all child cells
-        let children = CellsCache::get_all(value, |x| x.try_into())?
-            .into_iter()
-            .filter_map(|x| x.ok())
-            .collect();
 
         // Extract cgroup and isolation specifications
-        let super::cells::CellSpec { cgroup_spec, iso_ctl } = spec;
+        let super::cells::CellSpec {
+            cgroup_spec,
+            iso_ctl,
+            variables,
+            reserved_ports,
+            default_uid,
+            default_gid,
+            user_namespace,
+            per_executable_accounting,
+        } = spec;
         // Extract CPU, cpuset, and memory specifications
-        let super::cells::cgroups::CgroupSpec { cpu, cpuset, memory } =
-            cgroup_spec;
+        let super::cells::cgroups::CgroupSpec {
+            cpu,
+            cpuset,
+            memory,
+            device_allow,
+        } = cgroup_spec;
+
+        // Create a new Cell instance with the extracted specifications
+        Self {
+            name: name.to_string(),
+            cpu: cpu.as_ref().map(|x| x.into()),
+            cpuset: cpuset.as_ref().map(|x| x.into()),
+            memory: memory.as_ref().map(|x| x.into()),
+            device_allow: device_allow.iter().map(|x| x.into()).collect(),
+            isolate_process: iso_ctl.isolate_process,
+            isolate_network: iso_ctl.isolate_network,
+            isolate_uts: iso_ctl.isolate_uts,
+            hostname: iso_ctl.hostname.clone().map(|x| x.into_inner()),
+            variables: variables.clone().into_iter().collect(),
+            reserved_ports: reserved_ports
+                .iter()
+                .map(|port| u32::from(*port))
+                .collect(),
+            default_uid: *default_uid,
+            default_gid: *default_gid,
+            userns: user_namespace.as_ref().map(|x| x.into()),
+            per_executable_accounting: *per_executable_accounting,
+        }
+    }
+}
 
-        Ok(Self {
-            // Create a new Cell instance with the extracted specifications
-            cell: Some(Cell {
-                name: name.to_string(),
-                cpu: cpu.as_ref().map(|x| x.into()),
-                cpuset: cpuset.as_ref().map(|x| x.into()),
-                memory: memory.as_ref().map(|x| x.into()),
-                isolate_process: iso_ctl.isolate_process,
-                isolate_network: iso_ctl.isolate_network,
-            }),
-            children,
-        })
+impl From<&super::executables::UserNamespace> for UserNamespace {
+    fn from(value: &super::executables::UserNamespace) -> Self {
+        let super::executables::UserNamespace { uid_map, gid_map } = value;
+
+        Self {
+            uid_map: uid_map.iter().map(|x| x.into()).collect(),
+            gid_map: gid_map.iter().map(|x| x.into()).collect(),
+        }
+    }
+}
+
+impl From<&super::executables::IdMapping> for IdMapping {
+    fn from(value: &super::executables::IdMapping) -> Self {
+        let super::executables::IdMapping { container_id, host_id, size } =
+            *value;
+
+        Self { container_id, host_id, size }
     }
 }
 
@@ -444,6 +2606,146 @@ impl From<&super::cells::cgroups::memory::MemoryController>
     }
 }
 
+impl From<&super::cells::cgroups::DeviceRule> for DeviceRule {
+    fn from(value: &super::cells::cgroups::DeviceRule) -> Self {
+        let super::cells::cgroups::DeviceRule {
+            device_type,
+            major,
+            minor,
+            access,
+        } = *value;
+
+        Self {
+            device_type: device_type.to_string(),
+            major,
+            minor,
+            access: access.to_string(),
+        }
+    }
+}
+
+impl From<super::cells::cgroups::stats::CpuStats> for CpuStats {
+    fn from(value: super::cells::cgroups::stats::CpuStats) -> Self {
+        let super::cells::cgroups::stats::CpuStats {
+            usage_usec,
+            throttled_usec,
+        } = value;
+
+        Self { usage_usec, throttled_usec }
+    }
+}
+
+impl From<super::cells::cgroups::stats::MemoryStats> for MemoryStats {
+    fn from(value: super::cells::cgroups::stats::MemoryStats) -> Self {
+        let super::cells::cgroups::stats::MemoryStats {
+            current,
+            peak,
+            oom,
+            oom_kill,
+        } = value;
+
+        Self { current, peak, oom, oom_kill }
+    }
+}
+
+impl From<super::cells::cgroups::MemoryEvents> for MemoryEvents {
+    fn from(value: super::cells::cgroups::MemoryEvents) -> Self {
+        let super::cells::cgroups::MemoryEvents {
+            low,
+            high,
+            max,
+            oom,
+            oom_kill,
+        } = value;
+        Self { low, high, max, oom, oom_kill }
+    }
+}
+
+impl From<UsageSample> for CellServiceWatchUsageResponse {
+    fn from(value: UsageSample) -> Self {
+        let UsageSample {
+            source,
+            timestamp_ms,
+            cpu_usage_delta_usec,
+            memory_current,
+            rss,
+            gone,
+        } = value;
+
+        let target = match source {
+            UsageSource::Cell { cell_name, .. } => UsageTarget {
+                cell_name: Some(cell_name.to_string()),
+                executable_name: None,
+            },
+            UsageSource::Executable { executable_name, .. } => UsageTarget {
+                cell_name: None,
+                executable_name: Some(executable_name.to_string()),
+            },
+        };
+
+        Self {
+            target: Some(target),
+            timestamp_ms,
+            cpu_usage_delta_usec,
+            memory_current,
+            rss,
+            gone,
+        }
+    }
+}
+
+impl From<Resources> for AdmissionResources {
+    fn from(value: Resources) -> Self {
+        let Resources { memory_bytes, cpu_millis, pids } = value;
+        Self { memory_bytes, cpu_millis, pids }
+    }
+}
+
+impl From<super::cells::cgroups::stats::PidsStats> for PidsStats {
+    fn from(value: super::cells::cgroups::stats::PidsStats) -> Self {
+        let super::cells::cgroups::stats::PidsStats { current } = value;
+
+        Self { current }
+    }
+}
+
+impl From<super::cells::LifetimeStats> for LifetimeStats {
+    fn from(value: super::cells::LifetimeStats) -> Self {
+        let super::cells::LifetimeStats {
+            cpu_usage_usec,
+            oom_kills,
+            executables_started,
+            executables_stopped,
+            starts,
+            sampled_at,
+        } = value;
+
+        let millis = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as i64)
+                .unwrap_or_default()
+        };
+        let restarts = starts
+            .into_iter()
+            .filter(|(_, starts)| starts.restarts > 0)
+            .map(|(executable_name, starts)| ExecutableRestarts {
+                executable_name,
+                restarts: starts.restarts,
+                last_started_ms: millis(starts.last_started_at),
+            })
+            .collect();
+
+        Self {
+            cpu_usage_usec,
+            oom_kills,
+            executables_started,
+            executables_stopped,
+            restarts,
+            sampled_at_ms: sampled_at.map(millis).unwrap_or_default(),
+        }
+    }
+}
+
 /// ### Mapping cgroup options to the Cell API
 ///
 /// Here we *only* expose options from the CgroupBuilder
@@ -460,84 +2762,418 @@ impl cell_service_server::CellService for CellService {
         request: Request<CellServiceAllocateRequest>,
     ) -> std::result::Result<Response<CellServiceAllocateResponse>, Status>
     {
-        // Extract the inner request from the request
-        let request = request.into_inner();
-        // Validate the allocate request
-        let request = ValidatedCellServiceAllocateRequest::validate(
-            request.clone(),
-            None,
-        )?;
+        let cell_name = request.get_ref().cell.as_ref().map(|c| c.name.clone());
+        let audit = Audit::begin(
+            &request,
+            "Allocate",
+            RequestSummary { cell_name, ..Default::default() },
+        );
+
+        let deadline = request_deadline(&request);
+
+        // Detached, so a client that disconnects mid-allocate can't stop the
+        // allocated cell from being persisted and audited.
+        let service = self.clone();
+        let allocate = tokio::spawn(async move {
+            let response = async {
+                // Extract the inner request from the request
+                let request = request.into_inner();
+                // Validate the allocate request
+                let request = ValidatedCellServiceAllocateRequest::validate(
+                    request.clone(),
+                    None,
+                )?;
+
+                let response = service.allocate(request, deadline).await;
+                service.persist_state().await;
+
+                // return the allocated cell
+                Ok(Response::new(response?))
+            }
+            .await;
+
+            service.end_audit(audit, &response).await;
+            response
+        });
+
+        allocate.await.map_err(|e| Status::internal(e.to_string()))?
+    }
+
+    async fn update(
+        &self,
+        request: Request<CellServiceUpdateRequest>,
+    ) -> std::result::Result<Response<CellServiceUpdateResponse>, Status> {
+        let audit = Audit::begin(
+            &request,
+            "Update",
+            RequestSummary {
+                cell_name: Some(request.get_ref().cell_name.clone()),
+                ..Default::default()
+            },
+        );
+
+        let response = async {
+            let request = request.into_inner();
+            // Validate the update request
+            let request =
+                ValidatedCellServiceUpdateRequest::validate(request, None)?;
+
+            let response = self.update(request).await;
+            self.persist_state().await;
+
+            Ok(Response::new(response?))
+        }
+        .await;
 
-        // return the allocated cell
-        Ok(Response::new(self.allocate(request).await?))
+        self.end_audit(audit, &response).await;
+        response
     }
 
     async fn free(
         &self,
         request: Request<CellServiceFreeRequest>,
     ) -> std::result::Result<Response<CellServiceFreeResponse>, Status> {
-        let request = request.into_inner();
-        // Validate the free request
-        let request =
-            ValidatedCellServiceFreeRequest::validate(request.clone(), None)?;
+        let audit = Audit::begin(
+            &request,
+            "Free",
+            RequestSummary {
+                cell_name: Some(request.get_ref().cell_name.clone()),
+                ..Default::default()
+            },
+        );
+
+        // Detached, so a client that disconnects mid-free can't leave the
+        // cell taken out of the cache, nor its admission unreleased.
+        let service = self.clone();
+        let free = tokio::spawn(async move {
+            let response = async {
+                let request = request.into_inner();
+                // Validate the free request
+                let request = ValidatedCellServiceFreeRequest::validate(
+                    request.clone(),
+                    None,
+                )?;
+
+                // free the cell, which may have partially succeeded on error
+                let response = service.free(request).await;
+                service.persist_state().await;
+
+                Ok(Response::new(response?))
+            }
+            .await;
 
-        // free the cell
-        Ok(Response::new(self.free(request).await?))
+            service.end_audit(audit, &response).await;
+            response
+        });
+
+        free.await.map_err(|e| Status::internal(e.to_string()))?
     }
 
     async fn start(
         &self,
         request: Request<CellServiceStartRequest>,
     ) -> std::result::Result<Response<CellServiceStartResponse>, Status> {
-        let request = request.into_inner();
+        let executable = request.get_ref().executable.as_ref();
+        let mut audit = Audit::begin(
+            &request,
+            "Start",
+            RequestSummary {
+                cell_name: request.get_ref().cell_name.clone(),
+                executable_name: executable.map(|e| e.name.clone()),
+                command: executable.map(|e| e.command.clone()),
+            },
+        );
+
+        let response = async {
+            let request = request.into_inner();
+
+            // Execute start if cell_name is none
+            if request.cell_name.is_none() {
+                let request =
+                    ValidatedCellServiceStartRequest::validate(request, None)?;
+                let response = self.start(request).await;
+                self.persist_state().await;
+                response
+            } else {
+                // We are in a parent cell, or validation will fail
+                let validated = ValidatedCellServiceStartRequest::validate(
+                    request.clone(),
+                    None,
+                )?;
+
+                // Validation has succeeded, so we can make assumptions about the request and use expect
+                let cell_name = validated.cell_name.expect("cell name");
+                let mut request = request;
+                request.cell_name = None;
+
+                // Recorded as requested, for the cell to be exported
+                let definition = ExecutableDefinition {
+                    executable: request.executable.clone(),
+                    uid: request.uid,
+                    gid: request.gid,
+                };
+
+                let (default_uid, default_gid) =
+                    self.default_ids(&cell_name).await?;
+                request.uid = request.uid.or(default_uid);
+                request.gid = request.gid.or(default_gid);
+
+                let executable =
+                    request.executable.as_mut().expect("executable");
+                executable.command = self
+                    .resolve_command(
+                        &cell_name,
+                        &validated.executable.name,
+                        &executable.command,
+                    )
+                    .await?;
+                audit.set_command(executable.command.clone());
+
+                // start in the cell
+                let response = self.start_in_cell(&cell_name, request).await?;
+                self.started.lock().await.insert(cell_name, definition);
+                self.persist_state().await;
+                Ok(response)
+            }
+        }
+        .await;
 
-        // Execute start if cell_name is none
-        if request.cell_name.is_none() {
-            let request =
-                ValidatedCellServiceStartRequest::validate(request, None)?;
-            Ok(self.start(request).await?)
-        } else {
-            // We are in a parent cell, or validation will fail
-            let validated = ValidatedCellServiceStartRequest::validate(
-                request.clone(),
-                None,
-            )?;
-
-            // Validation has succeeded, so we can make assumptions about the request and use expect
-            let cell_name = validated.cell_name.expect("cell name");
-            let mut request = request;
-            request.cell_name = None;
+        self.end_audit(audit, &response).await;
+        response
+    }
+
+    async fn start_batch(
+        &self,
+        request: Request<CellServiceStartBatchRequest>,
+    ) -> std::result::Result<Response<CellServiceStartBatchResponse>, Status>
+    {
+        let executable_names = request
+            .get_ref()
+            .executables
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let audit = Audit::begin(
+            &request,
+            "StartBatch",
+            RequestSummary {
+                cell_name: request.get_ref().cell_name.clone(),
+                executable_name: Some(executable_names),
+                command: None,
+            },
+        );
+
+        let response = async {
+            let request = request.into_inner();
+
+            // Execute start_batch if cell_name is none
+            if request.cell_name.is_none() {
+                let request = ValidatedCellServiceStartBatchRequest::validate(
+                    request, None,
+                )?;
+                let response = self.start_batch(request).await;
+                self.persist_state().await;
+                response
+            } else {
+                // We are in a parent cell, or validation will fail
+                let validated =
+                    ValidatedCellServiceStartBatchRequest::validate(
+                        request.clone(),
+                        None,
+                    )?;
+                // Rejected before any command is resolved
+                let _ = batch_start_order(&validated.executables)?;
+
+                let cell_name = validated.cell_name.expect("cell name");
+                let mut request = request;
+                request.cell_name = None;
+
+                // Recorded as requested, for the cell to be exported
+                let definitions: Vec<_> = request
+                    .executables
+                    .iter()
+                    .map(|executable| ExecutableDefinition {
+                        executable: Some(executable.clone()),
+                        uid: request.uid,
+                        gid: request.gid,
+                    })
+                    .collect();
+
+                let (default_uid, default_gid) =
+                    self.default_ids(&cell_name).await?;
+                request.uid = request.uid.or(default_uid);
+                request.gid = request.gid.or(default_gid);
+
+                for (executable, spec) in
+                    request.executables.iter_mut().zip(&validated.executables)
+                {
+                    executable.command = self
+                        .resolve_command(
+                            &cell_name,
+                            &spec.name,
+                            &executable.command,
+                        )
+                        .await?;
+                }
 
-            // start in the cell
-            self.start_in_cell(&cell_name, request).await
+                let response =
+                    self.start_batch_in_cell(&cell_name, request).await?;
+
+                let mut started = self.started.lock().await;
+                for (result, definition) in
+                    response.get_ref().results.iter().zip(definitions)
+                {
+                    if let Some(Outcome::Pid(_) | Outcome::Exited(_)) =
+                        result.outcome
+                    {
+                        started.insert(cell_name.clone(), definition);
+                    }
+                }
+                drop(started);
+
+                self.persist_state().await;
+                Ok(response)
+            }
         }
+        .await;
+
+        self.end_audit(audit, &response).await;
+        response
     }
 
     async fn stop(
         &self,
         request: Request<CellServiceStopRequest>,
     ) -> std::result::Result<Response<CellServiceStopResponse>, Status> {
+        let CellServiceStopRequest { cell_name, executable_name, .. } =
+            request.get_ref().clone();
+        let audit = Audit::begin(
+            &request,
+            "Stop",
+            RequestSummary {
+                cell_name,
+                executable_name: Some(executable_name),
+                command: None,
+            },
+        );
+
+        // Detached, so a client that disconnects mid-stop can't leave the
+        // name of the executable reserved, as it is until it is stopped.
+        let service = self.clone();
+        let stop = tokio::spawn(async move {
+            let response = async {
+                let request = request.into_inner();
+
+                // Execute stop if cell_name is none
+                if request.cell_name.is_none() {
+                    let request = ValidatedCellServiceStopRequest::validate(
+                        request, None,
+                    )?;
+                    let response = service.stop(request).await;
+                    service.persist_state().await;
+                    response
+                } else {
+                    // Validate the request is valid
+                    let validated = ValidatedCellServiceStopRequest::validate(
+                        request.clone(),
+                        None,
+                    )?;
+
+                    // Validation has succeeded, so we can make assumptions about the request and use expect
+                    let cell_name = validated.cell_name.expect("cell name");
+                    let mut request = request;
+                    request.cell_name = None;
+
+                    // stop the cell
+                    let response =
+                        service.stop_in_cell(&cell_name, request).await?;
+
+                    service.started.lock().await.remove(
+                        &cell_name,
+                        &validated.executable_name.to_string(),
+                    );
+                    service.persist_state().await;
+                    Ok(response)
+                }
+            }
+            .await;
+
+            service.end_audit(audit, &response).await;
+            response
+        });
+
+        stop.await.map_err(|e| Status::internal(e.to_string()))?
+    }
+
+    async fn quarantine(
+        &self,
+        request: Request<CellServiceQuarantineRequest>,
+    ) -> std::result::Result<Response<CellServiceQuarantineResponse>, Status>
+    {
         let request = request.into_inner();
 
-        // Execute stop if cell_name is none
-        if request.cell_name.is_none() {
-            let request =
-                ValidatedCellServiceStopRequest::validate(request, None)?;
-            Ok(self.stop(request).await?)
+        let validated = ValidatedCellServiceQuarantineRequest::validate(
+            request.clone(),
+            None,
+        )?;
+
+        if let Some(cell_name) = validated.cell_name {
+            let mut request = request;
+            request.cell_name = None;
+
+            // quarantine in the cell
+            self.quarantine_in_cell(&cell_name, request).await
+        } else {
+            Ok(Response::new(self.quarantine(validated).await?))
+        }
+    }
+
+    async fn unquarantine(
+        &self,
+        request: Request<CellServiceUnquarantineRequest>,
+    ) -> std::result::Result<Response<CellServiceUnquarantineResponse>, Status>
+    {
+        let request = request.into_inner();
+
+        let validated = ValidatedCellServiceUnquarantineRequest::validate(
+            request.clone(),
+            None,
+        )?;
+
+        if let Some(cell_name) = validated.cell_name {
+            let mut request = request;
+            request.cell_name = None;
+
+            // unquarantine in the cell
+            self.unquarantine_in_cell(&cell_name, request).await
         } else {
-            // Validate the request is valid
-            let validated = ValidatedCellServiceStopRequest::validate(
-                request.clone(),
-                None,
-            )?;
-
-            // Validation has succeeded, so we can make assumptions about the request and use expect
-            let cell_name = validated.cell_name.expect("cell name");
+            Ok(Response::new(self.unquarantine(validated).await?))
+        }
+    }
+
+    async fn list_executables(
+        &self,
+        request: Request<CellServiceListExecutablesRequest>,
+    ) -> std::result::Result<Response<CellServiceListExecutablesResponse>, Status>
+    {
+        let request = request.into_inner();
+
+        // Validate the list_executables request
+        let validated = ValidatedCellServiceListExecutablesRequest::validate(
+            request.clone(),
+            None,
+        )?;
+
+        // Execute list_executables if cell_name is none
+        if let Some(cell_name) = validated.cell_name {
             let mut request = request;
             request.cell_name = None;
 
-            // stop the cell
-            self.stop_in_cell(&cell_name, request).await
+            // list the executables in the cell
+            self.list_executables_in_cell(&cell_name, request).await
+        } else {
+            Ok(Response::new(self.list_executables().await?))
         }
     }
 
@@ -554,6 +3190,218 @@ impl cell_service_server::CellService for CellService {
     ) -> std::result::Result<Response<CellServiceListResponse>, Status> {
         Ok(Response::new(self.list().await?))
     }
+
+    /// Response with the ports reserved by cells, ordered by port
+    ///
+    /// # Arguments
+    /// * `_request` - A request containing CellServiceListPortReservationsRequest.
+    ///
+    /// # Returns
+    /// A response containing CellServiceListPortReservationsResponse or a Status error.
+    async fn list_port_reservations(
+        &self,
+        _request: Request<CellServiceListPortReservationsRequest>,
+    ) -> std::result::Result<
+        Response<CellServiceListPortReservationsResponse>,
+        Status,
+    > {
+        Ok(Response::new(self.list_port_reservations().await?))
+    }
+
+    /// Responds with the resources committed against the capacity of the
+    /// host, if admission is enabled.
+    async fn admission(
+        &self,
+        _request: Request<CellServiceAdmissionRequest>,
+    ) -> std::result::Result<Response<CellServiceAdmissionResponse>, Status>
+    {
+        Ok(Response::new(self.admission()?))
+    }
+
+    async fn stats(
+        &self,
+        request: Request<CellServiceStatsRequest>,
+    ) -> std::result::Result<Response<CellServiceStatsResponse>, Status> {
+        let request = request.into_inner();
+        // Validate the stats request
+        let request =
+            ValidatedCellServiceStatsRequest::validate(request, None)?;
+
+        Ok(Response::new(self.stats(request).await?))
+    }
+
+    async fn copy_into(
+        &self,
+        request: Request<Streaming<CellServiceCopyIntoRequest>>,
+    ) -> std::result::Result<Response<CellServiceCopyIntoResponse>, Status>
+    {
+        let mut chunks = request.into_inner();
+
+        // The first message must be the header
+        let Some(CellServiceCopyIntoRequest {
+            payload:
+                Some(cell_service_copy_into_request::Payload::Header(header)),
+        }) = chunks.message().await?
+        else {
+            return Err(
+                CellsServiceError::from(CopyError::MissingHeader).into()
+            );
+        };
+
+        // Validate the header
+        let validated =
+            ValidatedCopyIntoHeader::validate(header.clone(), None)?;
+
+        // Execute copy_into if cell_name is none
+        if let Some(cell_name) = validated.cell_name {
+            let mut header = header;
+            header.cell_name = None;
+
+            // copy into the cell
+            self.copy_into_cell(&cell_name, header, chunks).await
+        } else {
+            Ok(Response::new(self.copy_into(validated.into(), chunks).await?))
+        }
+    }
+
+    type CopyFromStream = ReceiverStream<
+        std::result::Result<CellServiceCopyFromResponse, Status>,
+    >;
+
+    async fn copy_from(
+        &self,
+        request: Request<CellServiceCopyFromRequest>,
+    ) -> std::result::Result<Response<Self::CopyFromStream>, Status> {
+        let request = request.into_inner();
+
+        // Validate the copy_from request
+        let validated = ValidatedCellServiceCopyFromRequest::validate(
+            request.clone(),
+            None,
+        )?;
+
+        // Execute copy_from if cell_name is none
+        if let Some(cell_name) = validated.cell_name {
+            let mut request = request;
+            request.cell_name = None;
+
+            // copy from the cell
+            Ok(Response::new(self.copy_from_cell(&cell_name, request).await?))
+        } else {
+            Ok(Response::new(self.copy_from(validated.source_path).await?))
+        }
+    }
+
+    type WatchOomEventsStream = ReceiverStream<
+        std::result::Result<CellServiceWatchOomEventsResponse, Status>,
+    >;
+
+    async fn watch_oom_events(
+        &self,
+        request: Request<CellServiceWatchOomEventsRequest>,
+    ) -> std::result::Result<Response<Self::WatchOomEventsStream>, Status> {
+        let request = request.into_inner();
+        // Validate the watch request
+        let request =
+            ValidatedCellServiceWatchOomEventsRequest::validate(request, None)?;
+
+        Ok(Response::new(self.watch_oom_events(request).await?))
+    }
+
+    type WatchUsageStream = ReceiverStream<
+        std::result::Result<CellServiceWatchUsageResponse, Status>,
+    >;
+
+    async fn watch_usage(
+        &self,
+        request: Request<CellServiceWatchUsageRequest>,
+    ) -> std::result::Result<Response<Self::WatchUsageStream>, Status> {
+        let request = request.into_inner();
+        let request =
+            ValidatedCellServiceWatchUsageRequest::validate(request, None)?;
+
+        Ok(Response::new(self.watch_usage(request).await?))
+    }
+
+    async fn net_check(
+        &self,
+        request: Request<CellServiceNetCheckRequest>,
+    ) -> std::result::Result<Response<CellServiceNetCheckResponse>, Status>
+    {
+        let request = request.into_inner();
+
+        // Validate the net_check request
+        let validated = ValidatedCellServiceNetCheckRequest::validate(
+            request.clone(),
+            None,
+        )?;
+
+        // Reject the check if too many are already running
+        let _permit = self.net_checks.try_acquire().map_err(|_| {
+            CellsServiceError::from(net_check::NetCheckError::RateLimited)
+        })?;
+
+        // Execute net_check if cell_name is none
+        if let Some(cell_name) = validated.cell_name {
+            let mut request = request;
+            request.cell_name = None;
+
+            // check from within the cell
+            self.net_check_in_cell(&cell_name, request).await
+        } else {
+            let report = self.net_check(validated.into()).await?;
+            Ok(Response::new(report.into()))
+        }
+    }
+
+    async fn export(
+        &self,
+        _request: Request<CellServiceExportRequest>,
+    ) -> std::result::Result<Response<CellServiceExportResponse>, Status> {
+        Ok(Response::new(self.export().await?))
+    }
+
+    /// The import itself is not audited, but each change it makes is, as an
+    /// Allocate, Start or Free.
+    async fn import(
+        &self,
+        request: Request<CellServiceImportRequest>,
+    ) -> std::result::Result<Response<CellServiceImportResponse>, Status> {
+        // Validated as a whole, so that an invalid document changes nothing
+        let validated = ValidatedCellServiceImportRequest::validate(
+            request.get_ref().clone(),
+            None,
+        )?;
+
+        Ok(Response::new(self.import(&request, validated).await?))
+    }
+}
+
+impl From<NetCheckReport> for CellServiceNetCheckResponse {
+    fn from(value: NetCheckReport) -> Self {
+        let NetCheckReport { resolved_addresses, attempts } = value;
+
+        Self {
+            resolved_addresses: resolved_addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+            attempts: attempts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<net_check::NetCheckAttempt> for NetCheckAttempt {
+    fn from(value: net_check::NetCheckAttempt) -> Self {
+        let net_check::NetCheckAttempt { latency, error } = value;
+
+        Self {
+            success: error.is_none(),
+            latency_us: latency.as_micros().try_into().unwrap_or(u64::MAX),
+            error: error.as_ref().map(|e| e.to_string()).unwrap_or_default(),
+            errno: error.and_then(|e| e.raw_os_error()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -588,7 +3436,7 @@ mod tests {
         // Allocate a parent cell for testing
         let parent_cell_name = format!("ae-test-{}", uuid::Uuid::new_v4());
         assert!(service
-            .allocate(allocate_request(&parent_cell_name))
+            .allocate(allocate_request(&parent_cell_name), None)
             .await
             .is_ok());
 
@@ -596,7 +3444,7 @@ mod tests {
         let nested_cell_name =
             format!("{}/ae-test-{}", &parent_cell_name, uuid::Uuid::new_v4());
         assert!(service
-            .allocate(allocate_request(&nested_cell_name))
+            .allocate(allocate_request(&nested_cell_name), None)
             .await
             .is_ok());
 
@@ -604,7 +3452,7 @@ mod tests {
         let cell_without_children_name =
             format!("ae-test-{}", uuid::Uuid::new_v4());
         assert!(service
-            .allocate(allocate_request(&cell_without_children_name))
+            .allocate(allocate_request(&cell_without_children_name), None)
             .await
             .is_ok());
 
@@ -628,6 +3476,13 @@ mod tests {
         actual_root_cell_names.sort();
         assert_eq!(actual_root_cell_names, expected_root_cell_names);
 
+        // Verify the health of the nested auraeds was checked
+        for cell in &list.cells {
+            let nested_auraed = cell.nested_auraed.as_ref().unwrap();
+            assert!(nested_auraed.pid > 0);
+            assert_ne!(nested_auraed.health(), NestedAuraedHealth::Unspecified);
+        }
+
         // Verify the parent cell name in child cells.
         let parent_cell = list
             .cells
@@ -645,6 +3500,33 @@ mod tests {
         assert_eq!(actual_nested_cell_names, expected_nested_cell_names);
     }
 
+    #[tokio::test]
+    async fn test_allocate_does_not_start_after_the_deadline() {
+        let _ = AURAED_RUNTIME.set(AuraedRuntime::default());
+
+        let service = CellService::new(ObserveService::new(
+            Arc::new(LogChannel::new(String::from("test"))),
+            (None, None, None),
+        ));
+
+        // The cells are held, as by a slow request
+        let cells = service.cells.lock().await;
+
+        let cell_name = format!("ae-test-{}", uuid::Uuid::new_v4());
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let err = service
+            .allocate(allocate_request(&cell_name), Some(deadline))
+            .await
+            .expect_err("allocate past its deadline must fail");
+        assert!(matches!(err, CellsServiceError::DeadlineExceeded { .. }));
+        assert_eq!(Status::from(err).code(), Code::DeadlineExceeded);
+
+        // The cell was never allocated
+        drop(cells);
+        let mut cells = service.cells.lock().await;
+        assert!(cells.get(&CellName::from(&*cell_name), |_| Ok(())).is_err());
+    }
+
     /// Helper function to create a ValidatedCellServiceAllocateRequest.
     ///
     /// # Arguments
@@ -670,10 +3552,19 @@ mod tests {
                 high: None,
                 max: None,
             }),
+            device_allow: vec![],
             isolate_process: false,
             isolate_network: false,
+            isolate_uts: false,
+            hostname: None,
+            variables: Default::default(),
+            reserved_ports: Default::default(),
+            default_uid: None,
+            default_gid: None,
+            userns: None,
+            per_executable_accounting: false,
         };
         // Return the validated allocate request
-        ValidatedCellServiceAllocateRequest { cell }
+        ValidatedCellServiceAllocateRequest { cell, update: false }
     }
 }
@@ -14,11 +14,24 @@
 \* -------------------------------------------------------------------------- */
 
 use super::{
-    cgroups::Cgroup, nested_auraed::NestedAuraed, CellName, CellSpec, Cells,
-    CellsCache, CellsError, Result,
+    cgroups::{
+        Cgroup, CgroupSettings, CgroupStats, MemoryEvents, OomEvents,
+        OomWatcher,
+    },
+    nested_auraed::NestedAuraed,
+    CellAdoption, CellInfo, CellName, CellSpec, Cells, CellsCache, CellsError,
+    LifetimeStats, Result,
 };
 use client::AuraeSocket;
-use tracing::info;
+use nix::unistd::Pid;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, warn};
+
+/// How long the processes left in a cell are waited for when it is freed,
+/// before they are killed, unless the request says otherwise.
+pub const DEFAULT_FREE_TIMEOUT: Duration = Duration::from_secs(5);
 
 // TODO https://github.com/aurae-runtime/aurae/issues/199 &&
 //      aurae.io/signals, which is more accurate
@@ -28,10 +41,14 @@ macro_rules! do_free {
     (
         $self:ident,
         $nested_auraed_call:ident($($nested_auraed_call_arg:ident),*),
+        $drain:expr,
         $($children_call:ident($($children_call_arg:ident),*)),*
     ) => {{
-        if let CellState::Allocated { cgroup, nested_auraed, children } =
-            &mut $self.state
+        let mut escalated = false;
+
+        if let CellState::Allocated {
+            cgroup, nested_auraed, children, info, ..
+        } = &mut $self.state
         {
             $(children.$children_call($($children_call_arg),*));*;
 
@@ -44,21 +61,36 @@ macro_rules! do_free {
                     }
                 })?;
 
+            escalated = ($drain)(&*cgroup).map_err(|e| {
+                CellsError::FailedToFreeCell {
+                    cell_name: $self.cell_name.clone(),
+                    source: e,
+                }
+            })?;
+
             cgroup.delete().map_err(|e| CellsError::FailedToFreeCell {
                 cell_name: $self.cell_name.clone(),
                 source: e,
             })?;
+
+            if let Err(e) = info.remove() {
+                warn!(
+                    "failed to remove the info of cell {}: {e}",
+                    $self.cell_name
+                );
+            }
         }
 
         // set cell state to freed, independent of the current state
         $self.state = CellState::Freed;
 
-        Ok(())
+        Ok(escalated)
     }};
 }
 
-// We should not be able to change a cell after it has been created.
-// You must free the cell and create a new one if you want to change anything about the cell.
+// We should not be able to change a cell after it has been created, other than
+// the cgroup values [Cell::update] can apply in place.
+// You must free the cell and create a new one if you want to change anything else about the cell.
 // In order to facilitate that immutability:
 // NEVER MAKE THE FIELDS PUB (OF ANY KIND)
 #[derive(Debug)]
@@ -66,19 +98,92 @@ pub struct Cell {
     cell_name: CellName,
     spec: CellSpec,
     state: CellState,
+    oom_events: OomEvents,
+    /// Updated through shared references, as the cells are looked up in
+    /// the cache.
+    lifetime_stats: Mutex<LifetimeStats>,
 }
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 enum CellState {
     Unallocated,
-    Allocated { cgroup: Cgroup, nested_auraed: NestedAuraed, children: Cells },
+    Allocated {
+        cgroup: Cgroup,
+        nested_auraed: NestedAuraed,
+        children: Cells,
+        info: CellInfo,
+        // dropped, and thereby stopped, when the cell is freed
+        _oom_watcher: OomWatcher,
+    },
     Freed,
 }
 
 impl Cell {
-    pub fn new(cell_name: CellName, cell_spec: CellSpec) -> Self {
-        Self { cell_name, spec: cell_spec, state: CellState::Unallocated }
+    pub fn new(
+        cell_name: CellName,
+        cell_spec: CellSpec,
+        oom_events: OomEvents,
+    ) -> Self {
+        Self {
+            cell_name,
+            spec: cell_spec,
+            state: CellState::Unallocated,
+            oom_events,
+            lifetime_stats: Default::default(),
+        }
+    }
+
+    /// Creates an allocated [Cell] from a cell allocated by a previous
+    /// auraed, taking over its cgroup and nested auraed.
+    pub fn adopt(
+        cell_name: CellName,
+        adoption: CellAdoption,
+        oom_events: OomEvents,
+    ) -> Result<Self> {
+        let CellAdoption {
+            spec,
+            nested_auraed_pid,
+            nested_auraed_start_time,
+            client_socket,
+        } = adoption;
+
+        let cgroup = Cgroup::adopt(cell_name.clone()).ok_or_else(|| {
+            CellsError::CgroupNotFound { cell_name: cell_name.clone() }
+        })?;
+
+        let nested_auraed = NestedAuraed::adopt(
+            nested_auraed_pid,
+            nested_auraed_start_time,
+            client_socket,
+            spec.iso_ctl.clone(),
+        )
+        .map_err(|e| CellsError::FailedToAdoptCell {
+            cell_name: cell_name.clone(),
+            source: e,
+        })?;
+
+        info!("Adopted cell {cell_name}");
+
+        // The nested auraed already points its executables at the info
+        let info = CellInfo::new(&cell_name);
+        refresh_info(&info, &cell_name, &spec);
+
+        let oom_watcher = cgroup.watch_oom_kills(oom_events.clone());
+
+        Ok(Self {
+            cell_name: cell_name.clone(),
+            spec,
+            state: CellState::Allocated {
+                cgroup,
+                nested_auraed,
+                children: Cells::new(cell_name, oom_events.clone()),
+                info,
+                _oom_watcher: oom_watcher,
+            },
+            oom_events,
+            lifetime_stats: Default::default(),
+        })
     }
 
     /// Creates the underlying cgroup.
@@ -91,11 +196,45 @@ impl Cell {
 
         let name = self.cell_name.leaf().to_string();
 
-        let mut auraed = NestedAuraed::new(name, self.spec.iso_ctl.clone())
-            .map_err(|e| CellsError::FailedToAllocateCell {
+        // Written first, so that it is there for the first executable
+        let info = CellInfo::new(&self.cell_name);
+        info.write(&self.cell_name, &self.spec).map_err(|e| {
+            CellsError::FailedToAllocateCell {
                 cell_name: self.cell_name.clone(),
                 source: e,
-            })?;
+            }
+        })?;
+
+        let executable_cgroups = if self.spec.per_executable_accounting {
+            let path = Cgroup::executables_path(&self.cell_name);
+            if path.is_none() {
+                warn!(
+                    "cell '{}' can't account for its executables apart on cgroup v1",
+                    self.cell_name
+                );
+            }
+            path
+        } else {
+            None
+        };
+
+        let mut auraed = match NestedAuraed::new(
+            name,
+            self.spec.iso_ctl.clone(),
+            info.dir(),
+            &self.spec.reserved_ports,
+            self.spec.user_namespace.as_ref(),
+            executable_cgroups.as_deref(),
+        ) {
+            Ok(auraed) => auraed,
+            Err(e) => {
+                let _best_effort = info.remove();
+                return Err(CellsError::FailedToAllocateCell {
+                    cell_name: self.cell_name.clone(),
+                    source: e,
+                });
+            }
+        };
 
         let pid = auraed.pid();
 
@@ -107,6 +246,7 @@ impl Cell {
             Ok(cgroup) => cgroup,
             Err(e) => {
                 let _best_effort = auraed.kill();
+                let _best_effort = info.remove();
                 return Err(CellsError::AbortedAllocateCell {
                     cell_name: self.cell_name.clone(),
                     source: e,
@@ -117,6 +257,7 @@ impl Cell {
         if let Err(e) = cgroup.add_task(pid) {
             let _best_effort = auraed.kill();
             let _best_effort = cgroup.delete();
+            let _best_effort = info.remove();
 
             return Err(CellsError::AbortedAllocateCell {
                 cell_name: self.cell_name.clone(),
@@ -126,30 +267,152 @@ impl Cell {
 
         info!("Attach nested Auraed pid {} to cgroup {}", pid, self.cell_name);
 
+        let oom_watcher = cgroup.watch_oom_kills(self.oom_events.clone());
+
         self.state = CellState::Allocated {
             cgroup,
             nested_auraed: auraed,
-            children: Cells::new(self.cell_name.clone()),
+            children: Cells::new(
+                self.cell_name.clone(),
+                self.oom_events.clone(),
+            ),
+            info,
+            _oom_watcher: oom_watcher,
+        };
+
+        Ok(())
+    }
+
+    /// Applies the changes from the spec of the [Cell] to `spec` to its
+    /// cgroup, and makes `spec` the spec of the [Cell].
+    ///
+    /// Fails with [CellsError::ImmutableCellChange], without changing
+    /// anything, if any of the changes can not be applied in place
+    /// (see [CellSpec::diff]).
+    pub fn update(&mut self, spec: CellSpec) -> Result<()> {
+        let CellState::Allocated { cgroup, info, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            });
         };
 
+        let immutable: Vec<_> = self
+            .spec
+            .diff(&spec)
+            .into_iter()
+            .filter(|change| !change.mutable)
+            .collect();
+        if !immutable.is_empty() {
+            return Err(CellsError::ImmutableCellChange {
+                cell_name: self.cell_name.clone(),
+                changes: immutable,
+            });
+        }
+
+        cgroup.update(&spec.cgroup_spec).map_err(|e| {
+            CellsError::FailedToUpdateCell {
+                cell_name: self.cell_name.clone(),
+                source: e,
+            }
+        })?;
+
+        info!("Updated cell {}", self.cell_name);
+
+        refresh_info(info, &self.cell_name, &spec);
+        self.spec = spec;
         Ok(())
     }
 
+    /// Writes `settings` to the cgroup of the [Cell], without checking them
+    /// against its spec, and returns the `memory.events` counted meanwhile.
+    /// The spec is updated with the values it has in common with `settings`.
+    pub fn write_settings(
+        &mut self,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents> {
+        let CellState::Allocated { cgroup, info, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            });
+        };
+
+        let memory_events = cgroup.write_settings(settings).map_err(|e| {
+            CellsError::FailedToUpdateCell {
+                cell_name: self.cell_name.clone(),
+                source: e,
+            }
+        })?;
+
+        settings.merge_into(&mut self.spec.cgroup_spec);
+        refresh_info(info, &self.cell_name, &self.spec);
+        Ok(memory_events)
+    }
+
     /// Broadcasts a graceful shutdown signal to all [NestedAuraed] and
     /// deletes the underlying cgroup and all descendants.
     ///
+    /// Nested cells are only freed if `recursive`, in which case they are
+    /// freed leaf-first. If a nested cell fails to free, the nested cells
+    /// freed before it stay freed, and this [Cell] is left allocated.
+    ///
+    /// Processes left in the cgroup once the [NestedAuraed] exited, such as
+    /// the descendants of stopped executables, are waited for up to `timeout`
+    /// and then killed. Returns true if any were killed, in this [Cell] or in
+    /// a nested cell freed with it.
+    ///
     /// The [Cell::state] will be set to [CellState::Freed] regardless of it's state prior to this call.
     ///
     /// A [Cell] should never be reused once in the [CellState::Freed] state.
-    pub fn free(&mut self) -> Result<()> {
-        do_free!(self, shutdown(), broadcast_free())
+    pub fn free(&mut self, recursive: bool, timeout: Duration) -> Result<bool> {
+        // Nested cells share the timeout rather than each waiting for it
+        let deadline = Instant::now() + timeout;
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        let mut escalated = false;
+
+        if !recursive {
+            self.check_no_nested_cells()?;
+        } else if let CellState::Allocated { children, .. } = &mut self.state {
+            for child in nested_cell_names(children) {
+                escalated |= children.free(&child, true, remaining())?;
+            }
+        }
+
+        let timeout = remaining();
+        let escalated_here = do_free!(
+            self,
+            shutdown(),
+            |cgroup: &Cgroup| cgroup.drain(remaining()),
+            broadcast_free(timeout)
+        )?;
+
+        Ok(escalated || escalated_here)
+    }
+
+    /// Errors with [CellsError::CellHasNestedCells] if the [Cell] has any
+    /// allocated nested cells.
+    pub fn check_no_nested_cells(&self) -> Result<()> {
+        let CellState::Allocated { children, .. } = &self.state else {
+            return Ok(());
+        };
+
+        let nested_cells = nested_cell_names(children);
+        if nested_cells.is_empty() {
+            return Ok(());
+        }
+
+        Err(CellsError::CellHasNestedCells {
+            cell_name: self.cell_name.clone(),
+            nested_cells,
+        })
     }
 
     /// Sends a [SIGKILL] to the [NestedAuraed], and deletes the underlying cgroup.
     /// The [Cell::state] will be set to [CellState::Freed] regardless of it's state prior to this call.
     /// A [Cell] should never be reused once in the [CellState::Freed] state.
     pub fn kill(&mut self) -> Result<()> {
-        do_free!(self, kill(), broadcast_kill())
+        let _escalated =
+            do_free!(self, kill(), |_: &Cgroup| Ok(false), broadcast_kill())?;
+        Ok(())
     }
 
     pub fn client_socket(&self) -> Result<AuraeSocket> {
@@ -162,6 +425,56 @@ impl Cell {
         Ok(nested_auraed.client_socket.clone())
     }
 
+    /// Returns the host [Pid] of the [Cell]'s nested auraed.
+    pub fn nested_auraed_pid(&self) -> Result<Pid> {
+        let CellState::Allocated { nested_auraed, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            });
+        };
+
+        Ok(nested_auraed.pid())
+    }
+
+    /// Returns when the [Cell]'s nested auraed started, in clock ticks since
+    /// boot.
+    pub fn nested_auraed_start_time(&self) -> Result<u64> {
+        let CellState::Allocated { nested_auraed, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            });
+        };
+
+        Ok(nested_auraed.start_time())
+    }
+
+    /// Returns the [AuraeSocket] of the [Cell] and of all its allocated
+    /// nested cells, with nested cells before their parents.
+    pub fn client_sockets_recursive(
+        &self,
+    ) -> Result<Vec<(CellName, AuraeSocket)>> {
+        let CellState::Allocated { nested_auraed, children, .. } = &self.state
+        else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            });
+        };
+
+        let mut sockets: Vec<_> = children
+            .get_all(|child| child.client_sockets_recursive())?
+            .into_iter()
+            .filter_map(|res| res.ok())
+            .flatten()
+            .collect();
+
+        sockets.push((
+            self.cell_name.clone(),
+            nested_auraed.client_socket.clone(),
+        ));
+
+        Ok(sockets)
+    }
+
     /// Returns the [CellName] of the [Cell]
     pub fn name(&self) -> &CellName {
         &self.cell_name
@@ -179,6 +492,65 @@ impl Cell {
 
         Some(cgroup.v2())
     }
+
+    /// Reads the current resource usage of the [Cell] from its cgroup.
+    pub fn stats(&self) -> Result<CgroupStats> {
+        let CellState::Allocated { cgroup, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            })
+        };
+
+        cgroup.stats().map_err(|e| CellsError::FailedToReadStats {
+            cell_name: self.cell_name.clone(),
+            source: e,
+        })
+    }
+
+    /// The usage of the [Cell] since it was allocated, or adopted.
+    pub fn lifetime_stats(&self) -> LifetimeStats {
+        self.lifetime_stats.lock().expect("lifetime stats lock").clone()
+    }
+
+    /// Records the start of an executable in the [Cell].
+    pub fn record_start(&self, executable_name: &str) {
+        self.lifetime_stats
+            .lock()
+            .expect("lifetime stats lock")
+            .record_start(executable_name, SystemTime::now());
+    }
+
+    /// Records the stop of an executable in the [Cell], and samples its
+    /// cgroup for the usage of the executable to be accounted for.
+    pub fn record_stop(&self) -> Result<()> {
+        self.lifetime_stats.lock().expect("lifetime stats lock").record_stop();
+        self.sample_lifetime_stats()
+    }
+
+    /// Samples the CPU time and OOM kills of the [Cell] from its cgroup.
+    pub fn sample_lifetime_stats(&self) -> Result<()> {
+        let stats = self.stats()?;
+        self.lifetime_stats
+            .lock()
+            .expect("lifetime stats lock")
+            .sample(&stats, SystemTime::now());
+        Ok(())
+    }
+
+    /// The directory the stats of the cell are read from, see
+    /// [Cgroup::stats_path].
+    pub fn stats_path(&self) -> Result<PathBuf> {
+        let CellState::Allocated { cgroup, .. } = &self.state else {
+            return Err(CellsError::CellNotAllocated {
+                cell_name: self.cell_name.clone(),
+            })
+        };
+
+        cgroup.stats_path().map_err(|e| CellsError::FailedToReadStats {
+            cell_name: self.cell_name.clone(),
+            source: e,
+        })
+    }
 }
 
 impl CellsCache for Cell {
@@ -194,12 +566,69 @@ impl CellsCache for Cell {
         children.allocate(cell_name, cell_spec)
     }
 
-    fn free(&mut self, cell_name: &CellName) -> Result<()> {
+    fn adopt(
+        &mut self,
+        cell_name: CellName,
+        adoption: CellAdoption,
+    ) -> Result<&Cell> {
+        let CellState::Allocated { children, .. } = &mut self.state else {
+            return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
+        };
+
+        children.adopt(cell_name, adoption)
+    }
+
+    fn update(
+        &mut self,
+        cell_name: &CellName,
+        cell_spec: CellSpec,
+    ) -> Result<()> {
         let CellState::Allocated { children, .. } = &mut self.state else {
             return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
         };
 
-        children.free(cell_name)
+        children.update(cell_name, cell_spec)
+    }
+
+    fn write_settings(
+        &mut self,
+        cell_name: &CellName,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents> {
+        let CellState::Allocated { children, .. } = &mut self.state else {
+            return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
+        };
+
+        children.write_settings(cell_name, settings)
+    }
+
+    fn free(
+        &mut self,
+        cell_name: &CellName,
+        recursive: bool,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let CellState::Allocated { children, .. } = &mut self.state else {
+            return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
+        };
+
+        children.free(cell_name, recursive, timeout)
+    }
+
+    fn take(&mut self, cell_name: &CellName, recursive: bool) -> Result<Cell> {
+        let CellState::Allocated { children, .. } = &mut self.state else {
+            return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
+        };
+
+        children.take(cell_name, recursive)
+    }
+
+    fn restore(&mut self, cell_name: &CellName, cell: Cell) -> Result<()> {
+        let CellState::Allocated { children, .. } = &mut self.state else {
+            return Err(CellsError::CellNotAllocated { cell_name: self.cell_name.clone() })
+        };
+
+        children.restore(cell_name, cell)
     }
 
     fn get<F, R>(&mut self, cell_name: &CellName, f: F) -> Result<R>
@@ -224,12 +653,12 @@ impl CellsCache for Cell {
         children.get_all(f)
     }
 
-    fn broadcast_free(&mut self) {
+    fn broadcast_free(&mut self, timeout: Duration) {
         let CellState::Allocated { children, .. } = &mut self.state else {
             return;
         };
 
-        children.broadcast_free()
+        children.broadcast_free(timeout)
     }
 
     fn broadcast_kill(&mut self) {
@@ -241,6 +670,26 @@ impl CellsCache for Cell {
     }
 }
 
+/// Rewrites the info of a cell whose cgroup already changed, so failing to
+/// is only logged.
+fn refresh_info(info: &CellInfo, cell_name: &CellName, spec: &CellSpec) {
+    if let Err(e) = info.write(cell_name, spec) {
+        warn!("failed to write the info of cell {cell_name}: {e}");
+    }
+}
+
+/// Returns the names of the allocated cells in `children`, sorted.
+fn nested_cell_names(children: &Cells) -> Vec<CellName> {
+    let mut names: Vec<_> = children
+        .get_all(|child| Ok(child.name().clone()))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .collect();
+    names.sort();
+    names
+}
+
 impl Drop for Cell {
     /// During normal behavior, cells are freed before being dropped,
     /// but cache reconciliation may result in a drop in other circumstances.
@@ -266,13 +715,19 @@ mod tests {
         let _ = AURAED_RUNTIME.set(AuraedRuntime::default());
 
         let cell_name = CellName::random_for_tests();
-        let mut cell = Cell::new(cell_name, CellSpec::new_for_tests());
+        let mut cell = Cell::new(
+            cell_name,
+            CellSpec::new_for_tests(),
+            OomEvents::default(),
+        );
         assert!(matches!(cell.state, CellState::Unallocated));
 
         cell.allocate().expect("failed to allocate");
         assert!(matches!(cell.state, CellState::Allocated { .. }));
 
-        cell.free().expect("failed to free");
+        let escalated =
+            cell.free(false, DEFAULT_FREE_TIMEOUT).expect("failed to free");
+        assert!(!escalated);
         assert!(matches!(cell.state, CellState::Freed));
 
         // Calling allocate again should do nothing
@@ -0,0 +1,172 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! A read-only directory describing a cell, for the processes in it to read
+//! without calling auraed.
+
+use super::{CellName, CellSpec};
+use crate::AURAED_RUNTIME;
+use std::fs::{self, DirBuilder, Permissions};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Set, for every executable started in a cell, to its [CellInfo] directory.
+pub const CELL_INFO_ENV: &str = "AURAE_CELL_INFO";
+
+/// The period `cpu.max` has if the cell does not set one.
+const DEFAULT_CPU_PERIOD: u64 = 100000;
+
+/// The `<runtime_dir>/cells/<cell name>/info` directory of a cell, holding:
+///
+/// * `cell_name`: the name of the cell
+/// * `cpu.max`: the cpu quota and period, as in the cgroup
+/// * `cpuset.cpus`: the cpus of the cell, empty if inherited
+/// * `memory.max`: the memory limit, as in the cgroup
+/// * `variables.json`: the variables of the cell
+///
+/// It is readable, but not writable, by any user, so that executables can
+/// read it after dropping privileges.
+#[derive(Debug, Clone)]
+pub struct CellInfo {
+    dir: PathBuf,
+}
+
+impl CellInfo {
+    pub fn new(cell_name: &CellName) -> Self {
+        let runtime_dir = &AURAED_RUNTIME.get().expect("runtime").runtime_dir;
+        Self::in_dir(&runtime_dir.join("cells"), cell_name)
+    }
+
+    fn in_dir(cells_dir: &Path, cell_name: &CellName) -> Self {
+        Self { dir: cells_dir.join(cell_name.as_inner()).join("info") }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes the files describing `spec`. Each file is replaced atomically,
+    /// so readers see either the previous or the new value.
+    pub fn write(
+        &self,
+        cell_name: &CellName,
+        spec: &CellSpec,
+    ) -> io::Result<()> {
+        DirBuilder::new().recursive(true).mode(0o755).create(&self.dir)?;
+
+        let cpu = spec.cgroup_spec.cpu.as_ref();
+        let cpu_max = cpu
+            .and_then(|cpu| cpu.max)
+            .filter(|max| **max > 0)
+            .map_or_else(|| "max".to_string(), |max| max.to_string());
+        let cpu_period =
+            cpu.and_then(|cpu| cpu.period).unwrap_or(DEFAULT_CPU_PERIOD);
+        let cpus = spec
+            .cgroup_spec
+            .cpuset
+            .as_ref()
+            .and_then(|cpuset| cpuset.cpus.as_deref())
+            .unwrap_or_default();
+        let memory_max = spec
+            .cgroup_spec
+            .memory
+            .as_ref()
+            .and_then(|memory| memory.max)
+            .map_or_else(|| "max".to_string(), |max| max.to_string());
+        let variables = serde_json::to_string_pretty(&spec.variables)
+            .expect("variables serialize to json");
+
+        self.write_file("cell_name", &cell_name.to_string())?;
+        self.write_file("cpu.max", &format!("{cpu_max} {cpu_period}"))?;
+        self.write_file("cpuset.cpus", cpus)?;
+        self.write_file("memory.max", &memory_max)?;
+        self.write_file("variables.json", &variables)
+    }
+
+    fn write_file(&self, name: &str, contents: &str) -> io::Result<()> {
+        let path = self.dir.join(name);
+        let tmp_path = self.dir.join(format!(".{name}.tmp"));
+
+        fs::write(&tmp_path, format!("{contents}\n"))?;
+        fs::set_permissions(&tmp_path, Permissions::from_mode(0o644))?;
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Removes the directory of the cell, with the directories of any nested
+    /// cells left in it.
+    pub fn remove(&self) -> io::Result<()> {
+        let cell_dir = self.dir.parent().expect("cell directory");
+        match fs::remove_dir_all(cell_dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::cells::cgroups::Limit;
+
+    fn cells_dir() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ae-test-cell-info-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn read(info: &CellInfo, name: &str) -> String {
+        fs::read_to_string(info.dir().join(name)).expect("failed to read")
+    }
+
+    #[test]
+    fn test_write_describes_the_cell() {
+        let cells_dir = cells_dir();
+        let cell_name = CellName::from("ae-parent/ae-child");
+        let info = CellInfo::in_dir(&cells_dir, &cell_name);
+
+        let mut spec = CellSpec::new_for_tests();
+        let _ = spec.variables.insert("port".into(), "8080".into());
+        info.write(&cell_name, &spec).expect("failed to write");
+
+        assert_eq!(info.dir(), cells_dir.join("ae-parent/ae-child/info"));
+        assert_eq!(read(&info, "cell_name"), "ae-parent/ae-child\n");
+        assert_eq!(read(&info, "cpu.max"), "max 100000\n");
+        assert_eq!(read(&info, "cpuset.cpus"), "\n");
+        assert_eq!(read(&info, "memory.max"), "1000000\n");
+        let variables: serde_json::Value =
+            serde_json::from_str(&read(&info, "variables.json")).unwrap();
+        assert_eq!(variables, serde_json::json!({ "port": "8080" }));
+
+        let mode = fs::metadata(info.dir().join("cpu.max"))
+            .expect("failed to stat")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o644);
+
+        // Rewriting replaces the values
+        let cpu = spec.cgroup_spec.cpu.as_mut().expect("cpu");
+        cpu.max = Some(Limit::new(50000));
+        info.write(&cell_name, &spec).expect("failed to rewrite");
+        assert_eq!(read(&info, "cpu.max"), "50000 100000\n");
+
+        info.remove().expect("failed to remove");
+        assert!(!cells_dir.join("ae-parent/ae-child").exists());
+        assert!(cells_dir.join("ae-parent").exists());
+        // Removing twice is fine
+        info.remove().expect("failed to remove again");
+
+        fs::remove_dir_all(cells_dir).expect("failed to clean up");
+    }
+}
@@ -19,6 +19,10 @@ use std::path::{Path, PathBuf};
 use validation::{ValidatedField, ValidationError};
 
 pub const SEPARATOR: char = '/';
+/// The maximum number of components, i.e. levels of nesting, of a cell name.
+pub const MAX_DEPTH: usize = 8;
+/// The maximum length of a cell name in bytes, separators included.
+pub const MAX_LENGTH: u64 = 255;
 
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct CellName(PathBuf);
@@ -99,9 +103,29 @@ impl ValidatedField<String> for CellName {
         // Opting to be forgiving of paths that start or end with SEPARATOR
         let input = input.trim_matches(SEPARATOR);
 
-        let input = input
-            .split(SEPARATOR)
-            .flat_map(|component| {
+        validation::maximum_length(
+            input.as_bytes(),
+            MAX_LENGTH,
+            "bytes",
+            field_name,
+            parent_name,
+        )?;
+
+        let components: Vec<_> = input.split(SEPARATOR).collect();
+
+        validation::maximum_value(
+            components.len(),
+            MAX_DEPTH,
+            "levels",
+            field_name,
+            parent_name,
+        )?;
+
+        // Components become cgroup directories, so the regex must also
+        // reject '.', '..' and empty components.
+        let input = components
+            .into_iter()
+            .map(|component| {
                 // NOTE: We must always reserve '/' (separator) and '_' (name of leaf cgroup)
                 validation::allow_regex(
                     component,
@@ -112,7 +136,7 @@ impl ValidatedField<String> for CellName {
 
                 Ok::<_, ValidationError>(component)
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Self(input))
     }
@@ -136,6 +160,79 @@ mod tests {
     use super::*;
     use std::str::FromStr;
 
+    fn validate(input: &str) -> Result<CellName, ValidationError> {
+        CellName::validate(Some(input.into()), "test", None)
+    }
+
+    #[test]
+    fn test_validate_nested() {
+        let cell_name = validate("parent-cell/child-cell").unwrap();
+        assert_eq!(cell_name.as_inner(), Path::new("parent-cell/child-cell"));
+    }
+
+    #[test]
+    fn test_validate_rejects_parent_components() {
+        for input in ["..", "../etc", "cell/..", "cell/../../etc", "cell/./x"] {
+            assert!(
+                matches!(
+                    validate(input),
+                    Err(ValidationError::AllowRegexViolation { .. })
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_leading_separator_stays_relative() {
+        let cell_name = validate("/etc").unwrap();
+        assert_eq!(cell_name.as_inner(), Path::new("etc"));
+
+        assert!(validate("/").is_err());
+        assert!(validate("cell//child").is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_nul() {
+        assert!(matches!(
+            validate("cell\0/child"),
+            Err(ValidationError::AllowRegexViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_leaf_cgroup_name() {
+        assert!(validate("cell/_").is_err());
+    }
+
+    #[test]
+    fn test_validate_maximum_length() {
+        // 4 components of 63 bytes, and 3 separators
+        let input = vec!["a".repeat(63); 4].join("/");
+        assert_eq!(input.len() as u64, MAX_LENGTH);
+        assert!(validate(&input).is_ok());
+
+        let input = format!("{input}a");
+        assert!(matches!(
+            validate(&input),
+            Err(ValidationError::Maximum { .. })
+        ));
+
+        assert!(validate(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_validate_maximum_depth() {
+        let input = ["a"; MAX_DEPTH].join("/");
+        assert!(validate(&input).is_ok());
+
+        let input = format!("{input}/a");
+        assert!(matches!(
+            validate(&input),
+            Err(ValidationError::Maximum { .. })
+        ));
+    }
+
     #[test]
     fn test_leaf_top_level() {
         const CELL_NAME: &str = "top-level-cell";
@@ -13,9 +13,13 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use super::{cgroups::Cgroup, Cell, CellName, CellSpec, CellsError, Result};
+use super::{
+    cgroups::{Cgroup, CgroupSettings, MemoryEvents, OomEvents},
+    Cell, CellAdoption, CellName, CellSpec, CellsError, Result,
+};
 use crate::cells::cell_service::cells::cells_cache::CellsCache;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tracing::warn;
 
 macro_rules! proxy_if_needed {
@@ -48,6 +52,7 @@ type Cache = HashMap<CellName, Cell>;
 pub struct Cells {
     parent: Option<CellName>,
     cache: Cache,
+    oom_events: OomEvents,
 }
 
 // TODO: add to the impl
@@ -57,8 +62,13 @@ pub struct Cells {
 // [ ] Get Cgroup and pids from executable_name
 
 impl Cells {
-    pub fn new(parent: CellName) -> Self {
-        Self { parent: Some(parent), ..Self::default() }
+    pub fn new(parent: CellName, oom_events: OomEvents) -> Self {
+        Self { parent: Some(parent), cache: Default::default(), oom_events }
+    }
+
+    /// Returns the channel the OOM kills of all cells in the tree are sent on.
+    pub fn oom_events(&self) -> &OomEvents {
+        &self.oom_events
     }
 
     fn allocate(
@@ -85,10 +95,10 @@ impl Cells {
                 warn!("Found cached cell ('{cell_name}') without cgroup. Did you forget to call free on the cell?");
             }
 
-            let cell = self
-                .cache
-                .entry(cell_name.clone())
-                .or_insert_with(|| Cell::new(cell_name, cell_spec));
+            let cell =
+                self.cache.entry(cell_name.clone()).or_insert_with(|| {
+                    Cell::new(cell_name, cell_spec, self.oom_events.clone())
+                });
 
             // TODO: Should we remove the cell from the cache here if the call to allocate fails?
             cell.allocate()?;
@@ -97,11 +107,86 @@ impl Cells {
         })
     }
 
-    fn free(&mut self, cell_name: &CellName) -> Result<()> {
-        proxy_if_needed!(self, cell_name, free(cell_name), {
+    fn adopt(
+        &mut self,
+        cell_name: CellName,
+        adoption: CellAdoption,
+    ) -> Result<&Cell> {
+        proxy_if_needed!(self, cell_name, adopt(cell_name, adoption), {
+            if self.cache.contains_key(&cell_name) {
+                return Err(CellsError::CellExists { cell_name });
+            }
+
+            let cell = Cell::adopt(
+                cell_name.clone(),
+                adoption,
+                self.oom_events.clone(),
+            )?;
+
+            Ok(self.cache.entry(cell_name).or_insert(cell))
+        })
+    }
+
+    fn update(
+        &mut self,
+        cell_name: &CellName,
+        cell_spec: CellSpec,
+    ) -> Result<()> {
+        proxy_if_needed!(self, cell_name, update(cell_name, cell_spec), {
+            self.get_mut(cell_name, |cell| cell.update(cell_spec))
+        })
+    }
+
+    fn write_settings(
+        &mut self,
+        cell_name: &CellName,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents> {
+        proxy_if_needed!(
+            self,
+            cell_name,
+            write_settings(cell_name, settings),
+            self.get_mut(cell_name, |cell| cell.write_settings(settings))
+        )
+    }
+
+    fn free(
+        &mut self,
+        cell_name: &CellName,
+        recursive: bool,
+        timeout: Duration,
+    ) -> Result<bool> {
+        proxy_if_needed!(
+            self,
+            cell_name,
+            free(cell_name, recursive, timeout),
+            {
+                self.handle_cgroup_does_not_exist(cell_name)?;
+                let escalated = self
+                    .get_mut(cell_name, |cell| cell.free(recursive, timeout))?;
+                let _ = self.cache.remove(cell_name);
+                Ok(escalated)
+            }
+        )
+    }
+
+    fn take(&mut self, cell_name: &CellName, recursive: bool) -> Result<Cell> {
+        proxy_if_needed!(self, cell_name, take(cell_name, recursive), {
             self.handle_cgroup_does_not_exist(cell_name)?;
-            self.get_mut(cell_name, |cell| cell.free())?;
-            let _ = self.cache.remove(cell_name);
+            if !recursive {
+                if let Some(cell) = self.cache.get(cell_name) {
+                    cell.check_no_nested_cells()?;
+                }
+            }
+            self.cache.remove(cell_name).ok_or_else(|| {
+                CellsError::CgroupIsNotACell { cell_name: cell_name.clone() }
+            })
+        })
+    }
+
+    fn restore(&mut self, cell_name: &CellName, cell: Cell) -> Result<()> {
+        proxy_if_needed!(self, cell_name, restore(cell_name, cell), {
+            let _ = self.cache.insert(cell_name.clone(), cell);
             Ok(())
         })
     }
@@ -193,8 +278,13 @@ impl Cells {
         Err(CellsError::CgroupNotFound { cell_name: cell_name.clone() })
     }
 
-    fn broadcast_free(&mut self) {
-        let freed_cells = self.do_broadcast(|cell| cell.free());
+    fn broadcast_free(&mut self, timeout: Duration) {
+        // All the cells share the timeout rather than each waiting for it
+        let deadline = Instant::now() + timeout;
+        let freed_cells = self.do_broadcast(|cell| {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            cell.free(true, timeout).map(|_| ())
+        });
 
         for cell_name in freed_cells {
             let _ = self.cache.remove(&cell_name);
@@ -238,8 +328,37 @@ impl CellsCache for Cells {
         self.allocate(cell_name, cell_spec)
     }
 
-    fn free(&mut self, cell_name: &CellName) -> Result<()> {
-        self.free(cell_name)
+    fn adopt(
+        &mut self,
+        cell_name: CellName,
+        adoption: CellAdoption,
+    ) -> Result<&Cell> {
+        self.adopt(cell_name, adoption)
+    }
+
+    fn update(
+        &mut self,
+        cell_name: &CellName,
+        cell_spec: CellSpec,
+    ) -> Result<()> {
+        self.update(cell_name, cell_spec)
+    }
+
+    fn write_settings(
+        &mut self,
+        cell_name: &CellName,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents> {
+        self.write_settings(cell_name, settings)
+    }
+
+    fn free(
+        &mut self,
+        cell_name: &CellName,
+        recursive: bool,
+        timeout: Duration,
+    ) -> Result<bool> {
+        self.free(cell_name, recursive, timeout)
     }
 
     fn get<F, R>(&mut self, cell_name: &CellName, f: F) -> Result<R>
@@ -256,8 +375,8 @@ impl CellsCache for Cells {
         self.get_all(f)
     }
 
-    fn broadcast_free(&mut self) {
-        self.broadcast_free()
+    fn broadcast_free(&mut self, timeout: Duration) {
+        self.broadcast_free(timeout)
     }
 
     fn broadcast_kill(&mut self) {
@@ -268,6 +387,7 @@ impl CellsCache for Cells {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cells::cell_service::cells::DEFAULT_FREE_TIMEOUT;
     use crate::{AuraedRuntime, AURAED_RUNTIME};
     use test_helpers::*;
 
@@ -364,7 +484,10 @@ mod tests {
             .allocate(cell_name.clone(), cell)
             .expect("failed to allocate");
 
-        cells.free(&cell_name).expect("failed to free");
+        let escalated = cells
+            .free(&cell_name, false, DEFAULT_FREE_TIMEOUT)
+            .expect("failed to free");
+        assert!(!escalated);
         assert!(cells.cache.is_empty());
     }
 
@@ -376,7 +499,56 @@ mod tests {
         let cell_name_in = CellName::random_for_tests();
 
         assert!(matches!(
-            cells.free(&cell_name_in),
+            cells.free(&cell_name_in, false, DEFAULT_FREE_TIMEOUT),
+            Err(CellsError::CellNotFound { cell_name }) if cell_name == cell_name_in
+        ));
+    }
+
+    #[test]
+    fn test_take_and_restore() {
+        skip_if_not_root!("test_take_and_restore");
+        // Docker's seccomp security profile (https://docs.docker.com/engine/security/seccomp/) blocks clone
+        skip_if_seccomp!("test_take_and_restore");
+
+        let _ = AURAED_RUNTIME.set(AuraedRuntime::default());
+
+        let mut cells = Cells::default();
+        let cell_name = CellName::random_for_tests();
+        let cell = CellSpec::new_for_tests();
+        let _ = cells
+            .allocate(cell_name.clone(), cell)
+            .expect("failed to allocate");
+
+        let cell = cells.take(&cell_name, false).expect("failed to take");
+        assert!(cells.cache.is_empty());
+
+        // Taken, it is neither a cell to allocate nor to get
+        assert!(matches!(
+            cells.allocate(cell_name.clone(), CellSpec::new_for_tests()),
+            Err(CellsError::CgroupIsNotACell { .. })
+        ));
+        assert!(matches!(
+            cells.get(&cell_name, |_cell| Ok(())),
+            Err(CellsError::CgroupIsNotACell { .. })
+        ));
+
+        cells.restore(&cell_name, cell).expect("failed to restore");
+        cells.get(&cell_name, |_cell| Ok(())).expect("failed to get");
+
+        let mut cell = cells.take(&cell_name, false).expect("failed to take");
+        let escalated =
+            cell.free(false, DEFAULT_FREE_TIMEOUT).expect("failed to free");
+        assert!(!escalated);
+    }
+
+    #[test]
+    fn test_take_missing_is_error() {
+        let mut cells = Cells::default();
+
+        let cell_name_in = CellName::random_for_tests();
+
+        assert!(matches!(
+            cells.take(&cell_name_in, false),
             Err(CellsError::CellNotFound { cell_name }) if cell_name == cell_name_in
         ));
     }
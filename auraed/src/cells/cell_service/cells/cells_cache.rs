@@ -42,7 +42,11 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
-use super::{Cell, CellName, CellSpec, Result};
+use super::{
+    cgroups::{CgroupSettings, MemoryEvents},
+    Cell, CellAdoption, CellName, CellSpec, Result,
+};
+use std::time::Duration;
 
 pub trait CellsCache {
     /// Calls [Cell::allocate] on a new [Cell] and adds it to it's cache with key [CellName].
@@ -57,15 +61,79 @@ pub trait CellsCache {
         cell_spec: CellSpec,
     ) -> Result<&Cell>;
 
+    /// Adds a [Cell] allocated by a previous auraed to the cache.
+    /// The parent of the cell must have been adopted first.
+    ///
+    /// # Errors
+    /// * If cell exists -> [CellsError::CellExists]
+    /// * If cgroup does not exist -> [CellsError::CgroupNotFound]
+    /// * If the nested auraed is gone -> [CellsError::FailedToAdoptCell]
+    fn adopt(
+        &mut self,
+        cell_name: CellName,
+        adoption: CellAdoption,
+    ) -> Result<&Cell>;
+
+    /// Calls [Cell::update] on a [Cell] in the cache.
+    ///
+    /// # Errors
+    /// * If cell is not cached and cgroup does not exist -> [CellsError::CellNotFound]
+    /// * If a change can not be applied in place -> [CellsError::ImmutableCellChange]
+    /// * If cell fails to update (see [Cell::update])
+    fn update(
+        &mut self,
+        cell_name: &CellName,
+        cell_spec: CellSpec,
+    ) -> Result<()>;
+
+    /// Calls [Cell::write_settings] on a [Cell] in the cache.
+    ///
+    /// # Errors
+    /// * If cell is not cached and cgroup does not exist -> [CellsError::CellNotFound]
+    /// * If cell fails to write the settings (see [Cell::write_settings])
+    fn write_settings(
+        &mut self,
+        cell_name: &CellName,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents>;
+
     /// Calls [Cell::free] on a [Cell] and removes it from the cache.
+    /// If `recursive`, nested cells are freed first, leaf-first.
+    ///
+    /// Returns true if processes left in the cell, or in its nested cells,
+    /// had to be killed after waiting `timeout` for them (see [Cell::free]).
     ///
     /// # Errors
     /// * If cell is not cached and cgroup does not exist -> [CellsError::CellNotFound]
     /// * If cell is cached and cgroup does not exist -> [CellsError::CgroupNotFound]
     ///     - note: cell will be removed from cache
     /// * If cell is not cached and cgroup exists on fs -> [CellsError::CgroupIsNotACell]
+    /// * If cell has nested cells and is not freed recursively -> [CellsError::CellHasNestedCells]
     /// * If cell fails to free (see [Cell::free])
-    fn free(&mut self, cell_name: &CellName) -> Result<()>;
+    fn free(
+        &mut self,
+        cell_name: &CellName,
+        recursive: bool,
+        timeout: Duration,
+    ) -> Result<bool>;
+
+    /// Removes a [Cell] from the cache and returns it, for it to be freed
+    /// with [Cell::free] without holding the cache. A cell which fails to
+    /// free is returned to the cache with [CellsCache::restore].
+    ///
+    /// # Errors
+    /// * If cell is not cached and cgroup does not exist -> [CellsError::CellNotFound]
+    /// * If cell is cached and cgroup does not exist -> [CellsError::CgroupNotFound]
+    ///     - note: cell will be removed from cache
+    /// * If cell is not cached and cgroup exists on fs -> [CellsError::CgroupIsNotACell]
+    /// * If cell has nested cells and is not taken to be freed recursively -> [CellsError::CellHasNestedCells]
+    fn take(&mut self, cell_name: &CellName, recursive: bool) -> Result<Cell>;
+
+    /// Returns a [Cell] removed with [CellsCache::take] to the cache.
+    ///
+    /// # Errors
+    /// * If the parent of the cell is no longer cached -> [CellsError::CellNotFound]
+    fn restore(&mut self, cell_name: &CellName, cell: Cell) -> Result<()>;
 
     fn get<F, R>(&mut self, cell_name: &CellName, f: F) -> Result<R>
     where
@@ -76,8 +144,9 @@ pub trait CellsCache {
         F: Fn(&Cell) -> Result<R>;
 
     /// Calls [Cell::Free] on all cells in the cache, ignoring any errors.
+    /// Their processes are given up to `timeout` to exit before being killed.
     /// Successfully freed cells will be removed from the cache.
-    fn broadcast_free(&mut self);
+    fn broadcast_free(&mut self, timeout: Duration);
 
     /// Sends a [SIGKILL] to all Cells, ignoring any errors.
     fn broadcast_kill(&mut self);
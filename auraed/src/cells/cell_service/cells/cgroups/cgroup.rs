@@ -14,24 +14,42 @@
 \* -------------------------------------------------------------------------- */
 
 use crate::cells::cell_service::cells::{
-    cgroups::{CpuController, CpusetController, MemoryController},
+    cgroups::{
+        CgroupSettings, CpuController, CpusetController, MemoryController,
+        MemoryEvents, OomEvents, OomWatcher,
+    },
     CellName, CgroupSpec,
 };
 use libcgroups::common::{CgroupManager, ControllerOpt, DEFAULT_CGROUP_ROOT};
-use libcgroups::stats::Stats;
 use libcgroups::v2;
-use nix::unistd::Pid;
+use nix::unistd::{access, AccessFlags, Pid};
 use oci_spec::runtime::{
-    LinuxCpuBuilder, LinuxMemoryBuilder, LinuxResourcesBuilder,
+    LinuxCpuBuilder, LinuxMemoryBuilder, LinuxResources, LinuxResourcesBuilder,
 };
-use std::path::PathBuf;
+use std::os::fd::OwnedFd;
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+use std::{fs, io};
 
+use super::devices::bpf;
 use super::error::{CgroupsError, Result};
+use super::events;
+use super::executable_cgroup::is_executable_leaf;
+use super::mode::CgroupMode;
+use super::stats::CgroupStats;
+use super::v1;
+
+/// How long processes are waited for once killed with `cgroup.kill`.
+pub(super) const KILLED_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct Cgroup {
     cell_name: CellName,
+    v2: bool,
+    /// The device filter attached on cgroup v2, if the cell has one.
+    /// A cgroup that is adopted has it attached, but not this fd to it.
+    device_filter: Option<OwnedFd>,
 }
 
 impl Cgroup {
@@ -40,7 +58,19 @@ impl Cgroup {
         spec: CgroupSpec,
         nested_auraed_pid: Pid,
     ) -> Result<Self> {
-        let CgroupSpec { cpu, cpuset, memory } = spec;
+        let v2 = CgroupMode::current().is_v2();
+
+        check_under_root(&cell_name, v2)?;
+
+        if !v2 {
+            v1::create(
+                Path::new(DEFAULT_CGROUP_ROOT),
+                &cell_name,
+                &spec,
+                nested_auraed_pid,
+            )?;
+            return Ok(Self { cell_name, v2, device_filter: None });
+        }
 
         // Note: Cgroups v2 "no internal processes" rule.
         // Docs: https://man7.org/linux/man-pages/man7/cgroups.7.html
@@ -71,10 +101,339 @@ impl Cgroup {
             });
         }
 
-        let builder = LinuxResourcesBuilder::default();
+        let options = resources(&spec);
+        let options = ControllerOpt {
+            resources: &options,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        };
+
+        if let Err(e) = non_leaf.apply(&options) {
+            // try to remove, but ignore the error as the original error is more appropriate to return
+            // libcgroups takes care of killing any processes it finds
+            let _ = leaf.remove();
+            let _ = non_leaf.remove();
+            return Err(CgroupsError::CreateCgroup {
+                cell_name,
+                source: e.into(),
+            });
+        }
+
+        // The filter is attached to the non-leaf cgroup, so it applies to
+        // nested cells as well.
+        let device_filter = if spec.device_allow.is_empty() {
+            None
+        } else {
+            match bpf::attach(&non_leaf_path(&cell_name), &spec.device_allow) {
+                Ok(device_filter) => Some(device_filter),
+                Err(e) => {
+                    let _ = leaf.remove();
+                    let _ = non_leaf.remove();
+                    return Err(CgroupsError::CreateCgroup {
+                        cell_name,
+                        source: anyhow::Error::from(e)
+                            .context("failed to attach the device filter"),
+                    });
+                }
+            }
+        };
+
+        Ok(Self { cell_name, v2, device_filter })
+    }
+
+    /// Adopts the existing cgroup of a cell allocated by a previous auraed.
+    /// Returns [None] if the cgroup does not exist.
+    pub fn adopt(cell_name: CellName) -> Option<Self> {
+        let v2 = CgroupMode::current().is_v2();
+        Self::exists(&cell_name).then_some(Self {
+            cell_name,
+            v2,
+            device_filter: None,
+        })
+    }
+
+    pub fn add_task(&self, pid: Pid) -> Result<()> {
+        if !self.v2 {
+            return v1::add_task(
+                Path::new(DEFAULT_CGROUP_ROOT),
+                &self.cell_name,
+                pid,
+            );
+        }
+
+        let manager = v2::manager::Manager::new(
+            DEFAULT_CGROUP_ROOT.into(),
+            get_leaf_path(&self.cell_name),
+        )
+        .expect("valid cgroup");
+
+        manager.add_task(pid).map_err(|e| CgroupsError::AddTaskToCgroup {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        })
+    }
+
+    /// Applies the cpu, cpuset and memory values of `spec` to the existing
+    /// cgroup. Values that are not set in `spec` are left as they are.
+    pub fn update(&self, spec: &CgroupSpec) -> Result<()> {
+        if !self.v2 {
+            return v1::update(
+                Path::new(DEFAULT_CGROUP_ROOT),
+                &self.cell_name,
+                spec,
+            );
+        }
+
+        let non_leaf = v2::manager::Manager::new(
+            DEFAULT_CGROUP_ROOT.into(),
+            self.cell_name.clone().into_inner(),
+        )
+        .expect("valid cgroup");
+
+        let options = resources(spec);
+        let options = ControllerOpt {
+            resources: &options,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        };
+
+        non_leaf.apply(&options).map_err(|e| CgroupsError::UpdateCgroup {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        })
+    }
+
+    /// Writes `settings` to the cgroup, and returns the `memory.events`
+    /// counted while they were written. Only supported on cgroup v2.
+    pub fn write_settings(
+        &self,
+        settings: &CgroupSettings,
+    ) -> Result<MemoryEvents> {
+        if !self.v2 {
+            return Err(CgroupsError::Unsupported {
+                cell_name: self.cell_name.clone(),
+                feature: "update".into(),
+            });
+        }
+
+        let update_error = |e: io::Error| CgroupsError::UpdateCgroup {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        };
+
+        let path = non_leaf_path(&self.cell_name);
+
+        // Lowering the limits of a frozen cell could OOM kill processes that
+        // can not exit until the cell is thawed
+        if events::is_frozen(&path).map_err(update_error)? {
+            return Err(CgroupsError::Frozen {
+                cell_name: self.cell_name.clone(),
+            });
+        }
+
+        let before = MemoryEvents::read(&path).map_err(update_error)?;
+        settings.write(&path).map_err(update_error)?;
+        let after = MemoryEvents::read(&path).map_err(update_error)?;
+
+        Ok(after.since(&before))
+    }
+
+    /// Waits up to `timeout` for the processes of the cell, and of its nested
+    /// cells, to exit, then kills the ones left.
+    /// Returns true if any were left to be killed.
+    pub fn drain(&self, timeout: Duration) -> Result<bool> {
+        let map_err = |e: io::Error| CgroupsError::DeleteCgroup {
+            cell_name: self.cell_name.clone(),
+            source: anyhow::Error::from(e)
+                .context("failed to wait for the processes to exit"),
+        };
+
+        if !self.v2 {
+            let root = Path::new(DEFAULT_CGROUP_ROOT);
+            if v1::wait_empty(root, &self.cell_name, timeout)
+                .map_err(map_err)?
+            {
+                return Ok(false);
+            }
+
+            v1::kill_all(root, &self.cell_name).map_err(map_err)?;
+            return Ok(true);
+        }
+
+        let path = non_leaf_path(&self.cell_name);
+        if events::wait_unpopulated(&path, timeout).map_err(map_err)? {
+            return Ok(false);
+        }
+
+        // Without cgroup.kill (before Linux 5.14), the processes are killed
+        // one by one when the cgroup is deleted.
+        let kill = path.join("cgroup.kill");
+        if kill.exists() {
+            fs::write(&kill, "1").map_err(map_err)?;
+            let _ = events::wait_unpopulated(&path, KILLED_EXIT_TIMEOUT)
+                .map_err(map_err)?;
+        }
+
+        Ok(true)
+    }
+
+    pub fn delete(&self) -> Result<()> {
+        if !self.v2 {
+            return v1::delete(Path::new(DEFAULT_CGROUP_ROOT), &self.cell_name);
+        }
+
+        let leaf = v2::manager::Manager::new(
+            DEFAULT_CGROUP_ROOT.into(),
+            get_leaf_path(&self.cell_name),
+        )
+        .expect("valid cgroup");
+
+        leaf.remove().map_err(|e| CgroupsError::DeleteCgroup {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        })?;
+
+        // Left by a nested auraed that did not stop its executables
+        for leaf in executable_leaves(&self.cell_name).map_err(|e| {
+            CgroupsError::DeleteCgroup {
+                cell_name: self.cell_name.clone(),
+                source: e.into(),
+            }
+        })? {
+            fs::remove_dir(&leaf).map_err(|e| CgroupsError::DeleteCgroup {
+                cell_name: self.cell_name.clone(),
+                source: anyhow::Error::from(e).context(format!(
+                    "failed to remove the executable cgroup {}",
+                    leaf.display()
+                )),
+            })?;
+        }
+
+        // Detached once the processes it applies to have been killed
+        if let Some(device_filter) = &self.device_filter {
+            bpf::detach(&non_leaf_path(&self.cell_name), device_filter)
+                .map_err(|e| CgroupsError::DeleteCgroup {
+                    cell_name: self.cell_name.clone(),
+                    source: anyhow::Error::from(e)
+                        .context("failed to detach the device filter"),
+                })?;
+        }
+
+        let non_leaf = v2::manager::Manager::new(
+            DEFAULT_CGROUP_ROOT.into(),
+            self.cell_name.clone().into_inner(),
+        )
+        .expect("valid cgroup");
+
+        non_leaf.remove().map_err(|e| CgroupsError::DeleteCgroup {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        })
+    }
+
+    /// Returns true if the cgroup is in the v2 hierarchy. See [CgroupMode].
+    pub fn v2(&self) -> bool {
+        self.v2
+    }
+
+    /// Reads the resource usage of the cell from the non-leaf cgroup,
+    /// which includes the usage of any nested cells.
+    /// Only supported on cgroup v2.
+    pub fn stats(&self) -> Result<CgroupStats> {
+        let path = self.stats_path()?;
+
+        CgroupStats::read(&path).map_err(|e| CgroupsError::ReadStats {
+            cell_name: self.cell_name.clone(),
+            source: e.into(),
+        })
+    }
+
+    /// The directory the stats of the cell are read from, which is gone
+    /// once the cell is freed.
+    pub fn stats_path(&self) -> Result<PathBuf> {
+        if !self.v2 {
+            return Err(CgroupsError::Unsupported {
+                cell_name: self.cell_name.clone(),
+                feature: "stats".into(),
+            });
+        }
+
+        Ok(non_leaf_path(&self.cell_name))
+    }
+
+    /// Watches the OOM kills of the cell's own processes, in its leaf cgroup
+    /// and in the leaves of its executables. The cgroup of the cell is not
+    /// watched, as it counts those of its nested cells, which are watched on
+    /// their own.
+    pub fn watch_oom_kills(&self, events: OomEvents) -> OomWatcher {
+        // On v1, `memory.oom_control` has the same `oom_kill` counter, and
+        // executables have no leaves
+        let (path, executables) = if self.v2 {
+            let mut path =
+                PathBuf::from_str(DEFAULT_CGROUP_ROOT).expect("valid path");
+            path.push(get_leaf_path(&self.cell_name));
+            path.push("memory.events");
+            (path, Some(non_leaf_path(&self.cell_name)))
+        } else {
+            let path = v1::leaf_path(
+                Path::new(DEFAULT_CGROUP_ROOT),
+                "memory",
+                &self.cell_name,
+            )
+            .join("memory.oom_control");
+            (path, None)
+        };
+
+        OomWatcher::spawn(self.cell_name.clone(), path, executables, events)
+    }
+
+    /// The cgroup the executables of a cell with per executable accounting
+    /// are accounted in, each in a leaf of its own. See [ExecutableCgroup].
+    /// Returns [None] on cgroup v1, which does not support it.
+    ///
+    /// [ExecutableCgroup]: super::ExecutableCgroup
+    pub fn executables_path(cell_name: &CellName) -> Option<PathBuf> {
+        CgroupMode::current().is_v2().then(|| non_leaf_path(cell_name))
+    }
+
+    pub fn exists(cell_name: &CellName) -> bool {
+        if !CgroupMode::current().is_v2() {
+            return v1::exists(Path::new(DEFAULT_CGROUP_ROOT), cell_name);
+        }
+
+        let mut path =
+            PathBuf::from_str(DEFAULT_CGROUP_ROOT).expect("valid path");
+        path.push(cell_name.as_inner());
+        path.exists()
+    }
+
+    /// Checks that cells can be allocated, i.e. that the cgroup root, or on
+    /// cgroup v1 each of the hierarchies cells are created in, is writable.
+    pub fn check_root_writable() -> io::Result<()> {
+        let root = Path::new(DEFAULT_CGROUP_ROOT);
+        if !CgroupMode::current().is_v2() {
+            return v1::check_writable(root);
+        }
 
-        // oci_spec, which libcgroups uses, combines the cpu and cpuset controllers
-        let builder = if cpu.is_some() || cpuset.is_some() || memory.is_some() {
+        access(root, AccessFlags::W_OK).map_err(|e| {
+            let e = io::Error::from(e);
+            io::Error::new(e.kind(), format!("{DEFAULT_CGROUP_ROOT}: {e}"))
+        })
+    }
+}
+
+/// Builds the resources libcgroups applies for the cpu, cpuset and memory
+/// controllers of `spec`.
+fn resources(spec: &CgroupSpec) -> LinuxResources {
+    let CgroupSpec { cpu, cpuset, memory, .. } = spec.clone();
+
+    let builder = LinuxResourcesBuilder::default();
+
+    // oci_spec, which libcgroups uses, combines the cpu and cpuset controllers
+    let builder =
+        if cpu.is_some() || cpuset.is_some() || memory.is_some() {
             let cpu_builder = LinuxCpuBuilder::default();
 
             // cpu controller
@@ -146,98 +505,87 @@ impl Cgroup {
             builder
         };
 
-        let options = builder.build().expect("valid options");
-        let options = ControllerOpt {
-            resources: &options,
-            disable_oom_killer: false,
-            oom_score_adj: None,
-            freezer_state: None,
-        };
-
-        if let Err(e) = non_leaf.apply(&options) {
-            // try to remove, but ignore the error as the original error is more appropriate to return
-            // libcgroups takes care of killing any processes it finds
-            let _ = leaf.remove();
-            let _ = non_leaf.remove();
-            return Err(CgroupsError::CreateCgroup {
-                cell_name,
-                source: e.into(),
-            });
-        }
+    builder.build().expect("valid options")
+}
 
-        Ok(Self { cell_name })
-    }
+/// Verifies the cgroup of the cell is a direct child of the cgroup of its
+/// parent cell, and that the parent resolves to a path under the cgroup root.
+/// On v1, the hierarchy of the first controller is checked.
+fn check_under_root(cell_name: &CellName, v2: bool) -> Result<()> {
+    let outside_root =
+        || CgroupsError::OutsideCgroupRoot { cell_name: cell_name.clone() };
 
-    pub fn add_task(&self, pid: Pid) -> Result<()> {
-        let manager = v2::manager::Manager::new(
-            DEFAULT_CGROUP_ROOT.into(),
-            get_leaf_path(&self.cell_name),
-        )
-        .expect("valid cgroup");
-
-        manager.add_task(pid).map_err(|e| CgroupsError::AddTaskToCgroup {
-            cell_name: self.cell_name.clone(),
-            source: e.into(),
-        })
+    let cell_path = cell_name.as_inner();
+    if !cell_path.components().all(|x| matches!(x, Component::Normal(_))) {
+        return Err(outside_root());
     }
 
-    pub fn delete(&self) -> Result<()> {
-        let leaf = v2::manager::Manager::new(
-            DEFAULT_CGROUP_ROOT.into(),
-            get_leaf_path(&self.cell_name),
-        )
-        .expect("valid cgroup");
+    let root = if v2 {
+        PathBuf::from(DEFAULT_CGROUP_ROOT)
+    } else {
+        Path::new(DEFAULT_CGROUP_ROOT).join(v1::CONTROLLERS[0])
+    };
+    let parent = root.join(cell_path.parent().unwrap_or(Path::new("")));
 
-        leaf.remove().map_err(|e| CgroupsError::DeleteCgroup {
-            cell_name: self.cell_name.clone(),
-            source: e.into(),
-        })?;
-
-        let non_leaf = v2::manager::Manager::new(
-            DEFAULT_CGROUP_ROOT.into(),
-            self.cell_name.clone().into_inner(),
-        )
-        .expect("valid cgroup");
-
-        non_leaf.remove().map_err(|e| CgroupsError::DeleteCgroup {
-            cell_name: self.cell_name.clone(),
+    let canonicalize = |path: &Path| {
+        path.canonicalize().map_err(|e| CgroupsError::CreateCgroup {
+            cell_name: cell_name.clone(),
             source: e.into(),
         })
-    }
+    };
 
-    pub fn v2(&self) -> bool {
-        // Auraed will assume the V2 cgroup hierarchy by default.
-        // For now, we do not change this, albeit in theory we could
-        // likely create backwards compatability for V1 hierarchy.
-        //
-        // For now, we simply... don't.
-        true
+    if !canonicalize(&parent)?.starts_with(canonicalize(&root)?) {
+        return Err(outside_root());
     }
 
-    // TODO: use this
-    #[allow(unused)]
-    pub fn stats(&self) -> Result<Stats> {
-        let non_leaf = v2::manager::Manager::new(
-            DEFAULT_CGROUP_ROOT.into(),
-            self.cell_name.clone().into_inner(),
-        )
-        .expect("valid cgroup");
+    Ok(())
+}
 
-        non_leaf.stats().map_err(|e| CgroupsError::ReadStats {
-            cell_name: self.cell_name.clone(),
-            source: e.into(),
-        })
-    }
+fn non_leaf_path(cell_name: &CellName) -> PathBuf {
+    Path::new(DEFAULT_CGROUP_ROOT).join(cell_name.as_inner())
+}
 
-    pub fn exists(cell_name: &CellName) -> bool {
-        let mut path =
-            PathBuf::from_str(DEFAULT_CGROUP_ROOT).expect("valid path");
-        path.push(cell_name.as_inner());
-        path.exists()
+/// Returns the leaves of the executables of the cell accounted apart from
+/// its other processes, which are siblings of the leaf of its nested auraed.
+fn executable_leaves(cell_name: &CellName) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(non_leaf_path(cell_name)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut leaves = vec![];
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && is_executable_leaf(&entry.file_name())
+        {
+            leaves.push(entry.path());
+        }
     }
+    Ok(leaves)
 }
 
 fn get_leaf_path(cell_name: &CellName) -> PathBuf {
     // '_' is an invalid character in CellName, making it safe to use
     cell_name.as_inner().join("_")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_under_root() {
+        assert!(check_under_root(&CellName::random_for_tests(), true).is_ok());
+
+        for cell_name in ["..", "../etc", "cell/../../etc", "/etc"] {
+            assert!(
+                matches!(
+                    check_under_root(&CellName::from(cell_name), true),
+                    Err(CgroupsError::OutsideCgroupRoot { .. })
+                ),
+                "{cell_name}"
+            );
+        }
+    }
+}
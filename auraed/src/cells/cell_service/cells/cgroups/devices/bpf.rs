@@ -0,0 +1,342 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Compiles [DeviceRule]s into a `BPF_PROG_TYPE_CGROUP_DEVICE` program,
+//! the cgroup v2 replacement for the devices controller.
+//!
+//! The program is run with a `struct bpf_cgroup_dev_ctx`, and the constants
+//! are those of `include/uapi/linux/bpf.h`.
+
+use super::{DeviceRule, DeviceType};
+use std::{
+    ffi::CStr,
+    fs::File,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    path::Path,
+};
+
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_PROG_ATTACH: libc::c_long = 8;
+const BPF_PROG_DETACH: libc::c_long = 9;
+const BPF_PROG_TYPE_CGROUP_DEVICE: u32 = 15;
+const BPF_CGROUP_DEVICE: u32 = 6;
+/// Programs attached by the cells of parent cells keep applying.
+const BPF_F_ALLOW_MULTI: u32 = 2;
+
+const BPF_DEVCG_DEV_BLOCK: i32 = 1;
+const BPF_DEVCG_DEV_CHAR: i32 = 2;
+const BPF_DEVCG_ACC_MKNOD: i32 = 1;
+const BPF_DEVCG_ACC_READ: i32 = 2;
+const BPF_DEVCG_ACC_WRITE: i32 = 4;
+
+const LICENSE: &CStr = c"Apache-2.0";
+
+/// A `struct bpf_insn`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Insn {
+    code: u8,
+    /// The destination register in the low, and the source in the high nibble.
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+impl Insn {
+    const fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        Self { code, regs: dst | (src << 4), off, imm }
+    }
+
+    /// `dst = *(u32 *)(src + off)`
+    const fn load_u32(dst: u8, src: u8, off: i16) -> Self {
+        Self::new(0x61, dst, src, off, 0)
+    }
+
+    /// `(u32) dst = (u32) src`
+    const fn mov32(dst: u8, src: u8) -> Self {
+        Self::new(0xbc, dst, src, 0, 0)
+    }
+
+    /// `dst = imm`
+    const fn mov64_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0xb7, dst, 0, 0, imm)
+    }
+
+    /// `(u32) dst &= imm`
+    const fn and32_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0x54, dst, 0, 0, imm)
+    }
+
+    /// `(u32) dst >>= imm`
+    const fn rsh32_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0x74, dst, 0, 0, imm)
+    }
+
+    /// `if dst != imm goto pc + off`
+    const fn jne_imm(dst: u8, imm: i32, off: i16) -> Self {
+        Self::new(0x55, dst, 0, off, imm)
+    }
+
+    const fn exit() -> Self {
+        Self::new(0x95, 0, 0, 0, 0)
+    }
+}
+
+/// Compiles a program allowing the accesses matched by any of `rules`, and
+/// denying the others.
+pub(crate) fn compile(rules: &[DeviceRule]) -> Vec<Insn> {
+    // r1 is the context: { u32 access_type; u32 major; u32 minor; },
+    // where access_type is the access in the high and the type in the low
+    // 16 bits.
+    let mut program = vec![
+        Insn::load_u32(2, 1, 0),
+        Insn::mov32(3, 2),
+        Insn::and32_imm(2, 0xffff),
+        Insn::rsh32_imm(3, 16),
+        Insn::load_u32(4, 1, 4),
+        Insn::load_u32(5, 1, 8),
+    ];
+
+    for rule in rules {
+        // Each check jumps past the end of the rule when it does not match,
+        // so the offsets are filled in once the rule's length is known.
+        let mut checks = vec![];
+
+        let device_type = match rule.device_type {
+            DeviceType::Char => BPF_DEVCG_DEV_CHAR,
+            DeviceType::Block => BPF_DEVCG_DEV_BLOCK,
+        };
+        checks.push(Insn::jne_imm(2, device_type, 0));
+
+        // Any access that is not allowed must not be requested
+        let denied = [
+            (rule.access.mknod, BPF_DEVCG_ACC_MKNOD),
+            (rule.access.read, BPF_DEVCG_ACC_READ),
+            (rule.access.write, BPF_DEVCG_ACC_WRITE),
+        ]
+        .iter()
+        .filter(|(allowed, _)| !allowed)
+        .fold(0, |denied, (_, access)| denied | access);
+        if denied != 0 {
+            checks.push(Insn::mov32(6, 3));
+            checks.push(Insn::and32_imm(6, denied));
+            checks.push(Insn::jne_imm(6, 0, 0));
+        }
+
+        if let Some(major) = rule.major {
+            checks.push(Insn::jne_imm(4, major as i32, 0));
+        }
+        if let Some(minor) = rule.minor {
+            checks.push(Insn::jne_imm(5, minor as i32, 0));
+        }
+
+        checks.push(Insn::mov64_imm(0, 1));
+        checks.push(Insn::exit());
+
+        let len = checks.len();
+        for (i, insn) in checks.iter_mut().enumerate() {
+            if insn.code == 0x55 {
+                insn.off = (len - i - 1) as i16;
+            }
+        }
+        program.extend(checks);
+    }
+
+    program.push(Insn::mov64_imm(0, 0));
+    program.push(Insn::exit());
+    program
+}
+
+/// Loads a program compiled from `rules`, and attaches it to the cgroup at
+/// `path`. It is detached when the returned fd is passed to [detach], or
+/// when the cgroup is removed.
+pub(crate) fn attach(path: &Path, rules: &[DeviceRule]) -> io::Result<OwnedFd> {
+    let program = load(&compile(rules))?;
+    let cgroup = File::open(path)?;
+
+    #[repr(C)]
+    struct AttachAttr {
+        target_fd: u32,
+        attach_bpf_fd: u32,
+        attach_type: u32,
+        attach_flags: u32,
+    }
+
+    let attr = AttachAttr {
+        target_fd: cgroup.as_raw_fd() as u32,
+        attach_bpf_fd: program.as_raw_fd() as u32,
+        attach_type: BPF_CGROUP_DEVICE,
+        attach_flags: BPF_F_ALLOW_MULTI,
+    };
+    let _ = bpf(BPF_PROG_ATTACH, &attr)?;
+
+    Ok(program)
+}
+
+/// Detaches a program returned by [attach] from the cgroup at `path`.
+pub(crate) fn detach(path: &Path, program: &OwnedFd) -> io::Result<()> {
+    let cgroup = File::open(path)?;
+
+    #[repr(C)]
+    struct DetachAttr {
+        target_fd: u32,
+        attach_bpf_fd: u32,
+        attach_type: u32,
+    }
+
+    let attr = DetachAttr {
+        target_fd: cgroup.as_raw_fd() as u32,
+        attach_bpf_fd: program.as_raw_fd() as u32,
+        attach_type: BPF_CGROUP_DEVICE,
+    };
+    bpf(BPF_PROG_DETACH, &attr).map(|_| ())
+}
+
+fn load(program: &[Insn]) -> io::Result<OwnedFd> {
+    #[repr(C)]
+    struct LoadAttr {
+        prog_type: u32,
+        insn_cnt: u32,
+        insns: u64,
+        license: u64,
+    }
+
+    // Kernels before 5.11 charge BPF programs to RLIMIT_MEMLOCK, which is
+    // low by default.
+    let unlimited = libc::rlimit {
+        rlim_cur: libc::RLIM_INFINITY,
+        rlim_max: libc::RLIM_INFINITY,
+    };
+    // SAFETY: the rlimit is valid for the duration of the call.
+    let _ = unsafe { libc::setrlimit(libc::RLIMIT_MEMLOCK, &unlimited) };
+
+    let attr = LoadAttr {
+        prog_type: BPF_PROG_TYPE_CGROUP_DEVICE,
+        insn_cnt: program.len() as u32,
+        insns: program.as_ptr() as u64,
+        license: LICENSE.as_ptr() as u64,
+    };
+    let fd = bpf(BPF_PROG_LOAD, &attr)?;
+
+    // SAFETY: the fd was just opened, and nothing else owns it.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+fn bpf<T>(cmd: libc::c_long, attr: &T) -> io::Result<libc::c_long> {
+    // SAFETY: attr is a prefix of `union bpf_attr` for `cmd`, which the
+    // kernel zero-extends, and is valid for the duration of the call.
+    let result = unsafe {
+        libc::syscall(libc::SYS_bpf, cmd, attr, std::mem::size_of::<T>())
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+// The tests don't reap orphans, so they may spawn children of their own
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::cells::cgroups::devices::DeviceAccess;
+    use std::{fs, process::Command};
+
+    fn null() -> DeviceRule {
+        DeviceRule {
+            device_type: DeviceType::Char,
+            major: Some(1),
+            minor: Some(3),
+            access: DeviceAccess { read: true, write: true, mknod: false },
+        }
+    }
+
+    #[test]
+    fn test_compile_jumps_past_the_rule() {
+        let program = compile(&[null()]);
+
+        // The prologue, the checks of type, access, major and minor, the
+        // allowing exit, and the denying exit.
+        assert_eq!(program.len(), 6 + 6 + 2 + 2);
+        let rule = &program[6..14];
+        for (i, insn) in rule.iter().enumerate() {
+            if insn.code == 0x55 {
+                assert_eq!(6 + i + 1 + insn.off as usize, 14, "{i}");
+            }
+        }
+        assert_eq!(program[14], Insn::mov64_imm(0, 0));
+    }
+
+    #[test]
+    fn test_compile_wildcards() {
+        let rule = DeviceRule {
+            device_type: DeviceType::Block,
+            major: None,
+            minor: None,
+            access: DeviceAccess { read: true, write: true, mknod: true },
+        };
+        // Only the type is checked
+        assert_eq!(compile(&[rule]).len(), 6 + 3 + 2);
+    }
+
+    /// Runs `command` in a new cgroup filtered by `rules` in the unified
+    /// hierarchy, and returns whether it succeeded.
+    fn run_filtered(
+        unified: &Path,
+        rules: &[DeviceRule],
+        command: &str,
+    ) -> bool {
+        let path =
+            unified.join(format!("ae-test-devices-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&path).unwrap();
+        let program = attach(&path, rules).expect("failed to attach");
+
+        let procs = path.join("cgroup.procs");
+        let success = Command::new("sh")
+            .arg("-c")
+            .arg(format!("echo $$ > {} && {command}", procs.display()))
+            .status()
+            .unwrap()
+            .success();
+
+        detach(&path, &program).expect("failed to detach");
+        fs::remove_dir(&path).unwrap();
+        success
+    }
+
+    #[test]
+    fn test_attach_filters_device_access() {
+        // Attaching requires root, and a cgroup v2 hierarchy
+        let unified =
+            [Path::new("/sys/fs/cgroup/unified"), Path::new("/sys/fs/cgroup")]
+                .into_iter()
+                .find(|path| {
+                    path.join("cgroup.procs").exists()
+                        && path.join("cgroup.controllers").exists()
+                });
+        let Some(unified) = unified else {
+            return;
+        };
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+
+        let zero = DeviceRule { minor: Some(5), ..null() };
+        let read_zero = "head -c 1 /dev/zero > /dev/null";
+        assert!(run_filtered(unified, &[null(), zero], read_zero));
+        assert!(!run_filtered(unified, &[null()], read_zero));
+    }
+}
@@ -0,0 +1,192 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Rules for the device nodes the processes of a cell may access.
+//!
+//! Docs: https://docs.kernel.org/admin-guide/cgroup-v1/devices.html
+//! On cgroup v2, the rules are compiled into a BPF program. See [bpf].
+
+pub(super) mod bpf;
+
+use std::fmt::{Display, Formatter};
+use validation::{ValidatedField, ValidationError};
+
+/// Allows access to the devices matching `device_type`, `major` and `minor`.
+/// A major or minor of [None] matches any.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DeviceRule {
+    pub device_type: DeviceType,
+    pub major: Option<u32>,
+    pub minor: Option<u32>,
+    pub access: DeviceAccess,
+}
+
+impl Display for DeviceRule {
+    /// Formats the rule as written to `devices.allow` on cgroup v1.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let number = |x: Option<u32>| match x {
+            Some(x) => x.to_string(),
+            None => "*".into(),
+        };
+
+        write!(
+            f,
+            "{} {}:{} {}",
+            self.device_type,
+            number(self.major),
+            number(self.minor),
+            self.access
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DeviceType {
+    Char,
+    Block,
+}
+
+impl ValidatedField<String> for DeviceType {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        match &*input {
+            "c" => Ok(Self::Char),
+            "b" => Ok(Self::Block),
+            _ => Err(ValidationError::Invalid {
+                field: validation::field_name(field_name, parent_name),
+            }),
+        }
+    }
+}
+
+impl Display for DeviceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Char => f.write_str("c"),
+            Self::Block => f.write_str("b"),
+        }
+    }
+}
+
+/// A combination of read (`r`), write (`w`) and mknod (`m`) access.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DeviceAccess {
+    pub read: bool,
+    pub write: bool,
+    pub mknod: bool,
+}
+
+impl ValidatedField<String> for DeviceAccess {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        let invalid = || ValidationError::Invalid {
+            field: validation::field_name(field_name, parent_name),
+        };
+
+        let mut access = Self { read: false, write: false, mknod: false };
+        for c in input.chars() {
+            let allowed = match c {
+                'r' => &mut access.read,
+                'w' => &mut access.write,
+                'm' => &mut access.mknod,
+                _ => return Err(invalid()),
+            };
+            if *allowed {
+                return Err(invalid());
+            }
+            *allowed = true;
+        }
+
+        Ok(access)
+    }
+}
+
+impl Display for DeviceAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (allowed, c) in
+            [(self.read, "r"), (self.write, "w"), (self.mknod, "m")]
+        {
+            if allowed {
+                f.write_str(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_type_validation() {
+        assert_eq!(
+            DeviceType::validate(Some("c".into()), "type", None).unwrap(),
+            DeviceType::Char
+        );
+        assert_eq!(
+            DeviceType::validate(Some("b".into()), "type", None).unwrap(),
+            DeviceType::Block
+        );
+        for input in ["", "a", "cb"] {
+            assert!(
+                DeviceType::validate(Some(input.into()), "type", None).is_err(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_access_validation() {
+        let access =
+            DeviceAccess::validate(Some("mr".into()), "access", None).unwrap();
+        assert_eq!(
+            access,
+            DeviceAccess { read: true, write: false, mknod: true }
+        );
+        assert_eq!(access.to_string(), "rm");
+
+        for input in ["", "rr", "rwx"] {
+            assert!(
+                DeviceAccess::validate(Some(input.into()), "access", None)
+                    .is_err(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_rule_display() {
+        let rule = DeviceRule {
+            device_type: DeviceType::Char,
+            major: Some(1),
+            minor: None,
+            access: DeviceAccess { read: true, write: true, mknod: false },
+        };
+        assert_eq!(rule.to_string(), "c 1:* rw");
+    }
+}
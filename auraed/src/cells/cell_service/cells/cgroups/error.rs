@@ -22,6 +22,14 @@ pub type Result<T> = std::result::Result<T, CgroupsError>;
 pub enum CgroupsError {
     #[error("cgroup '{cell_name}' creation failed: {source}")]
     CreateCgroup { cell_name: CellName, source: anyhow::Error },
+    #[error("cgroup '{cell_name}' is frozen")]
+    Frozen { cell_name: CellName },
+    #[error("cgroup '{cell_name}' update failed: {source}")]
+    UpdateCgroup { cell_name: CellName, source: anyhow::Error },
+    #[error("cgroup '{cell_name}' can not use {feature} on cgroup v1")]
+    Unsupported { cell_name: CellName, feature: String },
+    #[error("cgroup '{cell_name}' would be outside of the cgroup root")]
+    OutsideCgroupRoot { cell_name: CellName },
     #[error("cgroup '{cell_name}' failed to add task: {source}")]
     AddTaskToCgroup { cell_name: CellName, source: anyhow::Error },
     #[error("cgroup '{cell_name}' deletion failed: {source}")]
@@ -0,0 +1,164 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Waits for the processes of a cgroup v2 to exit, by watching the
+//! `populated` key of its `cgroup.events` file with inotify, and reads
+//! whether the cgroup is frozen from the same file.
+
+use super::stats::{get_key, read_flat_keyed};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use std::{
+    io,
+    os::fd::{AsFd, AsRawFd},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const CGROUP_EVENTS: &str = "cgroup.events";
+
+/// Waits up to `timeout` for the cgroup at `path`, and its descendants, to
+/// have no processes left. Returns false if some are left when it expires.
+/// A removed cgroup has no processes.
+pub(super) fn wait_unpopulated(
+    path: &Path,
+    timeout: Duration,
+) -> io::Result<bool> {
+    let events = path.join(CGROUP_EVENTS);
+    let deadline = Instant::now() + timeout;
+
+    // Watching before the first read, so that no change is missed
+    let inotify =
+        Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)?;
+    match inotify.add_watch(&events, AddWatchFlags::IN_MODIFY) {
+        Ok(_) => {}
+        Err(nix::errno::Errno::ENOENT) => return Ok(true),
+        Err(e) => return Err(e.into()),
+    }
+
+    loop {
+        if !is_populated(&events)? {
+            return Ok(true);
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+
+        let mut pollfd = libc::pollfd {
+            fd: inotify.as_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = i32::try_from(remaining.as_micros().div_ceil(1000))
+            .unwrap_or(i32::MAX);
+        // SAFETY: pollfd is valid for the duration of the call.
+        if unsafe { libc::poll(&mut pollfd, 1, timeout_ms) } < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+
+        // The file is read again whatever the events are
+        let _ = inotify.read_events();
+    }
+}
+
+/// Returns true if the cgroup at `path` is frozen, by itself or by an
+/// ancestor. A removed cgroup is not frozen.
+pub(super) fn is_frozen(path: &Path) -> io::Result<bool> {
+    let events = read_flat_keyed(path.join(CGROUP_EVENTS))?;
+    Ok(get_key(&events, "frozen").is_some_and(|frozen| frozen != 0))
+}
+
+fn is_populated(events: &Path) -> io::Result<bool> {
+    let events = read_flat_keyed(events.to_path_buf())?;
+    Ok(get_key(&events, "populated").is_some_and(|populated| populated != 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf, thread};
+
+    fn test_dir(populated: u8) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-events-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        write_populated(&dir, populated);
+        dir
+    }
+
+    fn write_populated(dir: &Path, populated: u8) {
+        fs::write(
+            dir.join(CGROUP_EVENTS),
+            format!("populated {populated}\nfrozen 0\n"),
+        )
+        .expect("failed to write cgroup.events");
+    }
+
+    #[test]
+    fn test_unpopulated_returns_immediately() {
+        let dir = test_dir(0);
+        assert!(wait_unpopulated(&dir, Duration::ZERO).unwrap());
+    }
+
+    #[test]
+    fn test_removed_is_unpopulated() {
+        let dir = test_dir(1);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(wait_unpopulated(&dir, Duration::ZERO).unwrap());
+    }
+
+    #[test]
+    fn test_waits_for_unpopulated() {
+        let dir = test_dir(1);
+
+        let writer = {
+            let dir = dir.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                write_populated(&dir, 0);
+            })
+        };
+
+        let start = Instant::now();
+        assert!(wait_unpopulated(&dir, Duration::from_secs(10)).unwrap());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_times_out_while_populated() {
+        let dir = test_dir(1);
+
+        let start = Instant::now();
+        assert!(!wait_unpopulated(&dir, Duration::from_millis(100)).unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_is_frozen() {
+        let dir = test_dir(1);
+        assert!(!is_frozen(&dir).unwrap());
+
+        fs::write(dir.join(CGROUP_EVENTS), "populated 1\nfrozen 1\n").unwrap();
+        assert!(is_frozen(&dir).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!is_frozen(&dir).unwrap());
+    }
+}
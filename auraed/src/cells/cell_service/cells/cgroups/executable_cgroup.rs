@@ -0,0 +1,260 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The leaf cgroups executables are accounted in, apart from the other
+//! processes of their cell, when it has per executable accounting.
+//!
+//! The leaf of an executable is a child of the cgroup of its cell, named
+//! after it with a `_` prefix. Cell names can't contain `_`, so the leaves
+//! never collide with the cgroups of nested cells, nor with the leaf `_`
+//! the nested auraed of the cell runs in.
+
+use super::cgroup::KILLED_EXIT_TIMEOUT;
+use super::events;
+use super::stats::CgroupStats;
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fs, io};
+
+const LEAF_PREFIX: &str = "_";
+
+/// The leaf cgroup of the nested auraed of a cell, which the processes left
+/// in the leaf of an executable are moved to.
+const NESTED_AURAED_LEAF: &str = "_";
+
+#[derive(Debug)]
+pub struct ExecutableCgroup {
+    path: PathBuf,
+    /// The `cgroup.procs` of the cgroup, written by the process of the
+    /// executable between fork and exec, where it must not allocate.
+    procs: Arc<CStr>,
+}
+
+impl ExecutableCgroup {
+    /// Creates the leaf cgroup of `executable_name` in `cell_cgroup`. A leaf
+    /// left by a previous executable of the same name is reused.
+    pub fn create(
+        cell_cgroup: &Path,
+        executable_name: &str,
+    ) -> io::Result<Self> {
+        let path = leaf_path(cell_cgroup, executable_name)?;
+        match fs::create_dir(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self::at(path))
+    }
+
+    /// Returns the leaf cgroup of an executable started by a previous
+    /// auraed, or [None] if it has none.
+    pub fn adopt(cell_cgroup: &Path, executable_name: &str) -> Option<Self> {
+        let path = leaf_path(cell_cgroup, executable_name).ok()?;
+        path.is_dir().then(|| Self::at(path))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        let procs =
+            CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+                .expect("path without nul bytes");
+        Self { path, procs: procs.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a function moving the calling process into the cgroup, for
+    /// the process of the executable to call between fork and exec.
+    pub fn joiner(
+        &self,
+    ) -> impl Fn() -> io::Result<()> + Send + Sync + 'static {
+        let procs = self.procs.clone();
+        move || join(&procs)
+    }
+
+    /// Reads the usage of the executable.
+    pub fn stats(&self) -> io::Result<CgroupStats> {
+        CgroupStats::read(&self.path)
+    }
+
+    /// Kills every process in the cgroup with `cgroup.kill`, and waits for
+    /// them to exit. The nested auraed, in a leaf of its own, is never
+    /// among them. Requires Linux 5.14.
+    pub fn kill(&self) -> io::Result<()> {
+        match fs::write(self.path.join("cgroup.kill"), "1") {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return if self.path.exists() {
+                    Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "cgroup.kill requires Linux 5.14",
+                    ))
+                } else {
+                    Ok(())
+                };
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !events::wait_unpopulated(&self.path, KILLED_EXIT_TIMEOUT)? {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("processes are left in {}", self.path.display()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Removes the cgroup. The processes the executable left running, as
+    /// when only its own process is killed, are moved to the leaf of the
+    /// nested auraed first, as only an empty cgroup can be removed.
+    pub fn remove(&self) -> io::Result<()> {
+        let procs = match fs::read_to_string(self.path.join("cgroup.procs")) {
+            Ok(procs) => procs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let nested_auraed_procs =
+            self.path.with_file_name(NESTED_AURAED_LEAF).join("cgroup.procs");
+        for pid in procs.lines() {
+            if let Err(e) = fs::write(&nested_auraed_procs, pid) {
+                // The process exited meanwhile
+                if e.raw_os_error() != Some(libc::ESRCH) {
+                    return Err(e);
+                }
+            }
+        }
+
+        match fs::remove_dir(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for ExecutableCgroup {
+    /// Removes the cgroup if it is empty, as when the executable failed to
+    /// start, and is left otherwise.
+    fn drop(&mut self) {
+        let _best_effort = fs::remove_dir(&self.path);
+    }
+}
+
+/// Moves the calling process into the cgroup of `procs`. Only makes
+/// syscalls, so that it can be called between fork and exec.
+fn join(procs: &CStr) -> io::Result<()> {
+    // SAFETY: the path is a valid C string, and the fd is closed below
+    let fd =
+        unsafe { libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Writing 0 moves the writing process
+    // SAFETY: the buffer is valid for the length written
+    let written = unsafe { libc::write(fd, c"0".as_ptr().cast(), 1) };
+    let result =
+        if written < 0 { Err(io::Error::last_os_error()) } else { Ok(()) };
+    let _ = unsafe { libc::close(fd) };
+    result
+}
+
+/// Returns true if `name`, a child of the cgroup of a cell, is the leaf of
+/// an executable.
+pub(super) fn is_executable_leaf(name: &OsStr) -> bool {
+    name.as_bytes().starts_with(LEAF_PREFIX.as_bytes())
+        && name != NESTED_AURAED_LEAF
+}
+
+/// The leaf is named after the executable, which must then be a single path
+/// component. With its prefix, it is never `.` nor `..`.
+fn leaf_path(cell_cgroup: &Path, executable_name: &str) -> io::Result<PathBuf> {
+    if executable_name.contains(['/', '\0']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("executable name '{executable_name}' can't name a cgroup"),
+        ));
+    }
+
+    Ok(cell_cgroup.join(format!("{LEAF_PREFIX}{executable_name}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ae-test-executable-cgroup-{}",
+            uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        dir
+    }
+
+    #[test]
+    fn test_leaf_is_named_after_the_executable() {
+        let dir = test_dir();
+        let cgroup =
+            ExecutableCgroup::create(&dir, "sleeper").expect("create leaf");
+        assert_eq!(cgroup.path(), dir.join("_sleeper"));
+        assert!(cgroup.path().is_dir());
+        assert!(is_executable_leaf(OsStr::new("_sleeper")));
+        assert!(!is_executable_leaf(OsStr::new("_")));
+        assert!(!is_executable_leaf(OsStr::new("nested-cell")));
+
+        // An executable of the same name may reuse it
+        let adopted =
+            ExecutableCgroup::adopt(&dir, "sleeper").expect("adopt leaf");
+        assert_eq!(adopted.path(), cgroup.path());
+        assert!(ExecutableCgroup::adopt(&dir, "other").is_none());
+    }
+
+    #[test]
+    fn test_names_that_are_not_a_path_component_are_rejected() {
+        let dir = test_dir();
+        for name in ["a/b", "../..", "a\0b"] {
+            let err = ExecutableCgroup::create(&dir, name)
+                .expect_err("name must be rejected");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{name}");
+        }
+
+        // The prefix keeps them from naming the cell cgroup or its parent
+        for name in [".", ".."] {
+            let cgroup = ExecutableCgroup::create(&dir, name).expect("leaf");
+            assert_eq!(cgroup.path(), dir.join(format!("_{name}")));
+        }
+    }
+
+    #[test]
+    fn test_join_writes_the_calling_process() {
+        let dir = test_dir();
+        let cgroup =
+            ExecutableCgroup::create(&dir, "sleeper").expect("create leaf");
+        fs::write(cgroup.path().join("cgroup.procs"), "").unwrap();
+
+        cgroup.joiner()().expect("join");
+        assert_eq!(
+            fs::read_to_string(cgroup.path().join("cgroup.procs")).unwrap(),
+            "0"
+        );
+    }
+}
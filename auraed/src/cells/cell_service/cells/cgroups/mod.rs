@@ -16,20 +16,34 @@
 pub use cgroup::Cgroup;
 pub use cpu::CpuController;
 pub use cpuset::CpusetController;
+pub use devices::{DeviceAccess, DeviceRule, DeviceType};
+pub use executable_cgroup::ExecutableCgroup;
 pub use limit::Limit;
 pub use memory::MemoryController;
+pub use mode::CgroupMode;
+pub use oom::{OomEvent, OomEvents, OomWatcher};
 pub use protection::Protection;
+pub use settings::{CgroupSettings, IoLimit};
+pub use stats::{CgroupStats, MemoryEvents};
 pub use weight::Weight;
 
 pub mod cpu;
 pub mod cpuset;
+pub mod devices;
 pub mod error;
 pub mod memory;
+pub mod stats;
 
 mod allocation;
 mod cgroup;
+mod events;
+mod executable_cgroup;
 mod limit;
+mod mode;
+mod oom;
 mod protection;
+mod settings;
+mod v1;
 mod weight;
 
 #[derive(Debug, Clone)]
@@ -37,4 +51,7 @@ pub struct CgroupSpec {
     pub cpu: Option<CpuController>,
     pub cpuset: Option<CpusetController>,
     pub memory: Option<MemoryController>,
+    /// The devices the processes of the cell may access. Any device may be
+    /// accessed if empty.
+    pub device_allow: Vec<DeviceRule>,
 }
\ No newline at end of file
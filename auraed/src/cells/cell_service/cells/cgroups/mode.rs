@@ -0,0 +1,94 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Detection of the cgroup hierarchy mounted at the cgroup root, which
+//! decides the backend used for the cgroups of cells.
+
+use libcgroups::common::DEFAULT_CGROUP_ROOT;
+use nix::sys::statfs::{statfs, CGROUP2_SUPER_MAGIC, TMPFS_MAGIC};
+use once_cell::sync::Lazy;
+use std::{
+    fmt::{Display, Formatter},
+    io,
+    path::Path,
+};
+use tracing::warn;
+
+static CGROUP_MODE: Lazy<CgroupMode> =
+    Lazy::new(|| match CgroupMode::detect(Path::new(DEFAULT_CGROUP_ROOT)) {
+        Ok(mode) => mode,
+        Err(e) => {
+            warn!("failed to detect the cgroup mode, assuming unified: {e}");
+            CgroupMode::Unified
+        }
+    });
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgroupMode {
+    /// Only the cgroup v2 hierarchy is mounted.
+    Unified,
+    /// The controllers are mounted as cgroup v1 hierarchies, next to a
+    /// cgroup v2 hierarchy without controllers at `unified`.
+    Hybrid,
+    /// Only cgroup v1 hierarchies are mounted.
+    Legacy,
+}
+
+impl CgroupMode {
+    /// Returns the mode of the host, which is detected on first use.
+    pub fn current() -> Self {
+        *CGROUP_MODE
+    }
+
+    /// Detects the mode from the filesystem mounted at `root`.
+    pub fn detect(root: &Path) -> io::Result<Self> {
+        let filesystem_type = statfs(root)?.filesystem_type();
+
+        if filesystem_type == CGROUP2_SUPER_MAGIC {
+            return Ok(Self::Unified);
+        }
+
+        if filesystem_type != TMPFS_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("'{}' is not a cgroup mount", root.display()),
+            ));
+        }
+
+        match statfs(&root.join("unified")) {
+            Ok(unified) if unified.filesystem_type() == CGROUP2_SUPER_MAGIC => {
+                Ok(Self::Hybrid)
+            }
+            _ => Ok(Self::Legacy),
+        }
+    }
+
+    /// Returns true if cells use the cgroup v2 backend. Hybrid hosts use the
+    /// v1 backend, as the controllers are bound to the v1 hierarchies.
+    pub fn is_v2(&self) -> bool {
+        matches!(self, Self::Unified)
+    }
+}
+
+impl Display for CgroupMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unified => "unified",
+            Self::Hybrid => "hybrid",
+            Self::Legacy => "legacy",
+        }
+        .fmt(f)
+    }
+}
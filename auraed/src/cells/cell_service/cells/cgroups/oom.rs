@@ -0,0 +1,294 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Watches the `oom_kill` counter of a cell and broadcasts an [OomEvent]
+//! whenever it increases. The counters of the leaves of its executables, when
+//! it has per executable accounting, are added to that of its own leaf.
+//!
+//! The counter is polled rather than watched with inotify, so a watcher only
+//! holds a tokio task, which is aborted when the [OomWatcher] is dropped.
+
+use super::executable_cgroup::is_executable_leaf;
+use super::stats::{get_key, read_flat_keyed};
+use crate::cells::cell_service::cells::CellName;
+use crate::logging::get_timestamp_sec;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    sync::broadcast,
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+use tracing::{error, warn};
+
+/// How often the `oom_kill` counter is read.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of events a slow subscriber can fall behind before it misses events.
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OomEvent {
+    pub cell_name: CellName,
+    pub oom_kill_count: u64,
+    pub timestamp: i64,
+}
+
+/// Broadcasts the [OomEvent]s of every watched cell.
+#[derive(Debug, Clone)]
+pub struct OomEvents(broadcast::Sender<OomEvent>);
+
+impl OomEvents {
+    pub fn subscribe(&self) -> broadcast::Receiver<OomEvent> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for OomEvents {
+    fn default() -> Self {
+        Self(broadcast::channel(CHANNEL_CAPACITY).0)
+    }
+}
+
+/// Polls a `memory.events` file until dropped.
+#[derive(Debug)]
+pub struct OomWatcher {
+    task: Option<JoinHandle<()>>,
+}
+
+impl OomWatcher {
+    /// Starts watching the `memory.events` file at `path`, and those of the
+    /// leaves of executables in `executables`, attributing any OOM kills to
+    /// `cell_name`. Leaves are looked for on every read, as executables
+    /// start and stop meanwhile.
+    ///
+    /// Must be called from within a tokio runtime, otherwise nothing is watched.
+    pub fn spawn(
+        cell_name: CellName,
+        path: PathBuf,
+        executables: Option<PathBuf>,
+        events: OomEvents,
+    ) -> Self {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("not watching OOM kills of cell '{cell_name}': no runtime");
+            return Self { task: None };
+        };
+
+        let task = runtime.spawn(async move {
+            // Only increases after the watch started are reported
+            let mut last = HashMap::new();
+            let _ =
+                last.insert(path.clone(), read_oom_kill(&path).unwrap_or(0));
+            for leaf in executable_leaves(executables.as_deref()) {
+                let count = read_leaf_oom_kill(&leaf).unwrap_or(0);
+                let _ = last.insert(leaf, count);
+            }
+            // The last counts of the leaves created again since
+            let mut replaced = 0;
+
+            let mut interval = time::interval(POLL_INTERVAL);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                let _ = interval.tick().await;
+
+                let Some(count) = read_oom_kill(&path) else {
+                    // the cgroup has been removed
+                    break;
+                };
+                let mut counts = vec![(path.clone(), count)];
+                // Those of stopped executables keep their last count
+                for leaf in executable_leaves(executables.as_deref()) {
+                    if let Some(count) = read_leaf_oom_kill(&leaf) {
+                        counts.push((leaf, count));
+                    }
+                }
+
+                let mut increased = false;
+                for (path, count) in counts {
+                    // Leaves started since the last read count from 0
+                    let last = last.entry(path).or_insert(0);
+                    // The leaf of an executable started again is a new one
+                    if count < *last {
+                        replaced += *last;
+                        *last = 0;
+                    }
+                    increased |= count > *last;
+                    *last = count;
+                }
+                if !increased {
+                    continue;
+                }
+                let oom_kill_count = replaced + last.values().sum::<u64>();
+
+                // An error only means that nobody is subscribed
+                let _ = events.0.send(OomEvent {
+                    cell_name: cell_name.clone(),
+                    oom_kill_count,
+                    timestamp: get_timestamp_sec(),
+                });
+            }
+        });
+
+        Self { task: Some(task) }
+    }
+}
+
+impl Drop for OomWatcher {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// The `memory.events` files of the leaves of executables in `executables`.
+fn executable_leaves(executables: Option<&Path>) -> Vec<PathBuf> {
+    let Some(Ok(entries)) = executables.map(fs::read_dir) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| is_executable_leaf(&entry.file_name()))
+        .map(|entry| entry.path().join("memory.events"))
+        .collect()
+}
+
+/// Returns [None] if the leaf has been removed meanwhile.
+fn read_leaf_oom_kill(path: &Path) -> Option<u64> {
+    get_key(&read_flat_keyed(path.to_path_buf()).ok()?, "oom_kill")
+}
+
+/// Returns [None] if the file can not be read.
+fn read_oom_kill(path: &Path) -> Option<u64> {
+    match read_flat_keyed(path.to_path_buf()) {
+        Ok(entries) => get_key(&entries, "oom_kill"),
+        Err(e) => {
+            error!("failed to read '{}': {e}", path.display());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn memory_events(oom_kill: u64) -> String {
+        format!("low 0\nhigh 0\nmax 0\noom 0\noom_kill {oom_kill}\n")
+    }
+
+    fn test_file() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-oom-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        let path = dir.join("memory.events");
+        fs::write(&path, memory_events(1)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_increase_is_broadcast() {
+        let path = test_file();
+        let cell_name =
+            CellName::random_child_for_tests(&CellName::random_for_tests());
+        let events = OomEvents::default();
+        let mut rx = events.subscribe();
+
+        let _watcher =
+            OomWatcher::spawn(cell_name.clone(), path.clone(), None, events);
+
+        // let the watcher read the initial count
+        time::sleep(Duration::from_millis(100)).await;
+        fs::write(&path, memory_events(3)).unwrap();
+
+        let event = time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no event received")
+            .unwrap();
+        assert_eq!(event.cell_name, cell_name);
+        assert_eq!(event.oom_kill_count, 3);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_increase_of_executable_leaves_is_broadcast() {
+        let path = test_file();
+        let cell_cgroup = path.parent().unwrap().to_path_buf();
+        let started = cell_cgroup.join("_started");
+        fs::create_dir(&started).unwrap();
+        fs::write(started.join("memory.events"), memory_events(2)).unwrap();
+
+        let cell_name = CellName::random_for_tests();
+        let events = OomEvents::default();
+        let mut rx = events.subscribe();
+        let _watcher = OomWatcher::spawn(
+            cell_name.clone(),
+            path.clone(),
+            Some(cell_cgroup.clone()),
+            events,
+        );
+        time::sleep(Duration::from_millis(100)).await;
+
+        // A leaf created after the watch started counts from 0
+        let later = cell_cgroup.join("_later");
+        fs::create_dir(&later).unwrap();
+        fs::write(later.join("memory.events"), memory_events(1)).unwrap();
+        fs::write(started.join("memory.events"), memory_events(3)).unwrap();
+
+        // The leaves may be read between the writes
+        let counted = time::timeout(Duration::from_secs(5), async {
+            loop {
+                let event = rx.recv().await.unwrap();
+                assert_eq!(event.cell_name, cell_name);
+                if event.oom_kill_count == 5 {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(counted.is_ok(), "the kills of the leaves were not counted");
+
+        fs::remove_dir_all(cell_cgroup).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_stops_watching() {
+        let path = test_file();
+        let cell_name = CellName::random_for_tests();
+        let events = OomEvents::default();
+        let mut rx = events.subscribe();
+
+        let watcher =
+            OomWatcher::spawn(cell_name, path.clone(), None, events.clone());
+        time::sleep(Duration::from_millis(100)).await;
+        drop(watcher);
+
+        fs::write(&path, memory_events(2)).unwrap();
+
+        assert!(time::timeout(Duration::from_secs(3), rx.recv())
+            .await
+            .is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Writes cgroup values to the interface files of a live cgroup v2.
+
+use super::{
+    CgroupSpec, CpuController, Limit, MemoryController, Protection, Weight,
+};
+use std::{fs, io, path::Path};
+
+/// The cgroup values to write to the cgroup of an allocated cell.
+/// Values that are not set are left as they are.
+#[derive(Debug, Clone, Default)]
+pub struct CgroupSettings {
+    pub cpu_weight: Option<Weight>,
+    pub cpu_max: Option<Limit>,
+    pub memory_max: Option<Limit>,
+    pub memory_low: Option<Protection>,
+    pub pids_max: Option<Limit>,
+    pub io_max: Vec<IoLimit>,
+}
+
+/// The io throttling of a block device, written to `io.max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoLimit {
+    pub major: u32,
+    pub minor: u32,
+    pub rbps: Option<u64>,
+    pub wbps: Option<u64>,
+    pub riops: Option<u64>,
+    pub wiops: Option<u64>,
+}
+
+impl IoLimit {
+    /// Returns the line to write to `io.max`, or [None] if no limit is set.
+    fn io_max_line(&self) -> Option<String> {
+        let limits: Vec<_> = [
+            ("rbps", self.rbps),
+            ("wbps", self.wbps),
+            ("riops", self.riops),
+            ("wiops", self.wiops),
+        ]
+        .into_iter()
+        .filter_map(|(key, limit)| Some(format!("{key}={}", limit?)))
+        .collect();
+
+        if limits.is_empty() {
+            return None;
+        }
+
+        Some(format!("{}:{} {}", self.major, self.minor, limits.join(" ")))
+    }
+}
+
+impl CgroupSettings {
+    /// Writes the set values to the cgroup v2 directory at `path`.
+    pub(super) fn write(&self, path: &Path) -> io::Result<()> {
+        let Self {
+            cpu_weight,
+            cpu_max,
+            memory_max,
+            memory_low,
+            pids_max,
+            io_max,
+        } = self;
+
+        if let Some(weight) = cpu_weight {
+            fs::write(
+                path.join("cpu.weight"),
+                cpu_weight_from_shares(*weight).to_string(),
+            )?;
+        }
+
+        // Like libcgroups, which applies the max of allocated cells, a max of
+        // 0 is no limit. The period is left as it is.
+        if let Some(max) = cpu_max {
+            let max = match max.into_inner() {
+                0 => "max".to_string(),
+                max => max.to_string(),
+            };
+            fs::write(path.join("cpu.max"), max)?;
+        }
+
+        // The kernel reclaims memory from the cell, and OOM kills its
+        // processes if it can not, before the write returns.
+        if let Some(max) = memory_max {
+            fs::write(path.join("memory.max"), max.into_inner().to_string())?;
+        }
+
+        if let Some(low) = memory_low {
+            fs::write(path.join("memory.low"), low.into_inner().to_string())?;
+        }
+
+        if let Some(max) = pids_max {
+            fs::write(path.join("pids.max"), max.into_inner().to_string())?;
+        }
+
+        // Each write sets the limits of one device
+        for line in io_max.iter().filter_map(IoLimit::io_max_line) {
+            fs::write(path.join("io.max"), line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the values of `spec` that are also set in the settings, so that
+    /// `spec` describes the cgroup once they have been written. The pids and
+    /// io values are not part of a [CgroupSpec].
+    pub fn merge_into(&self, spec: &mut CgroupSpec) {
+        if self.cpu_weight.is_some() || self.cpu_max.is_some() {
+            let cpu = spec.cpu.get_or_insert(CpuController {
+                weight: None,
+                max: None,
+                period: None,
+            });
+            cpu.weight = self.cpu_weight.or(cpu.weight);
+            cpu.max = self.cpu_max.or(cpu.max);
+        }
+
+        if self.memory_max.is_some() || self.memory_low.is_some() {
+            let memory = spec.memory.get_or_insert(MemoryController {
+                min: None,
+                low: None,
+                high: None,
+                max: None,
+            });
+            memory.max = self.memory_max.or(memory.max);
+            memory.low = self.memory_low.or(memory.low);
+        }
+    }
+}
+
+/// The weight of allocated cells is applied as cpu shares, which libcgroups
+/// converts to a `cpu.weight`, so the weight is converted the same way.
+fn cpu_weight_from_shares(weight: Weight) -> u64 {
+    1 + (weight.into_inner().saturating_sub(2) * 9999) / 262142
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-settings-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        dir
+    }
+
+    fn read(dir: &Path, file: &str) -> Option<String> {
+        fs::read_to_string(dir.join(file)).ok()
+    }
+
+    #[test]
+    fn test_writes_only_set_values() {
+        let dir = test_dir();
+
+        CgroupSettings {
+            memory_max: Some(Limit::new(1 << 20)),
+            pids_max: Some(Limit::new(32)),
+            ..Default::default()
+        }
+        .write(&dir)
+        .expect("failed to write settings");
+
+        assert_eq!(read(&dir, "memory.max").as_deref(), Some("1048576"));
+        assert_eq!(read(&dir, "pids.max").as_deref(), Some("32"));
+        for file in ["cpu.weight", "cpu.max", "memory.low", "io.max"] {
+            assert_eq!(read(&dir, file), None, "{file}");
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_writes_cpu_values_like_allocate() {
+        let dir = test_dir();
+
+        CgroupSettings {
+            cpu_weight: Some(Weight::new(200)),
+            cpu_max: Some(Limit::new(0)),
+            ..Default::default()
+        }
+        .write(&dir)
+        .expect("failed to write settings");
+
+        assert_eq!(read(&dir, "cpu.weight").as_deref(), Some("8"));
+        assert_eq!(read(&dir, "cpu.max").as_deref(), Some("max"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_io_max_line_has_only_set_limits() {
+        let limit = IoLimit {
+            major: 8,
+            minor: 0,
+            rbps: Some(1024),
+            wbps: None,
+            riops: None,
+            wiops: Some(10),
+        };
+        assert_eq!(
+            limit.io_max_line().as_deref(),
+            Some("8:0 rbps=1024 wiops=10")
+        );
+
+        let unset = IoLimit { rbps: None, wiops: None, ..limit };
+        assert_eq!(unset.io_max_line(), None);
+    }
+}
@@ -0,0 +1,259 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Parsing of the cgroup v2 interface files used to report cell resource usage.
+//!
+//! Every value is optional: interface files that are missing (e.g.
+//! `memory.peak` on kernels older than 5.19, or a controller that is not
+//! enabled) are reported as [None] rather than failing the whole read.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CgroupStats {
+    pub cpu: CpuStats,
+    pub memory: MemoryStats,
+    pub pids: PidsStats,
+}
+
+/// Values from `cpu.stat`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuStats {
+    pub usage_usec: Option<u64>,
+    pub throttled_usec: Option<u64>,
+}
+
+/// Values from `memory.current`, `memory.peak` and `memory.events`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub current: Option<u64>,
+    pub peak: Option<u64>,
+    pub oom: Option<u64>,
+    pub oom_kill: Option<u64>,
+}
+
+/// Values from `pids.current`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PidsStats {
+    pub current: Option<u64>,
+}
+
+impl CgroupStats {
+    /// Reads the stats from the cgroup directory at `path`.
+    /// Only errors if a file exists but can not be read.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let cpu_stat = read_flat_keyed(path.join("cpu.stat"))?;
+        let memory_events = read_flat_keyed(path.join("memory.events"))?;
+
+        Ok(Self {
+            cpu: CpuStats {
+                usage_usec: get_key(&cpu_stat, "usage_usec"),
+                throttled_usec: get_key(&cpu_stat, "throttled_usec"),
+            },
+            memory: MemoryStats {
+                current: read_single_value(path.join("memory.current"))?,
+                peak: read_single_value(path.join("memory.peak"))?,
+                oom: get_key(&memory_events, "oom"),
+                oom_kill: get_key(&memory_events, "oom_kill"),
+            },
+            pids: PidsStats {
+                current: read_single_value(path.join("pids.current"))?,
+            },
+        })
+    }
+}
+
+/// The counters of `memory.events`. Counters that the kernel does not expose
+/// are read as zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEvents {
+    pub low: u64,
+    pub high: u64,
+    pub max: u64,
+    pub oom: u64,
+    pub oom_kill: u64,
+}
+
+impl MemoryEvents {
+    /// Reads the counters from the cgroup directory at `path`.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let events = read_flat_keyed(path.join("memory.events"))?;
+        let get = |key| get_key(&events, key).unwrap_or_default();
+
+        Ok(Self {
+            low: get("low"),
+            high: get("high"),
+            max: get("max"),
+            oom: get("oom"),
+            oom_kill: get("oom_kill"),
+        })
+    }
+
+    /// Returns the events counted since `earlier` was read.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            low: self.low.saturating_sub(earlier.low),
+            high: self.high.saturating_sub(earlier.high),
+            max: self.max.saturating_sub(earlier.max),
+            oom: self.oom.saturating_sub(earlier.oom),
+            oom_kill: self.oom_kill.saturating_sub(earlier.oom_kill),
+        }
+    }
+}
+
+/// Reads a file, returning [None] if it does not exist.
+fn read_optional(path: PathBuf) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads a single value file (e.g. `memory.current`).
+/// The literal "max" and unparsable values are reported as [None].
+fn read_single_value(path: PathBuf) -> io::Result<Option<u64>> {
+    Ok(read_optional(path)?.and_then(|contents| contents.trim().parse().ok()))
+}
+
+/// Reads a flat keyed file (e.g. `cpu.stat`), where each line is
+/// `<key> <value>`.
+pub(super) fn read_flat_keyed(
+    path: PathBuf,
+) -> io::Result<Option<Vec<(String, u64)>>> {
+    Ok(read_optional(path)?.map(|contents| parse_flat_keyed(&contents)))
+}
+
+fn parse_flat_keyed(contents: &str) -> Vec<(String, u64)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+pub(super) fn get_key(
+    entries: &Option<Vec<(String, u64)>>,
+    key: &str,
+) -> Option<u64> {
+    entries
+        .as_ref()?
+        .iter()
+        .find_map(|(k, v)| if k == key { Some(*v) } else { None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-stats-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        dir
+    }
+
+    #[test]
+    fn test_read_all_files() {
+        let dir = test_dir();
+        fs::write(
+            dir.join("cpu.stat"),
+            "usage_usec 1234\nuser_usec 1000\nsystem_usec 234\nnr_periods 0\nnr_throttled 0\nthrottled_usec 56\n",
+        )
+        .unwrap();
+        fs::write(dir.join("memory.current"), "4096\n").unwrap();
+        fs::write(dir.join("memory.peak"), "8192\n").unwrap();
+        fs::write(
+            dir.join("memory.events"),
+            "low 0\nhigh 0\nmax 3\noom 2\noom_kill 1\noom_group_kill 0\n",
+        )
+        .unwrap();
+        fs::write(dir.join("pids.current"), "7\n").unwrap();
+
+        let stats = CgroupStats::read(&dir).expect("failed to read stats");
+        assert_eq!(
+            stats,
+            CgroupStats {
+                cpu: CpuStats {
+                    usage_usec: Some(1234),
+                    throttled_usec: Some(56)
+                },
+                memory: MemoryStats {
+                    current: Some(4096),
+                    peak: Some(8192),
+                    oom: Some(2),
+                    oom_kill: Some(1),
+                },
+                pids: PidsStats { current: Some(7) },
+            }
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_files_are_unset() {
+        let dir = test_dir();
+        fs::write(dir.join("cpu.stat"), "usage_usec 10\n").unwrap();
+        fs::write(dir.join("memory.current"), "max\n").unwrap();
+
+        let stats = CgroupStats::read(&dir).expect("failed to read stats");
+        assert_eq!(
+            stats,
+            CgroupStats {
+                cpu: CpuStats { usage_usec: Some(10), throttled_usec: None },
+                memory: MemoryStats::default(),
+                pids: PidsStats::default(),
+            }
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_memory_events_since() {
+        let dir = test_dir();
+        assert_eq!(
+            MemoryEvents::read(&dir).expect("failed to read events"),
+            MemoryEvents::default()
+        );
+
+        fs::write(
+            dir.join("memory.events"),
+            "low 1\nhigh 0\nmax 3\noom 2\noom_kill 1\noom_group_kill 0\n",
+        )
+        .unwrap();
+        let earlier = MemoryEvents::read(&dir).expect("failed to read events");
+
+        fs::write(
+            dir.join("memory.events"),
+            "low 1\nhigh 0\nmax 7\noom 4\noom_kill 2\noom_group_kill 0\n",
+        )
+        .unwrap();
+        let later = MemoryEvents::read(&dir).expect("failed to read events");
+
+        assert_eq!(
+            later.since(&earlier),
+            MemoryEvents { low: 0, high: 0, max: 4, oom: 2, oom_kill: 1 }
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,559 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The cgroup v1 backend, used on hosts with a legacy or hybrid hierarchy.
+//!
+//! A cell gets a directory, with a leaf directory for its processes, in each
+//! of the [CONTROLLERS] hierarchies. This mirrors the layout used on cgroup v2.
+
+use super::{
+    error::{CgroupsError, Result},
+    CgroupSpec, CpuController, CpusetController, DeviceRule, MemoryController,
+};
+use crate::cells::cell_service::cells::CellName;
+use nix::{
+    errno::Errno,
+    sys::signal::{kill, Signal},
+    unistd::{access, AccessFlags, Pid},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The hierarchies a cell is created in, by the name they are mounted as.
+/// `cpu` is usually a link to the hierarchy shared with `cpuacct`.
+pub(super) const CONTROLLERS: [&str; 5] =
+    ["cpu", "cpuset", "devices", "memory", "pids"];
+
+/// The name of the leaf directory, which is invalid in a [CellName].
+const LEAF: &str = "_";
+
+/// How many times an emptied cgroup is attempted to be removed.
+const REMOVE_ATTEMPTS: u32 = 5;
+
+/// How often the processes of a cell are checked while waiting for it to be
+/// empty, as cgroup v1 has no `cgroup.events` to watch.
+const EMPTY_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Fails with [CgroupsError::Unsupported] for the first field of `spec` that
+/// has no cgroup v1 equivalent.
+pub(super) fn check_spec(
+    cell_name: &CellName,
+    spec: &CgroupSpec,
+) -> Result<()> {
+    let unsupported = |feature: &str| {
+        Err(CgroupsError::Unsupported {
+            cell_name: cell_name.clone(),
+            feature: feature.into(),
+        })
+    };
+
+    if let Some(MemoryController { min, high, .. }) = &spec.memory {
+        if min.is_some() {
+            return unsupported("memory.min");
+        }
+        if high.is_some() {
+            return unsupported("memory.high");
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the cgroups of the cell in every hierarchy under `root`, applies
+/// `spec` and moves `pid` into them. Created cgroups are removed on error.
+pub(super) fn create(
+    root: &Path,
+    cell_name: &CellName,
+    spec: &CgroupSpec,
+    pid: Pid,
+) -> Result<()> {
+    check_spec(cell_name, spec)?;
+
+    let result = CONTROLLERS
+        .iter()
+        .try_for_each(|controller| create_in(root, controller, cell_name, spec))
+        .and_then(|_| add_task(root, cell_name, pid));
+
+    if result.is_err() {
+        let _ = delete(root, cell_name);
+    }
+
+    result
+}
+
+/// Applies the cpu, cpuset and memory values of `spec` to the existing
+/// cgroups of the cell under `root`.
+pub(super) fn update(
+    root: &Path,
+    cell_name: &CellName,
+    spec: &CgroupSpec,
+) -> Result<()> {
+    check_spec(cell_name, spec)?;
+
+    let update_error = |e: io::Error| CgroupsError::UpdateCgroup {
+        cell_name: cell_name.clone(),
+        source: e.into(),
+    };
+    let path =
+        |controller: &str| root.join(controller).join(cell_name.as_inner());
+
+    apply_cpu(&path("cpu"), spec.cpu.as_ref()).map_err(update_error)?;
+
+    // The leaf copied the cpuset of the cell when it was created, so it is
+    // updated as well, after the cell, as it may only use what the cell has
+    let cpuset = path("cpuset");
+    apply_cpuset(&cpuset, spec.cpuset.as_ref())
+        .and_then(|_| apply_cpuset(&cpuset.join(LEAF), spec.cpuset.as_ref()))
+        .map_err(update_error)?;
+
+    apply_memory(&path("memory"), spec.memory.as_ref()).map_err(update_error)
+}
+
+fn create_in(
+    root: &Path,
+    controller: &str,
+    cell_name: &CellName,
+    spec: &CgroupSpec,
+) -> Result<()> {
+    let create_error = |e: io::Error| CgroupsError::CreateCgroup {
+        cell_name: cell_name.clone(),
+        source: anyhow::Error::from(e)
+            .context(format!("in the {controller} hierarchy")),
+    };
+
+    let path = root.join(controller).join(cell_name.as_inner());
+
+    // The cgroup of the parent cell must exist, so create_dir_all is not used
+    fs::create_dir(&path).map_err(create_error)?;
+
+    match controller {
+        "cpu" => apply_cpu(&path, spec.cpu.as_ref()),
+        "cpuset" => inherit_cpuset(&path)
+            .and_then(|_| apply_cpuset(&path, spec.cpuset.as_ref())),
+        "devices" => apply_devices(&path, &spec.device_allow),
+        "memory" => apply_memory(&path, spec.memory.as_ref()),
+        _ => Ok(()),
+    }
+    .map_err(create_error)?;
+
+    let leaf = path.join(LEAF);
+    fs::create_dir(&leaf).map_err(create_error)?;
+
+    if controller == "cpuset" {
+        inherit_cpuset(&leaf).map_err(create_error)?;
+    }
+
+    Ok(())
+}
+
+fn apply_cpu(path: &Path, cpu: Option<&CpuController>) -> io::Result<()> {
+    let Some(CpuController { weight, max, period }) = cpu else {
+        return Ok(());
+    };
+
+    // Like the v2 backend, the weight is applied as shares
+    if let Some(weight) = weight {
+        fs::write(path.join("cpu.shares"), weight.into_inner().to_string())?;
+    }
+
+    // The quota is checked against the period, so the period is set first
+    if let Some(period) = period {
+        fs::write(path.join("cpu.cfs_period_us"), period.to_string())?;
+    }
+
+    if let Some(max) = max {
+        fs::write(path.join("cpu.cfs_quota_us"), max.into_inner().to_string())?;
+    }
+
+    Ok(())
+}
+
+fn apply_cpuset(
+    path: &Path,
+    cpuset: Option<&CpusetController>,
+) -> io::Result<()> {
+    let Some(CpusetController { cpus, mems }) = cpuset else {
+        return Ok(());
+    };
+
+    if let Some(cpus) = cpus {
+        fs::write(path.join("cpuset.cpus"), cpus.clone().into_inner())?;
+    }
+
+    if let Some(mems) = mems {
+        fs::write(path.join("cpuset.mems"), mems.clone().into_inner())?;
+    }
+
+    Ok(())
+}
+
+/// A cell with rules may only access the devices they allow. A new cgroup
+/// copies the rules of its parent, so they apply to the leaf as well.
+fn apply_devices(path: &Path, device_allow: &[DeviceRule]) -> io::Result<()> {
+    if device_allow.is_empty() {
+        return Ok(());
+    }
+
+    fs::write(path.join("devices.deny"), "a")?;
+    for rule in device_allow {
+        fs::write(path.join("devices.allow"), rule.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn apply_memory(
+    path: &Path,
+    memory: Option<&MemoryController>,
+) -> io::Result<()> {
+    let Some(MemoryController { low, max, .. }) = memory else {
+        return Ok(());
+    };
+
+    // Like the v2 backend, the low protection is applied as a reservation
+    if let Some(low) = low {
+        fs::write(
+            path.join("memory.soft_limit_in_bytes"),
+            low.into_inner().to_string(),
+        )?;
+    }
+
+    if let Some(max) = max {
+        fs::write(
+            path.join("memory.limit_in_bytes"),
+            max.into_inner().to_string(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A new cpuset cgroup has no cpus or mems, and no process can join it until
+/// they are set, so they are copied from the parent.
+fn inherit_cpuset(path: &Path) -> io::Result<()> {
+    let parent = path.parent().expect("cgroup has a parent");
+
+    for file in ["cpuset.cpus", "cpuset.mems"] {
+        let current = fs::read_to_string(path.join(file)).unwrap_or_default();
+        if current.trim().is_empty() {
+            fs::write(path.join(file), fs::read_to_string(parent.join(file))?)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves `pid` into the leaf cgroups of the cell.
+pub(super) fn add_task(
+    root: &Path,
+    cell_name: &CellName,
+    pid: Pid,
+) -> Result<()> {
+    for controller in CONTROLLERS {
+        let procs = leaf_path(root, controller, cell_name).join("cgroup.procs");
+        fs::write(procs, pid.to_string()).map_err(|e| {
+            CgroupsError::AddTaskToCgroup {
+                cell_name: cell_name.clone(),
+                source: anyhow::Error::from(e)
+                    .context(format!("in the {controller} hierarchy")),
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Removes the cgroups of the cell from every hierarchy, killing any
+/// processes that remain in them. Missing cgroups are ignored.
+pub(super) fn delete(root: &Path, cell_name: &CellName) -> Result<()> {
+    let mut result = Ok(());
+
+    for controller in CONTROLLERS {
+        let path = root.join(controller).join(cell_name.as_inner());
+
+        for path in [path.join(LEAF), path] {
+            if let Err(e) = remove(&path) {
+                if result.is_ok() {
+                    result = Err(CgroupsError::DeleteCgroup {
+                        cell_name: cell_name.clone(),
+                        source: anyhow::Error::from(e)
+                            .context(format!("in the {controller} hierarchy")),
+                    });
+                }
+            }
+        }
+    }
+
+    result
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+
+        match fs::remove_dir(path) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if attempts >= REMOVE_ATTEMPTS => return Err(e),
+            Err(_) => {}
+        }
+
+        // A cgroup can only be removed once it has no processes
+        kill_procs(path)?;
+
+        thread::sleep(Duration::from_millis(10 * u64::from(attempts)));
+    }
+}
+
+/// Waits up to `timeout` for the leaf cgroup of the cell to have no
+/// processes. Returns false if some are left when it expires.
+pub(super) fn wait_empty(
+    root: &Path,
+    cell_name: &CellName,
+    timeout: Duration,
+) -> io::Result<bool> {
+    let procs = leaf_path(root, CONTROLLERS[0], cell_name).join("cgroup.procs");
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match fs::read_to_string(&procs) {
+            Ok(procs) if procs.trim().is_empty() => return Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+            Err(e) => return Err(e),
+            Ok(_) => {}
+        }
+
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+
+        thread::sleep(EMPTY_POLL_INTERVAL);
+    }
+}
+
+/// Kills the processes in the leaf cgroups of the cell.
+pub(super) fn kill_all(root: &Path, cell_name: &CellName) -> io::Result<()> {
+    for controller in CONTROLLERS {
+        kill_procs(&leaf_path(root, controller, cell_name))?;
+    }
+
+    Ok(())
+}
+
+fn kill_procs(path: &Path) -> io::Result<()> {
+    let procs =
+        fs::read_to_string(path.join("cgroup.procs")).unwrap_or_default();
+    for pid in procs.lines().filter_map(|pid| pid.parse().ok()) {
+        match kill(Pid::from_raw(pid), Signal::SIGKILL) {
+            Ok(()) | Err(Errno::ESRCH) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that cells can be created in each of the [CONTROLLERS]
+/// hierarchies under `root`.
+pub(super) fn check_writable(root: &Path) -> io::Result<()> {
+    CONTROLLERS.iter().try_for_each(|controller| {
+        let path = root.join(controller);
+        access(&path, AccessFlags::W_OK).map_err(|e| {
+            let e = io::Error::from(e);
+            io::Error::new(e.kind(), format!("{}: {e}", path.display()))
+        })
+    })
+}
+
+pub(super) fn exists(root: &Path, cell_name: &CellName) -> bool {
+    root.join(CONTROLLERS[0]).join(cell_name.as_inner()).exists()
+}
+
+/// The path of the leaf cgroup of the cell in the `controller` hierarchy.
+pub(super) fn leaf_path(
+    root: &Path,
+    controller: &str,
+    cell_name: &CellName,
+) -> PathBuf {
+    root.join(controller).join(cell_name.as_inner()).join(LEAF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::cells::cgroups::{
+        cpuset::Cpus, DeviceAccess, DeviceType, Limit, Protection, Weight,
+    };
+
+    /// Creates a fake v1 root, whose root cpuset has cpus and mems
+    fn test_root() -> PathBuf {
+        let root = std::env::temp_dir()
+            .join(format!("ae-test-cgroup-v1-{}", uuid::Uuid::new_v4()));
+        for controller in CONTROLLERS {
+            fs::create_dir_all(root.join(controller)).unwrap();
+        }
+        fs::write(root.join("cpuset/cpuset.cpus"), "0-3\n").unwrap();
+        fs::write(root.join("cpuset/cpuset.mems"), "0\n").unwrap();
+        root
+    }
+
+    fn read(path: PathBuf) -> String {
+        fs::read_to_string(path).unwrap().trim().to_string()
+    }
+
+    fn spec() -> CgroupSpec {
+        CgroupSpec {
+            cpu: None,
+            cpuset: None,
+            memory: None,
+            device_allow: vec![],
+        }
+    }
+
+    #[test]
+    fn test_create_applies_spec() {
+        let root = test_root();
+        let cell_name = CellName::random_for_tests();
+        let spec = CgroupSpec {
+            cpu: Some(CpuController {
+                weight: Some(Weight::new(100)),
+                max: Some(Limit::new(50000)),
+                period: Some(100000),
+            }),
+            cpuset: Some(CpusetController {
+                cpus: Some(Cpus::new("1".into())),
+                mems: None,
+            }),
+            memory: Some(MemoryController {
+                min: None,
+                low: Some(Protection::new(1024)),
+                high: None,
+                max: Some(Limit::new(4096)),
+            }),
+            device_allow: vec![DeviceRule {
+                device_type: DeviceType::Char,
+                major: Some(1),
+                minor: Some(3),
+                access: DeviceAccess { read: true, write: true, mknod: false },
+            }],
+        };
+
+        create(&root, &cell_name, &spec, Pid::this()).unwrap();
+
+        let cell =
+            |controller: &str| root.join(controller).join(cell_name.as_inner());
+        assert_eq!(read(cell("cpu").join("cpu.shares")), "100");
+        assert_eq!(read(cell("cpu").join("cpu.cfs_period_us")), "100000");
+        assert_eq!(read(cell("cpu").join("cpu.cfs_quota_us")), "50000");
+        assert_eq!(
+            read(cell("memory").join("memory.soft_limit_in_bytes")),
+            "1024"
+        );
+        assert_eq!(read(cell("memory").join("memory.limit_in_bytes")), "4096");
+        assert_eq!(read(cell("devices").join("devices.deny")), "a");
+        assert_eq!(read(cell("devices").join("devices.allow")), "c 1:3 rw");
+
+        // cpus is set by the spec, mems is inherited, and the leaf inherits both
+        assert_eq!(read(cell("cpuset").join("cpuset.cpus")), "1");
+        assert_eq!(read(cell("cpuset").join("cpuset.mems")), "0");
+        assert_eq!(read(cell("cpuset").join("_/cpuset.cpus")), "1");
+        assert_eq!(read(cell("cpuset").join("_/cpuset.mems")), "0");
+
+        for controller in CONTROLLERS {
+            let procs =
+                leaf_path(&root, controller, &cell_name).join("cgroup.procs");
+            assert_eq!(read(procs), Pid::this().to_string());
+        }
+        assert!(exists(&root, &cell_name));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_create_nested() {
+        let root = test_root();
+        let parent = CellName::random_for_tests();
+        let child = CellName::random_child_for_tests(&parent);
+
+        create(&root, &parent, &spec(), Pid::this()).unwrap();
+        create(&root, &child, &spec(), Pid::this()).unwrap();
+        assert_eq!(
+            read(leaf_path(&root, "cpuset", &child).join("cpuset.cpus")),
+            "0-3"
+        );
+
+        // A child without a parent can't be created
+        let orphan =
+            CellName::random_child_for_tests(&CellName::random_for_tests());
+        assert!(matches!(
+            create(&root, &orphan, &spec(), Pid::this()),
+            Err(CgroupsError::CreateCgroup { .. })
+        ));
+        assert!(!exists(&root, &orphan));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_wait_empty() {
+        let root = test_root();
+        let cell_name = CellName::random_for_tests();
+        create(&root, &cell_name, &spec(), Pid::this()).unwrap();
+
+        let timeout = Duration::from_millis(50);
+        assert!(!wait_empty(&root, &cell_name, timeout).unwrap());
+
+        let procs =
+            leaf_path(&root, CONTROLLERS[0], &cell_name).join("cgroup.procs");
+        fs::write(procs, "").unwrap();
+        assert!(wait_empty(&root, &cell_name, timeout).unwrap());
+
+        // A removed cell is empty
+        fs::remove_dir_all(&root).unwrap();
+        assert!(wait_empty(&root, &cell_name, timeout).unwrap());
+    }
+
+    #[test]
+    fn test_unsupported_fields() {
+        let root = test_root();
+        let cell_name = CellName::random_for_tests();
+        let spec = CgroupSpec {
+            cpu: None,
+            cpuset: None,
+            memory: Some(MemoryController {
+                min: None,
+                low: None,
+                high: Some(Limit::new(4096)),
+                max: None,
+            }),
+            device_allow: vec![],
+        };
+
+        let Err(CgroupsError::Unsupported { feature, .. }) =
+            create(&root, &cell_name, &spec, Pid::this())
+        else {
+            panic!("memory.high must be unsupported");
+        };
+        assert_eq!(feature, "memory.high");
+        assert!(!exists(&root, &cell_name));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -13,7 +13,7 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use super::{cgroups::error::CgroupsError, CellName};
+use super::{cgroups::error::CgroupsError, CellName, SpecChange};
 use std::io;
 use thiserror::Error;
 use tracing::error;
@@ -24,6 +24,23 @@ pub type Result<T> = std::result::Result<T, CellsError>;
 pub enum CellsError {
     #[error("cell '{cell_name}' already exists'")]
     CellExists { cell_name: CellName },
+    #[error(
+        "cell '{cell_name}' already exists with a different spec: {}",
+        join_changes(changes)
+    )]
+    CellExistsWithDifferentSpec {
+        cell_name: CellName,
+        changes: Vec<SpecChange>,
+    },
+    #[error(
+        "cell '{cell_name}' can not be updated in place: {}",
+        join_changes(changes)
+    )]
+    ImmutableCellChange { cell_name: CellName, changes: Vec<SpecChange> },
+    #[error(
+        "cell '{cell_name}' can not reserve port {port}: reserved by cell '{owner}'"
+    )]
+    PortReserved { cell_name: CellName, port: u16, owner: CellName },
     #[error("cell '{cell_name}' not found")]
     CellNotFound { cell_name: CellName },
     #[error("cell '{cell_name}' is not allocated")]
@@ -32,14 +49,41 @@ pub enum CellsError {
     FailedToAllocateCell { cell_name: CellName, source: io::Error },
     #[error("cell '{cell_name}' allocation was aborted: {source}")]
     AbortedAllocateCell { cell_name: CellName, source: CgroupsError },
+    #[error("cell '{cell_name}' could not be updated: {source}")]
+    FailedToUpdateCell { cell_name: CellName, source: CgroupsError },
     #[error("cell '{cell_name}' could not kill children: {source}")]
     FailedToKillCellChildren { cell_name: CellName, source: io::Error },
+    #[error("cell '{cell_name}' could not be adopted: {source}")]
+    FailedToAdoptCell { cell_name: CellName, source: io::Error },
     #[error("cell '{cell_name}' could not be freed: {source}")]
     FailedToFreeCell { cell_name: CellName, source: CgroupsError },
+    #[error(
+        "cell '{cell_name}' has running executables: {}",
+        executable_names.join(", ")
+    )]
+    CellHasRunningExecutables {
+        cell_name: CellName,
+        executable_names: Vec<String>,
+    },
+    #[error(
+        "cell '{cell_name}' has nested cells: {}",
+        nested_cells
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )]
+    CellHasNestedCells { cell_name: CellName, nested_cells: Vec<CellName> },
+    #[error("cell '{cell_name}' stats could not be read: {source}")]
+    FailedToReadStats { cell_name: CellName, source: CgroupsError },
     #[error(
         "cgroup '{cell_name}' exists on host, but is not controlled by auraed"
     )]
     CgroupIsNotACell { cell_name: CellName },
     #[error("cgroup '{cell_name}` not found on host")]
     CgroupNotFound { cell_name: CellName },
-}
\ No newline at end of file
+}
+
+fn join_changes(changes: &[SpecChange]) -> String {
+    changes.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
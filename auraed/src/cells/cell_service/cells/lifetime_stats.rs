@@ -0,0 +1,147 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The usage of a cell over its lifetime, for capacity planning. Unlike the
+//! stats read from the cgroup of a cell on request, the counters are kept as
+//! its executables come and go, and are only reset when the cell is freed,
+//! along with the [Cell] holding them.
+//!
+//! The CPU time and OOM kills are sampled from the cgroup of the cell every
+//! lifetime stats interval of auraed, and when one of its executables is
+//! stopped, so they lag behind the cgroup by at most one interval.
+//!
+//! [Cell]: super::Cell
+
+use super::cgroups::CgroupStats;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+/// How often the lifetime stats of the cells are sampled, unless auraed is
+/// configured otherwise.
+pub const DEFAULT_LIFETIME_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifetimeStats {
+    /// The CPU time consumed by the cell, from `cpu.stat`.
+    pub cpu_usage_usec: u64,
+    /// The processes of the cell killed by the OOM killer, from
+    /// `memory.events`.
+    pub oom_kills: u64,
+    /// The executables started in the cell, restarts included.
+    pub executables_started: u64,
+    /// The executables stopped in the cell.
+    pub executables_stopped: u64,
+    /// The starts of each executable started in the cell, by name.
+    pub starts: BTreeMap<String, ExecutableStarts>,
+    /// When the cgroup of the cell was last sampled.
+    pub sampled_at: Option<SystemTime>,
+}
+
+/// The starts of an executable of the same name in a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutableStarts {
+    /// The starts after the first.
+    pub restarts: u64,
+    pub last_started_at: SystemTime,
+}
+
+impl LifetimeStats {
+    /// Records the start of the executable `executable_name`, which is a
+    /// restart if one of the same name was started in the cell before.
+    pub fn record_start(&mut self, executable_name: &str, at: SystemTime) {
+        self.executables_started += 1;
+        match self.starts.get_mut(executable_name) {
+            Some(starts) => {
+                starts.restarts += 1;
+                starts.last_started_at = at;
+            }
+            None => {
+                let _ = self.starts.insert(
+                    executable_name.to_string(),
+                    ExecutableStarts { restarts: 0, last_started_at: at },
+                );
+            }
+        }
+    }
+
+    pub fn record_stop(&mut self) {
+        self.executables_stopped += 1;
+    }
+
+    /// Records the counters read from the cgroup of the cell. Those the
+    /// cgroup does not expose, e.g. of a disabled controller, keep their
+    /// last value.
+    pub fn sample(&mut self, stats: &CgroupStats, at: SystemTime) {
+        // The counters of a cgroup only grow, even as its processes exit
+        if let Some(usage_usec) = stats.cpu.usage_usec {
+            self.cpu_usage_usec = self.cpu_usage_usec.max(usage_usec);
+        }
+        if let Some(oom_kill) = stats.memory.oom_kill {
+            self.oom_kills = self.oom_kills.max(oom_kill);
+        }
+        self.sampled_at = Some(at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::cells::cgroups::stats::{
+        CpuStats, MemoryStats,
+    };
+
+    fn stats(usage_usec: Option<u64>, oom_kill: Option<u64>) -> CgroupStats {
+        CgroupStats {
+            cpu: CpuStats { usage_usec, throttled_usec: None },
+            memory: MemoryStats { oom_kill, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_start_counts_restarts_by_name() {
+        let mut lifetime_stats = LifetimeStats::default();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        lifetime_stats.record_start("web", at(1));
+        lifetime_stats.record_start("worker", at(2));
+        lifetime_stats.record_start("web", at(3));
+        lifetime_stats.record_stop();
+
+        assert_eq!(lifetime_stats.executables_started, 3);
+        assert_eq!(lifetime_stats.executables_stopped, 1);
+        assert_eq!(
+            lifetime_stats.starts["web"],
+            ExecutableStarts { restarts: 1, last_started_at: at(3) }
+        );
+        assert_eq!(lifetime_stats.starts["worker"].restarts, 0);
+    }
+
+    #[test]
+    fn test_sample_keeps_the_counters_the_cgroup_does_not_expose() {
+        let mut lifetime_stats = LifetimeStats::default();
+        let at = SystemTime::UNIX_EPOCH;
+
+        lifetime_stats.sample(&stats(Some(500), Some(2)), at);
+        lifetime_stats.sample(&stats(None, None), at);
+        assert_eq!(lifetime_stats.cpu_usage_usec, 500);
+        assert_eq!(lifetime_stats.oom_kills, 2);
+
+        lifetime_stats.sample(&stats(Some(800), Some(3)), at);
+        assert_eq!(lifetime_stats.cpu_usage_usec, 800);
+        assert_eq!(lifetime_stats.oom_kills, 3);
+        assert_eq!(lifetime_stats.sampled_at, Some(at));
+    }
+}
@@ -13,27 +13,69 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-pub use cell::Cell;
+pub use cell::{Cell, DEFAULT_FREE_TIMEOUT};
+pub use cell_info::{CellInfo, CELL_INFO_ENV};
 pub use cell_name::CellName;
 pub use cells::Cells;
 pub use cells_cache::CellsCache;
+use super::executables::UserNamespace;
 use cgroups::CgroupSpec;
 pub use error::{CellsError, Result};
-pub use nested_auraed::IsolationControls;
+pub use lifetime_stats::{
+    ExecutableStarts, LifetimeStats, DEFAULT_LIFETIME_STATS_INTERVAL,
+};
+pub use nested_auraed::{bring_up_loopback, Hostname, IsolationControls};
+use nix::unistd::Pid;
+pub use spec_change::SpecChange;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
 
 mod cell;
+mod cell_info;
 mod cell_name;
 #[allow(clippy::module_inception)]
 mod cells;
 mod cells_cache;
 pub mod cgroups;
 mod error;
+mod lifetime_stats;
 mod nested_auraed;
+mod spec_change;
 
 #[derive(Debug, Clone)]
 pub struct CellSpec {
     pub cgroup_spec: CgroupSpec,
     pub iso_ctl: IsolationControls,
+    /// Substituted in the commands of the executables started in the cell.
+    pub variables: BTreeMap<String, String>,
+    /// The ports no other cell may reserve, exported to the executables
+    /// started in the cell.
+    pub reserved_ports: BTreeSet<u16>,
+    /// The uid the executables started in the cell run as, unless the start
+    /// request sets one.
+    pub default_uid: Option<u32>,
+    /// The gid the executables started in the cell run as, unless the start
+    /// request sets one.
+    pub default_gid: Option<u32>,
+    /// The user namespace the executables started in the cell run in, with
+    /// their ids mapped to those of the host.
+    pub user_namespace: Option<UserNamespace>,
+    /// Start each executable of the cell in a leaf cgroup of its own, to
+    /// account for its usage apart from the other executables.
+    pub per_executable_accounting: bool,
+}
+
+/// A cell allocated by a previous auraed, whose nested auraed is still
+/// running.
+#[derive(Debug, Clone)]
+pub struct CellAdoption {
+    pub spec: CellSpec,
+    pub nested_auraed_pid: Pid,
+    /// [None] if it was not recorded.
+    pub nested_auraed_start_time: Option<u64>,
+    pub client_socket: PathBuf,
 }
 
 impl CellSpec {
@@ -57,11 +99,20 @@ impl CellSpec {
                     high: None,
                     max: Some(Limit::new(1000000)),
                 }),
+                device_allow: vec![],
             },
             iso_ctl: IsolationControls {
                 isolate_network: false,
                 isolate_process: false,
+                isolate_uts: false,
+                hostname: None,
             },
+            variables: BTreeMap::new(),
+            reserved_ports: BTreeSet::new(),
+            default_uid: None,
+            default_gid: None,
+            user_namespace: None,
+            per_executable_accounting: false,
         }
     }
 }
\ No newline at end of file
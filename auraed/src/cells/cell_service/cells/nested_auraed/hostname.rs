@@ -0,0 +1,119 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use std::{
+    fmt::{Display, Formatter},
+    ops::Deref,
+};
+use validation::{ValidatedField, ValidationError};
+
+/// HOST_NAME_MAX on Linux
+const MAX_LENGTH: u64 = 64;
+
+/// A hostname of dot separated labels, following the rules of RFC 1123.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Hostname(String);
+
+impl Hostname {
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl ValidatedField<String> for Hostname {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        validation::maximum_length(
+            input.as_bytes(),
+            MAX_LENGTH,
+            "bytes",
+            field_name,
+            parent_name,
+        )?;
+
+        for label in input.split('.') {
+            validation::allow_regex(
+                label,
+                &validation::DOMAIN_NAME_LABEL_REGEX,
+                field_name,
+                parent_name,
+            )?;
+        }
+
+        Ok(Self(input))
+    }
+}
+
+impl Deref for Hostname {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Hostname {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(input: &str) -> Result<Hostname, ValidationError> {
+        Hostname::validate(Some(input.into()), "hostname", None)
+    }
+
+    #[test]
+    fn test_validation_success() {
+        for input in ["web", "web-1", "1web", "web.example.com", "a"] {
+            assert_eq!(&*validate(input).unwrap(), input);
+        }
+        assert!(validate(&"a".repeat(63)).is_ok());
+    }
+
+    #[test]
+    fn test_validation_failure() {
+        assert!(matches!(validate(""), Err(ValidationError::Required { .. })));
+
+        for input in
+            ["-web", "web-", "web_1", "web..com", ".web", "web.", "web/1"]
+        {
+            assert!(
+                matches!(
+                    validate(input),
+                    Err(ValidationError::AllowRegexViolation { .. })
+                ),
+                "{input}"
+            );
+        }
+
+        assert!(matches!(
+            validate(&"a".repeat(64)),
+            Err(ValidationError::AllowRegexViolation { .. })
+        ));
+        assert!(matches!(
+            validate(&["a"; 33].join(".")),
+            Err(ValidationError::Maximum { .. })
+        ));
+    }
+}
@@ -13,6 +13,7 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
+use super::Hostname;
 use libc::c_char;
 use std::io::{self};
 use std::path::PathBuf;
@@ -22,6 +23,17 @@ use tracing::info;
 pub struct IsolationControls {
     pub isolate_process: bool,
     pub isolate_network: bool,
+    pub isolate_uts: bool,
+    /// Defaults to the last component of the name of the cell.
+    /// Implies [Self::isolate_uts].
+    pub hostname: Option<Hostname>,
+}
+
+impl IsolationControls {
+    /// Returns true if the cell gets its own UTS namespace, and hostname.
+    pub fn isolates_uts(&self) -> bool {
+        self.isolate_uts || self.isolate_process || self.hostname.is_some()
+    }
 }
 
 #[derive(Default)]
@@ -73,11 +85,11 @@ impl Isolation {
         )
         .map_err(|e| io::Error::from_raw_os_error(e as i32))?;
 
-        // We are in a new UTS namespace so we manage hostname and domainname.
-        // hostname and domainname both allow null bytes and are not required to be null terminated.
+        // We are in a new UTS namespace so we manage the domainname.
+        // It allows null bytes and is not required to be null terminated.
         if unsafe {
             #[allow(trivial_casts)]
-            libc::sethostname(
+            libc::setdomainname(
                 self.name.as_ptr() as *const c_char,
                 self.name.len(),
             )
@@ -85,18 +97,31 @@ impl Isolation {
         {
             return Err(io::Error::last_os_error());
         }
+        Ok(())
+    }
 
-        // Set domainname
+    pub fn isolate_uts(
+        &mut self,
+        iso_ctl: &IsolationControls,
+    ) -> io::Result<()> {
+        if !iso_ctl.isolates_uts() {
+            return Ok(());
+        }
+
+        // We are in a new UTS namespace so we manage the hostname.
+        // It allows null bytes and is not required to be null terminated.
+        let hostname = iso_ctl.hostname.as_deref().unwrap_or(&self.name);
         if unsafe {
             #[allow(trivial_casts)]
-            libc::setdomainname(
-                self.name.as_ptr() as *const c_char,
-                self.name.len(),
+            libc::sethostname(
+                hostname.as_ptr() as *const c_char,
+                hostname.len(),
             )
         } == -1
         {
             return Err(io::Error::last_os_error());
         }
+        info!("Isolation: Set hostname in cell");
         Ok(())
     }
 
@@ -107,7 +132,88 @@ impl Isolation {
         if !iso_ctl.isolate_network {
             return Ok(());
         }
-        // Insert pre_exec network logic here
+
+        // The new network namespace only has a loopback interface, which is
+        // down. Bring it up, so services in the cell can reach each other
+        // over localhost.
+        bring_up_loopback()?;
+        info!("Isolation: Brought up loopback in cell");
         Ok(())
     }
+}
+
+/// Sets the `lo` interface of the current network namespace up. The kernel
+/// has already assigned it 127.0.0.1/8 and ::1.
+/// Only uses syscalls, so it can run between fork and exec.
+pub fn bring_up_loopback() -> io::Result<()> {
+    // SAFETY: socket takes no pointers, and the fd it returns is closed
+    // below.
+    let socket = unsafe {
+        libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0)
+    };
+    if socket == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: ifreq is plain data, for which all zeroes is a valid value.
+    let mut ifreq: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in ifreq.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as c_char;
+    }
+
+    // SAFETY: SIOCGIFFLAGS and SIOCSIFFLAGS read and write the ifreq, which
+    // outlives the calls, and whose name is nul terminated by the zeroes
+    // after "lo". The flags are the field of the union SIOCGIFFLAGS set.
+    let result = unsafe {
+        if libc::ioctl(socket, libc::SIOCGIFFLAGS, &mut ifreq) == -1 {
+            -1
+        } else {
+            ifreq.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+            libc::ioctl(socket, libc::SIOCSIFFLAGS, &ifreq)
+        }
+    };
+    let error = io::Error::last_os_error();
+    // SAFETY: the socket was opened above, and is not used after this.
+    let _ = unsafe { libc::close(socket) };
+
+    if result == -1 {
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn test_bring_up_loopback() {
+        // Creating a network namespace requires root
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+
+        // Only the thread moves into the new network namespace
+        std::thread::spawn(|| {
+            nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWNET)
+                .expect("failed to unshare");
+
+            // The loopback is down in the new namespace
+            let error = TcpStream::connect("127.0.0.1:1").unwrap_err();
+            assert_eq!(error.raw_os_error(), Some(libc::ENETUNREACH));
+
+            bring_up_loopback().expect("failed to bring up loopback");
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let _ = TcpStream::connect(listener.local_addr().unwrap())
+                .expect("localhost must be reachable");
+
+            // There is no other interface, so no route to the outside
+            let error = TcpStream::connect("192.0.2.1:80").unwrap_err();
+            assert_eq!(error.raw_os_error(), Some(libc::ENETUNREACH));
+        })
+        .join()
+        .unwrap();
+    }
 }
\ No newline at end of file
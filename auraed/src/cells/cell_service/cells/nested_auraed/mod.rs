@@ -13,9 +13,11 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-pub use isolation_controls::IsolationControls;
+pub use hostname::Hostname;
+pub use isolation_controls::{bring_up_loopback, IsolationControls};
 pub use nested_auraed::NestedAuraed;
 
+mod hostname;
 mod isolation_controls;
 #[allow(clippy::module_inception)]
 mod nested_auraed;
\ No newline at end of file
@@ -14,6 +14,9 @@
 \* -------------------------------------------------------------------------- */
 
 use super::isolation_controls::{Isolation, IsolationControls};
+use crate::cells::cell_service::cells::CELL_INFO_ENV;
+use crate::cells::cell_service::executables::UserNamespace;
+use crate::reaper::{self, pidfd_open, ManagedChild};
 use crate::AURAED_RUNTIME;
 use client::AuraeSocket;
 use clone3::Flags;
@@ -22,26 +25,52 @@ use nix::{
     sys::signal::{Signal, Signal::SIGKILL, Signal::SIGTERM},
     unistd::Pid,
 };
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::{
     io::{self, ErrorKind},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
     os::unix::process::{CommandExt, ExitStatusExt},
     process::{Command, ExitStatus},
 };
 use tracing::{error, info, trace};
 
+/// The variable the ports reserved by a cell are exported to its
+/// executables in, comma separated.
+const PORTS_ENV: &str = "AURAE_PORTS";
+
 #[derive(Debug)]
 pub struct NestedAuraed {
     process: procfs::process::Process,
-    #[allow(unused)]
-    pidfd: i32,
+    /// When the process started, in clock ticks since boot, which tells it
+    /// apart from a later process reusing its pid.
+    start_time: u64,
+    pidfd: OwnedFd,
     #[allow(unused)]
     iso_ctl: IsolationControls,
     pub client_socket: AuraeSocket,
+    /// Started by a previous auraed, so it is not our child and can't be
+    /// waited on.
+    adopted: bool,
+    /// Leaves the process for us to wait on, rather than the reaper. [None]
+    /// if adopted.
+    #[allow(unused)]
+    managed: Option<ManagedChild>,
 }
 
 impl NestedAuraed {
-    pub fn new(name: String, iso_ctl: IsolationControls) -> io::Result<Self> {
+    /// Starts the nested auraed of a cell, which passes `cell_info` on to
+    /// the executables it starts as [CELL_INFO_ENV], and `reserved_ports` as
+    /// [PORTS_ENV], and starts them in `user_namespace` if any, each in a
+    /// leaf cgroup of its own under `executable_cgroups` if set.
+    pub fn new(
+        name: String,
+        iso_ctl: IsolationControls,
+        cell_info: &Path,
+        reserved_ports: &BTreeSet<u16>,
+        user_namespace: Option<&UserNamespace>,
+        executable_cgroups: Option<&Path>,
+    ) -> io::Result<Self> {
         // Here we launch a nested auraed with the --nested flag
         // which is used our way of "hooking" into the newly created
         // aurae isolation zone.
@@ -82,6 +111,36 @@ impl NestedAuraed {
         // to command.args, whose return value we ignored above.
         assert_eq!(command.get_args().len(), 13);
 
+        // The executables of the cell inherit the environment of its auraed
+        let _ = command.env(CELL_INFO_ENV, cell_info);
+        if !reserved_ports.is_empty() {
+            let ports: Vec<_> =
+                reserved_ports.iter().map(ToString::to_string).collect();
+            let _ = command.env(PORTS_ENV, ports.join(","));
+        }
+
+        // The nested auraed redacts the logs of its executables as we do
+        for rule in &auraed_runtime.log_redaction {
+            let _ = command.args(["--redact", &rule.to_string()]);
+        }
+
+        // Its executables run in the user namespace of the cell, not in the
+        // one this auraed may have been given in its environment
+        let _ =
+            command.env_remove("AURAED_UID_MAP").env_remove("AURAED_GID_MAP");
+        if let Some(UserNamespace { uid_map, gid_map }) = user_namespace {
+            for mapping in uid_map {
+                let _ = command.args(["--uid-map", &mapping.to_string()]);
+            }
+            for mapping in gid_map {
+                let _ = command.args(["--gid-map", &mapping.to_string()]);
+            }
+        }
+
+        if let Some(executable_cgroups) = executable_cgroups {
+            let _ = command.arg("--executable-cgroups").arg(executable_cgroups);
+        }
+
         // *****************************************************************
         // ██████╗██╗      ██████╗ ███╗   ██╗███████╗██████╗
         // ██╔════╝██║     ██╔═══██╗████╗  ██║██╔════╝╚════██╗
@@ -132,10 +191,15 @@ impl NestedAuraed {
             let _ = clone.flag_newpid();
             let _ = clone.flag_newns();
             let _ = clone.flag_newipc();
+        }
+
+        // Isolate UTS, which isolating the process does as well
+        if iso_ctl.isolates_uts() {
             let _ = clone.flag_newuts();
         }
 
         // Execute the clone system call and create the new process with the relevant namespaces.
+        let spawning = reaper::spawning();
         match unsafe { clone.call() }
             .map_err(|e| io::Error::from_raw_os_error(e.0))?
         {
@@ -145,6 +209,7 @@ impl NestedAuraed {
                     unsafe {
                         command.pre_exec(move || {
                             isolation.isolate_process(&iso_ctl)?;
+                            isolation.isolate_uts(&iso_ctl)?;
                             isolation.isolate_network(&iso_ctl)?;
                             Ok(())
                         })
@@ -158,27 +223,124 @@ impl NestedAuraed {
             pid => {
                 // parent
                 info!("Nested auraed running with host pid {}", pid.clone());
+                let managed = spawning.manage(Pid::from_raw(pid));
                 let process = procfs::process::Process::new(pid)
                     .map_err(|e| io::Error::new(ErrorKind::Other, e))?;
-
-                Ok(Self { process, pidfd, iso_ctl, client_socket })
+                let start_time = process
+                    .stat()
+                    .map_err(|e| io::Error::new(ErrorKind::Other, e))?
+                    .starttime;
+                // SAFETY: clone3 opened the pidfd for us, and nothing else
+                // owns it.
+                let pidfd = unsafe { OwnedFd::from_raw_fd(pidfd) };
+
+                Ok(Self {
+                    process,
+                    start_time,
+                    pidfd,
+                    iso_ctl,
+                    client_socket,
+                    adopted: false,
+                    managed: Some(managed),
+                })
             }
         }
     }
 
+    /// Adopts the nested auraed of a cell allocated by a previous auraed.
+    /// Fails if `pid` is not a nested auraed listening on `socket_path`
+    /// which started at `start_time`, e.g., because it exited and the pid
+    /// was reused. The start time is not checked if it was not recorded.
+    pub fn adopt(
+        pid: Pid,
+        start_time: Option<u64>,
+        socket_path: PathBuf,
+        iso_ctl: IsolationControls,
+    ) -> io::Result<Self> {
+        // Opened first, so the process checked below is the one it refers
+        // to, even if the pid is reused in between.
+        let pidfd = pidfd_open(pid)?;
+
+        let process = procfs::process::Process::new(pid.as_raw())
+            .map_err(|e| io::Error::new(ErrorKind::NotFound, e))?;
+
+        let cmdline = process
+            .cmdline()
+            .map_err(|e| io::Error::new(ErrorKind::NotFound, e))?;
+
+        let actual_start_time = process
+            .stat()
+            .map_err(|e| io::Error::new(ErrorKind::NotFound, e))?
+            .starttime;
+
+        let socket_arg =
+            ["--socket".to_string(), socket_path.to_string_lossy().into()];
+        let is_nested_auraed = process.is_alive()
+            && start_time
+                .is_none_or(|start_time| start_time == actual_start_time)
+            && cmdline.iter().any(|arg| arg == "--nested")
+            && cmdline.windows(2).any(|args| args == socket_arg);
+
+        if !is_nested_auraed {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "pid {pid} is not a nested auraed listening on '{}'",
+                    socket_path.display()
+                ),
+            ));
+        }
+
+        info!("Adopted nested auraed with host pid {pid}");
+
+        Ok(Self {
+            process,
+            start_time: actual_start_time,
+            pidfd,
+            iso_ctl,
+            client_socket: AuraeSocket::Path(socket_path),
+            adopted: true,
+            managed: None,
+        })
+    }
+
     /// Sends a graceful shutdown signal to the nested process.
-    pub fn shutdown(&mut self) -> io::Result<ExitStatus> {
+    /// Returns [None] if the nested auraed was adopted, as its exit status
+    /// is only observable by its parent.
+    pub fn shutdown(&mut self) -> io::Result<Option<ExitStatus>> {
         // TODO: Here, SIGTERM works when using auraescript, but hangs(?) during unit tests.
         //       SIGKILL, however, works. The hang is avoided if the process is not isolated.
         //       Tests have not been done to figure out which namespace is the cause of the hang.
         self.do_kill(Some(SIGTERM))?;
-        self.wait()
+        let exit_status = self.wait()?;
+        self.remove_socket();
+        Ok(exit_status)
     }
 
     /// Sends a [SIGKILL] signal to the nested process.
-    pub fn kill(&mut self) -> io::Result<ExitStatus> {
+    /// Returns [None] if the nested auraed was adopted, as its exit status
+    /// is only observable by its parent.
+    pub fn kill(&mut self) -> io::Result<Option<ExitStatus>> {
         self.do_kill(Some(SIGKILL))?;
-        self.wait()
+        let exit_status = self.wait()?;
+        self.remove_socket();
+        Ok(exit_status)
+    }
+
+    /// Removes the socket the exited nested process listened on, which it
+    /// only removes itself when shutting down gracefully.
+    fn remove_socket(&self) {
+        let AuraeSocket::Path(path) = &self.client_socket else {
+            return;
+        };
+
+        match std::fs::remove_file(path) {
+            Ok(()) => trace!("Removed socket: {}", path.display()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                error!("Failed to remove socket {}: {e}", path.display())
+            }
+        }
     }
 
     fn do_kill<T: Into<Option<Signal>>>(
@@ -192,9 +354,32 @@ impl NestedAuraed {
             .map_err(|e| io::Error::from_raw_os_error(e as i32))
     }
 
-    fn wait(&mut self) -> io::Result<ExitStatus> {
+    fn wait(&mut self) -> io::Result<Option<ExitStatus>> {
         let pid = Pid::from_raw(self.process.pid);
 
+        if self.adopted {
+            // Not our child, so it can't be waited on, but its pidfd becomes
+            // readable once it exits.
+            let mut pollfd = libc::pollfd {
+                fd: self.pidfd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            loop {
+                // SAFETY: pollfd is valid for the duration of the call.
+                if unsafe { libc::poll(&mut pollfd, 1, -1) } >= 0 {
+                    break;
+                }
+                let err = io::Error::last_os_error();
+                if err.kind() != ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+
+            trace!("Adopted pid {pid} exited");
+            return Ok(None);
+        }
+
         let mut exit_status = 0;
         let _child_pid = loop {
             let res =
@@ -215,10 +400,15 @@ impl NestedAuraed {
 
         trace!("Pid {pid} exited with status {exit_status}");
 
-        Ok(exit_status)
+        Ok(Some(exit_status))
     }
 
     pub fn pid(&self) -> Pid {
         Pid::from_raw(self.process.pid)
     }
-}
\ No newline at end of file
+
+    /// Returns when the process started, in clock ticks since boot.
+    pub fn start_time(&self) -> u64 {
+        self.start_time
+    }
+}
@@ -0,0 +1,501 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::CellSpec;
+use crate::cells::cell_service::executables::{IdMapping, UserNamespace};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// A field of a [CellSpec] that differs between an allocated cell and the
+/// spec it is requested to have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecChange {
+    pub field: &'static str,
+    pub current: String,
+    pub requested: String,
+    /// True if the change can be applied to the allocated cell in place.
+    pub mutable: bool,
+}
+
+impl Display for SpecChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.current, self.requested)?;
+        if !self.mutable {
+            write!(f, " (immutable)")?;
+        }
+        Ok(())
+    }
+}
+
+impl CellSpec {
+    /// Returns the fields of `requested` that differ from this spec.
+    ///
+    /// Weights and limits can be changed in place, as long as they are set,
+    /// the cpuset can be grown, and the variables and default ids replaced. Unsetting a value, shrinking the cpuset,
+    /// and changing the devices, the isolation, the reserved ports, the user namespace, or the per executable accounting of the cell can not.
+    pub fn diff(&self, requested: &CellSpec) -> Vec<SpecChange> {
+        let mut changes = vec![];
+
+        let cpu = self.cgroup_spec.cpu.as_ref();
+        let requested_cpu = requested.cgroup_spec.cpu.as_ref();
+        push_limit(
+            &mut changes,
+            "cpu.weight",
+            cpu.and_then(|cpu| cpu.weight),
+            requested_cpu.and_then(|cpu| cpu.weight),
+        );
+        push_limit(
+            &mut changes,
+            "cpu.max",
+            cpu.and_then(|cpu| cpu.max),
+            requested_cpu.and_then(|cpu| cpu.max),
+        );
+        push_limit(
+            &mut changes,
+            "cpu.period",
+            cpu.and_then(|cpu| cpu.period),
+            requested_cpu.and_then(|cpu| cpu.period),
+        );
+
+        let cpuset = self.cgroup_spec.cpuset.as_ref();
+        let requested_cpuset = requested.cgroup_spec.cpuset.as_ref();
+        push_cpuset(
+            &mut changes,
+            "cpuset.cpus",
+            cpuset.and_then(|cpuset| cpuset.cpus.as_deref()),
+            requested_cpuset.and_then(|cpuset| cpuset.cpus.as_deref()),
+        );
+        push_cpuset(
+            &mut changes,
+            "cpuset.mems",
+            cpuset.and_then(|cpuset| cpuset.mems.as_deref()),
+            requested_cpuset.and_then(|cpuset| cpuset.mems.as_deref()),
+        );
+
+        let memory = self.cgroup_spec.memory.as_ref();
+        let requested_memory = requested.cgroup_spec.memory.as_ref();
+        push_limit(
+            &mut changes,
+            "memory.min",
+            memory.and_then(|memory| memory.min),
+            requested_memory.and_then(|memory| memory.min),
+        );
+        push_limit(
+            &mut changes,
+            "memory.low",
+            memory.and_then(|memory| memory.low),
+            requested_memory.and_then(|memory| memory.low),
+        );
+        push_limit(
+            &mut changes,
+            "memory.high",
+            memory.and_then(|memory| memory.high),
+            requested_memory.and_then(|memory| memory.high),
+        );
+        push_limit(
+            &mut changes,
+            "memory.max",
+            memory.and_then(|memory| memory.max),
+            requested_memory.and_then(|memory| memory.max),
+        );
+
+        let device_allow = &self.cgroup_spec.device_allow;
+        let requested_device_allow = &requested.cgroup_spec.device_allow;
+        if device_allow != requested_device_allow {
+            changes.push(SpecChange {
+                field: "device_allow",
+                current: device_list(device_allow),
+                requested: device_list(requested_device_allow),
+                mutable: false,
+            });
+        }
+
+        let iso_ctl = &self.iso_ctl;
+        let requested_iso_ctl = &requested.iso_ctl;
+        push_immutable(
+            &mut changes,
+            "isolate_process",
+            iso_ctl.isolate_process,
+            requested_iso_ctl.isolate_process,
+        );
+        push_immutable(
+            &mut changes,
+            "isolate_network",
+            iso_ctl.isolate_network,
+            requested_iso_ctl.isolate_network,
+        );
+        push_immutable(
+            &mut changes,
+            "isolate_uts",
+            iso_ctl.isolate_uts,
+            requested_iso_ctl.isolate_uts,
+        );
+        if iso_ctl.hostname != requested_iso_ctl.hostname {
+            changes.push(SpecChange {
+                field: "hostname",
+                current: display_or_unset(iso_ctl.hostname.as_ref()),
+                requested: display_or_unset(
+                    requested_iso_ctl.hostname.as_ref(),
+                ),
+                mutable: false,
+            });
+        }
+
+        if self.variables != requested.variables {
+            changes.push(SpecChange {
+                field: "variables",
+                current: variable_list(&self.variables),
+                requested: variable_list(&requested.variables),
+                mutable: true,
+            });
+        }
+
+        // The nested auraed of the cell was started with them
+        if self.reserved_ports != requested.reserved_ports {
+            changes.push(SpecChange {
+                field: "reserved_ports",
+                current: port_list(&self.reserved_ports),
+                requested: port_list(&requested.reserved_ports),
+                mutable: false,
+            });
+        }
+
+        // Only applied to the executables started after the change
+        for (field, current, requested) in [
+            ("default_uid", self.default_uid, requested.default_uid),
+            ("default_gid", self.default_gid, requested.default_gid),
+        ] {
+            if current != requested {
+                changes.push(SpecChange {
+                    field,
+                    current: display_or_unset(current.as_ref()),
+                    requested: display_or_unset(requested.as_ref()),
+                    mutable: true,
+                });
+            }
+        }
+
+        // The nested auraed of the cell was started with it
+        if self.user_namespace != requested.user_namespace {
+            changes.push(SpecChange {
+                field: "userns",
+                current: user_namespace_maps(self.user_namespace.as_ref()),
+                requested: user_namespace_maps(
+                    requested.user_namespace.as_ref(),
+                ),
+                mutable: false,
+            });
+        }
+
+        // The nested auraed of the cell was started with it
+        push_immutable(
+            &mut changes,
+            "per_executable_accounting",
+            self.per_executable_accounting,
+            requested.per_executable_accounting,
+        );
+
+        changes
+    }
+}
+
+/// A weight or limit can be changed in place, but not unset, as the cgroup
+/// would keep the current value.
+fn push_limit<T: Display + PartialEq>(
+    changes: &mut Vec<SpecChange>,
+    field: &'static str,
+    current: Option<T>,
+    requested: Option<T>,
+) {
+    if current == requested {
+        return;
+    }
+
+    changes.push(SpecChange {
+        field,
+        current: display_or_unset(current.as_ref()),
+        requested: display_or_unset(requested.as_ref()),
+        mutable: requested.is_some(),
+    });
+}
+
+/// The cpus or mems of a cell can only be grown in place, as shrinking them
+/// could take them from running processes, or from nested cells.
+/// An unset cpuset is inherited from the parent, so it can not be grown.
+fn push_cpuset(
+    changes: &mut Vec<SpecChange>,
+    field: &'static str,
+    current: Option<&str>,
+    requested: Option<&str>,
+) {
+    let current = current.filter(|list| !list.is_empty());
+    let requested = requested.filter(|list| !list.is_empty());
+    if current == requested {
+        return;
+    }
+
+    let grows = match (current.and_then(id_list), requested.and_then(id_list)) {
+        (Some(current), Some(requested)) => requested.is_superset(&current),
+        _ => false,
+    };
+
+    changes.push(SpecChange {
+        field,
+        current: current.unwrap_or("unset").to_string(),
+        requested: requested.unwrap_or("unset").to_string(),
+        mutable: grows,
+    });
+}
+
+fn push_immutable(
+    changes: &mut Vec<SpecChange>,
+    field: &'static str,
+    current: bool,
+    requested: bool,
+) {
+    if current != requested {
+        changes.push(SpecChange {
+            field,
+            current: current.to_string(),
+            requested: requested.to_string(),
+            mutable: false,
+        });
+    }
+}
+
+/// Parses a list of ids, such as `0-2,4`, into the ids it contains.
+fn id_list(list: &str) -> Option<BTreeSet<u32>> {
+    let mut ids = BTreeSet::new();
+    for part in list.split(',').filter(|part| !part.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let first: u32 = first.trim().parse().ok()?;
+        let last: u32 = last.trim().parse().ok()?;
+        ids.extend(first..=last);
+    }
+    Some(ids)
+}
+
+fn device_list<T: Display>(devices: &[T]) -> String {
+    if devices.is_empty() {
+        return "any".to_string();
+    }
+
+    devices.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn variable_list(variables: &BTreeMap<String, String>) -> String {
+    if variables.is_empty() {
+        return "none".to_string();
+    }
+
+    variables
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn port_list(ports: &BTreeSet<u16>) -> String {
+    if ports.is_empty() {
+        return "none".to_string();
+    }
+
+    ports.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn user_namespace_maps(user_namespace: Option<&UserNamespace>) -> String {
+    let Some(UserNamespace { uid_map, gid_map }) = user_namespace else {
+        return "unset".to_string();
+    };
+
+    format!(
+        "uid_map {}, gid_map {}",
+        mapping_list(uid_map),
+        mapping_list(gid_map)
+    )
+}
+
+fn mapping_list(map: &[IdMapping]) -> String {
+    map.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn display_or_unset<T: Display>(value: Option<&T>) -> String {
+    value.map_or_else(|| "unset".to_string(), ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::cells::cgroups::{
+        cpuset::Cpus, CpusetController, Limit, Weight,
+    };
+    use crate::cells::cell_service::cells::Hostname;
+    use validation::ValidatedField;
+
+    fn with_cpus(cpus: &str) -> CellSpec {
+        let mut spec = CellSpec::new_for_tests();
+        spec.cgroup_spec.cpuset = Some(CpusetController {
+            cpus: Some(Cpus::new(cpus.to_string())),
+            mems: None,
+        });
+        spec
+    }
+
+    #[test]
+    fn test_identical_specs_have_no_changes() {
+        let spec = CellSpec::new_for_tests();
+        assert!(spec.diff(&CellSpec::new_for_tests()).is_empty());
+    }
+
+    #[test]
+    fn test_changed_limits_are_mutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        let cpu = requested.cgroup_spec.cpu.as_mut().expect("cpu");
+        cpu.weight = Some(Weight::new(200));
+        let memory = requested.cgroup_spec.memory.as_mut().expect("memory");
+        memory.max = Some(Limit::new(2000000));
+
+        let changes = current.diff(&requested);
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["cpu.weight: 100 -> 200", "memory.max: 1000000 -> 2000000"]
+        );
+        assert!(changes.iter().all(|change| change.mutable));
+    }
+
+    #[test]
+    fn test_unset_limits_are_immutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        requested.cgroup_spec.memory = None;
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            "memory.max: 1000000 -> unset (immutable)"
+        );
+    }
+
+    #[test]
+    fn test_only_growing_the_cpuset_is_mutable() {
+        let grown = with_cpus("0-1").diff(&with_cpus("0-2,4"));
+        assert_eq!(grown.len(), 1);
+        assert!(grown[0].mutable);
+
+        let shrunk = with_cpus("0-2").diff(&with_cpus("0,2"));
+        assert_eq!(shrunk.len(), 1);
+        assert!(!shrunk[0].mutable);
+
+        let restricted = CellSpec::new_for_tests().diff(&with_cpus("0"));
+        assert_eq!(restricted.len(), 1);
+        assert!(!restricted[0].mutable);
+    }
+
+    #[test]
+    fn test_isolation_changes_are_immutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        requested.iso_ctl.isolate_network = true;
+        requested.iso_ctl.hostname = Some(
+            Hostname::validate(Some("ae-host".into()), "hostname", None)
+                .expect("valid hostname"),
+        );
+
+        let changes = current.diff(&requested);
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "isolate_network: false -> true (immutable)",
+                "hostname: unset -> ae-host (immutable)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_reserved_ports_are_immutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        requested.reserved_ports = BTreeSet::from([8443, 8080]);
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            "reserved_ports: none -> 8080, 8443 (immutable)"
+        );
+        assert!(!changes[0].mutable);
+    }
+
+    #[test]
+    fn test_changed_default_ids_are_mutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        requested.default_uid = Some(1000);
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "default_uid: unset -> 1000");
+        assert!(changes[0].mutable);
+    }
+
+    #[test]
+    fn test_changed_user_namespace_is_immutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        let mapping = IdMapping { container_id: 0, host_id: 100000, size: 1 };
+        requested.user_namespace = Some(UserNamespace {
+            uid_map: vec![mapping],
+            gid_map: vec![mapping],
+        });
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            "userns: unset -> uid_map 0:100000:1, gid_map 0:100000:1 (immutable)"
+        );
+        assert!(!changes[0].mutable);
+    }
+
+    #[test]
+    fn test_changed_per_executable_accounting_is_immutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        requested.per_executable_accounting = true;
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            "per_executable_accounting: false -> true (immutable)"
+        );
+        assert!(!changes[0].mutable);
+    }
+
+    #[test]
+    fn test_changed_variables_are_mutable() {
+        let current = CellSpec::new_for_tests();
+        let mut requested = CellSpec::new_for_tests();
+        let _ =
+            requested.variables.insert("port".to_string(), "8080".to_string());
+
+        let changes = current.diff(&requested);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "variables: none -> port=8080");
+        assert!(changes[0].mutable);
+    }
+}
@@ -0,0 +1,115 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+use validation::{ValidatedField, ValidationError};
+
+/// Paths that may never be copied into or out of.
+const DENIED_PATHS: [&str; 3] = ["/proc", "/sys", "/dev"];
+
+/// An absolute path, without `..` components, that is allowed to be the
+/// source or destination of a copy. It is checked as it is written, so its
+/// symlinks must be resolved and checked again before it is opened.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct CopyPath(PathBuf);
+
+impl CopyPath {
+    #[cfg(test)]
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self(path)
+    }
+
+    pub fn into_inner(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl ValidatedField<String> for CopyPath {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        let path = PathBuf::from(input);
+
+        let is_allowed = path.is_absolute()
+            && !path.components().any(|c| matches!(c, Component::ParentDir))
+            && !is_denied(&path);
+
+        if !is_allowed {
+            return Err(ValidationError::Invalid {
+                field: validation::field_name(field_name, parent_name),
+            });
+        }
+
+        Ok(Self(path))
+    }
+}
+
+/// Returns true if `path` is, or is under, one of the [DENIED_PATHS].
+pub(super) fn is_denied(path: &Path) -> bool {
+    DENIED_PATHS.iter().any(|denied| path.starts_with(denied))
+}
+
+impl Deref for CopyPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for CopyPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(path: &str) -> Result<CopyPath, ValidationError> {
+        CopyPath::validate(Some(path.to_string()), "path", None)
+    }
+
+    #[test]
+    fn test_absolute_path_is_valid() {
+        assert!(validate("/var/log/app.log").is_ok());
+    }
+
+    #[test]
+    fn test_relative_path_is_invalid() {
+        assert!(validate("var/log/app.log").is_err());
+    }
+
+    #[test]
+    fn test_parent_dir_is_invalid() {
+        assert!(validate("/var/../proc/1/environ").is_err());
+    }
+
+    #[test]
+    fn test_denied_paths_are_invalid() {
+        assert!(validate("/proc/1/environ").is_err());
+        assert!(validate("/sys").is_err());
+        assert!(validate("/dev/sda").is_err());
+        assert!(validate("/processes").is_ok());
+    }
+}
@@ -0,0 +1,47 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use crate::blocking::BlockingError;
+use std::{io, path::PathBuf};
+use thiserror::Error;
+use tonic::Status;
+
+pub type Result<T> = std::result::Result<T, CopyError>;
+
+#[derive(Error, Debug)]
+pub enum CopyError {
+    #[error("copy exceeds the size limit of {limit} bytes")]
+    SizeLimitExceeded { limit: u64 },
+    #[error("'{path}' resolves to '{resolved}', which may not be copied")]
+    Denied { path: PathBuf, resolved: PathBuf },
+    #[error("'{path}' is not writable by uid {uid}")]
+    NotWritable { path: PathBuf, uid: u32 },
+    #[error("'{path}' is not a directory")]
+    NotADirectory { path: PathBuf },
+    #[error("'{path}' is a directory")]
+    IsADirectory { path: PathBuf },
+    #[error("copy stream ended after {received} of {expected} bytes")]
+    Incomplete { expected: u64, received: u64 },
+    #[error("copy stream must start with a header")]
+    MissingHeader,
+    #[error("copy stream contains more than one header")]
+    UnexpectedHeader,
+    #[error("copy stream failed: {0}")]
+    Stream(#[from] Status),
+    #[error(transparent)]
+    Blocking(#[from] BlockingError),
+    #[error("copy of '{path}' failed: {source}")]
+    Io { path: PathBuf, source: io::Error },
+}
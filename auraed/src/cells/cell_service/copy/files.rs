@@ -0,0 +1,563 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{copy_path::is_denied, CopyError, CopyPath, Result};
+use crate::blocking::{self, BlockingJob, Pool};
+use bytes::{Bytes, BytesMut};
+use proto::cells::CopyFromHeader;
+use std::{
+    io::{self, Cursor},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+use tokio_stream::{Stream, StreamExt};
+use walkdir::WalkDir;
+
+/// The maximum number of bytes that can be copied in a single request.
+const MAX_COPY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The size of the chunks streamed out of a cell.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub struct CopyDestination {
+    pub destination_path: CopyPath,
+    pub archive: bool,
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub size: Option<u64>,
+}
+
+/// Writes the streamed content to the destination.
+/// A partially written file is removed if the copy fails.
+///
+/// Returns the number of bytes received.
+pub async fn copy_into<S>(
+    destination: CopyDestination,
+    chunks: S,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    if destination.archive {
+        copy_archive_into(destination, chunks).await
+    } else {
+        copy_file_into(destination, chunks).await
+    }
+}
+
+async fn copy_file_into<S>(
+    destination: CopyDestination,
+    mut chunks: S,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let CopyDestination { destination_path, mode, uid, gid, size, .. } =
+        destination;
+
+    let (Some(parent), Some(file_name)) =
+        (destination_path.parent(), destination_path.file_name())
+    else {
+        return Err(CopyError::IsADirectory {
+            path: destination_path.to_path_buf(),
+        });
+    };
+
+    // The file itself is opened without following a symlink
+    let parent = resolve(parent).await?;
+    let path: &Path = &parent.join(file_name);
+    if path.is_dir() {
+        return Err(CopyError::IsADirectory { path: path.into() });
+    }
+    check_writable(&parent, uid, gid).await?;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode.unwrap_or(0o644))
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .await
+        .map_err(|e| io_error(path, e))?;
+
+    let written = async {
+        let mut written = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            written += chunk.len() as u64;
+            check_size(written, size)?;
+            file.write_all(&chunk).await.map_err(|e| io_error(path, e))?;
+        }
+        check_complete(written, size)?;
+        file.flush().await.map_err(|e| io_error(path, e))?;
+        Ok(written)
+    }
+    .await;
+
+    let written = match written {
+        Ok(written) => written,
+        Err(e) => {
+            drop(file);
+            let _best_effort = fs::remove_file(path).await;
+            return Err(e);
+        }
+    };
+
+    // the mode used when creating the file is subject to the umask
+    if let Some(mode) = mode {
+        file.set_permissions(PermissionsExt::from_mode(mode))
+            .await
+            .map_err(|e| io_error(path, e))?;
+    }
+
+    if uid.is_some() || gid.is_some() {
+        std::os::unix::fs::fchown(&file, uid, gid)
+            .map_err(|e| io_error(path, e))?;
+    }
+
+    Ok(written)
+}
+
+async fn copy_archive_into<S>(
+    destination: CopyDestination,
+    mut chunks: S,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let CopyDestination { destination_path, uid, gid, size, .. } = destination;
+
+    let path = resolve(&destination_path).await?;
+    check_writable(&path, uid, gid).await?;
+
+    // The archive is unpacked only after it has been received completely,
+    // so that a failed copy does not leave a partially unpacked directory.
+    let mut archive = BytesMut::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        check_size((archive.len() + chunk.len()) as u64, size)?;
+        archive.extend_from_slice(&chunk);
+    }
+
+    let written = archive.len() as u64;
+    check_complete(written, size)?;
+
+    blocking::run(UnpackArchive {
+        archive: archive.freeze(),
+        destination: path.clone(),
+        uid,
+        gid,
+    })
+    .await?
+    .map_err(|e| io_error(&path, e))?;
+
+    Ok(written)
+}
+
+/// Unpacks a tar archive into `destination`, chowning every entry if an
+/// owner is given. Entries that would escape `destination` are skipped.
+struct UnpackArchive {
+    archive: Bytes,
+    destination: PathBuf,
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl BlockingJob for UnpackArchive {
+    type Output = io::Result<()>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        let UnpackArchive { archive, destination, uid, gid } = self;
+        unpack(archive, &destination, uid, gid)
+    }
+}
+
+fn unpack(
+    archive: Bytes,
+    destination: &Path,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(Cursor::new(archive));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        if !entry.unpack_in(destination)? {
+            continue;
+        }
+
+        if uid.is_some() || gid.is_some() {
+            std::os::unix::fs::lchown(destination.join(path), uid, gid)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The content of a file or directory that is copied out of a cell.
+#[derive(Debug)]
+pub enum CopySource {
+    File(File),
+    Archive(Bytes),
+}
+
+impl CopySource {
+    /// Returns the next chunk of content, or [None] once all content has been read.
+    pub async fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        match self {
+            CopySource::File(file) => {
+                let mut buf = BytesMut::zeroed(CHUNK_SIZE);
+                let n = file.read(&mut buf).await?;
+                if n == 0 {
+                    return Ok(None);
+                }
+                buf.truncate(n);
+                Ok(Some(buf.freeze()))
+            }
+            CopySource::Archive(archive) => {
+                if archive.is_empty() {
+                    return Ok(None);
+                }
+                let n = CHUNK_SIZE.min(archive.len());
+                Ok(Some(archive.split_to(n)))
+            }
+        }
+    }
+}
+
+/// Opens the source for reading. Directories are packed into a tar archive.
+pub async fn copy_from(
+    source_path: CopyPath,
+) -> Result<(CopyFromHeader, CopySource)> {
+    let path = resolve(&source_path).await?;
+
+    let metadata = fs::metadata(&path).await.map_err(|e| io_error(&path, e))?;
+
+    let (size, source) = if metadata.is_dir() {
+        let archive = blocking::run(PackDirectory(path.clone())).await??;
+
+        (archive.len() as u64, CopySource::Archive(archive))
+    } else {
+        check_size(metadata.len(), None)?;
+
+        let file = File::open(&path).await.map_err(|e| io_error(&path, e))?;
+
+        (metadata.len(), CopySource::File(file))
+    };
+
+    Ok((
+        CopyFromHeader {
+            archive: metadata.is_dir(),
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            size,
+        },
+        source,
+    ))
+}
+
+/// Packs a directory into a tar archive, without following symlinks.
+struct PackDirectory(PathBuf);
+
+impl BlockingJob for PackDirectory {
+    type Output = Result<Bytes>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        pack(&self.0)
+    }
+}
+
+fn pack(path: &Path) -> Result<Bytes> {
+    let mut total = 0;
+    for entry in WalkDir::new(path) {
+        let entry = entry.map_err(|e| io_error(path, e.into()))?;
+        let metadata =
+            entry.metadata().map_err(|e| io_error(path, e.into()))?;
+        if metadata.is_file() {
+            total += metadata.len();
+            check_size(total, None)?;
+        }
+    }
+
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", path).map_err(|e| io_error(path, e))?;
+    let archive = builder.into_inner().map_err(|e| io_error(path, e))?;
+
+    Ok(archive.into())
+}
+
+/// Resolves the symlinks of `path`, which may point into a path that may not
+/// be copied, as a [CopyPath] is only checked as it is written.
+async fn resolve(path: &Path) -> Result<PathBuf> {
+    let resolved =
+        fs::canonicalize(path).await.map_err(|e| io_error(path, e))?;
+    if is_denied(&resolved) {
+        return Err(CopyError::Denied { path: path.into(), resolved });
+    }
+    Ok(resolved)
+}
+
+/// Checks that `dir` is a directory and, if a uid is given, that the owner
+/// can create files in it.
+async fn check_writable(
+    dir: &Path,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<()> {
+    let metadata = fs::metadata(dir).await.map_err(|e| io_error(dir, e))?;
+
+    if !metadata.is_dir() {
+        return Err(CopyError::NotADirectory { path: dir.into() });
+    }
+
+    let Some(uid) = uid else {
+        return Ok(());
+    };
+
+    if !is_writable_by(&metadata, uid, gid) {
+        return Err(CopyError::NotWritable { path: dir.into(), uid });
+    }
+
+    Ok(())
+}
+
+fn is_writable_by(
+    metadata: &std::fs::Metadata,
+    uid: u32,
+    gid: Option<u32>,
+) -> bool {
+    let mode = metadata.mode();
+
+    if uid == 0 {
+        true
+    } else if metadata.uid() == uid {
+        mode & 0o200 != 0
+    } else if gid == Some(metadata.gid()) {
+        mode & 0o020 != 0
+    } else {
+        mode & 0o002 != 0
+    }
+}
+
+fn check_size(size: u64, expected: Option<u64>) -> Result<()> {
+    let limit = expected.unwrap_or(MAX_COPY_SIZE).min(MAX_COPY_SIZE);
+    if size > limit {
+        return Err(CopyError::SizeLimitExceeded { limit });
+    }
+    Ok(())
+}
+
+fn check_complete(size: u64, expected: Option<u64>) -> Result<()> {
+    match expected {
+        Some(expected) if expected != size => {
+            Err(CopyError::Incomplete { expected, received: size })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn io_error(path: &Path, source: io::Error) -> CopyError {
+    CopyError::Io { path: path.into(), source }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-copy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("failed to create test dir");
+        dir
+    }
+
+    fn destination(
+        destination_path: PathBuf,
+        archive: bool,
+    ) -> CopyDestination {
+        CopyDestination {
+            destination_path: CopyPath::new(destination_path),
+            archive,
+            mode: None,
+            uid: None,
+            gid: None,
+            size: None,
+        }
+    }
+
+    fn chunks(
+        content: &'static [u8],
+    ) -> impl Stream<Item = Result<Bytes>> + Unpin {
+        tokio_stream::iter(
+            content
+                .chunks(3)
+                .map(|c| Ok(Bytes::from_static(c)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    async fn read_all(mut source: CopySource) -> Vec<u8> {
+        let mut content = vec![];
+        while let Some(chunk) = source.next_chunk().await.unwrap() {
+            content.extend_from_slice(&chunk);
+        }
+        content
+    }
+
+    #[tokio::test]
+    async fn test_copy_file_round_trip() {
+        let dir = test_dir();
+        let path = dir.join("app.log");
+
+        let mut destination = destination(path.clone(), false);
+        destination.mode = Some(0o600);
+        destination.size = Some(11);
+
+        let written =
+            copy_into(destination, chunks(b"hello world")).await.unwrap();
+        assert_eq!(written, 11);
+
+        let (header, source) =
+            copy_from(CopyPath::new(path.clone())).await.unwrap();
+        assert!(!header.archive);
+        assert_eq!(header.size, 11);
+        assert_eq!(header.mode & 0o777, 0o600);
+        assert_eq!(read_all(source).await, b"hello world");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_directory_round_trip() {
+        let src = test_dir();
+        std::fs::create_dir(src.join("nested")).unwrap();
+        std::fs::write(src.join("a.txt"), "a").unwrap();
+        std::fs::write(src.join("nested/b.txt"), "b").unwrap();
+
+        let (header, source) =
+            copy_from(CopyPath::new(src.clone())).await.unwrap();
+        assert!(header.archive);
+        let archive = read_all(source).await;
+        assert_eq!(archive.len() as u64, header.size);
+
+        let dst = test_dir();
+        let archive: &'static [u8] = archive.leak();
+        let _ = copy_into(destination(dst.clone(), true), chunks(archive))
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(dst.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            std::fs::read_to_string(dst.join("nested/b.txt")).unwrap(),
+            "b"
+        );
+
+        std::fs::remove_dir_all(src).unwrap();
+        std::fs::remove_dir_all(dst).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_incomplete_stream_removes_file() {
+        let dir = test_dir();
+        let path = dir.join("app.log");
+
+        let mut destination = destination(path.clone(), false);
+        destination.size = Some(100);
+
+        let res = copy_into(destination, chunks(b"hello")).await;
+        assert!(matches!(res, Err(CopyError::Incomplete { .. })));
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_not_writable_by_uid() {
+        let dir = test_dir();
+        std::fs::set_permissions(&dir, PermissionsExt::from_mode(0o755))
+            .unwrap();
+        let owner = std::fs::metadata(&dir).unwrap().uid();
+
+        let mut destination = destination(dir.join("app.log"), false);
+        destination.uid = Some(owner + 1);
+
+        let res = copy_into(destination, chunks(b"hello")).await;
+        assert!(matches!(res, Err(CopyError::NotWritable { .. })));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_symlinks_into_denied_paths_are_denied() {
+        let dir = test_dir();
+        std::os::unix::fs::symlink("/proc", dir.join("proc")).unwrap();
+
+        let res = copy_from(CopyPath::new(dir.join("proc/self/environ"))).await;
+        assert!(matches!(res, Err(CopyError::Denied { .. })));
+
+        let destination = destination(dir.join("proc/self/comm"), false);
+        let res = copy_into(destination, chunks(b"hello")).await;
+        assert!(matches!(res, Err(CopyError::Denied { .. })));
+
+        let res =
+            copy_into(destination(dir.join("proc"), true), chunks(b"")).await;
+        assert!(matches!(res, Err(CopyError::Denied { .. })));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_does_not_follow_a_symlinked_file() {
+        let dir = test_dir();
+        let target = dir.join("target");
+        std::fs::write(&target, "kept").unwrap();
+        std::os::unix::fs::symlink(&target, dir.join("link")).unwrap();
+
+        let res =
+            copy_into(destination(dir.join("link"), false), chunks(b"hello"))
+                .await;
+        assert!(matches!(res, Err(CopyError::Io { .. })));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "kept");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_directory_requires_archive() {
+        let dir = test_dir();
+
+        let res =
+            copy_into(destination(dir.clone(), false), chunks(b"hello")).await;
+        assert!(matches!(res, Err(CopyError::IsADirectory { .. })));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
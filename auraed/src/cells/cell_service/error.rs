@@ -13,9 +13,17 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use super::{cells::CellsError, executables::ExecutablesError};
-use crate::observe::ObserveServiceError;
-use client::ClientError;
+use super::{
+    cells::{cgroups::error::CgroupsError, CellName, CellsError},
+    copy::CopyError,
+    executables::ExecutablesError,
+    net_check::NetCheckError,
+};
+use crate::{
+    admission::AdmissionError, blocking::BlockingError,
+    observe::ObserveServiceError,
+};
+use client::{ClientError, ErrorDetails, Resource};
 use thiserror::Error;
 use tonic::Status;
 use tracing::error;
@@ -29,29 +37,68 @@ pub(crate) enum CellsServiceError {
     #[error(transparent)]
     ExecutablesError(#[from] ExecutablesError),
     #[error(transparent)]
+    CopyError(#[from] CopyError),
+    #[error(transparent)]
+    NetCheckError(#[from] NetCheckError),
+    #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     ClientError(#[from] ClientError),
     #[error(transparent)]
     ObserveServiceError(#[from] ObserveServiceError),
+    #[error(transparent)]
+    AdmissionError(#[from] AdmissionError),
+    #[error(transparent)]
+    BlockingError(#[from] BlockingError),
+    #[error("admission is not enabled")]
+    AdmissionNotEnabled,
+    #[error("cell '{cell_name}' was not allocated before the deadline")]
+    DeadlineExceeded { cell_name: CellName },
 }
 
 impl From<CellsServiceError> for Status {
     fn from(err: CellsServiceError) -> Self {
         let msg = err.to_string();
         error!("{msg}");
-        match err {
+        let details = details(&err);
+        let status = match err {
             CellsServiceError::CellsError(e) => match e {
-                CellsError::CgroupIsNotACell { .. } => {
+                CellsError::CgroupIsNotACell { .. }
+                | CellsError::ImmutableCellChange { .. }
+                | CellsError::FailedToUpdateCell {
+                    source: CgroupsError::Frozen { .. },
+                    ..
+                }
+                | CellsError::CellHasNestedCells { .. }
+                | CellsError::CellHasRunningExecutables { .. } => {
                     Status::failed_precondition(msg)
                 }
-                CellsError::CellExists { .. } => Status::already_exists(msg),
+                CellsError::CellExists { .. }
+                | CellsError::CellExistsWithDifferentSpec { .. }
+                | CellsError::PortReserved { .. } => {
+                    Status::already_exists(msg)
+                }
                 CellsError::CellNotFound { .. }
                 | CellsError::CgroupNotFound { .. } => Status::not_found(msg),
+                CellsError::AbortedAllocateCell {
+                    source: CgroupsError::Unsupported { .. },
+                    ..
+                }
+                | CellsError::FailedToUpdateCell {
+                    source: CgroupsError::Unsupported { .. },
+                    ..
+                }
+                | CellsError::FailedToReadStats {
+                    source: CgroupsError::Unsupported { .. },
+                    ..
+                } => Status::unimplemented(msg),
                 CellsError::FailedToAllocateCell { .. }
                 | CellsError::AbortedAllocateCell { .. }
+                | CellsError::FailedToUpdateCell { .. }
+                | CellsError::FailedToAdoptCell { .. }
                 | CellsError::FailedToKillCellChildren { .. }
-                | CellsError::FailedToFreeCell { .. } => Status::internal(msg),
+                | CellsError::FailedToFreeCell { .. }
+                | CellsError::FailedToReadStats { .. } => Status::internal(msg),
                 CellsError::CellNotAllocated { cell_name } => {
                     CellsServiceError::CellsError(CellsError::CellNotFound {
                         cell_name,
@@ -66,17 +113,137 @@ impl From<CellsServiceError> for Status {
                 ExecutablesError::ExecutableNotFound { .. } => {
                     Status::not_found(msg)
                 }
+                ExecutablesError::UnknownDependency { .. }
+                | ExecutablesError::DependencyCycle { .. } => {
+                    Status::invalid_argument(msg)
+                }
+                ExecutablesError::ExecutableNotRunning { .. }
+                | ExecutablesError::DependencyFailed { .. }
+                | ExecutablesError::ExecutableDaemonized { .. }
+                | ExecutablesError::InvalidSeccompProfile { .. }
+                | ExecutablesError::InvalidRootfs { .. }
+                | ExecutablesError::InvalidMounts { .. }
+                | ExecutablesError::UnresolvedVariables { .. }
+                | ExecutablesError::KillModeRequiresCell { .. } => {
+                    Status::failed_precondition(msg)
+                }
                 ExecutablesError::FailedToStartExecutable { .. }
-                | ExecutablesError::FailedToStopExecutable { .. } => {
+                | ExecutablesError::FailedToStopExecutable { .. }
+                | ExecutablesError::FailedToQuarantineExecutable { .. }
+                | ExecutablesError::FailedToUnquarantineExecutable { .. } => {
                     Status::internal(msg)
                 }
             },
+            CellsServiceError::CopyError(e) => match e {
+                CopyError::SizeLimitExceeded { .. } => {
+                    Status::resource_exhausted(msg)
+                }
+                CopyError::Denied { .. } | CopyError::NotWritable { .. } => {
+                    Status::permission_denied(msg)
+                }
+                CopyError::NotADirectory { .. }
+                | CopyError::IsADirectory { .. } => {
+                    Status::failed_precondition(msg)
+                }
+                CopyError::Incomplete { .. }
+                | CopyError::MissingHeader
+                | CopyError::UnexpectedHeader => Status::invalid_argument(msg),
+                CopyError::Stream(status) => status,
+                CopyError::Blocking(_) => Status::internal(msg),
+                CopyError::Io { source, .. } => match source.kind() {
+                    std::io::ErrorKind::NotFound => Status::not_found(msg),
+                    std::io::ErrorKind::PermissionDenied => {
+                        Status::permission_denied(msg)
+                    }
+                    _ => Status::internal(msg),
+                },
+            },
+            CellsServiceError::NetCheckError(e) => match e {
+                NetCheckError::Resolve { .. }
+                | NetCheckError::NoAddresses { .. } => Status::not_found(msg),
+                NetCheckError::InvalidServerName { .. } => {
+                    Status::invalid_argument(msg)
+                }
+                NetCheckError::RateLimited => Status::resource_exhausted(msg),
+            },
             CellsServiceError::Io(_) => Status::internal(msg),
             CellsServiceError::ClientError(e) => match e {
-                ClientError::ConnectionError(_) => Status::unavailable(msg),
+                ClientError::ConnectionError(_)
+                | ClientError::Disconnected(_)
+                | ClientError::Socket { .. } => Status::unavailable(msg),
+                ClientError::NodeNotFound { .. } => Status::not_found(msg),
                 ClientError::Other(_) => Status::unknown(msg),
             },
             CellsServiceError::ObserveServiceError(e) => e.into(),
+            CellsServiceError::AdmissionError(e) => e.into(),
+            CellsServiceError::BlockingError(_) => Status::internal(msg),
+            CellsServiceError::AdmissionNotEnabled => {
+                Status::failed_precondition(msg)
+            }
+            CellsServiceError::DeadlineExceeded { .. } => {
+                Status::deadline_exceeded(msg)
+            }
+        };
+
+        match details {
+            Some(details) => details.attach(status),
+            None => status,
         }
     }
-}
\ No newline at end of file
+}
+
+/// The structured details of `err`, for clients to match on.
+fn details(err: &CellsServiceError) -> Option<ErrorDetails> {
+    let details = match err {
+        CellsServiceError::CellsError(e) => match e {
+            CellsError::CellNotFound { cell_name }
+            | CellsError::CellNotAllocated { cell_name }
+            | CellsError::CgroupNotFound { cell_name } => {
+                ErrorDetails::NotFound {
+                    resource: Resource::Cell(cell_name.to_string()),
+                }
+            }
+            CellsError::CellExists { cell_name }
+            | CellsError::CellExistsWithDifferentSpec { cell_name, .. } => {
+                ErrorDetails::AlreadyExists {
+                    resource: Resource::Cell(cell_name.to_string()),
+                }
+            }
+            CellsError::PortReserved { port, .. } => {
+                ErrorDetails::AlreadyExists { resource: Resource::Port(*port) }
+            }
+            CellsError::AbortedAllocateCell { source, .. }
+            | CellsError::FailedToUpdateCell { source, .. }
+            | CellsError::FailedToFreeCell { source, .. }
+            | CellsError::FailedToReadStats { source, .. } => match source {
+                CgroupsError::CreateCgroup { cell_name, .. }
+                | CgroupsError::UpdateCgroup { cell_name, .. }
+                | CgroupsError::AddTaskToCgroup { cell_name, .. }
+                | CgroupsError::DeleteCgroup { cell_name, .. }
+                | CgroupsError::ReadStats { cell_name, .. } => {
+                    ErrorDetails::CgroupIo { cell_name: cell_name.to_string() }
+                }
+                CgroupsError::Frozen { .. }
+                | CgroupsError::Unsupported { .. }
+                | CgroupsError::OutsideCgroupRoot { .. } => return None,
+            },
+            _ => return None,
+        },
+        CellsServiceError::ExecutablesError(e) => match e {
+            ExecutablesError::ExecutableNotFound { executable_name } => {
+                ErrorDetails::NotFound {
+                    resource: Resource::Executable(executable_name.to_string()),
+                }
+            }
+            ExecutablesError::ExecutableExists { executable_name } => {
+                ErrorDetails::AlreadyExists {
+                    resource: Resource::Executable(executable_name.to_string()),
+                }
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(details)
+}
@@ -0,0 +1,166 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The order the executables of a batch start in, each after the executables
+//! it depends on.
+
+use super::{ExecutableName, ExecutablesError, Result};
+use std::collections::HashMap;
+
+/// Returns the indices of `executables`, given with the names they depend
+/// on, in the order to start them: each after its dependencies, and
+/// otherwise in the order given.
+pub fn start_order(
+    executables: &[(&ExecutableName, &[ExecutableName])],
+) -> Result<Vec<usize>> {
+    let indices: HashMap<&ExecutableName, usize> = executables
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| (*name, i))
+        .collect();
+
+    let mut dependencies = Vec::with_capacity(executables.len());
+    for (name, depends_on) in executables {
+        let indices = depends_on
+            .iter()
+            .map(|dependency| {
+                indices.get(dependency).copied().ok_or_else(|| {
+                    ExecutablesError::UnknownDependency {
+                        executable_name: (*name).clone(),
+                        dependency: dependency.clone(),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        dependencies.push(indices);
+    }
+
+    let mut order = OrderBuilder {
+        dependencies: &dependencies,
+        visiting: vec![],
+        order: Vec::with_capacity(executables.len()),
+    };
+    for i in 0..executables.len() {
+        order.visit(i).map_err(|cycle| ExecutablesError::DependencyCycle {
+            cycle: cycle
+                .into_iter()
+                .map(|i| executables[i].0.clone())
+                .collect(),
+        })?;
+    }
+
+    Ok(order.order)
+}
+
+struct OrderBuilder<'a> {
+    dependencies: &'a [Vec<usize>],
+    /// The executables whose dependencies are being visited, each depending
+    /// on the one after it.
+    visiting: Vec<usize>,
+    order: Vec<usize>,
+}
+
+impl OrderBuilder<'_> {
+    /// Adds `i` to the order after its dependencies. Returns the cycle, from
+    /// and back to the same executable, if `i` depends on itself.
+    fn visit(&mut self, i: usize) -> std::result::Result<(), Vec<usize>> {
+        if self.order.contains(&i) {
+            return Ok(());
+        }
+
+        if let Some(start) = self.visiting.iter().position(|&x| x == i) {
+            let mut cycle = self.visiting[start..].to_vec();
+            cycle.push(i);
+            return Err(cycle);
+        }
+
+        self.visiting.push(i);
+        for &dependency in &self.dependencies[i] {
+            self.visit(dependency)?;
+        }
+        let _ = self.visiting.pop();
+
+        self.order.push(i);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(executables: &[(&str, &[&str])]) -> Result<Vec<usize>> {
+        let executables: Vec<(ExecutableName, Vec<ExecutableName>)> =
+            executables
+                .iter()
+                .map(|(name, depends_on)| {
+                    (
+                        ExecutableName::new(name.to_string()),
+                        depends_on
+                            .iter()
+                            .map(|x| ExecutableName::new(x.to_string()))
+                            .collect(),
+                    )
+                })
+                .collect();
+        let executables: Vec<_> = executables
+            .iter()
+            .map(|(name, depends_on)| (name, depends_on.as_slice()))
+            .collect();
+
+        start_order(&executables)
+    }
+
+    #[test]
+    fn test_start_order_starts_dependencies_first() {
+        assert_eq!(order(&[("a", &[]), ("b", &[])]).unwrap(), [0, 1]);
+        assert_eq!(
+            order(&[
+                ("app", &["db", "cache"]),
+                ("worker", &["db"]),
+                ("db", &[]),
+                ("cache", &["db"]),
+            ])
+            .unwrap(),
+            [2, 3, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_start_order_rejects_cycles() {
+        let err = order(&[
+            ("app", &["db"]),
+            ("db", &["migrate"]),
+            ("migrate", &["db"]),
+        ])
+        .expect_err("cycle");
+        assert_eq!(
+            err.to_string(),
+            "executables depend on each other in a cycle: db -> migrate -> db"
+        );
+
+        let err = order(&[("app", &["app"])]).expect_err("cycle");
+        assert!(matches!(
+            err,
+            ExecutablesError::DependencyCycle { cycle } if cycle.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_start_order_rejects_unknown_dependencies() {
+        let err = order(&[("app", &["db"])]).expect_err("unknown dependency");
+        assert!(matches!(err, ExecutablesError::UnknownDependency { .. }));
+    }
+}
@@ -13,7 +13,7 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use super::ExecutableName;
+use super::{ExecutableName, MountsError, RootfsError, SeccompProfileError};
 use std::io;
 use thiserror::Error;
 
@@ -25,14 +25,71 @@ pub enum ExecutablesError {
     ExecutableExists { executable_name: ExecutableName },
     #[error("executable '{executable_name}' not found")]
     ExecutableNotFound { executable_name: ExecutableName },
+    #[error("executable '{executable_name}' is not running")]
+    ExecutableNotRunning { executable_name: ExecutableName },
+    #[error("executable '{executable_name}' daemonized, which is forbidden")]
+    ExecutableDaemonized { executable_name: ExecutableName },
+    #[error(
+        "executable '{executable_name}' has an invalid seccomp profile: {source}"
+    )]
+    InvalidSeccompProfile {
+        executable_name: ExecutableName,
+        source: SeccompProfileError,
+    },
+    #[error(
+        "executable '{executable_name}' references unresolved variables: {}",
+        variables.join(", ")
+    )]
+    UnresolvedVariables {
+        executable_name: ExecutableName,
+        variables: Vec<String>,
+    },
+    #[error("executable '{executable_name}' has an invalid rootfs: {source}")]
+    InvalidRootfs { executable_name: ExecutableName, source: RootfsError },
+    #[error("executable '{executable_name}' has an invalid mount: {source}")]
+    InvalidMounts { executable_name: ExecutableName, source: MountsError },
+    #[error(
+        "executable '{executable_name}' depends on '{dependency}', which is not started with it"
+    )]
+    UnknownDependency {
+        executable_name: ExecutableName,
+        dependency: ExecutableName,
+    },
+    #[error(
+        "executables depend on each other in a cycle: {}",
+        cycle.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    DependencyCycle { cycle: Vec<ExecutableName> },
+    #[error(
+        "executable '{executable_name}' was not started, as its dependency '{dependency}' {reason}"
+    )]
+    DependencyFailed {
+        executable_name: ExecutableName,
+        dependency: ExecutableName,
+        reason: String,
+    },
     #[error("executable '{executable_name}' failed to start: {source}")]
     FailedToStartExecutable {
         executable_name: ExecutableName,
         source: io::Error,
     },
+    #[error(
+        "executable '{executable_name}' can not be killed with its cgroup, as it does not run in a cell with per executable accounting"
+    )]
+    KillModeRequiresCell { executable_name: ExecutableName },
     #[error("executable '{executable_name}' failed to stop: {source}")]
     FailedToStopExecutable {
         executable_name: ExecutableName,
         source: io::Error,
     },
-}
\ No newline at end of file
+    #[error("executable '{executable_name}' failed to quarantine: {source}")]
+    FailedToQuarantineExecutable {
+        executable_name: ExecutableName,
+        source: io::Error,
+    },
+    #[error("executable '{executable_name}' failed to unquarantine: {source}")]
+    FailedToUnquarantineExecutable {
+        executable_name: ExecutableName,
+        source: io::Error,
+    },
+}
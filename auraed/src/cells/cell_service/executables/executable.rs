@@ -12,18 +12,75 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-use super::{ExecutableName, ExecutableSpec};
+use super::{
+    privileges, rootfs, user_namespace::UserNamespaceMapper, CapabilitiesSpec,
+    ExecutableName, ExecutableSpec, Mounts, Rootfs, SeccompProfile,
+    UserNamespace,
+};
+use crate::blocking::{self, BlockingJob, Pool};
+use crate::cells::cell_service::cells::cgroups::{
+    CgroupStats, ExecutableCgroup,
+};
 use crate::logging::log_channel::LogChannel;
-use nix::unistd::Pid;
+use crate::logging::log_registry::{LogKey, LogRegistry};
+use crate::logging::output_drain;
+use crate::logging::output_limit::{self, OutputLimit, OutputLimiter};
+use crate::reaper::{self, pidfd_open, ReapedChild};
+use nix::{
+    errno::Errno,
+    sys::signal::{killpg, Signal},
+    unistd::{getpgid, Pid},
+};
+use proto::observe::{LogChannelType, LogLevel};
 use std::{
-    ffi::OsString,
+    ffi::{OsStr, OsString},
     io,
+    os::fd::{AsRawFd, OwnedFd},
+    path::Path,
     process::{ExitStatus, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::io::{
+    unix::AsyncFd, AsyncBufReadExt, AsyncRead, BufReader, Interest,
 };
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
+use tokio::process::{ChildStderr, ChildStdout, Command};
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
-use tracing::info_span;
+use tracing::{info, info_span, warn, Span};
+
+/// How often a starting executable is checked for having daemonized.
+const DAEMONIZE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long the processes of a killed process group are waited for.
+const KILLED_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a killed process group is checked for processes left.
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The number of lines of each output channel retained, to replay to those
+/// who start reading the output late.
+const LOG_HISTORY_LINES: usize = 1000;
+
+/// Set in the environment of every started executable, and inherited by the
+/// processes it starts, to find the ones it leaves running when it exits.
+pub const EXECUTABLE_ID_ENV: &str = "AURAE_EXECUTABLE_ID";
+
+/// Which of the processes of an executable are killed when it is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillMode {
+    /// Only the process of the executable, or the process tracked as the
+    /// leader of a daemonized executable.
+    Process,
+    /// The process group of the executable, and that of the process
+    /// tracked as the leader of a daemonized executable.
+    ProcessGroup,
+    /// Every process in the leaf cgroup of the executable, killed with
+    /// `cgroup.kill`, and then its process group. Only executables of a
+    /// cell with per executable accounting have a leaf of their own.
+    Cgroup,
+}
 
 // TODO: decide if we're going to use the description or not.  Remove if not.
 #[allow(dead_code)]
@@ -33,94 +90,316 @@ pub struct Executable {
     pub description: String,
     pub stdout: LogChannel,
     pub stderr: LogChannel,
+    output_limit: OutputLimit,
+    suppressed_stdout_lines: Arc<AtomicU64>,
+    suppressed_stderr_lines: Arc<AtomicU64>,
     state: ExecutableState,
+    quarantined: bool,
+    forbid_daemonize: bool,
+    daemonized: bool,
+    output_truncated: bool,
+    /// The leaf cgroup the executable is accounted in, if its cell has per
+    /// executable accounting.
+    cgroup: Option<ExecutableCgroup>,
+    /// The pid the process was started or adopted with, which is kept once
+    /// it exits. The log channels are registered under it as well.
+    started_pid: Option<Pid>,
 }
 
 #[derive(Debug)]
 enum ExecutableState {
     Init {
         command: Command,
+        no_new_privs: bool,
+        capabilities: Option<CapabilitiesSpec>,
     },
     Started {
-        #[allow(unused)]
         program: OsString,
-        #[allow(unused)]
         args: Vec<OsString>,
-        child: Child,
-        stdout: JoinHandle<()>,
-        stderr: JoinHandle<()>,
+        id: String,
+        /// When the process started, in clock ticks since boot.
+        start_time: Option<u64>,
+        /// The process group the process leads.
+        pgid: Pid,
+        /// Reaped by the reaper, rather than waited on by tokio, which
+        /// would consume its exit status first.
+        child: ReapedChild,
+        stdout: OutputTask,
+        stderr: OutputTask,
+    },
+    /// The process exited, leaving processes it started running, as a
+    /// program that daemonizes does. One of them is tracked as the leader,
+    /// and the executable runs until they are all gone.
+    Daemonized {
+        program: OsString,
+        args: Vec<OsString>,
+        id: String,
+        pgid: Pid,
+        leader: Pid,
+        leader_pidfd: OwnedFd,
+        stdout: OutputTask,
+        stderr: OutputTask,
+    },
+    /// Started by a previous auraed. The process is not our child, so its
+    /// output and exit status can't be observed.
+    Adopted {
+        program: OsString,
+        args: Vec<OsString>,
+        pid: Pid,
+        start_time: u64,
+        /// Refers to the adopted process, even once its pid is reused.
+        pidfd: OwnedFd,
     },
-    Stopped(ExitStatus),
+    /// The exit status is [None] if it is unknown.
+    Stopped(Option<ExitStatus>),
 }
 
 impl Executable {
     pub fn new<T: Into<ExecutableSpec>>(spec: T) -> Self {
-        let ExecutableSpec { name, description, command } = spec.into();
-        let state = ExecutableState::Init { command };
-        let stdout = LogChannel::new(format!("{name}::stdout"));
-        let stderr = LogChannel::new(format!("{name}::stderr"));
-        Self { name, description, stdout, stderr, state }
+        let ExecutableSpec {
+            name,
+            description,
+            command,
+            forbid_daemonize,
+            disable_log_redaction,
+            seccomp_profile: _,
+            no_new_privs,
+            capabilities,
+            rootfs: _,
+            output_limit,
+            mounts: _,
+        } = spec.into();
+        let state =
+            ExecutableState::Init { command, no_new_privs, capabilities };
+        let stdout = register_log_channel(
+            &name,
+            LogChannelType::Stdout,
+            !disable_log_redaction,
+        );
+        let stderr = register_log_channel(
+            &name,
+            LogChannelType::Stderr,
+            !disable_log_redaction,
+        );
+        Self {
+            name,
+            description,
+            stdout,
+            stderr,
+            output_limit: output_limit.or_default(),
+            suppressed_stdout_lines: Default::default(),
+            suppressed_stderr_lines: Default::default(),
+            state,
+            quarantined: false,
+            forbid_daemonize,
+            daemonized: false,
+            output_truncated: false,
+            cgroup: None,
+            started_pid: None,
+        }
     }
 
-    /// Starts the underlying process.
+    /// Creates an [Executable] from one started by a previous auraed, with
+    /// the `cgroup` it is accounted in, if any.
+    /// If `pid` is no longer the process that started at `start_time`, the
+    /// executable is created as stopped, with an unknown exit status, as it
+    /// is if either is unknown. A process that is stopped by a signal was
+    /// quarantined by the previous auraed, and remains so.
+    pub fn adopt(
+        name: ExecutableName,
+        description: String,
+        command: Vec<OsString>,
+        pid: Option<Pid>,
+        start_time: Option<u64>,
+        cgroup: Option<ExecutableCgroup>,
+    ) -> Self {
+        let stdout = register_log_channel(&name, LogChannelType::Stdout, true);
+        let stderr = register_log_channel(&name, LogChannelType::Stderr, true);
+
+        let mut command = command.into_iter();
+        let state = match (pid, start_time, command.next()) {
+            (Some(pid), Some(start_time), Some(program)) => {
+                match open_started_at(pid, start_time) {
+                    Some(pidfd) => ExecutableState::Adopted {
+                        program,
+                        args: command.collect(),
+                        pid,
+                        start_time,
+                        pidfd,
+                    },
+                    None => {
+                        warn!("executable '{name}' (pid {pid}) is gone, marking it as stopped");
+                        ExecutableState::Stopped(None)
+                    }
+                }
+            }
+            _ => ExecutableState::Stopped(None),
+        };
+
+        let quarantined = match &state {
+            ExecutableState::Adopted { pid, .. } => is_stopped(*pid),
+            _ => false,
+        };
+
+        let mut executable = Self {
+            name,
+            description,
+            stdout,
+            stderr,
+            output_limit: OutputLimit::UNSET,
+            suppressed_stdout_lines: Default::default(),
+            suppressed_stderr_lines: Default::default(),
+            state,
+            quarantined,
+            forbid_daemonize: false,
+            daemonized: false,
+            output_truncated: false,
+            cgroup,
+            started_pid: None,
+        };
+
+        // Adopted processes write to the pipes of the previous auraed, so
+        // their channels only make them observable again.
+        if let Ok(Some(pid)) = executable.pid() {
+            executable.register_log_channels_of(pid);
+        }
+        executable
+    }
+
+    /// Starts the underlying process, in `user_namespace`, confined to
+    /// `rootfs`, with `mounts` made, and under `seccomp_profile` if any.
+    /// In a user namespace, the ids are those in it, and default to root.
+    /// The process is moved into `cgroup`, if any, before it execs.
     /// Does nothing if [Executable] has previously been started.
+    #[allow(clippy::too_many_arguments)]
     pub fn start(
         &mut self,
         uid: Option<u32>,
         gid: Option<u32>,
+        seccomp_profile: Option<SeccompProfile>,
+        rootfs: Option<Rootfs>,
+        mounts: Option<Mounts>,
+        user_namespace: Option<&UserNamespace>,
+        cgroup: Option<ExecutableCgroup>,
     ) -> io::Result<()> {
-        let ExecutableState::Init { command } = &mut self.state else {
+        let ExecutableState::Init { command, no_new_privs, capabilities } =
+            &mut self.state
+        else {
             return Ok(());
         };
 
+        let (mapper, enter_user_namespace) =
+            match user_namespace.map(UserNamespace::prepare).transpose()? {
+                Some((mapper, enter)) => (Some(mapper), Some(enter)),
+                None => (None, None),
+            };
+        let (uid, gid) = match user_namespace {
+            Some(_) => (uid.or(Some(0)), gid.or(Some(0))),
+            None => (uid, gid),
+        };
+
+        // Lead a process group, so the processes the executable starts can be
+        // quarantined along with it.
+        let id = uuid::Uuid::new_v4().to_string();
         let mut command = command
-            .kill_on_drop(true)
+            .env(EXECUTABLE_ID_ENV, &id)
+            .process_group(0)
             .current_dir("/")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
-        if uid.is_some() {
-            command = command.uid(uid.expect("uid"));
-        }
-        if gid.is_some() {
-            command = command.gid(gid.expect("gid"));
-        }
-        let mut child = command.spawn()?;
-
-        let log_channel = self.stdout.clone();
-        let stdout = child.stdout.take().expect("stdout");
-        let span = info_span!("running process", name = ?self.name);
-        let stdout = tokio::spawn(async move {
-            let log_channel = log_channel;
-            let mut span = Some(span);
-            let mut stdout = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = stdout.next_line().await {
-                let entered_span = span.take().expect("span").entered();
-                //info!(level = "info", channel = log_channel.name, line);
-                // if std::env::var("AER").is_ok() {
-                //     println!("{line}");
-                // }
-                log_channel.send(line);
-                span = Some(entered_span.exit());
+        let capabilities = *capabilities;
+        let join_cgroup = cgroup.as_ref().map(ExecutableCgroup::joiner);
+        if capabilities.is_some()
+            || rootfs.is_some()
+            || mounts.is_some()
+            || enter_user_namespace.is_some()
+            || join_cgroup.is_some()
+        {
+            // SAFETY: joining the cgroup, entering the user namespace and the
+            // rootfs, making the mounts, and setting the ids and
+            // capabilities, only make syscalls. The ids are set here rather
+            // than by the command, as they are those of the user namespace,
+            // the cgroup is joined, the rootfs entered and the mounts made as
+            // root, and keeping capabilities when the uid changes takes
+            // setting PR_SET_KEEPCAPS first.
+            command = unsafe {
+                command.pre_exec(move || {
+                    if let Some(join_cgroup) = &join_cgroup {
+                        join_cgroup()?;
+                    }
+                    if let Some(enter) = &enter_user_namespace {
+                        enter.enter()?;
+                    }
+                    if let Some(rootfs) = &rootfs {
+                        rootfs.enter()?;
+                    }
+                    if let Some(mounts) = &mounts {
+                        if rootfs.is_none() {
+                            rootfs::unshare_mount_namespace()?;
+                        }
+                        mounts.apply()?;
+                    }
+                    match &capabilities {
+                        Some(capabilities) => {
+                            privileges::set_ids_and_capabilities(
+                                capabilities,
+                                uid,
+                                gid,
+                            )
+                        }
+                        None => privileges::set_ids(uid, gid),
+                    }
+                })
+            };
+        } else {
+            if uid.is_some() {
+                command = command.uid(uid.expect("uid"));
             }
-        });
-
-        let log_channel = self.stderr.clone();
-        let stderr = child.stderr.take().expect("stderr");
-        let span = info_span!("running process", name = ?self.name);
-        let stderr = tokio::spawn(async move {
-            let log_channel = log_channel;
-            let mut span = Some(span);
-            let mut stderr = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = stderr.next_line().await {
-                let entered_span = span.take().expect("span").entered();
-                // info!(level = "error", channel = log_channel.name, line);
-                // if std::env::var("AER").is_ok() {
-                //     println!("{line}");
-                // }
-                log_channel.send(line);
-                span = Some(entered_span.exit());
+            if gid.is_some() {
+                command = command.gid(gid.expect("gid"));
             }
-        });
+        }
+        if *no_new_privs {
+            // SAFETY: setting no_new_privs only makes a syscall
+            command = unsafe { command.pre_exec(privileges::set_no_new_privs) };
+        }
+        if let Some(seccomp_profile) = seccomp_profile {
+            // SAFETY: applying the profile only makes syscalls, and doesn't
+            // allocate. It runs after the uid and gid are set, so the
+            // profile may deny changing them.
+            command =
+                unsafe { command.pre_exec(move || seccomp_profile.apply()) };
+        }
+        let spawning = reaper::spawning();
+        let mapping = mapper.map(UserNamespaceMapper::spawn);
+        #[allow(clippy::disallowed_methods)] // spawned under spawning
+        let child = command.as_std_mut().spawn();
+        if let Some(mapping) = mapping {
+            mapping.finish()?;
+        }
+        let mut child = child?;
+        let pid = Pid::from_raw(child.id() as i32);
+        let reaped = spawning.reap(pid);
+        let start_time = start_time_of(pid);
+
+        let stdout = OutputTask::spawn(
+            ChildStdout::from_std(child.stdout.take().expect("stdout"))?,
+            self.stdout.clone(),
+            OutputLimiter::new(
+                self.output_limit,
+                self.suppressed_stdout_lines.clone(),
+            ),
+            info_span!("running process", name = ?self.name),
+        );
+        let stderr = OutputTask::spawn(
+            ChildStderr::from_std(child.stderr.take().expect("stderr"))?,
+            self.stderr.clone(),
+            OutputLimiter::new(
+                self.output_limit,
+                self.suppressed_stderr_lines.clone(),
+            ),
+            info_span!("running process", name = ?self.name),
+        );
 
         self.state = ExecutableState::Started {
             program: command.as_std().get_program().to_os_string(),
@@ -129,37 +408,823 @@ impl Executable {
                 .get_args()
                 .map(|arg| arg.to_os_string())
                 .collect(),
-            child,
+            id,
+            start_time,
+            pgid: pid,
+            child: reaped,
             stdout,
             stderr,
         };
+        self.cgroup = cgroup;
+        self.register_log_channels_of(pid);
 
         Ok(())
     }
 
-    /// Stops the executable and returns the [ExitStatus].
-    /// If the executable has never been started, returns [None].
+    /// Stops the executable, killing its process group, and returns the
+    /// [ExitStatus]. See [Executable::kill_with].
     pub async fn kill(&mut self) -> io::Result<Option<ExitStatus>> {
-        Ok(match &mut self.state {
+        self.kill_with(KillMode::ProcessGroup).await
+    }
+
+    /// Stops the executable, killing the processes `kill_mode` applies to,
+    /// and returns the [ExitStatus].
+    /// Returns [None] if the executable has never been started, or if the
+    /// exit status is unknown.
+    /// Its outputs are read until they close, or for the drain timeout of
+    /// auraed, after which they are cut short. See
+    /// [Executable::output_truncated].
+    /// Its log channels are deregistered after the grace period of the
+    /// [LogRegistry], for late readers to drain the last lines.
+    pub async fn kill_with(
+        &mut self,
+        kill_mode: KillMode,
+    ) -> io::Result<Option<ExitStatus>> {
+        // The processes the executable started are frozen along with it,
+        // and would otherwise remain so.
+        if self.quarantined {
+            self.signal_group(Signal::SIGKILL)?;
+            self.quarantined = false;
+        }
+
+        // Killing the leaf kills the executable along with the processes
+        // it started, including those that left its process group.
+        if kill_mode == KillMode::Cgroup {
+            if let Some(cgroup) = self.cgroup.take() {
+                let (cgroup, killed) = blocking::run(KillCgroup(cgroup))
+                    .await
+                    .map_err(io::Error::other)?;
+                self.cgroup = Some(cgroup);
+                killed?;
+            }
+        }
+
+        let exit_status = match &mut self.state {
             ExecutableState::Init { .. } => None,
-            ExecutableState::Started { child, stdout, stderr, .. } => {
-                child.kill().await?;
-                let exit_status = child.wait().await?;
-                let _ = tokio::join!(stdout, stderr);
-                self.state = ExecutableState::Stopped(exit_status);
+            ExecutableState::Started {
+                child, pgid, stdout, stderr, ..
+            } => {
+                // The process may have exited (and been reaped) on its own,
+                // in which case it can no longer be killed.
+                let exit_status = match child.try_wait()? {
+                    Some(exit_status) => exit_status,
+                    None => {
+                        child.kill()?;
+                        child.wait().await?
+                    }
+                };
+                // Processes it left running may hold its output open.
+                if kill_mode != KillMode::Process {
+                    kill_groups(&[*pgid]).await?;
+                }
+                self.output_truncated = drain_outputs(stdout, stderr).await;
+                self.state = ExecutableState::Stopped(Some(exit_status));
                 Some(exit_status)
             }
-            ExecutableState::Stopped(status) => Some(*status),
+            ExecutableState::Daemonized {
+                pgid,
+                leader,
+                leader_pidfd,
+                stdout,
+                stderr,
+                ..
+            } => {
+                if kill_mode == KillMode::Process {
+                    // Signaled through the pidfd, which can't refer to
+                    // another process reusing the pid.
+                    pidfd_send_signal(leader_pidfd, Signal::SIGKILL)?;
+                } else {
+                    // The leader may have left the group in a session of
+                    // its own
+                    let mut pgids = vec![*pgid];
+                    if !has_exited(leader_pidfd) {
+                        pgids.extend(getpgid(Some(*leader)).ok());
+                    }
+                    kill_groups(&pgids).await?;
+                }
+                self.output_truncated = drain_outputs(stdout, stderr).await;
+                self.state = ExecutableState::Stopped(None);
+                None
+            }
+            ExecutableState::Adopted { pidfd, .. } => {
+                // Signaled through the pidfd, which can't refer to another
+                // process reusing the pid.
+                if !has_exited(pidfd) {
+                    pidfd_send_signal(pidfd, Signal::SIGKILL)?;
+                    exited(pidfd).await?;
+                }
+                self.state = ExecutableState::Stopped(None);
+                None
+            }
+            ExecutableState::Stopped(status) => *status,
+        };
+
+        // Removed once the processes of the executable are gone
+        if let Some(cgroup) = self.cgroup.take() {
+            if let Err(e) = cgroup.remove() {
+                warn!(
+                    "failed to remove the cgroup of executable '{}': {e}",
+                    self.name
+                );
+            }
+        }
+
+        self.deregister_log_channels();
+        Ok(exit_status)
+    }
+
+    /// Registers the log channels under the `pid` of the process as well,
+    /// for readers that know the process rather than the executable.
+    fn register_log_channels_of(&mut self, pid: Pid) {
+        self.started_pid = Some(pid);
+        let registry = LogRegistry::global();
+        for (channel_type, channel) in [
+            (LogChannelType::Stdout, &self.stdout),
+            (LogChannelType::Stderr, &self.stderr),
+        ] {
+            registry.register(
+                LogKey::process(pid.as_raw(), channel_type),
+                channel.clone(),
+            );
+        }
+    }
+
+    fn deregister_log_channels(&self) {
+        let registry = LogRegistry::global();
+        for (channel_type, channel) in [
+            (LogChannelType::Stdout, &self.stdout),
+            (LogChannelType::Stderr, &self.stderr),
+        ] {
+            registry.deregister(
+                LogKey::executable(self.name.clone(), channel_type),
+                channel,
+            );
+            if let Some(pid) = self.started_pid {
+                registry.deregister(
+                    LogKey::process(pid.as_raw(), channel_type),
+                    channel,
+                );
+            }
+        }
+    }
+
+    /// Returns true if the process has been started and has not exited, or
+    /// if it has daemonized and the processes it left running have not.
+    pub fn is_running(&mut self) -> io::Result<bool> {
+        self.track_daemonized()?;
+
+        Ok(match &mut self.state {
+            ExecutableState::Started { child, .. } => {
+                child.try_wait()?.is_none()
+            }
+            ExecutableState::Daemonized { .. } => true,
+            ExecutableState::Adopted { pidfd, .. } => !has_exited(pidfd),
+            ExecutableState::Init { .. } | ExecutableState::Stopped(_) => false,
         })
     }
 
+    /// Returns the number of lines of stdout, and of stderr, suppressed by
+    /// the output limit since the executable started.
+    pub fn suppressed_lines(&self) -> (u64, u64) {
+        (
+            self.suppressed_stdout_lines.load(Ordering::Relaxed),
+            self.suppressed_stderr_lines.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Returns the leaf cgroup the executable is accounted in, if its cell
+    /// has per executable accounting.
+    pub fn cgroup_path(&self) -> Option<&Path> {
+        self.cgroup.as_ref().map(ExecutableCgroup::path)
+    }
+
+    /// Reads the usage of the executable from its cgroup. Returns [None] if
+    /// it is not accounted in one, or if the cgroup can't be read.
+    pub fn cgroup_stats(&self) -> Option<CgroupStats> {
+        self.cgroup.as_ref().and_then(|cgroup| cgroup.stats().ok())
+    }
+
+    /// Returns true if the outputs were still held open when the executable
+    /// was killed, and were cut short rather than read to their end.
+    pub fn output_truncated(&self) -> bool {
+        self.output_truncated
+    }
+
+    /// Returns true if the process exited, leaving processes it started
+    /// running. See [Executable::track_daemonized].
+    pub fn is_daemonized(&self) -> bool {
+        self.daemonized
+    }
+
+    /// Waits up to `grace` for the process to daemonize, and returns
+    /// true if it did. Returns early if the process exits.
+    pub async fn wait_daemonized(
+        &mut self,
+        grace: Duration,
+    ) -> io::Result<bool> {
+        let deadline = tokio::time::Instant::now() + grace;
+        while self.is_running()?
+            && !self.is_daemonized()
+            && tokio::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(DAEMONIZE_POLL_INTERVAL).await;
+        }
+        Ok(self.is_daemonized())
+    }
+
+    /// Looks for the processes a started executable left running when it
+    /// exited, and keeps tracking them as [ExecutableState::Daemonized]
+    /// until they are all gone. Executables which forbid daemonizing have
+    /// them killed instead.
+    ///
+    /// The processes are found by the id in their environment, so this
+    /// does not apply to processes that clear it, nor to adopted executables.
+    fn track_daemonized(&mut self) -> io::Result<()> {
+        let mut exit_status = None;
+        match &mut self.state {
+            ExecutableState::Started { child, id, .. } => {
+                let Some(status) = child.try_wait()? else {
+                    return Ok(());
+                };
+                if survivors(id).is_empty() {
+                    return Ok(());
+                }
+
+                self.daemonized = true;
+                if self.forbid_daemonize {
+                    warn!(
+                        "executable '{}' daemonized, killing the processes it left running",
+                        self.name
+                    );
+                    signal_survivors(id, Signal::SIGKILL)?;
+                    self.state = ExecutableState::Stopped(Some(status));
+                    return Ok(());
+                }
+                exit_status = Some(status);
+            }
+            ExecutableState::Daemonized { leader_pidfd, .. } => {
+                if !has_exited(leader_pidfd) {
+                    return Ok(());
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        let (ExecutableState::Started {
+            program,
+            args,
+            id,
+            pgid,
+            stdout,
+            stderr,
+            ..
+        }
+        | ExecutableState::Daemonized {
+            program,
+            args,
+            id,
+            pgid,
+            stdout,
+            stderr,
+            ..
+        }) = std::mem::replace(
+            &mut self.state,
+            ExecutableState::Stopped(exit_status),
+        )
+        else {
+            unreachable!("executable is started or daemonized");
+        };
+
+        // Track the group leader of the remaining processes, or the one with
+        // the lowest pid. Another is picked whenever the tracked one exits.
+        let mut survivors = survivors(&id);
+        survivors.sort_by_key(|pid| (getpgid(Some(*pid)) != Ok(*pid), *pid));
+        for leader in survivors {
+            // The process may exit between being found and being opened.
+            let Ok(leader_pidfd) = pidfd_open(leader) else {
+                continue;
+            };
+            info!(
+                "executable '{}' daemonized, tracking pid {leader}",
+                self.name
+            );
+            self.state = ExecutableState::Daemonized {
+                program,
+                args,
+                id,
+                pgid,
+                leader,
+                leader_pidfd,
+                stdout,
+                stderr,
+            };
+            return Ok(());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the id set as the [EXECUTABLE_ID_ENV] of the executable, or
+    /// [None] if it is not running or was adopted.
+    pub fn id(&self) -> Option<&str> {
+        match &self.state {
+            ExecutableState::Started { id, .. }
+            | ExecutableState::Daemonized { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the program and arguments the executable was started with,
+    /// or [None] if it is not running.
+    pub fn command(&self) -> Option<Vec<OsString>> {
+        let (ExecutableState::Started { program, args, .. }
+        | ExecutableState::Daemonized { program, args, .. }
+        | ExecutableState::Adopted { program, args, .. }) = &self.state
+        else {
+            return None;
+        };
+
+        Some(std::iter::once(program).chain(args).cloned().collect())
+    }
+
+    /// Asks the executable to exit by sending SIGTERM to its process group.
+    /// A quarantined executable is frozen and only exits once killed. See
+    /// [Executable::kill].
+    pub fn terminate(&mut self) -> io::Result<()> {
+        self.signal_group(Signal::SIGTERM)
+    }
+
+    /// Freezes the processes of the executable with SIGSTOP, keeping them
+    /// for inspection. Does nothing if it is already quarantined.
+    pub fn quarantine(&mut self) -> io::Result<()> {
+        if !self.quarantined {
+            self.signal_group(Signal::SIGSTOP)?;
+            self.quarantined = true;
+        }
+        Ok(())
+    }
+
+    /// Resumes the processes of a quarantined executable with SIGCONT.
+    /// Does nothing if it is not quarantined.
+    pub fn unquarantine(&mut self) -> io::Result<()> {
+        if self.quarantined {
+            self.signal_group(Signal::SIGCONT)?;
+            self.quarantined = false;
+        }
+        Ok(())
+    }
+
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantined
+    }
+
+    /// Signals the process group of the executable, or only its process if
+    /// it does not lead a group. Does nothing if it is not running.
+    fn signal_group(&mut self, signal: Signal) -> io::Result<()> {
+        if !self.is_running()? {
+            return Ok(());
+        }
+        // A daemonized executable's processes may be in different groups.
+        if let ExecutableState::Daemonized { id, .. } = &self.state {
+            return signal_survivors(id, signal);
+        }
+
+        let Some(pid) = self.pid()? else {
+            return Ok(());
+        };
+
+        let result = if getpgid(Some(pid)) == Ok(pid) {
+            killpg(pid, signal)
+        } else {
+            nix::sys::signal::kill(pid, signal)
+        };
+
+        match result {
+            Ok(()) | Err(Errno::ESRCH) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Returns the [Pid] while [Executable] is running, otherwise returns [None].
     pub fn pid(&self) -> io::Result<Option<Pid>> {
-        let ExecutableState::Started { child: process, .. } = &self.state
-        else {
-            return Ok(None);
+        Ok(match &self.state {
+            ExecutableState::Started { child, .. } => child.id(),
+            ExecutableState::Daemonized { leader, .. } => Some(*leader),
+            ExecutableState::Adopted { pid, .. } => Some(*pid),
+            ExecutableState::Init { .. } | ExecutableState::Stopped(_) => None,
+        })
+    }
+
+    /// Returns the [ExitStatus] of the process once it exited, without
+    /// waiting. [None] if it has not, or if the exit status is unknown.
+    pub fn exit_status(&mut self) -> io::Result<Option<ExitStatus>> {
+        Ok(match &mut self.state {
+            ExecutableState::Started { child, .. } => child.try_wait()?,
+            ExecutableState::Stopped(exit_status) => *exit_status,
+            _ => None,
+        })
+    }
+
+    /// Returns the [Pid] the process was started or adopted with, even once
+    /// it exited, or [None] if it never was.
+    pub fn started_pid(&self) -> Option<Pid> {
+        self.started_pid
+    }
+
+    /// Returns when the process [Executable::pid] returns started, in clock
+    /// ticks since boot, which tells it apart from a later process reusing
+    /// its pid. [None] if it is not running, or can't be read.
+    pub fn start_time(&self) -> Option<u64> {
+        match &self.state {
+            ExecutableState::Started { start_time, .. } => *start_time,
+            ExecutableState::Daemonized { leader, leader_pidfd, .. } => {
+                // Read before checking the leader is still running, so it
+                // is not the start time of a process reusing its pid.
+                let start_time = start_time_of(*leader)?;
+                (!has_exited(leader_pidfd)).then_some(start_time)
+            }
+            ExecutableState::Adopted { start_time, .. } => Some(*start_time),
+            ExecutableState::Init { .. } | ExecutableState::Stopped(_) => None,
+        }
+    }
+
+    /// Calls `listener` with the [ExitStatus] of the process of a started
+    /// executable once it is reaped, or [None] if it is unknown. Returns
+    /// false, without calling it, if the process is not a child of auraed.
+    /// See [ReapedChild::on_exit].
+    pub fn on_exit(
+        &self,
+        listener: impl FnOnce(Option<ExitStatus>) + Send + 'static,
+    ) -> bool {
+        match &self.state {
+            ExecutableState::Started { child, .. } => {
+                child.on_exit(listener);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Drop for Executable {
+    /// Kills the process of an executable which was not killed, and
+    /// deregisters its log channels, such as one which failed to start.
+    fn drop(&mut self) {
+        if let ExecutableState::Started { child, .. } = &self.state {
+            let _ = child.kill();
+        }
+        self.deregister_log_channels();
+    }
+}
+
+/// Creates a log channel of the executable `name`, registered in the
+/// [LogRegistry] for readers to find it.
+fn register_log_channel(
+    name: &ExecutableName,
+    channel_type: LogChannelType,
+    redact: bool,
+) -> LogChannel {
+    let suffix = match channel_type {
+        LogChannelType::Stderr => "stderr",
+        _ => "stdout",
+    };
+    let mut channel = LogChannel::new(format!("{name}::{suffix}"))
+        .with_history(LOG_HISTORY_LINES);
+    if !redact {
+        channel = channel.without_redaction();
+    }
+
+    LogRegistry::global().register(
+        LogKey::executable(name.clone(), channel_type),
+        channel.clone(),
+    );
+    channel
+}
+
+/// The task forwarding an output of a started executable to its log
+/// channel.
+#[derive(Debug)]
+struct OutputTask {
+    /// Returns true if the output was cut short.
+    handle: JoinHandle<bool>,
+    /// Cuts the output short, flushing the partial line read so far.
+    cut: Arc<Notify>,
+}
+
+impl OutputTask {
+    fn spawn<R: AsyncRead + Unpin + Send + 'static>(
+        output: R,
+        log_channel: LogChannel,
+        limiter: OutputLimiter,
+        span: Span,
+    ) -> Self {
+        let cut = Arc::new(Notify::new());
+        let handle = tokio::spawn(forward_output(
+            output,
+            log_channel,
+            limiter,
+            cut.clone(),
+            span,
+        ));
+        Self { handle, cut }
+    }
+}
+
+/// Waits for both outputs to be read to their end, and cuts those still
+/// open short once the drain timeout of auraed has passed. Returns true if
+/// either was cut short.
+async fn drain_outputs(
+    stdout: &mut OutputTask,
+    stderr: &mut OutputTask,
+) -> bool {
+    let deadline = tokio::time::Instant::now() + output_drain::drain_timeout();
+    let mut truncated = false;
+    for output in [stdout, stderr] {
+        let cut_short =
+            match tokio::time::timeout_at(deadline, &mut output.handle).await {
+                Ok(cut_short) => cut_short,
+                Err(_) => {
+                    output.cut.notify_one();
+                    (&mut output.handle).await
+                }
+            };
+        truncated |= cut_short.unwrap_or(false);
+    }
+    truncated
+}
+
+/// Sends each line of `output` to `log_channel` until it is closed, or
+/// until `cut` is notified, returning true in the latter case. The lines
+/// over the limit of `limiter` are read and suppressed, rather than left to
+/// block the process, and reported at the end of each interval.
+async fn forward_output<R: AsyncRead + Unpin>(
+    output: R,
+    log_channel: LogChannel,
+    mut limiter: OutputLimiter,
+    cut: Arc<Notify>,
+    span: Span,
+) -> bool {
+    let report = |suppressed| {
+        log_channel
+            .send_at(LogLevel::Warn, output_limit::suppressed_line(suppressed))
+    };
+
+    // The bytes read are kept when the read is cancelled, so the partial
+    // line can be flushed when the output is cut short.
+    let mut output = BufReader::new(output);
+    let mut buf = Vec::new();
+    let cut_short = loop {
+        let report_deadline = limiter.report_deadline();
+        let read = tokio::select! {
+            read = output.read_until(b'\n', &mut buf) => read,
+            _ = cut.notified() => break true,
+            _ = tokio::time::sleep_until(
+                report_deadline.unwrap_or_else(Instant::now).into()
+            ), if report_deadline.is_some() => {
+                if let Some(suppressed) = limiter.take_report() {
+                    report(suppressed);
+                }
+                continue;
+            }
+        };
+        // The last line may not end with a line break.
+        let (Ok(1..), Some(line)) = (read, take_line(&mut buf)) else {
+            break false;
         };
 
-        Ok(process.id().map(|id| Pid::from_raw(id as i32)))
+        let _entered = span.enter();
+        let (suppressed, admitted) = limiter.admit(line.len(), Instant::now());
+        if let Some(suppressed) = suppressed {
+            report(suppressed);
+        }
+        if admitted {
+            log_channel.send(line);
+        }
+    };
+
+    if let Some(suppressed) = limiter.take_report() {
+        report(suppressed);
+    }
+    if cut_short {
+        let partial = take_line(&mut buf).unwrap_or_default();
+        log_channel.send(output_drain::truncated_line(&partial));
+    }
+    cut_short
+}
+
+/// Takes the line read into `buf`, without its line break. Returns [None]
+/// if it is not valid UTF-8.
+fn take_line(buf: &mut Vec<u8>) -> Option<String> {
+    let mut line = std::mem::take(buf);
+    if line.ends_with(b"\n") {
+        let _ = line.pop();
+        if line.ends_with(b"\r") {
+            let _ = line.pop();
+        }
+    }
+    String::from_utf8(line).ok()
+}
+
+/// Returns the live processes with `id` as their [EXECUTABLE_ID_ENV].
+fn survivors(id: &str) -> Vec<Pid> {
+    let Ok(processes) = procfs::process::all_processes() else {
+        return vec![];
+    };
+
+    processes
+        .filter_map(|process| process.ok())
+        .filter(|process| process.stat().is_ok_and(|stat| stat.state != 'Z'))
+        .filter(|process| {
+            process.environ().is_ok_and(|environ| {
+                environ
+                    .get(OsStr::new(EXECUTABLE_ID_ENV))
+                    .is_some_and(|value| value == id)
+            })
+        })
+        .map(|process| Pid::from_raw(process.pid))
+        .collect()
+}
+
+/// Signals the live processes with `id` as their [EXECUTABLE_ID_ENV].
+fn signal_survivors(id: &str, signal: Signal) -> io::Result<()> {
+    for pid in survivors(id) {
+        match nix::sys::signal::kill(pid, signal) {
+            Ok(()) | Err(Errno::ESRCH) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Kills the processes in the leaf cgroup of an executable, and waits for
+/// them to exit, handing the cgroup back to be removed.
+struct KillCgroup(ExecutableCgroup);
+
+impl BlockingJob for KillCgroup {
+    type Output = (ExecutableCgroup, io::Result<()>);
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        let killed = self.0.kill();
+        (self.0, killed)
+    }
+}
+
+/// Kills the process group `pgid`, if any of its processes are left.
+fn kill_group(pgid: Pid) -> io::Result<()> {
+    match killpg(pgid, Signal::SIGKILL) {
+        Ok(()) | Err(Errno::ESRCH) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Kills the process groups `pgids`, and waits up to [KILLED_EXIT_TIMEOUT]
+/// for their processes to be gone.
+async fn kill_groups(pgids: &[Pid]) -> io::Result<()> {
+    for pgid in pgids {
+        kill_group(*pgid)?;
+    }
+
+    let deadline = Instant::now() + KILLED_EXIT_TIMEOUT;
+    while pgids.iter().any(|pgid| killpg(*pgid, None).is_ok()) {
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("processes are left in the groups {pgids:?}"),
+            ));
+        }
+        tokio::time::sleep(KILL_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Returns true if the process referred to by `pidfd` has exited.
+fn has_exited(pidfd: &OwnedFd) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd: pidfd.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: pollfd is valid for the duration of the call.
+    unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+}
+
+/// Returns true if `pid` is stopped by a signal.
+fn is_stopped(pid: Pid) -> bool {
+    procfs::process::Process::new(pid.as_raw())
+        .and_then(|process| process.stat())
+        .is_ok_and(|stat| stat.state == 'T')
+}
+
+/// Returns when `pid` started, in clock ticks since boot.
+fn start_time_of(pid: Pid) -> Option<u64> {
+    procfs::process::Process::new(pid.as_raw())
+        .and_then(|process| process.stat())
+        .map(|stat| stat.starttime)
+        .ok()
+}
+
+/// Opens a pidfd referring to `pid` if it is the process that started at
+/// `start_time`, rather than a later one reusing its pid.
+fn open_started_at(pid: Pid, start_time: u64) -> Option<OwnedFd> {
+    // Opened first, so the process checked is the one it refers to
+    let pidfd = pidfd_open(pid).ok()?;
+    (start_time_of(pid) == Some(start_time) && !has_exited(&pidfd))
+        .then_some(pidfd)
+}
+
+/// Sends `signal` to the process referred to by `pidfd`.
+fn pidfd_send_signal(pidfd: &OwnedFd, signal: Signal) -> io::Result<()> {
+    // SAFETY: a null info is the same as the one kill sends.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            signal as libc::c_int,
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    match res {
+        -1 => match Errno::last() {
+            Errno::ESRCH => Ok(()),
+            e => Err(e.into()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Waits for the process referred to by `pidfd` to exit, which makes the
+/// pidfd readable.
+async fn exited(pidfd: &OwnedFd) -> io::Result<()> {
+    let pidfd = AsyncFd::with_interest(pidfd.as_raw_fd(), Interest::READABLE)?;
+    let _ = pidfd.readable().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+// The tests don't reap orphans, so they may spawn children of their own
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+
+    fn sh(command: &str) -> Vec<OsString> {
+        vec!["sh".into(), "-c".into(), command.into()]
+    }
+
+    /// Spawns `sleep 10`, and waits for the child to have exec'd it.
+    async fn spawn_sleep() -> (tokio::process::Child, Pid) {
+        let child =
+            tokio::process::Command::new("sleep").arg("10").spawn().unwrap();
+        let pid = Pid::from_raw(child.id().unwrap() as i32);
+
+        let process = procfs::process::Process::new(pid.as_raw()).unwrap();
+        while process.cmdline().unwrap() != ["sleep", "10"] {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        (child, pid)
+    }
+
+    #[tokio::test]
+    async fn test_adopt_running_process() {
+        let (mut child, pid) = spawn_sleep().await;
+
+        let mut executable = Executable::adopt(
+            ExecutableName::new("sleeper".into()),
+            String::new(),
+            sh("sleep 10"),
+            Some(pid),
+            start_time_of(pid),
+            None,
+        );
+        assert!(executable.is_running().unwrap());
+        assert_eq!(executable.pid().unwrap(), Some(pid));
+
+        assert_eq!(executable.kill().await.unwrap(), None);
+        assert!(!executable.is_running().unwrap());
+        assert!(!child.wait().await.unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn test_adopt_reused_pid_is_stopped() {
+        let (mut child, pid) = spawn_sleep().await;
+
+        // As if the adopted process exited, and its pid was reused
+        let start_time = start_time_of(pid).unwrap() - 1;
+        let mut executable = Executable::adopt(
+            ExecutableName::new("sleeper".into()),
+            String::new(),
+            sh("sleep 10"),
+            Some(pid),
+            Some(start_time),
+            None,
+        );
+        assert!(!executable.is_running().unwrap());
+        assert_eq!(executable.pid().unwrap(), None);
+        assert_eq!(executable.kill().await.unwrap(), None);
+
+        child.kill().await.unwrap();
     }
 }
@@ -14,20 +14,48 @@
 \* -------------------------------------------------------------------------- */
 
 use super::{
-    Executable, ExecutableName, ExecutableSpec, ExecutablesError, Result,
+    Executable, ExecutableName, ExecutableSpec, ExecutablesError, Mounts,
+    Result, Rootfs, SeccompProfile,
 };
-use std::{collections::HashMap, process::ExitStatus};
+use crate::cells::cell_service::cells::cgroups::ExecutableCgroup;
+use crate::AURAED_RUNTIME;
+use nix::unistd::Pid;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    io,
+    process::ExitStatus,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::Mutex;
 
 type Cache = HashMap<ExecutableName, Executable>;
 
+/// How long an executable which forbids daemonizing is watched for doing so
+/// before it is considered started.
+const DAEMONIZE_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// How often terminated executables are checked for having exited.
+const TERMINATE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// An in-memory store for the list of executables created with Aurae.
 #[derive(Debug, Default)]
 pub struct Executables {
     cache: Cache,
+    /// The names of the executables being started by [Executables::spawn].
+    reserved: HashSet<ExecutableName>,
 }
 
 impl Executables {
-    pub fn start<T: Into<ExecutableSpec>>(
+    /// Starts an executable and adds it to the cache, holding the cache
+    /// meanwhile. The cell service starts executables with
+    /// [Executables::reserve], [Executables::spawn] and
+    /// [Executables::insert] instead, so as not to hold it.
+    /// An executable which forbids daemonizing fails to start if it does
+    /// so within [DAEMONIZE_GRACE_PERIOD].
+    #[cfg(test)]
+    pub async fn start<T: Into<ExecutableSpec>>(
         &mut self,
         executable_spec: T,
         uid: Option<u32>,
@@ -37,30 +65,183 @@ impl Executables {
 
         // TODO: replace with try_insert when it becomes stable
         // Check if there was already an executable with the same name.
-        if self.cache.contains_key(&executable_spec.name) {
+        if self.is_taken(&executable_spec.name) {
             return Err(ExecutablesError::ExecutableExists {
                 executable_name: executable_spec.name,
             });
         }
 
+        let executable = Self::spawn(executable_spec, uid, gid).await?;
+        Ok(self.insert(executable))
+    }
+
+    /// Reserves the names of executables to start with [Executables::spawn],
+    /// so that no other executable takes them until they are inserted or
+    /// released. Reserves none if any of them is taken.
+    pub fn reserve(
+        &mut self,
+        executable_names: &[ExecutableName],
+    ) -> Result<()> {
+        if let Some(executable_name) =
+            executable_names.iter().find(|name| self.is_taken(name))
+        {
+            return Err(ExecutablesError::ExecutableExists {
+                executable_name: executable_name.clone(),
+            });
+        }
+
+        self.reserved.extend(executable_names.iter().cloned());
+        Ok(())
+    }
+
+    /// Releases the names reserved for executables that did not start.
+    pub fn release(&mut self, executable_names: &[ExecutableName]) {
+        for executable_name in executable_names {
+            let _ = self.reserved.remove(executable_name);
+        }
+    }
+
+    /// Adds an executable started with [Executables::spawn] to the cache,
+    /// releasing its name.
+    pub fn insert(&mut self, executable: Executable) -> &Executable {
+        let _ = self.reserved.remove(&executable.name);
+
+        // `or_insert` will always insert, as the name was reserved or
+        // checked before the executable was spawned.
+        self.cache.entry(executable.name.clone()).or_insert(executable)
+    }
+
+    fn is_taken(&self, executable_name: &ExecutableName) -> bool {
+        self.cache.contains_key(executable_name)
+            || self.reserved.contains(executable_name)
+    }
+
+    /// Starts an executable without adding it to the cache, so that the
+    /// cache need not be locked while it starts.
+    /// An executable which forbids daemonizing fails to start if it does
+    /// so within [DAEMONIZE_GRACE_PERIOD].
+    pub async fn spawn<T: Into<ExecutableSpec>>(
+        executable_spec: T,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> Result<Executable> {
+        let executable_spec = executable_spec.into();
         let executable_name = executable_spec.name.clone();
+        let forbid_daemonize = executable_spec.forbid_daemonize;
+
+        // Compile the profile here, so an invalid one fails the request
+        // rather than the spawned process.
+        let seccomp_profile = executable_spec
+            .seccomp_profile
+            .as_deref()
+            .map(SeccompProfile::load)
+            .transpose()
+            .map_err(|source| ExecutablesError::InvalidSeccompProfile {
+                executable_name: executable_name.clone(),
+                source,
+            })?;
+        let rootfs = executable_spec
+            .rootfs
+            .as_deref()
+            .map(|rootfs| Rootfs::new(rootfs, uid))
+            .transpose()
+            .map_err(|source| ExecutablesError::InvalidRootfs {
+                executable_name: executable_name.clone(),
+                source,
+            })?;
+        let mounts = Some(&executable_spec.mounts)
+            .filter(|mounts| !mounts.is_empty())
+            .map(|mounts| Mounts::new(mounts))
+            .transpose()
+            .map_err(|source| ExecutablesError::InvalidMounts {
+                executable_name: executable_name.clone(),
+                source,
+            })?;
+        // The nested auraed of a cell with a user namespace is started with
+        // its maps
+        let user_namespace =
+            AURAED_RUNTIME.get().and_then(|runtime| runtime.user_namespace());
+        // and that of a cell with per executable accounting with its cgroup
+        let cgroup = AURAED_RUNTIME
+            .get()
+            .and_then(|runtime| runtime.executable_cgroups.as_deref())
+            .map(|cell_cgroup| {
+                ExecutableCgroup::create(
+                    cell_cgroup,
+                    &executable_name.to_string(),
+                )
+            })
+            .transpose()
+            .map_err(|e| ExecutablesError::FailedToStartExecutable {
+                executable_name: executable_name.clone(),
+                source: e,
+            })?;
         let mut executable = Executable::new(executable_spec);
 
         // start the exe before we add it to the cache, as otherwise a failure leads to the
         // executable remaining in the cache and start cannot be called again.
-        executable.start(uid, gid).map_err(|e| {
-            ExecutablesError::FailedToStartExecutable {
+        executable
+            .start(
+                uid,
+                gid,
+                seccomp_profile,
+                rootfs,
+                mounts,
+                user_namespace.as_ref(),
+                cgroup,
+            )
+            .map_err(|e| ExecutablesError::FailedToStartExecutable {
                 executable_name: executable_name.clone(),
                 source: e,
-            }
-        })?;
+            })?;
+
+        if forbid_daemonize
+            && executable
+                .wait_daemonized(DAEMONIZE_GRACE_PERIOD)
+                .await
+                .unwrap_or(false)
+        {
+            return Err(ExecutablesError::ExecutableDaemonized {
+                executable_name,
+            });
+        }
+
+        Ok(executable)
+    }
+
+    /// Adds an executable started by a previous auraed to the cache.
+    /// See [Executable::adopt].
+    pub fn adopt(
+        &mut self,
+        executable_name: ExecutableName,
+        description: String,
+        command: Vec<OsString>,
+        pid: Option<Pid>,
+        start_time: Option<u64>,
+    ) -> Result<&Executable> {
+        if self.is_taken(&executable_name) {
+            return Err(ExecutablesError::ExecutableExists { executable_name });
+        }
 
-        // `or_insert` will always insert as we've already assured ourselves that the key does not
-        // exist.
-        let inserted_executable =
-            self.cache.entry(executable_name).or_insert_with(|| executable);
+        let cgroup = AURAED_RUNTIME
+            .get()
+            .and_then(|runtime| runtime.executable_cgroups.as_deref())
+            .and_then(|cell_cgroup| {
+                ExecutableCgroup::adopt(
+                    cell_cgroup,
+                    &executable_name.to_string(),
+                )
+            });
+        let executable = Executable::adopt(
+            executable_name.clone(),
+            description,
+            command,
+            pid,
+            start_time,
+            cgroup,
+        );
 
-        Ok(inserted_executable)
+        Ok(self.cache.entry(executable_name).or_insert(executable))
     }
 
     pub fn get(&self, executable_name: &ExecutableName) -> Result<&Executable> {
@@ -72,40 +253,143 @@ impl Executables {
         Ok(executable)
     }
 
-    pub async fn stop(
+    /// Returns the names of the executables whose process has not exited, sorted.
+    pub fn running(&mut self) -> Vec<ExecutableName> {
+        let mut names: Vec<_> = self
+            .cache
+            .values_mut()
+            .filter_map(|exe| {
+                exe.is_running().unwrap_or(false).then(|| exe.name.clone())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the names of the quarantined executables, sorted.
+    pub fn quarantined(&self) -> Vec<ExecutableName> {
+        let mut names: Vec<_> = self
+            .cache
+            .values()
+            .filter(|exe| exe.is_quarantined())
+            .map(|exe| exe.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Freezes the processes of a running executable.
+    /// See [Executable::quarantine].
+    pub fn quarantine(
         &mut self,
         executable_name: &ExecutableName,
-    ) -> Result<ExitStatus> {
-        let Some(executable) = self.cache.get_mut(executable_name) else {
-            return Err(ExecutablesError::ExecutableNotFound {
+    ) -> Result<&Executable> {
+        let executable = self.get_mut(executable_name)?;
+
+        if !executable.is_running().unwrap_or(false) {
+            return Err(ExecutablesError::ExecutableNotRunning {
                 executable_name: executable_name.clone(),
             });
-        };
+        }
 
-        let exit_status = executable.kill().await.map_err(|e| {
-            ExecutablesError::FailedToStopExecutable {
+        executable.quarantine().map_err(|e| {
+            ExecutablesError::FailedToQuarantineExecutable {
                 executable_name: executable_name.clone(),
                 source: e,
             }
         })?;
 
-        let Some(exit_status) = exit_status else {
-            // Exes that never started return None
-            let executable =
-                self.cache.remove(executable_name).expect("exe in cache");
-            return Err(ExecutablesError::ExecutableNotFound {
-                executable_name: executable.name,
-            });
-        };
+        Ok(executable)
+    }
 
-        let _ = self.cache.remove(executable_name).ok_or_else(|| {
-            // get_mut would have already thrown this error, so we should never reach here
-            ExecutablesError::ExecutableNotFound {
+    /// Resumes the processes of a quarantined executable.
+    /// See [Executable::unquarantine].
+    pub fn unquarantine(
+        &mut self,
+        executable_name: &ExecutableName,
+    ) -> Result<&Executable> {
+        let executable = self.get_mut(executable_name)?;
+
+        executable.unquarantine().map_err(|e| {
+            ExecutablesError::FailedToUnquarantineExecutable {
                 executable_name: executable_name.clone(),
+                source: e,
             }
         })?;
 
-        Ok(exit_status)
+        Ok(executable)
+    }
+
+    fn get_mut(
+        &mut self,
+        executable_name: &ExecutableName,
+    ) -> Result<&mut Executable> {
+        self.cache.get_mut(executable_name).ok_or_else(|| {
+            ExecutablesError::ExecutableNotFound {
+                executable_name: executable_name.clone(),
+            }
+        })
+    }
+
+    /// Returns all cached executables, including stopped ones.
+    pub fn iter(&self) -> impl Iterator<Item = &Executable> {
+        self.cache.values()
+    }
+
+    /// Stops the executable and removes it from the cache, holding the
+    /// cache meanwhile. The cell service stops executables with
+    /// [Executables::take] and [Executables::finish_stop] instead, so as
+    /// not to hold it.
+    /// Returns [None] if the exit status is unknown, as for adopted executables.
+    #[cfg(test)]
+    pub async fn stop(
+        &mut self,
+        executable_name: &ExecutableName,
+    ) -> Result<Option<ExitStatus>> {
+        let mut executable = self.take(executable_name)?;
+        let stopped = executable.kill().await;
+        self.finish_stop(executable, stopped)
+    }
+
+    /// Removes an executable from the cache, so that it can be stopped
+    /// without holding the cache. Its name stays reserved until
+    /// [Executables::finish_stop].
+    pub fn take(
+        &mut self,
+        executable_name: &ExecutableName,
+    ) -> Result<Executable> {
+        let executable =
+            self.cache.remove(executable_name).ok_or_else(|| {
+                ExecutablesError::ExecutableNotFound {
+                    executable_name: executable_name.clone(),
+                }
+            })?;
+
+        let _ = self.reserved.insert(executable_name.clone());
+        Ok(executable)
+    }
+
+    /// Releases the name of an executable taken with [Executables::take]
+    /// once `stopped`, or puts it back in the cache if it failed to stop.
+    pub fn finish_stop(
+        &mut self,
+        executable: Executable,
+        stopped: io::Result<Option<ExitStatus>>,
+    ) -> Result<Option<ExitStatus>> {
+        let executable_name = executable.name.clone();
+        match stopped {
+            Ok(exit_status) => {
+                let _ = self.reserved.remove(&executable_name);
+                Ok(exit_status)
+            }
+            Err(e) => {
+                let _ = self.insert(executable);
+                Err(ExecutablesError::FailedToStopExecutable {
+                    executable_name,
+                    source: e,
+                })
+            }
+        }
     }
 
     /// Stops all executables concurrently
@@ -120,4 +404,1054 @@ impl Executables {
             let _ = self.cache.remove(&name);
         }
     }
+
+    /// Sends SIGTERM to all executables, waits up to `grace` for them to
+    /// exit, then stops them all, killing the ones still running.
+    pub async fn broadcast_terminate(&mut self, grace: Duration) {
+        for exe in self.cache.values_mut() {
+            let _ = exe.terminate();
+        }
+
+        let deadline = tokio::time::Instant::now() + grace;
+        while tokio::time::Instant::now() < deadline
+            && self
+                .cache
+                .values_mut()
+                .any(|exe| exe.is_running().unwrap_or(false))
+        {
+            tokio::time::sleep(TERMINATE_POLL_INTERVAL).await;
+        }
+
+        self.broadcast_stop().await;
+    }
+}
+
+/// Names reserved with [Executables::reserve] for executables being
+/// started without holding the cache. They are released when dropped, so
+/// that a start cancelled by its client does not keep them taken.
+#[derive(Debug)]
+pub struct Reservation {
+    executables: Arc<Mutex<Executables>>,
+    names: Vec<ExecutableName>,
+}
+
+impl Reservation {
+    pub async fn new(
+        executables: &Arc<Mutex<Executables>>,
+        names: Vec<ExecutableName>,
+    ) -> Result<Self> {
+        executables.lock().await.reserve(&names)?;
+        Ok(Self { executables: executables.clone(), names })
+    }
+
+    /// Releases the names, once the executables that started are inserted
+    /// into `executables`, the cache the names were reserved in.
+    pub fn release(mut self, executables: &mut Executables) {
+        executables.release(&std::mem::take(&mut self.names));
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let names = std::mem::take(&mut self.names);
+        if names.is_empty() {
+            return;
+        }
+
+        match self.executables.try_lock() {
+            Ok(mut executables) => executables.release(&names),
+            Err(_) => {
+                let executables = self.executables.clone();
+                let _ignored = tokio::spawn(async move {
+                    executables.lock().await.release(&names);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+// The tests don't reap orphans, so they may spawn children of their own
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::super::{CapabilitiesSpec, CapabilitySet, KillMode, MountSpec};
+    use super::*;
+    use crate::logging::log_registry::{LogKey, LogRegistry};
+    use crate::logging::output_drain;
+    use crate::logging::output_limit::OutputLimit;
+    use proto::observe::LogChannelType;
+    use std::time::Duration;
+    use test_helpers::*;
+    use tokio::process::Command;
+    use tokio::sync::broadcast::error::RecvError;
+
+    fn spec(name: &str, program: &str, args: &[&str]) -> ExecutableSpec {
+        let mut command = Command::new(program);
+        let _ = command.args(args);
+
+        ExecutableSpec {
+            name: ExecutableName::new(name.to_string()),
+            description: String::new(),
+            command,
+            forbid_daemonize: false,
+            disable_log_redaction: false,
+            seccomp_profile: None,
+            no_new_privs: false,
+            capabilities: None,
+            rootfs: None,
+            output_limit: OutputLimit::UNSET,
+            mounts: vec![],
+        }
+    }
+
+    /// Forks a process which forks `sleep` into a new session, and exits,
+    /// leaving it running as a daemon.
+    fn daemon_spec(name: &str) -> ExecutableSpec {
+        spec(name, "sh", &["-c", "(setsid sleep 10 &); exit 0"])
+    }
+
+    #[tokio::test]
+    async fn test_reserved_names_are_taken() {
+        let mut executables = Executables::default();
+        let _ = executables
+            .start(spec("running", "sleep", &["10"]), None, None)
+            .await
+            .expect("failed to start");
+        let reserved = ExecutableName::new("reserved".into());
+        let running = ExecutableName::new("running".into());
+
+        // Reserves none if any is taken
+        let err = executables
+            .reserve(&[reserved.clone(), running])
+            .expect_err("taken name");
+        assert!(matches!(err, ExecutablesError::ExecutableExists { .. }));
+        executables.reserve(&[reserved.clone()]).expect("free name");
+
+        let err = executables
+            .start(spec("reserved", "true", &[]), None, None)
+            .await
+            .expect_err("reserved name");
+        assert!(matches!(err, ExecutablesError::ExecutableExists { .. }));
+
+        let executable =
+            Executables::spawn(spec("reserved", "sleep", &["10"]), None, None)
+                .await
+                .expect("failed to spawn");
+        let _ = executables.insert(executable);
+        assert!(executables.reserved.is_empty());
+        assert_eq!(executables.running().len(), 2);
+
+        executables.broadcast_stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_reservation_is_released_once_dropped() {
+        let executables = Arc::new(Mutex::new(Executables::default()));
+        let names = vec![ExecutableName::new("reserved".into())];
+
+        let reservation = Reservation::new(&executables, names.clone())
+            .await
+            .expect("free name");
+        let err = Reservation::new(&executables, names.clone())
+            .await
+            .expect_err("reserved name");
+        assert!(matches!(err, ExecutablesError::ExecutableExists { .. }));
+
+        // As when the start holding it is cancelled
+        drop(reservation);
+        assert!(executables.lock().await.reserved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_taken_names_stay_taken_until_stopped() {
+        let mut executables = Executables::default();
+        let _ = executables
+            .start(spec("sleeper", "sleep", &["10"]), None, None)
+            .await
+            .expect("failed to start");
+        let name = ExecutableName::new("sleeper".into());
+
+        let mut executable = executables.take(&name).expect("failed to take");
+        assert!(executables.running().is_empty());
+        assert!(matches!(
+            executables.take(&name),
+            Err(ExecutablesError::ExecutableNotFound { .. })
+        ));
+        let err = executables
+            .start(spec("sleeper", "true", &[]), None, None)
+            .await
+            .expect_err("name taken while stopping");
+        assert!(matches!(err, ExecutablesError::ExecutableExists { .. }));
+
+        // Put back if it fails to stop
+        let failed = Err(io::Error::other("failed"));
+        let err = executables
+            .finish_stop(executable, failed)
+            .expect_err("failed to stop");
+        assert!(matches!(err, ExecutablesError::FailedToStopExecutable { .. }));
+        assert_eq!(executables.running(), vec![name.clone()]);
+
+        executable = executables.take(&name).expect("failed to take");
+        let stopped = executable.kill().await;
+        let _ = executables
+            .finish_stop(executable, stopped)
+            .expect("failed to stop");
+        assert!(executables.reserved.is_empty());
+        assert!(executables.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_running_excludes_exited_executables() {
+        let mut executables = Executables::default();
+        let _ = executables
+            .start(spec("sleeper", "sleep", &["10"]), None, None)
+            .await;
+        let _ =
+            executables.start(spec("quitter", "true", &[]), None, None).await;
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(
+            executables.running(),
+            vec![ExecutableName::new("sleeper".into())]
+        );
+
+        executables.broadcast_stop().await;
+        assert!(executables.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_terminate_waits_for_executables_to_exit() {
+        let mut executables = Executables::default();
+        let _ = executables
+            .start(spec("sleeper", "sleep", &["10"]), None, None)
+            .await
+            .unwrap();
+
+        let started = tokio::time::Instant::now();
+        executables.broadcast_terminate(Duration::from_secs(5)).await;
+
+        // sleep exits on SIGTERM, well before the grace period
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(executables.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_terminate_kills_after_the_grace_period() {
+        let mut executables = Executables::default();
+        let _ = executables
+            .start(
+                spec("stubborn", "sh", &["-c", "trap '' TERM; sleep 10"]),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let started = tokio::time::Instant::now();
+        executables.broadcast_terminate(Duration::from_millis(300)).await;
+
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_secs(5));
+        assert!(executables.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_freezes_the_process_group() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("sleepers".into());
+        let pid = executables
+            .start(
+                spec("sleepers", "sh", &["-c", "sleep 10 & wait"]),
+                None,
+                None,
+            )
+            .await
+            .expect("failed to start")
+            .pid()
+            .unwrap()
+            .expect("pid");
+
+        // Wait for the shell to start its child
+        let children = || {
+            procfs::process::all_processes()
+                .unwrap()
+                .filter_map(|process| process.ok()?.stat().ok())
+                .filter(|stat| stat.pgrp == pid.as_raw())
+                .collect::<Vec<_>>()
+        };
+        let mut attempts = 0;
+        while children().len() < 2 {
+            attempts += 1;
+            assert!(attempts < 50, "child did not start");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let _ = executables.quarantine(&name).expect("failed to quarantine");
+        assert_eq!(executables.quarantined(), vec![name.clone()]);
+        assert_eq!(executables.running(), vec![name.clone()]);
+        assert!(children().iter().all(|stat| stat.state == 'T'));
+
+        let _ =
+            executables.unquarantine(&name).expect("failed to unquarantine");
+        assert!(executables.quarantined().is_empty());
+        assert!(children().iter().all(|stat| stat.state != 'T'));
+
+        // Stopping a quarantined executable kills its whole group
+        let _ = executables.quarantine(&name).expect("failed to quarantine");
+        let _ = executables.stop(&name).await.expect("failed to stop");
+        let mut attempts = 0;
+        while !children().iter().all(|stat| stat.state == 'Z') {
+            attempts += 1;
+            assert!(attempts < 50, "child was not killed");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_requires_a_running_executable() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("quitter".into());
+        let _ = executables
+            .start(spec("quitter", "true", &[]), None, None)
+            .await
+            .expect("failed to start");
+
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not exit");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert!(matches!(
+            executables.quarantine(&name),
+            Err(ExecutablesError::ExecutableNotRunning { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stop_after_executable_exited() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("quitter".into());
+        let _ = executables
+            .start(spec("quitter", "true", &[]), None, None)
+            .await
+            .expect("failed to start");
+
+        // Wait for the exit to be observed (and the process reaped),
+        // as happens when listing the executables before a force free.
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not exit");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let exit_status =
+            executables.stop(&name).await.expect("failed to stop");
+        assert!(exit_status.expect("exit status").success());
+        assert!(matches!(
+            executables.get(&name),
+            Err(ExecutablesError::ExecutableNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_daemonized_executable_keeps_running() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("daemon".into());
+        let shell = executables
+            .start(daemon_spec("daemon"), None, None)
+            .await
+            .expect("failed to start")
+            .pid()
+            .unwrap()
+            .expect("pid");
+
+        // The tracked pid moves from the shell to the daemon
+        let mut attempts = 0;
+        let daemon = loop {
+            assert_eq!(executables.running(), vec![name.clone()]);
+            let executable = executables.get(&name).unwrap();
+            if executable.is_daemonized() {
+                break executable.pid().unwrap().expect("pid");
+            }
+            attempts += 1;
+            assert!(attempts < 50, "executable did not daemonize");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        };
+        assert_ne!(daemon, shell);
+        assert_eq!(
+            procfs::process::Process::new(daemon.as_raw())
+                .unwrap()
+                .cmdline()
+                .unwrap(),
+            ["sleep", "10"]
+        );
+
+        // Stopping it kills the daemon
+        assert_eq!(
+            executables.stop(&name).await.expect("failed to stop"),
+            None
+        );
+        assert!(!procfs::process::Process::new(daemon.as_raw())
+            .and_then(|process| process.stat())
+            .is_ok_and(|stat| stat.state != 'Z'));
+    }
+
+    #[tokio::test]
+    async fn test_daemonized_executable_stops_with_its_daemon() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("daemon".into());
+        let _ = executables
+            .start(daemon_spec("daemon"), None, None)
+            .await
+            .expect("failed to start");
+
+        let mut attempts = 0;
+        while !executables.get(&name).unwrap().is_daemonized() {
+            let _ = executables.running();
+            attempts += 1;
+            assert!(attempts < 50, "executable did not daemonize");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let daemon = executables.get(&name).unwrap().pid().unwrap().unwrap();
+        nix::sys::signal::kill(daemon, nix::sys::signal::Signal::SIGKILL)
+            .unwrap();
+
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not stop");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forbid_daemonize_fails_the_start() {
+        let mut executables = Executables::default();
+        let name = ExecutableName::new("daemon".into());
+        // The daemon is found by the uncommon duration it sleeps for
+        let mut daemon_spec =
+            spec("daemon", "sh", &["-c", "(setsid sleep 1234 &); exit 0"]);
+        daemon_spec.forbid_daemonize = true;
+        let daemons = || {
+            procfs::process::all_processes()
+                .unwrap()
+                .filter_map(|process| process.ok())
+                .filter(|process| {
+                    process.stat().is_ok_and(|stat| stat.state != 'Z')
+                        && process
+                            .cmdline()
+                            .is_ok_and(|cmdline| cmdline == ["sleep", "1234"])
+                })
+                .count()
+        };
+
+        assert!(matches!(
+            executables.start(daemon_spec, None, None).await,
+            Err(ExecutablesError::ExecutableDaemonized { .. })
+        ));
+        assert!(matches!(
+            executables.get(&name),
+            Err(ExecutablesError::ExecutableNotFound { .. })
+        ));
+
+        let mut attempts = 0;
+        while daemons() > 0 {
+            attempts += 1;
+            assert!(attempts < 50, "daemon was not killed");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forbid_daemonize_allows_long_running_executables() {
+        let mut executables = Executables::default();
+        let mut sleeper = spec("sleeper", "sleep", &["10"]);
+        sleeper.forbid_daemonize = true;
+
+        let _ = executables
+            .start(sleeper, None, None)
+            .await
+            .expect("failed to start");
+        assert_eq!(
+            executables.running(),
+            vec![ExecutableName::new("sleeper".into())]
+        );
+
+        executables.broadcast_stop().await;
+    }
+
+    /// The first line the executable writes to stdout.
+    async fn first_line(executable: &Executable) -> String {
+        let (history, mut output) =
+            executable.stdout.subscribe_since("test", 0);
+        match history.into_iter().next() {
+            Some(item) => item.line,
+            None => {
+                tokio::time::timeout(Duration::from_secs(5), output.recv())
+                    .await
+                    .expect("no output")
+                    .expect("output")
+                    .line
+            }
+        }
+    }
+
+    /// Writes an OCI seccomp profile denying `names` to a temporary file.
+    fn deny_profile(names: &[&str]) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("seccomp-{}.json", uuid::Uuid::new_v4()));
+        let profile = serde_json::json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "syscalls": [
+                { "names": names, "action": "SCMP_ACT_ERRNO", "errnoRet": 1 }
+            ]
+        });
+        std::fs::write(&path, profile.to_string()).expect("write profile");
+        path
+    }
+
+    #[tokio::test]
+    async fn test_output_remains_registered_after_stop() {
+        let mut executables = Executables::default();
+        let echo = spec("registered-echo", "echo", &["bye"]);
+        let name = echo.name.clone();
+        let executable =
+            executables.start(echo, None, None).await.expect("failed to start");
+        assert_eq!(first_line(executable).await, "bye");
+
+        let key = LogKey::executable(name.clone(), LogChannelType::Stdout);
+        let channel = LogRegistry::global().lookup(&key).expect("registered");
+        assert_eq!(channel.name, "registered-echo::stdout");
+
+        let _ = executables.stop(&name).await.expect("failed to stop");
+        let channel = LogRegistry::global().lookup(&key).expect("grace period");
+        let (history, _) = channel.subscribe_since("test", 0);
+        assert_eq!(history.last().expect("last line").line, "bye");
+    }
+
+    #[tokio::test]
+    async fn test_output_over_the_limit_is_suppressed() {
+        let mut executables = Executables::default();
+        let mut flood =
+            spec("flood", "sh", &["-c", "seq 1 1000; exec sleep 10"]);
+        flood.output_limit =
+            OutputLimit { lines_per_second: 100, ..OutputLimit::UNSET };
+        let name = flood.name.clone();
+
+        let executable = executables
+            .start(flood, None, None)
+            .await
+            .expect("failed to start");
+
+        // The suppressed lines are reported once the interval ends, while
+        // the executable still runs
+        let (history, mut output) =
+            executable.stdout.subscribe_since("test", 0);
+        let reported =
+            history.iter().any(|item| item.line.contains("suppressed"));
+        if !reported {
+            tokio::time::timeout(Duration::from_secs(3), async {
+                loop {
+                    match output.recv().await {
+                        Ok(item) if item.line.contains("suppressed") => break,
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => panic!("output closed"),
+                    }
+                }
+            })
+            .await
+            .expect("suppressed lines were not reported");
+        }
+
+        let executable = executables.cache.get_mut(&name).expect("flood");
+        let _ = executable.kill().await.expect("failed to kill");
+        let (history, _) = executable.stdout.subscribe_since("test", 0);
+        let mut sent = 0;
+        let mut reported = 0;
+        for item in history {
+            match item.line.strip_prefix("[aurae: ") {
+                Some(report) => {
+                    reported += report
+                        .split(' ')
+                        .next()
+                        .and_then(|count| count.parse::<u64>().ok())
+                        .expect("suppressed count")
+                }
+                None => sent += 1,
+            }
+        }
+
+        let (suppressed, _) = executable.suppressed_lines();
+        assert!(suppressed > 0);
+        assert_eq!(reported, suppressed);
+        assert_eq!(sent + suppressed, 1000);
+        assert_eq!(executable.suppressed_lines().1, 0);
+    }
+
+    #[tokio::test]
+    async fn test_start_applies_the_seccomp_profile() {
+        let names: &[&str] = if cfg!(target_arch = "x86_64") {
+            &["mkdir", "mkdirat"]
+        } else {
+            &["mkdirat"]
+        };
+        let profile = deny_profile(names);
+        let dir = std::env::temp_dir()
+            .join(format!("seccomp-{}", uuid::Uuid::new_v4()));
+
+        let mut executables = Executables::default();
+        let mut mkdir = spec(
+            "mkdir",
+            "sh",
+            &["-c", &format!("mkdir {} 2>&1", dir.display())],
+        );
+        mkdir.seccomp_profile =
+            Some(profile.to_str().expect("utf-8").to_string());
+
+        let executable = executables
+            .start(mkdir, None, None)
+            .await
+            .expect("failed to start");
+        let line = first_line(executable).await;
+        let _ = std::fs::remove_file(&profile);
+
+        assert!(line.contains("Operation not permitted"), "{line}");
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_start_fails_on_an_invalid_seccomp_profile() {
+        let profile = deny_profile(&["not_a_syscall"]);
+
+        let mut executables = Executables::default();
+        let mut sleeper = spec("sleeper", "sleep", &["10"]);
+        sleeper.seccomp_profile =
+            Some(profile.to_str().expect("utf-8").to_string());
+
+        let result = executables.start(sleeper, None, None).await;
+        let _ = std::fs::remove_file(&profile);
+
+        let Err(error) = result else {
+            panic!("started under an invalid profile");
+        };
+        assert!(matches!(
+            error,
+            ExecutablesError::InvalidSeccompProfile { .. }
+        ));
+        assert!(error.to_string().contains("syscalls[0]"), "{error}");
+        assert!(executables.running().is_empty());
+    }
+
+    /// Binds port 80 with perl, as `uid`, printing "bound" or the error.
+    async fn bind_port_80(
+        uid: Option<u32>,
+        capabilities: Option<CapabilitiesSpec>,
+    ) -> String {
+        let mut bind = spec(
+            "bind",
+            "perl",
+            &[
+                "-MIO::Socket::INET",
+                "-e",
+                r#"my $s = IO::Socket::INET->new(LocalAddr => "127.0.0.1:80", Listen => 1, ReuseAddr => 1); print(($s ? "bound" : $!) . "\n");"#,
+            ],
+        );
+        bind.capabilities = capabilities;
+
+        let mut executables = Executables::default();
+        let executable =
+            executables.start(bind, uid, uid).await.expect("failed to start");
+        first_line(executable).await
+    }
+
+    fn privileged_ports_restricted() -> bool {
+        std::fs::read_to_string("/proc/sys/net/ipv4/ip_unprivileged_port_start")
+            .ok()
+            .and_then(|start| start.trim().parse::<u16>().ok())
+            .is_some_and(|start| start > 80)
+    }
+
+    #[tokio::test]
+    async fn test_kept_capabilities_survive_the_uid_drop() {
+        skip_if_not_root!("test_kept_capabilities_survive_the_uid_drop");
+        if !privileged_ports_restricted() {
+            skip!("port 80 is unprivileged. Skipping test.");
+        }
+        let net_bind_service = CapabilitySet::parse("CAP_NET_BIND_SERVICE");
+        let nobody = Some(65534);
+
+        let kept = CapabilitiesSpec {
+            keep: net_bind_service.expect("capability"),
+            ..Default::default()
+        };
+        assert_eq!(bind_port_80(nobody, Some(kept)).await, "bound");
+
+        let only_kept = CapabilitiesSpec {
+            drop: CapabilitySet::ALL,
+            keep: net_bind_service.expect("capability"),
+        };
+        assert_eq!(bind_port_80(nobody, Some(only_kept)).await, "bound");
+
+        assert_eq!(bind_port_80(nobody, None).await, "Permission denied");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_capabilities_are_lost_by_root() {
+        skip_if_not_root!("test_dropped_capabilities_are_lost_by_root");
+        if !privileged_ports_restricted() {
+            skip!("port 80 is unprivileged. Skipping test.");
+        }
+
+        let dropped = CapabilitiesSpec {
+            drop: CapabilitySet::parse("NET_BIND_SERVICE").expect("capability"),
+            ..Default::default()
+        };
+        assert_eq!(
+            bind_port_80(None, Some(dropped)).await,
+            "Permission denied"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_new_privs() {
+        let mut executables = Executables::default();
+        let mut status =
+            spec("status", "grep", &["NoNewPrivs", "/proc/self/status"]);
+        status.no_new_privs = true;
+
+        let executable = executables
+            .start(status, None, None)
+            .await
+            .expect("failed to start");
+        assert_eq!(first_line(executable).await, "NoNewPrivs:\t1");
+    }
+
+    /// Prepares a rootfs holding busybox, or ls and the libraries it links,
+    /// at /bin/ls.
+    fn prepare_rootfs() -> std::path::PathBuf {
+        let rootfs = std::env::temp_dir()
+            .join(format!("rootfs-{}", uuid::Uuid::new_v4()));
+        let copy = |path: &std::path::Path, to: &std::path::Path| {
+            let to = rootfs.join(to.strip_prefix("/").expect("absolute"));
+            std::fs::create_dir_all(to.parent().expect("parent"))
+                .expect("create dir");
+            let _ = std::fs::copy(path, to).expect("copy");
+        };
+
+        let busybox = std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join("busybox"))
+                .find(|path| path.exists())
+        });
+        if let Some(busybox) = busybox {
+            copy(&busybox, std::path::Path::new("/bin/busybox"));
+            std::os::unix::fs::symlink("busybox", rootfs.join("bin/ls"))
+                .expect("link ls");
+        } else {
+            let ls = std::path::Path::new("/bin/ls");
+            copy(ls, ls);
+            let ldd = std::process::Command::new("ldd")
+                .arg(ls)
+                .output()
+                .expect("ldd");
+            for library in String::from_utf8_lossy(&ldd.stdout)
+                .split_whitespace()
+                .filter(|word| word.starts_with('/'))
+            {
+                let library = std::path::Path::new(library);
+                copy(library, library);
+            }
+        }
+
+        rootfs
+    }
+
+    #[tokio::test]
+    async fn test_rootfs_confines_the_executable() {
+        skip_if_not_root!("test_rootfs_confines_the_executable");
+        let rootfs = prepare_rootfs();
+
+        let mut executables = Executables::default();
+        let mut ls = spec("ls", "/bin/ls", &["-1", "/", "/proc/self/"]);
+        ls.rootfs = Some(rootfs.clone());
+        let executable =
+            executables.start(ls, None, None).await.expect("failed to start");
+        let stdout = executable.stdout.clone();
+
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not exit");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let (output, _) = stdout.subscribe_since("test", 0);
+        let output: Vec<_> = output.into_iter().map(|item| item.line).collect();
+
+        let mut prepared: Vec<_> = std::fs::read_dir(&rootfs)
+            .expect("read rootfs")
+            .map(|entry| entry.expect("entry").file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+        prepared.sort();
+        std::fs::remove_dir_all(&rootfs).expect("remove rootfs");
+
+        // ls lists the root, then the /proc mounted in it
+        let root_end = output.iter().position(|line| line.is_empty());
+        let root_end = root_end.expect("two listings");
+        assert_eq!(output[0], "/:");
+        assert_eq!(output[1..root_end], prepared);
+        assert!(output[root_end..].iter().any(|line| line == "status"));
+    }
+
+    #[tokio::test]
+    async fn test_mounts_are_seen_by_the_executable_alone() {
+        skip_if_not_root!("test_mounts_are_seen_by_the_executable_alone");
+        let dir = std::env::temp_dir()
+            .join(format!("mounts-{}", uuid::Uuid::new_v4()));
+        let source = dir.join("source");
+        std::fs::create_dir_all(&source).expect("create source");
+        std::fs::write(source.join("data"), "bound\n").expect("write data");
+        let (bind, scratch) = (dir.join("a/bind"), dir.join("b/scratch"));
+
+        let mut executables = Executables::default();
+        let script = format!(
+            "cat {bind}/data; touch {bind}/new || echo read-only; \
+             echo written > {scratch}/file && cat {scratch}/file",
+            bind = bind.display(),
+            scratch = scratch.display(),
+        );
+        let mut sh = spec("mounts", "sh", &["-c", &script]);
+        sh.mounts = vec![
+            MountSpec::Bind {
+                source: source.clone(),
+                target: bind.clone(),
+                read_only: true,
+            },
+            MountSpec::Tmpfs {
+                target: scratch.clone(),
+                size_bytes: Some(4096),
+            },
+        ];
+        let executable =
+            executables.start(sh, None, None).await.expect("failed to start");
+        let stdout = executable.stdout.clone();
+
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not exit");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let (output, _) = stdout.subscribe_since("test", 0);
+        let output: Vec<_> = output.into_iter().map(|item| item.line).collect();
+        assert_eq!(output, vec!["bound", "read-only", "written"]);
+
+        // The targets were created, but the mounts are gone with the process
+        assert!(bind.is_dir() && scratch.is_dir());
+        assert!(!bind.join("data").exists());
+        assert!(!scratch.join("file").exists());
+        std::fs::remove_dir_all(&dir).expect("remove dir");
+    }
+
+    #[tokio::test]
+    async fn test_mounts_are_made_in_the_rootfs() {
+        skip_if_not_root!("test_mounts_are_made_in_the_rootfs");
+        let rootfs = prepare_rootfs();
+        let source = std::env::temp_dir()
+            .join(format!("mount-source-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&source).expect("create source");
+        std::fs::write(source.join("outside"), "").expect("write file");
+
+        let mut executables = Executables::default();
+        let mut ls = spec("ls", "/bin/ls", &["-1", "/data"]);
+        ls.rootfs = Some(rootfs.clone());
+        ls.mounts = vec![MountSpec::Bind {
+            source: source.clone(),
+            target: "/data".into(),
+            read_only: true,
+        }];
+        let executable =
+            executables.start(ls, None, None).await.expect("failed to start");
+        assert_eq!(first_line(executable).await, "outside");
+
+        let mut attempts = 0;
+        while !executables.running().is_empty() {
+            attempts += 1;
+            assert!(attempts < 50, "executable did not exit");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        // The target is created in the rootfs, rather than on the host
+        assert!(rootfs.join("data").is_dir());
+        std::fs::remove_dir_all(&rootfs).expect("remove rootfs");
+        std::fs::remove_dir_all(&source).expect("remove source");
+    }
+
+    #[tokio::test]
+    async fn test_stop_kills_the_processes_holding_the_output_open() {
+        let mut executables = Executables::default();
+        let orphaner = spec("orphaner", "sh", &["-c", "sleep 300 &"]);
+        let name = orphaner.name.clone();
+        let _ = executables
+            .start(orphaner, None, None)
+            .await
+            .expect("failed to start");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let started = tokio::time::Instant::now();
+        let mut executable = executables.take(&name).expect("failed to take");
+        let stopped =
+            tokio::time::timeout(Duration::from_secs(30), executable.kill())
+                .await
+                .expect("stop hung");
+        assert!(stopped.is_ok());
+        assert!(started.elapsed() < output_drain::drain_timeout());
+        assert!(!executable.output_truncated());
+        let _ = executables.finish_stop(executable, stopped);
+    }
+
+    #[tokio::test]
+    async fn test_stop_cuts_short_the_output_held_open() {
+        let mut executables = Executables::default();
+        // The process left running escapes being killed along with the
+        // executable, by clearing the id from its environment and leaving
+        // the process group. Its pid is written to stderr.
+        let holder = spec(
+            "holder",
+            "sh",
+            &[
+                "-c",
+                "env -u AURAE_EXECUTABLE_ID setsid sleep 300 & echo $! >&2; printf partial; sleep 10",
+            ],
+        );
+        let name = holder.name.clone();
+        let _ = executables
+            .start(holder, None, None)
+            .await
+            .expect("failed to start");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let started = tokio::time::Instant::now();
+        let mut executable = executables.take(&name).expect("failed to take");
+        let stopped =
+            tokio::time::timeout(Duration::from_secs(30), executable.kill())
+                .await
+                .expect("stop hung");
+        let elapsed = started.elapsed();
+        let (history, _) = executable.stderr.subscribe_since("test", 0);
+        let holder: i32 = history[0].line.parse().expect("holder pid");
+        let _ = nix::sys::signal::kill(
+            Pid::from_raw(holder),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+
+        assert!(stopped.is_ok());
+        assert!(elapsed >= output_drain::drain_timeout());
+        assert!(elapsed < output_drain::drain_timeout() * 3);
+        assert!(executable.output_truncated());
+        let (history, _) = executable.stdout.subscribe_since("test", 0);
+        assert_eq!(
+            history.last().expect("last line").line,
+            output_drain::truncated_line("partial")
+        );
+        let _ = executables.finish_stop(executable, stopped);
+    }
+
+    /// Returns true if a process of the group `pgid` is alive.
+    fn group_alive(pgid: Pid) -> bool {
+        procfs::process::all_processes().unwrap().any(|process| {
+            process.ok().and_then(|process| process.stat().ok()).is_some_and(
+                |stat| stat.pgrp == pgid.as_raw() && stat.state != 'Z',
+            )
+        })
+    }
+
+    #[tokio::test]
+    async fn test_kill_modes_of_a_double_forking_executable() {
+        // The grandchild is orphaned, and clears the executable id from its
+        // environment, but remains in the process group
+        const DOUBLE_FORK: &str =
+            "(env -u AURAE_EXECUTABLE_ID sleep 10 &); exec sleep 10";
+
+        for (kill_mode, survives) in
+            [(KillMode::Process, true), (KillMode::ProcessGroup, false)]
+        {
+            let mut executables = Executables::default();
+            let double_fork = spec("double-fork", "sh", &["-c", DOUBLE_FORK]);
+            let name = double_fork.name.clone();
+            let pgid = executables
+                .start(double_fork, None, None)
+                .await
+                .expect("failed to start")
+                .pid()
+                .unwrap()
+                .expect("pid");
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            let mut executable =
+                executables.take(&name).expect("failed to take");
+            let stopped = executable.kill_with(kill_mode).await;
+            assert!(stopped.is_ok());
+            assert_eq!(group_alive(pgid), survives, "{kill_mode:?}");
+
+            let _ = nix::sys::signal::killpg(
+                pgid,
+                nix::sys::signal::Signal::SIGKILL,
+            );
+            let _ = executables.finish_stop(executable, stopped);
+        }
+    }
+
+    /// Starts and stops many short-lived executables, measuring the tasks
+    /// alive meanwhile, and how long their exits take to be observed.
+    /// Exits are observed through the reaper, rather than by a task for
+    /// each executable, leaving only the tasks reading their outputs.
+    /// Run with `cargo test --release -- --ignored --nocapture start_stop_many`
+    #[tokio::test]
+    #[ignore]
+    async fn bench_start_stop_many_executables() {
+        const EXECUTABLES: usize = 500;
+        let metrics = tokio::runtime::Handle::current().metrics();
+        let baseline = metrics.num_alive_tasks();
+
+        let start = tokio::time::Instant::now();
+        let mut executables = Executables::default();
+        let (tx, mut exited) = tokio::sync::mpsc::unbounded_channel();
+        for i in 0..EXECUTABLES {
+            let executable = executables
+                .start(spec(&format!("true-{i}"), "true", &[]), None, None)
+                .await
+                .expect("failed to start");
+            let tx = tx.clone();
+            assert!(executable.on_exit(move |exit_status| {
+                let _ = tx.send(exit_status);
+            }));
+        }
+        let started = start.elapsed();
+        let tasks = metrics.num_alive_tasks() - baseline;
+
+        for _ in 0..EXECUTABLES {
+            let exit_status = exited.recv().await.expect("exit status");
+            assert!(exit_status.expect("exit status").success());
+        }
+        let observed = start.elapsed();
+
+        for i in 0..EXECUTABLES {
+            let name = ExecutableName::new(format!("true-{i}"));
+            let exit_status = executables.stop(&name).await.expect("stopped");
+            assert!(exit_status.expect("exit status").success());
+        }
+        let stopped = start.elapsed();
+
+        println!(
+            "{EXECUTABLES} executables: started in {started:?} with {tasks} tasks alive, exits observed after {observed:?}, stopped after {stopped:?}"
+        );
+        assert!(tasks <= 2 * EXECUTABLES, "{tasks} tasks");
+    }
 }
@@ -13,20 +13,67 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
+use crate::logging::output_limit::OutputLimit;
+pub use dependencies::start_order;
 pub use error::{ExecutablesError, Result};
-pub use executable::Executable;
+pub use executable::{Executable, KillMode, EXECUTABLE_ID_ENV};
 pub use executable_name::ExecutableName;
-pub use executables::Executables;
+pub use executables::{Executables, Reservation};
+pub use mounts::{MountSpec, Mounts, MountsError};
+pub use privileges::{CapabilitiesSpec, CapabilitySet};
+pub use readiness::{
+    wait_ready, NotReady, ReadinessCheck, ReadinessProbe, DEFAULT_PROBE_PERIOD,
+    DEFAULT_PROBE_TIMEOUT, DEFAULT_READY_AFTER,
+};
+pub use rootfs::{Rootfs, RootfsError};
+pub use seccomp::{SeccompProfile, SeccompProfileError};
+use std::path::PathBuf;
+pub use template::{
+    is_variable_name, substitute, AURAE_RUNTIME_DIR_VARIABLE,
+    CELL_NAME_VARIABLE,
+};
 use tokio::process::Command;
+pub use user_namespace::{IdMapping, UserNamespace};
 
+mod dependencies;
 mod error;
 mod executable;
 mod executable_name;
 #[allow(clippy::module_inception)]
 mod executables;
+mod mounts;
+mod privileges;
+mod readiness;
+mod rootfs;
+mod seccomp;
+mod template;
+mod user_namespace;
 
 pub struct ExecutableSpec {
     pub name: ExecutableName,
     pub description: String,
     pub command: Command,
+    /// Fail to start, rather than keep tracking the processes left running,
+    /// if the process daemonizes.
+    pub forbid_daemonize: bool,
+    /// Send the output of the executable unchanged, rather than redacting
+    /// the secrets auraed is configured with.
+    pub disable_log_redaction: bool,
+    /// The built-in seccomp profile, or the path to the OCI seccomp profile,
+    /// to apply to the process before it execs.
+    pub seccomp_profile: Option<String>,
+    /// Keep the process, and the programs it runs, from gaining privileges.
+    pub no_new_privs: bool,
+    /// The capabilities to keep and drop, rather than those the user the
+    /// process runs as has.
+    pub capabilities: Option<CapabilitiesSpec>,
+    /// The directory to confine the process to, in a mount namespace of its
+    /// own, rather than the host filesystem.
+    pub rootfs: Option<PathBuf>,
+    /// The rate the process may write each output at, with unset rates
+    /// taking the default of auraed.
+    pub output_limit: OutputLimit,
+    /// The mounts made for the process alone, in a mount namespace of its
+    /// own, after it is confined to its rootfs.
+    pub mounts: Vec<MountSpec>,
 }
@@ -0,0 +1,281 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+//! Mounts made for an executable alone, in its own mount namespace, which
+//! are gone once the processes in it exit.
+
+use super::rootfs::{c_path, check, mount};
+use std::{
+    ffi::CString,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+// From linux/mount.h, which libc does not define yet
+const OPEN_TREE_CLONE: libc::c_uint = 1;
+const AT_RECURSIVE: libc::c_uint = 0x8000;
+const MOVE_MOUNT_F_EMPTY_PATH: libc::c_uint = 0x4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MountSpec {
+    /// A tmpfs any user can write to, of at most `size_bytes`, or the tmpfs
+    /// default if unset.
+    Tmpfs { target: PathBuf, size_bytes: Option<u64> },
+    /// A file or directory of the host, seen at `target`.
+    Bind { source: PathBuf, target: PathBuf, read_only: bool },
+}
+
+#[derive(Error, Debug)]
+pub enum MountsError {
+    #[error("bind mount source {path:?} is not accessible: {source}")]
+    SourceNotAccessible { path: PathBuf, source: io::Error },
+    #[error(
+        "failed to clone the mount of bind mount source {path:?}: {source}"
+    )]
+    FailedToCloneSource { path: PathBuf, source: io::Error },
+}
+
+/// The mounts of an executable, with their paths prepared, as they can't be
+/// allocated once the process is forked.
+#[derive(Debug)]
+pub struct Mounts(Vec<Mount>);
+
+#[derive(Debug)]
+struct Mount {
+    /// The directories to create, outermost first, ending with the target
+    /// unless it is a file.
+    dirs: Vec<CString>,
+    /// The target, if it is a file, which a file is bound to.
+    file: Option<CString>,
+    target: CString,
+    kind: MountKind,
+}
+
+#[derive(Debug)]
+enum MountKind {
+    Tmpfs {
+        options: CString,
+    },
+    Bind {
+        /// A detached copy of the mount of the source, cloned on the host, as
+        /// the source may be out of reach once the executable is confined to
+        /// its rootfs.
+        tree: OwnedFd,
+        read_only: bool,
+    },
+}
+
+impl Mounts {
+    /// Clones the mounts of the sources of the bind mounts, which must exist
+    /// on the host.
+    pub fn new(specs: &[MountSpec]) -> Result<Self, MountsError> {
+        specs
+            .iter()
+            .map(|spec| match spec {
+                MountSpec::Tmpfs { target, size_bytes } => {
+                    let mut options = String::from("mode=1777");
+                    if let Some(size_bytes) = size_bytes {
+                        options.push_str(&format!(",size={size_bytes}"));
+                    }
+                    Ok(Mount::new(
+                        target,
+                        true,
+                        MountKind::Tmpfs {
+                            options: CString::new(options)
+                                .expect("options without nul bytes"),
+                        },
+                    ))
+                }
+                MountSpec::Bind { source, target, read_only } => {
+                    let is_dir = std::fs::metadata(source)
+                        .map_err(|e| MountsError::SourceNotAccessible {
+                            path: source.clone(),
+                            source: e,
+                        })?
+                        .is_dir();
+                    let tree = clone_tree(source).map_err(|e| {
+                        MountsError::FailedToCloneSource {
+                            path: source.clone(),
+                            source: e,
+                        }
+                    })?;
+                    Ok(Mount::new(
+                        target,
+                        is_dir,
+                        MountKind::Bind { tree, read_only: *read_only },
+                    ))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Makes the mounts, creating their targets if missing. Called between
+    /// fork and exec, in a mount namespace of the process' own, after it is
+    /// confined to its rootfs, so it only makes syscalls.
+    pub fn apply(&self) -> io::Result<()> {
+        for mount in &self.0 {
+            mount.apply()?;
+        }
+        Ok(())
+    }
+}
+
+impl Mount {
+    fn new(target: &Path, is_dir: bool, kind: MountKind) -> Self {
+        let mut dirs: Vec<_> = target
+            .ancestors()
+            .filter(|dir| dir.parent().is_some())
+            .skip(usize::from(!is_dir))
+            .map(c_path)
+            .collect();
+        dirs.reverse();
+
+        Self {
+            dirs,
+            file: (!is_dir).then(|| c_path(target)),
+            target: c_path(target),
+            kind,
+        }
+    }
+
+    fn apply(&self) -> io::Result<()> {
+        for dir in &self.dirs {
+            // SAFETY: the path is a valid C string
+            if unsafe { libc::mkdir(dir.as_ptr(), 0o755) } < 0 {
+                let e = io::Error::last_os_error();
+                if e.raw_os_error() != Some(libc::EEXIST) {
+                    return Err(e);
+                }
+            }
+        }
+        if let Some(file) = &self.file {
+            // SAFETY: the path is a valid C string
+            let fd = unsafe {
+                libc::open(
+                    file.as_ptr(),
+                    libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC,
+                    0o644,
+                )
+            };
+            check(fd)?;
+            // SAFETY: fd was opened above
+            let _ = unsafe { libc::close(fd) };
+        }
+
+        match &self.kind {
+            MountKind::Tmpfs { options } => mount(
+                Some(c"tmpfs"),
+                &self.target,
+                Some(c"tmpfs"),
+                libc::MS_NOSUID | libc::MS_NODEV,
+                Some(options),
+            ),
+            MountKind::Bind { tree, read_only } => {
+                // SAFETY: the paths are valid C strings, and tree is open
+                check(unsafe {
+                    libc::syscall(
+                        libc::SYS_move_mount,
+                        tree.as_raw_fd(),
+                        c"".as_ptr(),
+                        libc::AT_FDCWD,
+                        self.target.as_ptr(),
+                        MOVE_MOUNT_F_EMPTY_PATH,
+                    )
+                } as libc::c_int)?;
+                if !read_only {
+                    return Ok(());
+                }
+                // A bind mount only takes flags when it is remounted
+                mount(
+                    None,
+                    &self.target,
+                    None,
+                    libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY,
+                    None,
+                )
+            }
+        }
+    }
+}
+
+/// Clones the mount of `source`, and those under it, into a mount which is
+/// not attached anywhere yet.
+fn clone_tree(source: &Path) -> io::Result<OwnedFd> {
+    let source = c_path(source);
+    // SAFETY: the path is a valid C string
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_open_tree,
+            libc::AT_FDCWD,
+            source.as_ptr(),
+            OPEN_TREE_CLONE | libc::O_CLOEXEC as libc::c_uint | AT_RECURSIVE,
+        )
+    };
+    check(fd as libc::c_int)?;
+    // SAFETY: open_tree returned a new fd, which nothing else owns
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_prepares_the_targets() {
+        let mounts = Mounts::new(&[
+            MountSpec::Tmpfs {
+                target: PathBuf::from("/scratch/space"),
+                size_bytes: Some(1 << 20),
+            },
+            MountSpec::Bind {
+                source: PathBuf::from("/etc/hostname"),
+                target: PathBuf::from("/etc/name"),
+                read_only: true,
+            },
+        ])
+        .expect("valid mounts");
+
+        let tmpfs = &mounts.0[0];
+        assert_eq!(
+            tmpfs.dirs,
+            vec![c"/scratch".to_owned(), c"/scratch/space".to_owned()]
+        );
+        assert_eq!(tmpfs.file, None);
+        assert!(matches!(
+            &tmpfs.kind,
+            MountKind::Tmpfs { options } if options.as_c_str() == c"mode=1777,size=1048576"
+        ));
+
+        // A file is bound to a file, in the directories created for it
+        let bind = &mounts.0[1];
+        assert_eq!(bind.dirs, vec![c"/etc".to_owned()]);
+        assert_eq!(bind.file, Some(c"/etc/name".to_owned()));
+        assert!(matches!(bind.kind, MountKind::Bind { read_only: true, .. }));
+    }
+
+    #[test]
+    fn test_new_rejects_missing_sources() {
+        assert!(matches!(
+            Mounts::new(&[MountSpec::Bind {
+                source: PathBuf::from("/does/not/exist"),
+                target: PathBuf::from("/mnt"),
+                read_only: false,
+            }]),
+            Err(MountsError::SourceNotAccessible { .. })
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Limiting the privileges of executables: the capabilities they keep when
+//! started as another user, the capabilities they lose, and no_new_privs.
+//!
+//! The functions applying them are called between fork and exec, so they
+//! only make syscalls, and don't allocate.
+
+use caps::Capability;
+use std::{io, ops::BitOr, str::FromStr};
+
+/// A set of capabilities, as the kernel represents them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    pub const ALL: Self = Self(u64::MAX);
+
+    /// Parses "ALL", or the name of a capability, in any case and with or
+    /// without its "CAP_" prefix.
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("all") {
+            return Some(Self::ALL);
+        }
+
+        Capability::from_str(&caps::to_canonical(name))
+            .ok()
+            .map(|cap| Self(cap.bitmask()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for CapabilitySet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilitiesSpec {
+    /// Removed from the bounding set, so neither the executable nor the
+    /// programs it runs can gain them. Those kept are never dropped.
+    pub drop: CapabilitySet,
+    /// Kept when the executable is started as a user other than root, as
+    /// ambient capabilities, which programs it runs inherit.
+    pub keep: CapabilitySet,
+}
+
+/// Sets the gid and uid, dropping and keeping capabilities as `spec` says.
+pub(super) fn set_ids_and_capabilities(
+    spec: &CapabilitiesSpec,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> io::Result<()> {
+    let dropped = spec.drop.0 & !spec.keep.0;
+
+    // Dropping from the bounding set takes CAP_SETPCAP, which is lost
+    // along with root.
+    for cap in capabilities(dropped) {
+        // Capabilities the kernel doesn't know of can't be had anyway
+        if prctl(libc::PR_CAPBSET_READ, cap, 0).is_err() {
+            continue;
+        }
+        let _ = prctl(libc::PR_CAPBSET_DROP, cap, 0)?;
+    }
+
+    if uid.is_some() && !spec.keep.is_empty() {
+        let _ = prctl(libc::PR_SET_KEEPCAPS, 1, 0)?;
+    }
+    set_ids(uid, gid)?;
+
+    // Changing the uid cleared the effective set, and the permitted set
+    // too, unless capabilities are kept. Those kept must be inheritable to
+    // be ambient.
+    let current = capget()?;
+    let permitted = current.permitted & !dropped;
+    let inheritable = (current.inheritable | spec.keep.0) & permitted;
+    capset(Sets { effective: permitted, permitted, inheritable })?;
+
+    for cap in capabilities(spec.keep.0 & permitted) {
+        let _ = prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_RAISE as libc::c_ulong,
+            cap,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Sets the gid and uid, as [std::os::unix::process::CommandExt] does.
+pub(super) fn set_ids(uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+    if let Some(gid) = gid {
+        // SAFETY: setgid has no memory safety requirements
+        check(unsafe { libc::setgid(gid) })?;
+    }
+    if let Some(uid) = uid {
+        // SAFETY: an empty list of groups is not read
+        if unsafe { libc::getuid() } == 0 {
+            check(unsafe { libc::setgroups(0, std::ptr::null()) })?;
+        }
+        // SAFETY: setuid has no memory safety requirements
+        check(unsafe { libc::setuid(uid) })?;
+    }
+    Ok(())
+}
+
+/// Keeps the executable, and the programs it runs, from gaining privileges
+/// through setuid binaries and file capabilities.
+pub(super) fn set_no_new_privs() -> io::Result<()> {
+    prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0).map(|_| ())
+}
+
+/// The numbers of the capabilities in a set.
+fn capabilities(set: u64) -> impl Iterator<Item = libc::c_ulong> {
+    (0..64).filter(move |cap| set & (1 << cap) != 0)
+}
+
+/// Calls prctl with two arguments, passing the unused ones as zero, as some
+/// options require.
+fn prctl(
+    option: libc::c_int,
+    arg2: libc::c_ulong,
+    arg3: libc::c_ulong,
+) -> io::Result<libc::c_int> {
+    let unused: libc::c_ulong = 0;
+    // SAFETY: none of the options used take pointers
+    let result = unsafe { libc::prctl(option, arg2, arg3, unused, unused) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result)
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+#[repr(C)]
+struct CapUserHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+/// Version 3 of the capabilities ABI splits each set in two 32 bit words.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct CapUserData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+struct Sets {
+    effective: u64,
+    permitted: u64,
+    inheritable: u64,
+}
+
+fn capget() -> io::Result<Sets> {
+    let mut header =
+        CapUserHeader { version: LINUX_CAPABILITY_VERSION_3, pid: 0 };
+    let mut data = [CapUserData::default(); 2];
+    // SAFETY: the header and data are those of version 3
+    let result = unsafe {
+        libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr())
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let join = |low: u32, high: u32| u64::from(low) | (u64::from(high) << 32);
+    Ok(Sets {
+        effective: join(data[0].effective, data[1].effective),
+        permitted: join(data[0].permitted, data[1].permitted),
+        inheritable: join(data[0].inheritable, data[1].inheritable),
+    })
+}
+
+fn capset(sets: Sets) -> io::Result<()> {
+    let mut header =
+        CapUserHeader { version: LINUX_CAPABILITY_VERSION_3, pid: 0 };
+    let split = |set: u64| (set as u32, (set >> 32) as u32);
+    let (effective, permitted, inheritable) =
+        (split(sets.effective), split(sets.permitted), split(sets.inheritable));
+    let data = [
+        CapUserData {
+            effective: effective.0,
+            permitted: permitted.0,
+            inheritable: inheritable.0,
+        },
+        CapUserData {
+            effective: effective.1,
+            permitted: permitted.1,
+            inheritable: inheritable.1,
+        },
+    ];
+    // SAFETY: the header and data are those of version 3
+    let result =
+        unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_capability_names() {
+        let net_bind_service =
+            CapabilitySet(Capability::CAP_NET_BIND_SERVICE.bitmask());
+        assert_eq!(
+            CapabilitySet::parse("CAP_NET_BIND_SERVICE"),
+            Some(net_bind_service)
+        );
+        assert_eq!(
+            CapabilitySet::parse("net_bind_service"),
+            Some(net_bind_service)
+        );
+        assert_eq!(CapabilitySet::parse("All"), Some(CapabilitySet::ALL));
+        assert_eq!(CapabilitySet::parse("CAP_FLY"), None);
+        assert_eq!(CapabilitySet::parse(""), None);
+    }
+
+    #[test]
+    fn test_capabilities_of_a_set() {
+        let set = CapabilitySet::parse("chown").unwrap()
+            | CapabilitySet::parse("net_raw").unwrap();
+        assert_eq!(capabilities(set.0).collect::<Vec<_>>(), vec![0, 13]);
+        assert_eq!(capabilities(CapabilitySet::ALL.0).count(), 64);
+    }
+}
@@ -0,0 +1,166 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Whether an executable started in a batch is ready for the executables
+//! that depend on it to start.
+
+use super::Executable;
+use crate::reaper::{self, ReapedChild};
+use nix::unistd::Pid;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::net::SocketAddr;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// How long an executable without a readiness probe must keep running to
+/// be ready, unless its batch sets it.
+pub const DEFAULT_READY_AFTER: Duration = Duration::from_secs(1);
+pub const DEFAULT_PROBE_PERIOD: Duration = Duration::from_secs(1);
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadinessProbe {
+    pub check: ReadinessCheck,
+    /// The time between attempts, and the most each attempt may take.
+    pub period: Duration,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadinessCheck {
+    /// Ready once the shell command exits with 0.
+    Command(OsString),
+    /// Ready once the address accepts a TCP connection.
+    Tcp(SocketAddr),
+}
+
+#[derive(Debug, Error)]
+pub enum NotReady {
+    #[error("exited before it was ready")]
+    Exited,
+    #[error("was not ready within {}ms", .0.as_millis())]
+    TimedOut(Duration),
+    #[error("could not be probed: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Waits for `executable` to pass its `probe`, or, without one, to keep
+/// running for `ready_after`.
+pub async fn wait_ready(
+    executable: &mut Executable,
+    probe: Option<&ReadinessProbe>,
+    ready_after: Duration,
+) -> Result<(), NotReady> {
+    let Some(probe) = probe else {
+        tokio::time::sleep(ready_after).await;
+        return match executable.is_running()? {
+            true => Ok(()),
+            false => Err(NotReady::Exited),
+        };
+    };
+
+    let deadline = Instant::now() + probe.timeout;
+    loop {
+        if !executable.is_running()? {
+            return Err(NotReady::Exited);
+        }
+        if attempt(&probe.check, probe.period).await? {
+            return Ok(());
+        }
+        if Instant::now() + probe.period > deadline {
+            return Err(NotReady::TimedOut(probe.timeout));
+        }
+        tokio::time::sleep(probe.period).await;
+    }
+}
+
+/// Returns true if `check` passes within `timeout`.
+async fn attempt(
+    check: &ReadinessCheck,
+    timeout: Duration,
+) -> io::Result<bool> {
+    match check {
+        ReadinessCheck::Command(command) => {
+            let mut probe = CommandProbe::spawn(command)?;
+            match tokio::time::timeout(timeout, probe.0.wait()).await {
+                Ok(status) => Ok(status?.success()),
+                Err(_) => Ok(false),
+            }
+        }
+        ReadinessCheck::Tcp(address) => {
+            let connect = TcpStream::connect(address);
+            Ok(matches!(
+                tokio::time::timeout(timeout, connect).await,
+                Ok(Ok(_))
+            ))
+        }
+    }
+}
+
+/// The process of a command check, registered with the reaper like the
+/// processes of executables, so that auraed reaping orphans does not take
+/// its exit status. It is killed if it still runs once dropped, as when the
+/// check times out.
+struct CommandProbe(ReapedChild);
+
+impl CommandProbe {
+    fn spawn(script: &OsStr) -> io::Result<Self> {
+        let mut command = Command::new("sh");
+        let _ = command
+            .arg("-c")
+            .arg(script)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        let spawning = reaper::spawning();
+        #[allow(clippy::disallowed_methods)] // spawned under spawning
+        let child = command.spawn()?;
+        Ok(Self(spawning.reap(Pid::from_raw(child.id() as i32))))
+    }
+}
+
+impl Drop for CommandProbe {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_attempt_runs_the_check() {
+        let command = |command: &str| ReadinessCheck::Command(command.into());
+        let period = Duration::from_secs(1);
+
+        assert!(attempt(&command("exit 0"), period).await.unwrap());
+        assert!(!attempt(&command("exit 1"), period).await.unwrap());
+        assert!(!attempt(&command("sleep 10"), Duration::from_millis(10))
+            .await
+            .unwrap());
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        assert!(attempt(&ReadinessCheck::Tcp(address), period).await.unwrap());
+        drop(listener);
+        assert!(!attempt(&ReadinessCheck::Tcp(address), period).await.unwrap());
+    }
+}
@@ -0,0 +1,298 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Confining an executable to a directory tree, in a mount namespace of its
+//! own. The namespace, and the mounts in it, are gone once the processes in
+//! it exit.
+
+use std::{
+    ffi::{CStr, CString},
+    io,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::{Path, PathBuf},
+    ptr,
+};
+use thiserror::Error;
+
+/// The devices bound from the host into the /dev of the rootfs, if the host
+/// has them.
+const DEVICES: &[&str] = &["full", "null", "random", "tty", "urandom", "zero"];
+
+/// The links created in the /dev of the rootfs, and their targets.
+const DEV_LINKS: &[(&str, &str)] = &[
+    ("fd", "/proc/self/fd"),
+    ("stdin", "/proc/self/fd/0"),
+    ("stdout", "/proc/self/fd/1"),
+    ("stderr", "/proc/self/fd/2"),
+];
+
+#[derive(Error, Debug)]
+pub enum RootfsError {
+    #[error("rootfs {path:?} is not accessible: {source}")]
+    NotAccessible { path: PathBuf, source: io::Error },
+    #[error("rootfs {path:?} is not a directory")]
+    NotADirectory { path: PathBuf },
+    #[error(
+        "rootfs {path:?} is owned by uid {owner}, rather than root or the uid the executable runs as"
+    )]
+    WrongOwner { path: PathBuf, owner: u32 },
+    #[error("rootfs {path:?} is writable by users other than its owner")]
+    Writable { path: PathBuf },
+    #[error("failed to create {path:?} in the rootfs: {source}")]
+    FailedToCreate { path: PathBuf, source: io::Error },
+}
+
+/// A directory to confine an executable to, with the paths of the mounts in
+/// it prepared, as they can't be allocated once the process is forked.
+#[derive(Debug)]
+pub struct Rootfs {
+    root: CString,
+    proc: CString,
+    dev: CString,
+    /// The host devices, and where they are bound in the rootfs
+    devices: Vec<(CString, CString)>,
+    /// The links in /dev of the rootfs, and their targets
+    dev_links: Vec<(CString, CString)>,
+}
+
+impl Rootfs {
+    /// Checks that `path` is a directory, which only root or the user the
+    /// executable runs as (`uid`, or the user auraed runs as) can change,
+    /// creating the /proc and /dev mount points in it if missing.
+    pub fn new(path: &Path, uid: Option<u32>) -> Result<Self, RootfsError> {
+        let metadata = std::fs::metadata(path).map_err(|source| {
+            RootfsError::NotAccessible { path: path.to_path_buf(), source }
+        })?;
+        if !metadata.is_dir() {
+            return Err(RootfsError::NotADirectory {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let runs_as = uid.unwrap_or_else(|| nix::unistd::geteuid().as_raw());
+        if metadata.uid() != 0 && metadata.uid() != runs_as {
+            return Err(RootfsError::WrongOwner {
+                path: path.to_path_buf(),
+                owner: metadata.uid(),
+            });
+        }
+        if metadata.mode() & 0o022 != 0 {
+            return Err(RootfsError::Writable { path: path.to_path_buf() });
+        }
+
+        let proc = path.join("proc");
+        let dev = path.join("dev");
+        for mount_point in [&proc, &dev] {
+            if !mount_point.is_dir() {
+                std::fs::create_dir(mount_point).map_err(|source| {
+                    RootfsError::FailedToCreate {
+                        path: mount_point.clone(),
+                        source,
+                    }
+                })?;
+            }
+        }
+
+        let devices = DEVICES
+            .iter()
+            .map(|device| (Path::new("/dev").join(device), dev.join(device)))
+            .filter(|(host, _)| host.exists())
+            .map(|(host, target)| (c_path(&host), c_path(&target)))
+            .collect();
+        let dev_links = DEV_LINKS
+            .iter()
+            .map(|(link, target)| {
+                (c_path(&dev.join(link)), c_path(Path::new(target)))
+            })
+            .collect();
+
+        Ok(Self {
+            root: c_path(path),
+            proc: c_path(&proc),
+            dev: c_path(&dev),
+            devices,
+            dev_links,
+        })
+    }
+
+    /// Unshares the mount namespace of the calling process, and makes the
+    /// rootfs its root, with /proc and a minimal /dev mounted in it.
+    /// Called between fork and exec, so it only makes syscalls.
+    pub fn enter(&self) -> io::Result<()> {
+        unshare_mount_namespace()?;
+
+        // pivot_root requires the new root to be a mount point
+        mount(
+            Some(&self.root),
+            &self.root,
+            None,
+            libc::MS_BIND | libc::MS_REC,
+            None,
+        )?;
+        mount(
+            Some(c"/proc"),
+            &self.proc,
+            None,
+            libc::MS_BIND | libc::MS_REC,
+            None,
+        )?;
+        mount(
+            Some(c"tmpfs"),
+            &self.dev,
+            Some(c"tmpfs"),
+            libc::MS_NOSUID | libc::MS_NOEXEC,
+            Some(c"mode=755"),
+        )?;
+        for (host, target) in &self.devices {
+            // SAFETY: the path is a valid C string
+            let fd = unsafe {
+                libc::open(
+                    target.as_ptr(),
+                    libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC,
+                    0o666,
+                )
+            };
+            check(fd)?;
+            // SAFETY: fd was opened above
+            let _ = unsafe { libc::close(fd) };
+            mount(Some(host), target, None, libc::MS_BIND, None)?;
+        }
+        for (link, target) in &self.dev_links {
+            // SAFETY: the paths are valid C strings
+            check(unsafe { libc::symlink(target.as_ptr(), link.as_ptr()) })?;
+        }
+
+        // SAFETY: the path is a valid C string
+        check(unsafe { libc::chdir(self.root.as_ptr()) })?;
+        // Stack the rootfs over the old root, and detach the old root from
+        // under it. pivot_root fails on the initramfs auraed may run from as
+        // pid 1, so chroot is the fallback.
+        // SAFETY: the paths are valid C strings
+        let pivoted = unsafe {
+            libc::syscall(libc::SYS_pivot_root, c".".as_ptr(), c".".as_ptr())
+        } == 0;
+        if pivoted {
+            // SAFETY: the path is a valid C string
+            check(unsafe { libc::umount2(c".".as_ptr(), libc::MNT_DETACH) })?;
+        } else {
+            // SAFETY: the path is a valid C string
+            check(unsafe { libc::chroot(c".".as_ptr()) })?;
+        }
+        // SAFETY: the path is a valid C string
+        check(unsafe { libc::chdir(c"/".as_ptr()) })
+    }
+}
+
+/// Unshares the mount namespace of the calling process, keeping the mounts
+/// made in it from propagating to the host. Only makes syscalls.
+pub(super) fn unshare_mount_namespace() -> io::Result<()> {
+    // SAFETY: unshare has no memory safety requirements
+    check(unsafe { libc::unshare(libc::CLONE_NEWNS) })?;
+
+    mount(None, c"/", None, libc::MS_REC | libc::MS_PRIVATE, None)
+}
+
+pub(super) fn c_path(path: &Path) -> CString {
+    CString::new(path.as_os_str().as_bytes()).expect("path without nul bytes")
+}
+
+pub(super) fn mount(
+    source: Option<&CStr>,
+    target: &CStr,
+    fstype: Option<&CStr>,
+    flags: libc::c_ulong,
+    data: Option<&CStr>,
+) -> io::Result<()> {
+    let ptr = |s: Option<&CStr>| s.map_or(ptr::null(), CStr::as_ptr);
+    // SAFETY: the strings are valid C strings, or null
+    check(unsafe {
+        libc::mount(
+            ptr(source),
+            target.as_ptr(),
+            ptr(fstype),
+            flags,
+            ptr(data).cast(),
+        )
+    })
+}
+
+pub(super) fn check(result: libc::c_int) -> io::Result<()> {
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temp_dir(mode: u32) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("rootfs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path).expect("create rootfs");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+            .expect("set permissions");
+        path
+    }
+
+    #[test]
+    fn test_new_creates_the_mount_points() {
+        let path = temp_dir(0o755);
+
+        let rootfs = Rootfs::new(&path, None).expect("valid rootfs");
+        assert!(path.join("proc").is_dir());
+        assert!(path.join("dev").is_dir());
+        assert_eq!(rootfs.root, c_path(&path));
+        assert!(rootfs
+            .devices
+            .iter()
+            .any(|(host, _)| host.as_c_str() == c"/dev/null"));
+
+        std::fs::remove_dir_all(&path).expect("remove rootfs");
+    }
+
+    #[test]
+    fn test_new_rejects_unsafe_directories() {
+        assert!(matches!(
+            Rootfs::new(Path::new("/does/not/exist"), None),
+            Err(RootfsError::NotAccessible { .. })
+        ));
+        assert!(matches!(
+            Rootfs::new(Path::new("/proc/self/status"), None),
+            Err(RootfsError::NotADirectory { .. })
+        ));
+
+        let path = temp_dir(0o777);
+        assert!(matches!(
+            Rootfs::new(&path, None),
+            Err(RootfsError::Writable { .. })
+        ));
+        std::fs::remove_dir_all(&path).expect("remove rootfs");
+
+        // Only root can give the directory away
+        if nix::unistd::geteuid().is_root() {
+            let path = temp_dir(0o755);
+            std::os::unix::fs::chown(&path, Some(1000), None).expect("chown");
+            assert!(matches!(
+                Rootfs::new(&path, Some(2000)),
+                Err(RootfsError::WrongOwner { owner: 1000, .. })
+            ));
+            assert!(Rootfs::new(&path, Some(1000)).is_ok());
+            std::fs::remove_dir_all(&path).expect("remove rootfs");
+        }
+    }
+}
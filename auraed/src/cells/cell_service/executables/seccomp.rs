@@ -0,0 +1,485 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Seccomp filters for executables, compiled from OCI seccomp profiles.
+//!
+//! seccompiler compiles a filter with a single action for the syscalls it
+//! matches, so a profile is compiled to a filter per action, and another
+//! taking the default action on the syscalls the profile doesn't list. The
+//! kernel runs all of them, and takes the most restrictive action returned.
+
+use oci_spec::runtime::{
+    LinuxSeccomp, LinuxSeccompAction, LinuxSeccompArg, LinuxSeccompBuilder,
+    LinuxSeccompOperator, LinuxSyscall, LinuxSyscallBuilder,
+};
+use seccompiler::{BpfProgram, TargetArch};
+use serde_json::{json, Value};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Denied by the "default" profile: syscalls that administer the host,
+/// rather than the processes of the executable.
+const DEFAULT_DENIED: &[&str] = &[
+    "acct",
+    "add_key",
+    "adjtimex",
+    "bpf",
+    "clock_adjtime",
+    "clock_settime",
+    "delete_module",
+    "finit_module",
+    "fsconfig",
+    "fsmount",
+    "fsopen",
+    "init_module",
+    "kexec_file_load",
+    "kexec_load",
+    "keyctl",
+    "lookup_dcookie",
+    "mount",
+    "move_mount",
+    "open_by_handle_at",
+    "open_tree",
+    "perf_event_open",
+    "pivot_root",
+    "ptrace",
+    "quotactl",
+    "reboot",
+    "request_key",
+    "setns",
+    "settimeofday",
+    "swapoff",
+    "swapon",
+    "syslog",
+    "umount2",
+    "unshare",
+    "userfaultfd",
+    "vhangup",
+];
+
+#[cfg(target_arch = "x86_64")]
+const DEFAULT_DENIED_ARCH: &[&str] =
+    &["_sysctl", "ioperm", "iopl", "modify_ldt", "uselib"];
+#[cfg(not(target_arch = "x86_64"))]
+const DEFAULT_DENIED_ARCH: &[&str] = &[];
+
+/// Denied by the "strict" profile, on top of those the "default" profile
+/// denies: networking, changing credentials, and creating devices.
+const STRICT_DENIED: &[&str] = &[
+    "accept",
+    "accept4",
+    "bind",
+    "capset",
+    "chroot",
+    "connect",
+    "io_uring_enter",
+    "io_uring_register",
+    "io_uring_setup",
+    "listen",
+    "mknodat",
+    "personality",
+    "process_vm_readv",
+    "process_vm_writev",
+    "setfsgid",
+    "setfsuid",
+    "setgid",
+    "setgroups",
+    "setregid",
+    "setresgid",
+    "setresuid",
+    "setreuid",
+    "setuid",
+    "socket",
+    "socketpair",
+];
+
+#[cfg(target_arch = "x86_64")]
+const STRICT_DENIED_ARCH: &[&str] = &["mknod"];
+#[cfg(not(target_arch = "x86_64"))]
+const STRICT_DENIED_ARCH: &[&str] = &[];
+
+#[derive(Error, Debug)]
+pub enum SeccompProfileError {
+    #[error("unknown seccomp profile '{0}'")]
+    UnknownProfile(String),
+    #[error("failed to read seccomp profile {path:?}: {source}")]
+    FailedToRead { path: PathBuf, source: io::Error },
+    #[error("failed to parse seccomp profile {path:?}: {source}")]
+    FailedToParse { path: PathBuf, source: serde_json::Error },
+    #[error("seccomp filters are not supported on {0}")]
+    UnsupportedArch(&'static str),
+    #[error("defaultAction {0} is not supported")]
+    UnsupportedDefaultAction(LinuxSeccompAction),
+    #[error("syscalls[{index}] ({names}): action {action} is not supported")]
+    UnsupportedAction {
+        index: usize,
+        names: String,
+        action: LinuxSeccompAction,
+    },
+    #[error("syscalls[{index}] ({names}): {source}")]
+    InvalidSyscall { index: usize, names: String, source: seccompiler::Error },
+    #[error("failed to compile seccomp profile: {0}")]
+    FailedToCompile(seccompiler::Error),
+}
+
+/// A seccomp profile, compiled for the architecture auraed runs on.
+#[derive(Debug, Clone)]
+pub struct SeccompProfile {
+    filters: Vec<BpfProgram>,
+}
+
+impl SeccompProfile {
+    /// Denies the syscalls that administer the host.
+    pub const DEFAULT: &'static str = "default";
+    /// Denies networking and changing credentials too.
+    pub const STRICT: &'static str = "strict";
+
+    /// Loads one of the named built-in profiles, or the OCI seccomp profile
+    /// at an absolute path.
+    pub fn load(profile: &str) -> Result<Self, SeccompProfileError> {
+        let spec = match profile {
+            Self::DEFAULT => {
+                deny(DEFAULT_DENIED.iter().chain(DEFAULT_DENIED_ARCH))
+            }
+            Self::STRICT => deny(
+                DEFAULT_DENIED
+                    .iter()
+                    .chain(DEFAULT_DENIED_ARCH)
+                    .chain(STRICT_DENIED)
+                    .chain(STRICT_DENIED_ARCH),
+            ),
+            path if Path::new(path).is_absolute() => {
+                let path = PathBuf::from(path);
+                let contents = std::fs::read(&path).map_err(|source| {
+                    SeccompProfileError::FailedToRead {
+                        path: path.clone(),
+                        source,
+                    }
+                })?;
+                serde_json::from_slice(&contents).map_err(|source| {
+                    SeccompProfileError::FailedToParse { path, source }
+                })?
+            }
+            _ => {
+                return Err(SeccompProfileError::UnknownProfile(
+                    profile.to_string(),
+                ))
+            }
+        };
+
+        Self::compile(&spec)
+    }
+
+    /// Compiles an OCI seccomp profile. The architectures it lists are
+    /// ignored, as the profile is only applied on this one.
+    pub fn compile(spec: &LinuxSeccomp) -> Result<Self, SeccompProfileError> {
+        let arch =
+            TargetArch::try_from(std::env::consts::ARCH).map_err(|_| {
+                SeccompProfileError::UnsupportedArch(std::env::consts::ARCH)
+            })?;
+        let default_action =
+            action(spec.default_action(), spec.default_errno_ret()).ok_or(
+                SeccompProfileError::UnsupportedDefaultAction(
+                    spec.default_action(),
+                ),
+            )?;
+
+        // The rules of the listed syscalls, grouped by action
+        let mut groups: Vec<(Value, Vec<Value>)> = vec![];
+        for (index, syscall) in spec.syscalls().iter().flatten().enumerate() {
+            if syscall.names().is_empty() {
+                continue;
+            }
+
+            let names = syscall.names().join(", ");
+            let action = action(syscall.action(), syscall.errno_ret())
+                .ok_or_else(|| SeccompProfileError::UnsupportedAction {
+                    index,
+                    names: names.clone(),
+                    action: syscall.action(),
+                })?;
+            let rules = rules(syscall);
+
+            // Compile each entry on its own as well, so an error names the
+            // entry at fault.
+            let mismatch_action = if action == json!("allow") {
+                json!({ "errno": libc::EPERM })
+            } else {
+                json!("allow")
+            };
+            let _ = compile_filter(&rules, &mismatch_action, &action, arch)
+                .map_err(|source| SeccompProfileError::InvalidSyscall {
+                    index,
+                    names,
+                    source,
+                })?;
+
+            match groups.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, group)) => group.extend(rules),
+                None => groups.push((action, rules)),
+            }
+        }
+
+        let allow = json!("allow");
+        let mut filters = vec![];
+        for (action, rules) in &groups {
+            if *action != allow {
+                filters.push(
+                    compile_filter(rules, &allow, action, arch)
+                        .map_err(SeccompProfileError::FailedToCompile)?,
+                );
+            }
+        }
+        if default_action != allow {
+            let rules: Vec<_> =
+                groups.into_iter().flat_map(|(_, rules)| rules).collect();
+            filters.push(
+                compile_filter(&rules, &default_action, &allow, arch)
+                    .map_err(SeccompProfileError::FailedToCompile)?,
+            );
+        }
+
+        Ok(Self { filters })
+    }
+
+    /// Applies the profile to the calling thread, and the processes it
+    /// execs. Setting no_new_privs first, as an unprivileged process must.
+    /// Called between fork and exec, so it must not allocate.
+    pub fn apply(&self) -> io::Result<()> {
+        for filter in &self.filters {
+            seccompiler::apply_filter(filter).map_err(|e| match e {
+                seccompiler::Error::Prctl(e)
+                | seccompiler::Error::Seccomp(e) => e,
+                _ => io::Error::from_raw_os_error(libc::EINVAL),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A profile allowing all syscalls but `names`, which fail with EPERM.
+fn deny<'a>(names: impl Iterator<Item = &'a &'a str>) -> LinuxSeccomp {
+    let denied = LinuxSyscallBuilder::default()
+        .names(names.map(|name| name.to_string()).collect::<Vec<_>>())
+        .action(LinuxSeccompAction::ScmpActErrno)
+        .errno_ret(libc::EPERM as u32)
+        .build()
+        .expect("valid syscall rule");
+
+    LinuxSeccompBuilder::default()
+        .default_action(LinuxSeccompAction::ScmpActAllow)
+        .syscalls(vec![denied])
+        .build()
+        .expect("valid seccomp profile")
+}
+
+/// The seccompiler JSON of an action, if seccompiler supports it.
+fn action(action: LinuxSeccompAction, errno_ret: Option<u32>) -> Option<Value> {
+    Some(match action {
+        LinuxSeccompAction::ScmpActAllow => json!("allow"),
+        LinuxSeccompAction::ScmpActErrno => {
+            json!({ "errno": errno_ret.unwrap_or(libc::EPERM as u32) })
+        }
+        LinuxSeccompAction::ScmpActKill
+        | LinuxSeccompAction::ScmpActKillThread => json!("kill_thread"),
+        LinuxSeccompAction::ScmpActKillProcess => json!("kill_process"),
+        LinuxSeccompAction::ScmpActTrap => json!("trap"),
+        LinuxSeccompAction::ScmpActLog => json!("log"),
+        LinuxSeccompAction::ScmpActTrace => {
+            json!({ "trace": errno_ret.unwrap_or(0) })
+        }
+        LinuxSeccompAction::ScmpActNotify => return None,
+    })
+}
+
+/// The seccompiler JSON rules matching an entry of a profile.
+fn rules(syscall: &LinuxSyscall) -> Vec<Value> {
+    let args: Vec<_> = syscall.args().iter().flatten().map(condition).collect();
+
+    syscall
+        .names()
+        .iter()
+        .map(|name| {
+            if args.is_empty() {
+                json!({ "syscall": name })
+            } else {
+                json!({ "syscall": name, "args": args })
+            }
+        })
+        .collect()
+}
+
+fn condition(arg: &LinuxSeccompArg) -> Value {
+    let (op, value) = match arg.op() {
+        LinuxSeccompOperator::ScmpCmpNe => (json!("ne"), arg.value()),
+        LinuxSeccompOperator::ScmpCmpLt => (json!("lt"), arg.value()),
+        LinuxSeccompOperator::ScmpCmpLe => (json!("le"), arg.value()),
+        LinuxSeccompOperator::ScmpCmpEq => (json!("eq"), arg.value()),
+        LinuxSeccompOperator::ScmpCmpGe => (json!("ge"), arg.value()),
+        LinuxSeccompOperator::ScmpCmpGt => (json!("gt"), arg.value()),
+        // The mask is the value, and the value to compare with the masked
+        // argument is the second value.
+        LinuxSeccompOperator::ScmpCmpMaskedEq => {
+            (json!({ "masked_eq": arg.value() }), arg.value_two().unwrap_or(0))
+        }
+    };
+
+    json!({ "index": arg.index(), "type": "qword", "op": op, "val": value })
+}
+
+fn compile_filter(
+    rules: &[Value],
+    mismatch_action: &Value,
+    match_action: &Value,
+    arch: TargetArch,
+) -> seccompiler::Result<BpfProgram> {
+    let json = json!({
+        "filter": {
+            "mismatch_action": mismatch_action,
+            "match_action": match_action,
+            "filter": rules,
+        }
+    });
+
+    let mut filters =
+        seccompiler::compile_from_json(json.to_string().as_bytes(), arch)?;
+    Ok(filters.remove("filter").expect("compiled filter"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(json: &str) -> LinuxSeccomp {
+        serde_json::from_str(json).expect("valid profile")
+    }
+
+    #[test]
+    fn test_builtin_profiles_compile() {
+        let default =
+            SeccompProfile::load(SeccompProfile::DEFAULT).expect("default");
+        assert_eq!(default.filters.len(), 1);
+
+        let strict =
+            SeccompProfile::load(SeccompProfile::STRICT).expect("strict");
+        assert_eq!(strict.filters.len(), 1);
+        assert!(strict.filters[0].len() > default.filters[0].len());
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        assert!(matches!(
+            SeccompProfile::load("relaxed"),
+            Err(SeccompProfileError::UnknownProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_compiles_a_filter_per_action() {
+        let spec = profile(
+            r#"{
+                "defaultAction": "SCMP_ACT_ERRNO",
+                "syscalls": [
+                    { "names": ["read", "write"], "action": "SCMP_ACT_ALLOW" },
+                    { "names": ["mkdirat"], "action": "SCMP_ACT_KILL_PROCESS" },
+                    {
+                        "names": ["personality"],
+                        "action": "SCMP_ACT_ERRNO",
+                        "errnoRet": 38,
+                        "args": [
+                            { "index": 0, "value": 8, "op": "SCMP_CMP_NE" }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+
+        let compiled = SeccompProfile::compile(&spec).expect("compile");
+        // kill_process, errno 38, and the default
+        assert_eq!(compiled.filters.len(), 3);
+    }
+
+    #[test]
+    fn test_errors_name_the_offending_syscall_entry() {
+        let spec = profile(
+            r#"{
+                "defaultAction": "SCMP_ACT_ALLOW",
+                "syscalls": [
+                    { "names": ["mkdirat"], "action": "SCMP_ACT_ERRNO" },
+                    { "names": ["unlinkat", "not_a_syscall"], "action": "SCMP_ACT_ERRNO" }
+                ]
+            }"#,
+        );
+
+        let error = SeccompProfile::compile(&spec).expect_err("invalid name");
+        assert!(matches!(
+            error,
+            SeccompProfileError::InvalidSyscall { index: 1, .. }
+        ));
+        let message = error.to_string();
+        assert!(message.contains("syscalls[1]"), "{message}");
+        assert!(message.contains("not_a_syscall"), "{message}");
+    }
+
+    #[test]
+    fn test_errors_on_unsupported_actions_and_arguments() {
+        let spec = profile(
+            r#"{
+                "defaultAction": "SCMP_ACT_ALLOW",
+                "syscalls": [
+                    { "names": ["mkdirat"], "action": "SCMP_ACT_NOTIFY" }
+                ]
+            }"#,
+        );
+        assert!(matches!(
+            SeccompProfile::compile(&spec),
+            Err(SeccompProfileError::UnsupportedAction { index: 0, .. })
+        ));
+
+        let spec = profile(
+            r#"{
+                "defaultAction": "SCMP_ACT_ALLOW",
+                "syscalls": [
+                    {
+                        "names": ["mkdirat"],
+                        "action": "SCMP_ACT_ERRNO",
+                        "args": [{ "index": 9, "value": 0, "op": "SCMP_CMP_EQ" }]
+                    }
+                ]
+            }"#,
+        );
+        assert!(matches!(
+            SeccompProfile::compile(&spec),
+            Err(SeccompProfileError::InvalidSyscall { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_reports_unparsable_files() {
+        let path = std::env::temp_dir()
+            .join(format!("seccomp-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "{ \"defaultAction\": 42 }").expect("write");
+
+        let result = SeccompProfile::load(path.to_str().expect("utf-8"));
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(
+            result,
+            Err(SeccompProfileError::FailedToParse { .. })
+        ));
+    }
+}
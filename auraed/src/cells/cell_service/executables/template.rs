@@ -0,0 +1,137 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Substitution of `${name}` in the command of an executable, with the
+//! variables of the cell it is started in.
+
+use std::collections::BTreeMap;
+
+/// The name of the cell an executable is started in.
+pub const CELL_NAME_VARIABLE: &str = "cell_name";
+/// The runtime directory of the auraed that started the cell.
+pub const AURAE_RUNTIME_DIR_VARIABLE: &str = "aurae_runtime_dir";
+
+/// Returns true if `name` can be referenced as `${name}`: letters, digits
+/// and underscores, not starting with a digit.
+pub fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replaces every `${name}` in `template` with the value of `name`, and
+/// every `$${` with a literal `${`. Text that is not a reference, such as
+/// `${1}` or `${name:-default}`, is left as is.
+///
+/// Returns the names without a value, in the order they are referenced, if
+/// any.
+pub fn substitute(
+    template: &str,
+    variables: &BTreeMap<String, String>,
+) -> Result<String, Vec<String>> {
+    let mut resolved = String::with_capacity(template.len());
+    let mut unresolved: Vec<String> = vec![];
+    let mut rest = template;
+
+    while let Some(i) = rest.find('$') {
+        resolved.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if let Some(after) = rest.strip_prefix("$${") {
+            resolved.push_str("${");
+            rest = after;
+            continue;
+        }
+
+        let reference = rest
+            .strip_prefix("${")
+            .and_then(|after| after.split_once('}'))
+            .filter(|(name, _)| is_variable_name(name));
+        let Some((name, after)) = reference else {
+            resolved.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+
+        match variables.get(name) {
+            Some(value) => resolved.push_str(value),
+            None if !unresolved.iter().any(|n| n == name) => {
+                unresolved.push(name.to_string());
+            }
+            None => {}
+        }
+        rest = after;
+    }
+    resolved.push_str(rest);
+
+    if unresolved.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(unresolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("cell_name".to_string(), "ae-1".to_string()),
+            ("port".to_string(), "8080".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_substitutes_variables() {
+        assert_eq!(
+            substitute(
+                "serve --name ${cell_name} --port=${port}",
+                &variables()
+            ),
+            Ok("serve --name ae-1 --port=8080".to_string())
+        );
+    }
+
+    #[test]
+    fn test_escaped_references_are_literal() {
+        assert_eq!(
+            substitute("echo $${port} ${port}", &variables()),
+            Ok("echo ${port} 8080".to_string())
+        );
+    }
+
+    #[test]
+    fn test_text_that_is_not_a_reference_is_kept() {
+        let command = "echo $1 $ ${1} ${port:-80} ${port";
+        assert_eq!(substitute(command, &variables()), Ok(command.to_string()));
+    }
+
+    #[test]
+    fn test_lists_unresolved_variables_once() {
+        assert_eq!(
+            substitute("${host}:${port} ${user} ${host}", &variables()),
+            Err(vec!["host".to_string(), "user".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_variable_names() {
+        assert!(is_variable_name("_port2"));
+        assert!(!is_variable_name(""));
+        assert!(!is_variable_name("2port"));
+        assert!(!is_variable_name("port-number"));
+    }
+}
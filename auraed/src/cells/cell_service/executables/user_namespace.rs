@@ -0,0 +1,330 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Starting executables in a user namespace of their own, with the ids in it
+//! mapped to unprivileged ids of the host.
+//!
+//! The process unshares the namespace between fork and exec, and waits there
+//! for auraed to write its maps: directly when auraed runs as root, and with
+//! newuidmap and newgidmap otherwise, as an unprivileged process may only map
+//! the ids it was delegated in /etc/subuid and /etc/subgid.
+
+use super::rootfs::check;
+use std::{
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    process::Command,
+    str::FromStr,
+    thread::JoinHandle,
+};
+
+/// `size` ids starting at `container_id` in the namespace, mapped to those
+/// starting at `host_id` on the host. Written as
+/// `<container_id>:<host_id>:<size>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdMapping {
+    pub container_id: u32,
+    pub host_id: u32,
+    pub size: u32,
+}
+
+impl FromStr for IdMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("invalid id mapping '{s}', expected <container_id>:<host_id>:<size>")
+        };
+
+        let mut parts = s.splitn(3, ':').map(|part| part.parse::<u32>());
+        let (Some(Ok(container_id)), Some(Ok(host_id)), Some(Ok(size))) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+
+        let mapping = Self { container_id, host_id, size };
+        if !mapping.is_valid() {
+            return Err(invalid());
+        }
+
+        Ok(mapping)
+    }
+}
+
+impl Display for IdMapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.container_id, self.host_id, self.size)
+    }
+}
+
+impl IdMapping {
+    /// Maps at least one id, and none past the last id.
+    pub fn is_valid(&self) -> bool {
+        self.size > 0
+            && self.container_id.checked_add(self.size - 1).is_some()
+            && self.host_id.checked_add(self.size - 1).is_some()
+    }
+}
+
+/// The maps of the user namespace executables are started in. Both are
+/// required, as the process can't change its ids otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserNamespace {
+    pub uid_map: Vec<IdMapping>,
+    pub gid_map: Vec<IdMapping>,
+}
+
+impl UserNamespace {
+    /// Opens the pipes the process, once forked, and auraed synchronize
+    /// over, as they can't be allocated once the process is forked.
+    pub fn prepare(
+        &self,
+    ) -> io::Result<(UserNamespaceMapper, EnterUserNamespace)> {
+        let (ready_rx, ready_tx) = pipe()?;
+        let (mapped_rx, mapped_tx) = pipe()?;
+
+        let enter = EnterUserNamespace {
+            ready_tx: ready_tx.as_raw_fd(),
+            mapped_rx: mapped_rx.as_raw_fd(),
+            ours: [ready_rx.as_raw_fd(), mapped_tx.as_raw_fd()],
+        };
+        let mapper = UserNamespaceMapper {
+            user_namespace: self.clone(),
+            ready_rx,
+            mapped_tx,
+            theirs: [ready_tx, mapped_rx],
+        };
+
+        Ok((mapper, enter))
+    }
+}
+
+/// The ends of the pipes of the forked process.
+#[derive(Debug, Clone, Copy)]
+pub struct EnterUserNamespace {
+    ready_tx: RawFd,
+    mapped_rx: RawFd,
+    /// The ends of auraed, which the process closes, so it sees auraed
+    /// closing them if the maps can't be written.
+    ours: [RawFd; 2],
+}
+
+impl EnterUserNamespace {
+    /// Unshares the user namespace of the calling process, and waits for
+    /// auraed to write its maps. Only makes syscalls.
+    pub fn enter(&self) -> io::Result<()> {
+        for fd in self.ours {
+            // SAFETY: the fds are copies the forked process doesn't use
+            let _ = unsafe { libc::close(fd) };
+        }
+
+        // SAFETY: unshare has no memory safety requirements
+        check(unsafe { libc::unshare(libc::CLONE_NEWUSER) })?;
+
+        // SAFETY: getpid has no memory safety requirements
+        let pid = unsafe { libc::getpid() }.to_ne_bytes();
+        // SAFETY: the buffer outlives the call
+        let written = unsafe {
+            libc::write(self.ready_tx, pid.as_ptr().cast(), pid.len())
+        };
+        if written != pid.len() as isize {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut mapped = 0u8;
+        loop {
+            // SAFETY: the buffer outlives the call
+            let read = unsafe {
+                libc::read(self.mapped_rx, (&mut mapped as *mut u8).cast(), 1)
+            };
+            match read {
+                1 => break,
+                // auraed failed to write the maps
+                0 => return Err(io::Error::from_raw_os_error(libc::EPERM)),
+                _ if io::Error::last_os_error().kind()
+                    == io::ErrorKind::Interrupted => {}
+                _ => return Err(io::Error::last_os_error()),
+            }
+        }
+
+        // Drop the groups of the host, where setgroups is not denied, as it
+        // is for maps written by an unprivileged auraed.
+        // SAFETY: an empty list of groups is not read
+        let _ = unsafe { libc::setgroups(0, std::ptr::null()) };
+        Ok(())
+    }
+}
+
+/// The ends of the pipes of auraed.
+#[derive(Debug)]
+pub struct UserNamespaceMapper {
+    user_namespace: UserNamespace,
+    ready_rx: OwnedFd,
+    mapped_tx: OwnedFd,
+    /// The ends of the forked process, closed once it is spawned.
+    theirs: [OwnedFd; 2],
+}
+
+impl UserNamespaceMapper {
+    /// Writes the maps of the process once it has entered its namespace, on
+    /// a thread of its own, as the process is spawned meanwhile.
+    pub fn spawn(self) -> UserNamespaceMapping {
+        let Self { user_namespace, ready_rx, mapped_tx, theirs } = self;
+
+        let thread = std::thread::spawn(move || {
+            let mut pid = [0u8; 4];
+            File::from(ready_rx).read_exact(&mut pid).map_err(|_| {
+                io::Error::other(
+                    "the process exited before entering its user namespace",
+                )
+            })?;
+            let pid = libc::pid_t::from_ne_bytes(pid);
+
+            write_maps(pid, &user_namespace)?;
+            File::from(mapped_tx).write_all(&[1])
+        });
+
+        UserNamespaceMapping { thread, theirs }
+    }
+}
+
+/// The maps being written for a process being spawned.
+#[derive(Debug)]
+pub struct UserNamespaceMapping {
+    thread: JoinHandle<io::Result<()>>,
+    theirs: [OwnedFd; 2],
+}
+
+impl UserNamespaceMapping {
+    /// Returns whether the maps were written, once the process was spawned,
+    /// or failed to spawn.
+    pub fn finish(self) -> io::Result<()> {
+        let Self { thread, theirs } = self;
+
+        // The thread sees the end of the pipe if the process never entered
+        // its namespace
+        drop(theirs);
+        thread
+            .join()
+            .map_err(|_| io::Error::other("user namespace mapper panicked"))?
+    }
+}
+
+fn write_maps(
+    pid: libc::pid_t,
+    user_namespace: &UserNamespace,
+) -> io::Result<()> {
+    let UserNamespace { uid_map, gid_map } = user_namespace;
+
+    // SAFETY: geteuid has no memory safety requirements
+    if unsafe { libc::geteuid() } == 0 {
+        std::fs::write(format!("/proc/{pid}/uid_map"), map_lines(uid_map))?;
+        std::fs::write(format!("/proc/{pid}/gid_map"), map_lines(gid_map))
+    } else {
+        new_id_map("newuidmap", pid, uid_map)?;
+        new_id_map("newgidmap", pid, gid_map)
+    }
+}
+
+/// The lines of a `/proc/<pid>/{uid,gid}_map` file.
+fn map_lines(map: &[IdMapping]) -> String {
+    map.iter()
+        .map(|mapping| {
+            format!(
+                "{} {} {}\n",
+                mapping.container_id, mapping.host_id, mapping.size
+            )
+        })
+        .collect()
+}
+
+/// Writes the map with the setuid `newuidmap` or `newgidmap` of shadow-utils.
+/// It runs while the executable is spawned, so under its
+/// [crate::reaper::spawning], which keeps the reaper off its exit status.
+#[allow(clippy::disallowed_methods)]
+fn new_id_map(
+    program: &str,
+    pid: libc::pid_t,
+    map: &[IdMapping],
+) -> io::Result<()> {
+    let mut command = Command::new(program);
+    let _ = command.arg(pid.to_string());
+    for mapping in map {
+        let _ = command.args([
+            mapping.container_id.to_string(),
+            mapping.host_id.to_string(),
+            mapping.size.to_string(),
+        ]);
+    }
+
+    let output = command.output().map_err(|e| {
+        io::Error::new(e.kind(), format!("failed to run {program}: {e}"))
+    })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{program} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// A pipe, as its read and write ends, which are closed on exec.
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: pipe2 writes two fds to the array
+    check(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) })?;
+
+    // SAFETY: the fds were just opened, and are owned by nothing else
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_mapping_parses_and_displays() {
+        let mapping: IdMapping = "0:100000:65536".parse().unwrap();
+        assert_eq!(
+            mapping,
+            IdMapping { container_id: 0, host_id: 100000, size: 65536 }
+        );
+        assert_eq!(mapping.to_string(), "0:100000:65536");
+    }
+
+    #[test]
+    fn test_invalid_id_mappings_fail_to_parse() {
+        for mapping in
+            ["", "0:100000", "0:100000:0", "a:b:c", "1:4294967295:2", "0:1:2:3"]
+        {
+            assert!(mapping.parse::<IdMapping>().is_err(), "{mapping}");
+        }
+    }
+
+    #[test]
+    fn test_map_lines() {
+        let map = [
+            IdMapping { container_id: 0, host_id: 1000, size: 1 },
+            IdMapping { container_id: 1, host_id: 100000, size: 65535 },
+        ];
+        assert_eq!(map_lines(&map), "0 1000 1\n1 100000 65535\n");
+    }
+}
@@ -0,0 +1,244 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Exports the cells of an auraed as a [CellsDocument], for them to be
+//! imported again, such as on a rebuilt host.
+//!
+//! [CellsDocument]: proto::cells::CellsDocument
+
+use super::cells::CellName;
+use proto::cells::{CellDefinition, ExecutableDefinition};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// The executables started in each cell, as they were requested, before the
+/// variables of the cell were substituted in their commands.
+#[derive(Debug, Default)]
+pub(crate) struct StartedExecutables(
+    HashMap<CellName, Vec<ExecutableDefinition>>,
+);
+
+impl StartedExecutables {
+    /// Records an executable started in a cell, replacing the one of the
+    /// same name started before, which has exited since.
+    pub fn insert(
+        &mut self,
+        cell_name: CellName,
+        definition: ExecutableDefinition,
+    ) {
+        let definitions = self.0.entry(cell_name).or_default();
+        definitions.retain(|x| name(x) != name(&definition));
+        definitions.push(definition);
+    }
+
+    pub fn remove(&mut self, cell_name: &CellName, executable_name: &str) {
+        if let Some(definitions) = self.0.get_mut(cell_name) {
+            definitions.retain(|x| name(x) != executable_name);
+        }
+    }
+
+    /// Forgets the executables of a freed cell, and of its nested cells.
+    pub fn remove_cell(&mut self, cell_name: &CellName) {
+        self.0.retain(|x, _| !is_or_nests_in(x, cell_name));
+    }
+
+    /// The executables started in a cell, in the order they were started.
+    pub fn get(&self, cell_name: &CellName) -> &[ExecutableDefinition] {
+        self.0.get(cell_name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// The name of the executable of `definition`.
+pub(crate) fn name(definition: &ExecutableDefinition) -> &str {
+    definition.executable.as_ref().map(|x| x.name.as_str()).unwrap_or_default()
+}
+
+/// Replaces the values of the variables of the cell of `definition`, and the
+/// commands of its executables, that `redact` changes, listing them as
+/// redacted.
+pub(crate) fn redact(
+    definition: &mut CellDefinition,
+    redact: impl Fn(&str) -> Cow<'_, str>,
+) {
+    if let Some(cell) = &mut definition.cell {
+        let mut names: Vec<_> = cell.variables.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let value = cell.variables.get_mut(&name).expect("variable");
+            if let Cow::Owned(redacted) = redact(value) {
+                *value = redacted;
+                definition.redacted.push(format!("variables.{name}"));
+            }
+        }
+    }
+
+    for executable in
+        definition.executables.iter_mut().filter_map(|x| x.executable.as_mut())
+    {
+        if let Cow::Owned(redacted) = redact(&executable.command) {
+            executable.command = redacted;
+            definition
+                .redacted
+                .push(format!("executables.{}.command", executable.name));
+        }
+    }
+}
+
+/// The cells of `allocated` to free for only those of `document` to be left.
+/// Nested cells are freed with their parent, so only the topmost are
+/// returned. A cell with nested cells in `document` is kept.
+pub(crate) fn pruned(
+    allocated: &[CellName],
+    document: &HashSet<CellName>,
+) -> Vec<CellName> {
+    let is_pruned = |cell_name: &CellName| {
+        !document.iter().any(|kept| is_or_nests_in(kept, cell_name))
+    };
+
+    allocated
+        .iter()
+        .filter(|cell_name| is_pruned(cell_name))
+        .filter(|cell_name| {
+            !allocated.iter().any(|parent| {
+                parent != *cell_name
+                    && is_or_nests_in(cell_name, parent)
+                    && is_pruned(parent)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns true if `cell_name` is `parent`, or is nested in it. Compared as
+/// names, so that `ae-10` is not taken for a cell nested in `ae-1`.
+fn is_or_nests_in(cell_name: &CellName, parent: &CellName) -> bool {
+    let (cell_name, parent) = (cell_name.to_string(), parent.to_string());
+    cell_name == parent || cell_name.starts_with(&format!("{parent}/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::cells::{Cell, Executable};
+
+    fn executable(name: &str, command: &str) -> ExecutableDefinition {
+        ExecutableDefinition {
+            executable: Some(Executable {
+                name: name.into(),
+                command: command.into(),
+                ..Default::default()
+            }),
+            uid: None,
+            gid: None,
+        }
+    }
+
+    fn names(cell_names: &[&str]) -> Vec<CellName> {
+        cell_names.iter().map(|&x| CellName::from(x)).collect()
+    }
+
+    #[test]
+    fn test_started_executables_replace_and_remove() {
+        let mut started = StartedExecutables::default();
+        started.insert("ae-1".into(), executable("web", "web --port 80"));
+        started.insert("ae-1".into(), executable("worker", "worker"));
+        started.insert("ae-1".into(), executable("web", "web --port 8080"));
+        started.insert("ae-1/nested".into(), executable("db", "db"));
+        started.insert("ae-10".into(), executable("cache", "cache"));
+
+        assert_eq!(
+            started.get(&"ae-1".into()),
+            [
+                executable("worker", "worker"),
+                executable("web", "web --port 8080")
+            ]
+        );
+
+        started.remove(&"ae-1".into(), "worker");
+        assert_eq!(
+            started.get(&"ae-1".into()),
+            [executable("web", "web --port 8080")]
+        );
+
+        // Freeing a cell forgets its nested cells, but not its siblings
+        started.remove_cell(&"ae-1".into());
+        assert!(started.get(&"ae-1".into()).is_empty());
+        assert!(started.get(&"ae-1/nested".into()).is_empty());
+        assert_eq!(
+            started.get(&"ae-10".into()),
+            [executable("cache", "cache")]
+        );
+    }
+
+    #[test]
+    fn test_redact_marks_redacted_fields() {
+        let mut definition = CellDefinition {
+            cell: Some(Cell {
+                name: "ae-1".into(),
+                variables: [
+                    ("token".to_string(), "secret-abc".to_string()),
+                    ("port".to_string(), "8080".to_string()),
+                ]
+                .into(),
+                ..Default::default()
+            }),
+            executables: vec![
+                executable("web", "web --token secret-abc --port ${port}"),
+                executable("worker", "worker"),
+            ],
+            redacted: vec![],
+        };
+
+        redact(&mut definition, |value| match value.contains("secret-") {
+            true => Cow::Owned(value.replace("secret-abc", "[REDACTED:token]")),
+            false => Cow::Borrowed(value),
+        });
+
+        let cell = definition.cell.as_ref().unwrap();
+        assert_eq!(cell.variables["token"], "[REDACTED:token]");
+        assert_eq!(cell.variables["port"], "8080");
+        assert_eq!(
+            definition.executables[0].executable.as_ref().unwrap().command,
+            "web --token [REDACTED:token] --port ${port}"
+        );
+        assert_eq!(
+            definition.redacted,
+            ["variables.token", "executables.web.command"]
+        );
+    }
+
+    #[test]
+    fn test_pruned_frees_the_topmost_cells_not_in_the_document() {
+        let allocated =
+            names(&["ae-1", "ae-1/a", "ae-1/b", "ae-2", "ae-2/a", "ae-3"]);
+        let document =
+            names(&["ae-1", "ae-1/a", "ae-3/a"]).into_iter().collect();
+
+        // ae-2/a is freed with ae-2, and ae-3 is kept for its nested cell
+        assert_eq!(pruned(&allocated, &document), names(&["ae-1/b", "ae-2"]));
+    }
+
+    #[test]
+    fn test_pruned_does_not_take_a_prefix_for_a_parent() {
+        let allocated = names(&["ae-1", "ae-10", "ae-10/a"]);
+
+        // ae-1 is not kept for ae-10/a, nor is ae-10 freed with ae-1
+        let document = names(&["ae-10/a"]).into_iter().collect();
+        assert_eq!(pruned(&allocated, &document), names(&["ae-1"]));
+
+        let document = names(&["ae-1"]).into_iter().collect();
+        assert_eq!(pruned(&allocated, &document), names(&["ae-10"]));
+    }
+}
@@ -12,12 +12,27 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-pub use cell_service::CellService;
+pub use cell_service::{CellService, CellSockets};
+pub use cells::cgroups::{
+    cpuset::{Cpus, Mems},
+    CgroupMode, Limit,
+};
+pub use cells::{
+    bring_up_loopback, CellName, DEFAULT_LIFETIME_STATS_INTERVAL,
+};
 use error::Result;
+pub use executables::{ExecutableName, IdMapping, UserNamespace};
+pub use workload::Workload;
 
 #[allow(clippy::module_inception)]
 mod cell_service;
 mod cells;
+mod copy;
 mod error;
 mod executables;
-mod validation;
\ No newline at end of file
+mod export;
+mod net_check;
+mod state;
+mod usage;
+mod validation;
+mod workload;
\ No newline at end of file
@@ -0,0 +1,31 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, NetCheckError>;
+
+#[derive(Error, Debug)]
+pub enum NetCheckError {
+    #[error("failed to resolve '{target_address}': {source}")]
+    Resolve { target_address: String, source: io::Error },
+    #[error("'{target_address}' did not resolve to any address")]
+    NoAddresses { target_address: String },
+    #[error("'{server_name}' is not a valid TLS server name")]
+    InvalidServerName { server_name: String },
+    #[error("too many concurrent network checks, try again later")]
+    RateLimited,
+}
@@ -0,0 +1,298 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{tls::TLS_CONNECTOR, NetCheckError, Result, TargetAddress};
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::Instant;
+use tokio_rustls::rustls::pki_types::ServerName;
+
+/// Payload of UDP probes.
+const UDP_PROBE_PAYLOAD: &[u8] = b"auraed netcheck\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetCheckProtocol {
+    Tcp,
+    Udp,
+    Tls,
+}
+
+/// A connectivity check against a target address.
+#[derive(Debug, Clone)]
+pub struct NetCheck {
+    pub target_address: TargetAddress,
+    pub protocol: NetCheckProtocol,
+    /// Timeout of the name resolution, and of each attempt.
+    pub timeout: Duration,
+    pub count: u32,
+    /// Defaults to the host of the target address.
+    pub tls_server_name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct NetCheckAttempt {
+    pub latency: Duration,
+    /// [None] if the attempt succeeded.
+    pub error: Option<io::Error>,
+}
+
+#[derive(Debug)]
+pub struct NetCheckReport {
+    pub resolved_addresses: Vec<SocketAddr>,
+    pub attempts: Vec<NetCheckAttempt>,
+}
+
+/// The probe to run, with everything it needs resolved ahead of the
+/// attempts.
+enum Probe {
+    Tcp,
+    Udp,
+    Tls(ServerName<'static>),
+}
+
+/// Resolves the target address, and probes the first resolved address
+/// `count` times in sequence. Failed attempts are reported, not returned as
+/// an error.
+pub async fn net_check(check: NetCheck) -> Result<NetCheckReport> {
+    let NetCheck { target_address, protocol, timeout, count, tls_server_name } =
+        check;
+
+    let probe = match protocol {
+        NetCheckProtocol::Tcp => Probe::Tcp,
+        NetCheckProtocol::Udp => Probe::Udp,
+        NetCheckProtocol::Tls => {
+            let server_name = tls_server_name
+                .unwrap_or_else(|| target_address.host().to_string());
+            let server_name = ServerName::try_from(server_name.clone())
+                .map_err(|_| NetCheckError::InvalidServerName {
+                    server_name,
+                })?;
+            Probe::Tls(server_name)
+        }
+    };
+
+    let resolved_addresses =
+        resolve(&target_address, timeout).await.map_err(|source| {
+            NetCheckError::Resolve {
+                target_address: target_address.to_string(),
+                source,
+            }
+        })?;
+
+    let Some(&address) = resolved_addresses.first() else {
+        return Err(NetCheckError::NoAddresses {
+            target_address: target_address.to_string(),
+        });
+    };
+
+    let mut attempts = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        attempts.push(attempt(address, &probe, timeout).await);
+    }
+
+    Ok(NetCheckReport { resolved_addresses, attempts })
+}
+
+/// Resolves the target address the same way the workloads of the cell do,
+/// as auraed shares the cell's mount and network namespaces.
+async fn resolve(
+    target_address: &TargetAddress,
+    timeout: Duration,
+) -> io::Result<Vec<SocketAddr>> {
+    let lookup =
+        tokio::net::lookup_host((target_address.host(), target_address.port()));
+
+    let addresses = tokio::time::timeout(timeout, lookup)
+        .await
+        .map_err(|_| timed_out(timeout))??;
+
+    Ok(addresses.collect())
+}
+
+async fn attempt(
+    address: SocketAddr,
+    probe: &Probe,
+    timeout: Duration,
+) -> NetCheckAttempt {
+    let start = Instant::now();
+
+    let result = tokio::time::timeout(timeout, run_probe(address, probe))
+        .await
+        .unwrap_or_else(|_| Err(timed_out(timeout)));
+
+    NetCheckAttempt { latency: start.elapsed(), error: result.err() }
+}
+
+async fn run_probe(address: SocketAddr, probe: &Probe) -> io::Result<()> {
+    match probe {
+        Probe::Tcp => {
+            let _stream = TcpStream::connect(address).await?;
+        }
+        Probe::Udp => {
+            let local_address: SocketAddr = match address {
+                SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+            };
+
+            // A connected socket receives the ICMP errors of the target,
+            // e.g., port unreachable as ECONNREFUSED.
+            let socket = UdpSocket::bind(local_address).await?;
+            socket.connect(address).await?;
+            let _ = socket.send(UDP_PROBE_PAYLOAD).await?;
+
+            let mut response = [0; 512];
+            let _ = socket.recv(&mut response).await?;
+        }
+        Probe::Tls(server_name) => {
+            let stream = TcpStream::connect(address).await?;
+            let _stream =
+                TLS_CONNECTOR.connect(server_name.clone(), stream).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn timed_out(timeout: Duration) -> io::Error {
+    io::Error::new(ErrorKind::TimedOut, format!("timed out after {timeout:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use validation::ValidatedField;
+
+    fn net_check_to(
+        target_address: SocketAddr,
+        protocol: NetCheckProtocol,
+    ) -> NetCheck {
+        NetCheck {
+            target_address: TargetAddress::validate(
+                Some(target_address.to_string()),
+                "target_address",
+                None,
+            )
+            .unwrap(),
+            protocol,
+            timeout: Duration::from_secs(1),
+            count: 2,
+            tls_server_name: None,
+        }
+    }
+
+    /// Returns an address on which nothing listens.
+    async fn closed_address() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tcp_succeeds_against_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let report = net_check(net_check_to(address, NetCheckProtocol::Tcp))
+            .await
+            .unwrap();
+
+        assert_eq!(report.resolved_addresses, vec![address]);
+        assert_eq!(report.attempts.len(), 2);
+        assert!(report.attempts.iter().all(|a| a.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_reports_syscall_error() {
+        let address = closed_address().await;
+
+        let report = net_check(net_check_to(address, NetCheckProtocol::Tcp))
+            .await
+            .unwrap();
+
+        for attempt in report.attempts {
+            let error = attempt.error.expect("attempt must fail");
+            assert_eq!(error.raw_os_error(), Some(libc::ECONNREFUSED));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_udp_succeeds_against_echo_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+
+        let _echo = tokio::spawn(async move {
+            let mut buf = [0; 512];
+            loop {
+                let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+                let _ = server.send_to(&buf[..len], peer).await.unwrap();
+            }
+        });
+
+        let report = net_check(net_check_to(address, NetCheckProtocol::Udp))
+            .await
+            .unwrap();
+
+        assert!(report.attempts.iter().all(|a| a.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_udp_times_out_without_response() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+
+        let mut check = net_check_to(address, NetCheckProtocol::Udp);
+        check.timeout = Duration::from_millis(50);
+        check.count = 1;
+
+        let report = net_check(check).await.unwrap();
+
+        let error = report.attempts[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_tls_fails_against_plain_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let _server = tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let _ = stream.write_all(b"not tls\r\n\r\n").await;
+            }
+        });
+
+        let report = net_check(net_check_to(address, NetCheckProtocol::Tls))
+            .await
+            .unwrap();
+
+        assert!(report.attempts.iter().all(|a| a.error.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tls_server_name_is_error() {
+        let mut check =
+            net_check_to(closed_address().await, NetCheckProtocol::Tls);
+        check.tls_server_name = Some("not a name".to_string());
+
+        assert!(matches!(
+            net_check(check).await,
+            Err(NetCheckError::InvalidServerName { .. })
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use std::fmt::{Display, Formatter};
+use std::net::Ipv6Addr;
+use validation::{ValidatedField, ValidationError};
+
+/// A `host:port` address, where host is an IP address, a bracketed IPv6
+/// address (e.g., `[::1]:443`), or a hostname.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TargetAddress {
+    host: String,
+    port: u16,
+}
+
+impl TargetAddress {
+    /// The host, without brackets if it is an IPv6 address.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl ValidatedField<String> for TargetAddress {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        let invalid = || ValidationError::Invalid {
+            field: validation::field_name(field_name, parent_name),
+        };
+
+        let (host, port) = input.rsplit_once(':').ok_or_else(invalid)?;
+        let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+        let host = match host.strip_prefix('[') {
+            Some(host) => {
+                let host = host.strip_suffix(']').ok_or_else(invalid)?;
+                let _ = host.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+                host
+            }
+            None if host.is_empty() || host.contains(':') => {
+                return Err(invalid())
+            }
+            None => host,
+        };
+
+        Ok(Self { host: host.to_string(), port })
+    }
+}
+
+impl Display for TargetAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(address: &str) -> Result<TargetAddress, ValidationError> {
+        TargetAddress::validate(Some(address.to_string()), "address", None)
+    }
+
+    #[test]
+    fn test_host_and_port_are_valid() {
+        let address = validate("db.internal:5432").unwrap();
+        assert_eq!(address.host(), "db.internal");
+        assert_eq!(address.port(), 5432);
+
+        let address = validate("10.0.0.5:5432").unwrap();
+        assert_eq!(address.host(), "10.0.0.5");
+        assert_eq!(address.to_string(), "10.0.0.5:5432");
+    }
+
+    #[test]
+    fn test_bracketed_ipv6_is_valid() {
+        let address = validate("[::1]:443").unwrap();
+        assert_eq!(address.host(), "::1");
+        assert_eq!(address.to_string(), "[::1]:443");
+    }
+
+    #[test]
+    fn test_missing_or_invalid_port_is_invalid() {
+        assert!(validate("10.0.0.5").is_err());
+        assert!(validate("10.0.0.5:").is_err());
+        assert!(validate("10.0.0.5:65536").is_err());
+        assert!(validate(":5432").is_err());
+    }
+
+    #[test]
+    fn test_unbracketed_ipv6_is_invalid() {
+        assert!(validate("::1:443").is_err());
+        assert!(validate("[db.internal]:443").is_err());
+    }
+}
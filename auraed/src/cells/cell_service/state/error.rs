@@ -0,0 +1,29 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, StateError>;
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("failed to read state file '{path}': {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("failed to write state file '{path}': {source}")]
+    Write { path: PathBuf, source: io::Error },
+    #[error("state file '{path}' is corrupt: {source}")]
+    Corrupt { path: PathBuf, source: serde_json::Error },
+}
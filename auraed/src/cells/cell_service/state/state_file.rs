@@ -0,0 +1,185 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{Result, StateError};
+use serde::{Deserialize, Serialize};
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Everything needed to adopt the cells and executables of an auraed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellServiceState {
+    /// Parents are listed before their nested cells.
+    pub cells: Vec<CellRecord>,
+    pub executables: Vec<ExecutableRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellRecord {
+    /// The spec of the cell, as it was allocated. The cgroup of the cell
+    /// is derived from its name.
+    pub cell: proto::cells::Cell,
+    /// Host pid of the cell's nested auraed.
+    pub nested_auraed_pid: i32,
+    /// When the nested auraed started, in clock ticks since boot, which
+    /// tells it apart from a later process reusing its pid. Absent from the
+    /// files of earlier auraeds.
+    #[serde(default)]
+    pub nested_auraed_start_time: Option<u64>,
+    /// Socket the cell's nested auraed listens on.
+    pub client_socket: PathBuf,
+    /// The executables started in the cell, as they were requested, to be
+    /// exported. Absent from the files of earlier auraeds.
+    #[serde(default)]
+    pub executables: Vec<proto::cells::ExecutableDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutableRecord {
+    pub name: String,
+    pub description: String,
+    /// The program and arguments the executable was started with.
+    pub command: Vec<String>,
+    /// [None] if the executable is no longer running.
+    pub pid: Option<i32>,
+    /// When the process started, in clock ticks since boot. Absent from the
+    /// files of earlier auraeds, whose processes are not adopted.
+    #[serde(default)]
+    pub start_time: Option<u64>,
+}
+
+/// A JSON file holding a [CellServiceState].
+///
+/// The file is replaced atomically on save, so a crash while saving leaves
+/// the previous state in place.
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Returns [None] if the file does not exist.
+    pub async fn load(&self) -> Result<Option<CellServiceState>> {
+        let contents = match tokio::fs::read(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(StateError::Read {
+                    path: self.path.clone(),
+                    source,
+                })
+            }
+        };
+
+        serde_json::from_slice(&contents).map(Some).map_err(|source| {
+            StateError::Corrupt { path: self.path.clone(), source }
+        })
+    }
+
+    pub async fn save(&self, state: &CellServiceState) -> Result<()> {
+        let contents =
+            serde_json::to_vec_pretty(state).expect("state serializes to json");
+
+        let tmp_path = self.path.with_extension("json.tmp");
+
+        let write_error =
+            |source| StateError::Write { path: self.path.clone(), source };
+
+        tokio::fs::write(&tmp_path, contents).await.map_err(write_error)?;
+        tokio::fs::rename(&tmp_path, &self.path).await.map_err(write_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_file() -> (StateFile, PathBuf) {
+        let dir = std::env::temp_dir()
+            .join(format!("auraed-state-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (StateFile::new(dir.join("cell_service.json")), dir)
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_file_is_none() {
+        let (state_file, dir) = state_file();
+
+        assert_eq!(state_file.load().await.unwrap(), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_round_trip() {
+        let (state_file, dir) = state_file();
+
+        let state = CellServiceState {
+            cells: vec![CellRecord {
+                cell: proto::cells::Cell {
+                    name: "ae-1".into(),
+                    isolate_network: true,
+                    ..Default::default()
+                },
+                nested_auraed_pid: 42,
+                nested_auraed_start_time: Some(4200),
+                client_socket: PathBuf::from("/var/run/aurae/aurae-1.sock"),
+                executables: vec![proto::cells::ExecutableDefinition {
+                    executable: Some(proto::cells::Executable {
+                        name: "sleeper".into(),
+                        command: "sleep ${duration}".into(),
+                        ..Default::default()
+                    }),
+                    uid: Some(1000),
+                    gid: None,
+                }],
+            }],
+            executables: vec![ExecutableRecord {
+                name: "sleeper".into(),
+                description: String::new(),
+                command: vec!["sh".into(), "-c".into(), "sleep 10".into()],
+                pid: Some(43),
+                start_time: Some(4300),
+            }],
+        };
+
+        state_file.save(&state).await.unwrap();
+        assert_eq!(state_file.load().await.unwrap(), Some(state));
+
+        state_file.save(&CellServiceState::default()).await.unwrap();
+        assert_eq!(
+            state_file.load().await.unwrap(),
+            Some(CellServiceState::default())
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_corrupt_file_is_error() {
+        let (state_file, dir) = state_file();
+        std::fs::write(&state_file.path, b"{ not json").unwrap();
+
+        assert!(matches!(
+            state_file.load().await,
+            Err(StateError::Corrupt { .. })
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,569 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Samples the resource usage of cells and executables on an interval. The
+//! watches of a source at the same interval share its samples, so that the
+//! source is read once per interval however many watches there are.
+
+use super::cells::cgroups::CgroupStats;
+use super::cells::CellName;
+use super::executables::ExecutableName;
+use crate::blocking::{self, BlockingJob, Pool};
+use procfs::process::Process;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+
+/// The shortest interval between samples, which intervals are rounded up to
+/// a multiple of.
+pub const MIN_SAMPLE_INTERVAL_MS: u32 = 100;
+
+/// The interval between samples if the watch does not choose one.
+pub const DEFAULT_SAMPLE_INTERVAL_MS: u32 = 1000;
+
+/// The number of intervals a slow watch can fall behind before it misses
+/// samples.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Returns the interval between samples for `interval_ms`, rounded up to a
+/// multiple of [MIN_SAMPLE_INTERVAL_MS], so that more watches share it.
+pub fn sample_interval(interval_ms: u32) -> Duration {
+    let interval_ms = if interval_ms == 0 {
+        DEFAULT_SAMPLE_INTERVAL_MS
+    } else {
+        interval_ms.div_ceil(MIN_SAMPLE_INTERVAL_MS) * MIN_SAMPLE_INTERVAL_MS
+    };
+    Duration::from_millis(interval_ms.into())
+}
+
+/// What samples are read from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UsageSource {
+    /// The cgroup of a cell, at the path its stats are read from.
+    Cell { cell_name: CellName, path: PathBuf },
+    /// The process of an executable of this auraed, and the cgroup it is
+    /// accounted in, if its cell has per executable accounting.
+    Executable {
+        executable_name: ExecutableName,
+        pid: i32,
+        cgroup: Option<PathBuf>,
+    },
+}
+
+/// A sample of the resource usage of a source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageSample {
+    pub source: UsageSource,
+    pub timestamp_ms: i64,
+    /// The CPU time consumed since the previous sample, unset in the first.
+    pub cpu_usage_delta_usec: Option<u64>,
+    pub memory_current: Option<u64>,
+    pub rss: Option<u64>,
+    /// The source is gone, and no more samples of it follow.
+    pub gone: bool,
+}
+
+impl UsageSample {
+    fn gone(source: UsageSource, timestamp_ms: i64) -> Self {
+        Self {
+            source,
+            timestamp_ms,
+            cpu_usage_delta_usec: None,
+            memory_current: None,
+            rss: None,
+            gone: true,
+        }
+    }
+}
+
+/// The values read from a source, to compute its sample from.
+#[derive(Debug, Default)]
+struct Reading {
+    cpu_usec: Option<u64>,
+    memory_current: Option<u64>,
+    rss: Option<u64>,
+    /// When the process of an executable started, to tell it from a later
+    /// process that reuses its pid.
+    start_time: Option<u64>,
+}
+
+impl UsageSource {
+    /// Reads the source, or returns [None] if it is gone.
+    fn read(&self) -> Option<Reading> {
+        match self {
+            UsageSource::Cell { path, .. } => {
+                if !path.is_dir() {
+                    return None;
+                }
+                // A cell freed while it is read is gone on the next sample
+                let stats = CgroupStats::read(path).unwrap_or_default();
+                Some(Reading {
+                    cpu_usec: stats.cpu.usage_usec,
+                    memory_current: stats.memory.current,
+                    ..Default::default()
+                })
+            }
+            UsageSource::Executable { pid, cgroup, .. } => {
+                let process = Process::new(*pid).ok()?;
+                let stat = process.stat().ok()?;
+                if stat.state == 'Z' {
+                    return None;
+                }
+
+                let ticks = stat.utime + stat.stime;
+                let mut reading = Reading {
+                    cpu_usec: Some(
+                        ticks * 1_000_000 / procfs::ticks_per_second(),
+                    ),
+                    rss: process
+                        .statm()
+                        .ok()
+                        .map(|statm| statm.resident * procfs::page_size()),
+                    start_time: Some(stat.starttime),
+                    ..Default::default()
+                };
+
+                // The cgroup accounts for the processes it started as well
+                if let Some(cgroup) = cgroup {
+                    let stats = CgroupStats::read(cgroup).unwrap_or_default();
+                    reading.cpu_usec = stats.cpu.usage_usec;
+                    reading.memory_current = stats.memory.current;
+                }
+                Some(reading)
+            }
+        }
+    }
+}
+
+/// Shares the sampling of sources between the watches of them.
+#[derive(Debug, Clone, Default)]
+pub struct UsageSampler(Arc<Mutex<HashMap<Duration, Sampling>>>);
+
+/// The sampling of the sources watched at an interval.
+#[derive(Debug)]
+struct Sampling {
+    /// The number of watches of each source.
+    watches: HashMap<UsageSource, usize>,
+    /// The sources found gone, which are not read anymore.
+    gone: HashSet<UsageSource>,
+    /// The samples of every source read at a tick.
+    tx: broadcast::Sender<Arc<[UsageSample]>>,
+}
+
+impl UsageSampler {
+    /// Watches `sources`, sampled every `interval`, which starts being
+    /// sampled if no other watch uses it yet.
+    pub fn watch(
+        &self,
+        interval: Duration,
+        sources: impl IntoIterator<Item = UsageSource>,
+    ) -> UsageWatch {
+        let mut samplings = self.0.lock().expect("usage sampler lock");
+        let sampling = samplings.entry(interval).or_insert_with(|| {
+            let _ignored = tokio::spawn(sample(self.clone(), interval));
+            Sampling {
+                watches: HashMap::new(),
+                gone: HashSet::new(),
+                tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            }
+        });
+
+        let sources: HashSet<_> = sources.into_iter().collect();
+        for source in &sources {
+            *sampling.watches.entry(source.clone()).or_default() += 1;
+            // Read again, in case it is back, e.g. a cell allocated again
+            let _ = sampling.gone.remove(source);
+        }
+
+        UsageWatch {
+            sampler: self.clone(),
+            interval,
+            sources,
+            rx: sampling.tx.subscribe(),
+        }
+    }
+
+    /// Releases a watch of `source`, which is not read anymore once no
+    /// watch is left.
+    fn release(&self, interval: Duration, source: &UsageSource) {
+        let mut samplings = self.0.lock().expect("usage sampler lock");
+        let Some(sampling) = samplings.get_mut(&interval) else {
+            return;
+        };
+        let Some(watches) = sampling.watches.get_mut(source) else {
+            return;
+        };
+
+        *watches -= 1;
+        if *watches == 0 {
+            let _ = sampling.watches.remove(source);
+            let _ = sampling.gone.remove(source);
+        }
+    }
+
+    /// Returns a last sample of each of `sources` found gone, for a watch
+    /// that fell behind and missed them.
+    fn gone_among(
+        &self,
+        interval: Duration,
+        sources: &HashSet<UsageSource>,
+    ) -> Vec<UsageSample> {
+        let samplings = self.0.lock().expect("usage sampler lock");
+        let Some(sampling) = samplings.get(&interval) else {
+            return vec![];
+        };
+
+        let timestamp_ms = timestamp_ms();
+        sources
+            .intersection(&sampling.gone)
+            .map(|source| UsageSample::gone(source.clone(), timestamp_ms))
+            .collect()
+    }
+}
+
+/// Reads a batch of sources, off the async runtime.
+struct ReadUsage {
+    sources: Vec<UsageSource>,
+}
+
+impl BlockingJob for ReadUsage {
+    type Output = Vec<(UsageSource, Option<Reading>)>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        self.sources
+            .into_iter()
+            .map(|source| {
+                let reading = source.read();
+                (source, reading)
+            })
+            .collect()
+    }
+}
+
+/// Samples the sources watched at `interval`, until none are. The sources
+/// are read together on a blocking thread, and their samples are broadcast
+/// without waiting on the watches, so that neither the number of watches
+/// nor a slow one delays the next tick.
+async fn sample(sampler: UsageSampler, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    // A late tick is skipped, rather than bunched up with the next ones
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut previous: HashMap<UsageSource, Reading> = HashMap::new();
+
+    loop {
+        let _ = ticks.tick().await;
+
+        let (sources, tx) = {
+            let mut samplings = sampler.0.lock().expect("usage sampler lock");
+            let Some(sampling) = samplings.get(&interval) else {
+                return;
+            };
+            if sampling.watches.is_empty() {
+                let _ = samplings.remove(&interval);
+                return;
+            }
+
+            let sources: Vec<_> = sampling
+                .watches
+                .keys()
+                .filter(|source| !sampling.gone.contains(source))
+                .cloned()
+                .collect();
+            (sources, sampling.tx.clone())
+        };
+
+        let Ok(readings) = blocking::run(ReadUsage { sources }).await else {
+            continue;
+        };
+
+        let timestamp_ms = timestamp_ms();
+        let mut gone = vec![];
+        let samples: Arc<[UsageSample]> = readings
+            .into_iter()
+            .map(|(source, reading)| {
+                // Only the sources still read are kept
+                let last = previous.remove(&source);
+                // A process that reuses the pid of an executable is not it
+                let reading = reading.filter(|reading| {
+                    last.as_ref().is_none_or(|last| {
+                        last.start_time == reading.start_time
+                    })
+                });
+                let Some(reading) = reading else {
+                    gone.push(source.clone());
+                    return UsageSample::gone(source, timestamp_ms);
+                };
+
+                let cpu_usage_delta_usec = match (
+                    last.and_then(|last| last.cpu_usec),
+                    reading.cpu_usec,
+                ) {
+                    (Some(last), Some(current)) => {
+                        Some(current.saturating_sub(last))
+                    }
+                    _ => None,
+                };
+                let sample = UsageSample {
+                    source: source.clone(),
+                    timestamp_ms,
+                    cpu_usage_delta_usec,
+                    memory_current: reading.memory_current,
+                    rss: reading.rss,
+                    gone: false,
+                };
+                let _ = previous.insert(source, reading);
+                sample
+            })
+            .collect();
+
+        if !gone.is_empty() {
+            let mut samplings = sampler.0.lock().expect("usage sampler lock");
+            if let Some(sampling) = samplings.get_mut(&interval) {
+                sampling.gone.extend(gone);
+            }
+        }
+
+        // send returns an Err if there are no receivers. We ignore that.
+        let _ = tx.send(samples);
+    }
+}
+
+/// A watch of sources, sampled at an interval. Dropping it stops watching
+/// the sources that are not gone yet.
+#[derive(Debug)]
+pub struct UsageWatch {
+    sampler: UsageSampler,
+    interval: Duration,
+    /// The sources watched, until they are gone.
+    sources: HashSet<UsageSource>,
+    rx: broadcast::Receiver<Arc<[UsageSample]>>,
+}
+
+impl UsageWatch {
+    /// Waits for the next samples of the sources watched, which includes a
+    /// last sample of those that are gone. Returns [None] once all of them
+    /// are gone.
+    pub async fn next(&mut self) -> Option<Vec<UsageSample>> {
+        while !self.sources.is_empty() {
+            let samples: Vec<_> = match self.rx.recv().await {
+                Ok(samples) => samples
+                    .iter()
+                    .filter(|sample| self.sources.contains(&sample.source))
+                    .cloned()
+                    .collect(),
+                // The samples missed are not reported, but the sources
+                // found gone meanwhile still are
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    self.sampler.gone_among(self.interval, &self.sources)
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+
+            for sample in samples.iter().filter(|sample| sample.gone) {
+                if self.sources.remove(&sample.source) {
+                    self.sampler.release(self.interval, &sample.source);
+                }
+            }
+
+            if !samples.is_empty() {
+                return Some(samples);
+            }
+        }
+
+        None
+    }
+}
+
+impl Drop for UsageWatch {
+    fn drop(&mut self) {
+        for source in &self.sources {
+            self.sampler.release(self.interval, source);
+        }
+    }
+}
+
+fn timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+// The tests don't reap orphans, so they may spawn children of their own
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tokio::time::timeout;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn cell_source(path: PathBuf) -> UsageSource {
+        UsageSource::Cell { cell_name: CellName::random_for_tests(), path }
+    }
+
+    fn temp_dir() -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("aurae-usage-{}", CellName::random_for_tests()));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        path
+    }
+
+    async fn next(watch: &mut UsageWatch) -> Option<Vec<UsageSample>> {
+        timeout(Duration::from_secs(5), watch.next())
+            .await
+            .expect("samples in time")
+    }
+
+    #[test]
+    fn test_sample_interval_is_rounded_up() {
+        assert_eq!(sample_interval(0), Duration::from_millis(1000));
+        assert_eq!(sample_interval(1), Duration::from_millis(100));
+        assert_eq!(sample_interval(100), Duration::from_millis(100));
+        assert_eq!(sample_interval(250), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_gone_cell_is_tombstoned_once() {
+        let path = temp_dir();
+        let source = cell_source(path.clone());
+        let sampler = UsageSampler::default();
+        let mut watch = sampler.watch(INTERVAL, [source.clone()]);
+
+        let samples = next(&mut watch).await.expect("samples");
+        assert_eq!(samples.len(), 1);
+        assert!(!samples[0].gone);
+
+        std::fs::remove_dir(&path).expect("remove temp dir");
+        let samples = loop {
+            let samples = next(&mut watch).await.expect("samples");
+            if samples.iter().any(|sample| sample.gone) {
+                break samples;
+            }
+        };
+        assert_eq!(
+            samples,
+            vec![UsageSample::gone(source, samples[0].timestamp_ms)]
+        );
+
+        // The watch ends once its sources are gone
+        assert_eq!(next(&mut watch).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_watches_share_sampling() {
+        let path = temp_dir();
+        let source = cell_source(path.clone());
+        let sampler = UsageSampler::default();
+        let mut first = sampler.watch(INTERVAL, [source.clone()]);
+        let mut second = sampler.watch(INTERVAL, [source.clone()]);
+
+        {
+            let samplings = sampler.0.lock().expect("usage sampler lock");
+            assert_eq!(samplings.len(), 1);
+            assert_eq!(samplings[&INTERVAL].watches[&source], 2);
+        }
+
+        let first_samples = next(&mut first).await.expect("samples");
+        let second_samples = next(&mut second).await.expect("samples");
+        assert_eq!(first_samples, second_samples);
+
+        drop(first);
+        {
+            let samplings = sampler.0.lock().expect("usage sampler lock");
+            assert_eq!(samplings[&INTERVAL].watches[&source], 1);
+        }
+
+        drop(second);
+        std::fs::remove_dir(&path).expect("remove temp dir");
+        // The sampling stops once it has no watches left
+        timeout(Duration::from_secs(5), async {
+            while !sampler.0.lock().expect("usage sampler lock").is_empty() {
+                tokio::time::sleep(INTERVAL).await;
+            }
+        })
+        .await
+        .expect("sampling stopped");
+    }
+
+    #[tokio::test]
+    async fn test_executable_is_sampled_until_it_exits() {
+        let mut child =
+            Command::new("sleep").arg("1").spawn().expect("spawn sleep");
+        let source = UsageSource::Executable {
+            executable_name: ExecutableName::new("sleep".to_string()),
+            pid: child.id() as i32,
+            cgroup: None,
+        };
+        let sampler = UsageSampler::default();
+        let mut watch = sampler.watch(INTERVAL, [source.clone()]);
+
+        let samples = next(&mut watch).await.expect("samples");
+        assert!(!samples[0].gone);
+        assert!(samples[0].rss.is_some_and(|rss| rss > 0));
+        assert_eq!(samples[0].cpu_usage_delta_usec, None);
+
+        let samples = next(&mut watch).await.expect("samples");
+        assert!(samples[0].cpu_usage_delta_usec.is_some());
+
+        let _ = child.wait().expect("wait sleep");
+        let gone = loop {
+            let samples = next(&mut watch).await.expect("samples");
+            if samples[0].gone {
+                break samples;
+            }
+        };
+        assert_eq!(gone[0].source, source);
+        assert_eq!(next(&mut watch).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_executable_with_a_cgroup_is_read_from_it() {
+        let path = temp_dir();
+        std::fs::write(path.join("cpu.stat"), "usage_usec 1000\n").unwrap();
+        std::fs::write(path.join("memory.current"), "4096\n").unwrap();
+        let mut child =
+            Command::new("sleep").arg("10").spawn().expect("spawn sleep");
+        let source = UsageSource::Executable {
+            executable_name: ExecutableName::new("sleep".to_string()),
+            pid: child.id() as i32,
+            cgroup: Some(path.clone()),
+        };
+        let sampler = UsageSampler::default();
+        let mut watch = sampler.watch(INTERVAL, [source]);
+
+        let _ = next(&mut watch).await.expect("samples");
+        std::fs::write(path.join("cpu.stat"), "usage_usec 3000\n").unwrap();
+        // A sample may have been read before the write
+        let samples = loop {
+            let samples = next(&mut watch).await.expect("samples");
+            if samples[0].cpu_usage_delta_usec != Some(0) {
+                break samples;
+            }
+        };
+        assert_eq!(samples[0].cpu_usage_delta_usec, Some(2000));
+        assert_eq!(samples[0].memory_current, Some(4096));
+        assert!(samples[0].rss.is_some());
+
+        child.kill().expect("kill sleep");
+        let _ = child.wait().expect("wait sleep");
+    }
+}
@@ -16,20 +16,42 @@ use super::cells::{
     cgroups::{
         self,
         cpuset::{Cpus, Mems},
-        CgroupSpec, Limit, Protection, Weight,
+        CgroupSpec, DeviceAccess, DeviceType, Limit, Protection, Weight,
     },
-    IsolationControls,
+    CellSpec, Hostname, IsolationControls, DEFAULT_FREE_TIMEOUT,
 };
-use super::executables::ExecutableName;
+use super::copy::{CopyDestination, CopyPath};
+use super::executables::{
+    is_variable_name, CapabilitiesSpec, CapabilitySet, ExecutableName,
+    IdMapping, KillMode, MountSpec, ReadinessCheck, ReadinessProbe,
+    SeccompProfile, UserNamespace, AURAE_RUNTIME_DIR_VARIABLE,
+    CELL_NAME_VARIABLE, DEFAULT_PROBE_PERIOD, DEFAULT_PROBE_TIMEOUT,
+    DEFAULT_READY_AFTER,
+};
+use super::net_check::{NetCheck, NetCheckProtocol, TargetAddress};
+use super::usage;
 use crate::cells::cell_service::cells::CellName;
+use crate::logging::output_limit::OutputLimit;
 use proto::cells::{
-    Cell, CellServiceAllocateRequest, CellServiceFreeRequest,
-    CellServiceStartRequest, CellServiceStopRequest, CpuController,
-    CpusetController, Executable, MemoryController,
+    mount, BindMount, Capabilities, Cell, CellServiceAllocateRequest,
+    CellServiceCopyFromRequest, CellServiceFreeRequest,
+    CellServiceImportRequest, CellServiceListExecutablesRequest,
+    CellServiceNetCheckRequest, CellServiceQuarantineRequest,
+    CellServiceStartBatchRequest, CellServiceStartRequest,
+    CellServiceStatsRequest, CellServiceStopRequest,
+    CellServiceUnquarantineRequest, CellServiceUpdateRequest,
+    CellServiceWatchOomEventsRequest, CellServiceWatchUsageRequest,
+    CellsDocument, CopyIntoHeader, CpuController, CpusetController, DeviceRule,
+    Executable, ExecutableDefinition, MemoryController, Mount, TmpfsMount,
+    UsageTarget,
 };
+use proto::cells::{readiness_probe, ReadinessProbe as ProtoReadinessProbe};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 use tokio::process::Command;
-use validation::{ValidatedType, ValidationError};
+use validation::{ValidatedField, ValidatedType, ValidationError};
 use validation_macros::ValidatedType;
 
 // TODO: Following the discord discussion of wanting to keep the logic on CellService,
@@ -41,6 +63,9 @@ use validation_macros::ValidatedType;
 pub struct ValidatedCellServiceAllocateRequest {
     #[field_type(Option<Cell>)]
     pub cell: ValidatedCell,
+
+    #[validate(none)]
+    pub update: bool,
 }
 
 impl CellServiceAllocateRequestTypeValidator
@@ -75,11 +100,39 @@ pub struct ValidatedCell {
     #[field_type(Option<MemoryController>)]
     pub memory: Option<ValidatedMemoryController>,
 
+    #[field_type(Vec<DeviceRule>)]
+    pub device_allow: Vec<ValidatedDeviceRule>,
+
     #[validate(none)]
     pub isolate_process: bool,
 
     #[validate(none)]
     pub isolate_network: bool,
+
+    #[validate(none)]
+    pub isolate_uts: bool,
+
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub hostname: Option<Hostname>,
+
+    #[field_type(HashMap<String, String>)]
+    pub variables: BTreeMap<String, String>,
+
+    #[field_type(Vec<u32>)]
+    pub reserved_ports: BTreeSet<u16>,
+
+    #[validate(none)]
+    pub default_uid: Option<u32>,
+
+    #[validate(none)]
+    pub default_gid: Option<u32>,
+
+    #[field_type(Option<proto::cells::UserNamespace>)]
+    pub userns: Option<UserNamespace>,
+
+    #[validate(none)]
+    pub per_executable_accounting: bool,
 }
 
 impl CellTypeValidator for CellValidator {
@@ -127,6 +180,134 @@ impl CellTypeValidator for CellValidator {
             Some(&*validation::field_name(field_name, parent_name)),
         )?))
     }
+
+    fn validate_device_allow(
+        device_allow: Vec<DeviceRule>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<ValidatedDeviceRule>, ValidationError> {
+        device_allow
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                ValidatedDeviceRule::validate(
+                    rule,
+                    Some(&*validation::field_name(
+                        &format!("{field_name}[{i}]"),
+                        parent_name,
+                    )),
+                )
+            })
+            .collect()
+    }
+
+    fn validate_variables(
+        variables: HashMap<String, String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<BTreeMap<String, String>, ValidationError> {
+        for name in variables.keys() {
+            // The built-in variables can not be overridden
+            if !is_variable_name(name)
+                || name == CELL_NAME_VARIABLE
+                || name == AURAE_RUNTIME_DIR_VARIABLE
+            {
+                return Err(ValidationError::Invalid {
+                    field: validation::field_name(
+                        &format!("{field_name}[{name}]"),
+                        parent_name,
+                    ),
+                });
+            }
+        }
+
+        Ok(variables.into_iter().collect())
+    }
+
+    fn validate_reserved_ports(
+        reserved_ports: Vec<u32>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<BTreeSet<u16>, ValidationError> {
+        let mut ports = BTreeSet::new();
+        for (i, port) in reserved_ports.into_iter().enumerate() {
+            // Port 0 asks the kernel for any port, so it can not be reserved
+            let port = match u16::try_from(port) {
+                Ok(port) if port != 0 => port,
+                _ => {
+                    return Err(ValidationError::Invalid {
+                        field: validation::field_name(
+                            &format!("{field_name}[{i}]"),
+                            parent_name,
+                        ),
+                    })
+                }
+            };
+
+            if !ports.insert(port) {
+                return Err(ValidationError::Invalid {
+                    field: validation::field_name(
+                        &format!("{field_name}[{i}]"),
+                        parent_name,
+                    ),
+                });
+            }
+        }
+
+        Ok(ports)
+    }
+
+    fn validate_userns(
+        userns: Option<proto::cells::UserNamespace>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<UserNamespace>, ValidationError> {
+        let Some(proto::cells::UserNamespace { uid_map, gid_map }) = userns
+        else {
+            return Ok(None);
+        };
+        if uid_map.is_empty() && gid_map.is_empty() {
+            return Ok(None);
+        }
+
+        // The executables can't change their ids without both maps
+        let parent_name = validation::field_name(field_name, parent_name);
+        Ok(Some(UserNamespace {
+            uid_map: validate_id_map(uid_map, "uid_map", &parent_name)?,
+            gid_map: validate_id_map(gid_map, "gid_map", &parent_name)?,
+        }))
+    }
+}
+
+fn validate_id_map(
+    map: Vec<proto::cells::IdMapping>,
+    field_name: &str,
+    parent_name: &str,
+) -> Result<Vec<IdMapping>, ValidationError> {
+    if map.is_empty() {
+        return Err(ValidationError::Required {
+            field: validation::field_name(field_name, Some(parent_name)),
+        });
+    }
+
+    map.into_iter()
+        .enumerate()
+        .map(|(i, mapping)| {
+            let proto::cells::IdMapping { container_id, host_id, size } =
+                mapping;
+            let mapping = IdMapping { container_id, host_id, size };
+            if !mapping.is_valid() {
+                return Err(ValidationError::Invalid {
+                    field: validation::field_name(
+                        &format!("{field_name}[{i}]"),
+                        Some(parent_name),
+                    ),
+                });
+            }
+
+            Ok(mapping)
+        })
+        .collect()
 }
 
 impl From<ValidatedCell> for super::cells::CellSpec {
@@ -136,8 +317,17 @@ impl From<ValidatedCell> for super::cells::CellSpec {
             cpu,
             cpuset,
             memory,
+            device_allow,
             isolate_process,
             isolate_network,
+            isolate_uts,
+            hostname,
+            variables,
+            reserved_ports,
+            default_uid,
+            default_gid,
+            userns,
+            per_executable_accounting,
         } = x;
 
         Self {
@@ -145,8 +335,23 @@ impl From<ValidatedCell> for super::cells::CellSpec {
                 cpu: cpu.map(|x| x.into()),
                 cpuset: cpuset.map(|x| x.into()),
                 memory: memory.map(|x| x.into()),
+                device_allow: device_allow
+                    .into_iter()
+                    .map(|x| x.into())
+                    .collect(),
             },
-            iso_ctl: IsolationControls { isolate_process, isolate_network },
+            iso_ctl: IsolationControls {
+                isolate_process,
+                isolate_network,
+                isolate_uts,
+                hostname,
+            },
+            variables,
+            reserved_ports,
+            default_uid,
+            default_gid,
+            user_namespace: userns,
+            per_executable_accounting,
         }
     }
 }
@@ -222,14 +427,418 @@ impl From<ValidatedMemoryController> for cgroups::memory::MemoryController {
     }
 }
 
+#[derive(ValidatedType, Debug, Clone)]
+pub struct ValidatedDeviceRule {
+    #[field_type(String)]
+    #[validate]
+    pub device_type: DeviceType,
+
+    #[validate(none)]
+    pub major: Option<u32>,
+
+    #[validate(none)]
+    pub minor: Option<u32>,
+
+    #[field_type(String)]
+    #[validate]
+    pub access: DeviceAccess,
+}
+
+impl DeviceRuleTypeValidator for DeviceRuleValidator {}
+
+impl From<ValidatedDeviceRule> for cgroups::DeviceRule {
+    fn from(value: ValidatedDeviceRule) -> Self {
+        let ValidatedDeviceRule { device_type, major, minor, access } = value;
+        Self { device_type, major, minor, access }
+    }
+}
+
 #[derive(Debug, ValidatedType)]
 pub struct ValidatedCellServiceFreeRequest {
     #[field_type(String)]
     #[validate]
     pub cell_name: CellName,
+    #[validate(none)]
+    pub force: bool,
+    #[validate(none)]
+    pub recursive: bool,
+    #[field_type(u32)]
+    pub timeout_ms: Duration,
 }
 
-impl CellServiceFreeRequestTypeValidator for CellServiceFreeRequestValidator {}
+impl CellServiceFreeRequestTypeValidator for CellServiceFreeRequestValidator {
+    /// A timeout of 0 defaults to [DEFAULT_FREE_TIMEOUT].
+    fn validate_timeout_ms(
+        timeout_ms: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Duration, ValidationError> {
+        if timeout_ms == 0 {
+            return Ok(DEFAULT_FREE_TIMEOUT);
+        }
+
+        validation::maximum_value(
+            timeout_ms,
+            60_000,
+            "milliseconds",
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(Duration::from_millis(timeout_ms.into()))
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceListExecutablesRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+}
+
+impl CellServiceListExecutablesRequestTypeValidator
+    for CellServiceListExecutablesRequestValidator
+{
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceStatsRequest {
+    #[field_type(String)]
+    #[validate]
+    pub cell_name: CellName,
+}
+
+impl CellServiceStatsRequestTypeValidator for CellServiceStatsRequestValidator {}
+
+#[derive(ValidatedType, Debug, Clone)]
+pub struct ValidatedCellServiceUpdateRequest {
+    #[field_type(String)]
+    #[validate]
+    pub cell_name: CellName,
+
+    #[field_type(Option<u64>)]
+    #[validate(opt)]
+    pub cpu_weight: Option<Weight>,
+
+    #[field_type(Option<i64>)]
+    #[validate(opt)]
+    pub cpu_max: Option<Limit>,
+
+    #[field_type(Option<i64>)]
+    #[validate(opt)]
+    pub memory_max: Option<Limit>,
+
+    #[field_type(Option<i64>)]
+    #[validate(opt)]
+    pub memory_low: Option<Protection>,
+
+    #[field_type(Option<i64>)]
+    #[validate(opt)]
+    pub pids_max: Option<Limit>,
+
+    #[field_type(Vec<proto::cells::IoLimit>)]
+    pub io_max: Vec<cgroups::IoLimit>,
+}
+
+impl CellServiceUpdateRequestTypeValidator
+    for CellServiceUpdateRequestValidator
+{
+    fn validate_io_max(
+        io_max: Vec<proto::cells::IoLimit>,
+        _field_name: &str,
+        _parent_name: Option<&str>,
+    ) -> Result<Vec<cgroups::IoLimit>, ValidationError> {
+        Ok(io_max
+            .into_iter()
+            .map(|limit| {
+                let proto::cells::IoLimit {
+                    major,
+                    minor,
+                    rbps,
+                    wbps,
+                    riops,
+                    wiops,
+                } = limit;
+                cgroups::IoLimit { major, minor, rbps, wbps, riops, wiops }
+            })
+            .collect())
+    }
+}
+
+impl From<ValidatedCellServiceUpdateRequest> for cgroups::CgroupSettings {
+    fn from(value: ValidatedCellServiceUpdateRequest) -> Self {
+        let ValidatedCellServiceUpdateRequest {
+            cell_name: _,
+            cpu_weight,
+            cpu_max,
+            memory_max,
+            memory_low,
+            pids_max,
+            io_max,
+        } = value;
+
+        Self { cpu_weight, cpu_max, memory_max, memory_low, pids_max, io_max }
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCopyIntoHeader {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(String)]
+    #[validate]
+    pub destination_path: CopyPath,
+    #[validate(none)]
+    pub archive: bool,
+    #[validate(none)]
+    pub mode: Option<u32>,
+    #[validate(none)]
+    pub uid: Option<u32>,
+    #[validate(none)]
+    pub gid: Option<u32>,
+    #[validate(none)]
+    pub size: Option<u64>,
+}
+
+impl CopyIntoHeaderTypeValidator for CopyIntoHeaderValidator {}
+
+impl From<ValidatedCopyIntoHeader> for CopyDestination {
+    fn from(x: ValidatedCopyIntoHeader) -> Self {
+        let ValidatedCopyIntoHeader {
+            cell_name: _,
+            destination_path,
+            archive,
+            mode,
+            uid,
+            gid,
+            size,
+        } = x;
+
+        Self { destination_path, archive, mode, uid, gid, size }
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceCopyFromRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(String)]
+    #[validate]
+    pub source_path: CopyPath,
+}
+
+impl CellServiceCopyFromRequestTypeValidator
+    for CellServiceCopyFromRequestValidator
+{
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceWatchOomEventsRequest {
+    #[field_type(String)]
+    pub cell_name: Option<CellName>,
+}
+
+impl CellServiceWatchOomEventsRequestTypeValidator
+    for CellServiceWatchOomEventsRequestValidator
+{
+    /// An empty cell name watches all cells.
+    fn validate_cell_name(
+        cell_name: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<CellName>, ValidationError> {
+        if cell_name.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(CellName::validate(Some(cell_name), field_name, parent_name)?))
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceWatchUsageRequest {
+    #[field_type(Vec<UsageTarget>)]
+    pub targets: Vec<ValidatedUsageTarget>,
+    #[field_type(u32)]
+    pub interval_ms: Duration,
+}
+
+impl CellServiceWatchUsageRequestTypeValidator
+    for CellServiceWatchUsageRequestValidator
+{
+    fn validate_targets(
+        targets: Vec<UsageTarget>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<ValidatedUsageTarget>, ValidationError> {
+        if targets.is_empty() {
+            return Err(ValidationError::Required {
+                field: validation::field_name(field_name, parent_name),
+            });
+        }
+
+        targets
+            .into_iter()
+            .enumerate()
+            .map(|(i, target)| {
+                ValidatedUsageTarget::validate(
+                    target,
+                    Some(&*validation::field_name(
+                        &format!("{field_name}[{i}]"),
+                        parent_name,
+                    )),
+                )
+            })
+            .collect()
+    }
+
+    /// Rounded up to a multiple of the minimum interval.
+    fn validate_interval_ms(
+        interval_ms: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Duration, ValidationError> {
+        validation::maximum_value(
+            interval_ms,
+            3_600_000,
+            "milliseconds",
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(usage::sample_interval(interval_ms))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ValidatedType)]
+pub struct ValidatedUsageTarget {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub executable_name: Option<ExecutableName>,
+}
+
+impl UsageTargetTypeValidator for UsageTargetValidator {
+    /// A target is a cell, an executable, or both.
+    fn post_validate(
+        output: &ValidatedUsageTarget,
+        parent_name: Option<&str>,
+    ) -> Result<(), ValidationError> {
+        if output.cell_name.is_none() && output.executable_name.is_none() {
+            return Err(ValidationError::Required {
+                field: validation::field_name("cell_name", parent_name),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceNetCheckRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(String)]
+    #[validate]
+    pub target_address: TargetAddress,
+    #[field_type(i32)]
+    pub protocol: NetCheckProtocol,
+    #[field_type(u32)]
+    pub timeout_ms: Duration,
+    #[field_type(u32)]
+    pub count: u32,
+    #[validate(none)]
+    pub tls_server_name: Option<String>,
+}
+
+impl CellServiceNetCheckRequestTypeValidator
+    for CellServiceNetCheckRequestValidator
+{
+    /// An unspecified protocol defaults to TCP.
+    fn validate_protocol(
+        protocol: i32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<NetCheckProtocol, ValidationError> {
+        let protocol: proto::cells::NetCheckProtocol =
+            validation::valid_enum(protocol, field_name, parent_name)?;
+
+        Ok(match protocol {
+            proto::cells::NetCheckProtocol::Unspecified
+            | proto::cells::NetCheckProtocol::Tcp => NetCheckProtocol::Tcp,
+            proto::cells::NetCheckProtocol::Udp => NetCheckProtocol::Udp,
+            proto::cells::NetCheckProtocol::Tls => NetCheckProtocol::Tls,
+        })
+    }
+
+    /// A timeout of 0 defaults to 1 second.
+    fn validate_timeout_ms(
+        timeout_ms: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Duration, ValidationError> {
+        if timeout_ms == 0 {
+            return Ok(Duration::from_secs(1));
+        }
+
+        validation::maximum_value(
+            timeout_ms,
+            30_000,
+            "milliseconds",
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(Duration::from_millis(timeout_ms.into()))
+    }
+
+    /// A count of 0 defaults to 3 attempts.
+    fn validate_count(
+        count: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<u32, ValidationError> {
+        if count == 0 {
+            return Ok(3);
+        }
+
+        validation::maximum_value(
+            count,
+            10,
+            validation::UNIT_ITEMS,
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(count)
+    }
+}
+
+impl From<ValidatedCellServiceNetCheckRequest> for NetCheck {
+    fn from(x: ValidatedCellServiceNetCheckRequest) -> Self {
+        let ValidatedCellServiceNetCheckRequest {
+            cell_name: _,
+            target_address,
+            protocol,
+            timeout_ms,
+            count,
+            tls_server_name,
+        } = x;
+
+        Self {
+            target_address,
+            protocol,
+            timeout: timeout_ms,
+            count,
+            tls_server_name,
+        }
+    }
+}
 
 #[derive(Debug, ValidatedType)]
 pub struct ValidatedCellServiceStartRequest {
@@ -260,7 +869,245 @@ impl CellServiceStartRequestTypeValidator for CellServiceStartRequestValidator {
 }
 
 #[derive(Debug, ValidatedType)]
-pub struct ValidatedCellServiceStopRequest {
+pub struct ValidatedCellServiceStartBatchRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(Vec<Executable>)]
+    pub executables: Vec<ValidatedExecutable>,
+    #[validate(none)]
+    pub uid: Option<u32>,
+    #[validate(none)]
+    pub gid: Option<u32>,
+    #[validate(none)]
+    pub atomic: bool,
+    #[field_type(u32)]
+    pub ready_after_ms: Duration,
+}
+
+impl CellServiceStartBatchRequestTypeValidator
+    for CellServiceStartBatchRequestValidator
+{
+    fn validate_executables(
+        executables: Vec<Executable>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<ValidatedExecutable>, ValidationError> {
+        if executables.is_empty() {
+            return Err(ValidationError::Required {
+                field: validation::field_name(field_name, parent_name),
+            });
+        }
+
+        let mut names = HashSet::new();
+        executables
+            .into_iter()
+            .enumerate()
+            .map(|(i, executable)| {
+                let field_name = validation::field_name(
+                    &format!("{field_name}[{i}]"),
+                    parent_name,
+                );
+                let executable = ValidatedExecutable::validate(
+                    executable,
+                    Some(&field_name),
+                )?;
+                // Rejected before any executable starts
+                if !names.insert(executable.name.clone()) {
+                    return Err(ValidationError::Invalid {
+                        field: validation::field_name(
+                            "name",
+                            Some(&field_name),
+                        ),
+                    });
+                }
+                Ok(executable)
+            })
+            .collect()
+    }
+
+    /// A duration of 0 defaults to [DEFAULT_READY_AFTER].
+    fn validate_ready_after_ms(
+        ready_after_ms: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Duration, ValidationError> {
+        if ready_after_ms == 0 {
+            return Ok(DEFAULT_READY_AFTER);
+        }
+
+        validation::maximum_value(
+            ready_after_ms,
+            300_000,
+            "milliseconds",
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(Duration::from_millis(ready_after_ms.into()))
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceImportRequest {
+    #[field_type(Option<CellsDocument>)]
+    pub document: Vec<ValidatedCellDefinition>,
+    #[validate(none)]
+    pub dry_run: bool,
+    #[validate(none)]
+    pub prune: bool,
+}
+
+/// A cell of an imported document. The cell and its executables are kept as
+/// in the document, to be allocated and started as if requested one by one.
+#[derive(Debug, Clone)]
+pub struct ValidatedCellDefinition {
+    pub name: CellName,
+    pub spec: CellSpec,
+    pub cell: Cell,
+    pub executables: Vec<ExecutableDefinition>,
+}
+
+impl CellServiceImportRequestTypeValidator
+    for CellServiceImportRequestValidator
+{
+    /// The whole document is validated, so that an invalid cell rejects the
+    /// import before any cell is allocated.
+    fn validate_document(
+        document: Option<CellsDocument>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<ValidatedCellDefinition>, ValidationError> {
+        let document = validation::required(document, field_name, parent_name)?;
+        let document_name = validation::field_name(field_name, parent_name);
+
+        let mut cell_names = HashSet::new();
+        document
+            .cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, definition)| {
+                let field_name = validation::field_name(
+                    &format!("cells[{i}]"),
+                    Some(&document_name),
+                );
+
+                // The secrets redacted on export have to be filled in first
+                if !definition.redacted.is_empty() {
+                    return Err(ValidationError::Invalid {
+                        field: validation::field_name(
+                            "redacted",
+                            Some(&field_name),
+                        ),
+                    });
+                }
+
+                let cell = validation::required(
+                    definition.cell,
+                    "cell",
+                    Some(&field_name),
+                )?;
+                let validated = ValidatedCell::validate(
+                    cell.clone(),
+                    Some(&validation::field_name("cell", Some(&field_name))),
+                )?;
+                let name = validated.name.clone();
+                if !cell_names.insert(name.clone()) {
+                    return Err(ValidationError::Invalid {
+                        field: validation::field_name(
+                            "cell.name",
+                            Some(&field_name),
+                        ),
+                    });
+                }
+
+                let mut executable_names = HashSet::new();
+                for (j, executable) in definition.executables.iter().enumerate()
+                {
+                    let field_name = validation::field_name(
+                        &format!("executables[{j}]"),
+                        Some(&field_name),
+                    );
+                    let ExecutableDefinition { executable, uid, gid } =
+                        executable.clone();
+                    let start = ValidatedCellServiceStartRequest::validate(
+                        CellServiceStartRequest {
+                            cell_name: Some(name.to_string()),
+                            executable,
+                            uid,
+                            gid,
+                        },
+                        Some(&field_name),
+                    )?;
+                    if !executable_names.insert(start.executable.name) {
+                        return Err(ValidationError::Invalid {
+                            field: validation::field_name(
+                                "executable.name",
+                                Some(&field_name),
+                            ),
+                        });
+                    }
+                }
+
+                Ok(ValidatedCellDefinition {
+                    name,
+                    spec: validated.into(),
+                    cell,
+                    executables: definition.executables,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceStopRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(String)]
+    #[validate]
+    pub executable_name: ExecutableName,
+    #[field_type(i32)]
+    pub kill_mode: KillMode,
+}
+
+impl CellServiceStopRequestTypeValidator for CellServiceStopRequestValidator {
+    /// An unspecified kill mode defaults to the process group.
+    fn validate_kill_mode(
+        kill_mode: i32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<KillMode, ValidationError> {
+        let kill_mode: proto::cells::KillMode =
+            validation::valid_enum(kill_mode, field_name, parent_name)?;
+
+        Ok(match kill_mode {
+            proto::cells::KillMode::Process => KillMode::Process,
+            proto::cells::KillMode::Unspecified
+            | proto::cells::KillMode::ProcessGroup => KillMode::ProcessGroup,
+            proto::cells::KillMode::Cgroup => KillMode::Cgroup,
+        })
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceQuarantineRequest {
+    #[field_type(Option<String>)]
+    #[validate(opt)]
+    pub cell_name: Option<CellName>,
+    #[field_type(String)]
+    #[validate]
+    pub executable_name: ExecutableName,
+}
+
+impl CellServiceQuarantineRequestTypeValidator
+    for CellServiceQuarantineRequestValidator
+{
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedCellServiceUnquarantineRequest {
     #[field_type(Option<String>)]
     #[validate(opt)]
     pub cell_name: Option<CellName>,
@@ -269,7 +1116,10 @@ pub struct ValidatedCellServiceStopRequest {
     pub executable_name: ExecutableName,
 }
 
-impl CellServiceStopRequestTypeValidator for CellServiceStopRequestValidator {}
+impl CellServiceUnquarantineRequestTypeValidator
+    for CellServiceUnquarantineRequestValidator
+{
+}
 
 #[derive(ValidatedType, Debug, PartialEq, Eq)]
 pub struct ValidatedExecutable {
@@ -283,6 +1133,36 @@ pub struct ValidatedExecutable {
     // TODO: `#[validate(none)] is used to skip validation. Actually validate when restrictions are known.
     #[validate(none)]
     pub description: String,
+
+    #[validate(none)]
+    pub forbid_daemonize: bool,
+
+    #[validate(none)]
+    pub disable_log_redaction: bool,
+
+    #[field_type(String)]
+    pub seccomp_profile: Option<String>,
+
+    #[validate(none)]
+    pub no_new_privs: bool,
+
+    #[field_type(Option<Capabilities>)]
+    pub capabilities: Option<ValidatedCapabilities>,
+
+    #[field_type(String)]
+    pub rootfs: Option<PathBuf>,
+
+    #[field_type(Option<proto::cells::OutputLimit>)]
+    pub output_limit: OutputLimit,
+
+    #[field_type(Vec<Mount>)]
+    pub mounts: Vec<MountSpec>,
+
+    #[field_type(Vec<String>)]
+    pub depends_on: Vec<ExecutableName>,
+
+    #[field_type(Option<ProtoReadinessProbe>)]
+    pub readiness_probe: Option<ReadinessProbe>,
 }
 
 impl ExecutableTypeValidator for ExecutableValidator {
@@ -299,11 +1179,201 @@ impl ExecutableTypeValidator for ExecutableValidator {
 
         Ok(OsString::from(command))
     }
+
+    fn validate_seccomp_profile(
+        seccomp_profile: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<String>, ValidationError> {
+        // The profile itself is compiled when the executable starts
+        match seccomp_profile.as_str() {
+            "" => Ok(None),
+            SeccompProfile::DEFAULT | SeccompProfile::STRICT => {
+                Ok(Some(seccomp_profile))
+            }
+            path if Path::new(path).is_absolute() => Ok(Some(seccomp_profile)),
+            _ => Err(ValidationError::Invalid {
+                field: validation::field_name(field_name, parent_name),
+            }),
+        }
+    }
+
+    fn validate_rootfs(
+        rootfs: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<PathBuf>, ValidationError> {
+        // Whether it's a directory fit to be a rootfs is checked when the
+        // executable starts, as it depends on the user it runs as
+        match PathBuf::from(rootfs) {
+            rootfs if rootfs.as_os_str().is_empty() => Ok(None),
+            rootfs if rootfs.is_absolute() => Ok(Some(rootfs)),
+            _ => Err(ValidationError::Invalid {
+                field: validation::field_name(field_name, parent_name),
+            }),
+        }
+    }
+
+    fn validate_output_limit(
+        output_limit: Option<proto::cells::OutputLimit>,
+        _field_name: &str,
+        _parent_name: Option<&str>,
+    ) -> Result<OutputLimit, ValidationError> {
+        // Unset rates take the limit of auraed when the executable starts
+        let Some(proto::cells::OutputLimit {
+            lines_per_second,
+            bytes_per_second,
+        }) = output_limit
+        else {
+            return Ok(OutputLimit::UNSET);
+        };
+
+        Ok(OutputLimit { lines_per_second, bytes_per_second })
+    }
+
+    fn validate_capabilities(
+        capabilities: Option<Capabilities>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<ValidatedCapabilities>, ValidationError> {
+        let Some(capabilities) = capabilities else {
+            return Ok(None);
+        };
+
+        Ok(Some(ValidatedCapabilities::validate(
+            capabilities,
+            Some(&*validation::field_name(field_name, parent_name)),
+        )?))
+    }
+
+    fn validate_mounts(
+        mounts: Vec<Mount>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<MountSpec>, ValidationError> {
+        mounts
+            .into_iter()
+            .enumerate()
+            .map(|(i, Mount { mount })| {
+                let field_name = validation::field_name(
+                    &format!("{field_name}[{i}]"),
+                    parent_name,
+                );
+                match mount {
+                    Some(mount::Mount::Tmpfs(tmpfs)) => {
+                        ValidatedTmpfsMount::validate(tmpfs, Some(&field_name))
+                            .map(MountSpec::from)
+                    }
+                    Some(mount::Mount::Bind(bind)) => {
+                        ValidatedBindMount::validate(bind, Some(&field_name))
+                            .map(MountSpec::from)
+                    }
+                    None => {
+                        Err(ValidationError::Required { field: field_name })
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the dependencies are started in the same batch, and not in a
+    /// cycle, is checked as the batch starts.
+    fn validate_depends_on(
+        depends_on: Vec<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<ExecutableName>, ValidationError> {
+        depends_on
+            .into_iter()
+            .enumerate()
+            .map(|(i, dependency)| {
+                ExecutableName::validate(
+                    Some(dependency),
+                    &format!("{field_name}[{i}]"),
+                    parent_name,
+                )
+            })
+            .collect()
+    }
+
+    /// A period or timeout of 0 defaults to [DEFAULT_PROBE_PERIOD] or
+    /// [DEFAULT_PROBE_TIMEOUT].
+    fn validate_readiness_probe(
+        readiness_probe: Option<ProtoReadinessProbe>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Option<ReadinessProbe>, ValidationError> {
+        let Some(ProtoReadinessProbe { probe, period_ms, timeout_ms }) =
+            readiness_probe
+        else {
+            return Ok(None);
+        };
+
+        let field_name = validation::field_name(field_name, parent_name);
+        let check = match probe {
+            Some(readiness_probe::Probe::Command(command)) => {
+                let command = validation::required_not_empty(
+                    Some(command),
+                    "command",
+                    Some(&field_name),
+                )?;
+                ReadinessCheck::Command(OsString::from(command))
+            }
+            Some(readiness_probe::Probe::TcpAddress(address)) => {
+                ReadinessCheck::Tcp(address.parse().map_err(|_| {
+                    ValidationError::Invalid {
+                        field: validation::field_name(
+                            "tcp_address",
+                            Some(&field_name),
+                        ),
+                    }
+                })?)
+            }
+            None => {
+                return Err(ValidationError::Required {
+                    field: validation::field_name("probe", Some(&field_name)),
+                })
+            }
+        };
+
+        let duration = |ms: u32, name: &str, default| match ms {
+            0 => Ok(default),
+            ms => validation::maximum_value(
+                ms,
+                300_000,
+                "milliseconds",
+                name,
+                Some(&field_name),
+            )
+            .map(|()| Duration::from_millis(ms.into())),
+        };
+
+        Ok(Some(ReadinessProbe {
+            check,
+            period: duration(period_ms, "period_ms", DEFAULT_PROBE_PERIOD)?,
+            timeout: duration(timeout_ms, "timeout_ms", DEFAULT_PROBE_TIMEOUT)?,
+        }))
+    }
 }
 
 impl From<ValidatedExecutable> for super::executables::ExecutableSpec {
     fn from(x: ValidatedExecutable) -> Self {
-        let ValidatedExecutable { name, command, description } = x;
+        let ValidatedExecutable {
+            name,
+            command,
+            description,
+            forbid_daemonize,
+            disable_log_redaction,
+            seccomp_profile,
+            no_new_privs,
+            capabilities,
+            rootfs,
+            output_limit,
+            mounts,
+            // Honored by the batch the executable is started in
+            depends_on: _,
+            readiness_probe: _,
+        } = x;
 
         let mut c = Command::new("sh");
         let _ = c.args([OsString::from("-c"), command]);
@@ -312,7 +1382,186 @@ impl From<ValidatedExecutable> for super::executables::ExecutableSpec {
         // mutates command, and is not making a clone to return
         assert_eq!(c.as_std().get_args().len(), 2);
 
-        Self { name, command: c, description }
+        Self {
+            name,
+            command: c,
+            description,
+            forbid_daemonize,
+            disable_log_redaction,
+            seccomp_profile,
+            no_new_privs,
+            capabilities: capabilities.map(|x| x.into()),
+            rootfs,
+            output_limit,
+            mounts,
+        }
+    }
+}
+
+#[derive(ValidatedType, Debug, PartialEq, Eq)]
+pub struct ValidatedTmpfsMount {
+    #[field_type(String)]
+    pub target: PathBuf,
+
+    #[field_type(u64)]
+    pub size_bytes: Option<u64>,
+}
+
+impl TmpfsMountTypeValidator for TmpfsMountValidator {
+    fn validate_target(
+        target: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<PathBuf, ValidationError> {
+        mount_target(target, field_name, parent_name)
+    }
+
+    fn validate_size_bytes(
+        size_bytes: u64,
+        _field_name: &str,
+        _parent_name: Option<&str>,
+    ) -> Result<Option<u64>, ValidationError> {
+        Ok(Some(size_bytes).filter(|size_bytes| *size_bytes > 0))
+    }
+}
+
+impl From<ValidatedTmpfsMount> for MountSpec {
+    fn from(x: ValidatedTmpfsMount) -> Self {
+        let ValidatedTmpfsMount { target, size_bytes } = x;
+        MountSpec::Tmpfs { target, size_bytes }
+    }
+}
+
+#[derive(ValidatedType, Debug, PartialEq, Eq)]
+pub struct ValidatedBindMount {
+    #[field_type(String)]
+    pub source: PathBuf,
+
+    #[field_type(String)]
+    pub target: PathBuf,
+
+    #[validate(none)]
+    pub read_only: bool,
+}
+
+impl BindMountTypeValidator for BindMountValidator {
+    fn validate_source(
+        source: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<PathBuf, ValidationError> {
+        // Whether it exists is checked when the executable starts
+        let source = PathBuf::from(validation::required_not_empty(
+            Some(source),
+            field_name,
+            parent_name,
+        )?);
+        if !source.is_absolute() {
+            return Err(ValidationError::Invalid {
+                field: validation::field_name(field_name, parent_name),
+            });
+        }
+
+        Ok(source)
+    }
+
+    fn validate_target(
+        target: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<PathBuf, ValidationError> {
+        mount_target(target, field_name, parent_name)
+    }
+}
+
+impl From<ValidatedBindMount> for MountSpec {
+    fn from(x: ValidatedBindMount) -> Self {
+        let ValidatedBindMount { source, target, read_only } = x;
+        MountSpec::Bind { source, target, read_only }
+    }
+}
+
+/// Parses the target of a mount, which is absolute, not the root, and
+/// without `..` components, so it stays in the rootfs of the executable.
+fn mount_target(
+    target: String,
+    field_name: &str,
+    parent_name: Option<&str>,
+) -> Result<PathBuf, ValidationError> {
+    let target = PathBuf::from(validation::required_not_empty(
+        Some(target),
+        field_name,
+        parent_name,
+    )?);
+
+    let mut components = target.components();
+    let is_valid = components.next() == Some(Component::RootDir)
+        && components.clone().next().is_some()
+        && components
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !is_valid {
+        return Err(ValidationError::Invalid {
+            field: validation::field_name(field_name, parent_name),
+        });
+    }
+
+    Ok(target)
+}
+
+#[derive(ValidatedType, Debug, PartialEq, Eq)]
+pub struct ValidatedCapabilities {
+    #[field_type(Vec<String>)]
+    pub drop: CapabilitySet,
+
+    #[field_type(Vec<String>)]
+    pub keep: CapabilitySet,
+}
+
+impl CapabilitiesTypeValidator for CapabilitiesValidator {
+    fn validate_drop(
+        drop: Vec<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<CapabilitySet, ValidationError> {
+        capability_set(drop, true, field_name, parent_name)
+    }
+
+    fn validate_keep(
+        keep: Vec<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<CapabilitySet, ValidationError> {
+        capability_set(keep, false, field_name, parent_name)
+    }
+}
+
+/// Parses capability names, naming the first invalid one in the error.
+fn capability_set(
+    names: Vec<String>,
+    allow_all: bool,
+    field_name: &str,
+    parent_name: Option<&str>,
+) -> Result<CapabilitySet, ValidationError> {
+    names.iter().enumerate().try_fold(
+        CapabilitySet::default(),
+        |set, (i, name)| match CapabilitySet::parse(name) {
+            Some(parsed) if allow_all || parsed != CapabilitySet::ALL => {
+                Ok(set | parsed)
+            }
+            _ => Err(ValidationError::Invalid {
+                field: validation::field_name(
+                    &format!("{field_name}[{i}]"),
+                    parent_name,
+                ),
+            }),
+        },
+    )
+}
+
+impl From<ValidatedCapabilities> for CapabilitiesSpec {
+    fn from(x: ValidatedCapabilities) -> Self {
+        let ValidatedCapabilities { drop, keep } = x;
+        Self { drop, keep }
     }
 }
 
@@ -478,6 +1727,134 @@ mod tests {
         assert!(validated.is_err());
     }
 
+    #[test]
+    fn test_cell_type_variables_valid() {
+        let validated = CellValidator::validate_variables(
+            HashMap::from([("port_2".to_string(), "8080".to_string())]),
+            "field",
+            Some("parent"),
+        );
+        assert!(validated.is_ok());
+    }
+
+    #[test]
+    fn test_cell_type_variables_invalid_name() {
+        for name in ["2port", "port-number", "cell_name", "aurae_runtime_dir"] {
+            let validated = CellValidator::validate_variables(
+                HashMap::from([(name.to_string(), "8080".to_string())]),
+                "field",
+                Some("parent"),
+            );
+            assert!(validated.is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_validation_errors_carry_details() {
+        let err = CellValidator::validate_reserved_ports(
+            vec![8080, 0],
+            "reserved_ports",
+            Some("cell"),
+        )
+        .expect_err("port 0 must be invalid");
+
+        let status = tonic::Status::from(err);
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            client::ErrorDetails::from_status(&status),
+            Some(client::ErrorDetails::InvalidSpec {
+                field: String::from("cell.reserved_ports[1]"),
+                reason: String::from("invalid"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cell_type_reserved_ports_valid() {
+        let validated = CellValidator::validate_reserved_ports(
+            vec![8443, 8080],
+            "field",
+            Some("parent"),
+        )
+        .expect("valid ports");
+        assert_eq!(validated, BTreeSet::from([8080, 8443]));
+    }
+
+    #[test]
+    fn test_cell_type_reserved_ports_invalid() {
+        for ports in [vec![0], vec![65536], vec![8080, 8080]] {
+            let validated = CellValidator::validate_reserved_ports(
+                ports.clone(),
+                "field",
+                Some("parent"),
+            );
+            assert!(validated.is_err(), "{ports:?}");
+        }
+    }
+
+    #[test]
+    fn test_cell_type_userns_valid() {
+        let mapping = || proto::cells::IdMapping {
+            container_id: 0,
+            host_id: 100000,
+            size: 65536,
+        };
+        let validated = CellValidator::validate_userns(
+            Some(proto::cells::UserNamespace {
+                uid_map: vec![mapping()],
+                gid_map: vec![mapping()],
+            }),
+            "userns",
+            Some("cell"),
+        )
+        .expect("valid user namespace")
+        .expect("user namespace");
+        assert_eq!(
+            validated.uid_map,
+            vec![IdMapping { container_id: 0, host_id: 100000, size: 65536 }]
+        );
+
+        // Empty maps are no user namespace
+        let validated = CellValidator::validate_userns(
+            Some(proto::cells::UserNamespace::default()),
+            "userns",
+            Some("cell"),
+        )
+        .expect("valid user namespace");
+        assert!(validated.is_none());
+    }
+
+    #[test]
+    fn test_cell_type_userns_requires_both_maps() {
+        let mapping = |size| proto::cells::IdMapping {
+            container_id: 0,
+            host_id: 100000,
+            size,
+        };
+        let err = CellValidator::validate_userns(
+            Some(proto::cells::UserNamespace {
+                uid_map: vec![mapping(1)],
+                gid_map: vec![],
+            }),
+            "userns",
+            Some("cell"),
+        )
+        .expect_err("gid map is missing");
+        assert_eq!(err.get_field(), "cell.userns.gid_map");
+        assert_eq!(err.reason(), "required");
+
+        let err = CellValidator::validate_userns(
+            Some(proto::cells::UserNamespace {
+                uid_map: vec![mapping(1)],
+                gid_map: vec![mapping(0)],
+            }),
+            "userns",
+            Some("cell"),
+        )
+        .expect_err("empty mapping");
+        assert_eq!(err.get_field(), "cell.userns.gid_map[0]");
+    }
+
     #[test]
     fn test_cell_service_start_request_empty_executable() {
         let validated = CellServiceStartRequestValidator::validate_executable(
@@ -495,6 +1872,14 @@ mod tests {
                 command: String::from(""),
                 name: String::from("name"),
                 description: String::from("description"),
+                forbid_daemonize: false,
+                disable_log_redaction: false,
+                seccomp_profile: String::new(),
+                no_new_privs: false,
+                capabilities: None,
+                rootfs: String::new(),
+                output_limit: None,
+                mounts: vec![],
             }),
             "field",
             Some("parent"),
@@ -502,6 +1887,39 @@ mod tests {
         assert!(validated.is_err());
     }
 
+    #[test]
+    fn test_cell_service_start_batch_request_duplicate_names() {
+        let executable = |name: &str| Executable {
+            name: name.into(),
+            command: "command".into(),
+            ..Default::default()
+        };
+
+        let validated =
+            CellServiceStartBatchRequestValidator::validate_executables(
+                vec![executable("a"), executable("b")],
+                "executables",
+                None,
+            )
+            .expect("unique names");
+        assert_eq!(validated.len(), 2);
+
+        let err = CellServiceStartBatchRequestValidator::validate_executables(
+            vec![executable("a"), executable("b"), executable("a")],
+            "executables",
+            None,
+        )
+        .expect_err("duplicate name");
+        assert_eq!(err.get_field(), "executables[2].name");
+
+        assert!(CellServiceStartBatchRequestValidator::validate_executables(
+            vec![],
+            "executables",
+            None,
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_cell_service_start_request_valid() {
         let validated = CellServiceStartRequestValidator::validate_executable(
@@ -509,6 +1927,16 @@ mod tests {
                 command: String::from("command"),
                 name: String::from("name"),
                 description: String::from("description"),
+                forbid_daemonize: false,
+                disable_log_redaction: false,
+                seccomp_profile: String::new(),
+                no_new_privs: false,
+                capabilities: None,
+                rootfs: String::new(),
+                output_limit: None,
+                mounts: vec![],
+                depends_on: vec![],
+                readiness_probe: None,
             }),
             "field",
             Some("parent"),
@@ -520,10 +1948,67 @@ mod tests {
                 name: ExecutableName::new(String::from("name")),
                 description: String::from("description"),
                 command: OsString::from("command"),
+                forbid_daemonize: false,
+                disable_log_redaction: false,
+                seccomp_profile: None,
+                no_new_privs: false,
+                capabilities: None,
+                rootfs: None,
+                output_limit: OutputLimit::UNSET,
+                mounts: vec![],
+                depends_on: vec![],
+                readiness_probe: None,
             },
         );
     }
 
+    #[test]
+    fn test_executable_readiness_probe() {
+        let probe = |probe, period_ms| {
+            ExecutableValidator::validate_readiness_probe(
+                Some(ProtoReadinessProbe { probe, period_ms, timeout_ms: 0 }),
+                "readiness_probe",
+                None,
+            )
+        };
+
+        assert_eq!(
+            probe(
+                Some(readiness_probe::Probe::TcpAddress(
+                    "127.0.0.1:5432".into()
+                )),
+                500,
+            )
+            .unwrap(),
+            Some(ReadinessProbe {
+                check: ReadinessCheck::Tcp("127.0.0.1:5432".parse().unwrap()),
+                period: Duration::from_millis(500),
+                timeout: DEFAULT_PROBE_TIMEOUT,
+            })
+        );
+
+        let err = probe(
+            Some(readiness_probe::Probe::TcpAddress("localhost:5432".into())),
+            0,
+        )
+        .expect_err("not an ip address");
+        assert_eq!(err.get_field(), "readiness_probe.tcp_address");
+
+        let err = probe(Some(readiness_probe::Probe::Command("".into())), 0)
+            .expect_err("empty command");
+        assert_eq!(err.get_field(), "readiness_probe.command");
+
+        let err = probe(None, 0).expect_err("no probe");
+        assert_eq!(err.get_field(), "readiness_probe.probe");
+
+        let err = probe(
+            Some(readiness_probe::Probe::Command("true".into())),
+            300_001,
+        )
+        .expect_err("period over the maximum");
+        assert_eq!(err.get_field(), "readiness_probe.period_ms");
+    }
+
     #[test]
     fn test_executable_empty_command() {
         assert!(ExecutableValidator::validate_command(
@@ -544,4 +2029,154 @@ mod tests {
         assert!(validated.is_ok());
         assert_eq!(validated.unwrap(), OsString::from("command"));
     }
+
+    #[test]
+    fn test_executable_seccomp_profile() {
+        let validate = |profile: &str| {
+            ExecutableValidator::validate_seccomp_profile(
+                profile.to_string(),
+                "field",
+                Some("parent"),
+            )
+        };
+
+        assert_eq!(validate("").unwrap(), None);
+        assert_eq!(validate("strict").unwrap(), Some("strict".into()));
+        assert_eq!(
+            validate("/etc/aurae/seccomp.json").unwrap(),
+            Some("/etc/aurae/seccomp.json".into())
+        );
+        assert!(validate("relaxed").is_err());
+        assert!(validate("seccomp.json").is_err());
+    }
+
+    #[test]
+    fn test_executable_rootfs() {
+        let validate = |rootfs: &str| {
+            ExecutableValidator::validate_rootfs(
+                rootfs.to_string(),
+                "field",
+                Some("parent"),
+            )
+        };
+
+        assert_eq!(validate("").unwrap(), None);
+        assert_eq!(
+            validate("/var/lib/rootfs").unwrap(),
+            Some(PathBuf::from("/var/lib/rootfs"))
+        );
+        assert!(validate("rootfs").is_err());
+    }
+
+    #[test]
+    fn test_executable_mounts() {
+        let validate = |mount| {
+            ExecutableValidator::validate_mounts(
+                vec![Mount { mount }],
+                "mounts",
+                Some("executable"),
+            )
+        };
+        let tmpfs = |target: &str| {
+            Some(mount::Mount::Tmpfs(TmpfsMount {
+                target: target.into(),
+                size_bytes: 0,
+            }))
+        };
+
+        assert_eq!(
+            validate(tmpfs("/scratch")).unwrap(),
+            vec![MountSpec::Tmpfs {
+                target: PathBuf::from("/scratch"),
+                size_bytes: None
+            }]
+        );
+        assert_eq!(
+            validate(Some(mount::Mount::Bind(BindMount {
+                source: "/var/lib/data".into(),
+                target: "/data".into(),
+                read_only: true,
+            })))
+            .unwrap(),
+            vec![MountSpec::Bind {
+                source: PathBuf::from("/var/lib/data"),
+                target: PathBuf::from("/data"),
+                read_only: true,
+            }]
+        );
+
+        for target in ["", "/", "scratch", "/scratch/../etc"] {
+            assert!(validate(tmpfs(target)).is_err(), "{target}");
+        }
+        let err = validate(Some(mount::Mount::Bind(BindMount {
+            source: "data".into(),
+            target: "/data".into(),
+            read_only: false,
+        })))
+        .expect_err("relative source");
+        assert_eq!(err.get_field(), "executable.mounts[0].source");
+        let err = validate(None).expect_err("no mount");
+        assert_eq!(err.get_field(), "executable.mounts[0]");
+    }
+
+    #[test]
+    fn test_executable_output_limit() {
+        let validate = |output_limit| {
+            ExecutableValidator::validate_output_limit(
+                output_limit,
+                "output_limit",
+                Some("executable"),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(validate(None), OutputLimit::UNSET);
+        assert_eq!(
+            validate(Some(proto::cells::OutputLimit {
+                lines_per_second: 10,
+                bytes_per_second: 0,
+            })),
+            OutputLimit { lines_per_second: 10, bytes_per_second: 0 }
+        );
+    }
+
+    #[test]
+    fn test_capabilities_valid() {
+        let validated = ValidatedCapabilities::validate(
+            Capabilities {
+                drop: vec!["ALL".into()],
+                keep: vec!["CAP_NET_BIND_SERVICE".into(), "chown".into()],
+            },
+            None,
+        )
+        .expect("valid");
+
+        assert_eq!(validated.drop, CapabilitySet::ALL);
+        assert_eq!(
+            validated.keep,
+            CapabilitySet::parse("net_bind_service").unwrap()
+                | CapabilitySet::parse("CAP_CHOWN").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_capabilities_invalid_name() {
+        let validated = ValidatedCapabilities::validate(
+            Capabilities {
+                drop: vec!["CAP_CHOWN".into(), "CAP_FLY".into()],
+                keep: vec![],
+            },
+            Some("executable"),
+        );
+        assert_eq!(validated.unwrap_err().get_field(), "executable.drop[1]");
+    }
+
+    #[test]
+    fn test_capabilities_cant_keep_all() {
+        let validated = ValidatedCapabilities::validate(
+            Capabilities { drop: vec![], keep: vec!["ALL".into()] },
+            None,
+        );
+        assert!(validated.is_err());
+    }
 }
@@ -0,0 +1,248 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Resolves which workload managed by auraed a process belongs to.
+
+use super::cells::CellName;
+use super::executables::EXECUTABLE_ID_ENV;
+use procfs::process::Process;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::Path;
+use validation::ValidatedField;
+
+const PROC_ROOT: &str = "/proc";
+/// The name of the leaf cgroup of a cell, holding its processes.
+const LEAF: &str = "_";
+
+/// The workload a process belongs to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Workload {
+    /// The cell of the process, if it is in the cgroup of one.
+    pub cell_name: Option<CellName>,
+    /// The id of the executable that started the process, if any.
+    pub executable_id: Option<String>,
+}
+
+impl Workload {
+    /// Resolves the workload of the process `pid`. The cell is only found
+    /// for processes in the cgroup namespace of auraed, or a descendant of it.
+    pub fn of_pid(pid: i32) -> Self {
+        Self::of_pid_in(Path::new(PROC_ROOT), pid)
+    }
+
+    fn of_pid_in(proc_root: &Path, pid: i32) -> Self {
+        let Ok(process) =
+            Process::new_with_root(proc_root.join(pid.to_string()))
+        else {
+            return Self::default();
+        };
+
+        Self {
+            cell_name: cell_name(&process),
+            executable_id: executable_id(&process),
+        }
+    }
+}
+
+/// Returns the live processes in the same cgroups as `own_pid`, other than
+/// itself, which were not started by one of the executables whose id is in
+/// `running`. In the auraed of a cell, these are the processes left in the
+/// cell by the executables it stopped.
+pub fn orphans(own_pid: i32, running: &HashSet<String>) -> Vec<i32> {
+    orphans_in(Path::new(PROC_ROOT), own_pid, running)
+}
+
+fn orphans_in(
+    proc_root: &Path,
+    own_pid: i32,
+    running: &HashSet<String>,
+) -> Vec<i32> {
+    let own = Process::new_with_root(proc_root.join(own_pid.to_string()))
+        .ok()
+        .and_then(|process| cgroup_paths(&process));
+    let (Some(own), Ok(processes)) =
+        (own, procfs::process::all_processes_with_root(proc_root))
+    else {
+        return vec![];
+    };
+
+    let mut orphans: Vec<_> = processes
+        .filter_map(|process| process.ok())
+        .filter(|process| process.pid != own_pid)
+        .filter(|process| process.stat().is_ok_and(|stat| stat.state != 'Z'))
+        .filter(|process| cgroup_paths(process).as_ref() == Some(&own))
+        .filter(|process| {
+            !matches!(executable_id(process), Some(id) if running.contains(&id))
+        })
+        .map(|process| process.pid)
+        .collect();
+    orphans.sort_unstable();
+    orphans
+}
+
+/// Returns the cgroup of `process` in each hierarchy, as seen from the
+/// cgroup namespace of auraed.
+fn cgroup_paths(process: &Process) -> Option<Vec<(u32, String)>> {
+    let cgroups = process.cgroups().ok()?;
+    Some(
+        cgroups
+            .0
+            .into_iter()
+            .map(|cgroup| (cgroup.hierarchy, cgroup.pathname))
+            .collect(),
+    )
+}
+
+/// Returns the cell whose leaf cgroup holds `process`, in any hierarchy.
+fn cell_name(process: &Process) -> Option<CellName> {
+    let cgroups = process.cgroups().ok()?;
+    cgroups.0.into_iter().find_map(|cgroup| {
+        let path = cgroup.pathname.strip_suffix(LEAF)?.strip_suffix('/')?;
+        // Paths outside of our cgroup namespace start with '..', which is
+        // not valid in a cell name.
+        CellName::validate(Some(path.into()), "cgroup", None).ok()
+    })
+}
+
+fn executable_id(process: &Process) -> Option<String> {
+    let environ = process.environ().ok()?;
+    let id = environ.get(OsStr::new(EXECUTABLE_ID_ENV))?;
+    id.to_str().map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const PID: i32 = 42;
+
+    fn proc_root(cgroup: &str, environ: &[&str]) -> PathBuf {
+        let root = std::env::temp_dir()
+            .join(format!("ae-test-proc-{}", uuid::Uuid::new_v4()));
+        let dir = root.join(PID.to_string());
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        fs::write(dir.join("cgroup"), cgroup).unwrap();
+        let environ: String =
+            environ.iter().flat_map(|var| [*var, "\0"]).collect();
+        fs::write(dir.join("environ"), environ).unwrap();
+        root
+    }
+
+    fn cell_name(name: &str) -> Option<CellName> {
+        Some(CellName::validate(Some(name.into()), "test", None).unwrap())
+    }
+
+    #[test]
+    fn test_of_pid_in_cell() {
+        let root = proc_root("0::/ae-1/_\n", &["PATH=/bin"]);
+        let workload = Workload::of_pid_in(&root, PID);
+        assert_eq!(
+            workload,
+            Workload { cell_name: cell_name("ae-1"), executable_id: None }
+        );
+    }
+
+    #[test]
+    fn test_of_pid_in_nested_cell_with_executable() {
+        let root = proc_root(
+            "0::/parent/child/_\n",
+            &["PATH=/bin", "AURAE_EXECUTABLE_ID=abc-123"],
+        );
+        let workload = Workload::of_pid_in(&root, PID);
+        assert_eq!(
+            workload,
+            Workload {
+                cell_name: cell_name("parent/child"),
+                executable_id: Some("abc-123".into())
+            }
+        );
+    }
+
+    #[test]
+    fn test_of_pid_in_cell_v1_hierarchies() {
+        let root = proc_root(
+            "12:pids:/user.slice\n4:cpu,cpuacct:/ae-1/_\n0::/user.slice\n",
+            &[],
+        );
+        let workload = Workload::of_pid_in(&root, PID);
+        assert_eq!(workload.cell_name, cell_name("ae-1"));
+    }
+
+    #[test]
+    fn test_of_pid_not_in_cell() {
+        for cgroup in [
+            "0::/\n",
+            "0::/_aurae\n",
+            "0::/init.scope\n",
+            "0::/ae-1\n",
+            "0::/../../../ae-1/_\n",
+            "0::/user.slice/user-1000.slice/session-3.scope\n",
+        ] {
+            let root = proc_root(cgroup, &[]);
+            assert_eq!(
+                Workload::of_pid_in(&root, PID),
+                Workload::default(),
+                "{cgroup}"
+            );
+        }
+    }
+
+    /// Adds the process `pid` in `state` to the proc filesystem at `root`.
+    fn add_process(
+        root: &Path,
+        pid: i32,
+        state: char,
+        cgroup: &str,
+        environ: &[&str],
+    ) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).expect("failed to create test dir");
+        fs::write(
+            dir.join("stat"),
+            format!(
+                "{pid} (sh) {state} 1 {pid} {pid} 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 100 0 0 {} 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n",
+                u64::MAX
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("cgroup"), cgroup).unwrap();
+        let environ: String =
+            environ.iter().flat_map(|var| [*var, "\0"]).collect();
+        fs::write(dir.join("environ"), environ).unwrap();
+    }
+
+    #[test]
+    fn test_orphans_exclude_running_executables() {
+        let root = proc_root("0::/\n", &[]);
+        add_process(&root, PID, 'S', "0::/\n", &[]);
+        add_process(&root, 43, 'S', "0::/\n", &["AURAE_EXECUTABLE_ID=a"]);
+        add_process(&root, 44, 'S', "0::/\n", &["AURAE_EXECUTABLE_ID=b"]);
+        add_process(&root, 45, 'S', "0::/\n", &[]);
+        add_process(&root, 46, 'Z', "0::/\n", &[]);
+        add_process(&root, 47, 'S', "0::/nested/_\n", &[]);
+
+        let running = HashSet::from(["a".to_string()]);
+        assert_eq!(orphans_in(&root, PID, &running), vec![44, 45]);
+    }
+
+    #[test]
+    fn test_of_pid_gone() {
+        let root = proc_root("0::/ae-1/_\n", &[]);
+        assert_eq!(Workload::of_pid_in(&root, PID + 1), Workload::default());
+    }
+}
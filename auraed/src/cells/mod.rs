@@ -13,6 +13,10 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-pub(crate) use cell_service::CellService;
+pub(crate) use cell_service::{
+    bring_up_loopback, CellName, CellService, CellSockets, CgroupMode, Cpus,
+    ExecutableName, Limit, Mems, Workload, DEFAULT_LIFETIME_STATS_INTERVAL,
+};
+pub use cell_service::{IdMapping, UserNamespace};
 
 mod cell_service;
\ No newline at end of file
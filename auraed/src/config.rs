@@ -0,0 +1,541 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The configuration file of auraed. Each setting may be overridden by an
+//! `AURAED_*` environment variable, and then by a flag of auraed.
+
+use crate::{
+    AdmissionConfig, AuditConfig, AuraedRuntime, BlockingPoolsConfig,
+    DiscoveryConfig, DnsConfig, EventJournalConfig, IdMapping, ListenerConfig,
+    OutputLimit, RedactionRule, ShutdownConfig, SocketPermissions,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// The config file read when auraed is not given one.
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/aurae/auraed.toml";
+
+/// The config file could not be used.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// The file could not be read.
+    #[error("failed to read config '{}': {source}", path.display())]
+    Read {
+        /// The path of the config file.
+        path: PathBuf,
+        /// Why it could not be read.
+        source: std::io::Error,
+    },
+    /// The file is not a valid config.
+    #[error("invalid config '{}': {source}", path.display())]
+    Parse {
+        /// The path of the config file.
+        path: PathBuf,
+        /// Where it is invalid.
+        source: toml::de::Error,
+    },
+}
+
+/// The settings of auraed, read from a TOML file. Missing keys take the
+/// same defaults as the flags of auraed.
+///
+/// ```toml
+/// runtime_dir = "/var/run/aurae"
+/// listeners = ["tcp:[::]:8443", "unix:/run/aurae/local.sock,mode=660"]
+/// authz_policy = "/etc/aurae/authz.toml"
+/// log_grace_period = 5
+/// output_drain_timeout = 3
+/// lifetime_stats_interval = 10
+///
+/// [socket_permissions]
+/// mode = 0o766
+///
+/// [output_limit]
+/// lines_per_second = 10000
+/// bytes_per_second = 10485760
+///
+/// [audit]
+/// path = "/var/log/aurae/audit.jsonl"
+///
+/// [shutdown]
+/// grace_period = 10
+/// deadline = 30
+///
+/// [discovery]
+/// peer_ttl = 30
+/// persist = true
+///
+/// [events]
+/// max_bytes = 16777216
+/// max_age = 86400
+///
+/// [admission]
+/// enabled = true
+/// memory_overcommit = 150
+///
+/// [dns]
+/// nameservers = ["10.0.0.10"]
+/// searches = ["aurae.local"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuraedConfig {
+    /// Certificate Authority for an organization or mesh of Aurae instances.
+    pub ca_crt: PathBuf,
+    /// The signed server X509 certificate for this unique instance.
+    pub server_crt: PathBuf,
+    /// The secret key for this unique instance.
+    pub server_key: PathBuf,
+    /// The address of the main socket. Defaults to a socket file in the
+    /// runtime directory, or to a TCP address, depending on context.
+    pub socket: Option<String>,
+    /// The sockets auraed listens on next to its main socket, written as
+    /// the `--listen` flag.
+    #[serde(with = "strings")]
+    pub listeners: Vec<ListenerConfig>,
+    /// Configurable runtime directory. Defaults to /var/run/aurae.
+    pub runtime_dir: PathBuf,
+    /// Configurable library directory. Defaults to /var/lib/aurae.
+    pub library_dir: PathBuf,
+    /// Secret patterns redacted from logs, written as `<name>=<regex>`.
+    #[serde(with = "strings")]
+    pub log_redaction: Vec<RedactionRule>,
+    /// The default rate of the output of each executable. Zero is unlimited.
+    pub output_limit: OutputLimit,
+    /// How long the output of a stopped executable remains available to
+    /// late readers.
+    #[serde(with = "secs")]
+    pub log_grace_period: Duration,
+    /// How long the output of a stopped executable is read for, before it
+    /// is cut short.
+    #[serde(with = "secs")]
+    pub output_drain_timeout: Duration,
+    /// How often the lifetime stats of the cells are sampled. Zero only
+    /// samples them when an executable of the cell is stopped.
+    #[serde(with = "secs")]
+    pub lifetime_stats_interval: Duration,
+    /// The policy of which clients may call which methods. Required by the
+    /// listeners served without TLS.
+    pub authz_policy: Option<PathBuf>,
+    /// Reap the processes orphaned to auraed as a child subreaper.
+    pub subreaper: bool,
+    /// The uid map of the user namespace the executables are started in,
+    /// written as `<container_id>:<host_id>:<size>`.
+    #[serde(with = "strings")]
+    pub uid_map: Vec<IdMapping>,
+    /// The gid map of the user namespace the executables are started in.
+    #[serde(with = "strings")]
+    pub gid_map: Vec<IdMapping>,
+    /// Ownership and mode of the unix socket auraed listens on.
+    pub socket_permissions: SocketPermissions,
+    /// Sizes of the thread pools that run blocking operations.
+    pub blocking_pools: BlockingPoolsConfig,
+    /// Where the calls that change workloads are audited.
+    pub audit: AuditConfig,
+    /// How long workloads are given to exit when auraed shuts down.
+    pub shutdown: ShutdownConfig,
+    /// How auraed keeps track of the peers which register with it.
+    pub discovery: DiscoveryConfig,
+    /// How the lifecycle events are journaled.
+    pub events: EventJournalConfig,
+    /// Whether and how cells and pods are admitted against the capacity of
+    /// the host.
+    pub admission: AdmissionConfig,
+    /// The DNS settings the pods are started with.
+    pub dns: DnsConfig,
+}
+
+impl AuraedConfig {
+    /// Reads the config at `path`, or at [DEFAULT_CONFIG_PATH] if [None].
+    /// Only the default config may be missing, in which case the defaults
+    /// are returned.
+    ///
+    /// Also returns the path of each key of the file which is not a setting,
+    /// such as `audit.max_byte`, for it to be warned of.
+    pub fn load(
+        path: Option<&Path>,
+    ) -> Result<(Self, Vec<String>), ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path, true),
+            None => (Path::new(DEFAULT_CONFIG_PATH), false),
+        };
+
+        let config = match std::fs::read_to_string(path) {
+            Ok(config) => config,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok((Self::default(), vec![]));
+            }
+            Err(source) => {
+                return Err(ConfigError::Read { path: path.into(), source })
+            }
+        };
+
+        Self::parse(&config)
+            .map_err(|source| ConfigError::Parse { path: path.into(), source })
+    }
+
+    fn parse(config: &str) -> Result<(Self, Vec<String>), toml::de::Error> {
+        let table: toml::Table = toml::from_str(config)?;
+        let config: Self = table.clone().try_into()?;
+
+        // Every key which is a setting is kept when serialized back
+        let mut unknown = vec![];
+        if let Ok(toml::Value::Table(known)) = toml::Value::try_from(&config) {
+            unknown_keys(&table, &known, "", &mut unknown);
+        }
+
+        Ok((config, unknown))
+    }
+
+    /// The runtime of auraed, and the address of its main socket.
+    pub fn into_runtime(self) -> (AuraedRuntime, Option<String>) {
+        let Self {
+            ca_crt,
+            server_crt,
+            server_key,
+            socket,
+            listeners,
+            runtime_dir,
+            library_dir,
+            log_redaction,
+            output_limit,
+            log_grace_period,
+            output_drain_timeout,
+            lifetime_stats_interval,
+            authz_policy,
+            subreaper,
+            uid_map,
+            gid_map,
+            socket_permissions,
+            blocking_pools,
+            audit,
+            shutdown,
+            discovery,
+            events,
+            admission,
+            dns,
+        } = self;
+
+        let runtime = AuraedRuntime {
+            ca_crt,
+            server_crt,
+            server_key,
+            runtime_dir,
+            library_dir,
+            blocking_pools,
+            socket_permissions,
+            log_redaction,
+            output_limit,
+            log_grace_period,
+            output_drain_timeout,
+            audit,
+            authz_policy,
+            shutdown,
+            discovery,
+            events,
+            admission,
+            dns,
+            listeners,
+            subreaper,
+            uid_map,
+            gid_map,
+            lifetime_stats_interval,
+            ..AuraedRuntime::default()
+        };
+
+        (runtime, socket)
+    }
+}
+
+impl Default for AuraedConfig {
+    fn default() -> Self {
+        let AuraedRuntime {
+            auraed: _,
+            ca_crt,
+            server_crt,
+            server_key,
+            runtime_dir,
+            library_dir,
+            blocking_pools,
+            socket_permissions,
+            log_redaction,
+            output_limit,
+            log_grace_period,
+            output_drain_timeout,
+            audit,
+            authz_policy,
+            shutdown,
+            discovery,
+            events,
+            admission,
+            dns,
+            listeners,
+            subreaper,
+            uid_map,
+            gid_map,
+            lifetime_stats_interval,
+            executable_cgroups: _,
+        } = AuraedRuntime::default();
+
+        Self {
+            ca_crt,
+            server_crt,
+            server_key,
+            socket: None,
+            listeners,
+            runtime_dir,
+            library_dir,
+            log_redaction,
+            output_limit,
+            log_grace_period,
+            output_drain_timeout,
+            lifetime_stats_interval,
+            authz_policy,
+            subreaper,
+            uid_map,
+            gid_map,
+            socket_permissions,
+            blocking_pools,
+            audit,
+            shutdown,
+            discovery,
+            events,
+            admission,
+            dns,
+        }
+    }
+}
+
+fn unknown_keys(
+    read: &toml::Table,
+    known: &toml::Table,
+    prefix: &str,
+    unknown: &mut Vec<String>,
+) {
+    for (key, value) in read {
+        let path = match prefix {
+            "" => key.clone(),
+            prefix => format!("{prefix}.{key}"),
+        };
+
+        match (value, known.get(key)) {
+            (_, None) => unknown.push(path),
+            (toml::Value::Table(read), Some(toml::Value::Table(known))) => {
+                unknown_keys(read, known, &path, unknown)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// (De)serializes a [std::time::Duration] as a number of seconds.
+pub(crate) mod secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// (De)serializes values as the strings they are written as in flags.
+mod strings {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::{fmt::Display, str::FromStr};
+
+    pub fn serialize<T: Display, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(ToString::to_string))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|value| value.parse().map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_empty_is_default() {
+        let (config, unknown) = AuraedConfig::parse("").unwrap();
+        assert_eq!(config, AuraedConfig::default());
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_parse() {
+        let (config, unknown) = AuraedConfig::parse(
+            r#"
+            socket = "/run/aurae/aurae.sock"
+            listeners = ["tcp:[::1]:8443"]
+            log_redaction = ["token=Bearer [a-z]+"]
+            uid_map = ["0:100000:65536"]
+            lifetime_stats_interval = 30
+
+            [socket_permissions]
+            mode = 0o660
+            group = 100
+
+            [shutdown]
+            grace_period = 20
+
+            [discovery]
+            persist = true
+
+            [events]
+            persist = false
+
+            [admission]
+            enabled = true
+            enforce = false
+
+            [dns]
+            nameservers = ["10.0.0.10", "fd00::a"]
+            "#,
+        )
+        .unwrap();
+        assert!(unknown.is_empty());
+
+        assert_eq!(config.socket.as_deref(), Some("/run/aurae/aurae.sock"));
+        assert_eq!(
+            config.listeners,
+            vec![ListenerConfig::Tcp { addr: "[::1]:8443".parse().unwrap() }]
+        );
+        assert_eq!(config.log_redaction[0].name, "token");
+        assert_eq!(
+            config.uid_map,
+            vec![IdMapping { container_id: 0, host_id: 100000, size: 65536 }]
+        );
+        assert!(config.gid_map.is_empty());
+        assert_eq!(config.lifetime_stats_interval, Duration::from_secs(30));
+        assert_eq!(
+            config.socket_permissions,
+            SocketPermissions { mode: 0o660, owner: None, group: Some(100) }
+        );
+        assert_eq!(config.shutdown.grace_period, Duration::from_secs(20));
+        // Missing keys of a table are defaults too
+        assert_eq!(
+            config.shutdown.deadline,
+            ShutdownConfig::default().deadline
+        );
+        assert!(config.discovery.persist);
+        assert_eq!(
+            config.discovery.peer_ttl,
+            DiscoveryConfig::default().peer_ttl
+        );
+        assert!(!config.events.persist);
+        assert_eq!(
+            config.events.max_bytes,
+            EventJournalConfig::default().max_bytes
+        );
+        assert_eq!(
+            config.admission,
+            AdmissionConfig {
+                enabled: true,
+                enforce: false,
+                ..AdmissionConfig::default()
+            }
+        );
+        assert_eq!(
+            config.dns,
+            DnsConfig {
+                nameservers: vec![
+                    "10.0.0.10".parse().unwrap(),
+                    "fd00::a".parse().unwrap()
+                ],
+                ..DnsConfig::default()
+            }
+        );
+        assert_eq!(config.runtime_dir, AuraedConfig::default().runtime_dir);
+    }
+
+    #[test]
+    fn test_parse_reports_unknown_keys() {
+        let (_, unknown) = AuraedConfig::parse(
+            r#"
+            runtime_dirs = "/run/aurae"
+            audit = { path = "/var/log/audit.jsonl", max_byte = 1024 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(unknown, vec!["audit.max_byte", "runtime_dirs"]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for config in [
+            r#"listeners = ["/run/aurae/local.sock"]"#,
+            r#"runtime_dir = 1"#,
+            r#"shutdown = { grace_period = "10s" }"#,
+            r#"dns = { nameservers = ["dns.local"] }"#,
+        ] {
+            assert!(AuraedConfig::parse(config).is_err(), "{config}");
+        }
+    }
+
+    #[test]
+    fn test_printed_config_parses_back() {
+        let (config, _) = AuraedConfig::parse(
+            r#"
+            socket = "[::1]:8080"
+            authz_policy = "/etc/aurae/authz.toml"
+            listeners = ["unix:/run/aurae/local.sock,mode=600,group=100"]
+
+            [audit]
+            path = "/var/log/aurae/audit.jsonl"
+            "#,
+        )
+        .unwrap();
+
+        let printed = toml::to_string(&config).unwrap();
+        let (parsed, unknown) = AuraedConfig::parse(&printed).unwrap();
+        assert_eq!(parsed, config);
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_load_missing_config_fails() {
+        let path = std::env::temp_dir()
+            .join(format!("ae-test-config-{}.toml", uuid::Uuid::new_v4()));
+        assert!(matches!(
+            AuraedConfig::load(Some(&path)),
+            Err(ConfigError::Read { .. })
+        ));
+    }
+}
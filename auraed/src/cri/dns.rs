@@ -0,0 +1,245 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::error::{Result, RuntimeServiceError};
+use proto::cri::PodSandboxConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// The file name of the resolv.conf of a pod, in the directory of the pod.
+pub(crate) const RESOLV_CONF: &str = "resolv.conf";
+
+/// The file name of the hosts file of a pod, in the directory of the pod.
+pub(crate) const HOSTS: &str = "hosts";
+
+/// The DNS settings the pods are started with, unless their DNS config
+/// overrides them. Read as each pod starts, so that changing them does not
+/// change the pods already running.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsConfig {
+    /// The nameservers of the pods. Without any, their resolver queries
+    /// their own localhost.
+    pub nameservers: Vec<IpAddr>,
+    /// The search domains of the pods.
+    pub searches: Vec<String>,
+    /// The resolver options of the pods, such as `ndots:2`.
+    pub options: Vec<String>,
+}
+
+/// The `/etc/resolv.conf` and `/etc/hosts` of a pod.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PodDns {
+    resolv_conf: String,
+    hosts: String,
+}
+
+impl PodDns {
+    /// The DNS files of the pod `sandbox_id` configured by `config`. Each of
+    /// the nameservers, search domains and options of its DNS config
+    /// replaces those of `defaults`, if it has any.
+    pub fn new(
+        sandbox_id: &str,
+        config: &PodSandboxConfig,
+        defaults: &DnsConfig,
+    ) -> Result<Self> {
+        let invalid = |reason: String| RuntimeServiceError::InvalidDnsConfig {
+            sandbox_id: sandbox_id.to_string(),
+            reason,
+        };
+
+        let dns = config.dns_config.clone().unwrap_or_default();
+        let nameservers = if dns.servers.is_empty() {
+            defaults.nameservers.clone()
+        } else {
+            dns.servers
+                .iter()
+                .map(|server| {
+                    server.parse::<IpAddr>().map_err(|_| {
+                        invalid(format!("nameserver '{server}' is not an IP"))
+                    })
+                })
+                .collect::<Result<_>>()?
+        };
+        let searches = if dns.searches.is_empty() {
+            &defaults.searches
+        } else {
+            &dns.searches
+        };
+        let options = if dns.options.is_empty() {
+            &defaults.options
+        } else {
+            &dns.options
+        };
+
+        for name in searches.iter().chain(options) {
+            if !is_word(name) {
+                return Err(invalid(format!("'{name}' is not a single word")));
+            }
+        }
+
+        let mut resolv_conf = String::new();
+        for nameserver in &nameservers {
+            let _ = writeln!(resolv_conf, "nameserver {nameserver}");
+        }
+        if !searches.is_empty() {
+            let _ = writeln!(resolv_conf, "search {}", searches.join(" "));
+        }
+        if !options.is_empty() {
+            let _ = writeln!(resolv_conf, "options {}", options.join(" "));
+        }
+
+        // The pod resolves its own name, as well as its hostname
+        let mut names = vec![sandbox_id];
+        if !config.hostname.is_empty() && config.hostname != sandbox_id {
+            names.insert(0, &config.hostname);
+        }
+        for name in &names {
+            if !is_word(name) {
+                return Err(invalid(format!("'{name}' is not a host name")));
+            }
+        }
+
+        let mut hosts = String::new();
+        let _ = writeln!(hosts, "127.0.0.1\tlocalhost");
+        let _ = writeln!(hosts, "::1\tlocalhost ip6-localhost ip6-loopback");
+        let _ = writeln!(hosts, "127.0.1.1\t{}", names.join(" "));
+
+        // Sorted, for the file to be the same for the same hosts
+        let extra_hosts: BTreeMap<_, _> = config.extra_hosts.iter().collect();
+        for (name, ip) in extra_hosts {
+            if !is_word(name) {
+                return Err(invalid(format!("'{name}' is not a host name")));
+            }
+            let ip = ip.parse::<IpAddr>().map_err(|_| {
+                invalid(format!("'{ip}' of host '{name}' is not an IP"))
+            })?;
+            let _ = writeln!(hosts, "{ip}\t{name}");
+        }
+
+        Ok(Self { resolv_conf, hosts })
+    }
+
+    /// Writes the files to the directory of the pod, from where they are
+    /// mounted in its containers.
+    pub fn write_to(&self, pod_dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(pod_dir)?;
+        std::fs::write(pod_dir.join(RESOLV_CONF), &self.resolv_conf)?;
+        std::fs::write(pod_dir.join(HOSTS), &self.hosts)
+    }
+}
+
+/// Whether `name` may be written to resolv.conf or hosts as it is.
+fn is_word(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains('#')
+        && !name.contains(|c: char| c.is_whitespace() || c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::cri::DnsConfig as PodDnsConfig;
+    use std::collections::HashMap;
+
+    fn defaults() -> DnsConfig {
+        DnsConfig {
+            nameservers: vec!["10.0.0.10".parse().unwrap()],
+            searches: vec!["aurae.local".into()],
+            options: vec![],
+        }
+    }
+
+    #[test]
+    fn test_pod_dns_takes_the_defaults() {
+        let dns =
+            PodDns::new("nginx", &PodSandboxConfig::default(), &defaults())
+                .unwrap();
+
+        assert_eq!(
+            dns.resolv_conf,
+            "nameserver 10.0.0.10\nsearch aurae.local\n"
+        );
+        assert_eq!(
+            dns.hosts,
+            "127.0.0.1\tlocalhost\n\
+             ::1\tlocalhost ip6-localhost ip6-loopback\n\
+             127.0.1.1\tnginx\n"
+        );
+    }
+
+    #[test]
+    fn test_pod_dns_config_overrides_the_defaults() {
+        let config = PodSandboxConfig {
+            hostname: "web".into(),
+            dns_config: Some(PodDnsConfig {
+                servers: vec!["fd00::53".into(), "1.1.1.1".into()],
+                searches: vec![],
+                options: vec!["ndots:2".into()],
+            }),
+            extra_hosts: HashMap::from([
+                ("db".into(), "10.1.0.2".into()),
+                ("cache".into(), "fd00::2".into()),
+            ]),
+            ..Default::default()
+        };
+        let dns = PodDns::new("nginx", &config, &defaults()).unwrap();
+
+        assert_eq!(
+            dns.resolv_conf,
+            "nameserver fd00::53\n\
+             nameserver 1.1.1.1\n\
+             search aurae.local\n\
+             options ndots:2\n"
+        );
+        assert!(dns.hosts.ends_with(
+            "127.0.1.1\tweb nginx\n\
+             fd00::2\tcache\n\
+             10.1.0.2\tdb\n"
+        ));
+    }
+
+    #[test]
+    fn test_pod_dns_rejects_invalid_hosts_and_ips() {
+        for config in [
+            PodSandboxConfig {
+                extra_hosts: HashMap::from([("db".into(), "db.local".into())]),
+                ..Default::default()
+            },
+            PodSandboxConfig {
+                extra_hosts: HashMap::from([("a b".into(), "10.1.0.2".into())]),
+                ..Default::default()
+            },
+            PodSandboxConfig {
+                dns_config: Some(PodDnsConfig {
+                    servers: vec!["dns.local".into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ] {
+            assert!(
+                matches!(
+                    PodDns::new("nginx", &config, &defaults()),
+                    Err(RuntimeServiceError::InvalidDnsConfig { .. })
+                ),
+                "{config:?}"
+            );
+        }
+    }
+}
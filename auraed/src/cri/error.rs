@@ -13,10 +13,14 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use client::ClientError;
+use super::rootless::RootlessError;
+use crate::{admission::AdmissionError, blocking::BlockingError};
+use client::{ClientError, ErrorDetails};
+use std::net::SocketAddr;
 use thiserror::Error;
 use tonic::Status;
 use tracing::error;
+use validation::ValidationError;
 
 pub(crate) type Result<T> = std::result::Result<T, RuntimeServiceError>;
 
@@ -26,33 +30,110 @@ pub enum RuntimeServiceError {
     SandboxExists { sandbox_id: String },
     #[error("sandbox '{sandbox_id}' not found")]
     SandboxNotFound { sandbox_id: String },
-    #[error("sandobx '{sandbox_id}' not in exited state")]
-    SandboxNotExited { sandbox_id: String },
     #[error("Failed to kill sandbox '{sandbox_id}': {error}")]
     KillError { sandbox_id: String, error: String },
+    #[error("invalid pod sandbox config: {field}: {reason}")]
+    InvalidSpec { field: String, reason: String },
+    #[error("failed to build the OCI spec of sandbox '{sandbox_id}': {error}")]
+    SpecError { sandbox_id: String, error: String },
+    #[error("failed to create sandbox '{sandbox_id}': {error}")]
+    CreateError { sandbox_id: String, error: String },
+    #[error("failed to remove sandbox '{sandbox_id}': {error}")]
+    RemoveError { sandbox_id: String, error: String },
+    #[error("sandbox '{sandbox_id}' was not run before the deadline")]
+    DeadlineExceeded { sandbox_id: String },
+    #[error("invalid DNS config of sandbox '{sandbox_id}': {reason}")]
+    InvalidDnsConfig { sandbox_id: String, reason: String },
+    #[error(
+        "failed to write the DNS files of sandbox '{sandbox_id}': {source}"
+    )]
+    DnsFilesError { sandbox_id: String, source: std::io::Error },
+    #[error("port {host} of sandbox '{sandbox_id}' is already in use")]
+    PortInUse { sandbox_id: String, host: SocketAddr },
+    #[error(
+        "failed to publish port {host} of sandbox '{sandbox_id}': {source}"
+    )]
+    PublishError {
+        sandbox_id: String,
+        host: SocketAddr,
+        source: std::io::Error,
+    },
     #[error(transparent)]
     ClientError(#[from] ClientError),
+    #[error(transparent)]
+    BlockingError(#[from] BlockingError),
+    #[error(transparent)]
+    AdmissionError(#[from] AdmissionError),
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
+    #[error(transparent)]
+    RootlessError(#[from] RootlessError),
 }
 
 impl From<RuntimeServiceError> for Status {
     fn from(err: RuntimeServiceError) -> Self {
         let msg = err.to_string();
         error!("{msg}");
-        match err {
+        let details = match &err {
+            RuntimeServiceError::InvalidSpec { field, reason } => {
+                Some(ErrorDetails::InvalidSpec {
+                    field: field.clone(),
+                    reason: reason.clone(),
+                })
+            }
+            RuntimeServiceError::ValidationError(e) => {
+                Some(ErrorDetails::InvalidSpec {
+                    field: e.get_field().to_string(),
+                    reason: e.reason(),
+                })
+            }
+            _ => None,
+        };
+        let status = match err {
             RuntimeServiceError::SandboxExists { .. } => {
                 Status::already_exists(msg)
             }
             RuntimeServiceError::SandboxNotFound { .. } => {
                 Status::not_found(msg)
             }
-            RuntimeServiceError::SandboxNotExited { .. } => {
-                Status::failed_precondition(msg)
-            }
             RuntimeServiceError::KillError { .. } => Status::internal(msg),
+            RuntimeServiceError::InvalidSpec { .. } => {
+                Status::invalid_argument(msg)
+            }
+            RuntimeServiceError::SpecError { .. }
+            | RuntimeServiceError::CreateError { .. }
+            | RuntimeServiceError::RemoveError { .. } => Status::internal(msg),
+            RuntimeServiceError::DeadlineExceeded { .. } => {
+                Status::deadline_exceeded(msg)
+            }
+            RuntimeServiceError::InvalidDnsConfig { .. } => {
+                Status::invalid_argument(msg)
+            }
+            RuntimeServiceError::DnsFilesError { .. } => Status::internal(msg),
+            RuntimeServiceError::PortInUse { .. } => {
+                Status::already_exists(msg)
+            }
+            RuntimeServiceError::PublishError { .. } => Status::internal(msg),
             RuntimeServiceError::ClientError(e) => match e {
-                ClientError::ConnectionError(_) => Status::unavailable(msg),
+                ClientError::ConnectionError(_)
+                | ClientError::Disconnected(_)
+                | ClientError::Socket { .. } => Status::unavailable(msg),
+                ClientError::NodeNotFound { .. } => Status::not_found(msg),
                 ClientError::Other(_) => Status::unknown(msg),
             },
+            RuntimeServiceError::BlockingError(_) => Status::internal(msg),
+            RuntimeServiceError::AdmissionError(e) => e.into(),
+            RuntimeServiceError::ValidationError(_) => {
+                Status::invalid_argument(msg)
+            }
+            RuntimeServiceError::RootlessError(_) => {
+                Status::failed_precondition(msg)
+            }
+        };
+
+        match details {
+            Some(details) => details.attach(status),
+            None => status,
         }
     }
-}
\ No newline at end of file
+}
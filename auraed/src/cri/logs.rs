@@ -0,0 +1,180 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The output of the init containers of pod sandboxes, sent to log channels
+//! as the output of executables is.
+
+use crate::logging::log_channel::LogChannel;
+use crate::logging::log_registry::{LogKey, LogRegistry};
+use nix::fcntl::OFlag;
+use nix::unistd::pipe2;
+use proto::observe::LogChannelType;
+use std::io;
+use std::os::fd::OwnedFd;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::unix::pipe;
+
+/// The number of lines of each output retained, to replay to those who
+/// start reading after the pod sandbox started.
+const LOG_HISTORY_LINES: usize = 1000;
+
+/// The read ends of the pipes the init container of a pod sandbox writes
+/// its stdout and stderr to.
+#[derive(Debug)]
+pub(crate) struct OutputPipes {
+    stdout: OwnedFd,
+    stderr: OwnedFd,
+}
+
+impl OutputPipes {
+    /// Creates the pipes, and returns them with the write ends of stdout
+    /// and of stderr, to give to the container.
+    pub fn new() -> io::Result<(Self, OwnedFd, OwnedFd)> {
+        let (stdout, stdout_writer) = pipe2(OFlag::O_CLOEXEC)?;
+        let (stderr, stderr_writer) = pipe2(OFlag::O_CLOEXEC)?;
+        Ok((Self { stdout, stderr }, stdout_writer, stderr_writer))
+    }
+}
+
+/// The log channels of the init container of a pod sandbox, named
+/// `<pod>::stdout` and `<pod>::stderr`. They are registered by the id of the
+/// sandbox, and by the pid of the container if it started.
+#[derive(Debug, Clone)]
+pub(crate) struct PodLogs {
+    sandbox_id: String,
+    pid: Option<i32>,
+    stdout: LogChannel,
+    stderr: LogChannel,
+}
+
+impl PodLogs {
+    /// Registers the channels of the pod sandbox, and forwards the output
+    /// written to `pipes` to them until the container and the processes it
+    /// started close it.
+    pub fn start(
+        sandbox_id: &str,
+        pid: Option<i32>,
+        pipes: OutputPipes,
+    ) -> io::Result<Self> {
+        let stdout = pipe::Receiver::from_owned_fd(pipes.stdout)?;
+        let stderr = pipe::Receiver::from_owned_fd(pipes.stderr)?;
+
+        let logs = Self {
+            sandbox_id: sandbox_id.to_string(),
+            pid,
+            stdout: LogChannel::new(format!("{sandbox_id}::stdout"))
+                .with_history(LOG_HISTORY_LINES),
+            stderr: LogChannel::new(format!("{sandbox_id}::stderr"))
+                .with_history(LOG_HISTORY_LINES),
+        };
+        let _stdout = tokio::spawn(forward(stdout, logs.stdout.clone()));
+        let _stderr = tokio::spawn(forward(stderr, logs.stderr.clone()));
+
+        let registry = LogRegistry::global();
+        for (key, channel) in logs.keys() {
+            registry.register(key, channel.clone());
+        }
+        Ok(logs)
+    }
+
+    /// Deregisters the channels, once the pod sandbox is removed.
+    pub fn deregister(&self) {
+        let registry = LogRegistry::global();
+        for (key, channel) in self.keys() {
+            registry.deregister(key, channel);
+        }
+    }
+
+    fn keys(&self) -> Vec<(LogKey, &LogChannel)> {
+        let mut keys = vec![];
+        for (channel_type, channel) in [
+            (LogChannelType::Stdout, &self.stdout),
+            (LogChannelType::Stderr, &self.stderr),
+        ] {
+            keys.push((
+                LogKey::pod_sandbox(self.sandbox_id.clone(), channel_type),
+                channel,
+            ));
+            if let Some(pid) = self.pid {
+                keys.push((LogKey::process(pid, channel_type), channel));
+            }
+        }
+        keys
+    }
+}
+
+/// Sends each line of `output` to `log_channel` until it is closed. Bytes
+/// which are not valid UTF-8 are replaced, rather than ending the output.
+async fn forward<R: AsyncRead + Unpin>(output: R, log_channel: LogChannel) {
+    let mut output = BufReader::new(output);
+    let mut buf = Vec::new();
+    // The last line may not end with a line break.
+    while let Ok(1..) = output.read_until(b'\n', &mut buf).await {
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        log_channel.send(String::from_utf8_lossy(line).into_owned());
+        buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn lines(channel: &LogChannel) -> Vec<String> {
+        let (items, _) = channel.subscribe_since("test", 0);
+        items.into_iter().map(|item| item.line).collect()
+    }
+
+    #[tokio::test]
+    async fn test_forward_replaces_invalid_utf8() {
+        let channel = LogChannel::new("pod::stdout".into()).with_history(10);
+        let output: &[u8] = b"before\n\xff\xfeinvalid\r\nafter";
+
+        forward(output, channel.clone()).await;
+
+        assert_eq!(
+            lines(&channel),
+            ["before", "\u{fffd}\u{fffd}invalid", "after"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_written_before_subscribing_is_retained() {
+        let (pipes, stdout, _stderr) = OutputPipes::new().unwrap();
+        let logs = PodLogs::start("ae-test-pod", None, pipes).unwrap();
+
+        let mut stdout = std::fs::File::from(stdout);
+        stdout.write_all(b"hello\n").unwrap();
+        drop(stdout);
+
+        let key =
+            LogKey::pod_sandbox("ae-test-pod".into(), LogChannelType::Stdout);
+        let channel = LogRegistry::global().lookup(&key).expect("registered");
+        assert!(channel.same_channel(&logs.stdout));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while lines(&channel).is_empty() {
+            assert!(Instant::now() < deadline, "no output");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(lines(&channel), ["hello"]);
+
+        logs.deregister();
+    }
+}
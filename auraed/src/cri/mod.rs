@@ -46,6 +46,13 @@ pub mod image_service;
 pub mod oci;
 pub mod runtime_service;
 
+mod dns;
 mod error;
+mod logs;
+mod ports;
+mod rootless;
 mod sandbox;
-mod sandbox_cache;
\ No newline at end of file
+mod sandbox_cache;
+
+pub use dns::DnsConfig;
+pub use sandbox_cache::PodSandboxes;
\ No newline at end of file
@@ -42,21 +42,33 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
+use crate::cells::{Cpus, Limit, Mems};
 use oci_spec::runtime::{
-    Capability, LinuxBuilder, LinuxDeviceCgroupBuilder, LinuxNamespaceBuilder,
-    LinuxNamespaceType, LinuxResourcesBuilder, PosixRlimitBuilder,
-    PosixRlimitType,
+    Capability, LinuxBuilder, LinuxCpu, LinuxCpuBuilder,
+    LinuxDeviceCgroupBuilder, LinuxMemory, LinuxMemoryBuilder,
+    LinuxNamespaceBuilder, LinuxNamespaceType, LinuxPids, LinuxPidsBuilder,
+    LinuxResourcesBuilder, PosixRlimitBuilder, PosixRlimitType,
 };
 use oci_spec::runtime::{
-    LinuxCapabilitiesBuilder, MountBuilder, ProcessBuilder, RootBuilder, Spec,
-    SpecBuilder, UserBuilder,
+    LinuxCapabilitiesBuilder, Mount, MountBuilder, ProcessBuilder, RootBuilder,
+    Spec, SpecBuilder, UserBuilder,
 };
 use oci_spec::OciSpecError;
-use proto::cri::PodSandboxConfig;
+use proto::cri::{LinuxContainerResources, PodSandboxConfig};
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use validation::{ValidatedField, ValidationError};
+
+use super::dns::{HOSTS, RESOLV_CONF};
 
 pub struct AuraeOCIBuilder {
     spec_builder: SpecBuilder,
+    /// Mounts appended to the default mounts.
+    mounts: Vec<Mount>,
+    /// Limits set next to the default resources.
+    cpu: Option<LinuxCpu>,
+    memory: Option<LinuxMemory>,
+    pids: Option<LinuxPids>,
 }
 
 impl AuraeOCIBuilder {
@@ -255,7 +267,11 @@ impl AuraeOCIBuilder {
                         "/proc/sys".to_string(),
                         "/proc/sysrq-trigger".to_string(),
                     ]       )
-                    .build().expect("default oci: linux"))
+                    .build().expect("default oci: linux")),
+            mounts: vec![],
+            cpu: None,
+            memory: None,
+            pids: None,
         }
     }
 
@@ -267,7 +283,229 @@ impl AuraeOCIBuilder {
         // Appends the current pod config to the SpecBuilder
         self
     }
+
+    /// Bind mounts the resolv.conf and hosts written to `pod_dir` over
+    /// those of the container, read only.
+    pub fn with_pod_dns(mut self, pod_dir: &Path) -> AuraeOCIBuilder {
+        for (file, destination) in
+            [(RESOLV_CONF, "/etc/resolv.conf"), (HOSTS, "/etc/hosts")]
+        {
+            self.mounts.push(
+                MountBuilder::default()
+                    .destination(destination)
+                    .typ("bind")
+                    .source(pod_dir.join(file))
+                    .options(vec!["bind".to_string(), "ro".to_string()])
+                    .build()
+                    .expect("pod dns mount"),
+            );
+        }
+        self
+    }
+
+    /// Caps the pod at the limits of `resources`, validated as the limits
+    /// of the cells are. Limits which are zero or unset are left out.
+    pub fn with_resources(
+        mut self,
+        resources: &LinuxContainerResources,
+    ) -> Result<AuraeOCIBuilder, ValidationError> {
+        let parent_name = Some("config.linux.resources");
+        let limit = |value: i64, field_name: &str| {
+            (value != 0)
+                .then(|| Limit::validate(Some(value), field_name, parent_name))
+                .transpose()
+        };
+        let set = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+        let shares = limit(resources.cpu_shares, "cpu_shares")?;
+        let quota = limit(resources.cpu_quota, "cpu_quota")?;
+        let period = limit(resources.cpu_period, "cpu_period")?;
+        let cpus = set(&resources.cpuset_cpus)
+            .map(|cpus| Cpus::validate(Some(cpus), "cpuset_cpus", parent_name))
+            .transpose()?;
+        let mems = set(&resources.cpuset_mems)
+            .map(|mems| Mems::validate(Some(mems), "cpuset_mems", parent_name))
+            .transpose()?;
+        if shares.is_some()
+            || quota.is_some()
+            || period.is_some()
+            || cpus.is_some()
+            || mems.is_some()
+        {
+            let mut cpu = LinuxCpuBuilder::default();
+            if let Some(shares) = shares {
+                cpu = cpu.shares(shares.into_inner() as u64);
+            }
+            if let Some(quota) = quota {
+                cpu = cpu.quota(quota.into_inner());
+            }
+            if let Some(period) = period {
+                cpu = cpu.period(period.into_inner() as u64);
+            }
+            if let Some(cpus) = cpus {
+                cpu = cpu.cpus(cpus.into_inner());
+            }
+            if let Some(mems) = mems {
+                cpu = cpu.mems(mems.into_inner());
+            }
+            self.cpu = Some(cpu.build().expect("pod cpu"));
+        }
+
+        let memory_limit =
+            limit(resources.memory_limit_in_bytes, "memory_limit_in_bytes")?;
+        let swap_limit = limit(
+            resources.memory_swap_limit_in_bytes,
+            "memory_swap_limit_in_bytes",
+        )?;
+        if memory_limit.is_some() || swap_limit.is_some() {
+            let mut memory = LinuxMemoryBuilder::default();
+            if let Some(memory_limit) = memory_limit {
+                memory = memory.limit(memory_limit.into_inner());
+            }
+            if let Some(swap_limit) = swap_limit {
+                memory = memory.swap(swap_limit.into_inner());
+            }
+            self.memory = Some(memory.build().expect("pod memory"));
+        }
+
+        // The cgroup v2 value, where "max" is no limit
+        if let Some(pids_max) = resources.unified.get("pids.max") {
+            let field_name = "unified.pids.max";
+            let pids_max = match pids_max.as_str() {
+                "max" => None,
+                pids_max => limit(
+                    pids_max.parse().map_err(|_| ValidationError::Invalid {
+                        field: validation::field_name(field_name, parent_name),
+                    })?,
+                    field_name,
+                )?,
+            };
+            if let Some(pids_max) = pids_max {
+                self.pids = Some(
+                    LinuxPidsBuilder::default()
+                        .limit(pids_max.into_inner())
+                        .build()
+                        .expect("pod pids"),
+                );
+            }
+        }
+
+        Ok(self)
+    }
+
     pub fn build(self) -> Result<Spec, OciSpecError> {
-        self.spec_builder.build()
+        let mut spec = self.spec_builder.build()?;
+        if !self.mounts.is_empty() {
+            let mut mounts = spec.mounts().clone().unwrap_or_default();
+            mounts.extend(self.mounts);
+            let _ = spec.set_mounts(Some(mounts));
+        }
+        if self.cpu.is_some() || self.memory.is_some() || self.pids.is_some() {
+            let mut linux = spec.linux().clone().unwrap_or_default();
+            let mut resources = linux.resources().clone().unwrap_or_default();
+            let _ = resources
+                .set_cpu(self.cpu)
+                .set_memory(self.memory)
+                .set_pids(self.pids);
+            let _ = linux.set_resources(Some(resources));
+            let _ = spec.set_linux(Some(linux));
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// The resources of the spec built with `resources`, as in config.json.
+    fn resources_of(resources: LinuxContainerResources) -> Value {
+        let spec = AuraeOCIBuilder::new()
+            .with_resources(&resources)
+            .unwrap()
+            .build()
+            .unwrap();
+        serde_json::to_value(spec).unwrap()["linux"]["resources"].take()
+    }
+
+    #[test]
+    fn test_resources_are_applied_to_the_spec() {
+        let resources = resources_of(LinuxContainerResources {
+            cpu_shares: 512,
+            cpu_quota: 50_000,
+            cpu_period: 100_000,
+            cpuset_cpus: "0-1".into(),
+            memory_limit_in_bytes: 64 << 20,
+            memory_swap_limit_in_bytes: 128 << 20,
+            unified: HashMap::from([("pids.max".into(), "100".into())]),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            resources["cpu"],
+            json!({
+                "shares": 512,
+                "quota": 50_000,
+                "period": 100_000,
+                "cpus": "0-1",
+            })
+        );
+        assert_eq!(
+            resources["memory"],
+            json!({ "limit": 64 << 20, "swap": 128 << 20 })
+        );
+        assert_eq!(resources["pids"], json!({ "limit": 100 }));
+
+        // The default device rules are kept
+        assert!(resources["devices"].is_array());
+    }
+
+    #[test]
+    fn test_unset_resources_are_left_out() {
+        let resources = resources_of(LinuxContainerResources {
+            unified: HashMap::from([("pids.max".into(), "max".into())]),
+            ..Default::default()
+        });
+
+        assert!(resources.get("cpu").is_none());
+        assert!(resources.get("memory").is_none());
+        assert!(resources.get("pids").is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_invalid_resources_name_their_field() {
+        for (resources, field) in [
+            (
+                LinuxContainerResources {
+                    memory_limit_in_bytes: -1,
+                    ..Default::default()
+                },
+                "config.linux.resources.memory_limit_in_bytes",
+            ),
+            (
+                LinuxContainerResources {
+                    cpuset_cpus: "all".into(),
+                    ..Default::default()
+                },
+                "config.linux.resources.cpuset_cpus",
+            ),
+            (
+                LinuxContainerResources {
+                    unified: HashMap::from([(
+                        "pids.max".into(),
+                        "many".into(),
+                    )]),
+                    ..Default::default()
+                },
+                "config.linux.resources.unified.pids.max",
+            ),
+        ] {
+            let Err(err) = AuraeOCIBuilder::new().with_resources(&resources)
+            else {
+                panic!("{field} must be invalid");
+            };
+            assert_eq!(err.get_field(), field);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Publishes the ports of pod sandboxes. Each port mapping of a sandbox is
+//! a TCP proxy from its host port to its container port on the localhost of
+//! the network namespace of the sandbox.
+
+use super::error::{Result, RuntimeServiceError};
+use crate::cells::bring_up_loopback;
+use nix::sched::{setns, CloneFlags};
+use proto::cri::{PortMapping, Protocol};
+use std::fs::File;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinSet;
+use tracing::warn;
+
+/// A port of a pod sandbox published on the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PublishedPort {
+    pub host: SocketAddr,
+    pub container_port: u16,
+}
+
+impl std::fmt::Display for PublishedPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}->{}/tcp", self.host, self.container_port)
+    }
+}
+
+/// The host ports of a pod sandbox, bound before the sandbox is created so
+/// that a port already in use fails it.
+#[derive(Debug)]
+pub(crate) struct HostPorts(Vec<(PublishedPort, TcpListener)>);
+
+impl HostPorts {
+    /// Binds the host ports of the `mappings` of the pod `sandbox_id`.
+    /// Mappings without a host port are not published.
+    pub async fn bind(
+        sandbox_id: &str,
+        mappings: &[PortMapping],
+    ) -> Result<Self> {
+        let mut listeners = vec![];
+        for (i, mapping) in mappings.iter().enumerate() {
+            let Some(port) = published_port(i, mapping)? else {
+                continue;
+            };
+            let listener = TcpListener::bind(port.host)
+                .await
+                .map_err(|source| bind_error(sandbox_id, port.host, source))?;
+            listeners.push((port, listener));
+        }
+        Ok(Self(listeners))
+    }
+
+    /// Forwards the connections to the host ports into the network
+    /// namespace `netns`, until the forwards are closed or dropped.
+    pub fn forward(self, netns: Arc<File>) -> PortForwards {
+        if self.0.is_empty() {
+            return PortForwards::default();
+        }
+
+        let (close, _) = broadcast::channel(1);
+        let ports = self
+            .0
+            .into_iter()
+            .map(|(port, listener)| {
+                let _ = tokio::spawn(forward(
+                    listener,
+                    port.container_port,
+                    netns.clone(),
+                    close.subscribe(),
+                ));
+                port
+            })
+            .collect();
+        PortForwards { ports, close: Some(close) }
+    }
+}
+
+/// The ports a pod sandbox publishes, while they are forwarded.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PortForwards {
+    ports: Vec<PublishedPort>,
+    /// Stops the forwards once sent to, or once dropped.
+    close: Option<broadcast::Sender<()>>,
+}
+
+impl PortForwards {
+    /// The ports being forwarded.
+    pub fn ports(&self) -> &[PublishedPort] {
+        &self.ports
+    }
+
+    /// Stops forwarding the ports, closing their connections, and frees
+    /// their host ports.
+    pub fn close(&mut self) {
+        if let Some(close) = self.close.take() {
+            // Fails only if the forwards have already stopped
+            let _ = close.send(());
+        }
+        self.ports.clear();
+    }
+}
+
+/// The error binding the host port `host` of the pod `sandbox_id`, where a
+/// port in use is a conflict with another pod or process.
+fn bind_error(
+    sandbox_id: &str,
+    host: SocketAddr,
+    source: io::Error,
+) -> RuntimeServiceError {
+    let sandbox_id = sandbox_id.to_string();
+    match source.kind() {
+        io::ErrorKind::AddrInUse => {
+            RuntimeServiceError::PortInUse { sandbox_id, host }
+        }
+        _ => RuntimeServiceError::PublishError { sandbox_id, host, source },
+    }
+}
+
+/// The port `mapping`, the `i`th of the config, publishes, if any.
+fn published_port(
+    i: usize,
+    mapping: &PortMapping,
+) -> Result<Option<PublishedPort>> {
+    let invalid =
+        |field: &str, reason: &str| RuntimeServiceError::InvalidSpec {
+            field: format!("config.port_mappings[{i}].{field}"),
+            reason: reason.into(),
+        };
+
+    if mapping.host_port == 0 {
+        return Ok(None);
+    }
+    if mapping.protocol() != Protocol::Tcp {
+        return Err(invalid("protocol", "only TCP ports are published"));
+    }
+    let host_port = u16::try_from(mapping.host_port)
+        .map_err(|_| invalid("host_port", "must be a port number"))?;
+    let container_port = u16::try_from(mapping.container_port)
+        .ok()
+        .filter(|port| *port != 0)
+        .ok_or_else(|| invalid("container_port", "must be a port number"))?;
+    let host_ip = if mapping.host_ip.is_empty() {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        mapping
+            .host_ip
+            .parse()
+            .map_err(|_| invalid("host_ip", "must be an IP address"))?
+    };
+
+    Ok(Some(PublishedPort {
+        host: SocketAddr::new(host_ip, host_port),
+        container_port,
+    }))
+}
+
+/// Forwards each connection to `listener` to `container_port` in `netns`,
+/// until told to `close`.
+async fn forward(
+    listener: TcpListener,
+    container_port: u16,
+    netns: Arc<File>,
+    mut close: broadcast::Receiver<()>,
+) {
+    // Dropped with the forward, which aborts the connections
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            _ = close.recv() => break,
+            accepted = listener.accept() => {
+                let mut inbound = match accepted {
+                    Ok((inbound, _)) => inbound,
+                    Err(e) => {
+                        warn!("failed to accept a connection to a pod: {e}");
+                        continue;
+                    }
+                };
+                let netns = netns.clone();
+                let _ = connections.spawn(async move {
+                    let mut outbound =
+                        match connect_in(netns, container_port).await {
+                            Ok(outbound) => outbound,
+                            Err(e) => {
+                                warn!("failed to connect into a pod: {e}");
+                                return;
+                            }
+                        };
+                    let _ = tokio::io::copy_bidirectional(
+                        &mut inbound,
+                        &mut outbound,
+                    )
+                    .await;
+                });
+            }
+            // Forget the connections that are closed
+            Some(_) = connections.join_next() => {}
+        }
+    }
+}
+
+/// Connects to `port` on the localhost of the network namespace `netns`.
+async fn connect_in(netns: Arc<File>, port: u16) -> io::Result<TcpStream> {
+    // The thread moves into the network namespace for good, so it is one of
+    // its own rather than one of the blocking pools
+    let (tx, rx) = oneshot::channel();
+    let _ = std::thread::Builder::new().name("pod-port".into()).spawn(
+        move || {
+            let connected = setns(&*netns, CloneFlags::CLONE_NEWNET)
+                .map_err(io::Error::from)
+                // The loopback of a new network namespace is down
+                .and_then(|_| bring_up_loopback())
+                .and_then(|_| {
+                    std::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+                });
+            let _ = tx.send(connected);
+        },
+    )?;
+
+    let stream = rx
+        .await
+        .map_err(|_| io::Error::other("the pod port thread panicked"))??;
+    stream.set_nonblocking(true)?;
+    TcpStream::from_std(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(host_port: i32, container_port: i32) -> PortMapping {
+        PortMapping { host_port, container_port, ..Default::default() }
+    }
+
+    #[test]
+    fn test_published_port() {
+        assert_eq!(
+            published_port(0, &mapping(8080, 80)).unwrap(),
+            Some(PublishedPort {
+                host: "0.0.0.0:8080".parse().unwrap(),
+                container_port: 80,
+            })
+        );
+        assert_eq!(
+            published_port(
+                0,
+                &PortMapping { host_ip: "::1".into(), ..mapping(8080, 80) }
+            )
+            .unwrap()
+            .unwrap()
+            .to_string(),
+            "[::1]:8080->80/tcp"
+        );
+
+        // Not published without a host port
+        assert_eq!(published_port(0, &mapping(0, 80)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_port_mappings_name_their_field() {
+        for (mapping, field) in [
+            (mapping(70000, 80), "config.port_mappings[1].host_port"),
+            (mapping(8080, 0), "config.port_mappings[1].container_port"),
+            (
+                PortMapping {
+                    host_ip: "localhost".into(),
+                    ..mapping(8080, 80)
+                },
+                "config.port_mappings[1].host_ip",
+            ),
+            (
+                PortMapping {
+                    protocol: Protocol::Udp.into(),
+                    ..mapping(8080, 80)
+                },
+                "config.port_mappings[1].protocol",
+            ),
+        ] {
+            match published_port(1, &mapping) {
+                Err(RuntimeServiceError::InvalidSpec { field: f, .. }) => {
+                    assert_eq!(f, field)
+                }
+                res => panic!("expected {field} to be invalid, got {res:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Running pod sandboxes when auraed is not privileged.
+//!
+//! The init container is started in a user namespace of its own, where root
+//! is the user auraed runs as, and the other ids are those delegated to it in
+//! /etc/subuid and /etc/subgid. libcontainer writes the maps with newuidmap
+//! and newgidmap, as an unprivileged process may not write them itself. The
+//! pods are given cgroups next to that of auraed, which must be delegated to
+//! it.
+
+use crate::cells::IdMapping;
+use caps::{CapSet, Capability};
+use nix::unistd::{access, getegid, geteuid, AccessFlags};
+use oci_spec::runtime::{
+    LinuxIdMapping, LinuxIdMappingBuilder, LinuxNamespaceBuilder,
+    LinuxNamespaceType, MountBuilder, Spec,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const SUBUID: &str = "/etc/subuid";
+const SUBGID: &str = "/etc/subgid";
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+#[derive(Debug, Clone, Error)]
+pub(crate) enum RootlessError {
+    #[error(
+        "rootless pods need subordinate ids: failed to read {file}: {error}"
+    )]
+    Unreadable { file: &'static str, error: String },
+    #[error(
+        "rootless pods need subordinate ids: user '{user}' has none in {file}, add a range such as '{user}:100000:65536' to it"
+    )]
+    NoSubordinateIds { user: String, file: &'static str },
+    #[error(
+        "rootless pods need {program} to map the ids of {file}: install it with the uidmap package (shadow-utils)"
+    )]
+    MissingHelper { program: &'static str, file: &'static str },
+    #[error(
+        "rootless pods need the unified cgroup hierarchy (cgroup v2), which auraed is not in"
+    )]
+    NoUnifiedCgroup,
+    #[error(
+        "rootless pods need a delegated cgroup: {path} is not writable by uid {uid}, run auraed with 'systemd-run --user --scope -p Delegate=yes auraed'"
+    )]
+    CgroupNotDelegated { path: String, uid: u32 },
+}
+
+/// How the pod sandboxes are run when auraed is not privileged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rootless {
+    uid_map: Vec<IdMapping>,
+    gid_map: Vec<IdMapping>,
+    /// The cgroup the cgroups of the pods are created in, from the root of
+    /// the hierarchy.
+    cgroup: PathBuf,
+}
+
+impl Rootless {
+    /// None if auraed is privileged, and pods are run as root. Otherwise,
+    /// the ids and cgroup to run them with, or why they can't be run.
+    pub(crate) fn detect() -> Option<Result<Self, RootlessError>> {
+        if is_privileged() {
+            return None;
+        }
+        Some(Self::new())
+    }
+
+    fn new() -> Result<Self, RootlessError> {
+        let uid = geteuid().as_raw();
+        let gid = getegid().as_raw();
+        // Ranges are delegated by user name, or by uid
+        let user = fs::read_to_string("/etc/passwd")
+            .ok()
+            .and_then(|passwd| user_name(&passwd, uid))
+            .unwrap_or_else(|| uid.to_string());
+
+        let uid_map = id_map(SUBUID, "newuidmap", &user, uid, uid)?;
+        let gid_map = id_map(SUBGID, "newgidmap", &user, uid, gid)?;
+
+        let cgroup = fs::read_to_string("/proc/self/cgroup")
+            .ok()
+            .and_then(|cgroups| pods_cgroup(&cgroups))
+            .ok_or(RootlessError::NoUnifiedCgroup)?;
+        let path = Path::new(CGROUP_ROOT)
+            .join(cgroup.strip_prefix("/").unwrap_or(&cgroup));
+        if access(&path, AccessFlags::W_OK).is_err() {
+            return Err(RootlessError::CgroupNotDelegated {
+                path: path.display().to_string(),
+                uid,
+            });
+        }
+
+        Ok(Self { uid_map, gid_map, cgroup })
+    }
+
+    /// Runs the pod in a user namespace with the maps of auraed, in a cgroup
+    /// of its own, without the mounts and device rules only root may have.
+    pub(crate) fn apply(&self, sandbox_id: &str, spec: &mut Spec) {
+        if let Some(mounts) = spec.mounts_mut() {
+            for mount in mounts.iter_mut() {
+                // A cgroup2 mount needs a cgroup namespace root owns
+                if mount.typ().as_deref() == Some("cgroup") {
+                    *mount = MountBuilder::default()
+                        .destination(mount.destination().clone())
+                        .typ("bind")
+                        .source(CGROUP_ROOT)
+                        .options(vec![
+                            "rbind".to_string(),
+                            "nosuid".to_string(),
+                            "noexec".to_string(),
+                            "nodev".to_string(),
+                            "ro".to_string(),
+                        ])
+                        .build()
+                        .expect("rootless oci: mount /sys/fs/cgroup");
+                }
+            }
+        }
+
+        let mut linux = spec.linux().clone().unwrap_or_default();
+        let mut namespaces = linux.namespaces().clone().unwrap_or_default();
+        namespaces.push(
+            LinuxNamespaceBuilder::default()
+                .typ(LinuxNamespaceType::User)
+                .build()
+                .expect("rootless oci: linux.namespaces"),
+        );
+        let _ = linux
+            .set_namespaces(Some(namespaces))
+            .set_uid_mappings(Some(oci_id_map(&self.uid_map)))
+            .set_gid_mappings(Some(oci_id_map(&self.gid_map)))
+            .set_cgroups_path(Some(
+                self.cgroup.join(format!("aurae-pod-{sandbox_id}")),
+            ));
+        // The device controller of cgroup v2 is an eBPF program, which an
+        // unprivileged process can't attach
+        if let Some(resources) = linux.resources_mut() {
+            let _ = resources.set_devices(None);
+        }
+        let _ = spec.set_linux(Some(linux));
+    }
+}
+
+/// Root with CAP_SYS_ADMIN, as libcontainer requires a user namespace of
+/// any other user.
+fn is_privileged() -> bool {
+    geteuid().is_root()
+        && caps::has_cap(None, CapSet::Effective, Capability::CAP_SYS_ADMIN)
+            .unwrap_or(false)
+}
+
+/// Maps root of the namespace to `id`, and the ids after it to the range
+/// delegated to `user` in `file`, which `program` must be found to map.
+fn id_map(
+    file: &'static str,
+    program: &'static str,
+    user: &str,
+    uid: u32,
+    id: u32,
+) -> Result<Vec<IdMapping>, RootlessError> {
+    let contents = fs::read_to_string(file).map_err(|e| {
+        RootlessError::Unreadable { file, error: e.to_string() }
+    })?;
+    let (start, count) =
+        subordinate_range(&contents, user, uid).ok_or_else(|| {
+            RootlessError::NoSubordinateIds { user: user.to_string(), file }
+        })?;
+    if !in_path(program) {
+        return Err(RootlessError::MissingHelper { program, file });
+    }
+
+    Ok(vec![
+        IdMapping { container_id: 0, host_id: id, size: 1 },
+        IdMapping { container_id: 1, host_id: start, size: count },
+    ])
+}
+
+/// The first range of ids delegated to `user`, by name or by `uid`, in the
+/// contents of /etc/subuid or /etc/subgid, as its start and count.
+fn subordinate_range(
+    contents: &str,
+    user: &str,
+    uid: u32,
+) -> Option<(u32, u32)> {
+    let uid = uid.to_string();
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let owner = fields.next()?;
+            if owner != user && owner != uid {
+                return None;
+            }
+            let start = fields.next()?.parse().ok()?;
+            let count = fields.next()?.parse().ok()?;
+            let mapping =
+                IdMapping { container_id: 1, host_id: start, size: count };
+            mapping.is_valid().then_some((start, count))
+        })
+}
+
+/// The name of `uid` in the contents of /etc/passwd.
+fn user_name(passwd: &str, uid: u32) -> Option<String> {
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id = fields.nth(1)?.parse::<u32>().ok()?;
+        (id == uid).then(|| name.to_string())
+    })
+}
+
+/// The parent of the cgroup of auraed in the unified hierarchy, from the
+/// contents of /proc/self/cgroup, as auraed's own is not left with processes
+/// of its own once it has children.
+fn pods_cgroup(cgroups: &str) -> Option<PathBuf> {
+    let cgroup = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+    let cgroup = Path::new(cgroup);
+    Some(cgroup.parent().unwrap_or(cgroup).to_path_buf())
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+    })
+}
+
+fn oci_id_map(map: &[IdMapping]) -> Vec<LinuxIdMapping> {
+    map.iter()
+        .map(|mapping| {
+            LinuxIdMappingBuilder::default()
+                .container_id(mapping.container_id)
+                .host_id(mapping.host_id)
+                .size(mapping.size)
+                .build()
+                .expect("rootless oci: linux id mapping")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cri::oci::AuraeOCIBuilder;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_subordinate_range_by_name_or_uid() {
+        let subuid = "# comment\nother:200000:65536\nalice:100000:65536\n";
+        assert_eq!(
+            subordinate_range(subuid, "alice", 1000),
+            Some((100000, 65536))
+        );
+        assert_eq!(
+            subordinate_range("1000:300000:1000\n", "alice", 1000),
+            Some((300000, 1000))
+        );
+        assert_eq!(subordinate_range(subuid, "bob", 1001), None);
+        assert_eq!(subordinate_range("alice:100000:0\n", "alice", 1000), None);
+    }
+
+    #[test]
+    fn test_user_name() {
+        let passwd = "root:x:0:0::/root:/bin/sh\nalice:x:1000:1000::/home/alice:/bin/sh\n";
+        assert_eq!(user_name(passwd, 1000), Some("alice".to_string()));
+        assert_eq!(user_name(passwd, 1001), None);
+    }
+
+    #[test]
+    fn test_pods_cgroup_is_the_parent_of_that_of_auraed() {
+        let cgroups = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/auraed.scope\n";
+        assert_eq!(
+            pods_cgroup(cgroups),
+            Some(PathBuf::from(
+                "/user.slice/user-1000.slice/user@1000.service/app.slice"
+            ))
+        );
+        assert_eq!(pods_cgroup("0::/\n"), Some(PathBuf::from("/")));
+        assert_eq!(pods_cgroup("1:name=systemd:/init.scope\n"), None);
+    }
+
+    #[test]
+    fn test_missing_subordinate_ids_name_the_file_to_edit() {
+        let error = RootlessError::NoSubordinateIds {
+            user: "alice".to_string(),
+            file: SUBUID,
+        };
+        assert!(error.to_string().contains("/etc/subuid"));
+        assert!(error.to_string().contains("alice:100000:65536"));
+    }
+
+    #[test]
+    fn test_apply_maps_the_ids_and_cgroup_of_the_pod() {
+        let rootless = Rootless {
+            uid_map: vec![
+                IdMapping { container_id: 0, host_id: 1000, size: 1 },
+                IdMapping { container_id: 1, host_id: 100000, size: 65536 },
+            ],
+            gid_map: vec![
+                IdMapping { container_id: 0, host_id: 1000, size: 1 },
+                IdMapping { container_id: 1, host_id: 100000, size: 65536 },
+            ],
+            cgroup: PathBuf::from("/user.slice"),
+        };
+        let mut spec = AuraeOCIBuilder::new().build().unwrap();
+        rootless.apply("pod", &mut spec);
+        let spec = serde_json::to_value(spec).unwrap();
+
+        let map = json!([
+            { "containerID": 0, "hostID": 1000, "size": 1 },
+            { "containerID": 1, "hostID": 100000, "size": 65536 },
+        ]);
+        assert_eq!(spec["linux"]["uidMappings"], map);
+        assert_eq!(spec["linux"]["gidMappings"], map);
+        assert_eq!(spec["linux"]["cgroupsPath"], "/user.slice/aurae-pod-pod");
+        assert!(spec["linux"]["namespaces"]
+            .as_array()
+            .unwrap()
+            .contains(&json!({ "type": "user" })));
+        assert_eq!(spec["linux"]["resources"]["devices"], Value::Null);
+
+        let cgroup = spec["mounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|mount| mount["destination"] == "/sys/fs/cgroup")
+            .unwrap();
+        assert_eq!(cgroup["type"], "bind");
+        assert_eq!(cgroup["source"], "/sys/fs/cgroup");
+    }
+}
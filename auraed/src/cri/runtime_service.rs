@@ -42,148 +42,642 @@
  *                                                                            *
 \* -------------------------------------------------------------------------- */
 
+use crate::admission::{AdmissionController, Resources, Workload};
+use crate::blocking::{self, BlockingJob, Pool};
 #[allow(unused_imports)]
 use crate::cri::oci::AuraeOCIBuilder;
-use crate::cri::sandbox::SandboxBuilder;
+use crate::cri::sandbox::{Sandbox, SandboxBuilder};
+use crate::deadline::request_deadline;
 use crate::spawn_auraed_oci_to;
 use chrono::Utc;
+use client::cri::labels::{validate_label, LabelSelector};
 use libcontainer;
 use libcontainer::container::builder::ContainerBuilder;
+use libcontainer::container::Container;
 use libcontainer::syscall::syscall::SyscallType;
 use nix::sys::signal::Signal::SIGKILL;
 use proto::cri::{
     runtime_service_server, AttachRequest, AttachResponse,
     CheckpointContainerRequest, CheckpointContainerResponse,
-    ContainerEventResponse, ContainerStatsRequest, ContainerStatsResponse,
-    ContainerStatusRequest, ContainerStatusResponse, CreateContainerRequest,
-    CreateContainerResponse, ExecRequest, ExecResponse, ExecSyncRequest,
-    ExecSyncResponse, GetEventsRequest, ListContainerStatsRequest,
-    ListContainerStatsResponse, ListContainersRequest, ListContainersResponse,
+    ContainerEventResponse, ContainerEventType, ContainerState,
+    ContainerStatsRequest, ContainerStatsResponse, ContainerStatusRequest,
+    ContainerStatusResponse, CreateContainerRequest, CreateContainerResponse,
+    ExecRequest, ExecResponse, ExecSyncRequest, ExecSyncResponse,
+    GetEventsRequest, LinuxContainerResources, LinuxPodSandboxConfig,
+    ListContainerStatsRequest, ListContainerStatsResponse,
+    ListContainersRequest, ListContainersResponse,
     ListMetricDescriptorsRequest, ListMetricDescriptorsResponse,
     ListPodSandboxMetricsRequest, ListPodSandboxMetricsResponse,
     ListPodSandboxRequest, ListPodSandboxResponse, ListPodSandboxStatsRequest,
-    ListPodSandboxStatsResponse, PodSandbox, PodSandboxStatsRequest,
-    PodSandboxStatsResponse, PodSandboxStatusRequest, PodSandboxStatusResponse,
-    PortForwardRequest, PortForwardResponse, RemoveContainerRequest,
-    RemoveContainerResponse, RemovePodSandboxRequest, RemovePodSandboxResponse,
-    ReopenContainerLogRequest, ReopenContainerLogResponse,
-    RunPodSandboxRequest, RunPodSandboxResponse, StartContainerRequest,
-    StartContainerResponse, StatusRequest, StatusResponse,
-    StopContainerRequest, StopContainerResponse, StopPodSandboxRequest,
-    StopPodSandboxResponse, UpdateContainerResourcesRequest,
-    UpdateContainerResourcesResponse, UpdateRuntimeConfigRequest,
-    UpdateRuntimeConfigResponse, VersionRequest, VersionResponse,
+    ListPodSandboxStatsResponse, PodSandbox, PodSandboxConfig,
+    PodSandboxFilter, PodSandboxState, PodSandboxStatsRequest,
+    PodSandboxStatsResponse, PodSandboxStatus, PodSandboxStatusRequest,
+    PodSandboxStatusResponse, PortForwardRequest, PortForwardResponse,
+    RemoveContainerRequest, RemoveContainerResponse, RemovePodSandboxRequest,
+    RemovePodSandboxResponse, ReopenContainerLogRequest,
+    ReopenContainerLogResponse, RunPodSandboxRequest, RunPodSandboxResponse,
+    StartContainerRequest, StartContainerResponse, StatusRequest,
+    StatusResponse, StopContainerRequest, StopContainerResponse,
+    StopPodSandboxRequest, StopPodSandboxResponse,
+    UpdateContainerResourcesRequest, UpdateContainerResourcesResponse,
+    UpdateRuntimeConfigRequest, UpdateRuntimeConfigResponse, VersionRequest,
+    VersionResponse,
 };
+use std::collections::HashMap;
+use std::fs::File;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::{fs, io};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::Instant;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
+use tracing::{info, warn};
 
-use super::{error::RuntimeServiceError, sandbox_cache::SandboxCache};
+use super::{
+    dns::PodDns,
+    error::{self, RuntimeServiceError},
+    logs::{OutputPipes, PodLogs},
+    ports::HostPorts,
+    rootless::{Rootless, RootlessError},
+    sandbox_cache::{PodSandboxes, SandboxCache},
+};
 
 // The string to refer to the nested runtime spaces for recursive Auraed environments.
 const AURAE_SELF_IDENTIFIER: &str = "_aurae";
 
+/// The number of events buffered for each watcher.
+const EVENTS_CAPACITY: usize = 64;
+
 #[derive(Debug, Clone)]
 pub struct RuntimeService {
     sandboxes: Arc<Mutex<SandboxCache>>,
+    admission: Option<AdmissionController>,
+    /// The events of the pod sandboxes, for those watching them.
+    events: broadcast::Sender<ContainerEventResponse>,
+    /// How the pod sandboxes are run when auraed is not privileged, or why
+    /// they can't be. None when auraed is privileged.
+    rootless: Option<Result<Rootless, RootlessError>>,
 }
 
 impl RuntimeService {
     pub fn new() -> Self {
-        RuntimeService { sandboxes: Default::default() }
+        let rootless = Rootless::detect();
+        match &rootless {
+            None => info!("running pod sandboxes as root"),
+            Some(Ok(_)) => info!("running pod sandboxes rootless"),
+            Some(Err(e)) => warn!("pod sandboxes can't be run: {e}"),
+        }
+
+        RuntimeService {
+            sandboxes: Default::default(),
+            admission: None,
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            rootless,
+        }
     }
-}
 
-#[tonic::async_trait]
-impl runtime_service_server::RuntimeService for RuntimeService {
-    async fn version(
-        &self,
-        _request: Request<VersionRequest>,
-    ) -> Result<Response<VersionResponse>, Status> {
-        todo!()
+    /// Admits the pod sandboxes against the capacity of the host, committing
+    /// their resources with those of the other workloads of `admission`.
+    pub fn with_admission(mut self, admission: AdmissionController) -> Self {
+        self.admission = Some(admission);
+        self
     }
 
-    /// Run a pod with the Aurae runtime daemon.
+    /// A handle on the pod sandboxes, to find the pod sandbox of a process.
+    pub fn pod_sandboxes(&self) -> PodSandboxes {
+        PodSandboxes(self.sandboxes.clone())
+    }
+
+    /// Runs a pod sandbox, unless the `deadline` passes before it starts
+    /// to.
     async fn run_pod_sandbox(
         &self,
-        request: Request<RunPodSandboxRequest>,
-    ) -> Result<Response<RunPodSandboxResponse>, Status> {
-        // TODO: RuntimeServiceErrors
-
-        // Handle Request
-        let r = request.into_inner();
+        request: RunPodSandboxRequest,
+        deadline: Option<Instant>,
+    ) -> error::Result<RunPodSandboxResponse> {
         // Handle Config
-        let config = r.config.expect("config from pod sandbox request");
+        let config = request.config.ok_or_else(|| missing("config"))?;
         // Check for Windows config (currently unsupported)
-        let windows = config.clone().windows;
-        if windows.is_some() {
-            panic!("Windows architecture is currently unsupported.") // TODO Unsure if we want to panic here?
+        if config.windows.is_some() {
+            return Err(RuntimeServiceError::InvalidSpec {
+                field: "config.windows".into(),
+                reason: "Windows pod sandboxes are unsupported".into(),
+            });
         }
 
-        let mut sandboxes = self.sandboxes.lock().await;
-
         // Extract the metadata (name, uid, etc)
-        let metadata = config.clone().metadata.expect("metadata from config");
+        let metadata = config
+            .metadata
+            .clone()
+            .ok_or_else(|| missing("config.metadata"))?;
         let sandbox_id = metadata.name;
+        validate_sandbox_id(&sandbox_id)?;
+        validate_labels(&config.labels)?;
         // Extract the Linux config (OCI and runtime parameters, security context, etc)
-        let _linux =
-            config.clone().linux.expect("linux from pod sandbox config");
-        let oci_builder =
-            AuraeOCIBuilder::new().overload_pod_sandbox_config(config);
+        let linux =
+            config.linux.clone().ok_or_else(|| missing("config.linux"))?;
+
+        let runtime = crate::AURAED_RUNTIME.get().expect("runtime");
+        let dns = PodDns::new(&sandbox_id, &config, &runtime.dns)?;
+        let mut oci_builder = AuraeOCIBuilder::new()
+            .with_pod_dns(&runtime.pods_dir().join(&sandbox_id))
+            .overload_pod_sandbox_config(config.clone());
+        if let Some(resources) = &linux.resources {
+            oci_builder = oci_builder.with_resources(resources)?;
+        }
 
         // TODO Switch on "KernelSpec" which is a field that we will add to the RunPodSandboxRequest message
         // TODO Switch on KernelSpec (if exists) and toggle between "VM Mode" and "Container Mode"
         // TODO Switch on "WASM" which is a field that we will add to the RunPodSandboxRequest
         // TODO We made the decision to create a "KernelSpec" *name structure that will be how we distinguish between VMs and Containers
 
-        let sandbox = {
-            // Initialize a new container builder with the AURAE_SELF_IDENTIFIER name as the "init" container running a recursive Auraed
-            let container_builder = ContainerBuilder::new(
-                AURAE_SELF_IDENTIFIER.to_string(),
-                SyscallType::default(),
-            );
+        let mut spec = oci_builder.build().map_err(|e| {
+            RuntimeServiceError::SpecError {
+                sandbox_id: sandbox_id.clone(),
+                error: e.to_string(),
+            }
+        })?;
+        // The same image runs in a user namespace when auraed is unprivileged
+        if let Some(rootless) = &self.rootless {
+            rootless
+                .as_ref()
+                .map_err(Clone::clone)?
+                .apply(&sandbox_id, &mut spec);
+        }
 
-            let bundle_path = crate::AURAED_RUNTIME
-                .get()
-                .expect("runtime")
-                .bundles_dir()
-                .join(AURAE_SELF_IDENTIFIER);
-
-            // Spawn auraed here
-            // TODO Check if sandbox already exists?
-            let _spawned = spawn_auraed_oci_to(
-                bundle_path.clone(),
-                oci_builder.build().expect("building pod oci spec"),
-            );
+        let exceeded = |_| RuntimeServiceError::DeadlineExceeded {
+            sandbox_id: sandbox_id.clone(),
+        };
+        let mut sandboxes = match deadline {
+            Some(deadline) => {
+                tokio::time::timeout_at(deadline, self.sandboxes.lock())
+                    .await
+                    .map_err(exceeded)?
+            }
+            None => self.sandboxes.lock().await,
+        };
+        if sandboxes.get(&sandbox_id).is_ok() {
+            return Err(RuntimeServiceError::SandboxExists { sandbox_id });
+        }
 
-            let pod_path = crate::AURAED_RUNTIME
-                .get()
-                .expect("runtime")
-                .pods_dir()
-                .join(sandbox_id.clone());
-
-            // Define the init container startup environment
-            let mut init_container = container_builder
-                .with_root_path(pod_path)
-                .expect("Setting pods directory")
-                .as_init(bundle_path)
-                .with_systemd(false)
-                .build()
-                .expect("failed building pod sandbox: ensure valid OCI spec and proper container starting point");
-
-            // Start the init container
-            init_container.start().expect("starting pod sandbox");
-
-            // Assemble the pod sandbox from the init container
-            let sandbox_builder =
-                SandboxBuilder::new(sandbox_id.clone(), init_container);
-            sandbox_builder.build()
+        // Admitted once its spec is built, and committed again as it was if
+        // the sandbox is not created.
+        let admitted = match &self.admission {
+            Some(admission) => Some(Admitted::admit(
+                admission,
+                Workload::Pod(sandbox_id.clone()),
+                pod_resources(&linux),
+            )?),
+            None => None,
         };
 
+        // Bound before the sandbox is created, so a port in use fails it
+        let host_ports =
+            HostPorts::bind(&sandbox_id, &config.port_mappings).await?;
+
+        let (mut sandbox, outputs) = blocking::run(CreateSandbox {
+            sandbox_id: sandbox_id.clone(),
+            config,
+            spec,
+            dns,
+        })
+        .await
+        .map_err(RuntimeServiceError::from)
+        .and_then(|sandbox| sandbox)?;
+        if let Some(netns) = &sandbox.netns {
+            sandbox.ports = host_ports.forward(netns.clone());
+        }
+        let pid = sandbox.init.pid().map(|pid| pid.as_raw());
+        sandbox.logs = match PodLogs::start(&sandbox_id, pid, outputs) {
+            Ok(logs) => Some(logs),
+            Err(e) => {
+                warn!("failed to read the output of pod sandbox '{sandbox_id}': {e}");
+                None
+            }
+        };
+
+        let state = container_state(sandbox.init.status());
         sandboxes.add(sandbox_id.clone(), sandbox)?;
+        if let Some(admitted) = admitted {
+            admitted.keep();
+        }
+
+        self.publish(
+            &sandbox_id,
+            ContainerEventType::ContainerCreatedEvent,
+            ContainerState::ContainerCreated,
+        );
+        if state == ContainerState::ContainerRunning {
+            self.publish(
+                &sandbox_id,
+                ContainerEventType::ContainerStartedEvent,
+                state,
+            );
+        }
+
+        Ok(RunPodSandboxResponse { pod_sandbox_id: sandbox_id })
+    }
+
+    /// Removes a pod sandbox, running or not.
+    async fn remove_pod_sandbox(
+        &self,
+        sandbox_id: String,
+    ) -> error::Result<()> {
+        let mut sandboxes = self.sandboxes.lock().await;
+        let sandbox = sandboxes.get(&sandbox_id)?;
+        let (init, logs) = (sandbox.init.clone(), sandbox.logs.clone());
+        blocking::run(RemoveSandbox { sandbox_id: sandbox_id.clone(), init })
+            .await
+            .map_err(RuntimeServiceError::from)
+            .and_then(|removed| removed)?;
+        sandboxes.remove(&sandbox_id)?;
+        if let Some(logs) = logs {
+            logs.deregister();
+        }
+
+        self.publish(
+            &sandbox_id,
+            ContainerEventType::ContainerDeletedEvent,
+            ContainerState::ContainerExited,
+        );
+        if let Some(admission) = &self.admission {
+            admission.release(&Workload::Pod(sandbox_id));
+        }
+        Ok(())
+    }
+
+    /// Sends an event of the init container of a pod sandbox, which has the
+    /// id of the sandbox, to those watching.
+    fn publish(
+        &self,
+        sandbox_id: &str,
+        event_type: ContainerEventType,
+        state: ContainerState,
+    ) {
+        // Fails only if no one is watching
+        let _ = self.events.send(ContainerEventResponse {
+            container_id: sandbox_id.to_string(),
+            container_event_type: event_type.into(),
+            created_at: Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            pod_sandbox_status: None,
+            containers_statuses: vec![proto::cri::ContainerStatus {
+                id: sandbox_id.to_string(),
+                state: state.into(),
+                ..Default::default()
+            }],
+        });
+    }
+}
+
+/// The state of a container, as in the CRI.
+fn container_state(
+    status: libcontainer::container::ContainerStatus,
+) -> ContainerState {
+    use libcontainer::container::ContainerStatus;
+    match status {
+        ContainerStatus::Creating | ContainerStatus::Created => {
+            ContainerState::ContainerCreated
+        }
+        ContainerStatus::Running | ContainerStatus::Paused => {
+            ContainerState::ContainerRunning
+        }
+        ContainerStatus::Stopped => ContainerState::ContainerExited,
+    }
+}
+
+/// The state of a pod sandbox, which is ready while its init container
+/// runs.
+fn sandbox_state(
+    status: libcontainer::container::ContainerStatus,
+) -> PodSandboxState {
+    match container_state(status) {
+        ContainerState::ContainerRunning => PodSandboxState::SandboxReady,
+        _ => PodSandboxState::SandboxNotready,
+    }
+}
+
+/// Whether a pod sandbox is one of those `filter` selects, where `selector`
+/// is the label selector of the filter.
+fn matches_filter(
+    filter: &PodSandboxFilter,
+    selector: &LabelSelector,
+    sandbox: &PodSandbox,
+) -> bool {
+    (filter.id.is_empty() || sandbox.id == filter.id)
+        && filter
+            .state
+            .as_ref()
+            .is_none_or(|state| state.state == sandbox.state)
+        && selector.matches(&sandbox.labels)
+}
+
+/// The label selector of `filter`.
+fn label_selector(filter: &PodSandboxFilter) -> error::Result<LabelSelector> {
+    LabelSelector::from_filter(&filter.label_selector).map_err(|e| {
+        RuntimeServiceError::InvalidSpec {
+            field: "filter.label_selector".into(),
+            reason: e.to_string(),
+        }
+    })
+}
+
+/// The resources a pod sandbox commits: the limits of the pod and its
+/// overhead. Unlimited resources are not committed.
+fn pod_resources(linux: &LinuxPodSandboxConfig) -> Resources {
+    let resources = |resources: &LinuxContainerResources| Resources {
+        memory_bytes: u64::try_from(resources.memory_limit_in_bytes)
+            .unwrap_or(0),
+        cpu_millis: Resources::cpu_millis(
+            u64::try_from(resources.cpu_quota).unwrap_or(0),
+            u64::try_from(resources.cpu_period).unwrap_or(0),
+        ),
+        pids: resources
+            .unified
+            .get("pids.max")
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(0),
+    };
+
+    [&linux.resources, &linux.overhead]
+        .into_iter()
+        .flatten()
+        .map(resources)
+        .fold(Resources::default(), Resources::saturating_add)
+}
+
+/// The resources of a pod sandbox admitted before it is created, which are
+/// committed again as they were once dropped, unless kept.
+struct Admitted {
+    admission: AdmissionController,
+    workload: Workload,
+    previous: Option<Option<Resources>>,
+}
+
+impl Admitted {
+    fn admit(
+        admission: &AdmissionController,
+        workload: Workload,
+        resources: Resources,
+    ) -> error::Result<Self> {
+        let previous = admission.committed(&workload);
+        admission.admit(workload.clone(), resources)?;
+        Ok(Self {
+            admission: admission.clone(),
+            workload,
+            previous: Some(previous),
+        })
+    }
+
+    /// Keeps the resources committed, as the sandbox was created.
+    fn keep(mut self) {
+        self.previous = None;
+    }
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.admission.restore(self.workload.clone(), previous);
+        }
+    }
+}
 
-        Ok(Response::new(RunPodSandboxResponse { pod_sandbox_id: sandbox_id }))
+/// The id of a pod sandbox names its directories, so it must be a single
+/// path component.
+fn validate_sandbox_id(sandbox_id: &str) -> error::Result<()> {
+    if sandbox_id.is_empty()
+        || sandbox_id == "."
+        || sandbox_id == ".."
+        || sandbox_id.contains(['/', '\0'])
+    {
+        return Err(RuntimeServiceError::InvalidSpec {
+            field: "config.metadata.name".into(),
+            reason: "must be a single path component".into(),
+        });
+    }
+    Ok(())
+}
+
+/// Pod sandboxes are listed by their labels, so they must be valid labels.
+/// Their annotations are free-form.
+fn validate_labels(labels: &HashMap<String, String>) -> error::Result<()> {
+    for (key, value) in labels {
+        validate_label(key, value).map_err(|e| {
+            RuntimeServiceError::InvalidSpec {
+                field: "config.labels".into(),
+                reason: e.to_string(),
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// The error of a required `field` of a request that is missing.
+fn missing(field: &str) -> RuntimeServiceError {
+    RuntimeServiceError::InvalidSpec {
+        field: field.into(),
+        reason: "missing".into(),
+    }
+}
+
+/// Spawns the nested auraed for a pod sandbox, and starts it as the init
+/// container of the sandbox.
+struct CreateSandbox {
+    sandbox_id: String,
+    config: PodSandboxConfig,
+    spec: oci_spec::runtime::Spec,
+    dns: PodDns,
+}
+
+impl BlockingJob for CreateSandbox {
+    /// The sandbox, and the pipes its init container writes its output to.
+    type Output = error::Result<(Sandbox, OutputPipes)>;
+
+    const POOL: Pool = Pool::MountOps;
+
+    fn run(self) -> Self::Output {
+        let CreateSandbox { sandbox_id, config, spec, dns } = self;
+
+        // The output of the init container is read into the log channels of
+        // the pod, as that of executables is
+        let (outputs, stdout, stderr) = OutputPipes::new().map_err(|e| {
+            RuntimeServiceError::CreateError {
+                sandbox_id: sandbox_id.clone(),
+                error: format!("failed to create the output pipes: {e}"),
+            }
+        })?;
+
+        // Initialize a new container builder with the AURAE_SELF_IDENTIFIER name as the "init" container running a recursive Auraed
+        let container_builder = ContainerBuilder::new(
+            AURAE_SELF_IDENTIFIER.to_string(),
+            SyscallType::default(),
+        )
+        .with_stdout(stdout)
+        .with_stderr(stderr);
+
+        // Each pod has a bundle of its own, removed with the pod
+        let bundle_path = crate::AURAED_RUNTIME
+            .get()
+            .expect("runtime")
+            .bundles_dir()
+            .join(&sandbox_id);
+
+        // Spawn auraed here
+        spawn_auraed_oci_to(bundle_path.clone(), spec).map_err(|e| {
+            RuntimeServiceError::CreateError {
+                sandbox_id: sandbox_id.clone(),
+                error: format!("{e:#}"),
+            }
+        })?;
+
+        let pod_path = crate::AURAED_RUNTIME
+            .get()
+            .expect("runtime")
+            .pods_dir()
+            .join(&sandbox_id);
+
+        // Written for each pod, for it to keep the files it started with
+        dns.write_to(&pod_path).map_err(|source| {
+            RuntimeServiceError::DnsFilesError {
+                sandbox_id: sandbox_id.clone(),
+                source,
+            }
+        })?;
+
+        let create_error = |e: libcontainer::error::LibcontainerError| {
+            RuntimeServiceError::CreateError {
+                sandbox_id: sandbox_id.clone(),
+                error: e.to_string(),
+            }
+        };
+
+        // Define the init container startup environment
+        let mut init_container = container_builder
+            .with_root_path(pod_path)
+            .map_err(create_error)?
+            .as_init(&bundle_path)
+            .with_systemd(false)
+            .build()
+            .map_err(create_error)?;
+
+        // Start the init container
+        init_container.start().map_err(create_error)?;
+
+        // Held open for the ports of the pod to be forwarded into
+        let netns = init_container
+            .pid()
+            .map(|pid| File::open(format!("/proc/{pid}/ns/net")))
+            .transpose()
+            .map_err(|e| RuntimeServiceError::CreateError {
+                sandbox_id: sandbox_id.clone(),
+                error: format!("failed to open the network namespace: {e}"),
+            })?;
+
+        // Assemble the pod sandbox from the init container
+        let mut sandbox_builder =
+            SandboxBuilder::new(sandbox_id, init_container)
+                .config(config)
+                .bundle(bundle_path);
+        if let Some(netns) = netns {
+            sandbox_builder = sandbox_builder.netns(netns);
+        }
+        Ok((sandbox_builder.build(), outputs))
+    }
+}
+
+/// Deletes the init container of a pod sandbox, killing it first if it is
+/// still running, and removes the bundle and the files of the pod.
+struct RemoveSandbox {
+    sandbox_id: String,
+    init: Container,
+}
+
+impl BlockingJob for RemoveSandbox {
+    type Output = error::Result<()>;
+
+    const POOL: Pool = Pool::MountOps;
+
+    fn run(self) -> Self::Output {
+        let RemoveSandbox { sandbox_id, mut init } = self;
+        let remove_error = |error: String| RuntimeServiceError::RemoveError {
+            sandbox_id: sandbox_id.clone(),
+            error,
+        };
+
+        // Forced, so a running init container is killed before it is deleted
+        init.delete(true).map_err(|e| remove_error(e.to_string()))?;
+
+        let runtime = crate::AURAED_RUNTIME.get().expect("runtime");
+        for dir in [
+            runtime.pods_dir().join(&sandbox_id),
+            runtime.bundles_dir().join(&sandbox_id),
+        ] {
+            match fs::remove_dir_all(&dir) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(remove_error(format!(
+                        "{}: {e}",
+                        dir.display()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Refreshes the status of the init containers of pod sandboxes from their
+/// state, as their processes may have exited since they were last looked
+/// at. A container whose state is gone is stopped.
+struct RefreshStatus(Vec<Container>);
+
+impl BlockingJob for RefreshStatus {
+    type Output = Vec<libcontainer::container::ContainerStatus>;
+
+    const POOL: Pool = Pool::MountOps;
+
+    fn run(self) -> Self::Output {
+        self.0
+            .into_iter()
+            .map(|mut init| {
+                let refreshed = init.refresh_state().and_then(|init| {
+                    init.refresh_status()?;
+                    Ok(init.status())
+                });
+                refreshed.unwrap_or(
+                    libcontainer::container::ContainerStatus::Stopped,
+                )
+            })
+            .collect()
+    }
+}
+
+#[tonic::async_trait]
+impl runtime_service_server::RuntimeService for RuntimeService {
+    async fn version(
+        &self,
+        _request: Request<VersionRequest>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        todo!()
+    }
+
+    /// Run a pod with the Aurae runtime daemon.
+    async fn run_pod_sandbox(
+        &self,
+        request: Request<RunPodSandboxRequest>,
+    ) -> Result<Response<RunPodSandboxResponse>, Status> {
+        let deadline = request_deadline(&request);
+
+        // Detached, so a client that disconnects mid-run can't leave an
+        // init container started, but not in the sandboxes.
+        let service = self.clone();
+        let run = tokio::spawn(async move {
+            service.run_pod_sandbox(request.into_inner(), deadline).await
+        });
+
+        let response =
+            run.await.map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(response?))
     }
 
     async fn stop_pod_sandbox(
@@ -194,9 +688,18 @@ impl runtime_service_server::RuntimeService for RuntimeService {
 
         let mut sandboxes = self.sandboxes.lock().await;
         let sandbox = sandboxes.get_mut(&sandbox_id)?;
+        sandbox.ports.close();
         sandbox.init.kill(SIGKILL, false).map_err(|e| {
-            RuntimeServiceError::KillError { sandbox_id, error: e.to_string() }
+            RuntimeServiceError::KillError {
+                sandbox_id: sandbox_id.clone(),
+                error: e.to_string(),
+            }
         })?;
+        self.publish(
+            &sandbox_id,
+            ContainerEventType::ContainerStoppedEvent,
+            container_state(sandbox.init.status()),
+        );
         Ok(Response::new(StopPodSandboxResponse {}))
     }
 
@@ -205,15 +708,15 @@ impl runtime_service_server::RuntimeService for RuntimeService {
         request: Request<RemovePodSandboxRequest>,
     ) -> Result<Response<RemovePodSandboxResponse>, Status> {
         let sandbox_id = request.into_inner().pod_sandbox_id;
-        let mut sandboxes = self.sandboxes.lock().await;
-        if sandboxes.get(&sandbox_id)?.init.status()
-            != libcontainer::container::ContainerStatus::Stopped
-        {
-            return Err(
-                RuntimeServiceError::SandboxNotExited { sandbox_id }.into()
-            );
-        }
-        sandboxes.remove(&sandbox_id)?;
+
+        // Detached, so a client that disconnects mid-remove can't leave the
+        // sandbox deleted, but still in the sandboxes.
+        let service = self.clone();
+        let remove = tokio::spawn(async move {
+            service.remove_pod_sandbox(sandbox_id).await
+        });
+
+        remove.await.map_err(|e| Status::internal(e.to_string()))??;
         Ok(Response::new(RemovePodSandboxResponse {}))
     }
 
@@ -221,19 +724,53 @@ impl runtime_service_server::RuntimeService for RuntimeService {
         &self,
         request: Request<PodSandboxStatusRequest>,
     ) -> Result<Response<PodSandboxStatusResponse>, Status> {
-        let sandbox_id = request.into_inner().pod_sandbox_id;
+        let PodSandboxStatusRequest { pod_sandbox_id: sandbox_id, verbose } =
+            request.into_inner();
         let sandboxes = self.sandboxes.lock().await;
-        let state = sandboxes.get(&sandbox_id)?.init.status();
-        // FIXME: this needs to be mapped more correctly.
-        let container_status = proto::cri::ContainerStatus {
-            id: sandbox_id,
-            state: state as i32,
+        let sandbox = sandboxes.get(&sandbox_id)?;
+        let status = blocking::run(RefreshStatus(vec![sandbox.init.clone()]))
+            .await
+            .map_err(RuntimeServiceError::from)?
+            .pop()
+            .unwrap_or(libcontainer::container::ContainerStatus::Stopped);
 
+        let container_status = proto::cri::ContainerStatus {
+            id: sandbox_id.clone(),
+            state: container_state(status).into(),
+            created_at: sandbox.created_at,
             ..Default::default()
         };
+        let mut info = HashMap::new();
+        if verbose {
+            if let Some(pid) = sandbox.init.pid() {
+                let _ = info.insert("pid".to_string(), pid.to_string());
+            }
+            let _ = info.insert(
+                "bundle".to_string(),
+                sandbox.bundle.display().to_string(),
+            );
+            // The ports published on the host, until the sandbox is stopped
+            let ports: Vec<String> = sandbox
+                .ports
+                .ports()
+                .iter()
+                .map(|port| port.to_string())
+                .collect();
+            if !ports.is_empty() {
+                let _ = info.insert("ports".to_string(), ports.join(","));
+            }
+        }
         Ok(Response::new(PodSandboxStatusResponse {
-            status: None,
-            info: Default::default(),
+            status: Some(PodSandboxStatus {
+                id: sandbox_id,
+                metadata: sandbox.config.metadata.clone(),
+                state: sandbox_state(status).into(),
+                created_at: sandbox.created_at,
+                labels: sandbox.config.labels.clone(),
+                annotations: sandbox.config.annotations.clone(),
+                ..Default::default()
+            }),
+            info,
             containers_statuses: vec![container_status],
             timestamp: Utc::now().timestamp(),
         }))
@@ -241,21 +778,40 @@ impl runtime_service_server::RuntimeService for RuntimeService {
 
     async fn list_pod_sandbox(
         &self,
-        _request: Request<ListPodSandboxRequest>,
+        request: Request<ListPodSandboxRequest>,
     ) -> Result<Response<ListPodSandboxResponse>, Status> {
-        // TODO: filter
+        let filter = request.into_inner().filter.unwrap_or_default();
+        let selector = label_selector(&filter)?;
         let sandboxes = self.sandboxes.lock().await;
-        let all_sandboxes = sandboxes.list()?;
-        Ok(Response::new(ListPodSandboxResponse {
-            items: all_sandboxes
-                .iter()
-                .map(|s| PodSandbox {
-                    // TODO: other fields
-                    id: s.init.id().to_string(),
-                    ..Default::default()
-                })
-                .collect(),
-        }))
+        // Only the sandbox of the id, if any, is refreshed
+        let sandboxes: Vec<&Sandbox> = sandboxes
+            .list()?
+            .into_iter()
+            .filter(|sandbox| {
+                filter.id.is_empty() || sandbox.name() == filter.id
+            })
+            .collect();
+        let statuses = blocking::run(RefreshStatus(
+            sandboxes.iter().map(|sandbox| sandbox.init.clone()).collect(),
+        ))
+        .await
+        .map_err(RuntimeServiceError::from)?;
+
+        let items = sandboxes
+            .into_iter()
+            .zip(statuses)
+            .map(|(sandbox, status)| PodSandbox {
+                id: sandbox.name().to_string(),
+                metadata: sandbox.config.metadata.clone(),
+                state: sandbox_state(status).into(),
+                created_at: sandbox.created_at,
+                labels: sandbox.config.labels.clone(),
+                annotations: sandbox.config.annotations.clone(),
+                ..Default::default()
+            })
+            .filter(|item| matches_filter(&filter, &selector, item))
+            .collect();
+        Ok(Response::new(ListPodSandboxResponse { items }))
     }
 
     async fn create_container(
@@ -404,11 +960,35 @@ impl runtime_service_server::RuntimeService for RuntimeService {
     type GetContainerEventsStream =
         ReceiverStream<Result<ContainerEventResponse, Status>>;
 
+    /// Streams the events of the init containers of the pod sandboxes from
+    /// now on. The stream ends if the events are not read fast enough, as
+    /// some were missed, for the watcher to look at the pod sandboxes again.
     async fn get_container_events(
         &self,
         _request: Request<GetEventsRequest>,
     ) -> Result<Response<Self::GetContainerEventsStream>, Status> {
-        todo!()
+        let mut events = self.events.subscribe();
+        let (tx, rx) = mpsc::channel(16);
+
+        let _ignored = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    received = events.recv() => match received {
+                        Ok(event) => {
+                            if tx.send(Ok(event)).await.is_err() {
+                                // receiver is gone
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_))
+                        | Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
     }
 
     async fn list_metric_descriptors(
@@ -424,4 +1004,151 @@ impl runtime_service_server::RuntimeService for RuntimeService {
     ) -> Result<Response<ListPodSandboxMetricsResponse>, Status> {
         todo!()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuraedRuntime, AURAED_RUNTIME};
+    use proto::cri::{PodSandboxMetadata, PodSandboxStateValue};
+    use std::time::Duration;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn test_run_pod_sandbox_does_not_start_after_the_deadline() {
+        let _ = AURAED_RUNTIME.set(AuraedRuntime::default());
+        let service = RuntimeService::new();
+
+        // The sandboxes are held, as by a slow request
+        let sandboxes = service.sandboxes.lock().await;
+
+        let sandbox_id = format!("ae-test-{}", uuid::Uuid::new_v4());
+        let request = RunPodSandboxRequest {
+            config: Some(PodSandboxConfig {
+                metadata: Some(PodSandboxMetadata {
+                    name: sandbox_id.clone(),
+                    ..Default::default()
+                }),
+                linux: Some(LinuxPodSandboxConfig::default()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let err = service
+            .run_pod_sandbox(request, Some(deadline))
+            .await
+            .expect_err("run past its deadline must fail");
+        assert!(matches!(err, RuntimeServiceError::DeadlineExceeded { .. }));
+        assert_eq!(Status::from(err).code(), Code::DeadlineExceeded);
+
+        // The sandbox was never created
+        drop(sandboxes);
+        assert!(service.sandboxes.lock().await.get(&sandbox_id).is_err());
+    }
+
+    #[test]
+    fn test_filter_selects_sandboxes_by_id_and_state() {
+        let sandbox = PodSandbox {
+            id: "nginx".into(),
+            state: PodSandboxState::SandboxReady.into(),
+            ..Default::default()
+        };
+        let state = |state: PodSandboxState| {
+            Some(PodSandboxStateValue { state: state.into() })
+        };
+
+        let matches = |filter: &PodSandboxFilter| {
+            matches_filter(filter, &label_selector(filter).unwrap(), &sandbox)
+        };
+
+        assert!(matches(&PodSandboxFilter::default()));
+        assert!(matches(&PodSandboxFilter {
+            id: "nginx".into(),
+            state: state(PodSandboxState::SandboxReady),
+            ..Default::default()
+        }));
+        assert!(!matches(&PodSandboxFilter {
+            id: "redis".into(),
+            ..Default::default()
+        }));
+        assert!(!matches(&PodSandboxFilter {
+            state: state(PodSandboxState::SandboxNotready),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_filter_selects_sandboxes_by_labels() {
+        let sandbox = PodSandbox {
+            id: "nginx".into(),
+            labels: HashMap::from([
+                ("app".into(), "web".into()),
+                ("tier".into(), "front".into()),
+            ]),
+            ..Default::default()
+        };
+        let matches = |terms: &[(&str, &str)]| {
+            let filter = PodSandboxFilter {
+                label_selector: terms
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..Default::default()
+            };
+            matches_filter(&filter, &label_selector(&filter).unwrap(), &sandbox)
+        };
+
+        assert!(matches(&[("app", "web")]));
+        assert!(matches(&[("app", "web"), ("tier!", "db")]));
+        assert!(!matches(&[("app", "web"), ("tier!", "front")]));
+        assert!(!matches(&[("app", "api")]));
+        assert!(!matches(&[("team", "core")]));
+    }
+
+    #[test]
+    fn test_malformed_label_selectors_are_invalid_arguments() {
+        for (key, value) in [("my app", "web"), ("app", "web!"), ("app!!", "")]
+        {
+            let filter = PodSandboxFilter {
+                label_selector: HashMap::from([(
+                    key.to_string(),
+                    value.to_string(),
+                )]),
+                ..Default::default()
+            };
+            let err = label_selector(&filter).unwrap_err();
+            assert_eq!(Status::from(err).code(), Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn test_labels_must_be_valid() {
+        assert!(validate_labels(&HashMap::from([(
+            "app.kubernetes.io/name".into(),
+            "nginx".into()
+        )]))
+        .is_ok());
+
+        let err = validate_labels(&HashMap::from([(
+            "app".into(),
+            "not a label value".into(),
+        )]))
+        .unwrap_err();
+        assert_eq!(Status::from(err).code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_sandbox_ids_must_be_single_path_components() {
+        assert!(validate_sandbox_id("nginx").is_ok());
+        assert!(validate_sandbox_id("nginx.default").is_ok());
+
+        for sandbox_id in ["", ".", "..", "../nginx", "a/b", "a\0b"] {
+            let err = validate_sandbox_id(sandbox_id).unwrap_err();
+            assert!(
+                matches!(err, RuntimeServiceError::InvalidSpec { .. }),
+                "{sandbox_id}"
+            );
+        }
+    }
+}
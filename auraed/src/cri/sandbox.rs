@@ -14,7 +14,14 @@
 \* -------------------------------------------------------------------------- */
 #![allow(dead_code)]
 
+use super::logs::PodLogs;
+use super::ports::PortForwards;
+use chrono::Utc;
 use libcontainer::container::Container;
+use proto::cri::PodSandboxConfig;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Default)]
 pub struct Sandbox {
@@ -44,21 +51,92 @@ pub struct Sandbox {
     /// In the case of large enterprise workload management, these specifically
     /// are "your app".
     pub(crate) tenants: Vec<Container>,
+
+    /// The config the Pod sandbox was run with.
+    pub(crate) config: PodSandboxConfig,
+
+    /// When the Pod sandbox was created, in nanoseconds since the epoch.
+    pub(crate) created_at: i64,
+
+    /// The OCI bundle the init container was created from.
+    pub(crate) bundle: PathBuf,
+
+    /// The network namespace of the init container, held open so that it
+    /// outlives the process of the init container.
+    pub(crate) netns: Option<Arc<File>>,
+
+    /// The ports of the Pod sandbox published on the host.
+    pub(crate) ports: PortForwards,
+
+    /// The output of the init container, unless it could not be read.
+    pub(crate) logs: Option<PodLogs>,
 }
 
 pub struct SandboxBuilder {
     name: String,
     init: Container,
+    config: PodSandboxConfig,
+    bundle: PathBuf,
+    netns: Option<Arc<File>>,
 }
 
 impl SandboxBuilder {
     // TODO: Consider embedding the ContainerBuilder directly into this SandboxBuilder. For now just require a started init container.
     pub fn new(name: String, init: Container) -> SandboxBuilder {
-        SandboxBuilder { name, init }
+        SandboxBuilder {
+            name,
+            init,
+            config: PodSandboxConfig::default(),
+            bundle: PathBuf::new(),
+            netns: None,
+        }
+    }
+
+    /// The config the Pod sandbox was run with.
+    pub fn config(mut self, config: PodSandboxConfig) -> SandboxBuilder {
+        self.config = config;
+        self
+    }
+
+    /// The OCI bundle the init container was created from.
+    pub fn bundle(mut self, bundle: PathBuf) -> SandboxBuilder {
+        self.bundle = bundle;
+        self
     }
+
+    /// The network namespace of the init container.
+    pub fn netns(mut self, netns: File) -> SandboxBuilder {
+        self.netns = Some(Arc::new(netns));
+        self
+    }
+
     /// The SandboxBuilder will require that the libcontainer::Container be built before
     /// we can build the Sandbox.
     pub fn build(self) -> Sandbox {
-        Sandbox { name: self.name, init: self.init, tenants: vec![] }
+        Sandbox {
+            name: self.name,
+            init: self.init,
+            tenants: vec![],
+            config: self.config,
+            created_at: Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            bundle: self.bundle,
+            netns: self.netns,
+            ports: PortForwards::default(),
+            logs: None,
+        }
+    }
+}
+
+impl Sandbox {
+    /// The unique name of the Pod sandbox at runtime.
+    pub fn name(&self) -> &str {
+        &self.name
     }
-}
\ No newline at end of file
+
+    /// Whether `pid` is the process of the init container or of a tenant.
+    pub fn has_pid(&self, pid: i32) -> bool {
+        std::iter::once(&self.init)
+            .chain(&self.tenants)
+            .any(|container| container.pid().is_some_and(|p| p.as_raw() == pid))
+    }
+}
@@ -16,6 +16,8 @@
 use super::error::{Result, RuntimeServiceError};
 use crate::cri::sandbox::Sandbox;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
 /// Cache is the in-memory cache which is embedded
 /// into the SandboxCache structure which provides access
@@ -50,6 +52,11 @@ impl SandboxCache {
         Ok(sandbox)
     }
 
+    /// Returns the sandbox one of whose containers has `pid` as its process.
+    pub fn of_pid(&self, pid: i32) -> Option<&Sandbox> {
+        self.cache.values().find(|sandbox| sandbox.has_pid(pid))
+    }
+
     pub fn list(&self) -> Result<Vec<&Sandbox>> {
         Ok(self.cache.values().collect())
     }
@@ -62,4 +69,18 @@ impl SandboxCache {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// A shared handle on the pod sandboxes of the runtime service, to find the
+/// pod sandbox of a process from outside of it.
+#[derive(Debug, Clone)]
+pub struct PodSandboxes(pub(crate) Arc<Mutex<SandboxCache>>);
+
+impl PodSandboxes {
+    /// Returns the name of the pod sandbox one of whose containers has `pid`
+    /// as its process.
+    pub async fn of_pid(&self, pid: i32) -> Option<String> {
+        let sandboxes = self.0.lock().await;
+        sandboxes.of_pid(pid).map(|sandbox| sandbox.name().to_owned())
+    }
+}
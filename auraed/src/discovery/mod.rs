@@ -13,21 +13,59 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
+use self::{
+    peers::Peers,
+    validation::{ValidatedDeregisterRequest, ValidatedRegisterRequest},
+};
+use crate::{cells::CgroupMode, graceful_shutdown::StreamCloser};
+use ::validation::{ValidatedType, ValidationError};
 use proto::discovery::{
-    discovery_service_server, DiscoverRequest, DiscoverResponse,
+    self, discovery_service_server, DeregisterRequest, DeregisterResponse,
+    DiscoverRequest, DiscoverResponse, Peer, PeerEventKind, RegisterRequest,
+    RegisterResponse, WatchPeersRequest, WatchPeersResponse,
 };
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 use tracing::error;
 
+mod peers;
+mod validation;
+
 pub(crate) type Result<T> = std::result::Result<T, DiscoveryServiceError>;
 
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
+/// How auraed keeps track of the peers which register with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    /// How long a peer is remembered after it last registered, in seconds
+    /// in the config of auraed.
+    #[serde(with = "crate::config::secs")]
+    pub peer_ttl: Duration,
+    /// Save the peers to the runtime directory, for a restarted auraed to
+    /// remember them.
+    pub persist: bool,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self { peer_ttl: Duration::from_secs(30), persist: false }
+    }
+}
+
 #[derive(Debug, Error)]
 pub(crate) enum DiscoveryServiceError {
     #[error(transparent)]
     IO(#[from] std::io::Error),
+    #[error("peer '{name}' not found")]
+    PeerNotFound { name: String },
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
 }
 
 impl From<DiscoveryServiceError> for Status {
@@ -36,25 +74,94 @@ impl From<DiscoveryServiceError> for Status {
         error!("{msg}");
         match err {
             DiscoveryServiceError::IO(_) => Status::internal(msg),
+            DiscoveryServiceError::PeerNotFound { .. } => {
+                Status::not_found(msg)
+            }
+            DiscoveryServiceError::ValidationError(_) => {
+                Status::failed_precondition(msg)
+            }
         }
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct DiscoveryService {}
+pub struct DiscoveryService {
+    peers: Peers,
+    stream_closer: StreamCloser,
+}
 
 impl DiscoveryService {
-    pub fn new() -> Self {
-        DiscoveryService {}
+    pub fn new(config: DiscoveryConfig, stream_closer: StreamCloser) -> Self {
+        DiscoveryService { peers: Peers::new(config.peer_ttl), stream_closer }
+    }
+
+    /// Remembers the peers in `path`, across restarts of auraed.
+    pub(crate) async fn with_state_file(mut self, path: PathBuf) -> Self {
+        self.peers = self.peers.with_state_file(path).await;
+        self
+    }
+
+    /// Forgets the peers which stop registering, for as long as auraed runs.
+    pub(crate) fn expire_peers(&self) {
+        self.peers.expire_periodically();
     }
 
     #[tracing::instrument(skip(self))]
-    fn discover(&self, request: DiscoverRequest) -> Result<DiscoverResponse> {
+    async fn discover(
+        &self,
+        request: DiscoverRequest,
+    ) -> Result<DiscoverResponse> {
         Ok(DiscoverResponse {
             healthy: true,
             version: VERSION.unwrap_or("unknown").into(),
+            cgroup_mode: discovery::CgroupMode::from(CgroupMode::current())
+                .into(),
+            peers: self.peers.list().await,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn register(
+        &self,
+        request: ValidatedRegisterRequest,
+    ) -> Result<RegisterResponse> {
+        let ValidatedRegisterRequest { name, address, capabilities } = request;
+        self.peers.register(name.into_inner(), address, capabilities).await;
+
+        Ok(RegisterResponse {
+            ttl_seconds: self
+                .peers
+                .ttl()
+                .as_secs()
+                .try_into()
+                .unwrap_or(u32::MAX),
         })
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn deregister(
+        &self,
+        request: ValidatedDeregisterRequest,
+    ) -> Result<DeregisterResponse> {
+        let ValidatedDeregisterRequest { name } = request;
+        if !self.peers.deregister(name.as_ref()).await {
+            return Err(DiscoveryServiceError::PeerNotFound {
+                name: name.into_inner(),
+            });
+        }
+
+        Ok(DeregisterResponse {})
+    }
+}
+
+impl From<CgroupMode> for discovery::CgroupMode {
+    fn from(value: CgroupMode) -> Self {
+        match value {
+            CgroupMode::Unified => Self::Unified,
+            CgroupMode::Hybrid => Self::Hybrid,
+            CgroupMode::Legacy => Self::Legacy,
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -64,24 +171,217 @@ impl discovery_service_server::DiscoveryService for DiscoveryService {
         request: Request<DiscoverRequest>,
     ) -> std::result::Result<Response<DiscoverResponse>, Status> {
         let request = request.into_inner();
-        Ok(Response::new(self.discover(request)?))
+        Ok(Response::new(self.discover(request).await?))
+    }
+
+    async fn register(
+        &self,
+        request: Request<RegisterRequest>,
+    ) -> std::result::Result<Response<RegisterResponse>, Status> {
+        let request = request.into_inner();
+        let request = ValidatedRegisterRequest::validate(request, None)?;
+        Ok(Response::new(self.register(request).await?))
+    }
+
+    async fn deregister(
+        &self,
+        request: Request<DeregisterRequest>,
+    ) -> std::result::Result<Response<DeregisterResponse>, Status> {
+        let request = request.into_inner();
+        let request = ValidatedDeregisterRequest::validate(request, None)?;
+        Ok(Response::new(self.deregister(request).await?))
+    }
+
+    type WatchPeersStream =
+        ReceiverStream<std::result::Result<WatchPeersResponse, Status>>;
+
+    async fn watch_peers(
+        &self,
+        _request: Request<WatchPeersRequest>,
+    ) -> std::result::Result<Response<Self::WatchPeersStream>, Status> {
+        let (current, mut events) = self.peers.subscribe().await;
+        let peers = self.peers.clone();
+        let (tx, rx) = mpsc::channel(16);
+
+        let _ignored = tokio::spawn(async move {
+            // The peers as last sent, by name
+            let mut sent = HashMap::new();
+            let mut pending: Vec<_> = current
+                .into_iter()
+                .map(|peer| event(PeerEventKind::Joined, peer))
+                .collect();
+
+            loop {
+                for event in pending.drain(..) {
+                    let peer = event.peer.clone().expect("peer");
+                    if event.kind == i32::from(PeerEventKind::Left) {
+                        let _ = sent.remove(&peer.name);
+                    } else {
+                        let _ = sent.insert(peer.name.clone(), peer);
+                    }
+                    if tx.send(Ok(event)).await.is_err() {
+                        // receiver is gone
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    received = events.recv() => match received {
+                        Ok(event) => pending.push(event),
+                        // Catch up by comparing the peers to those sent
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            pending = resync(&sent, peers.list().await);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(
+            self.stream_closer.until_closed(ReceiverStream::new(rx)),
+        ))
+    }
+}
+
+fn event(kind: PeerEventKind, peer: Peer) -> WatchPeersResponse {
+    WatchPeersResponse { kind: kind.into(), peer: Some(peer) }
+}
+
+/// The events turning the peers `sent` into the `current` ones.
+fn resync(
+    sent: &HashMap<String, Peer>,
+    current: Vec<Peer>,
+) -> Vec<WatchPeersResponse> {
+    let mut events: Vec<_> = sent
+        .values()
+        .filter(|peer| !current.iter().any(|p| p.name == peer.name))
+        .map(|peer| event(PeerEventKind::Left, peer.clone()))
+        .collect();
+
+    for peer in current {
+        match sent.get(&peer.name) {
+            None => events.push(event(PeerEventKind::Joined, peer)),
+            Some(known)
+                if known.address != peer.address
+                    || known.capabilities != peer.capabilities =>
+            {
+                events.push(event(PeerEventKind::Updated, peer))
+            }
+            Some(_) => {}
+        }
     }
+    events
 }
 
 #[cfg(test)]
 mod tests {
-    use proto::discovery::DiscoverRequest;
+    use super::*;
+    use proto::discovery::discovery_service_server::DiscoveryService as Server;
+    use tokio_stream::StreamExt;
 
-    use crate::discovery::{DiscoveryService, VERSION};
+    fn service() -> DiscoveryService {
+        DiscoveryService::new(
+            DiscoveryConfig::default(),
+            StreamCloser::default(),
+        )
+    }
 
-    #[test]
-    fn test_discover() {
-        let resp = DiscoveryService::new().discover(DiscoverRequest {});
+    fn peer(name: &str, address: &str) -> Peer {
+        Peer {
+            name: name.into(),
+            address: address.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover() {
+        let resp = service().discover(DiscoverRequest {}).await;
         assert!(resp.is_ok());
 
         let resp = resp.unwrap();
 
         assert!(resp.healthy);
         assert_eq!(resp.version, VERSION.expect("valid version"));
+        assert!(resp.peers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discover_lists_the_registered_peers() {
+        let service = service();
+        let res = Server::register(
+            &service,
+            Request::new(RegisterRequest {
+                name: "node-1".into(),
+                address: "[::1]:8080".into(),
+                capabilities: vec!["pods".into(), "vms".into()],
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        assert_eq!(res.ttl_seconds, 30);
+
+        let peers = service.discover(DiscoverRequest {}).await.unwrap().peers;
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].name, "node-1");
+        assert_eq!(peers[0].capabilities, vec!["pods", "vms"]);
+
+        let deregister = || {
+            Server::deregister(
+                &service,
+                Request::new(DeregisterRequest { name: "node-1".into() }),
+            )
+        };
+        let _ = deregister().await.unwrap();
+        let status = deregister().await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_watch_peers_sends_the_current_peers_first() {
+        let service = service();
+        service
+            .peers
+            .register("node-1".into(), "[::1]:8080".into(), vec![])
+            .await;
+
+        let mut stream = service
+            .watch_peers(Request::new(WatchPeersRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        let joined = stream.next().await.unwrap().unwrap();
+        assert_eq!(joined.kind, i32::from(PeerEventKind::Joined));
+        assert_eq!(joined.peer.unwrap().name, "node-1");
+
+        assert!(service.peers.deregister("node-1").await);
+        let left = stream.next().await.unwrap().unwrap();
+        assert_eq!(left.kind, i32::from(PeerEventKind::Left));
+    }
+
+    #[test]
+    fn test_resync_sends_the_differences() {
+        let sent = HashMap::from([
+            ("node-1".to_string(), peer("node-1", "[::1]:8080")),
+            ("node-2".to_string(), peer("node-2", "[::2]:8080")),
+        ]);
+        let current =
+            vec![peer("node-2", "[::22]:8080"), peer("node-3", "[::3]:8080")];
+
+        let events: Vec<_> = resync(&sent, current)
+            .into_iter()
+            .map(|e| (e.kind, e.peer.unwrap().name))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (PeerEventKind::Left.into(), "node-1".to_string()),
+                (PeerEventKind::Updated.into(), "node-2".to_string()),
+                (PeerEventKind::Joined.into(), "node-3".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The peers registered with auraed, which are forgotten once they have not
+//! registered again for a TTL.
+
+use proto::discovery::{Peer, PeerEventKind, WatchPeersResponse};
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+
+/// How often expired peers are looked for.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of events buffered for each watcher.
+const EVENTS_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Peers {
+    inner: Arc<Mutex<Inner>>,
+    events: broadcast::Sender<WatchPeersResponse>,
+    ttl: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    peers: HashMap<String, (Peer, SystemTime)>,
+    /// Where the peers are saved as they join and leave, if anywhere.
+    state_file: Option<PathBuf>,
+}
+
+impl Peers {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Default::default(),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            ttl,
+        }
+    }
+
+    /// Loads the peers saved at `path` by a previous auraed, and saves them
+    /// there from now on. The loaded peers are given a full TTL to register
+    /// again, as they could not while auraed was down.
+    pub async fn with_state_file(self, path: PathBuf) -> Self {
+        {
+            let mut inner = self.inner.lock().await;
+            match tokio::fs::read(&path).await {
+                Ok(contents) => {
+                    match serde_json::from_slice::<Vec<Peer>>(&contents) {
+                        Ok(peers) => {
+                            let now = SystemTime::now();
+                            for peer in peers {
+                                let _ = inner
+                                    .peers
+                                    .insert(peer.name.clone(), (peer, now));
+                            }
+                        }
+                        Err(e) => warn!(
+                            "ignoring corrupt peers '{}': {e}",
+                            path.display()
+                        ),
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    warn!("failed to read peers '{}': {e}", path.display())
+                }
+            }
+            inner.state_file = Some(path);
+        }
+        self
+    }
+
+    /// How long a peer is remembered after it last registered.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Registers the peer `name`, or refreshes it if it is known.
+    pub async fn register(
+        &self,
+        name: String,
+        address: String,
+        capabilities: Vec<String>,
+    ) {
+        let now = SystemTime::now();
+        let peer =
+            Peer { name, address, capabilities, last_seen: unix_secs(now) };
+
+        let mut inner = self.inner.lock().await;
+        let kind =
+            match inner.peers.insert(peer.name.clone(), (peer.clone(), now)) {
+                None => PeerEventKind::Joined,
+                Some((known, _))
+                    if known.address != peer.address
+                        || known.capabilities != peer.capabilities =>
+                {
+                    PeerEventKind::Updated
+                }
+                // A heartbeat
+                Some(_) => return,
+            };
+
+        info!("Peer '{}' {kind:?} at {}", peer.name, peer.address);
+        inner.save().await;
+        self.publish(kind, peer);
+    }
+
+    /// Forgets the peer `name`. Returns false if it was not known.
+    pub async fn deregister(&self, name: &str) -> bool {
+        let mut inner = self.inner.lock().await;
+        let Some((peer, _)) = inner.peers.remove(name) else {
+            return false;
+        };
+
+        info!("Peer '{name}' deregistered");
+        inner.save().await;
+        self.publish(PeerEventKind::Left, peer);
+        true
+    }
+
+    /// Forgets the peers which have not registered within the TTL at `now`.
+    pub async fn expire(&self, now: SystemTime) {
+        let mut inner = self.inner.lock().await;
+        let expired: Vec<_> = inner
+            .peers
+            .iter()
+            .filter(|(_, (_, last_seen))| *last_seen + self.ttl <= now)
+            .map(|(name, _)| name.clone())
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+
+        let mut left = vec![];
+        for name in expired {
+            info!("Peer '{name}' expired");
+            let (peer, _) = inner.peers.remove(&name).expect("expired peer");
+            left.push(peer);
+        }
+        inner.save().await;
+        for peer in left {
+            self.publish(PeerEventKind::Left, peer);
+        }
+    }
+
+    /// Forgets the expired peers for as long as auraed runs.
+    pub fn expire_periodically(&self) {
+        let peers = self.clone();
+        let _ignored = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+            loop {
+                let _ = interval.tick().await;
+                peers.expire(SystemTime::now()).await;
+            }
+        });
+    }
+
+    /// The peers, by name.
+    pub async fn list(&self) -> Vec<Peer> {
+        self.inner.lock().await.list()
+    }
+
+    /// The peers, by name, and the events of the peers joining and leaving
+    /// from then on.
+    pub async fn subscribe(
+        &self,
+    ) -> (Vec<Peer>, broadcast::Receiver<WatchPeersResponse>) {
+        let inner = self.inner.lock().await;
+        (inner.list(), self.events.subscribe())
+    }
+
+    fn publish(&self, kind: PeerEventKind, peer: Peer) {
+        // Fails only if no one is watching
+        let _ = self
+            .events
+            .send(WatchPeersResponse { kind: kind.into(), peer: Some(peer) });
+    }
+}
+
+impl Inner {
+    fn list(&self) -> Vec<Peer> {
+        let mut peers: Vec<_> =
+            self.peers.values().map(|(peer, _)| peer.clone()).collect();
+        peers.sort_by(|a, b| a.name.cmp(&b.name));
+        peers
+    }
+
+    /// Replaces the state file atomically, so a crash while saving leaves
+    /// the previous peers in place.
+    async fn save(&self) {
+        let Some(path) = &self.state_file else {
+            return;
+        };
+
+        let contents = serde_json::to_vec_pretty(&self.list())
+            .expect("peers serialize to json");
+        let tmp_path = path.with_extension("json.tmp");
+        let res = match tokio::fs::write(&tmp_path, contents).await {
+            Ok(()) => tokio::fs::rename(&tmp_path, path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            warn!("failed to save peers '{}': {e}", path.display());
+        }
+    }
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(30);
+
+    fn kind(event: &WatchPeersResponse) -> PeerEventKind {
+        PeerEventKind::try_from(event.kind).expect("valid kind")
+    }
+
+    #[tokio::test]
+    async fn test_register_announces_joined_and_updated_peers() {
+        let peers = Peers::new(TTL);
+        let (known, mut events) = peers.subscribe().await;
+        assert!(known.is_empty());
+
+        peers.register("node-1".into(), "[::1]:8080".into(), vec![]).await;
+        // A heartbeat is not an event
+        peers.register("node-1".into(), "[::1]:8080".into(), vec![]).await;
+        peers
+            .register("node-1".into(), "[::1]:8080".into(), vec!["vms".into()])
+            .await;
+
+        let joined = events.recv().await.unwrap();
+        assert_eq!(kind(&joined), PeerEventKind::Joined);
+        assert_eq!(joined.peer.unwrap().name, "node-1");
+        let updated = events.recv().await.unwrap();
+        assert_eq!(kind(&updated), PeerEventKind::Updated);
+        assert_eq!(updated.peer.unwrap().capabilities, vec!["vms"]);
+        assert!(events.try_recv().is_err());
+
+        let listed = peers.list().await;
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_seen > 0);
+    }
+
+    #[tokio::test]
+    async fn test_peers_expire_unless_they_register_again() {
+        let peers = Peers::new(TTL);
+        peers.register("node-1".into(), "[::1]:8080".into(), vec![]).await;
+        peers.register("node-2".into(), "[::2]:8080".into(), vec![]).await;
+        let (_, mut events) = peers.subscribe().await;
+
+        let now = SystemTime::now();
+        peers.expire(now + TTL / 2).await;
+        assert_eq!(peers.list().await.len(), 2);
+
+        peers.expire(now + TTL).await;
+        assert!(peers.list().await.is_empty());
+        for _ in 0..2 {
+            assert_eq!(
+                kind(&events.recv().await.unwrap()),
+                PeerEventKind::Left
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deregister_forgets_a_peer() {
+        let peers = Peers::new(TTL);
+        peers.register("node-1".into(), "[::1]:8080".into(), vec![]).await;
+
+        assert!(peers.deregister("node-1").await);
+        assert!(!peers.deregister("node-1").await);
+        assert!(peers.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_peers_are_remembered_across_restarts() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-peers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("peers.json");
+
+        let peers = Peers::new(TTL).with_state_file(path.clone()).await;
+        peers
+            .register("node-1".into(), "[::1]:8080".into(), vec!["pods".into()])
+            .await;
+        peers.register("node-2".into(), "[::2]:8080".into(), vec![]).await;
+        assert!(peers.deregister("node-2").await);
+
+        let restarted = Peers::new(TTL).with_state_file(path).await;
+        let listed = restarted.list().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "node-1");
+        assert_eq!(listed[0].capabilities, vec!["pods"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
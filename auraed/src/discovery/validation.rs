@@ -0,0 +1,127 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use proto::discovery::{DeregisterRequest, RegisterRequest};
+use validation::{ValidatedField, ValidationError};
+use validation_macros::ValidatedType;
+
+/// The maximum length of the name of a peer: that of a DNS name.
+const MAX_PEER_NAME_LENGTH: u64 = 253;
+
+/// The name a peer is known by, unique among the peers of an auraed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerName(String);
+
+impl PeerName {
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl ValidatedField<String> for PeerName {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        validation::maximum_length(
+            input.as_bytes(),
+            MAX_PEER_NAME_LENGTH,
+            "bytes",
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(Self(input))
+    }
+}
+
+impl AsRef<str> for PeerName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedRegisterRequest {
+    #[field_type(String)]
+    #[validate]
+    pub name: PeerName,
+    #[field_type(String)]
+    pub address: String,
+    #[validate(none)]
+    pub capabilities: Vec<String>,
+}
+
+impl RegisterRequestTypeValidator for RegisterRequestValidator {
+    fn validate_address(
+        address: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<String, ValidationError> {
+        validation::required_not_empty(Some(address), field_name, parent_name)
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedDeregisterRequest {
+    #[field_type(String)]
+    #[validate]
+    pub name: PeerName,
+}
+
+impl DeregisterRequestTypeValidator for DeregisterRequestValidator {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validation::ValidatedType;
+
+    #[test]
+    fn test_register_request_requires_a_name_and_address() {
+        let request = RegisterRequest {
+            name: "node-1.example.com".into(),
+            address: "[::1]:8080".into(),
+            capabilities: vec!["vms".into()],
+        };
+        let validated =
+            ValidatedRegisterRequest::validate(request.clone(), None).unwrap();
+        assert_eq!(validated.name.as_ref(), "node-1.example.com");
+
+        let err = ValidatedRegisterRequest::validate(
+            RegisterRequest { name: String::new(), ..request.clone() },
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.get_field(), "name");
+
+        let err = ValidatedRegisterRequest::validate(
+            RegisterRequest { address: String::new(), ..request.clone() },
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.get_field(), "address");
+
+        let err = ValidatedRegisterRequest::validate(
+            RegisterRequest { name: "a".repeat(254), ..request },
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ValidationError::Maximum { .. }));
+    }
+}
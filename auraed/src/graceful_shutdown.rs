@@ -13,32 +13,125 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use crate::{cells::CellService, discovery::DiscoveryService};
-use proto::{
-    cells::cell_service_server::CellServiceServer,
-    discovery::discovery_service_server::DiscoveryServiceServer,
+use crate::{
+    cells::CellService, health::HealthRegistry, observe::ObserveService,
+    systemd,
 };
-use std::borrow::BorrowMut;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
 use tokio::{
     signal::unix::SignalKind,
-    sync::watch::{channel, Receiver, Sender},
+    sync::{
+        mpsc,
+        watch::{self, channel, Receiver, Sender},
+    },
 };
-use tonic_health::server::HealthReporter;
-use tracing::error;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::Status;
+use tracing::{error, info};
+
+/// How long auraed gives its workloads to exit when shutting down.
+///
+/// Written in seconds in the config of auraed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// How long executables and the processes of cells are given to exit
+    /// after SIGTERM, before they are killed.
+    #[serde(with = "crate::config::secs")]
+    pub grace_period: Duration,
+    /// How long shutting down may take in all. auraed exits once it passes,
+    /// even if a workload could not be stopped.
+    #[serde(with = "crate::config::secs")]
+    pub deadline: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(10),
+            deadline: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Ends streaming responses once auraed starts shutting down, with an
+/// UNAVAILABLE status, rather than have them reset when the server stops.
+#[derive(Debug, Clone)]
+pub(crate) struct StreamCloser(Arc<watch::Sender<bool>>);
+
+impl StreamCloser {
+    /// Ends the streams, and any stream started after.
+    pub fn close(&self) {
+        let _ = self.0.send_replace(true);
+    }
+
+    /// Forwards `stream` until it ends, or until the streams are closed.
+    pub fn until_closed<T: Send + 'static>(
+        &self,
+        mut stream: ReceiverStream<Result<T, Status>>,
+    ) -> ReceiverStream<Result<T, Status>> {
+        let mut closed = self.0.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+
+        let _ignored = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    item = stream.next() => {
+                        let Some(item) = item else {
+                            break;
+                        };
+                        if tx.send(item).await.is_err() {
+                            // receiver is gone
+                            break;
+                        }
+                    }
+                    true = async {
+                        closed.wait_for(|closed| *closed).await.is_ok()
+                    } => {
+                        let _ = tx
+                            .send(Err(Status::unavailable("auraed is shutting down")))
+                            .await;
+                        break;
+                    }
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl Default for StreamCloser {
+    fn default() -> Self {
+        Self(Arc::new(channel(false).0))
+    }
+}
 
 pub(crate) struct GracefulShutdown {
-    health_reporter: HealthReporter,
+    health: HealthRegistry,
     cell_service: CellService,
+    observe_service: ObserveService,
     shutdown_broadcaster: Sender<()>,
+    config: ShutdownConfig,
 }
 
 impl GracefulShutdown {
     pub fn new(
-        health_reporter: HealthReporter,
+        health: HealthRegistry,
         cell_service: CellService,
+        observe_service: ObserveService,
+        config: ShutdownConfig,
     ) -> Self {
         let (tx, _) = channel(());
-        Self { health_reporter, cell_service, shutdown_broadcaster: tx }
+        Self {
+            health,
+            cell_service,
+            observe_service,
+            shutdown_broadcaster: tx,
+            config,
+        }
     }
 
     /// Subscribe to the shutdown broadcast channel
@@ -47,45 +140,61 @@ impl GracefulShutdown {
     }
 
     /// Waits for a signal and then...
+    /// * Closes the streaming responses. See [StreamCloser]
     /// * Broadcasts a shutdown signal to all subscribers. See [subscribe]
     /// * Waits for all subscribers to drop
+    /// * Calls [CellService::stop_all]
     /// * Calls [CellService::free_all]
+    ///
+    /// Gives up once [ShutdownConfig::deadline] passes.
     /// ---
     /// Signals:
     /// * [SIGTERM]
     /// * [SIGINT]
     /// ---
     /// Returns after processing the first received signal.
-    pub async fn wait(mut self) {
+    pub async fn wait(self) {
         tokio::select! {
             _ = wait_for_sigterm() => {},
             _ = wait_for_sigint() => {},
         }
 
-        // update health reporter
-        let health_reporter = self.health_reporter.borrow_mut();
-        health_reporter
-            .set_not_serving::<CellServiceServer<CellService>>()
-            .await;
-        health_reporter
-            .set_not_serving::<DiscoveryServiceServer<DiscoveryService>>()
-            .await;
+        let ShutdownConfig { grace_period, deadline } = self.config;
+        info!("Shutting down with a grace period of {grace_period:?}");
+        systemd::notify("STOPPING=1");
+
+        // Cells are freed on the blocking pool, but the deadline is still
+        // kept from another task in case any step of the shutdown blocks.
+        let shutdown = tokio::spawn(self.shut_down());
+        match tokio::time::timeout(deadline, shutdown).await {
+            Ok(Ok(())) => info!("Shut down successfully"),
+            Ok(Err(e)) => error!("Failed to shut down: {e}"),
+            Err(_) => error!(
+                "Failed to shut down within {deadline:?}, exiting regardless"
+            ),
+        }
+    }
+
+    async fn shut_down(self) {
+        self.health.set_shutting_down().await;
 
-        // health_reporter.set_not_serving::<PodServiceServer<PodService>>().await;
+        // The server waits for in-flight streams to end before it stops
+        self.observe_service.stream_closer().close();
 
         self.shutdown_broadcaster.send_replace(());
         // wait for all subscribers to drop
         self.shutdown_broadcaster.closed().await;
 
-        if let Err(e) = self.cell_service.free_all().await {
+        let grace_period = self.config.grace_period;
+        if let Err(e) = self.cell_service.stop_all(grace_period).await {
             error!(
-                "Attempt to free all cells on terminate resulted in error: {e}"
+                "Attempt to stop all executables on terminate resulted in error: {e}"
             )
         }
 
-        if let Err(e) = self.cell_service.stop_all().await {
+        if let Err(e) = self.cell_service.free_all(grace_period).await {
             error!(
-                "Attempt to stop all executables on terminate resulted in error: {e}"
+                "Attempt to free all cells on terminate resulted in error: {e}"
             )
         }
     }
@@ -103,4 +212,31 @@ pub async fn wait_for_sigint() {
         .expect("failed to listen for SIGINT");
 
     let _ = stream.recv().await;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn test_closed_stream_ends_with_unavailable() {
+        let stream_closer = StreamCloser::default();
+        let (tx, rx) = mpsc::channel::<Result<u32, Status>>(4);
+        let mut stream = stream_closer.until_closed(ReceiverStream::new(rx));
+
+        tx.send(Ok(1)).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+
+        stream_closer.close();
+        let status = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(stream.next().await.is_none());
+
+        // Streams started after closing end straight away
+        let (_tx, rx) = mpsc::channel::<Result<u32, Status>>(4);
+        let mut stream = stream_closer.until_closed(ReceiverStream::new(rx));
+        let status = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+}
@@ -0,0 +1,157 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The serving status of each service of auraed, served by the
+//! `grpc.health.v1.Health` service for load balancers and watchdogs.
+
+use crate::cells::CellService;
+use proto::cells::cell_service_server::CellServiceServer;
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+use tonic::server::NamedService;
+use tonic_health::{server::HealthReporter, ServingStatus};
+use tracing::{info, warn};
+
+/// How often the health of the services is checked again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The serving status of the services of auraed. A service flips its status
+/// when it detects it is degraded, and watchers of the Health service are
+/// sent the change.
+#[derive(Debug, Clone)]
+pub(crate) struct HealthRegistry(Arc<Mutex<Inner>>);
+
+#[derive(Debug)]
+struct Inner {
+    reporter: HealthReporter,
+    /// The reason each degraded service is not serving, by service name.
+    /// [None] if it is serving.
+    services: HashMap<&'static str, Option<String>>,
+    /// Services are not serving from then on, whatever their health.
+    shutting_down: bool,
+}
+
+impl HealthRegistry {
+    pub fn new(reporter: HealthReporter) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            reporter,
+            services: HashMap::new(),
+            shutting_down: false,
+        })))
+    }
+
+    /// Reports `S` as serving, unless auraed is shutting down.
+    pub async fn set_serving<S: NamedService>(&self) {
+        let mut inner = self.0.lock().await;
+        if let Some(Some(reason)) = inner.services.insert(S::NAME, None) {
+            info!("{} is serving again, after: {reason}", S::NAME);
+        }
+
+        if !inner.shutting_down {
+            inner.reporter.set_serving::<S>().await;
+        }
+    }
+
+    /// Reports `S` as not serving because of `reason`, until it is set
+    /// serving again.
+    pub async fn set_degraded<S: NamedService>(&self, reason: impl Display) {
+        let reason = reason.to_string();
+        let mut inner = self.0.lock().await;
+        let previous = inner.services.insert(S::NAME, Some(reason.clone()));
+        if previous.flatten().as_ref() != Some(&reason) {
+            warn!("{} is not serving: {reason}", S::NAME);
+        }
+
+        inner.reporter.set_not_serving::<S>().await;
+    }
+
+    /// Reports every service, and auraed as a whole, as not serving from now
+    /// on.
+    pub async fn set_shutting_down(&self) {
+        let mut inner = self.0.lock().await;
+        inner.shutting_down = true;
+
+        let Inner { reporter, services, .. } = &mut *inner;
+        for name in services.keys() {
+            reporter.set_service_status(name, ServingStatus::NotServing).await;
+        }
+        // The empty service name is the health of auraed as a whole
+        reporter.set_service_status("", ServingStatus::NotServing).await;
+    }
+
+    #[cfg(test)]
+    async fn degraded_reason<S: NamedService>(&self) -> Option<String> {
+        self.0.lock().await.services.get(S::NAME).cloned().flatten()
+    }
+}
+
+/// Checks the health of the services now, and again every [CHECK_INTERVAL].
+pub(crate) async fn start_checks(
+    registry: HealthRegistry,
+    cell_service: CellService,
+) {
+    check(&registry, &cell_service).await;
+
+    let _ignored = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        // The first tick completes immediately
+        let _ = interval.tick().await;
+        loop {
+            let _ = interval.tick().await;
+            check(&registry, &cell_service).await;
+        }
+    });
+}
+
+async fn check(registry: &HealthRegistry, cell_service: &CellService) {
+    match cell_service.check_health() {
+        Ok(()) => {
+            registry.set_serving::<CellServiceServer<CellService>>().await
+        }
+        Err(e) => {
+            registry
+                .set_degraded::<CellServiceServer<CellService>>(format!(
+                    "cells cannot be allocated: {e}"
+                ))
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::observe::observe_service_server::ObserveServiceServer;
+
+    type Observe = ObserveServiceServer<crate::observe::ObserveService>;
+
+    #[tokio::test]
+    async fn test_degraded_service_serves_again() {
+        let (reporter, _) = tonic_health::server::health_reporter();
+        let registry = HealthRegistry::new(reporter);
+
+        registry.set_serving::<Observe>().await;
+        assert_eq!(registry.degraded_reason::<Observe>().await, None);
+
+        registry.set_degraded::<Observe>("no probes").await;
+        assert_eq!(
+            registry.degraded_reason::<Observe>().await.as_deref(),
+            Some("no probes")
+        );
+
+        registry.set_serving::<Observe>().await;
+        assert_eq!(registry.degraded_reason::<Observe>().await, None);
+    }
+}
@@ -14,8 +14,11 @@
 \* -------------------------------------------------------------------------- */
 
 use lazy_static::lazy_static;
-use nix::{mount::MsFlags, sys::stat::Mode};
-use std::io;
+use nix::{
+    mount::MsFlags,
+    sys::stat::{stat, Mode},
+};
+use std::{io, path::Path};
 use tracing::{error, info};
 
 #[derive(thiserror::Error, Debug)]
@@ -77,4 +80,28 @@ impl MountSpec {
 
         Ok(())
     }
+
+    /// Mounts, unless a filesystem is already mounted on the target, e.g.
+    /// by the kernel or an initramfs.
+    pub fn mount_if_absent(self) -> Result<(), FsError> {
+        if is_mount_point(Path::new(self.target)) {
+            info!("{} is already mounted", self.target);
+            return Ok(());
+        }
+
+        self.mount()
+    }
+}
+
+/// Whether `path` is on another filesystem than its parent directory.
+fn is_mount_point(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        // The root is always mounted
+        return true;
+    };
+
+    match (stat(path), stat(parent)) {
+        (Ok(path), Ok(parent)) => path.st_dev != parent.st_dev,
+        _ => false,
+    }
 }
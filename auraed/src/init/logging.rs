@@ -12,9 +12,14 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-use tracing::{info, Level};
+use crate::logging::channel_layer::{auraed_channel, ChannelLayer};
+use crate::logging::log_level;
+use tracing::{info, Level, Subscriber};
 use tracing_subscriber::{
-    layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+    layer::{Filter, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -52,19 +57,33 @@ pub(crate) fn init(verbose: bool, container: bool) -> Result<(), LoggingError> {
     }
 }
 
+/// Sends the events of auraed to its log channel, for the observe service.
+fn channel_layer<S>(filter: impl Filter<S> + 'static) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    Layer::with_filter(ChannelLayer::new(auraed_channel().clone()), filter)
+}
+
 fn init_container_logging(tracing_level: Level) -> Result<(), LoggingError> {
     info!("initializing container logging");
 
-    // Stdout
+    // Stdout and the log channel, filtered by the log level of auraed
+    let directives = format!("auraed={tracing_level}");
+    let (stdout_filter, stdout_reload) = log_level::filter(&directives);
     let stdout_layer = Layer::with_filter(
         tracing_subscriber::fmt::layer().compact(),
-        EnvFilter::new(format!("auraed={tracing_level}")),
+        stdout_filter,
     );
+    let (channel_filter, channel_reload) = log_level::filter(&directives);
 
     tracing_subscriber::registry()
         .with(stdout_layer)
-        .try_init()
-        .map_err(|e| e.into())
+        .with(channel_layer(channel_filter))
+        .try_init()?;
+
+    log_level::init(&directives, vec![stdout_reload, channel_reload]);
+    Ok(())
 }
 
 /// when we run as a daemon we want to log to stdout and syslog.
@@ -85,17 +104,23 @@ fn init_daemon_logging(tracing_level: Level) -> Result<(), LoggingError> {
 
     let syslog_layer = tracing_subscriber::fmt::layer().with_writer(syslog);
 
-    // Stdout
+    // Stdout and the log channel, filtered by the log level of auraed
+    let directives = format!("auraed={tracing_level}");
+    let (stdout_filter, stdout_reload) = log_level::filter(&directives);
     let stdout_layer = Layer::with_filter(
         tracing_subscriber::fmt::layer().compact(),
-        EnvFilter::new(format!("auraed={tracing_level}")),
+        stdout_filter,
     );
+    let (channel_filter, channel_reload) = log_level::filter(&directives);
 
     tracing_subscriber::registry()
         .with(syslog_layer)
         .with(stdout_layer)
-        .try_init()
-        .map_err(|e| e.into())
+        .with(channel_layer(channel_filter))
+        .try_init()?;
+
+    log_level::init(&directives, vec![stdout_reload, channel_reload]);
+    Ok(())
 }
 
 #[allow(unused)]
@@ -111,9 +136,22 @@ fn init_stdout_logging(tracing_level: Level) -> Result<(), LoggingError> {
 
 fn init_pid1_logging(tracing_level: Level) -> Result<(), LoggingError> {
     info!("initializing pid1 logging");
-    tracing_subscriber::fmt()
-        .compact()
-        .with_env_filter(format!("auraed={tracing_level}"))
+
+    // Stdout and the log channel, filtered by the log level of auraed
+    let directives = format!("auraed={tracing_level}");
+    let (stdout_filter, stdout_reload) = log_level::filter(&directives);
+    let stdout_layer = Layer::with_filter(
+        tracing_subscriber::fmt::layer().compact(),
+        stdout_filter,
+    );
+    let (channel_filter, channel_reload) = log_level::filter(&directives);
+
+    tracing_subscriber::registry()
+        .with(stdout_layer)
+        .with(channel_layer(channel_filter))
         .try_init()
-        .map_err(|e| LoggingError::SetupFailure { source: e })
+        .map_err(|e| LoggingError::SetupFailure { source: e.into() })?;
+
+    log_level::init(&directives, vec![stdout_reload, channel_reload]);
+    Ok(())
 }
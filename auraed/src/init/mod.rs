@@ -18,11 +18,18 @@
 //! The Aurae daemon assumes that if the current process id (PID) is 1 to
 //! run itself as an initialization program, otherwise bypass the init module.
 
-pub use self::system_runtimes::SocketStream;
+pub(crate) use self::power::power_off;
+pub(crate) use self::system_runtimes::{
+    adopt_socket_stream, create_listener_stream,
+};
 use self::system_runtimes::{
     CellSystemRuntime, ContainerSystemRuntime, DaemonSystemRuntime,
     Pid1SystemRuntime, SystemRuntime, SystemRuntimeError,
 };
+pub use self::system_runtimes::{
+    ListenerConfig, SocketPermissions, SocketStream,
+};
+use crate::systemd;
 use std::fs::File;
 use std::io::{BufReader, Read};
 mod fileio;
@@ -74,24 +81,39 @@ pub(crate) enum InitError {
 }
 
 /// Initialize aurae, depending on our context.
+///
+/// Returns the socket to serve on, and the other sockets passed by systemd
+/// if socket activated.
 pub async fn init(
     verbose: bool,
     nested: bool,
     socket_address: Option<String>,
-) -> (Context, SocketStream) {
+) -> (Context, SocketStream, Vec<SocketStream>) {
     let context = Context::get(nested);
+    let mut listen_fds = match systemd::take_listen_fds() {
+        Ok(listen_fds) => listen_fds.into_iter(),
+        Err(e) => panic!("Failed to initialize: {e:?}"),
+    };
+
     let init_result = match context {
         Context::Pid1 => Pid1SystemRuntime {}.init(verbose, socket_address),
         Context::Cell => CellSystemRuntime {}.init(verbose, socket_address),
         Context::Container => {
             ContainerSystemRuntime {}.init(verbose, socket_address)
         }
-        Context::Daemon => DaemonSystemRuntime {}.init(verbose, socket_address),
+        Context::Daemon => DaemonSystemRuntime { listen_fd: listen_fds.next() }
+            .init(verbose, socket_address),
     }
     .await;
 
+    let init_result = init_result.and_then(|stream| {
+        let activated =
+            listen_fds.map(adopt_socket_stream).collect::<Result<_, _>>()?;
+        Ok((stream, activated))
+    });
+
     match init_result {
-        Ok(stream) => (context, stream),
+        Ok((stream, activated)) => (context, stream, activated),
         Err(e) => panic!("Failed to initialize: {e:?}"),
     }
 }
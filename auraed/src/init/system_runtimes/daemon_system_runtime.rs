@@ -13,19 +13,25 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{net::SocketAddr, os::fd::OwnedFd, path::PathBuf, str::FromStr};
 
 use super::{SocketStream, SystemRuntime, SystemRuntimeError};
 use crate::init::{
     logging,
-    system_runtimes::{create_tcp_socket_stream, create_unix_socket_stream},
+    system_runtimes::{
+        adopt_socket_stream, create_tcp_socket_stream,
+        create_unix_socket_stream,
+    },
     BANNER,
 };
 use crate::AURAED_RUNTIME;
 use tonic::async_trait;
 use tracing::{info, trace};
 
-pub(crate) struct DaemonSystemRuntime;
+pub(crate) struct DaemonSystemRuntime {
+    /// The socket systemd passed to auraed to listen on, if socket activated.
+    pub listen_fd: Option<OwnedFd>,
+}
 
 #[async_trait]
 impl SystemRuntime for DaemonSystemRuntime {
@@ -38,6 +44,11 @@ impl SystemRuntime for DaemonSystemRuntime {
         logging::init(verbose, false)?;
         info!("Running as a daemon.");
 
+        if let Some(fd) = self.listen_fd {
+            trace!("Listening on the socket passed by systemd");
+            return adopt_socket_stream(fd);
+        }
+
         // Running as a daemon supports both TCP and Unix sockets for listening, depending on the
         // socket address that's passed in.
         let sockaddr = socket_address.unwrap_or_else(|| {
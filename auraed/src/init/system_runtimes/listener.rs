@@ -0,0 +1,228 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{SocketPermissions, ABSTRACT_SOCKET_PREFIX};
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A socket auraed listens on next to its main socket, serving the same
+/// services.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerConfig {
+    /// A unix socket without TLS. Clients are identified by the uid and gid
+    /// of their process (SO_PEERCRED) instead of a certificate, so access is
+    /// governed by the permissions of the socket and the authorization
+    /// policy, which is required. A path starting with '@' names a socket in
+    /// the abstract namespace.
+    Unix {
+        /// The path of the socket file, or '@' and the abstract name.
+        path: PathBuf,
+        /// The permissions of the socket file.
+        permissions: SocketPermissions,
+    },
+    /// A TCP socket, with mTLS.
+    Tcp {
+        /// The address to listen on.
+        addr: SocketAddr,
+    },
+}
+
+impl ListenerConfig {
+    /// Checks that the clients of the listener are authorized by a policy
+    /// if it is served without TLS, as every client could otherwise call
+    /// every method. Unix sockets are never served with TLS, and TCP sockets
+    /// are not when auraed runs in a cell.
+    pub fn check_authorized(
+        &self,
+        tls: bool,
+        authz_policy: bool,
+    ) -> Result<(), String> {
+        if authz_policy {
+            return Ok(());
+        }
+
+        match self {
+            Self::Unix { path, .. }
+                if path.to_str().is_some_and(|path| {
+                    path.starts_with(ABSTRACT_SOCKET_PREFIX)
+                }) =>
+            {
+                Err(format!(
+                    "{self} is in the abstract namespace, where any process may connect to it, and requires an authorization policy"
+                ))
+            }
+            Self::Unix { .. } => Err(format!(
+                "{self} is served without TLS, and requires an authorization policy"
+            )),
+            Self::Tcp { .. } if !tls => Err(format!(
+                "{self} is served without TLS, and requires an authorization policy"
+            )),
+            Self::Tcp { .. } => Ok(()),
+        }
+    }
+}
+
+impl FromStr for ListenerConfig {
+    type Err = String;
+
+    /// Parses a listener written as `tcp:<address>`, or as
+    /// `unix:<path>[,mode=<octal>][,owner=<uid>][,group=<gid>]`. The mode
+    /// of a unix socket defaults to 600, as it has no TLS.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tcp:") {
+            let addr = addr
+                .parse()
+                .map_err(|e| format!("invalid address in '{s}': {e}"))?;
+            return Ok(Self::Tcp { addr });
+        }
+
+        let Some(unix) = s.strip_prefix("unix:") else {
+            return Err(format!(
+                "expected tcp:<address> or unix:<path>, got '{s}'"
+            ));
+        };
+
+        let mut options = unix.split(',');
+        let path = options.next().unwrap_or_default();
+        if path.is_empty() {
+            return Err(format!("missing a path in '{s}'"));
+        }
+
+        let mut permissions =
+            SocketPermissions { mode: 0o600, owner: None, group: None };
+        for option in options {
+            let invalid = || format!("invalid option '{option}' in '{s}'");
+            let (name, value) = option.split_once('=').ok_or_else(invalid)?;
+            match name {
+                "mode" => {
+                    permissions.mode =
+                        u32::from_str_radix(value, 8).map_err(|_| invalid())?
+                }
+                "owner" => {
+                    permissions.owner =
+                        Some(value.parse().map_err(|_| invalid())?)
+                }
+                "group" => {
+                    permissions.group =
+                        Some(value.parse().map_err(|_| invalid())?)
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Self::Unix { path: path.into(), permissions })
+    }
+}
+
+impl Display for ListenerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unix { path, permissions } => {
+                let SocketPermissions { mode, owner, group } = permissions;
+                write!(f, "unix:{},mode={mode:o}", path.display())?;
+                if let Some(owner) = owner {
+                    write!(f, ",owner={owner}")?;
+                }
+                if let Some(group) = group {
+                    write!(f, ",group={group}")?;
+                }
+                Ok(())
+            }
+            Self::Tcp { addr } => write!(f, "tcp:{addr}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcp() {
+        assert_eq!(
+            "tcp:[::1]:8443".parse(),
+            Ok(ListenerConfig::Tcp { addr: "[::1]:8443".parse().unwrap() })
+        );
+        assert!("tcp:localhost".parse::<ListenerConfig>().is_err());
+    }
+
+    #[test]
+    fn test_parse_unix() {
+        assert_eq!(
+            "unix:/run/aurae/local.sock".parse(),
+            Ok(ListenerConfig::Unix {
+                path: "/run/aurae/local.sock".into(),
+                permissions: SocketPermissions {
+                    mode: 0o600,
+                    owner: None,
+                    group: None
+                },
+            })
+        );
+
+        assert_eq!(
+            "unix:@aurae,mode=660,group=100".parse(),
+            Ok(ListenerConfig::Unix {
+                path: "@aurae".into(),
+                permissions: SocketPermissions {
+                    mode: 0o660,
+                    owner: None,
+                    group: Some(100)
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_display_parses_back() {
+        for input in [
+            "tcp:[::1]:8443",
+            "unix:/run/aurae/local.sock,mode=600",
+            "unix:@aurae,mode=660,owner=0,group=100",
+        ] {
+            let listener = input.parse::<ListenerConfig>().unwrap();
+            assert_eq!(listener.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_listeners_without_tls_require_a_policy() {
+        let tcp: ListenerConfig = "tcp:[::1]:8443".parse().unwrap();
+        assert!(tcp.check_authorized(true, false).is_ok());
+        assert!(tcp.check_authorized(false, false).is_err());
+        assert!(tcp.check_authorized(false, true).is_ok());
+
+        for input in ["unix:/run/aurae/local.sock", "unix:@aurae"] {
+            let unix: ListenerConfig = input.parse().unwrap();
+            assert!(unix.check_authorized(true, false).is_err(), "{input}");
+            assert!(unix.check_authorized(true, true).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for input in [
+            "/run/aurae/local.sock",
+            "unix:",
+            "unix:/a.sock,mode=999",
+            "unix:/a.sock,owner",
+            "unix:/a.sock,user=0",
+        ] {
+            assert!(input.parse::<ListenerConfig>().is_err(), "{input}");
+        }
+    }
+}
@@ -12,18 +12,30 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
+use crate::AURAED_RUNTIME;
 use anyhow::{anyhow, Context};
 pub(crate) use cell_system_runtime::CellSystemRuntime;
 pub(crate) use container_system_runtime::ContainerSystemRuntime;
 pub(crate) use daemon_system_runtime::DaemonSystemRuntime;
+pub use listener::ListenerConfig;
+use nix::sys::socket::{
+    getsockname, AddressFamily, SockaddrLike, SockaddrStorage, UnixAddr,
+};
 pub(crate) use pid1_system_runtime::Pid1SystemRuntime;
+use serde::{Deserialize, Serialize};
 use std::{
+    io,
     net::SocketAddr,
-    os::unix::prelude::PermissionsExt,
+    os::fd::{AsRawFd, OwnedFd},
+    os::linux::net::SocketAddrExt,
+    os::unix::prelude::{MetadataExt, PermissionsExt},
     path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
 };
-use tokio::net::{TcpListener, UnixListener};
+use tokio::net::{TcpListener, UnixListener, UnixStream};
 use tokio_stream::wrappers::{TcpListenerStream, UnixListenerStream};
+use tokio_stream::Stream;
 use tonic::async_trait;
 use tracing::{info, trace};
 
@@ -32,6 +44,7 @@ use super::{fs::FsError, logging::LoggingError, network::NetworkError};
 mod cell_system_runtime;
 mod container_system_runtime;
 mod daemon_system_runtime;
+mod listener;
 mod pid1_system_runtime;
 
 #[derive(thiserror::Error, Debug)]
@@ -50,6 +63,33 @@ pub(crate) enum SystemRuntimeError {
     Other(#[from] anyhow::Error),
 }
 
+/// Unix socket addresses starting with this are bound in the abstract
+/// namespace, which leaves no node on the filesystem.
+const ABSTRACT_SOCKET_PREFIX: char = '@';
+
+/// The ownership and mode of the unix socket auraed listens on.
+///
+/// These do not apply to sockets in the abstract namespace, which any process
+/// in the network namespace of auraed may connect to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocketPermissions {
+    /// The mode of the socket file.
+    pub mode: u32,
+    /// The user id to own the socket file. Defaults to the user of auraed.
+    pub owner: Option<u32>,
+    /// The group id to own the socket file. Defaults to the group of auraed.
+    pub group: Option<u32>,
+}
+
+impl Default for SocketPermissions {
+    fn default() -> Self {
+        // The mode 766 is what allows non-root users to dial the socket
+        // and authenticate with mTLS.
+        Self { mode: 0o766, owner: None, group: None }
+    }
+}
+
 /// A [SocketStream] can represent either a TCP or Unix socket stream.
 #[derive(Debug)]
 pub enum SocketStream {
@@ -57,7 +97,76 @@ pub enum SocketStream {
     Tcp(TcpListenerStream),
 
     /// Contains a stream for listening over a Unix socket.
-    Unix(UnixListenerStream),
+    Unix(UnixSocketStream),
+}
+
+/// A stream for listening over a Unix socket, which removes the socket file
+/// once dropped, i.e. once the server stops.
+#[derive(Debug)]
+pub struct UnixSocketStream {
+    stream: UnixListenerStream,
+    /// [None] for sockets in the abstract namespace.
+    path: Option<PathBuf>,
+}
+
+impl Stream for UnixSocketStream {
+    type Item = io::Result<UnixStream>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().stream).poll_next(cx)
+    }
+}
+
+impl Drop for UnixSocketStream {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            trace!("Removing socket: {}", path.display());
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl SocketStream {
+    /// Describes the socket the stream listens on, e.g. to check that the
+    /// clients of a socket passed by systemd are authorized.
+    pub(crate) fn listener_config(&self) -> io::Result<ListenerConfig> {
+        match self {
+            SocketStream::Tcp(stream) => {
+                Ok(ListenerConfig::Tcp { addr: stream.as_ref().local_addr()? })
+            }
+            SocketStream::Unix(stream) => {
+                let fd = stream.stream.as_ref().as_raw_fd();
+                let addr =
+                    getsockname::<UnixAddr>(fd).map_err(io::Error::from)?;
+                if let Some(path) = addr.path() {
+                    let metadata = std::fs::metadata(path)?;
+                    return Ok(ListenerConfig::Unix {
+                        path: path.to_path_buf(),
+                        permissions: SocketPermissions {
+                            mode: metadata.mode() & 0o777,
+                            owner: Some(metadata.uid()),
+                            group: Some(metadata.gid()),
+                        },
+                    });
+                }
+
+                // Any process may connect to an abstract or unnamed socket
+                let name = addr.as_abstract().unwrap_or_default();
+                let name = String::from_utf8_lossy(name);
+                Ok(ListenerConfig::Unix {
+                    path: format!("{ABSTRACT_SOCKET_PREFIX}{name}").into(),
+                    permissions: SocketPermissions {
+                        mode: 0o777,
+                        owner: None,
+                        group: None,
+                    },
+                })
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -69,9 +178,73 @@ pub(crate) trait SystemRuntime {
     ) -> Result<SocketStream, SystemRuntimeError>;
 }
 
+/// Binds a socket auraed listens on next to its main socket.
+pub(crate) async fn create_listener_stream(
+    listener: &ListenerConfig,
+) -> Result<SocketStream, SystemRuntimeError> {
+    match listener {
+        ListenerConfig::Unix { path, permissions } => {
+            bind_unix_socket(path.clone(), permissions).await
+        }
+        ListenerConfig::Tcp { addr } => create_tcp_socket_stream(*addr).await,
+    }
+}
+
+/// Serves on a socket bound by another process, e.g. passed by systemd.
+/// The socket file of a unix socket is left to that process to remove.
+#[allow(clippy::result_large_err)]
+pub(crate) fn adopt_socket_stream(
+    fd: OwnedFd,
+) -> Result<SocketStream, SystemRuntimeError> {
+    let addr = getsockname::<SockaddrStorage>(fd.as_raw_fd())
+        .map_err(io::Error::from)?;
+
+    match addr.family() {
+        Some(AddressFamily::Unix) => {
+            let sock = std::os::unix::net::UnixListener::from(fd);
+            sock.set_nonblocking(true)?;
+            let sock = UnixListener::from_std(sock)?;
+            info!("Adopted User Access Socket: {addr}");
+
+            Ok(SocketStream::Unix(UnixSocketStream {
+                stream: UnixListenerStream::new(sock),
+                path: None,
+            }))
+        }
+        Some(AddressFamily::Inet | AddressFamily::Inet6) => {
+            let sock = std::net::TcpListener::from(fd);
+            sock.set_nonblocking(true)?;
+            let sock = TcpListener::from_std(sock)?;
+            info!("Adopted TCP Access Socket: {addr}");
+
+            Ok(SocketStream::Tcp(TcpListenerStream::new(sock)))
+        }
+        family => Err(anyhow!(
+            "cannot serve on a socket of family {family:?}: {addr}"
+        )
+        .into()),
+    }
+}
+
 async fn create_unix_socket_stream(
     socket_path: PathBuf,
 ) -> Result<SocketStream, SystemRuntimeError> {
+    let permissions =
+        &AURAED_RUNTIME.get().expect("runtime").socket_permissions;
+    bind_unix_socket(socket_path, permissions).await
+}
+
+async fn bind_unix_socket(
+    socket_path: PathBuf,
+    permissions: &SocketPermissions,
+) -> Result<SocketStream, SystemRuntimeError> {
+    if let Some(name) = socket_path
+        .to_str()
+        .and_then(|path| path.strip_prefix(ABSTRACT_SOCKET_PREFIX))
+    {
+        return create_abstract_socket_stream(name).await;
+    }
+
     let _ = std::fs::remove_file(&socket_path);
     let sock_path = Path::new(&socket_path).parent().ok_or_else(|| {
         anyhow!("not a valid socket path: {:?}", &socket_path)
@@ -87,17 +260,49 @@ async fn create_unix_socket_stream(
 
     let sock = UnixListener::bind(&socket_path)?;
 
-    // We set the mode to 766 for the Unix domain socket.
-    // This is what allows non-root users to dial the socket
-    // and authenticate with mTLS.
-    trace!("Setting socket mode {} -> 766", &socket_path.display());
+    trace!(
+        "Setting socket mode {} -> {:o}",
+        &socket_path.display(),
+        permissions.mode
+    );
     std::fs::set_permissions(
         &socket_path,
-        std::fs::Permissions::from_mode(0o766),
+        std::fs::Permissions::from_mode(permissions.mode),
     )?;
+    if permissions.owner.is_some() || permissions.group.is_some() {
+        trace!(
+            "Setting socket owner {} -> {:?}:{:?}",
+            &socket_path.display(),
+            permissions.owner,
+            permissions.group
+        );
+        std::os::unix::fs::chown(
+            &socket_path,
+            permissions.owner,
+            permissions.group,
+        )?;
+    }
     info!("User Access Socket Created: {}", socket_path.display());
 
-    Ok(SocketStream::Unix(UnixListenerStream::new(sock)))
+    Ok(SocketStream::Unix(UnixSocketStream {
+        stream: UnixListenerStream::new(sock),
+        path: Some(socket_path),
+    }))
+}
+
+async fn create_abstract_socket_stream(
+    name: &str,
+) -> Result<SocketStream, SystemRuntimeError> {
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+    let sock = std::os::unix::net::UnixListener::bind_addr(&addr)?;
+    sock.set_nonblocking(true)?;
+    let sock = UnixListener::from_std(sock)?;
+    info!("User Access Socket Created: {ABSTRACT_SOCKET_PREFIX}{name}");
+
+    Ok(SocketStream::Unix(UnixSocketStream {
+        stream: UnixListenerStream::new(sock),
+        path: None,
+    }))
 }
 
 async fn create_tcp_socket_stream(
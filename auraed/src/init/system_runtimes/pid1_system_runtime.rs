@@ -22,6 +22,7 @@ use crate::init::{
     BANNER,
 };
 use nix::{
+    errno::Errno,
     mount::MsFlags,
     unistd::{mkdir, symlinkat},
 };
@@ -69,7 +70,19 @@ impl SystemRuntime for Pid1SystemRuntime {
         info!("Running as pid 1");
         trace!("Configure filesystem");
 
-        mkdir("/dev/pts", *CHMOD_0755).map_err(FsError::FileCreationFailure)?;
+        MountSpec {
+            source: Some("devtmpfs"),
+            target: "/dev",
+            fstype: Some("devtmpfs"),
+            flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
+            data: Some("mode=0755"),
+        }
+        .mount_if_absent()?;
+
+        match mkdir("/dev/pts", *CHMOD_0755) {
+            Ok(()) | Err(Errno::EEXIST) => {}
+            Err(e) => return Err(FsError::FileCreationFailure(e).into()),
+        }
         MountSpec {
             source: Some("devpts"),
             target: "/dev/pts",
@@ -79,7 +92,7 @@ impl SystemRuntime for Pid1SystemRuntime {
                 | MsFlags::MS_NOATIME,
             data: Some("mode=0620,gid=5,ptmxmode=666"),
         }
-        .mount()?;
+        .mount_if_absent()?;
 
         MountSpec {
             source: Some("sysfs"),
@@ -88,7 +101,7 @@ impl SystemRuntime for Pid1SystemRuntime {
             flags: *COMMON_MNT_FLAGS,
             data: None,
         }
-        .mount()?;
+        .mount_if_absent()?;
 
         MountSpec {
             source: Some("proc"),
@@ -97,7 +110,7 @@ impl SystemRuntime for Pid1SystemRuntime {
             flags: *COMMON_MNT_FLAGS,
             data: None,
         }
-        .mount()?;
+        .mount_if_absent()?;
 
         MountSpec {
             source: Some("run"),
@@ -124,7 +137,7 @@ impl SystemRuntime for Pid1SystemRuntime {
             flags: *CGROUP_MNT_FLAGS,
             data: None,
         }
-        .mount()?;
+        .mount_if_absent()?;
 
         MountSpec {
             source: Some("debugfs"),
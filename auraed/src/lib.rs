@@ -63,17 +63,37 @@
 )]
 #![warn(clippy::unwrap_used)]
 
+pub use crate::admission::AdmissionConfig;
+pub use crate::audit::AuditConfig;
 pub use crate::auraed_path::AuraedPath;
+pub use crate::blocking::BlockingPoolsConfig;
+pub use crate::cells::IdMapping;
+pub use crate::config::{AuraedConfig, ConfigError, DEFAULT_CONFIG_PATH};
+pub use crate::cri::DnsConfig;
+pub use crate::discovery::DiscoveryConfig;
 use crate::ebpf::{
     BpfContext, SchedProcessForkTracepointProgram,
     SignalSignalGenerateTracepointProgram, TaskstatsExitKProbeProgram,
 };
+pub use crate::graceful_shutdown::ShutdownConfig;
+pub use crate::init::{ListenerConfig, SocketPermissions};
+pub use crate::logging::output_limit::OutputLimit;
+pub use crate::logging::redaction::RedactionRule;
+pub use crate::observe::EventJournalConfig;
 use crate::{
-    cells::CellService, cri::oci::AuraeOCIBuilder,
-    cri::runtime_service::RuntimeService, discovery::DiscoveryService,
-    init::Context as AuraeContext, init::SocketStream,
-    logging::log_channel::LogChannel, observe::ObserveService,
+    admission::AdmissionController,
+    authz::AuthzLayer,
+    cells::{CellService, CgroupMode, UserNamespace},
+    cri::oci::AuraeOCIBuilder,
+    cri::runtime_service::RuntimeService,
+    discovery::DiscoveryService,
+    health::HealthRegistry,
+    init::Context as AuraeContext,
+    init::{create_listener_stream, SocketStream},
+    observe::ObserveService,
+    peer::accept_peers,
     spawn::spawn_auraed_oci_to,
+    tls::TlsPaths,
 };
 use anyhow::{anyhow, Context};
 use aurae_ebpf_shared::{ForkedProcess, ProcessExit, Signal};
@@ -87,24 +107,39 @@ use proto::{
 };
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncWrite;
-use tokio::task::JoinHandle;
-use tonic::transport::server::Connected;
-use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tokio::sync::watch::Receiver;
+use tokio::task::{JoinHandle, JoinSet};
+use tonic::service::Routes;
+use tonic::transport::server::{Connected, Router};
+use tonic::transport::Server;
+use tower_layer::{Identity as IdentityLayer, Stack};
 use tracing::{error, info, trace, warn};
-use vms::VmService;
+use vms::{CloudHypervisor, VmService};
 
+mod admission;
+mod audit;
 mod auraed_path;
+mod authz;
+mod blocking;
 mod cells;
+mod config;
 mod cri;
+mod deadline;
 mod discovery;
 mod ebpf;
 mod graceful_shutdown;
+mod health;
 mod init;
 mod logging;
 mod observe;
+mod peer;
+mod reaper;
 mod spawn;
+mod systemd;
+mod tls;
 mod vms;
 
 static AURAED_RUNTIME: OnceCell<AuraedRuntime> = OnceCell::new();
@@ -129,6 +164,56 @@ pub struct AuraedRuntime {
     pub runtime_dir: PathBuf,
     /// Configurable library directory. Defaults to /var/lib/aurae.
     pub library_dir: PathBuf,
+    /// Sizes of the thread pools that run blocking operations.
+    pub blocking_pools: BlockingPoolsConfig,
+    /// Ownership and mode of the unix socket auraed listens on.
+    pub socket_permissions: SocketPermissions,
+    /// Secret patterns redacted from the logs of executables and auraed.
+    pub log_redaction: Vec<RedactionRule>,
+    /// The default rate of the output of each executable, over which
+    /// lines are suppressed.
+    pub output_limit: OutputLimit,
+    /// How long the log channels of a stopped executable remain available
+    /// to late readers.
+    pub log_grace_period: Duration,
+    /// How long the outputs of a stopped executable are read for, before
+    /// they are cut short, as when a process it left running holds them
+    /// open.
+    pub output_drain_timeout: Duration,
+    /// Where the calls that change workloads are audited.
+    pub audit: AuditConfig,
+    /// The policy of which clients may call which methods, reloaded on
+    /// SIGHUP. Any client trusted by the CA may call any method if unset.
+    pub authz_policy: Option<PathBuf>,
+    /// How long workloads are given to exit when auraed shuts down.
+    pub shutdown: ShutdownConfig,
+    /// How auraed keeps track of the peers which register with it.
+    pub discovery: DiscoveryConfig,
+    /// How the lifecycle events are journaled.
+    pub events: EventJournalConfig,
+    /// Whether and how cells and pods are admitted against the capacity of
+    /// the host.
+    pub admission: AdmissionConfig,
+    /// The DNS settings the pods are started with.
+    pub dns: DnsConfig,
+    /// The sockets auraed listens on next to its main socket.
+    pub listeners: Vec<ListenerConfig>,
+    /// Reap the processes orphaned to auraed as a child subreaper, as
+    /// auraed always does when it runs as pid 1.
+    pub subreaper: bool,
+    /// The uid map of the user namespace the executables are started in.
+    /// They share the user namespace of auraed if it is empty.
+    pub uid_map: Vec<IdMapping>,
+    /// The gid map of the user namespace the executables are started in.
+    pub gid_map: Vec<IdMapping>,
+    /// How often the lifetime stats of the cells are sampled from their
+    /// cgroups, which is how stale they may be. Zero only samples them
+    /// when an executable of the cell is stopped.
+    pub lifetime_stats_interval: Duration,
+    /// The cgroup of the cell a nested auraed runs in, under which each
+    /// executable it starts is accounted in a leaf cgroup of its own. Set
+    /// for the cells with per executable accounting.
+    pub executable_cgroups: Option<PathBuf>,
     // /// Provides logging channels to expose auraed logging via grpc
     //pub log_collector: Arc<LogChannel>,
 }
@@ -142,9 +227,37 @@ impl AuraedRuntime {
         self.runtime_dir.join("pods")
     }
 
+    pub(crate) fn vms_dir(&self) -> PathBuf {
+        self.runtime_dir.join("vms")
+    }
+
     pub(crate) fn default_socket_address(&self) -> PathBuf {
         self.runtime_dir.join("aurae.sock")
     }
+
+    pub(crate) fn cell_service_state_file(&self) -> PathBuf {
+        self.runtime_dir.join("cell_service.json")
+    }
+
+    pub(crate) fn peers_file(&self) -> PathBuf {
+        self.runtime_dir.join("peers.json")
+    }
+
+    pub(crate) fn events_journal_file(&self) -> PathBuf {
+        self.runtime_dir.join("events.journal")
+    }
+
+    /// The user namespace the executables are started in, if any.
+    pub(crate) fn user_namespace(&self) -> Option<UserNamespace> {
+        if self.uid_map.is_empty() {
+            return None;
+        }
+
+        Some(UserNamespace {
+            uid_map: self.uid_map.clone(),
+            gid_map: self.gid_map.clone(),
+        })
+    }
 }
 
 impl Default for AuraedRuntime {
@@ -157,6 +270,25 @@ impl Default for AuraedRuntime {
             server_key: PathBuf::from("/etc/aurae/pki/server.key"),
             runtime_dir: PathBuf::from("/var/run/aurae"),
             library_dir: PathBuf::from("/var/lib/aurae"),
+            blocking_pools: BlockingPoolsConfig::default(),
+            socket_permissions: SocketPermissions::default(),
+            log_redaction: vec![],
+            output_limit: OutputLimit::default(),
+            log_grace_period: logging::log_registry::DEFAULT_GRACE_PERIOD,
+            output_drain_timeout: logging::output_drain::DEFAULT_DRAIN_TIMEOUT,
+            audit: AuditConfig::default(),
+            authz_policy: None,
+            shutdown: ShutdownConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            events: EventJournalConfig::default(),
+            admission: AdmissionConfig::default(),
+            dns: DnsConfig::default(),
+            listeners: vec![],
+            subreaper: false,
+            uid_map: vec![],
+            gid_map: vec![],
+            lifetime_stats_interval: cells::DEFAULT_LIFETIME_STATS_INTERVAL,
+            executable_cgroups: None,
         }
     }
 }
@@ -172,11 +304,12 @@ pub async fn run(
         runtime: &AuraedRuntime,
         context: AuraeContext,
         socket_stream: T,
+        activated: Vec<SocketStream>,
     ) -> Result<(), Box<dyn std::error::Error>>
     where
         T: tokio_stream::Stream<Item = Result<IO, IE>> + Send + 'static,
         IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
-        IE: Into<Box<dyn std::error::Error + Send + Sync>>,
+        IE: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
     {
         trace!("{:#?}", runtime);
 
@@ -189,40 +322,31 @@ pub async fn run(
             )
         })?;
 
-        // We don't want TLS in cell context
-        let mut server = if context != AuraeContext::Cell {
-            let server_crt =
-                tokio::fs::read(&runtime.server_crt).await.with_context(|| {
-                    format!(
-                        "Aurae requires a signed TLS certificate to run as a server, but failed to
-                        load: '{}'. Please see https://aurae.io/certs/ for information on best
-                        practices to quickly generate one.",
-                        runtime.server_crt.display()
-                    )
-                })?;
-            let server_key = tokio::fs::read(&runtime.server_key).await?;
-            let server_identity = Identity::from_pem(server_crt, server_key);
-            info!("Register Server SSL Identity");
-
-            let ca_crt = tokio::fs::read(&runtime.ca_crt).await?;
-            let ca_crt_pem = Certificate::from_pem(ca_crt);
-
-            let tls = ServerTlsConfig::new()
-                .identity(server_identity)
-                .client_ca_root(ca_crt_pem);
-
-            info!(
-                "Validating SSL Identity and Root Certificate Authority (CA)"
-            );
-            //let _log_collector = self.log_collector.clone();
-
-            Server::builder()
-                .tls_config(tls)
-                .with_context(|| "gRPC server failed to configure tls")?
+        // We don't want TLS in cell context. The TLS material is reloaded
+        // as it is rotated, for the connections accepted from there on.
+        let tls = if context != AuraeContext::Cell {
+            let server_tls = tls::init(TlsPaths {
+                server_crt: runtime.server_crt.clone(),
+                server_key: runtime.server_key.clone(),
+                ca_crt: runtime.ca_crt.clone(),
+            })
+            .with_context(|| {
+                format!(
+                    "Aurae requires a signed TLS certificate to run as a server, but failed to
+                    load: '{}'. Please see https://aurae.io/certs/ for information on best
+                    practices to quickly generate one.",
+                    runtime.server_crt.display()
+                )
+            })?;
+            info!("Validated SSL Identity and Root Certificate Authority (CA)");
+            Some(server_tls)
         } else {
-            Server::builder()
+            None
         };
 
+        let authz = AuthzLayer::new(runtime.authz_policy.as_deref())?;
+        let mut server = Server::builder().layer(authz);
+
         // Install eBPF probes in the host Aurae daemon
         let (_bpf_handle, perf_events) = if context == AuraeContext::Cell
             || context == AuraeContext::Container
@@ -243,74 +367,193 @@ pub async fn run(
         };
 
         // Build gRPC Services
-        let (mut health_reporter, health_service) =
+        let (health_reporter, health_service) =
             tonic_health::server::health_reporter();
+        let health = HealthRegistry::new(health_reporter);
 
         let observe_service = ObserveService::new(
-            Arc::new(LogChannel::new(String::from("auraed"))),
+            Arc::new(logging::channel_layer::auraed_channel().clone()),
             perf_events,
         );
+        // Like the cell service, only the host auraed journals its events
+        let observe_service = if runtime.events.persist
+            && context != AuraeContext::Cell
+            && context != AuraeContext::Container
+        {
+            observe_service
+                .with_event_journal(
+                    runtime.events_journal_file(),
+                    runtime.events,
+                )
+                .await
+        } else {
+            observe_service
+        };
         let observe_service_server =
             ObserveServiceServer::new(observe_service.clone());
 
-        let cell_service = CellService::new(observe_service.clone());
-        let cell_service_server = CellServiceServer::new(cell_service.clone());
-        health_reporter.set_serving::<CellServiceServer<CellService>>().await;
+        // Detect the cgroup hierarchy before any cell is allocated
+        let cgroup_mode = CgroupMode::current();
+        info!(
+            "Using the cgroup {} backend for the {cgroup_mode} cgroup hierarchy",
+            if cgroup_mode.is_v2() { "v2" } else { "v1" }
+        );
+
+        // Only the host auraed admits workloads, as those of nested auraeds
+        // are bounded by the limits of their cell or pod.
+        let admission = (runtime.admission.enabled
+            && context != AuraeContext::Cell
+            && context != AuraeContext::Container)
+            .then(|| AdmissionController::new(runtime.admission));
+
+        let cell_service = CellService::new(observe_service.clone())
+            .with_lifetime_stats_interval(runtime.lifetime_stats_interval);
+        let cell_service = match &admission {
+            Some(admission) => cell_service.with_admission(admission.clone()),
+            None => cell_service,
+        };
+        // Only the host auraed persists its workloads, as nested auraeds
+        // share its runtime directory.
+        let cell_service = if context == AuraeContext::Cell {
+            cell_service.in_cell()
+        } else if context == AuraeContext::Container {
+            cell_service
+        } else {
+            cell_service
+                .with_state_file(runtime.cell_service_state_file())
+                .await
+        };
+        let cell_service_server = CellServiceServer::with_interceptor(
+            cell_service.clone(),
+            audit::interceptor,
+        );
+        // Reports the CellService serving if cells can be allocated
+        health::start_checks(health.clone(), cell_service.clone()).await;
 
-        let discovery_service = DiscoveryService::new();
+        let discovery_service = DiscoveryService::new(
+            runtime.discovery,
+            observe_service.stream_closer().clone(),
+        );
+        // Like the cell service, only the host auraed persists its peers
+        let discovery_service = if runtime.discovery.persist
+            && context != AuraeContext::Cell
+            && context != AuraeContext::Container
+        {
+            discovery_service.with_state_file(runtime.peers_file()).await
+        } else {
+            discovery_service
+        };
+        discovery_service.expire_peers();
         let discovery_service_server =
             DiscoveryServiceServer::new(discovery_service);
-        health_reporter
-            .set_serving::<DiscoveryServiceServer<DiscoveryService>>()
-            .await;
+        health.set_serving::<DiscoveryServiceServer<DiscoveryService>>().await;
 
-        health_reporter
-            .set_serving::<ObserveServiceServer<ObserveService>>()
-            .await;
+        health.set_serving::<ObserveServiceServer<ObserveService>>().await;
 
         // let pod_service = PodService::new(self.runtime_dir.clone());
         // let pod_service_server = PodServiceServer::new(pod_service.clone());
-        // health_reporter.set_serving::<PodServiceServer<PodService>>().await;
-        let runtime_service = RuntimeService::new();
+        // health.set_serving::<PodServiceServer<PodService>>().await;
+        let runtime_service = match admission {
+            Some(admission) => RuntimeService::new().with_admission(admission),
+            None => RuntimeService::new(),
+        };
+        observe_service.set_pod_sandboxes(runtime_service.pod_sandboxes());
         let runtime_service_server =
             RuntimeServiceServer::new(runtime_service.clone());
-        health_reporter
-            .set_serving::<RuntimeServiceServer<RuntimeService>>()
-            .await;
+        health.set_serving::<RuntimeServiceServer<RuntimeService>>().await;
 
-        let vm_service = VmService::new();
+        let vm_service = VmService::new(
+            runtime.vms_dir(),
+            Arc::new(CloudHypervisor::default()),
+        );
         let vm_service_server = VmServiceServer::new(vm_service.clone());
-        health_reporter.set_serving::<VmServiceServer<VmService>>().await;
+        health.set_serving::<VmServiceServer<VmService>>().await;
 
         let graceful_shutdown = graceful_shutdown::GracefulShutdown::new(
-            health_reporter,
-            cell_service,
+            health,
+            cell_service.clone(),
+            observe_service,
+            runtime.shutdown,
         );
-        let graceful_shutdown_signal = graceful_shutdown.subscribe();
 
-        // Run the server concurrently
         // TODO: pass a known-good path to CellService to store any runtime data.
-        let server_handle = tokio::spawn(async move {
-            server
-                .add_service(health_service)
-                .add_service(cell_service_server)
-                .add_service(discovery_service_server)
-                .add_service(observe_service_server)
-                // .add_service(pod_service_server)
-                .add_service(runtime_service_server)
-                .add_service(vm_service_server)
-                .serve_with_incoming_shutdown(socket_stream, async {
-                    let mut graceful_shutdown_signal = graceful_shutdown_signal;
-                    let _ = graceful_shutdown_signal.changed().await;
-                    info!("gRPC server received shutdown signal...");
-                })
+        let mut routes = Routes::builder();
+        let _ = routes
+            .add_service(health_service)
+            .add_service(cell_service_server)
+            .add_service(discovery_service_server)
+            .add_service(observe_service_server)
+            // .add_service(pod_service_server)
+            .add_service(runtime_service_server)
+            .add_service(vm_service_server);
+        let routes = routes.routes();
+
+        // Run a server concurrently for the socket, for each other socket
+        // passed by systemd, and for each listener
+        let mut servers = JoinSet::new();
+        let router = server.add_routes(routes.clone());
+        let shutdown = graceful_shutdown.subscribe();
+        let _ = match tls {
+            Some(tls) => servers.spawn(serve(
+                router,
+                tls.accept(socket_stream),
+                shutdown,
+            )),
+            None => servers.spawn(serve(router, socket_stream, shutdown)),
+        };
+        // Sockets passed by systemd are configured in its socket units
+        // rather than as listeners, but must be authorized all the same
+        for stream in &activated {
+            let listener = stream
+                .listener_config()
+                .map_err(|e| anyhow!("failed to inspect socket: {e}"))?;
+            listener
+                .check_authorized(tls.is_some(), runtime.authz_policy.is_some())
+                .map_err(|e| anyhow!("refusing to serve: {e}"))?;
+        }
+        let mut streams = activated;
+        for listener in &runtime.listeners {
+            listener
+                .check_authorized(tls.is_some(), runtime.authz_policy.is_some())
+                .map_err(|e| anyhow!("refusing to listen: {e}"))?;
+            let stream = create_listener_stream(listener)
                 .await
-                .with_context(|| "gRPC server exited with error")?;
-
-            info!("gRPC server exited successfully");
+                .map_err(|e| anyhow!("failed to listen on {listener}: {e}"))?;
+            streams.push(stream);
+        }
+        for stream in streams {
+            let shutdown = graceful_shutdown.subscribe();
+            let router = server.add_routes(routes.clone());
+            let _ = match (stream, tls) {
+                (SocketStream::Tcp(stream), Some(tls)) => {
+                    servers.spawn(serve(router, tls.accept(stream), shutdown))
+                }
+                (SocketStream::Tcp(stream), None) => {
+                    servers.spawn(serve(router, stream, shutdown))
+                }
+                // Unix socket listeners identify clients by their process,
+                // not TLS
+                (SocketStream::Unix(stream), _) => {
+                    servers.spawn(serve(router, accept_peers(stream), shutdown))
+                }
+            };
+        }
 
-            Ok(())
-        });
+        // systemd considers auraed started once all services are served and
+        // cells can be allocated. Otherwise, it fails the start once it
+        // times out.
+        match cell_service.check_health() {
+            Ok(()) => {
+                systemd::notify("READY=1");
+                systemd::start_watchdog();
+            }
+            Err(e) => {
+                error!("Cells cannot be allocated, not ready: {e}");
+                systemd::notify(&format!(
+                    "STATUS=Cells cannot be allocated: {e}"
+                ));
+            }
+        }
 
         // Event loop
         let graceful_shutdown_handle = tokio::spawn(async {
@@ -328,23 +571,105 @@ pub async fn run(
             }
         }
 
-        if let Err(e) = tokio::try_join!(
-            flatten(server_handle),
-            flatten(graceful_shutdown_handle)
-        ) {
-            error!("exiting due to error: {e:?}");
+        // Exit once shut down, even if a server is still waiting on a
+        // connection past the shutdown deadline.
+        let graceful_shutdown_handle = flatten(graceful_shutdown_handle);
+        tokio::pin!(graceful_shutdown_handle);
+        loop {
+            tokio::select! {
+                Some(result) = servers.join_next() => {
+                    let result = result
+                        .map_err(|e| anyhow!("failed to join task: {e:?}"))
+                        .and_then(|result| result);
+                    if let Err(e) = result {
+                        error!("exiting due to error: {e:?}");
+                        break;
+                    }
+                }
+                result = &mut graceful_shutdown_handle => {
+                    if let Err(e) = result {
+                        error!("exiting due to error: {e:?}");
+                    }
+                    break;
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Serves `router` on `incoming` until shutdown is broadcast.
+    async fn serve<I, IO, IE>(
+        router: Router<Stack<AuthzLayer, IdentityLayer>>,
+        incoming: I,
+        mut shutdown: Receiver<()>,
+    ) -> Result<(), anyhow::Error>
+    where
+        I: tokio_stream::Stream<Item = Result<IO, IE>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IO::ConnectInfo: Clone + Send + Sync + 'static,
+        IE: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        router
+            .serve_with_incoming_shutdown(incoming, async {
+                let _ = shutdown.changed().await;
+                info!("gRPC server received shutdown signal...");
+            })
+            .await
+            .with_context(|| "gRPC server exited with error")?;
+
+        info!("gRPC server exited successfully");
+
+        Ok(())
+    }
+
     let runtime = AURAED_RUNTIME.get_or_init(|| runtime);
+    blocking::init(&runtime.blocking_pools);
+    logging::redaction::init(&runtime.log_redaction)?;
+    logging::output_limit::init(runtime.output_limit);
+    logging::log_registry::init(runtime.log_grace_period);
+    logging::output_drain::init(runtime.output_drain_timeout);
+    audit::init(&runtime.audit)?;
+    if runtime.uid_map.is_empty() != runtime.gid_map.is_empty() {
+        return Err(anyhow!(
+            "the uid and gid maps of the user namespace must both be set"
+        ));
+    }
+
+    // Before any process is started, which would inherit the environment
+    systemd::init();
 
-    let (context, stream) = init::init(verbose, nested, socket).await;
-    match stream {
-        SocketStream::Tcp(stream) => inner(runtime, context, stream).await,
-        SocketStream::Unix(stream) => inner(runtime, context, stream).await,
+    let (context, stream, activated) =
+        init::init(verbose, nested, socket).await;
+
+    // Orphans are re-parented to pid 1, which must reap them for as long as
+    // it runs, including the nested auraed of a cell isolating processes.
+    if std::process::id() == 1 || runtime.subreaper {
+        reaper::start()?;
     }
+
+    let power_off = context == AuraeContext::Pid1;
+    let result = match stream {
+        SocketStream::Tcp(stream) => {
+            inner(runtime, context, stream, activated).await
+        }
+        SocketStream::Unix(stream) => {
+            let stream = accept_peers(stream);
+            inner(runtime, context, stream, activated).await
+        }
+    };
+
+    // The kernel panics if init exits, so the machine is powered off instead,
+    // once the workloads were stopped.
+    if power_off {
+        if let Err(e) = &result {
+            error!("exiting due to error: {e:?}");
+        }
+        info!("Powering off");
+        init::power_off();
+    }
+
+    result
 }
 
 /// Write the container OCI spec to the filesystem in preparation for spawning Auraed using a container runtime.
@@ -0,0 +1,127 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::log_channel::LogChannel;
+use once_cell::sync::Lazy;
+use proto::observe::LogLevel;
+use std::fmt::{Debug, Write};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+static AURAED_CHANNEL: Lazy<LogChannel> =
+    Lazy::new(|| LogChannel::new(String::from("auraed")));
+
+/// The channel of the tracing events of auraed.
+pub fn auraed_channel() -> &'static LogChannel {
+    &AURAED_CHANNEL
+}
+
+/// Sends tracing events to a [LogChannel], as `<target>: <message> <fields>`.
+/// Events are only formatted while the channel has subscribers.
+#[derive(Debug)]
+pub struct ChannelLayer {
+    channel: LogChannel,
+}
+
+impl ChannelLayer {
+    pub fn new(channel: LogChannel) -> Self {
+        Self { channel }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ChannelLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if !self.channel.has_subscribers() {
+            return;
+        }
+
+        let metadata = event.metadata();
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+
+        self.channel.send_at(
+            log_level(metadata.level()),
+            format!(
+                "{}: {}{}",
+                metadata.target(),
+                visitor.message,
+                visitor.fields
+            ),
+        );
+    }
+}
+
+fn log_level(level: &Level) -> LogLevel {
+    match *level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warn,
+        Level::INFO => LogLevel::Info,
+        Level::DEBUG => LogLevel::Debug,
+        Level::TRACE => LogLevel::Trace,
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test]
+    async fn test_events_are_sent_with_their_level() {
+        let channel = LogChannel::new("test".into());
+        let mut rx = channel.subscribe("test");
+        let subscriber = tracing_subscriber::registry()
+            .with(ChannelLayer::new(channel.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "auraed::test", cell = "ae-1", "cell is {}", "gone");
+            tracing::debug!(target: "auraed::test", "details");
+        });
+
+        let item = rx.recv().await.expect("warn event");
+        assert_eq!(item.level(), LogLevel::Warn);
+        assert_eq!(item.line, "auraed::test: cell is gone cell=ae-1");
+
+        let item = rx.recv().await.expect("debug event");
+        assert_eq!(item.level(), LogLevel::Debug);
+        assert_eq!(item.line, "auraed::test: details");
+    }
+}
@@ -13,41 +13,331 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use super::get_timestamp_sec;
-use proto::observe::LogItem;
-use tokio::sync::broadcast::{self, Receiver, Sender};
+use super::{get_timestamp_sec, redaction};
+use futures::Stream;
+use proto::observe::{LogItem, LogLevel};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::Notify;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+
+/// The lines buffered for subscribers which fall behind, before they miss
+/// some.
+// TODO: decide for a cap. 40 is arbitrary
+const SUBSCRIBER_CAPACITY: usize = 40;
+
+/// The lines of a [Subscription], as a stream. Missed lines are reported as
+/// lagged, and the stream ends once the channel is closed.
+pub type SubscriptionStream = Pin<
+    Box<dyn Stream<Item = Result<LogItem, BroadcastStreamRecvError>> + Send>,
+>;
 
 /// Abstraction Layer for one log generating entity
 /// LogChannel provides channels between Log producers and log consumers
+///
+/// The lines sent are written to a ring buffer, which each subscriber reads
+/// from at its own position. A subscriber which falls behind by more than
+/// the ring holds misses the oldest lines, and is told how many, without
+/// holding up the sender or the other subscribers.
 #[derive(Clone, Debug)]
 pub struct LogChannel {
     /// The human readable (public) name for this log channel.
     pub name: String,
-    tx: Sender<LogItem>,
+    sender: Arc<Sender>,
+    redaction: Redaction,
+}
+
+/// The rules the lines sent to a [LogChannel] are redacted with.
+#[derive(Clone, Debug)]
+enum Redaction {
+    /// The rules passed to [redaction::init].
+    Global,
+    Disabled,
+    #[cfg(test)]
+    Local(Arc<redaction::Redactor>),
+}
+
+/// Closes the ring once the last clone of the channel is dropped.
+#[derive(Debug)]
+struct Sender(Arc<Ring>);
+
+#[derive(Debug)]
+struct Ring {
+    slots: Box<[RwLock<Slot>]>,
+    /// The sequence number of the next line sent.
+    tail: AtomicU64,
+    /// Serializes the senders. Subscribers never take it to read lines.
+    send_lock: Mutex<()>,
+    /// The number of the last lines sent replayed to new subscribers that
+    /// ask for them, up to the length of the ring.
+    retained: usize,
+    closed: AtomicBool,
+    sent: Notify,
+    /// The names of the subscribers, by id.
+    subscribers: Mutex<HashMap<u64, String>>,
+    next_subscriber: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    /// The sequence number of the line, which the slot is reused for every
+    /// length of the ring.
+    seq: u64,
+    item: Option<LogItem>,
+}
+
+/// A subscriber of a [LogChannel], reading the lines sent after it
+/// subscribed at its own pace. Dropping it unsubscribes.
+#[derive(Debug)]
+pub struct Subscription {
+    ring: Arc<Ring>,
+    id: u64,
+    /// The sequence number of the next line to read.
+    next: u64,
 }
 
 impl LogChannel {
     /// Constructor creating the channel for log communication
     pub fn new(name: String) -> LogChannel {
-        // TODO: decide for a cap. 40 is arbitrary
-        let (tx, _) = broadcast::channel(40);
-        LogChannel { name, tx }
+        LogChannel {
+            name,
+            sender: Arc::new(Sender(Arc::new(Ring::new(0)))),
+            redaction: Redaction::Global,
+        }
+    }
+
+    /// Retains the last `capacity` lines sent, for [Self::subscribe_since].
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.sender = Arc::new(Sender(Arc::new(Ring::new(capacity))));
+        self
     }
 
-    /// Getter for consumer channel
-    pub fn subscribe(&self) -> Receiver<LogItem> {
-        self.tx.subscribe()
+    /// Opts the channel out of log redaction, sending lines unchanged.
+    pub fn without_redaction(mut self) -> Self {
+        self.redaction = Redaction::Disabled;
+        self
     }
 
-    /// Wrapper that sends a log line to the channel
+    /// Redacts the lines with the rules of `redactor`, rather than those
+    /// passed to [redaction::init].
+    #[cfg(test)]
+    fn with_redactor(mut self, redactor: Arc<redaction::Redactor>) -> Self {
+        self.redaction = Redaction::Local(redactor);
+        self
+    }
+
+    /// Subscribes to the lines sent from now on, as `name`, e.g. the kind
+    /// of consumer, to tell the subscribers apart.
+    pub fn subscribe(&self, name: impl Into<String>) -> Subscription {
+        self.sender.0.subscribe(name.into())
+    }
+
+    /// Subscribes to the channel, returning the retained lines sent at or
+    /// after `since`, a unix timestamp in seconds, as well. No line is both
+    /// returned and received.
+    pub fn subscribe_since(
+        &self,
+        name: impl Into<String>,
+        since: i64,
+    ) -> (Vec<LogItem>, Subscription) {
+        let ring = &self.sender.0;
+        let _sending = ring.send_lock.lock().expect("poisoned");
+        let tail = ring.tail.load(Ordering::Acquire);
+        let first = tail.saturating_sub(ring.retained as u64);
+        let items = (first..tail)
+            .filter_map(|seq| {
+                ring.slot(seq).read().expect("poisoned").item.clone()
+            })
+            .filter(|item| item.timestamp >= since)
+            .collect();
+        (items, ring.subscribe(name.into()))
+    }
+
+    /// The names of the subscribers of the channel.
+    #[allow(dead_code)]
+    pub fn subscribers(&self) -> Vec<String> {
+        let subscribers = self.sender.0.subscribers.lock().expect("poisoned");
+        subscribers.values().cloned().collect()
+    }
+
+    /// Whether `other` is a clone of this channel.
+    pub fn same_channel(&self, other: &LogChannel) -> bool {
+        Arc::ptr_eq(&self.sender, &other.sender)
+    }
+
+    /// Whether anyone is subscribed to the channel, and would receive lines.
+    pub fn has_subscribers(&self) -> bool {
+        !self.sender.0.subscribers.lock().expect("poisoned").is_empty()
+    }
+
+    /// Wrapper that sends a log line to the channel, redacting the secrets
+    /// it contains unless the channel opted out.
     pub fn send(&self, line: String) {
-        // send returns an Err if there are no receivers. We ignore that.
-        let _ = self.tx.send(LogItem {
+        self.send_at(LogLevel::Unspecified, line)
+    }
+
+    /// Sends a log line with the level of the event it describes. Never
+    /// waits for the subscribers, only for other senders.
+    pub fn send_at(&self, level: LogLevel, line: String) {
+        let redacted = match &self.redaction {
+            Redaction::Global => redaction::redact(&line),
+            Redaction::Disabled => Cow::Borrowed(line.as_str()),
+            #[cfg(test)]
+            Redaction::Local(redactor) => redactor.redact(&line),
+        };
+        let line = match redacted {
+            Cow::Borrowed(_) => line,
+            Cow::Owned(redacted) => redacted,
+        };
+
+        let item = LogItem {
             channel: self.name.clone(),
             line,
             // TODO: milliseconds type in protobuf requires 128bit type
             timestamp: get_timestamp_sec(),
-        });
+            level: level.into(),
+        };
+
+        self.sender.0.send(item);
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.0.closed.store(true, Ordering::Release);
+        self.0.sent.notify_waiters();
+    }
+}
+
+impl Ring {
+    fn new(retained: usize) -> Self {
+        let len = retained.max(SUBSCRIBER_CAPACITY);
+        Self {
+            slots: (0..len).map(|_| Default::default()).collect(),
+            tail: AtomicU64::new(0),
+            send_lock: Mutex::new(()),
+            retained,
+            closed: AtomicBool::new(false),
+            sent: Notify::new(),
+            subscribers: Default::default(),
+            next_subscriber: AtomicU64::new(0),
+        }
+    }
+
+    fn slot(&self, seq: u64) -> &RwLock<Slot> {
+        &self.slots[(seq % self.slots.len() as u64) as usize]
+    }
+
+    /// Subscribes to the lines sent after those sent so far.
+    fn subscribe(self: &Arc<Self>, name: String) -> Subscription {
+        let id = self.next_subscriber.fetch_add(1, Ordering::Relaxed);
+        let _ = self.subscribers.lock().expect("poisoned").insert(id, name);
+        Subscription {
+            ring: self.clone(),
+            id,
+            next: self.tail.load(Ordering::Acquire),
+        }
+    }
+
+    fn send(&self, item: LogItem) {
+        {
+            let _sending = self.send_lock.lock().expect("poisoned");
+            let seq = self.tail.load(Ordering::Relaxed);
+            // Contended only by a subscriber reading the line overwritten,
+            // which it missed by then
+            *self.slot(seq).write().expect("poisoned") =
+                Slot { seq, item: Some(item) };
+            self.tail.store(seq + 1, Ordering::Release);
+        }
+        self.sent.notify_waiters();
+    }
+}
+
+impl Subscription {
+    /// The name the subscriber subscribed as.
+    #[allow(dead_code)]
+    pub fn name(&self) -> String {
+        let subscribers = self.ring.subscribers.lock().expect("poisoned");
+        subscribers.get(&self.id).cloned().unwrap_or_default()
+    }
+
+    /// Receives the next line, waiting for it to be sent. Fails with
+    /// [RecvError::Lagged] and the number of lines missed if the subscriber
+    /// fell behind by more than the channel holds, after which it receives
+    /// the oldest line held. Fails with [RecvError::Closed] once the lines
+    /// sent before the channel was dropped are received.
+    pub async fn recv(&mut self) -> Result<LogItem, RecvError> {
+        loop {
+            // Listen before looking, not to miss a line sent in between
+            let sent = self.ring.sent.notified();
+            tokio::pin!(sent);
+            let _ = sent.as_mut().enable();
+
+            match self.try_recv() {
+                Ok(item) => return Ok(item),
+                Err(TryRecvError::Lagged(n)) => {
+                    return Err(RecvError::Lagged(n))
+                }
+                Err(TryRecvError::Closed) => return Err(RecvError::Closed),
+                Err(TryRecvError::Empty) => sent.await,
+            }
+        }
+    }
+
+    /// Receives the next line if it was sent, as [Self::recv] otherwise.
+    pub fn try_recv(&mut self) -> Result<LogItem, TryRecvError> {
+        loop {
+            // Read before the tail, so that every line sent before the
+            // channel was closed is seen
+            let closed = self.ring.closed.load(Ordering::Acquire);
+            let tail = self.ring.tail.load(Ordering::Acquire);
+            if self.next == tail {
+                return Err(if closed {
+                    TryRecvError::Closed
+                } else {
+                    TryRecvError::Empty
+                });
+            }
+
+            let oldest = tail.saturating_sub(self.ring.slots.len() as u64);
+            if self.next < oldest {
+                let missed = oldest - self.next;
+                self.next = oldest;
+                return Err(TryRecvError::Lagged(missed));
+            }
+
+            let slot = self.ring.slot(self.next).read().expect("poisoned");
+            // Otherwise it was overwritten since the tail was read
+            if slot.seq == self.next {
+                self.next += 1;
+                return Ok(slot.item.clone().expect("sent line"));
+            }
+        }
+    }
+
+    /// Streams the lines, ending once the channel is closed.
+    pub fn into_stream(self) -> SubscriptionStream {
+        Box::pin(futures::stream::unfold(self, |mut subscription| async move {
+            match subscription.recv().await {
+                Ok(item) => Some((Ok(item), subscription)),
+                Err(RecvError::Lagged(n)) => Some((
+                    Err(BroadcastStreamRecvError::Lagged(n)),
+                    subscription,
+                )),
+                Err(RecvError::Closed) => None,
+            }
+        }))
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ =
+            self.ring.subscribers.lock().expect("poisoned").remove(&self.id);
     }
 }
 
@@ -67,11 +357,24 @@ mod tests {
             .expect("failed to initialize logger");
     }
 
+    /// Runs `send` on a thread of its own, outside of the runtime the
+    /// subscribers run on. Resolves once it returns.
+    fn spawn_sender(
+        send: impl FnOnce() + Send + 'static,
+    ) -> tokio::sync::oneshot::Receiver<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let _ = std::thread::spawn(move || {
+            send();
+            let _ = tx.send(());
+        });
+        rx
+    }
+
     #[tokio::test]
     async fn test_ringbuffer_queue() {
         init_logging();
         let channel = LogChannel::new("Test".into());
-        let mut rx = channel.subscribe();
+        let mut rx = channel.subscribe("test");
 
         channel.send("hello".into());
         channel.send("aurae".into());
@@ -89,4 +392,184 @@ mod tests {
         assert!(cur_item.is_some());
         assert_eq!(cur_item.unwrap().line, "bye".to_string());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_redaction_opt_out() {
+        let redactor = redaction::Redactor::new(&[redaction::RedactionRule {
+            name: "test".into(),
+            pattern: "ae-test-secret-[0-9]+".into(),
+        }])
+        .expect("valid rules");
+
+        let channel =
+            LogChannel::new("Test".into()).with_redactor(Arc::new(redactor));
+        let opted_out = LogChannel::new("Test".into()).without_redaction();
+        let mut rx = channel.subscribe("test");
+        let mut opted_out_rx = opted_out.subscribe("test");
+
+        channel.send("key ae-test-secret-42".into());
+        opted_out.send("key ae-test-secret-42".into());
+
+        let item = rx.recv().await.expect("log item");
+        assert_eq!(item.line, "key [REDACTED:test]");
+        let item = opted_out_rx.recv().await.expect("log item");
+        assert_eq!(item.line, "key ae-test-secret-42");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_since_replays_history() {
+        let channel = LogChannel::new("Test".into()).with_history(2);
+        channel.send("hello".into());
+        channel.send("aurae".into());
+        channel.send("bye".into());
+
+        // Only the last lines are retained
+        let (items, mut rx) = channel.subscribe_since("test", 0);
+        let lines: Vec<_> = items.into_iter().map(|item| item.line).collect();
+        assert_eq!(lines, ["aurae", "bye"]);
+
+        let (items, _) = channel.subscribe_since("test", i64::MAX);
+        assert!(items.is_empty());
+
+        // Later lines are received, not replayed
+        channel.send("again".into());
+        assert_eq!(rx.recv().await.expect("line").line, "again");
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_lags_without_holding_up_others() {
+        let channel = LogChannel::new("Test".into());
+        let mut slow = channel.subscribe("slow");
+        let mut fast = channel.subscribe("fast");
+
+        let lines = SUBSCRIBER_CAPACITY as u64 + 10;
+        for i in 0..lines {
+            channel.send(i.to_string());
+            assert_eq!(fast.recv().await.expect("line").line, i.to_string());
+        }
+
+        // The slow subscriber missed the oldest lines, and reads on from
+        // the oldest line held
+        assert!(matches!(slow.recv().await, Err(RecvError::Lagged(10))));
+        assert_eq!(slow.recv().await.expect("line").line, "10");
+    }
+
+    #[tokio::test]
+    async fn test_dropping_a_subscription_unsubscribes() {
+        let channel = LogChannel::new("Test".into());
+        let follower = channel.subscribe("follower");
+        let shipper = channel.subscribe("shipper");
+        assert_eq!(follower.name(), "follower");
+
+        let mut names = channel.subscribers();
+        names.sort();
+        assert_eq!(names, ["follower", "shipper"]);
+
+        drop(follower);
+        assert_eq!(channel.subscribers(), ["shipper"]);
+        drop(shipper);
+        assert!(!channel.has_subscribers());
+    }
+
+    #[tokio::test]
+    async fn test_lines_sent_before_closing_are_received() {
+        let channel = LogChannel::new("Test".into());
+        let mut rx = channel.subscribe("test");
+        let waiting = tokio::spawn(async move {
+            let mut lines = vec![];
+            while let Ok(item) = rx.recv().await {
+                lines.push(item.line);
+            }
+            lines
+        });
+
+        channel.send("hello".into());
+        channel.send("bye".into());
+        drop(channel);
+
+        let lines = waiting.await.expect("subscriber");
+        assert_eq!(lines, ["hello", "bye"]);
+    }
+
+    /// Each subscriber receives the lines in the order they were sent,
+    /// missing only those it is told it lagged behind on, however the
+    /// sender and the subscribers are interleaved.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_subscribers_receive_every_line_in_order() {
+        const LINES: u64 = 20_000;
+        const SUBSCRIBERS: usize = 8;
+
+        for _ in 0..4 {
+            let channel = LogChannel::new("Test".into());
+            let subscribers: Vec<_> = (0..SUBSCRIBERS)
+                .map(|i| {
+                    let mut rx = channel.subscribe(format!("subscriber-{i}"));
+                    tokio::spawn(async move {
+                        let (mut next, mut received) = (0, 0);
+                        loop {
+                            match rx.recv().await {
+                                Ok(item) => {
+                                    let seq: u64 =
+                                        item.line.parse().expect("sequence");
+                                    assert_eq!(seq, next, "out of order");
+                                    next += 1;
+                                    received += 1;
+                                }
+                                Err(RecvError::Lagged(n)) => next += n,
+                                Err(RecvError::Closed) => break,
+                            }
+                            // Some subscribers are slower than the sender
+                            if i % 2 == 1 && next % 64 == 0 {
+                                tokio::task::yield_now().await;
+                            }
+                        }
+                        (next, received)
+                    })
+                })
+                .collect();
+
+            let sender = spawn_sender(move || {
+                for i in 0..LINES {
+                    channel.send(i.to_string());
+                }
+            });
+            sender.await.expect("sender");
+
+            for subscriber in subscribers {
+                let (next, received) = subscriber.await.expect("subscriber");
+                assert_eq!(next, LINES, "lines unaccounted for");
+                assert!(received > 0);
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_senders_never_wait_for_subscribers() {
+        let channel = LogChannel::new("Test".into()).with_history(100);
+        // Never read from
+        let _stuck = channel.subscribe("stuck");
+
+        let senders: Vec<_> = (0..4)
+            .map(|i| {
+                let channel = channel.clone();
+                spawn_sender(move || {
+                    for j in 0..5_000 {
+                        channel.send(format!("{i}-{j}"));
+                    }
+                })
+            })
+            .collect();
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            for sender in senders {
+                sender.await.expect("sender");
+            }
+        })
+        .await
+        .expect("senders were held up");
+
+        // Every line was sent, and the last of them are retained
+        let (items, _) = channel.subscribe_since("test", 0);
+        assert_eq!(items.len(), 100);
+        assert_eq!(channel.sender.0.tail.load(Ordering::Acquire), 20_000);
+    }
+}
@@ -0,0 +1,218 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use once_cell::sync::OnceCell;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::info;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::{reload, EnvFilter};
+
+static LOG_LEVEL: OnceCell<LogLevel> = OnceCell::new();
+
+/// Replaces the filter of one of the layers logs are emitted by.
+pub(crate) type Reload =
+    Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+/// The filter of the logs of auraed, which can be changed while it runs.
+#[derive(Clone)]
+pub(crate) struct LogLevel(Arc<Inner>);
+
+struct Inner {
+    reloads: Vec<Reload>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// The active directives.
+    directives: String,
+    /// The directives of the last permanent change, and the task restoring
+    /// them, while a temporary change is active.
+    revert: Option<(String, JoinHandle<()>)>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogLevelError {
+    #[error("'{directives}' are not valid filter directives: {source}")]
+    InvalidDirectives { directives: String, source: ParseError },
+    #[error("The log level of auraed cannot be changed")]
+    NotAdjustable,
+}
+
+/// Returns a filter for a layer with `directives`, changed along with the
+/// log level once [init] is called with the returned [Reload].
+pub(crate) fn filter<S>(
+    directives: &str,
+) -> (reload::Layer<EnvFilter, S>, Reload)
+where
+    S: 'static,
+{
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(directives));
+    (filter, Box::new(move |filter| handle.reload(filter)))
+}
+
+/// Makes the filters of `reloads`, all created with `directives`, the log
+/// level of auraed.
+pub(crate) fn init(directives: &str, reloads: Vec<Reload>) {
+    let _ = LOG_LEVEL.set(LogLevel::new(directives, reloads));
+}
+
+/// Returns the log level of auraed, if it can be changed.
+pub(crate) fn get() -> Result<&'static LogLevel, LogLevelError> {
+    LOG_LEVEL.get().ok_or(LogLevelError::NotAdjustable)
+}
+
+impl LogLevel {
+    fn new(directives: &str, reloads: Vec<Reload>) -> Self {
+        Self(Arc::new(Inner {
+            reloads,
+            state: Mutex::new(State {
+                directives: directives.into(),
+                revert: None,
+            }),
+        }))
+    }
+
+    /// Returns the active directives, and the directives a temporary change
+    /// reverts to.
+    pub fn directives(&self) -> (String, Option<String>) {
+        let state = self.0.state.lock().expect("log level lock");
+        let revert_to = state.revert.as_ref().map(|(directives, _)| directives);
+        (state.directives.clone(), revert_to.cloned())
+    }
+
+    /// Changes the log level to `directives`, and returns the directives that
+    /// were active. The change is reverted after `revert_after`, if any, to
+    /// the directives of the last permanent change.
+    pub fn set(
+        &self,
+        directives: &str,
+        revert_after: Option<Duration>,
+    ) -> Result<String, LogLevelError> {
+        let mut state = self.0.state.lock().expect("log level lock");
+
+        self.apply(directives)?;
+        info!("Log level changed to '{directives}'");
+        let previous =
+            std::mem::replace(&mut state.directives, directives.into());
+
+        let permanent = match state.revert.take() {
+            Some((permanent, task)) => {
+                task.abort();
+                permanent
+            }
+            None => previous.clone(),
+        };
+
+        if let Some(revert_after) = revert_after {
+            let log_level = self.clone();
+            let task = tokio::spawn(async move {
+                tokio::time::sleep(revert_after).await;
+                log_level.revert();
+            });
+            state.revert = Some((permanent, task));
+        }
+
+        Ok(previous)
+    }
+
+    /// Restores the directives of the last permanent change.
+    fn revert(&self) {
+        let mut state = self.0.state.lock().expect("log level lock");
+        let Some((permanent, _)) = state.revert.take() else {
+            return;
+        };
+
+        // The directives were valid when they were set
+        if self.apply(&permanent).is_ok() {
+            info!("Log level reverted to '{permanent}'");
+            state.directives = permanent;
+        }
+    }
+
+    /// Replaces the filter of each layer, after checking `directives` are
+    /// valid so that no layer is left with a different filter.
+    fn apply(&self, directives: &str) -> Result<(), LogLevelError> {
+        let parse = || {
+            EnvFilter::try_new(directives).map_err(|source| {
+                LogLevelError::InvalidDirectives {
+                    directives: directives.into(),
+                    source,
+                }
+            })
+        };
+
+        let _ = parse()?;
+        for reload in &self.0.reloads {
+            // A filter only fails to reload once its subscriber is dropped
+            let _ = reload(parse()?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::{layer::SubscriberExt, Layer, Registry};
+
+    fn log_level() -> (LogLevel, impl tracing::Subscriber) {
+        let (filter, reload) = filter("auraed=info");
+        let subscriber = Registry::default()
+            .with(tracing_subscriber::fmt::layer().with_filter(filter));
+        (LogLevel::new("auraed=info", vec![reload]), subscriber)
+    }
+
+    #[tokio::test]
+    async fn test_set_returns_previous_directives() {
+        let (log_level, _subscriber) = log_level();
+
+        assert_eq!(log_level.set("auraed=debug", None).unwrap(), "auraed=info");
+        assert_eq!(log_level.directives(), ("auraed=debug".into(), None));
+    }
+
+    #[tokio::test]
+    async fn test_set_invalid_directives_keeps_filter() {
+        let (log_level, _subscriber) = log_level();
+
+        assert!(matches!(
+            log_level.set("auraed=loud", None),
+            Err(LogLevelError::InvalidDirectives { .. })
+        ));
+        assert_eq!(log_level.directives(), ("auraed=info".into(), None));
+    }
+
+    #[tokio::test]
+    async fn test_temporary_changes_revert_to_last_permanent_change() {
+        let (log_level, _subscriber) = log_level();
+
+        let _ = log_level
+            .set("auraed=debug", Some(Duration::from_secs(60)))
+            .unwrap();
+        let _ = log_level
+            .set("auraed=trace", Some(Duration::from_millis(100)))
+            .unwrap();
+        assert_eq!(
+            log_level.directives(),
+            ("auraed=trace".into(), Some("auraed=info".into()))
+        );
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(log_level.directives(), ("auraed=info".into(), None));
+    }
+}
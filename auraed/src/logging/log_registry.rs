@@ -0,0 +1,247 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use super::log_channel::LogChannel;
+use crate::cells::ExecutableName;
+use once_cell::sync::OnceCell;
+use proto::observe::LogChannelType;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::warn;
+
+static LOG_REGISTRY: OnceCell<LogRegistry> = OnceCell::new();
+
+/// How long the channels of a killed executable remain registered by
+/// default, for late readers to drain its last lines.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// What the lines of a registered [LogChannel] are the output of. The
+/// executables of a cell are registered with the auraed of the cell.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LogOwner {
+    /// An executable of this auraed.
+    Executable(ExecutableName),
+    /// A process of this auraed, such as that of an executable, or the
+    /// hypervisor of a virtual machine.
+    Process(i32),
+    /// The init container of a pod sandbox, by the id of the sandbox.
+    PodSandbox(String),
+}
+
+/// Identifies a registered [LogChannel].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LogKey {
+    pub owner: LogOwner,
+    pub channel_type: LogChannelType,
+}
+
+impl LogKey {
+    /// The key of a channel of an executable, by its name.
+    pub fn executable(
+        executable_name: ExecutableName,
+        channel_type: LogChannelType,
+    ) -> Self {
+        Self { owner: LogOwner::Executable(executable_name), channel_type }
+    }
+
+    /// The key of a channel of a process, by its pid.
+    pub fn process(pid: i32, channel_type: LogChannelType) -> Self {
+        Self { owner: LogOwner::Process(pid), channel_type }
+    }
+
+    /// The key of a channel of the init container of a pod sandbox, by the
+    /// id of the sandbox.
+    pub fn pod_sandbox(
+        sandbox_id: String,
+        channel_type: LogChannelType,
+    ) -> Self {
+        Self { owner: LogOwner::PodSandbox(sandbox_id), channel_type }
+    }
+}
+
+/// The log channels of the executables and processes, found by [LogKey]
+/// rather than through what owns them.
+#[derive(Debug, Clone)]
+pub struct LogRegistry {
+    channels: Arc<RwLock<HashMap<LogKey, LogChannel>>>,
+    grace_period: Duration,
+}
+
+impl LogRegistry {
+    /// Deregistered channels remain for `grace_period` before they are
+    /// removed.
+    pub fn new(grace_period: Duration) -> Self {
+        Self { channels: Default::default(), grace_period }
+    }
+
+    /// The registry of this auraed, with the grace period passed to [init].
+    pub fn global() -> &'static LogRegistry {
+        LOG_REGISTRY.get_or_init(|| LogRegistry::new(DEFAULT_GRACE_PERIOD))
+    }
+
+    /// Registers `channel` under `key`, replacing the channel of a previous
+    /// executable of the same name, or process of the same pid, which is
+    /// still in its grace period.
+    pub fn register(&self, key: LogKey, channel: LogChannel) {
+        let _ = self.channels.write().expect("poisoned").insert(key, channel);
+    }
+
+    /// Removes `channel` once the grace period passes, unless another
+    /// channel was registered under `key` in the meantime.
+    pub fn deregister(&self, key: LogKey, channel: &LogChannel) {
+        if self.grace_period.is_zero() {
+            self.remove(&key, channel);
+            return;
+        }
+
+        // Removed right away when there is no runtime to wait in, such as
+        // when auraed is exiting.
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            self.remove(&key, channel);
+            return;
+        };
+        let registry = self.clone();
+        let channel = channel.clone();
+        let _ignored = handle.spawn(async move {
+            tokio::time::sleep(registry.grace_period).await;
+            registry.remove(&key, &channel);
+        });
+    }
+
+    fn remove(&self, key: &LogKey, channel: &LogChannel) {
+        let mut channels = self.channels.write().expect("poisoned");
+        if channels.get(key).is_some_and(|c| c.same_channel(channel)) {
+            let _ = channels.remove(key);
+        }
+    }
+
+    /// Returns the channel registered under `key`.
+    pub fn lookup(&self, key: &LogKey) -> Option<LogChannel> {
+        self.channels.read().expect("poisoned").get(key).cloned()
+    }
+
+    /// Returns the keys of the registered channels.
+    pub fn list(&self) -> Vec<LogKey> {
+        self.channels.read().expect("poisoned").keys().cloned().collect()
+    }
+}
+
+/// Sets the grace period of [LogRegistry::global].
+/// Must be called before the first channel is registered, otherwise
+/// [DEFAULT_GRACE_PERIOD] applies.
+pub(crate) fn init(grace_period: Duration) {
+    if LOG_REGISTRY.set(LogRegistry::new(grace_period)).is_err() {
+        warn!("log registry is already initialized, ignoring grace period {grace_period:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::RecvError;
+
+    fn key(name: &str) -> LogKey {
+        LogKey::executable(
+            ExecutableName::new(name.to_string()),
+            LogChannelType::Stdout,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lookup_and_list() {
+        let registry = LogRegistry::new(Duration::ZERO);
+        let channel = LogChannel::new("foo::stdout".into());
+        registry.register(key("foo"), channel.clone());
+
+        let found = registry.lookup(&key("foo")).expect("registered");
+        assert!(found.same_channel(&channel));
+        assert!(registry.lookup(&key("bar")).is_none());
+        assert_eq!(registry.list(), vec![key("foo")]);
+
+        registry.deregister(key("foo"), &channel);
+        assert!(registry.lookup(&key("foo")).is_none());
+        assert!(registry.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deregistered_channels_remain_for_the_grace_period() {
+        let registry = LogRegistry::new(Duration::from_millis(100));
+        let channel = LogChannel::new("foo::stdout".into());
+        registry.register(key("foo"), channel.clone());
+
+        registry.deregister(key("foo"), &channel);
+        let mut rx = registry
+            .lookup(&key("foo"))
+            .expect("in grace period")
+            .subscribe("test");
+        channel.send("last".into());
+        assert_eq!(rx.recv().await.expect("last line").line, "last");
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(registry.lookup(&key("foo")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deregistering_keeps_a_newer_channel() {
+        let registry = LogRegistry::new(Duration::from_millis(50));
+        let old = LogChannel::new("foo::stdout".into());
+        let new = LogChannel::new("foo::stdout".into());
+        registry.register(key("foo"), old.clone());
+
+        registry.deregister(key("foo"), &old);
+        registry.register(key("foo"), new.clone());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let found = registry.lookup(&key("foo")).expect("newer channel");
+        assert!(found.same_channel(&new));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_subscribe_racing_with_deregistration() {
+        let registry = LogRegistry::new(Duration::ZERO);
+
+        for round in 0..50 {
+            let channel = LogChannel::new(format!("foo-{round}::stdout"));
+            registry.register(key("foo"), channel.clone());
+
+            let subscribers: Vec<_> = (0..8)
+                .map(|_| {
+                    let registry = registry.clone();
+                    tokio::spawn(async move {
+                        registry
+                            .lookup(&key("foo"))
+                            .map(|c| c.subscribe("test"))
+                    })
+                })
+                .collect();
+            registry.deregister(key("foo"), &channel);
+
+            let mut receivers = vec![];
+            for subscriber in subscribers {
+                receivers.extend(subscriber.await.expect("subscriber"));
+            }
+            assert!(registry.lookup(&key("foo")).is_none());
+
+            // Whoever subscribed before the channel was removed still gets
+            // its last line, and then sees it close.
+            channel.send("last".into());
+            drop(channel);
+            for mut rx in receivers {
+                assert_eq!(rx.recv().await.expect("last line").line, "last");
+                assert!(matches!(rx.recv().await, Err(RecvError::Closed)));
+            }
+        }
+    }
+}
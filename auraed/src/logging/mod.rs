@@ -22,9 +22,28 @@ use std::time::SystemTime;
 /// LogChannel provides channels between Log producers and log consumers
 pub mod log_channel;
 
+/// Finds the log channels of executables and processes by name or pid
+pub mod log_registry;
+
+/// Redacts configured secret patterns from log lines before they are sent
+pub mod redaction;
+
+/// Limits the rate of the output of executables, suppressing the excess
+pub mod output_limit;
+
+/// Bounds how long the output of a stopped executable is read for
+pub mod output_drain;
+
+/// The filter of the logs of auraed, changed while it runs
+pub(crate) mod log_level;
+
 /// Implements Log trait. Used to add grpc API to log targets for rust internal logging
 pub mod stream_logger;
 
+/// Implements tracing's Layer trait, sending the events of auraed to its log
+/// channel
+pub mod channel_layer;
+
 /// Get UNIX timestamp in seconds for logging
 pub fn get_timestamp_sec() -> i64 {
     let unix_ts = SystemTime::now()
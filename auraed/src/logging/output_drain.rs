@@ -0,0 +1,52 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use once_cell::sync::OnceCell;
+use std::time::Duration;
+use tracing::warn;
+
+static DRAIN_TIMEOUT: OnceCell<Duration> = OnceCell::new();
+
+/// How long a stopped executable's outputs are read for by default, before
+/// they are cut short.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Returns how long the outputs of a stopped executable are read for before
+/// they are cut short. A process it left running, having cleared its
+/// environment, may hold them open until it exits.
+pub(crate) fn drain_timeout() -> Duration {
+    DRAIN_TIMEOUT.get().copied().unwrap_or(DEFAULT_DRAIN_TIMEOUT)
+}
+
+/// Sets how long the outputs of a stopped executable are read for.
+/// Must be called before the first executable stops, otherwise
+/// [DEFAULT_DRAIN_TIMEOUT] applies.
+pub(crate) fn init(timeout: Duration) {
+    if DRAIN_TIMEOUT.set(timeout).is_err() {
+        warn!(
+            "output drain timeout is already initialized, ignoring {timeout:?}"
+        );
+    }
+}
+
+/// The line sent when an output is cut short, ending the `partial` line
+/// read so far, if any.
+pub(crate) fn truncated_line(partial: &str) -> String {
+    let marker = "[aurae: output truncated, it was still held open when the executable stopped]";
+    if partial.is_empty() {
+        marker.into()
+    } else {
+        format!("{partial} {marker}")
+    }
+}
@@ -0,0 +1,222 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+static DEFAULT_LIMIT: OnceCell<OutputLimit> = OnceCell::new();
+
+/// The interval the rates of an [OutputLimit] are counted over, and at
+/// which the suppressed lines are reported.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many lines, and bytes, an executable may write to each of its
+/// outputs per second. A rate of zero is unlimited in the config of auraed,
+/// and takes the default of auraed for an executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputLimit {
+    /// The lines an output may send per second.
+    pub lines_per_second: u64,
+    /// The bytes an output may send per second, not counting line endings.
+    pub bytes_per_second: u64,
+}
+
+impl OutputLimit {
+    /// No limit, or the default of auraed for an executable.
+    pub const UNSET: Self = Self { lines_per_second: 0, bytes_per_second: 0 };
+
+    /// Takes the rates which are unset from the limit passed to [init].
+    pub(crate) fn or_default(self) -> Self {
+        let default = DEFAULT_LIMIT.get().copied().unwrap_or_default();
+        let or_default = |rate, default| if rate == 0 { default } else { rate };
+        Self {
+            lines_per_second: or_default(
+                self.lines_per_second,
+                default.lines_per_second,
+            ),
+            bytes_per_second: or_default(
+                self.bytes_per_second,
+                default.bytes_per_second,
+            ),
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        *self == Self::UNSET
+    }
+}
+
+impl Default for OutputLimit {
+    fn default() -> Self {
+        Self { lines_per_second: 10_000, bytes_per_second: 10 * 1024 * 1024 }
+    }
+}
+
+/// Sets the default limit of the outputs of executables.
+/// Must be called before the first executable starts, otherwise
+/// [OutputLimit::default] applies.
+pub(crate) fn init(limit: OutputLimit) {
+    if DEFAULT_LIMIT.set(limit).is_err() {
+        warn!("output limit is already initialized, ignoring {limit:?}");
+    }
+}
+
+/// The line sent in place of `count` lines which were suppressed.
+pub(crate) fn suppressed_line(count: u64) -> String {
+    format!("[aurae: {count} lines suppressed by the output limit]")
+}
+
+/// Counts the lines of one output against an [OutputLimit]. The lines over
+/// the limit are suppressed, and reported as one line per interval.
+#[derive(Debug)]
+pub(crate) struct OutputLimiter {
+    limit: OutputLimit,
+    interval_end: Option<Instant>,
+    lines: u64,
+    bytes: u64,
+    /// Suppressed in the current interval, and not reported yet.
+    pending: u64,
+    /// Suppressed since the output was opened.
+    suppressed: Arc<AtomicU64>,
+}
+
+impl OutputLimiter {
+    /// Counts the lines suppressed in `suppressed` as well.
+    pub fn new(limit: OutputLimit, suppressed: Arc<AtomicU64>) -> Self {
+        Self {
+            limit,
+            interval_end: None,
+            lines: 0,
+            bytes: 0,
+            pending: 0,
+            suppressed,
+        }
+    }
+
+    /// Counts a line of `len` bytes read at `now`, returning whether it may
+    /// be sent. Once an interval ends, the number of lines suppressed in it
+    /// is returned first, to be reported before the line.
+    pub fn admit(&mut self, len: usize, now: Instant) -> (Option<u64>, bool) {
+        if self.limit.is_unlimited() {
+            return (None, true);
+        }
+
+        let mut report = None;
+        match self.interval_end {
+            Some(interval_end) if now < interval_end => {}
+            _ => {
+                report = self.take_report();
+                self.interval_end = Some(now + INTERVAL);
+                self.lines = 0;
+                self.bytes = 0;
+            }
+        }
+
+        self.lines += 1;
+        self.bytes += len as u64;
+        let over = |count, limit| limit != 0 && count > limit;
+        if over(self.lines, self.limit.lines_per_second)
+            || over(self.bytes, self.limit.bytes_per_second)
+        {
+            self.pending += 1;
+            let _ = self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return (report, false);
+        }
+
+        (report, true)
+    }
+
+    /// When the lines suppressed in the current interval are due to be
+    /// reported, if any were.
+    pub fn report_deadline(&self) -> Option<Instant> {
+        match self.pending {
+            0 => None,
+            _ => self.interval_end,
+        }
+    }
+
+    /// Takes the number of lines suppressed and not reported yet, if any.
+    pub fn take_report(&mut self) -> Option<u64> {
+        match std::mem::take(&mut self.pending) {
+            0 => None,
+            pending => Some(pending),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(lines: u64, bytes: u64) -> (OutputLimiter, Arc<AtomicU64>) {
+        let suppressed = Arc::new(AtomicU64::new(0));
+        let limit =
+            OutputLimit { lines_per_second: lines, bytes_per_second: bytes };
+        (OutputLimiter::new(limit, suppressed.clone()), suppressed)
+    }
+
+    #[test]
+    fn test_unlimited_admits_every_line() {
+        let (mut limiter, suppressed) = limiter(0, 0);
+        let now = Instant::now();
+        for _ in 0..100_000 {
+            assert_eq!(limiter.admit(1024, now), (None, true));
+        }
+        assert_eq!(suppressed.load(Ordering::Relaxed), 0);
+        assert_eq!(limiter.report_deadline(), None);
+    }
+
+    #[test]
+    fn test_lines_over_the_limit_are_reported_once_per_interval() {
+        let (mut limiter, suppressed) = limiter(3, 0);
+        let start = Instant::now();
+
+        let admitted: Vec<_> =
+            (0..10).map(|_| limiter.admit(1, start).1).collect();
+        assert_eq!(admitted.iter().filter(|x| **x).count(), 3);
+        assert_eq!(suppressed.load(Ordering::Relaxed), 7);
+        assert_eq!(limiter.report_deadline(), Some(start + INTERVAL));
+
+        // The next interval reports the suppressed lines before its first
+        let next = start + INTERVAL;
+        assert_eq!(limiter.admit(1, next), (Some(7), true));
+        assert_eq!(limiter.report_deadline(), None);
+        assert_eq!(limiter.admit(1, next), (None, true));
+    }
+
+    #[test]
+    fn test_bytes_over_the_limit_are_suppressed() {
+        let (mut limiter, suppressed) = limiter(0, 100);
+        let now = Instant::now();
+
+        assert_eq!(limiter.admit(60, now), (None, true));
+        assert_eq!(limiter.admit(60, now), (None, false));
+        assert_eq!(limiter.take_report(), Some(1));
+        assert_eq!(limiter.take_report(), None);
+        assert_eq!(suppressed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_unset_rates_take_the_default() {
+        let limit = OutputLimit { lines_per_second: 5, ..OutputLimit::UNSET };
+        let limit = limit.or_default();
+        assert_eq!(limit.lines_per_second, 5);
+        assert_ne!(limit.bytes_per_second, 0);
+    }
+}
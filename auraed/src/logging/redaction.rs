@@ -0,0 +1,291 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use once_cell::sync::OnceCell;
+use regex::{Regex, RegexSet};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+static REDACTOR: OnceCell<Redactor> = OnceCell::new();
+
+/// A named pattern to redact from log lines, such as a bearer token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRule {
+    /// Matches are replaced with `[REDACTED:<name>]`.
+    pub name: String,
+    /// The regular expression matching the secret.
+    pub pattern: String,
+}
+
+impl FromStr for RedactionRule {
+    type Err = String;
+
+    /// Parses a rule written as `<name>=<pattern>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, pattern)) = s.split_once('=') else {
+            return Err(format!("expected <name>=<pattern>, got '{s}'"));
+        };
+
+        if name.is_empty() {
+            return Err(format!("missing a name in '{s}'"));
+        }
+
+        Ok(Self { name: name.into(), pattern: pattern.into() })
+    }
+}
+
+impl Display for RedactionRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.pattern)
+    }
+}
+
+/// Applies a set of [RedactionRule], counting the matches of each rule.
+#[derive(Debug)]
+pub(crate) struct Redactor {
+    set: RegexSet,
+    rules: Vec<(String, Regex)>,
+    hits: Vec<AtomicU64>,
+}
+
+impl Redactor {
+    pub fn new(rules: &[RedactionRule]) -> Result<Self, regex::Error> {
+        let set = RegexSet::new(rules.iter().map(|rule| &rule.pattern))?;
+        let regexes = rules
+            .iter()
+            .map(|rule| Ok((rule.name.clone(), Regex::new(&rule.pattern)?)))
+            .collect::<Result<_, regex::Error>>()?;
+        let hits = rules.iter().map(|_| AtomicU64::new(0)).collect();
+
+        Ok(Self { set, rules: regexes, hits })
+    }
+
+    /// Replaces the matches of every rule in `line`. Overlapping matches,
+    /// of the same or different rules, are replaced as one, named after the
+    /// rule of the match that starts first, or is listed first.
+    ///
+    /// Lines are only searched for the matches of each rule if the set of
+    /// all rules matches, which is the case for few lines.
+    pub fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let matched = self.set.matches(line);
+        if !matched.matched_any() {
+            return Cow::Borrowed(line);
+        }
+
+        let mut spans = vec![];
+        for rule in matched.iter() {
+            for m in self.rules[rule].1.find_iter(line) {
+                // An empty match has nothing to redact
+                if m.is_empty() {
+                    continue;
+                }
+
+                let _ = self.hits[rule].fetch_add(1, Ordering::Relaxed);
+                spans.push((m.start(), m.end(), rule));
+            }
+        }
+
+        if spans.is_empty() {
+            return Cow::Borrowed(line);
+        }
+
+        spans.sort_unstable_by_key(|&(start, _, rule)| (start, rule));
+
+        // Merges overlapping spans
+        let mut merged: Vec<(usize, usize, usize)> = vec![];
+        for (start, end, rule) in spans {
+            match merged.last_mut() {
+                Some((_, last_end, _)) if start < *last_end => {
+                    *last_end = end.max(*last_end);
+                }
+                _ => merged.push((start, end, rule)),
+            }
+        }
+
+        let mut redacted = String::with_capacity(line.len());
+        let mut last_end = 0;
+        for (start, end, rule) in merged {
+            redacted.push_str(&line[last_end..start]);
+            let _ = write!(redacted, "[REDACTED:{}]", self.rules[rule].0);
+            last_end = end;
+        }
+        redacted.push_str(&line[last_end..]);
+
+        Cow::Owned(redacted)
+    }
+
+    /// Returns the name of each rule, with the number of matches it redacted.
+    pub fn hits(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.rules.iter().zip(&self.hits).map(|((name, _), hits)| {
+            (name.as_str(), hits.load(Ordering::Relaxed))
+        })
+    }
+}
+
+/// Compiles the rules that are redacted from every [LogChannel] line.
+/// Must be called before the first line is sent, otherwise nothing is
+/// redacted.
+///
+/// [LogChannel]: super::log_channel::LogChannel
+pub(crate) fn init(rules: &[RedactionRule]) -> Result<(), regex::Error> {
+    let redactor = Redactor::new(rules)?;
+    if REDACTOR.set(redactor).is_err() {
+        warn!("log redaction is already initialized, ignoring {rules:?}");
+    }
+
+    Ok(())
+}
+
+/// Redacts `line` with the rules passed to [init].
+pub(crate) fn redact(line: &str) -> Cow<'_, str> {
+    match REDACTOR.get() {
+        Some(redactor) => redactor.redact(line),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// Returns the name of each rule passed to [init], with the number of
+/// matches it redacted. See [Redactor::hits].
+pub(crate) fn hits() -> Vec<(String, u64)> {
+    REDACTOR.get().map_or_else(Vec::new, |redactor| {
+        redactor.hits().map(|(name, hits)| (name.into(), hits)).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn rule(name: &str, pattern: &str) -> RedactionRule {
+        RedactionRule { name: name.into(), pattern: pattern.into() }
+    }
+
+    fn redactor() -> Redactor {
+        Redactor::new(&[
+            rule("bearer", r"Bearer [A-Za-z0-9._-]+"),
+            rule("password", r"password=[^ ;]+"),
+            rule("url", r"postgres://[^ ]+"),
+        ])
+        .expect("valid rules")
+    }
+
+    fn hits(redactor: &Redactor) -> Vec<(&str, u64)> {
+        redactor.hits().collect()
+    }
+
+    #[test]
+    fn test_no_match_is_borrowed() {
+        let redactor = redactor();
+        let line = "GET /healthz 200";
+        assert!(matches!(redactor.redact(line), Cow::Borrowed(x) if x == line));
+        assert_eq!(
+            hits(&redactor),
+            vec![("bearer", 0), ("password", 0), ("url", 0)]
+        );
+    }
+
+    #[test]
+    fn test_multiple_matches() {
+        let redactor = redactor();
+        let line = "auth=Bearer abc.def password=hunter2; retry Bearer ghi";
+        assert_eq!(
+            redactor.redact(line),
+            "auth=[REDACTED:bearer] [REDACTED:password]; retry [REDACTED:bearer]"
+        );
+        assert_eq!(
+            hits(&redactor),
+            vec![("bearer", 2), ("password", 1), ("url", 0)]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_matches_are_redacted_as_one() {
+        let redactor = redactor();
+        // The password is part of the url
+        let line = "connecting to postgres://app:x@db/app?password=hunter2 now";
+        assert_eq!(redactor.redact(line), "connecting to [REDACTED:url] now");
+        assert_eq!(
+            hits(&redactor),
+            vec![("bearer", 0), ("password", 1), ("url", 1)]
+        );
+
+        // The first rule to match names the redaction, and the whole of
+        // both matches is redacted
+        let line = "password=Bearer abc.def tail";
+        assert_eq!(redactor.redact(line), "[REDACTED:password] tail");
+        let line = "x password=a Bearer b";
+        assert_eq!(
+            redactor.redact(line),
+            "x [REDACTED:password] [REDACTED:bearer]"
+        );
+    }
+
+    #[test]
+    fn test_empty_matches_are_ignored() {
+        let redactor = Redactor::new(&[rule("maybe", r"x*")]).unwrap();
+        assert_eq!(redactor.redact("abc"), "abc");
+        assert_eq!(redactor.redact("axxc"), "a[REDACTED:maybe]c");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Redactor::new(&[rule("bad", "(")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(
+            "token=a=b+".parse::<RedactionRule>(),
+            Ok(rule("token", "a=b+"))
+        );
+        assert_eq!(rule("token", "a=b+").to_string(), "token=a=b+");
+        assert!("token".parse::<RedactionRule>().is_err());
+        assert!("=abc".parse::<RedactionRule>().is_err());
+    }
+
+    /// Compares the throughput of lines without secrets, the vast majority,
+    /// with and without redaction.
+    /// Run with `cargo test --release -- --ignored --nocapture redact_throughput`
+    #[test]
+    #[ignore]
+    fn bench_redact_throughput() {
+        const LINES: usize = 1_000_000;
+        let redactor = redactor();
+        let line = "2024-01-01T00:00:00Z INFO request completed path=/api/v0/cells status=200 latency_ms=12";
+
+        let start = Instant::now();
+        let mut bytes = 0;
+        for _ in 0..LINES {
+            bytes += std::hint::black_box(line).len();
+        }
+        let baseline = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..LINES {
+            bytes += redactor.redact(std::hint::black_box(line)).len();
+        }
+        let redacted = start.elapsed();
+
+        println!(
+            "{LINES} lines ({bytes} bytes): {baseline:?} without redaction, {redacted:?} with {} rules ({:.0} lines/s)",
+            redactor.rules.len(),
+            LINES as f64 / redacted.as_secs_f64()
+        );
+    }
+}
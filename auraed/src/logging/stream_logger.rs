@@ -14,7 +14,7 @@
 \* -------------------------------------------------------------------------- */
 
 use log::Log;
-use proto::observe::LogItem;
+use proto::observe::{LogItem, LogLevel};
 use tokio::sync::broadcast::Sender;
 
 /// Sends log messages generated in rust code to the logging channel
@@ -49,6 +49,14 @@ impl Log for StreamLogger {
                 record.args()
             ),
             timestamp: 0,
+            level: match record.level() {
+                log::Level::Error => LogLevel::Error,
+                log::Level::Warn => LogLevel::Warn,
+                log::Level::Info => LogLevel::Info,
+                log::Level::Debug => LogLevel::Debug,
+                log::Level::Trace => LogLevel::Trace,
+            }
+            .into(),
         });
     }
 
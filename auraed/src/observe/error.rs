@@ -13,21 +13,44 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use proto::observe::LogChannelType;
+use crate::blocking::BlockingError;
+use crate::logging::log_level::LogLevelError;
+use crate::tls::TlsError;
+use client::ClientError;
 use thiserror::Error;
 use tonic::Status;
 use tracing::error;
 
 #[derive(Debug, Error)]
 pub enum ObserveServiceError {
-    #[error("Channel already registered with type {channel_type:?} for {pid}")]
-    ChannelAlreadyRegistered { pid: i32, channel_type: LogChannelType },
     #[error("Failed to find any registered channels for {pid}")]
     NoChannelsForPid { pid: i32 },
-    #[error("Failed to find channel type {channel_type:?} for {pid}")]
-    ChannelNotRegistered { pid: i32, channel_type: LogChannelType },
     #[error("{channel_type} is not a valid LogChannelType")]
     InvalidLogChannelType { channel_type: i32 },
+    #[error("Failed to find any registered channels for executable '{executable_name}'")]
+    NoChannelsForExecutable { executable_name: String },
+    #[error("Failed to find any registered channels for pod sandbox '{pod_sandbox_id}'")]
+    NoChannelsForPodSandbox { pod_sandbox_id: String },
+    #[error("Failed to find cell '{cell_name}'")]
+    CellNotFound { cell_name: String },
+    #[error("Failed to connect to the auraed of cell '{cell_name}': {source}")]
+    CellUnreachable { cell_name: String, source: ClientError },
+    #[error("'{line_regex}' is not a valid regular expression: {source}")]
+    InvalidLineRegex { line_regex: String, source: regex::Error },
+    #[error("{kind} is not a valid LifecycleEventKind")]
+    InvalidLifecycleEventKind { kind: i32 },
+    #[error(
+        "Events after {since} are no longer retained, the oldest is {oldest}"
+    )]
+    EventsNotRetained { since: u64, oldest: u64 },
+    #[error("Signals sent to unmanaged processes are only observed by the eBPF probe of the signals, which is not loaded")]
+    SignalProbeNotLoaded,
+    #[error(transparent)]
+    LogLevel(#[from] LogLevelError),
+    #[error(transparent)]
+    Tls(#[from] TlsError),
+    #[error(transparent)]
+    Blocking(#[from] BlockingError),
 }
 
 impl From<ObserveServiceError> for Status {
@@ -35,16 +58,35 @@ impl From<ObserveServiceError> for Status {
         let msg = err.to_string();
         error!("{msg}");
         match err {
-            ObserveServiceError::ChannelAlreadyRegistered { .. } => {
-                Status::internal(msg)
-            }
             ObserveServiceError::NoChannelsForPid { .. }
-            | ObserveServiceError::ChannelNotRegistered { .. } => {
+            | ObserveServiceError::NoChannelsForExecutable { .. }
+            | ObserveServiceError::NoChannelsForPodSandbox { .. }
+            | ObserveServiceError::CellNotFound { .. } => {
                 Status::not_found(msg)
             }
-            ObserveServiceError::InvalidLogChannelType { .. } => {
+            ObserveServiceError::CellUnreachable { .. } => {
+                Status::unavailable(msg)
+            }
+            ObserveServiceError::InvalidLogChannelType { .. }
+            | ObserveServiceError::InvalidLineRegex { .. }
+            | ObserveServiceError::InvalidLifecycleEventKind { .. } => {
                 Status::invalid_argument(msg)
             }
+            ObserveServiceError::EventsNotRetained { .. } => {
+                Status::out_of_range(msg)
+            }
+            ObserveServiceError::SignalProbeNotLoaded => {
+                Status::unimplemented(msg)
+            }
+            ObserveServiceError::LogLevel(
+                LogLevelError::InvalidDirectives { .. },
+            ) => Status::invalid_argument(msg),
+            ObserveServiceError::LogLevel(LogLevelError::NotAdjustable) => {
+                Status::failed_precondition(msg)
+            }
+            // The material on disk is invalid, or auraed serves no TLS
+            ObserveServiceError::Tls(_) => Status::failed_precondition(msg),
+            ObserveServiceError::Blocking(_) => Status::internal(msg),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,586 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! A journal of the lifecycle events on disk, so a restarted auraed carries
+//! on numbering them, and watches resume from events published before it
+//! restarted.
+//!
+//! Each record is the length and CRC-32 of the event, followed by the event
+//! as JSON. A torn or corrupted record ends the journal, and is cut off when
+//! the journal is opened. Events are kept in two files: the journal, and the
+//! journal it replaced once it filled half of [EventJournalConfig::max_bytes].
+//! The replaced journal is dropped once all its events are older than
+//! [EventJournalConfig::max_age].
+
+use crate::blocking::{self, BlockingJob, Pool};
+use crate::logging::get_timestamp_sec;
+use proto::observe::LifecycleEvent;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// The length and the CRC-32 of the event of a record.
+const HEADER_LEN: usize = 8;
+
+/// How the lifecycle events are journaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventJournalConfig {
+    /// Journal the events to the runtime directory, for a restarted auraed
+    /// to replay them.
+    pub persist: bool,
+    /// The size in bytes of the journal, over which its oldest events are
+    /// dropped.
+    pub max_bytes: u64,
+    /// How long events are kept, in seconds in the config of auraed.
+    #[serde(with = "crate::config::secs")]
+    pub max_age: Duration,
+}
+
+impl Default for EventJournalConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            max_bytes: 16 * 1024 * 1024,
+            max_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// The journal, written to by a task of its own, so publishing an event
+/// doesn't wait for the disk.
+#[derive(Debug, Clone)]
+pub(crate) struct EventJournal(mpsc::UnboundedSender<Op>);
+
+/// The events journaled before the journal was opened.
+#[derive(Debug)]
+pub(crate) struct Journaled {
+    /// The events not older than [EventJournalConfig::max_age], oldest
+    /// first.
+    pub events: Vec<LifecycleEvent>,
+    /// The sequence of the last event, even if it is too old to replay.
+    pub last_sequence: u64,
+}
+
+#[derive(Debug)]
+enum Op {
+    Append(LifecycleEvent),
+    Read {
+        since: u64,
+        before: u64,
+        reply: oneshot::Sender<io::Result<Vec<LifecycleEvent>>>,
+    },
+}
+
+impl EventJournal {
+    /// Opens the journal at `path`, cutting off a corrupted end.
+    pub async fn open(
+        path: PathBuf,
+        config: EventJournalConfig,
+    ) -> io::Result<(Self, Journaled)> {
+        let (files, journaled) = blocking::run(OpenJob { path, config })
+            .await
+            .map_err(io::Error::other)??;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let _ignored = tokio::spawn(write(Arc::new(Mutex::new(files)), rx));
+
+        Ok((Self(tx), journaled))
+    }
+
+    /// Appends the event, after the events appended before it.
+    pub fn append(&self, event: LifecycleEvent) {
+        // send returns an Err if the task is gone. We ignore that.
+        let _ = self.0.send(Op::Append(event));
+    }
+
+    /// Returns the journaled events after `since` and before `before`,
+    /// once the events appended before are written.
+    pub async fn read(
+        &self,
+        since: u64,
+        before: u64,
+    ) -> io::Result<Vec<LifecycleEvent>> {
+        let (reply, rx) = oneshot::channel();
+        self.0
+            .send(Op::Read { since, before, reply })
+            .map_err(|_| io::Error::other("event journal is closed"))?;
+
+        rx.await.map_err(|_| io::Error::other("event journal is closed"))?
+    }
+}
+
+/// Writes the events in batches of those appended while the last batch was
+/// written, and serves reads in between.
+async fn write(
+    files: Arc<Mutex<JournalFiles>>,
+    mut rx: mpsc::UnboundedReceiver<Op>,
+) {
+    let mut next = rx.recv().await;
+    while let Some(op) = next.take() {
+        match op {
+            Op::Append(event) => {
+                let mut events = vec![event];
+                loop {
+                    match rx.try_recv() {
+                        Ok(Op::Append(event)) => events.push(event),
+                        Ok(op) => {
+                            next = Some(op);
+                            break;
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                let res =
+                    blocking::run(AppendJob { files: files.clone(), events })
+                        .await
+                        .map_err(io::Error::other)
+                        .and_then(|res| res);
+                if let Err(e) = res {
+                    warn!("failed to journal lifecycle events: {e}");
+                }
+            }
+            Op::Read { since, before, reply } => {
+                let res = blocking::run(ReadJob {
+                    files: files.clone(),
+                    since,
+                    before,
+                })
+                .await
+                .map_err(io::Error::other)
+                .and_then(|res| res);
+                let _ = reply.send(res);
+            }
+        }
+
+        if next.is_none() {
+            next = rx.recv().await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct JournalFiles {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    /// The timestamp of the last event of the journal.
+    newest: Option<i64>,
+    /// The timestamp of the last event of the replaced journal, if any.
+    replaced_newest: Option<i64>,
+    config: EventJournalConfig,
+}
+
+impl JournalFiles {
+    fn open(
+        path: PathBuf,
+        config: EventJournalConfig,
+    ) -> io::Result<(Self, Journaled)> {
+        let (replaced, _) = read_records(&replaced(&path))?;
+        let (current, valid_len) = read_records(&path)?;
+
+        let file = open(&path)?;
+        let size = file.metadata()?.len();
+        if valid_len < size {
+            warn!(
+                "cutting off {} corrupted bytes at the end of the event journal '{}'",
+                size - valid_len,
+                path.display()
+            );
+            file.set_len(valid_len)?;
+        }
+
+        let last_sequence = current
+            .last()
+            .or(replaced.last())
+            .map_or(0, |event| event.sequence);
+
+        let mut files = Self {
+            path,
+            file,
+            size: valid_len,
+            newest: current.last().map(|event| event.timestamp),
+            replaced_newest: replaced.last().map(|event| event.timestamp),
+            config,
+        };
+        files.expire()?;
+
+        let oldest = files.oldest_kept();
+        let events = replaced
+            .into_iter()
+            .chain(current)
+            .filter(|event| event.timestamp >= oldest)
+            .collect();
+
+        Ok((files, Journaled { events, last_sequence }))
+    }
+
+    fn append(&mut self, events: &[LifecycleEvent]) -> io::Result<()> {
+        let mut records = vec![];
+        for event in events {
+            encode(event, &mut records)?;
+        }
+
+        let len = records.len() as u64;
+        if self.size > 0 && self.size + len > self.config.max_bytes / 2 {
+            self.replace()?;
+        }
+
+        // A failed write may leave part of a record behind, which would hide
+        // the records appended after it, so it is cut off right away.
+        let written =
+            self.file.write_all(&records).and_then(|()| self.file.sync_data());
+        if let Err(e) = written {
+            if let Err(cut) = self.file.set_len(self.size) {
+                warn!(
+                    "failed to cut off a partly written record of the event journal '{}': {cut}",
+                    self.path.display()
+                );
+            }
+            return Err(e);
+        }
+        self.size += len;
+        self.newest = events.last().map(|event| event.timestamp);
+
+        self.expire()
+    }
+
+    /// Starts a new journal, replacing the last replaced one.
+    fn replace(&mut self) -> io::Result<()> {
+        std::fs::rename(&self.path, replaced(&self.path))?;
+        self.file = open(&self.path)?;
+        self.size = 0;
+        self.replaced_newest = self.newest.take();
+        Ok(())
+    }
+
+    /// Drops the replaced journal once all its events are too old.
+    fn expire(&mut self) -> io::Result<()> {
+        let Some(replaced_newest) = self.replaced_newest else {
+            return Ok(());
+        };
+        if replaced_newest >= self.oldest_kept() {
+            return Ok(());
+        }
+
+        match std::fs::remove_file(replaced(&self.path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        self.replaced_newest = None;
+        Ok(())
+    }
+
+    /// The timestamp of the oldest event kept.
+    fn oldest_kept(&self) -> i64 {
+        let max_age =
+            i64::try_from(self.config.max_age.as_secs()).unwrap_or(i64::MAX);
+        get_timestamp_sec().saturating_sub(max_age)
+    }
+
+    fn read(&self, since: u64, before: u64) -> io::Result<Vec<LifecycleEvent>> {
+        let (replaced, _) = read_records(&replaced(&self.path))?;
+        let (current, _) = read_records(&self.path)?;
+
+        Ok(replaced
+            .into_iter()
+            .chain(current)
+            .filter(|event| event.sequence > since && event.sequence < before)
+            .collect())
+    }
+}
+
+struct OpenJob {
+    path: PathBuf,
+    config: EventJournalConfig,
+}
+
+impl BlockingJob for OpenJob {
+    type Output = io::Result<(JournalFiles, Journaled)>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        JournalFiles::open(self.path, self.config)
+    }
+}
+
+struct AppendJob {
+    files: Arc<Mutex<JournalFiles>>,
+    events: Vec<LifecycleEvent>,
+}
+
+impl BlockingJob for AppendJob {
+    type Output = io::Result<()>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        self.files.lock().expect("event journal lock").append(&self.events)
+    }
+}
+
+struct ReadJob {
+    files: Arc<Mutex<JournalFiles>>,
+    since: u64,
+    before: u64,
+}
+
+impl BlockingJob for ReadJob {
+    type Output = io::Result<Vec<LifecycleEvent>>;
+
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        self.files
+            .lock()
+            .expect("event journal lock")
+            .read(self.since, self.before)
+    }
+}
+
+fn encode(event: &LifecycleEvent, records: &mut Vec<u8>) -> io::Result<()> {
+    let json = serde_json::to_vec(event)?;
+    let len = u32::try_from(json.len()).map_err(io::Error::other)?;
+
+    records.extend_from_slice(&len.to_le_bytes());
+    records.extend_from_slice(&crc32(&json).to_le_bytes());
+    records.extend_from_slice(&json);
+    Ok(())
+}
+
+/// Reads the records of the journal at `path` up to the first incomplete or
+/// corrupted one, and returns their events and length. A missing journal is
+/// empty.
+fn read_records(path: &Path) -> io::Result<(Vec<LifecycleEvent>, u64)> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e),
+    };
+
+    let mut events = vec![];
+    let mut rest = &bytes[..];
+    while let Some((header, after)) = rest.split_first_chunk::<HEADER_LEN>() {
+        let (len, crc) = header.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize;
+        let crc = u32::from_le_bytes(crc.try_into().expect("4 bytes"));
+
+        let Some((json, after)) = after.split_at_checked(len) else {
+            break;
+        };
+        if crc32(json) != crc {
+            break;
+        }
+        let Ok(event) = serde_json::from_slice(json) else {
+            break;
+        };
+
+        events.push(event);
+        rest = after;
+    }
+
+    Ok((events, (bytes.len() - rest.len()) as u64))
+}
+
+/// The CRC-32 (IEEE) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc =
+                if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).mode(0o600).open(path)
+}
+
+fn replaced(path: &Path) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(".1");
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::observe::{lifecycle_event::Kind, CellAllocated};
+
+    fn event(sequence: u64, timestamp: i64) -> LifecycleEvent {
+        LifecycleEvent {
+            sequence,
+            timestamp,
+            cell_name: "ae-1".into(),
+            kind: Some(Kind::CellAllocated(CellAllocated {})),
+        }
+    }
+
+    fn sequences(events: &[LifecycleEvent]) -> Vec<u64> {
+        events.iter().map(|event| event.sequence).collect()
+    }
+
+    fn journal_path() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-events-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("events.journal");
+        (dir, path)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reopen_carries_on_from_the_journaled_events() {
+        let (dir, path) = journal_path();
+        let config = EventJournalConfig::default();
+
+        let (journal, journaled) =
+            EventJournal::open(path.clone(), config).await.unwrap();
+        assert!(journaled.events.is_empty());
+        assert_eq!(journaled.last_sequence, 0);
+
+        let now = get_timestamp_sec();
+        for sequence in 1..=3 {
+            journal.append(event(sequence, now));
+        }
+        // Reads wait for the events appended before
+        let events = journal.read(1, u64::MAX).await.unwrap();
+        assert_eq!(sequences(&events), vec![2, 3]);
+        drop(journal);
+
+        let (_, journaled) = EventJournal::open(path, config).await.unwrap();
+        assert_eq!(sequences(&journaled.events), vec![1, 2, 3]);
+        assert_eq!(journaled.last_sequence, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_cuts_off_a_corrupted_end() {
+        let (dir, path) = journal_path();
+        let config = EventJournalConfig::default();
+        let now = get_timestamp_sec();
+
+        let (mut files, _) = JournalFiles::open(path.clone(), config).unwrap();
+        files.append(&[event(1, now), event(2, now), event(3, now)]).unwrap();
+        let valid_len = files.size;
+        drop(files);
+
+        // Flip the last byte of the last event
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+
+        let (mut files, journaled) =
+            JournalFiles::open(path.clone(), config).unwrap();
+        assert_eq!(sequences(&journaled.events), vec![1, 2]);
+        assert_eq!(journaled.last_sequence, 2);
+        assert!(files.size < valid_len);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), files.size);
+
+        // A torn write of the next event
+        files.append(&[event(3, now)]).unwrap();
+        let mut torn = vec![];
+        encode(&event(4, now), &mut torn).unwrap();
+        torn.truncate(torn.len() / 2);
+        files.file.write_all(&torn).unwrap();
+        drop(files);
+
+        let (_, journaled) = JournalFiles::open(path, config).unwrap();
+        assert_eq!(sequences(&journaled.events), vec![1, 2, 3]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_replaces_the_full_journal() {
+        let (dir, path) = journal_path();
+        let now = get_timestamp_sec();
+
+        let mut record = vec![];
+        encode(&event(1, now), &mut record).unwrap();
+        // Two events per file
+        let config = EventJournalConfig {
+            max_bytes: 4 * record.len() as u64,
+            ..EventJournalConfig::default()
+        };
+
+        let (mut files, _) = JournalFiles::open(path.clone(), config).unwrap();
+        for sequence in 1..=5 {
+            files.append(&[event(sequence, now)]).unwrap();
+        }
+
+        assert_eq!(sequences(&read_records(&path).unwrap().0), vec![5]);
+        assert_eq!(
+            sequences(&read_records(&replaced(&path)).unwrap().0),
+            vec![3, 4]
+        );
+        assert_eq!(sequences(&files.read(3, 5).unwrap()), vec![4]);
+        drop(files);
+
+        let (_, journaled) = JournalFiles::open(path, config).unwrap();
+        assert_eq!(sequences(&journaled.events), vec![3, 4, 5]);
+        assert_eq!(journaled.last_sequence, 5);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expired_events_are_dropped() {
+        let (dir, path) = journal_path();
+        let now = get_timestamp_sec();
+        let old = now - 120;
+
+        let mut record = vec![];
+        encode(&event(1, old), &mut record).unwrap();
+        let config = EventJournalConfig {
+            // Two events per file
+            max_bytes: 4 * record.len() as u64,
+            max_age: Duration::from_secs(60),
+            ..EventJournalConfig::default()
+        };
+
+        let (mut files, _) = JournalFiles::open(path.clone(), config).unwrap();
+        files.append(&[event(1, old), event(2, old)]).unwrap();
+        files.append(&[event(3, old)]).unwrap();
+        // The replaced journal only has expired events
+        assert!(!replaced(&path).exists());
+        files.append(&[event(4, now)]).unwrap();
+        drop(files);
+
+        let (_, journaled) = JournalFiles::open(path, config).unwrap();
+        assert_eq!(sequences(&journaled.events), vec![4]);
+        assert_eq!(journaled.last_sequence, 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! An event bus of the lifecycle events of cells and executables. Events are
+//! numbered, and the last [RETAINED_EVENTS] are kept, so a watcher can resume
+//! after reconnecting without missing any. With an [EventJournal], watchers
+//! resume from the journaled events before those, and across restarts.
+
+use super::event_journal::{EventJournal, Journaled};
+use crate::logging::get_timestamp_sec;
+use proto::observe::{
+    lifecycle_event::Kind, LifecycleEvent, LifecycleEventKind,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// The number of events kept to resume watches from.
+const RETAINED_EVENTS: usize = 1024;
+
+/// The number of events a slow watcher can fall behind before it has to
+/// catch up from the retained events.
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+pub(crate) struct LifecycleEvents(Arc<Mutex<Inner>>);
+
+#[derive(Debug)]
+struct Inner {
+    next_sequence: u64,
+    retained: VecDeque<LifecycleEvent>,
+    /// The names of the executables started and not exited yet, by the name
+    /// of their cell and their pid.
+    executables: HashMap<(String, i32), String>,
+    tx: broadcast::Sender<LifecycleEvent>,
+    journal: Option<EventJournal>,
+}
+
+/// A watch of new events, after the retained events it resumes from.
+#[derive(Debug)]
+pub(crate) struct Subscription {
+    /// The retained events after the sequence the watch resumes from.
+    pub retained: Vec<LifecycleEvent>,
+    pub rx: broadcast::Receiver<LifecycleEvent>,
+    /// The sequence of the last event published before subscribing.
+    pub last_sequence: u64,
+}
+
+/// The events after `since` are no longer retained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EventsNotRetained {
+    pub since: u64,
+    pub oldest: u64,
+}
+
+impl LifecycleEvents {
+    /// Journals the events from now on, and carries on from the journaled
+    /// events. Called before any event is published.
+    pub fn journal_to(&self, journal: EventJournal, journaled: Journaled) {
+        let mut inner = self.0.lock().expect("lifecycle events lock");
+
+        let Journaled { events, last_sequence } = journaled;
+        inner.next_sequence = inner.next_sequence.max(last_sequence + 1);
+        let skip = events.len().saturating_sub(RETAINED_EVENTS);
+        inner.retained.extend(events.into_iter().skip(skip));
+        inner.journal = Some(journal);
+    }
+
+    /// Numbers the event, retains it, and sends it to the watchers.
+    pub fn publish(&self, cell_name: String, kind: Kind) {
+        self.publish_at(cell_name, kind, get_timestamp_sec())
+    }
+
+    /// Like [LifecycleEvents::publish], for an event that happened at
+    /// `timestamp`, e.g. in the auraed of a cell.
+    pub fn publish_at(&self, cell_name: String, kind: Kind, timestamp: i64) {
+        let mut inner = self.0.lock().expect("lifecycle events lock");
+
+        let event = LifecycleEvent {
+            sequence: inner.next_sequence,
+            timestamp,
+            cell_name,
+            kind: Some(kind),
+        };
+        inner.next_sequence += 1;
+        inner.track_executables(&event);
+
+        if inner.retained.len() == RETAINED_EVENTS {
+            let _ = inner.retained.pop_front();
+        }
+        inner.retained.push_back(event.clone());
+
+        if let Some(journal) = &inner.journal {
+            journal.append(event.clone());
+        }
+
+        // send returns an Err if there are no receivers. We ignore that.
+        let _ = inner.tx.send(event);
+    }
+
+    /// Subscribes to new events only.
+    pub fn subscribe_new(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.0.lock().expect("lifecycle events lock").tx.subscribe()
+    }
+
+    /// Subscribes to new events, and returns the retained events after
+    /// `since`, if any. No event is missed or repeated between the two.
+    pub async fn subscribe(
+        &self,
+        since: Option<u64>,
+    ) -> Result<Subscription, EventsNotRetained> {
+        let (retained, rx, last_sequence, journal) = {
+            let inner = self.0.lock().expect("lifecycle events lock");
+            (
+                since.map(|since| inner.retained_after(since)),
+                inner.tx.subscribe(),
+                inner.next_sequence - 1,
+                inner.journal.clone(),
+            )
+        };
+
+        let retained = match (since, retained) {
+            (Some(since), Some(retained)) => {
+                catch_up(journal, since, retained).await?
+            }
+            _ => vec![],
+        };
+
+        Ok(Subscription { retained, rx, last_sequence })
+    }
+
+    /// Returns the name of the executable of the cell `cell_name`, or of
+    /// auraed if it is empty, whose process is `pid`. Executables that exited
+    /// are found as long as their exit event is retained.
+    pub fn executable_of(&self, cell_name: &str, pid: i32) -> Option<String> {
+        self.0
+            .lock()
+            .expect("lifecycle events lock")
+            .executable_of(cell_name, pid)
+    }
+
+    /// Returns the retained events after `since`, to catch up after falling
+    /// behind.
+    pub async fn retained_after(
+        &self,
+        since: u64,
+    ) -> Result<Vec<LifecycleEvent>, EventsNotRetained> {
+        let (retained, journal) = {
+            let inner = self.0.lock().expect("lifecycle events lock");
+            (inner.retained_after(since), inner.journal.clone())
+        };
+
+        catch_up(journal, since, retained).await
+    }
+}
+
+/// The events after `since`, from the `retained` events, preceded by the
+/// journaled events they don't reach back to.
+async fn catch_up(
+    journal: Option<EventJournal>,
+    since: u64,
+    retained: Retained,
+) -> Result<Vec<LifecycleEvent>, EventsNotRetained> {
+    let Retained { events, oldest } = retained;
+    // Events are numbered from 1
+    if since + 1 >= oldest {
+        return Ok(events);
+    }
+
+    let not_retained = EventsNotRetained { since, oldest };
+    let Some(journal) = journal else {
+        return Err(not_retained);
+    };
+    let journaled = journal.read(since, oldest).await.map_err(|e| {
+        warn!("failed to read the event journal: {e}");
+        not_retained
+    })?;
+
+    // A watcher can't tell a gap in the events replayed
+    if !journaled.iter().map(|event| event.sequence).eq(since + 1..oldest) {
+        return Err(not_retained);
+    }
+
+    Ok(journaled.into_iter().chain(events).collect())
+}
+
+/// The retained events after a sequence.
+#[derive(Debug)]
+struct Retained {
+    events: Vec<LifecycleEvent>,
+    /// The sequence of the oldest event retained, or of the next event if
+    /// none is.
+    oldest: u64,
+}
+
+impl Inner {
+    fn track_executables(&mut self, event: &LifecycleEvent) {
+        let cell_name = &event.cell_name;
+        match &event.kind {
+            Some(Kind::ExecutableStarted(started)) => {
+                let _ = self.executables.insert(
+                    (cell_name.clone(), started.pid),
+                    started.executable_name.clone(),
+                );
+            }
+            Some(Kind::ExecutableExited(exited)) => {
+                let _ =
+                    self.executables.remove(&(cell_name.clone(), exited.pid));
+            }
+            // Including the executables of the cells nested in it
+            Some(Kind::CellFreed(_)) => {
+                let nested = format!("{cell_name}/");
+                self.executables.retain(|(name, _), _| {
+                    name != cell_name && !name.starts_with(&nested)
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn executable_of(&self, cell_name: &str, pid: i32) -> Option<String> {
+        if let Some(name) = self.executables.get(&(cell_name.into(), pid)) {
+            return Some(name.clone());
+        }
+
+        self.retained.iter().rev().find_map(|event| match &event.kind {
+            Some(Kind::ExecutableExited(exited))
+                if event.cell_name == cell_name && exited.pid == pid =>
+            {
+                Some(exited.executable_name.clone())
+            }
+            _ => None,
+        })
+    }
+
+    fn retained_after(&self, since: u64) -> Retained {
+        let oldest =
+            self.retained.front().map_or(self.next_sequence, |e| e.sequence);
+        let events = self
+            .retained
+            .iter()
+            .filter(|event| event.sequence > since)
+            .cloned()
+            .collect();
+
+        Retained { events, oldest }
+    }
+}
+
+impl Default for LifecycleEvents {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            next_sequence: 1,
+            retained: VecDeque::with_capacity(RETAINED_EVENTS),
+            executables: HashMap::new(),
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            journal: None,
+        })))
+    }
+}
+
+/// The kind of the event, to filter watches by.
+pub(crate) fn event_kind(event: &LifecycleEvent) -> LifecycleEventKind {
+    match event.kind {
+        Some(Kind::CellAllocated(_)) => LifecycleEventKind::CellAllocated,
+        Some(Kind::CellFreed(_)) => LifecycleEventKind::CellFreed,
+        Some(Kind::ExecutableStarted(_)) => {
+            LifecycleEventKind::ExecutableStarted
+        }
+        Some(Kind::ExecutableExited(_)) => LifecycleEventKind::ExecutableExited,
+        Some(Kind::OomKill(_)) => LifecycleEventKind::OomKill,
+        Some(Kind::Audit(_)) => LifecycleEventKind::Audit,
+        None => LifecycleEventKind::Unspecified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::observe::{
+        CellAllocated, CellFreed, ExecutableExited, ExecutableStarted,
+    };
+
+    fn sequences(events: &[LifecycleEvent]) -> Vec<u64> {
+        events.iter().map(|event| event.sequence).collect()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_without_since_only_gets_new_events() {
+        let events = LifecycleEvents::default();
+        events.publish("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+
+        let Subscription { retained, mut rx, last_sequence } =
+            events.subscribe(None).await.unwrap();
+        assert!(retained.is_empty());
+        assert_eq!(last_sequence, 1);
+
+        events.publish("ae-1".into(), Kind::CellFreed(CellFreed {}));
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.sequence, 2);
+        assert_eq!(event.cell_name, "ae-1");
+        assert_eq!(event_kind(&event), LifecycleEventKind::CellFreed);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_since_replays_retained_events() {
+        let events = LifecycleEvents::default();
+        for _ in 0..3 {
+            events
+                .publish("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+        }
+
+        let retained = events.subscribe(Some(0)).await.unwrap().retained;
+        assert_eq!(sequences(&retained), vec![1, 2, 3]);
+
+        let retained = events.subscribe(Some(2)).await.unwrap().retained;
+        assert_eq!(sequences(&retained), vec![3]);
+
+        let retained = events.subscribe(Some(3)).await.unwrap().retained;
+        assert!(retained.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_since_evicted_events_fails() {
+        let events = LifecycleEvents::default();
+        for _ in 0..RETAINED_EVENTS + 2 {
+            events
+                .publish("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+        }
+
+        // Events 1 and 2 were evicted
+        assert_eq!(
+            events.subscribe(Some(1)).await.unwrap_err(),
+            EventsNotRetained { since: 1, oldest: 3 }
+        );
+
+        let retained = events.subscribe(Some(2)).await.unwrap().retained;
+        assert_eq!(retained.len(), RETAINED_EVENTS);
+        assert_eq!(retained[0].sequence, 3);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_since_evicted_events_replays_the_journal() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-events-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("events.journal");
+
+        let events = LifecycleEvents::default();
+        let (journal, journaled) =
+            EventJournal::open(path.clone(), Default::default()).await.unwrap();
+        events.journal_to(journal, journaled);
+        for _ in 0..RETAINED_EVENTS + 2 {
+            events
+                .publish("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+        }
+
+        let retained = events.subscribe(Some(1)).await.unwrap().retained;
+        assert_eq!(retained.len(), RETAINED_EVENTS + 1);
+        assert_eq!(retained[0].sequence, 2);
+
+        // A restarted auraed carries on numbering the events
+        let events = LifecycleEvents::default();
+        let (journal, journaled) =
+            EventJournal::open(path, Default::default()).await.unwrap();
+        events.journal_to(journal, journaled);
+        events.publish("ae-1".into(), Kind::CellFreed(CellFreed {}));
+
+        let retained = events.subscribe(Some(0)).await.unwrap().retained;
+        assert_eq!(retained.len(), RETAINED_EVENTS + 3);
+        assert_eq!(
+            retained.last().unwrap().sequence,
+            RETAINED_EVENTS as u64 + 3
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn started(name: &str, pid: i32) -> Kind {
+        Kind::ExecutableStarted(ExecutableStarted {
+            executable_name: name.into(),
+            pid,
+        })
+    }
+
+    #[test]
+    fn test_executable_of_tracks_started_and_exited_executables() {
+        let events = LifecycleEvents::default();
+        events.publish(String::new(), started("ae-host", 42));
+        events.publish("ae-1".into(), started("ae-exe", 42));
+        events.publish("ae-1/ae-2".into(), started("ae-nested", 7));
+
+        assert_eq!(events.executable_of("", 42), Some("ae-host".into()));
+        assert_eq!(events.executable_of("ae-1", 42), Some("ae-exe".into()));
+        assert_eq!(events.executable_of("ae-1", 7), None);
+
+        // Exited executables are found by their retained exit event
+        events.publish(
+            "ae-1".into(),
+            Kind::ExecutableExited(ExecutableExited {
+                executable_name: "ae-exe".into(),
+                pid: 42,
+                code: None,
+                signal: Some(9),
+            }),
+        );
+        assert_eq!(events.0.lock().unwrap().executables.len(), 2);
+        assert_eq!(events.executable_of("ae-1", 42), Some("ae-exe".into()));
+
+        // Freeing a cell forgets the executables of its nested cells
+        events.publish("ae-1".into(), Kind::CellFreed(CellFreed {}));
+        assert_eq!(events.executable_of("ae-1/ae-2", 7), None);
+        assert_eq!(events.executable_of("", 42), Some("ae-host".into()));
+    }
+}
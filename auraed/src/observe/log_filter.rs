@@ -0,0 +1,225 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::error::ObserveServiceError;
+use proto::observe::{
+    GetLogStreamRequest, GetLogStreamResponse, LogLevel, LogSource,
+};
+use regex::Regex;
+
+/// The filters of a [GetLogStreamRequest], applied to records before they
+/// are sent.
+#[derive(Debug)]
+pub(crate) struct LogFilter {
+    cell_name_prefix: String,
+    executable_name: String,
+    min_level: LogLevel,
+    line_regex: Option<Regex>,
+}
+
+impl LogFilter {
+    pub fn new(
+        request: &GetLogStreamRequest,
+    ) -> Result<Self, ObserveServiceError> {
+        let line_regex = if request.line_regex.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&request.line_regex).map_err(|e| {
+                ObserveServiceError::InvalidLineRegex {
+                    line_regex: request.line_regex.clone(),
+                    source: e,
+                }
+            })?)
+        };
+
+        Ok(Self {
+            cell_name_prefix: request.cell_name_prefix.clone(),
+            executable_name: request.executable_name.clone(),
+            min_level: request.min_level(),
+            line_regex,
+        })
+    }
+
+    /// Whether the logs from outside of any cell are included.
+    pub fn includes_local(&self) -> bool {
+        self.cell_name_prefix.is_empty()
+    }
+
+    pub fn includes_cell(&self, cell_name: &str) -> bool {
+        cell_name.starts_with(&self.cell_name_prefix)
+    }
+
+    /// Whether the tracing events of auraed are included.
+    pub fn includes_auraed(&self) -> bool {
+        self.executable_name.is_empty()
+    }
+
+    pub fn includes_executable(&self, executable_name: &str) -> bool {
+        self.executable_name.is_empty()
+            || self.executable_name == executable_name
+    }
+
+    /// Whether the record passes the level and line filters. Records without
+    /// a level are always included.
+    pub fn matches(&self, response: &GetLogStreamResponse) -> bool {
+        let Some(item) = &response.item else {
+            return false;
+        };
+
+        if response.source() == LogSource::Auraed
+            && item.level() != LogLevel::Unspecified
+            && (item.level() as i32) < (self.min_level as i32)
+        {
+            return false;
+        }
+
+        self.line_regex.as_ref().is_none_or(|regex| regex.is_match(&item.line))
+    }
+
+    /// The request to forward to the auraed of a cell, which has no cells
+    /// of its own.
+    pub fn to_cell_request(&self) -> GetLogStreamRequest {
+        GetLogStreamRequest {
+            cell_name_prefix: String::new(),
+            executable_name: self.executable_name.clone(),
+            min_level: self.min_level.into(),
+            line_regex: self
+                .line_regex
+                .as_ref()
+                .map(|regex| regex.as_str().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::observe::LogItem;
+    use tonic::{Code, Status};
+
+    fn response(
+        source: LogSource,
+        level: LogLevel,
+        line: &str,
+    ) -> GetLogStreamResponse {
+        GetLogStreamResponse {
+            item: Some(LogItem {
+                line: line.into(),
+                level: level.into(),
+                ..Default::default()
+            }),
+            source: source.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_no_filters() {
+        let filter = LogFilter::new(&GetLogStreamRequest::default()).unwrap();
+        assert!(filter.includes_local());
+        assert!(filter.includes_cell("ae-1"));
+        assert!(filter.includes_auraed());
+        assert!(filter.includes_executable("ae-exe"));
+        assert!(filter.matches(&response(
+            LogSource::Auraed,
+            LogLevel::Trace,
+            "x"
+        )));
+        assert!(!filter.matches(&GetLogStreamResponse::default()));
+    }
+
+    #[test]
+    fn test_cell_name_prefix() {
+        let filter = LogFilter::new(&GetLogStreamRequest {
+            cell_name_prefix: "ae-1".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!filter.includes_local());
+        assert!(filter.includes_cell("ae-1"));
+        assert!(filter.includes_cell("ae-1/nested"));
+        assert!(!filter.includes_cell("ae-2"));
+        assert!(filter.to_cell_request().cell_name_prefix.is_empty());
+    }
+
+    #[test]
+    fn test_executable_name_excludes_auraed() {
+        let filter = LogFilter::new(&GetLogStreamRequest {
+            executable_name: "ae-exe".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!filter.includes_auraed());
+        assert!(filter.includes_executable("ae-exe"));
+        assert!(!filter.includes_executable("ae-other"));
+    }
+
+    #[test]
+    fn test_min_level_only_applies_to_auraed() {
+        let filter = LogFilter::new(&GetLogStreamRequest {
+            min_level: LogLevel::Warn.into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!filter.matches(&response(
+            LogSource::Auraed,
+            LogLevel::Info,
+            "x"
+        )));
+        assert!(filter.matches(&response(
+            LogSource::Auraed,
+            LogLevel::Warn,
+            "x"
+        )));
+        assert!(filter.matches(&response(
+            LogSource::Auraed,
+            LogLevel::Error,
+            "x"
+        )));
+        assert!(filter.matches(&response(
+            LogSource::Executable,
+            LogLevel::Unspecified,
+            "x"
+        )));
+    }
+
+    #[test]
+    fn test_line_regex() {
+        let filter = LogFilter::new(&GetLogStreamRequest {
+            line_regex: "^err(or)?".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(filter.matches(&response(
+            LogSource::Executable,
+            LogLevel::Unspecified,
+            "error: oops"
+        )));
+        assert!(!filter.matches(&response(
+            LogSource::Executable,
+            LogLevel::Unspecified,
+            "ok"
+        )));
+        assert_eq!(filter.to_cell_request().line_regex, "^err(or)?");
+
+        let err = LogFilter::new(&GetLogStreamRequest {
+            line_regex: "(".into(),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(Status::from(err).code(), Code::InvalidArgument);
+    }
+}
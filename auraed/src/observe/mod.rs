@@ -14,10 +14,14 @@
 \* -------------------------------------------------------------------------- */
 
 pub(crate) use error::ObserveServiceError;
+pub use event_journal::EventJournalConfig;
 pub(crate) use observe_service::ObserveService;
 
 mod cgroup_cache;
 mod error;
+mod event_journal;
+mod lifecycle_events;
+mod log_filter;
 mod observe_service;
 mod observed_event_stream;
 mod proc_cache;
\ No newline at end of file
@@ -18,27 +18,84 @@
 
 use super::cgroup_cache;
 use super::error::ObserveServiceError;
+use super::event_journal::{EventJournal, EventJournalConfig};
+use super::lifecycle_events::{event_kind, LifecycleEvents, Subscription};
+use super::log_filter::LogFilter;
 use super::observed_event_stream::ObservedEventStream;
 use super::proc_cache::{ProcCache, ProcfsProcessInfo};
+use crate::cells::{CellSockets, ExecutableName, Workload as ProcessWorkload};
+use crate::cri::PodSandboxes;
+
+use crate::blocking;
 use crate::ebpf::tracepoint::PerfEventBroadcast;
-use crate::logging::log_channel::LogChannel;
+use crate::graceful_shutdown::StreamCloser;
+use crate::logging::get_timestamp_sec;
+use crate::logging::log_channel::{
+    LogChannel, Subscription, SubscriptionStream,
+};
+use crate::logging::log_level;
+use crate::logging::log_registry::{LogKey, LogOwner, LogRegistry};
+use crate::logging::redaction;
+use crate::tls;
 use aurae_ebpf_shared::{ForkedProcess, ProcessExit, Signal};
 use cgroup_cache::CgroupCache;
+use client::{
+    observe::observe_service::ObserveServiceClient, AuraeSocket, Client,
+};
+use once_cell::sync::OnceCell;
+use proto::observe::lifecycle_event::Kind;
+use proto::observe::ExecutableExited;
 use proto::observe::{
-    observe_service_server, GetAuraeDaemonLogStreamRequest,
-    GetAuraeDaemonLogStreamResponse, GetPosixSignalsStreamRequest,
-    GetPosixSignalsStreamResponse, GetSubProcessStreamRequest,
-    GetSubProcessStreamResponse, LogChannelType, LogItem,
-    Signal as PosixSignal, WorkloadType,
+    observe_service_server, BlockingPoolStats, GetAuraeDaemonLogStreamRequest,
+    GetAuraeDaemonLogStreamResponse, GetBlockingPoolStatsRequest,
+    GetBlockingPoolStatsResponse, GetLogLevelRequest, GetLogLevelResponse,
+    GetLogStreamRequest, GetLogStreamResponse, GetPosixSignalsStreamRequest,
+    GetPosixSignalsStreamResponse, GetRedactionStatsRequest,
+    GetRedactionStatsResponse, GetSubProcessStreamRequest,
+    GetSubProcessStreamResponse, GetTlsStatusRequest, GetTlsStatusResponse,
+    LifecycleEventKind, LogChannelType, LogItem, LogSource, RedactionRuleStats,
+    ReloadTlsRequest, ReloadTlsResponse, SetLogLevelRequest,
+    SetLogLevelResponse, Signal as PosixSignal, TlsStatus, WatchEventsRequest,
+    WatchEventsResponse, Workload, WorkloadType,
 };
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::{Duration, UNIX_EPOCH};
 use std::{ffi::OsString, sync::Arc};
-use tokio::sync::mpsc;
-use tokio::sync::{broadcast::Receiver, Mutex};
-use tokio_stream::wrappers::ReceiverStream;
+use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{
+    errors::BroadcastStreamRecvError, ReceiverStream,
+};
+use tokio_stream::Stream;
+use tokio_stream::{StreamExt, StreamMap};
 use tonic::{Request, Response, Status};
-use tracing::info;
+use tracing::{info, warn};
+
+/// How often a log stream looks for new cells to include.
+const LOG_STREAM_CELLS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often, and how many times in a row, connecting to the auraed of a
+/// cell to watch its events is tried before giving up on the cell.
+const CELL_EVENTS_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+const CELL_EVENTS_CONNECT_ATTEMPTS: u32 = 50;
+
+/// The names the streams of this service subscribe to log channels as.
+const SUB_PROCESS_SUBSCRIBER: &str = "get_sub_process_stream";
+const LOG_STREAM_SUBSCRIBER: &str = "get_log_stream";
+const DAEMON_LOG_STREAM_SUBSCRIBER: &str = "get_aurae_daemon_log_stream";
+
+/// A source of records for a log stream. Records without an item report
+/// lines dropped by the source.
+type LogStream = Pin<Box<dyn Stream<Item = GetLogStreamResponse> + Send>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum LogStreamKey {
+    Auraed,
+    Executable(String, LogChannelType),
+    Cell(String),
+}
 
 #[derive(Debug, Clone)]
 pub struct ObserveService {
@@ -46,8 +103,19 @@ pub struct ObserveService {
     cgroup_cache: Arc<Mutex<CgroupCache>>,
     proc_cache: Option<Arc<Mutex<ProcCache>>>,
     posix_signals: Option<PerfEventBroadcast<Signal>>,
-    sub_process_consumer_list:
-        Arc<Mutex<HashMap<i32, HashMap<LogChannelType, LogChannel>>>>,
+    /// Where the log channels of the executables and processes are found.
+    log_registry: LogRegistry,
+    /// The names of executables as they are registered.
+    registered_executables: broadcast::Sender<String>,
+    /// Set by the cell service, to forward requests about the executables
+    /// of a cell to its auraed.
+    cell_sockets: Arc<OnceCell<CellSockets>>,
+    /// Set by the runtime service, to label the signals sent to the
+    /// processes of pod sandboxes.
+    pod_sandboxes: Arc<OnceCell<PodSandboxes>>,
+    lifecycle_events: LifecycleEvents,
+    /// Ends the streaming responses when auraed shuts down.
+    stream_closer: StreamCloser,
 }
 
 type PerfEvents = (
@@ -77,85 +145,546 @@ impl ObserveService {
             ))),
             proc_cache,
             posix_signals: perf_events.2,
-            sub_process_consumer_list: Arc::new(Mutex::new(HashMap::new())),
+            log_registry: LogRegistry::global().clone(),
+            registered_executables: broadcast::channel(16).0,
+            cell_sockets: Arc::new(OnceCell::new()),
+            pod_sandboxes: Arc::new(OnceCell::new()),
+            lifecycle_events: LifecycleEvents::default(),
+            stream_closer: StreamCloser::default(),
+        }
+    }
+
+    /// Journals the lifecycle events to `path`, carrying on from the
+    /// events journaled there before.
+    pub(crate) async fn with_event_journal(
+        self,
+        path: PathBuf,
+        config: EventJournalConfig,
+    ) -> Self {
+        match EventJournal::open(path.clone(), config).await {
+            Ok((journal, journaled)) => {
+                self.lifecycle_events.journal_to(journal, journaled);
+            }
+            Err(e) => {
+                warn!("failed to open event journal '{}': {e}", path.display())
+            }
+        }
+        self
+    }
+
+    /// The [StreamCloser] of the streaming responses of auraed.
+    pub fn stream_closer(&self) -> &StreamCloser {
+        &self.stream_closer
+    }
+
+    pub fn set_cell_sockets(&self, cell_sockets: CellSockets) {
+        if self.cell_sockets.set(cell_sockets).is_err() {
+            warn!("cell sockets are already set for the observe service");
+        }
+    }
+
+    pub fn set_pod_sandboxes(&self, pod_sandboxes: PodSandboxes) {
+        if self.pod_sandboxes.set(pod_sandboxes).is_err() {
+            warn!("pod sandboxes are already set for the observe service");
         }
     }
 
-    pub async fn register_sub_process_channel(
+    /// Tells the log streams that the channels of `executable_name` are
+    /// registered in the [LogRegistry], for them to include its output.
+    pub fn notify_executable_registered(&self, executable_name: String) {
+        // send returns an Err if there are no receivers. We ignore that.
+        let _ = self.registered_executables.send(executable_name);
+    }
+
+    /// Publishes a lifecycle event of this auraed, or of the cell
+    /// `cell_name` if it is not empty, to WatchEvents.
+    pub fn publish_event(&self, cell_name: String, kind: Kind) {
+        self.lifecycle_events.publish(cell_name, kind);
+    }
+
+    /// Republishes the lifecycle events of the auraed of the cell, with the
+    /// name of the cell, until the auraed is gone. The events after `since`
+    /// are replayed first, if any.
+    pub fn watch_cell_events(
         &self,
-        pid: i32,
-        channel_type: LogChannelType,
-        channel: LogChannel,
-    ) -> Result<(), ObserveServiceError> {
-        info!("Registering channel for pid {pid} {channel_type:?}");
-        let mut consumer_list = self.sub_process_consumer_list.lock().await;
-        if consumer_list.get(&pid).is_none() {
-            let _ = consumer_list.insert(pid, HashMap::new());
+        cell_name: String,
+        client_socket: AuraeSocket,
+        mut since: Option<u64>,
+    ) {
+        let events = self.lifecycle_events.clone();
+        let _ignored = tokio::spawn(async move {
+            let mut attempts = 0;
+            while attempts < CELL_EVENTS_CONNECT_ATTEMPTS {
+                let response =
+                    match Client::new_no_tls(client_socket.clone()).await {
+                        Ok(client) => {
+                            client
+                                .watch_events(WatchEventsRequest {
+                                    kinds: vec![],
+                                    since,
+                                })
+                                .await
+                        }
+                        Err(e) => Err(Status::unavailable(e.to_string())),
+                    };
+                let Ok(response) = response else {
+                    attempts += 1;
+                    tokio::time::sleep(CELL_EVENTS_RETRY_INTERVAL).await;
+                    continue;
+                };
+                attempts = 0;
+
+                let mut stream = response.into_inner();
+                while let Some(Ok(WatchEventsResponse { event: Some(event) })) =
+                    stream.next().await
+                {
+                    // Resume after the last event seen when reconnecting
+                    since = Some(event.sequence);
+                    let Some(kind) = event.kind else {
+                        continue;
+                    };
+                    let event_cell_name = if event.cell_name.is_empty() {
+                        cell_name.clone()
+                    } else {
+                        format!("{cell_name}/{}", event.cell_name)
+                    };
+                    events.publish_at(event_cell_name, kind, event.timestamp);
+                }
+            }
+        });
+    }
+
+    /// Subscribes to the channels of a sub process, by what owns them.
+    /// Returns the retained lines sent at or after `since` as well, in the
+    /// order they were sent.
+    async fn subscribe_sub_process(
+        &self,
+        owner: LogOwner,
+        channel_types: &[LogChannelType],
+        since: Option<i64>,
+    ) -> Result<
+        (
+            Vec<(LogChannelType, LogItem)>,
+            StreamMap<LogChannelType, SubscriptionStream>,
+        ),
+        ObserveServiceError,
+    > {
+        let mut replay = vec![];
+        let mut streams = StreamMap::new();
+        for channel_type in channel_types {
+            let key =
+                LogKey { owner: owner.clone(), channel_type: *channel_type };
+            let Some(channel) = self.log_registry.lookup(&key) else {
+                return Err(match owner {
+                    LogOwner::Executable(executable_name) => {
+                        ObserveServiceError::NoChannelsForExecutable {
+                            executable_name: executable_name.to_string(),
+                        }
+                    }
+                    LogOwner::Process(pid) => {
+                        ObserveServiceError::NoChannelsForPid { pid }
+                    }
+                    LogOwner::PodSandbox(pod_sandbox_id) => {
+                        ObserveServiceError::NoChannelsForPodSandbox {
+                            pod_sandbox_id,
+                        }
+                    }
+                });
+            };
+            let rx = match since {
+                None => channel.subscribe(SUB_PROCESS_SUBSCRIBER),
+                Some(since) => {
+                    let (items, rx) =
+                        channel.subscribe_since(SUB_PROCESS_SUBSCRIBER, since);
+                    replay.extend(
+                        items.into_iter().map(|item| (*channel_type, item)),
+                    );
+                    rx
+                }
+            };
+            let _ = streams.insert(*channel_type, rx.into_stream());
+        }
+
+        // Timestamps are in seconds, so lines of different channels sent
+        // within the same second may be out of order
+        replay.sort_by_key(|(_, item)| item.timestamp);
+
+        Ok((replay, streams))
+    }
+
+    /// Forwards the request to the auraed of the cell, which streams the
+    /// output of its executables.
+    async fn get_sub_process_stream_in_cell(
+        &self,
+        cell_name: String,
+        request: GetSubProcessStreamRequest,
+    ) -> Result<
+        ReceiverStream<Result<GetSubProcessStreamResponse, Status>>,
+        Status,
+    > {
+        let Some(cell_sockets) = self.cell_sockets.get() else {
+            return Err(ObserveServiceError::CellNotFound { cell_name }.into());
+        };
+
+        let client_socket = cell_sockets.get(cell_name.clone()).await?;
+        let client = Client::new_no_tls(client_socket).await.map_err(|e| {
+            ObserveServiceError::CellUnreachable { cell_name, source: e }
+        })?;
+
+        let mut items =
+            client.get_sub_process_stream(request).await?.into_inner();
+
+        let (tx, rx) =
+            mpsc::channel::<Result<GetSubProcessStreamResponse, Status>>(4);
+
+        let _ignored = tokio::spawn(async move {
+            while let Some(item) = items.next().await {
+                if tx.send(item).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Adds the channels of an executable to the streams of a log stream,
+    /// if it is still registered.
+    async fn add_executable_log_streams(
+        &self,
+        executable_name: String,
+        streams: &mut StreamMap<LogStreamKey, LogStream>,
+    ) {
+        const CHANNEL_TYPES: [LogChannelType; 2] =
+            [LogChannelType::Stdout, LogChannelType::Stderr];
+
+        let owner =
+            LogOwner::Executable(ExecutableName::new(executable_name.clone()));
+        let Ok((_, mut channels)) =
+            self.subscribe_sub_process(owner, &CHANNEL_TYPES, None).await
+        else {
+            return;
+        };
+
+        for channel_type in CHANNEL_TYPES {
+            let Some(channel) = channels.remove(&channel_type) else {
+                continue;
+            };
+
+            let template = GetLogStreamResponse {
+                source: LogSource::Executable.into(),
+                executable_name: executable_name.clone(),
+                channel_type: channel_type.into(),
+                ..Default::default()
+            };
+            let _ = streams.insert(
+                LogStreamKey::Executable(executable_name.clone(), channel_type),
+                log_channel_stream(channel, template),
+            );
         }
-        if consumer_list
-            .get(&pid)
-            .expect("pid channels")
-            .get(&channel_type)
-            .is_some()
+    }
+
+    /// Adds the log streams of auraed and of its executables not yet
+    /// streamed from, unless they are excluded by `filter`.
+    async fn add_local_log_streams(
+        &self,
+        filter: &LogFilter,
+        streams: &mut StreamMap<LogStreamKey, LogStream>,
+    ) {
+        if !filter.includes_local() {
+            return;
+        }
+
+        if filter.includes_auraed()
+            && !streams.contains_key(&LogStreamKey::Auraed)
         {
-            return Err(ObserveServiceError::ChannelAlreadyRegistered {
-                pid,
-                channel_type,
+            let template = GetLogStreamResponse {
+                source: LogSource::Auraed.into(),
+                ..Default::default()
+            };
+            let _ = streams.insert(
+                LogStreamKey::Auraed,
+                log_channel_stream(
+                    self.aurae_logger.subscribe(LOG_STREAM_SUBSCRIBER),
+                    template,
+                ),
+            );
+        }
+
+        let executable_names =
+            self.log_registry.list().into_iter().filter_map(|key| match key {
+                LogKey {
+                    owner: LogOwner::Executable(executable_name),
+                    channel_type: LogChannelType::Stdout,
+                } => Some(executable_name.to_string()),
+                _ => None,
             });
+        for executable_name in executable_names {
+            let key = LogStreamKey::Executable(
+                executable_name.clone(),
+                LogChannelType::Stdout,
+            );
+            if filter.includes_executable(&executable_name)
+                && !streams.contains_key(&key)
+            {
+                self.add_executable_log_streams(executable_name, streams).await;
+            }
         }
-        let _ = consumer_list
-            .get_mut(&pid)
-            .expect("pid channels")
-            .insert(channel_type, channel);
-        Ok(())
     }
 
-    pub async fn unregister_sub_process_channel(
+    /// Adds the log streams of the cells not yet streamed from. The auraed
+    /// of each cell filters its own records.
+    async fn add_cell_log_streams(
         &self,
-        pid: i32,
-        channel_type: LogChannelType,
-    ) -> Result<(), ObserveServiceError> {
-        info!("Unregistering for pid {pid} {channel_type:?}");
-        let mut consumer_list = self.sub_process_consumer_list.lock().await;
-        if let Some(channels) = consumer_list.get_mut(&pid) {
-            if channels.remove(&channel_type).is_none() {
-                return Err(ObserveServiceError::ChannelNotRegistered {
-                    pid,
-                    channel_type,
-                });
+        filter: &LogFilter,
+        streams: &mut StreamMap<LogStreamKey, LogStream>,
+    ) {
+        let Some(cell_sockets) = self.cell_sockets.get() else {
+            return;
+        };
+
+        for (cell_name, client_socket) in cell_sockets.all().await {
+            let key = LogStreamKey::Cell(cell_name.clone());
+            if !filter.includes_cell(&cell_name) || streams.contains_key(&key) {
+                continue;
             }
-        } else {
-            return Err(ObserveServiceError::NoChannelsForPid { pid });
+
+            // The auraed of a new cell may not listen yet, in which case it
+            // is retried on the next interval
+            let Ok(client) = Client::new_no_tls(client_socket).await else {
+                continue;
+            };
+            let Ok(response) =
+                client.get_log_stream(filter.to_cell_request()).await
+            else {
+                continue;
+            };
+
+            let stream = response.into_inner().map_while(move |response| {
+                let mut response = response.ok()?;
+                response.cell_name = cell_name.clone();
+                Some(response)
+            });
+            let _ = streams.insert(key, Box::pin(stream));
         }
-        Ok(())
     }
 
-    fn get_aurae_daemon_log_stream(&self) -> Receiver<LogItem> {
-        self.aurae_logger.subscribe()
+    fn get_aurae_daemon_log_stream(&self) -> Subscription {
+        self.aurae_logger.subscribe(DAEMON_LOG_STREAM_SUBSCRIBER)
     }
 
-    async fn get_posix_signals_stream(
+    /// Streams the signals observed by the eBPF probe, labelled with the
+    /// workload of the process they were sent to.
+    fn get_probed_signals_stream(
+        &self,
+        posix_signals: &PerfEventBroadcast<Signal>,
+        filter: Option<(WorkloadType, String)>,
+        include_unmanaged: bool,
+    ) -> ReceiverStream<Result<GetPosixSignalsStreamResponse, Status>> {
+        let mut stream = ObservedEventStream::new(posix_signals);
+        let _ = stream.filter_by_workload(filter);
+        // Without the fork and exit probes, pids are not mapped
+        if let Some(proc_cache) = &self.proc_cache {
+            let _ = stream.map_pids(proc_cache.clone());
+        }
+        let mut events = stream.subscribe(|signal, pid| (signal, pid));
+
+        let (tx, rx) =
+            mpsc::channel::<Result<GetPosixSignalsStreamResponse, Status>>(4);
+
+        let svc = self.clone();
+        let _ignored = tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let response = match event {
+                    Ok((signal, pid)) => {
+                        let signal = svc.label_signal(signal, pid).await;
+                        let managed = !signal.executable_name.is_empty()
+                            || signal.workload.is_some();
+                        if !managed && !include_unmanaged {
+                            continue;
+                        }
+                        Ok(GetPosixSignalsStreamResponse {
+                            signal: Some(signal),
+                        })
+                    }
+                    Err(e) => Err(e),
+                };
+                if tx.send(response).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        self.stream_closer.until_closed(ReceiverStream::new(rx))
+    }
+
+    /// Labels the signal with its sender, and the executable and workload of
+    /// the process it was sent to, `pid` in its pid namespace.
+    async fn label_signal(&self, signal: Signal, pid: i32) -> PosixSignal {
+        let workload = self.workload_of(signal.pid).await;
+        let cell_name = match &workload {
+            Some(workload)
+                if workload.workload_type() == WorkloadType::Cell =>
+            {
+                workload.id.as_str()
+            }
+            _ => "",
+        };
+        let executable_name = self
+            .lifecycle_events
+            .executable_of(cell_name, pid)
+            .unwrap_or_default();
+
+        // Signals the kernel generates are reported in the context of
+        // whichever task triggered them, which is not their sender
+        let sender_pid = match signal.code {
+            libc::SI_USER | libc::SI_TKILL | libc::SI_QUEUE => {
+                match &self.proc_cache {
+                    Some(proc_cache) => {
+                        let proc_cache = proc_cache.lock().await;
+                        proc_cache.get(signal.sender_pid).await
+                    }
+                    None => None,
+                }
+                .or(Some(signal.sender_pid))
+            }
+            _ => None,
+        };
+
+        PosixSignal {
+            signal: signal.signum,
+            process_id: pid,
+            sender_pid,
+            code: signal.code,
+            timestamp: get_timestamp_sec(),
+            executable_name,
+            workload,
+        }
+    }
+
+    /// Returns the cell or pod sandbox of the process `host_pid`, if any.
+    async fn workload_of(&self, host_pid: i32) -> Option<Workload> {
+        if let Some(cell_name) = ProcessWorkload::of_pid(host_pid).cell_name {
+            return Some(Workload {
+                workload_type: WorkloadType::Cell.into(),
+                id: cell_name.to_string(),
+            });
+        }
+
+        let pod_sandbox = self.pod_sandboxes.get()?.of_pid(host_pid).await?;
+        Some(Workload {
+            workload_type: WorkloadType::PodSandbox.into(),
+            id: pod_sandbox,
+        })
+    }
+
+    /// Streams the executables terminated by a signal, from the lifecycle
+    /// events, for the auraeds without the eBPF probe of the signals. Their
+    /// sender is unknown.
+    fn get_executable_terminations_stream(
         &self,
         filter: Option<(WorkloadType, String)>,
     ) -> ReceiverStream<Result<GetPosixSignalsStreamResponse, Status>> {
-        //TODO map err -> gRPC error status
-        let events = ObservedEventStream::new(
-            self.posix_signals.as_ref().expect("signals"),
-        )
-        .filter_by_workload(filter)
-        .map_pids(self.proc_cache.as_ref().expect("proc_cache").clone())
-        .subscribe(map_get_posix_signals_stream_response);
+        let cell_filter = match filter {
+            Some((WorkloadType::Cell, id)) => Some(id),
+            _ => None,
+        };
+        let mut rx = self.lifecycle_events.subscribe_new();
+
+        let (tx, out) =
+            mpsc::channel::<Result<GetPosixSignalsStreamResponse, Status>>(4);
+
+        let _ignored = tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = rx.recv() => match event {
+                        Ok(event) => event,
+                        // The terminations missed are not reported
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = tx.closed() => break,
+                };
+
+                let Some(Kind::ExecutableExited(ExecutableExited {
+                    executable_name,
+                    pid,
+                    signal: Some(signal),
+                    ..
+                })) = event.kind
+                else {
+                    continue;
+                };
+                if cell_filter.as_ref().is_some_and(|id| *id != event.cell_name)
+                {
+                    continue;
+                }
 
-        ReceiverStream::new(events)
+                let workload =
+                    (!event.cell_name.is_empty()).then(|| Workload {
+                        workload_type: WorkloadType::Cell.into(),
+                        id: event.cell_name,
+                    });
+                let signal = PosixSignal {
+                    signal,
+                    process_id: pid,
+                    sender_pid: None,
+                    code: libc::CLD_KILLED,
+                    timestamp: event.timestamp,
+                    executable_name,
+                    workload,
+                };
+                let response =
+                    GetPosixSignalsStreamResponse { signal: Some(signal) };
+                if tx.send(Ok(response)).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        self.stream_closer.until_closed(ReceiverStream::new(out))
     }
 }
 
-fn map_get_posix_signals_stream_response(
-    signal: Signal,
-    pid: i32,
-) -> GetPosixSignalsStreamResponse {
-    GetPosixSignalsStreamResponse {
-        signal: Some(PosixSignal { signal: signal.signum, process_id: pid }),
+/// Streams the items of a log channel as records like `template`. Lines
+/// dropped by the channel are reported by records without an item.
+fn log_channel_stream(
+    channel: Subscription,
+    template: GetLogStreamResponse,
+) -> LogStream {
+    Box::pin(channel.into_stream().map(move |item| match item {
+        Ok(item) => {
+            GetLogStreamResponse { item: Some(item), ..template.clone() }
+        }
+        Err(BroadcastStreamRecvError::Lagged(dropped)) => {
+            GetLogStreamResponse { dropped, ..template.clone() }
+        }
+    }))
+}
+
+impl From<tls::TlsStatus> for TlsStatus {
+    fn from(status: tls::TlsStatus) -> Self {
+        let tls::TlsStatus {
+            not_after,
+            loaded_at,
+            reloads,
+            failed_reloads,
+            last_error,
+        } = status;
+
+        Self {
+            not_after_ms: not_after.timestamp_millis(),
+            loaded_at_ms: loaded_at
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as i64)
+                .unwrap_or_default(),
+            reloads,
+            failed_reloads,
+            last_error: last_error.unwrap_or_default(),
+        }
     }
 }
 
@@ -188,7 +717,9 @@ impl observe_service_server::ObserveService for ObserveService {
             }
         });
 
-        Ok(Response::new(ReceiverStream::new(rx)))
+        Ok(Response::new(
+            self.stream_closer.until_closed(ReceiverStream::new(rx)),
+        ))
     }
 
     type GetSubProcessStreamStream =
@@ -198,28 +729,39 @@ impl observe_service_server::ObserveService for ObserveService {
         &self,
         request: Request<GetSubProcessStreamRequest>,
     ) -> Result<Response<Self::GetSubProcessStreamStream>, Status> {
-        let channel = LogChannelType::try_from(request.get_ref().channel_type)
-            .map_err(|_| ObserveServiceError::InvalidLogChannelType {
-                channel_type: request.get_ref().channel_type,
-            })?;
-        let pid: i32 = request.get_ref().process_id;
-
-        println!("Requested Channel {channel:?}");
-        println!("Requested Process ID {pid}");
-
-        let mut log_consumer = {
-            let mut consumer_list = self.sub_process_consumer_list.lock().await;
-            consumer_list
-                .get_mut(&pid)
-                .ok_or(ObserveServiceError::NoChannelsForPid { pid })?
-                .get_mut(&channel)
-                .ok_or(ObserveServiceError::ChannelNotRegistered {
-                    pid,
-                    channel_type: channel,
-                })?
-                .clone()
+        let mut request = request.into_inner();
+        if let Some(cell_name) = request.cell_name.take() {
+            let stream =
+                self.get_sub_process_stream_in_cell(cell_name, request).await?;
+            return Ok(Response::new(self.stream_closer.until_closed(stream)));
         }
-        .subscribe();
+
+        let channel_types = match LogChannelType::try_from(request.channel_type)
+        {
+            Ok(LogChannelType::Unspecified) => {
+                vec![LogChannelType::Stdout, LogChannelType::Stderr]
+            }
+            Ok(channel_type) => vec![channel_type],
+            Err(_) => {
+                return Err(ObserveServiceError::InvalidLogChannelType {
+                    channel_type: request.channel_type,
+                }
+                .into())
+            }
+        };
+
+        // By executable name, then by pod sandbox, then by pid
+        let owner = if !request.executable_name.is_empty() {
+            LogOwner::Executable(ExecutableName::new(request.executable_name))
+        } else if !request.pod_sandbox_id.is_empty() {
+            LogOwner::PodSandbox(request.pod_sandbox_id)
+        } else {
+            LogOwner::Process(request.process_id)
+        };
+        let (replay, mut log_consumer) = self
+            .subscribe_sub_process(owner, &channel_types, request.since)
+            .await?;
+        let follow = !request.no_follow;
 
         let (tx, rx) =
             mpsc::channel::<Result<GetSubProcessStreamResponse, Status>>(4);
@@ -227,11 +769,43 @@ impl observe_service_server::ObserveService for ObserveService {
         // TODO: error handling. Warning: recursively logging if error message is also send to this grpc api endpoint
         //  .. thus disabled logging here.
         let _ignored = tokio::spawn(async move {
-            // Log consumer will error if:
-            //  the producer is closed (no more logs)
-            //  the receiver is lagging
-            while let Ok(log_item) = log_consumer.recv().await {
-                let resp = GetSubProcessStreamResponse { item: Some(log_item) };
+            for (channel_type, log_item) in replay {
+                let resp = GetSubProcessStreamResponse {
+                    item: Some(log_item),
+                    channel_type: channel_type.into(),
+                    dropped: 0,
+                };
+                if tx.send(Ok(resp)).await.is_err() {
+                    // receiver is gone
+                    return;
+                }
+            }
+
+            if !follow {
+                return;
+            }
+
+            // The channels drop their oldest lines rather than wait for a
+            // slow client, which is told how many it missed. The stream ends
+            // once the channels are closed, when the executable is stopped.
+            let mut dropped = 0;
+            while let Some((channel_type, log_item)) = log_consumer.next().await
+            {
+                let log_item = match log_item {
+                    Ok(log_item) => log_item,
+                    Err(BroadcastStreamRecvError::Lagged(n)) => {
+                        dropped += n;
+                        continue;
+                    }
+                };
+
+                let resp = GetSubProcessStreamResponse {
+                    item: Some(log_item),
+                    channel_type: channel_type.into(),
+                    dropped,
+                };
+                dropped = 0;
+
                 if tx.send(Ok(resp)).await.is_err() {
                     // receiver is gone
                     break;
@@ -239,7 +813,179 @@ impl observe_service_server::ObserveService for ObserveService {
             }
         });
 
-        Ok(Response::new(ReceiverStream::new(rx)))
+        Ok(Response::new(
+            self.stream_closer.until_closed(ReceiverStream::new(rx)),
+        ))
+    }
+
+    type GetLogStreamStream =
+        ReceiverStream<Result<GetLogStreamResponse, Status>>;
+
+    async fn get_log_stream(
+        &self,
+        request: Request<GetLogStreamRequest>,
+    ) -> Result<Response<Self::GetLogStreamStream>, Status> {
+        let filter = LogFilter::new(request.get_ref())?;
+
+        // Subscribe before listing the executables, to not miss any
+        let mut registered_executables =
+            self.registered_executables.subscribe();
+        let mut streams = StreamMap::new();
+
+        self.add_local_log_streams(&filter, &mut streams).await;
+
+        let (tx, rx) = mpsc::channel::<Result<GetLogStreamResponse, Status>>(4);
+
+        let svc = self.clone();
+        // TODO: error handling. Warning: recursively logging if error message is also send to this grpc api endpoint
+        //  .. thus disabled logging here.
+        let _ignored = tokio::spawn(async move {
+            let mut cells_interval =
+                tokio::time::interval(LOG_STREAM_CELLS_INTERVAL);
+            let mut dropped: HashMap<LogStreamKey, u64> = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    Some((key, mut response)) = streams.next() => {
+                        if response.item.is_none() {
+                            *dropped.entry(key).or_default() +=
+                                response.dropped;
+                            continue;
+                        }
+
+                        // Filter before sending, so filtered lines never
+                        // leave auraed
+                        if !filter.matches(&response) {
+                            continue;
+                        }
+
+                        response.dropped +=
+                            dropped.remove(&key).unwrap_or_default();
+                        if tx.send(Ok(response)).await.is_err() {
+                            // receiver is gone
+                            break;
+                        }
+                    }
+                    executable_name = registered_executables.recv() => {
+                        match executable_name {
+                            Ok(executable_name) => {
+                                if filter.includes_local()
+                                    && filter.includes_executable(&executable_name)
+                                {
+                                    svc.add_executable_log_streams(
+                                        executable_name,
+                                        &mut streams,
+                                    )
+                                    .await;
+                                }
+                            }
+                            // Executables were missed, so look for them all
+                            Err(broadcast::error::RecvError::Lagged(_)) => {
+                                svc.add_local_log_streams(&filter, &mut streams)
+                                    .await;
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        }
+                    }
+                    _ = cells_interval.tick() => {
+                        svc.add_cell_log_streams(&filter, &mut streams).await;
+                    }
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(
+            self.stream_closer.until_closed(ReceiverStream::new(rx)),
+        ))
+    }
+
+    type WatchEventsStream =
+        ReceiverStream<Result<WatchEventsResponse, Status>>;
+
+    async fn watch_events(
+        &self,
+        request: Request<WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        let WatchEventsRequest { kinds, since } = request.into_inner();
+        let kinds = kinds
+            .into_iter()
+            .map(|kind| {
+                LifecycleEventKind::try_from(kind).map_err(|_| {
+                    ObserveServiceError::InvalidLifecycleEventKind { kind }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Subscription { retained, mut rx, last_sequence } =
+            self.lifecycle_events.subscribe(since).await.map_err(|e| {
+                ObserveServiceError::EventsNotRetained {
+                    since: e.since,
+                    oldest: e.oldest,
+                }
+            })?;
+
+        let events = self.lifecycle_events.clone();
+        let (tx, out) =
+            mpsc::channel::<Result<WatchEventsResponse, Status>>(16);
+
+        let _ignored = tokio::spawn(async move {
+            // The sequence of the last event sent, or filtered out
+            let mut last = since.unwrap_or(last_sequence);
+            let mut pending = VecDeque::from(retained);
+
+            loop {
+                let event = match pending.pop_front() {
+                    Some(event) => event,
+                    None => tokio::select! {
+                        event = rx.recv() => match event {
+                            Ok(event) => event,
+                            // Catch up from the retained events
+                            Err(broadcast::error::RecvError::Lagged(_)) => {
+                                match events.retained_after(last).await {
+                                    Ok(retained) => {
+                                        pending.extend(retained);
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        let _ = tx
+                                            .send(Err(ObserveServiceError::EventsNotRetained {
+                                                since: e.since,
+                                                oldest: e.oldest,
+                                            }
+                                            .into()))
+                                            .await;
+                                        break;
+                                    }
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        },
+                        _ = tx.closed() => break,
+                    },
+                };
+
+                // Events caught up on are received again
+                if event.sequence <= last {
+                    continue;
+                }
+                last = event.sequence;
+
+                if !kinds.is_empty() && !kinds.contains(&event_kind(&event)) {
+                    continue;
+                }
+
+                let response = WatchEventsResponse { event: Some(event) };
+                if tx.send(Ok(response)).await.is_err() {
+                    // receiver is gone
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            self.stream_closer.until_closed(ReceiverStream::new(out)),
+        ))
     }
 
     type GetPosixSignalsStreamStream =
@@ -249,128 +995,640 @@ impl observe_service_server::ObserveService for ObserveService {
         &self,
         request: Request<GetPosixSignalsStreamRequest>,
     ) -> Result<Response<Self::GetPosixSignalsStreamStream>, Status> {
-        if self.posix_signals.is_none() {
-            return Err(Status::unimplemented("GetPosixSignalStream is not implemented for nested Aurae daemons"));
-        }
+        let GetPosixSignalsStreamRequest { workload, include_unmanaged } =
+            request.into_inner();
+        let filter = workload.map(|w| (w.workload_type(), w.id));
 
-        Ok(Response::new(
-            self.get_posix_signals_stream(
-                request
-                    .into_inner()
-                    .workload
-                    .map(|w| (w.workload_type(), w.id)),
-            )
-            .await,
-        ))
+        let stream = match &self.posix_signals {
+            Some(posix_signals) => self.get_probed_signals_stream(
+                posix_signals,
+                filter,
+                include_unmanaged,
+            ),
+            // Only the terminations of executables are observed without the
+            // probe, e.g. in nested auraeds or on kernels lacking BPF support
+            None if include_unmanaged => {
+                return Err(ObserveServiceError::SignalProbeNotLoaded.into())
+            }
+            None => self.get_executable_terminations_stream(filter),
+        };
+
+        Ok(Response::new(stream))
+    }
+
+    async fn set_log_level(
+        &self,
+        request: Request<SetLogLevelRequest>,
+    ) -> Result<Response<SetLogLevelResponse>, Status> {
+        let SetLogLevelRequest { directives, revert_after_ms } =
+            request.into_inner();
+        let revert_after =
+            revert_after_ms.map(|ms| Duration::from_millis(ms.into()));
+
+        let previous_directives = log_level::get()
+            .and_then(|log_level| log_level.set(&directives, revert_after))
+            .map_err(ObserveServiceError::from)?;
+
+        Ok(Response::new(SetLogLevelResponse { previous_directives }))
+    }
+
+    async fn get_log_level(
+        &self,
+        _request: Request<GetLogLevelRequest>,
+    ) -> Result<Response<GetLogLevelResponse>, Status> {
+        let (directives, revert_to) =
+            log_level::get().map_err(ObserveServiceError::from)?.directives();
+
+        Ok(Response::new(GetLogLevelResponse { directives, revert_to }))
+    }
+
+    async fn reload_tls(
+        &self,
+        _request: Request<ReloadTlsRequest>,
+    ) -> Result<Response<ReloadTlsResponse>, Status> {
+        let server_tls = tls::get().map_err(ObserveServiceError::from)?;
+        let _ = blocking::run(tls::ReloadTls { server_tls, forced: true })
+            .await
+            .map_err(ObserveServiceError::from)?
+            .map_err(ObserveServiceError::from)?;
+
+        Ok(Response::new(ReloadTlsResponse {
+            status: Some(server_tls.status().into()),
+        }))
+    }
+
+    async fn get_tls_status(
+        &self,
+        _request: Request<GetTlsStatusRequest>,
+    ) -> Result<Response<GetTlsStatusResponse>, Status> {
+        let server_tls = tls::get().map_err(ObserveServiceError::from)?;
+
+        Ok(Response::new(GetTlsStatusResponse {
+            status: Some(server_tls.status().into()),
+        }))
+    }
+
+    async fn get_redaction_stats(
+        &self,
+        _request: Request<GetRedactionStatsRequest>,
+    ) -> Result<Response<GetRedactionStatsResponse>, Status> {
+        let rules = redaction::hits()
+            .into_iter()
+            .map(|(name, hits)| RedactionRuleStats { name, hits })
+            .collect();
+
+        Ok(Response::new(GetRedactionStatsResponse { rules }))
+    }
+
+    async fn get_blocking_pool_stats(
+        &self,
+        _request: Request<GetBlockingPoolStatsRequest>,
+    ) -> Result<Response<GetBlockingPoolStatsResponse>, Status> {
+        let pools = blocking::stats()
+            .into_iter()
+            .map(|stats| BlockingPoolStats {
+                name: stats.name.to_string(),
+                size: stats.size as u32,
+                queued: stats.queued as u32,
+                running: stats.running as u32,
+            })
+            .collect();
+
+        Ok(Response::new(GetBlockingPoolStatsResponse { pools }))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::ObserveService;
+    use crate::cells::ExecutableName;
     use crate::logging::log_channel::LogChannel;
-    use proto::observe::LogChannelType;
-    use std::sync::Arc;
+    use crate::logging::log_registry::{LogKey, LogRegistry};
+    use proto::observe::{
+        lifecycle_event::Kind, observe_service_server::ObserveService as _,
+        CellAllocated, CellFreed, ExecutableExited, ExecutableStarted,
+        GetLogStreamRequest, GetPosixSignalsStreamRequest,
+        GetSubProcessStreamRequest, LifecycleEventKind, LogChannelType,
+        LogLevel, LogSource, WatchEventsRequest, Workload, WorkloadType,
+    };
+    use std::{sync::Arc, time::Duration};
+    use tokio_stream::StreamExt;
+    use tonic::{Code, Request};
 
-    #[tokio::test]
-    async fn test_register_sub_process_channel_success() {
-        let svc = ObserveService::new(
+    async fn service_with_executable(
+        name: &str,
+        pid: i32,
+    ) -> (ObserveService, LogChannel, LogChannel) {
+        let mut svc = ObserveService::new(
             Arc::new(LogChannel::new(String::from("auraed"))),
             (None, None, None),
         );
-        assert!(svc
-            .register_sub_process_channel(
-                42,
-                LogChannelType::Stdout,
-                LogChannel::new(String::from("foo"))
-            )
+        svc.log_registry = LogRegistry::new(Duration::ZERO);
+        let stdout =
+            LogChannel::new(format!("{name}::stdout")).with_history(10);
+        let stderr =
+            LogChannel::new(format!("{name}::stderr")).with_history(10);
+        register_executable(&svc, name, pid, &stdout, &stderr);
+
+        (svc, stdout, stderr)
+    }
+
+    /// Registers the channels of an executable under its name and `pid`,
+    /// as a started executable does.
+    fn register_executable(
+        svc: &ObserveService,
+        name: &str,
+        pid: i32,
+        stdout: &LogChannel,
+        stderr: &LogChannel,
+    ) {
+        for (channel_type, channel) in
+            [(LogChannelType::Stdout, stdout), (LogChannelType::Stderr, stderr)]
+        {
+            let executable_name = ExecutableName::new(name.into());
+            svc.log_registry.register(
+                LogKey::executable(executable_name, channel_type),
+                channel.clone(),
+            );
+            svc.log_registry
+                .register(LogKey::process(pid, channel_type), channel.clone());
+        }
+        svc.notify_executable_registered(name.into());
+    }
+
+    #[tokio::test]
+    async fn test_get_sub_process_stream_by_executable_name() {
+        let (svc, stdout, stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let mut stream = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                executable_name: "ae-test-exe".into(),
+                ..Default::default()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+
+        stdout.send("out".into());
+        let item = stream.next().await.expect("item").expect("response");
+        assert_eq!(item.channel_type(), LogChannelType::Stdout);
+        assert_eq!(item.item.expect("log item").line, "out");
+
+        stderr.send("err".into());
+        let item = stream.next().await.expect("item").expect("response");
+        assert_eq!(item.channel_type(), LogChannelType::Stderr);
+        assert_eq!(item.item.expect("log item").line, "err");
+
+        // The stream ends once the executable is stopped, and its channels
+        // are dropped
+        for (channel_type, channel) in [
+            (LogChannelType::Stdout, &stdout),
+            (LogChannelType::Stderr, &stderr),
+        ] {
+            let executable_name = ExecutableName::new("ae-test-exe".into());
+            svc.log_registry.deregister(
+                LogKey::executable(executable_name, channel_type),
+                channel,
+            );
+            svc.log_registry
+                .deregister(LogKey::process(42, channel_type), channel);
+        }
+        drop((stdout, stderr));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_sub_process_stream_by_pid() {
+        let (svc, _stdout, stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let mut stream = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                process_id: 42,
+                channel_type: LogChannelType::Stderr.into(),
+                ..Default::default()
+            }))
             .await
-            .is_ok());
+            .expect("stream")
+            .into_inner();
+
+        stderr.send("err".into());
+        let item = stream.next().await.expect("item").expect("response");
+        assert_eq!(item.channel_type(), LogChannelType::Stderr);
+        assert_eq!(item.item.expect("log item").line, "err");
 
-        svc.sub_process_consumer_list.lock().await.clear();
+        let Err(status) = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                process_id: 43,
+                ..Default::default()
+            }))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::NotFound);
     }
 
     #[tokio::test]
-    async fn test_register_sub_process_channel_duplicate_error() {
+    async fn test_get_sub_process_stream_by_pod_sandbox_id() {
+        let (svc, _stdout, _stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+        let channel = LogChannel::new("ae-test-pod::stdout".into());
+        svc.log_registry.register(
+            LogKey::pod_sandbox("ae-test-pod".into(), LogChannelType::Stdout),
+            channel.clone(),
+        );
+
+        let mut stream = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                pod_sandbox_id: "ae-test-pod".into(),
+                channel_type: LogChannelType::Stdout.into(),
+                ..Default::default()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+
+        channel.send("out".into());
+        let item = stream.next().await.expect("item").expect("response");
+        assert_eq!(item.item.expect("log item").line, "out");
+
+        let Err(status) = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                pod_sandbox_id: "ae-missing".into(),
+                ..Default::default()
+            }))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_get_sub_process_stream_replays_retained_output() {
+        let (svc, stdout, stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+        stdout.send("out".into());
+        stderr.send("err".into());
+
+        let request = GetSubProcessStreamRequest {
+            executable_name: "ae-test-exe".into(),
+            since: Some(0),
+            ..Default::default()
+        };
+
+        // Without following, the stream ends after the replay
+        let stream = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                no_follow: true,
+                ..request.clone()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+        let lines: Vec<_> = stream
+            .map(|res| res.expect("response").item.expect("log item").line)
+            .collect()
+            .await;
+        assert_eq!(lines.len(), 2);
+        assert!(lines.contains(&"out".to_string()));
+        assert!(lines.contains(&"err".to_string()));
+
+        // Following, new lines come after the replay
+        let mut stream = svc
+            .get_sub_process_stream(Request::new(request))
+            .await
+            .expect("stream")
+            .into_inner();
+        stdout.send("new".into());
+        let lines: Vec<_> = (&mut stream)
+            .take(3)
+            .map(|res| res.expect("response").item.expect("log item").line)
+            .collect()
+            .await;
+        assert_eq!(lines[2], "new");
+    }
+
+    #[tokio::test]
+    async fn test_get_sub_process_stream_reports_dropped_lines() {
+        let (svc, stdout, _stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let mut stream = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                executable_name: "ae-test-exe".into(),
+                channel_type: LogChannelType::Stdout.into(),
+                ..Default::default()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+
+        // More lines than the channel holds, before the client reads any
+        for i in 0..200 {
+            stdout.send(format!("{i}"));
+        }
+
+        // The oldest lines are dropped
+        let item = stream.next().await.expect("item").expect("response");
+        let dropped = item.dropped;
+        assert!(dropped > 0);
+        assert_eq!(item.item.expect("log item").line, dropped.to_string());
+
+        let item = stream.next().await.expect("item").expect("response");
+        assert_eq!(item.dropped, 0);
+        assert_eq!(
+            item.item.expect("log item").line,
+            (dropped + 1).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_sub_process_stream_not_found() {
+        let (svc, _stdout, _stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let Err(status) = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                executable_name: "ae-test-missing".into(),
+                ..Default::default()
+            }))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::NotFound);
+
+        // Without cells, no cell can be found either
+        let Err(status) = svc
+            .get_sub_process_stream(Request::new(GetSubProcessStreamRequest {
+                cell_name: Some("ae-test-cell".into()),
+                executable_name: "ae-test-exe".into(),
+                ..Default::default()
+            }))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_get_log_stream_filters_auraed_events_by_level() {
+        let auraed = Arc::new(LogChannel::new(String::from("auraed")));
+        let svc = ObserveService::new(auraed.clone(), (None, None, None));
+
+        let mut stream = svc
+            .get_log_stream(Request::new(GetLogStreamRequest {
+                min_level: LogLevel::Warn.into(),
+                ..Default::default()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+
+        auraed.send_at(LogLevel::Info, "ignored".into());
+        auraed.send_at(LogLevel::Error, "failed".into());
+
+        let response = stream.next().await.expect("item").expect("response");
+        assert_eq!(response.source(), LogSource::Auraed);
+        assert!(response.cell_name.is_empty());
+        let item = response.item.expect("log item");
+        assert_eq!(item.level(), LogLevel::Error);
+        assert_eq!(item.line, "failed");
+    }
+
+    #[tokio::test]
+    async fn test_get_log_stream_includes_executables_as_they_start() {
+        let (svc, stdout, _stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let mut stream = svc
+            .get_log_stream(Request::new(GetLogStreamRequest {
+                line_regex: "^keep".into(),
+                ..Default::default()
+            }))
+            .await
+            .expect("stream")
+            .into_inner();
+
+        stdout.send("drop me".into());
+        stdout.send("keep me".into());
+
+        let response = stream.next().await.expect("item").expect("response");
+        assert_eq!(response.source(), LogSource::Executable);
+        assert_eq!(response.executable_name, "ae-test-exe");
+        assert_eq!(response.channel_type(), LogChannelType::Stdout);
+        assert_eq!(response.item.expect("log item").line, "keep me");
+
+        // An executable started while the stream is open is included
+        let stdout = LogChannel::new(String::from("ae-test-late::stdout"));
+        let stderr = LogChannel::new(String::from("ae-test-late::stderr"));
+        register_executable(&svc, "ae-test-late", 43, &stdout, &stderr);
+
+        // Let the stream subscribe to the new executable
+        tokio::task::yield_now().await;
+        stderr.send("keep this too".into());
+
+        let response = stream.next().await.expect("item").expect("response");
+        assert_eq!(response.executable_name, "ae-test-late");
+        assert_eq!(response.channel_type(), LogChannelType::Stderr);
+        assert_eq!(response.item.expect("log item").line, "keep this too");
+    }
+
+    #[tokio::test]
+    async fn test_get_log_stream_invalid_regex() {
+        let (svc, _stdout, _stderr) =
+            service_with_executable("ae-test-exe", 42).await;
+
+        let Err(status) = svc
+            .get_log_stream(Request::new(GetLogStreamRequest {
+                line_regex: "(".into(),
+                ..Default::default()
+            }))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_watch_events_filters_by_kind() {
         let svc = ObserveService::new(
             Arc::new(LogChannel::new(String::from("auraed"))),
             (None, None, None),
         );
-        assert!(svc
-            .register_sub_process_channel(
-                42,
-                LogChannelType::Stdout,
-                LogChannel::new(String::from("foo"))
-            )
-            .await
-            .is_ok());
-        assert!(svc
-            .register_sub_process_channel(
-                42,
-                LogChannelType::Stdout,
-                LogChannel::new(String::from("bar"))
-            )
+
+        let mut stream = svc
+            .watch_events(Request::new(WatchEventsRequest {
+                kinds: vec![LifecycleEventKind::ExecutableStarted.into()],
+                since: None,
+            }))
             .await
-            .is_err());
+            .expect("stream")
+            .into_inner();
 
-        svc.sub_process_consumer_list.lock().await.clear();
+        svc.publish_event("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+        svc.publish_event(
+            String::new(),
+            Kind::ExecutableStarted(ExecutableStarted {
+                executable_name: "ae-exe".into(),
+                pid: 42,
+            }),
+        );
+
+        let event = stream
+            .next()
+            .await
+            .expect("item")
+            .expect("response")
+            .event
+            .expect("event");
+        assert_eq!(event.sequence, 2);
+        assert!(event.cell_name.is_empty());
+        assert_eq!(
+            event.kind,
+            Some(Kind::ExecutableStarted(ExecutableStarted {
+                executable_name: "ae-exe".into(),
+                pid: 42,
+            }))
+        );
     }
 
     #[tokio::test]
-    async fn test_unregister_sub_process_channel_success() {
+    async fn test_watch_events_resumes_since() {
         let svc = ObserveService::new(
             Arc::new(LogChannel::new(String::from("auraed"))),
             (None, None, None),
         );
-        assert!(svc
-            .register_sub_process_channel(
-                42,
-                LogChannelType::Stdout,
-                LogChannel::new(String::from("foo"))
-            )
-            .await
-            .is_ok());
-        assert!(svc
-            .unregister_sub_process_channel(42, LogChannelType::Stdout)
+        svc.publish_event("ae-1".into(), Kind::CellAllocated(CellAllocated {}));
+        svc.publish_event("ae-1".into(), Kind::CellFreed(CellFreed {}));
+
+        let mut stream = svc
+            .watch_events(Request::new(WatchEventsRequest {
+                kinds: vec![],
+                since: Some(1),
+            }))
             .await
-            .is_ok());
+            .expect("stream")
+            .into_inner();
+
+        svc.publish_event("ae-2".into(), Kind::CellAllocated(CellAllocated {}));
 
-        svc.sub_process_consumer_list.lock().await.clear();
+        for (sequence, cell_name) in [(2, "ae-1"), (3, "ae-2")] {
+            let event = stream
+                .next()
+                .await
+                .expect("item")
+                .expect("response")
+                .event
+                .expect("event");
+            assert_eq!(event.sequence, sequence);
+            assert_eq!(event.cell_name, cell_name);
+        }
     }
 
     #[tokio::test]
-    async fn test_unregister_sub_process_channel_no_pid_error() {
+    async fn test_watch_events_since_not_retained() {
         let svc = ObserveService::new(
             Arc::new(LogChannel::new(String::from("auraed"))),
             (None, None, None),
         );
-        assert!(svc
-            .unregister_sub_process_channel(42, LogChannelType::Stdout)
+        for _ in 0..1100 {
+            svc.publish_event(
+                "ae-1".into(),
+                Kind::CellAllocated(CellAllocated {}),
+            );
+        }
+
+        let Err(status) = svc
+            .watch_events(Request::new(WatchEventsRequest {
+                kinds: vec![],
+                since: Some(1),
+            }))
             .await
-            .is_err());
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::OutOfRange);
+    }
 
-        svc.sub_process_consumer_list.lock().await.clear();
+    fn exited(name: &str, pid: i32, signal: Option<i32>) -> Kind {
+        Kind::ExecutableExited(ExecutableExited {
+            executable_name: name.into(),
+            pid,
+            code: signal.is_none().then_some(0),
+            signal,
+        })
     }
 
     #[tokio::test]
-    async fn test_unregister_sub_process_channel_no_channel_type_error() {
+    async fn test_posix_signals_without_probe_streams_terminations() {
         let svc = ObserveService::new(
             Arc::new(LogChannel::new(String::from("auraed"))),
             (None, None, None),
         );
-        assert!(svc
-            .register_sub_process_channel(
-                42,
-                LogChannelType::Stdout,
-                LogChannel::new(String::from("foo"))
-            )
+
+        let mut stream = svc
+            .get_posix_signals_stream(Request::new(
+                GetPosixSignalsStreamRequest {
+                    workload: Some(Workload {
+                        workload_type: WorkloadType::Cell.into(),
+                        id: "ae-1".into(),
+                    }),
+                    include_unmanaged: false,
+                },
+            ))
             .await
-            .is_ok());
-        assert!(svc
-            .unregister_sub_process_channel(42, LogChannelType::Stderr)
+            .expect("stream")
+            .into_inner();
+
+        svc.publish_event("ae-1".into(), exited("ae-exited", 41, None));
+        svc.publish_event("ae-2".into(), exited("ae-other", 42, Some(9)));
+        svc.publish_event("ae-1".into(), exited("ae-killed", 43, Some(15)));
+
+        let signal = stream
+            .next()
             .await
-            .is_err());
+            .expect("item")
+            .expect("response")
+            .signal
+            .expect("signal");
+        assert_eq!(signal.signal, 15);
+        assert_eq!(signal.process_id, 43);
+        assert_eq!(signal.code, libc::CLD_KILLED);
+        assert_eq!(signal.sender_pid, None);
+        assert_eq!(signal.executable_name, "ae-killed");
+        assert_eq!(
+            signal.workload,
+            Some(Workload {
+                workload_type: WorkloadType::Cell.into(),
+                id: "ae-1".into(),
+            })
+        );
+    }
 
-        svc.sub_process_consumer_list.lock().await.clear();
+    #[tokio::test]
+    async fn test_posix_signals_of_unmanaged_processes_needs_probe() {
+        let svc = ObserveService::new(
+            Arc::new(LogChannel::new(String::from("auraed"))),
+            (None, None, None),
+        );
+
+        let Err(status) = svc
+            .get_posix_signals_stream(Request::new(
+                GetPosixSignalsStreamRequest {
+                    workload: None,
+                    include_unmanaged: true,
+                },
+            ))
+            .await
+        else {
+            panic!("expected an error");
+        };
+        assert_eq!(status.code(), Code::Unimplemented);
     }
 }
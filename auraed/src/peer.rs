@@ -0,0 +1,205 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Metadata about the local process on the other end of a connection.
+
+use crate::blocking::{self, BlockingJob, Pool};
+use crate::cells::Workload;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UnixStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::server::{Connected, TlsConnectInfo};
+use tonic::Request;
+use tracing::{trace, warn};
+
+/// The process that connected to the unix socket of auraed, from the
+/// SO_PEERCRED credentials of the connection.
+///
+/// Found in the extensions of each request on the connection with
+/// [Peer::of_request].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Peer {
+    /// The pid of the process. Absent if it is not in the pid namespace of
+    /// auraed.
+    pub pid: Option<i32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// The workload the process belongs to, resolved when it connected.
+    pub workload: Workload,
+}
+
+impl Peer {
+    /// The [Peer] of `stream`, without its workload, which is resolved by
+    /// [ResolveWorkload].
+    fn of_stream(stream: &UnixStream) -> Self {
+        let Ok(cred) = stream.peer_cred() else {
+            return Self::default();
+        };
+
+        // A pid of 0 is reported for processes outside our pid namespace
+        Self {
+            pid: cred.pid().filter(|pid| *pid > 0),
+            uid: Some(cred.uid()),
+            gid: Some(cred.gid()),
+            workload: Workload::default(),
+        }
+    }
+
+    /// Returns the [Peer] of the connection `request` was received on, with
+    /// or without TLS. Absent for connections that are not over a unix socket.
+    pub fn of_request<T>(request: &Request<T>) -> Option<&Peer> {
+        let extensions = request.extensions();
+        extensions.get::<Peer>().or_else(|| {
+            extensions.get::<TlsConnectInfo<Peer>>().map(|info| info.get_ref())
+        })
+    }
+}
+
+/// A unix socket connection, that attaches the [Peer] connected to it to the
+/// requests received on it.
+#[derive(Debug)]
+pub(crate) struct PeerStream {
+    stream: UnixStream,
+    peer: Peer,
+}
+
+impl PeerStream {
+    /// Finds the [Peer] connected to `stream`. Its workload is read from
+    /// /proc on a blocking pool, and left unknown if that fails.
+    pub async fn accept(stream: UnixStream) -> Self {
+        let peer = Peer::of_stream(&stream);
+        let peer = match blocking::run(ResolveWorkload(peer.clone())).await {
+            Ok(peer) => peer,
+            Err(e) => {
+                warn!("failed to resolve the workload of {peer:?}: {e}");
+                peer
+            }
+        };
+        trace!("Accepted connection from {peer:?}");
+        Self { stream, peer }
+    }
+}
+
+/// Wraps the connections accepted on a unix socket in [PeerStream].
+pub(crate) fn accept_peers<E>(
+    incoming: impl Stream<Item = Result<UnixStream, E>> + Send + 'static,
+) -> impl Stream<Item = Result<PeerStream, E>> + Send + 'static
+where
+    E: Send + 'static,
+{
+    incoming.then(|stream| async move {
+        match stream {
+            Ok(stream) => Ok(PeerStream::accept(stream).await),
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Resolves the workload of a [Peer] from its pid.
+struct ResolveWorkload(Peer);
+
+impl BlockingJob for ResolveWorkload {
+    type Output = Peer;
+    const POOL: Pool = Pool::IoHeavy;
+
+    fn run(self) -> Self::Output {
+        let mut peer = self.0;
+        peer.workload = peer.pid.map(Workload::of_pid).unwrap_or_default();
+        peer
+    }
+}
+
+impl Connected for PeerStream {
+    type ConnectInfo = Peer;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.peer.clone()
+    }
+}
+
+impl AsyncRead for PeerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PeerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_peer_of_stream_is_this_process() {
+        let (stream, _other) = UnixStream::pair().unwrap();
+        let peer = PeerStream::accept(stream).await.connect_info();
+
+        assert_eq!(peer.pid, Some(std::process::id() as i32));
+        assert_eq!(peer.uid, Some(nix::unistd::getuid().as_raw()));
+        assert_eq!(peer.gid, Some(nix::unistd::getgid().as_raw()));
+    }
+
+    #[test]
+    fn test_peer_of_request() {
+        let peer = Peer { pid: Some(1), ..Default::default() };
+
+        let mut request = Request::new(());
+        assert_eq!(Peer::of_request(&request), None);
+
+        let _ = request.extensions_mut().insert(peer.clone());
+        assert_eq!(Peer::of_request(&request), Some(&peer));
+    }
+}
@@ -0,0 +1,460 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Reaps the children of auraed.
+//!
+//! The processes of executables are registered as [ReapedChild]ren as they
+//! are spawned. A single reaper, woken by SIGCHLD, reaps them as soon as they
+//! exit, and sends their exit status to the [ReapedChild] over a oneshot
+//! channel, rather than each child being waited on by a task of its own.
+//!
+//! Children auraed waits on itself, such as nested auraeds and virtual
+//! machines, are registered as [ManagedChild]ren instead, and left for their
+//! owner to wait on.
+//!
+//! When auraed runs as pid 1, or as a child subreaper, processes orphaned by
+//! their parent, e.g. the grandchildren of a daemonizing executable, are
+//! re-parented to auraed, and are reaped as well, as they would otherwise be
+//! left as zombies. Any child which is not registered is taken for an
+//! orphan, so every child auraed spawns must be spawned while [spawning] is
+//! held, and registered.
+
+use nix::unistd::Pid;
+use once_cell::sync::{Lazy, OnceCell};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    os::fd::{FromRawFd, OwnedFd},
+    os::unix::process::ExitStatusExt,
+    process::ExitStatus,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::oneshot;
+use tracing::{info, trace, warn};
+
+/// How often children are reaped when no SIGCHLD is received, to catch the
+/// ones which were still spawning when they were last looked for.
+const REAP_INTERVAL: Duration = Duration::from_secs(1);
+
+static CHILDREN: Lazy<Mutex<Children>> = Lazy::new(Default::default);
+
+/// Whether the exited children which are not registered are reaped too.
+static REAP_ORPHANS: AtomicBool = AtomicBool::new(false);
+
+/// Called with the exit status of a reaped child, or [None] if it was
+/// reaped by someone else.
+type ExitListener = Box<dyn FnOnce(Option<ExitStatus>) + Send>;
+
+#[derive(Default)]
+struct Children {
+    /// The children waited on by their owner.
+    managed: HashSet<Pid>,
+    /// The children reaped by the reaper, by pid.
+    reaped: HashMap<Pid, Reaped>,
+}
+
+enum Reaped {
+    Running {
+        listeners: Vec<ExitListener>,
+        /// Whether the [ReapedChild] was dropped, for the child to be
+        /// forgotten once it is reaped.
+        abandoned: bool,
+    },
+    /// Kept until the [ReapedChild] is dropped, for the listeners added
+    /// after the child exited.
+    Exited(Option<ExitStatus>),
+}
+
+/// Keeps children from being reaped while one is spawned, until it is
+/// registered with [Spawning::manage] or [Spawning::reap].
+pub(crate) struct Spawning(MutexGuard<'static, Children>);
+
+/// A child waited on by its owner, which the reaper leaves alone until this
+/// is dropped.
+#[derive(Debug)]
+pub(crate) struct ManagedChild(Pid);
+
+/// A child reaped by the reaper as soon as it exits.
+#[derive(Debug)]
+pub(crate) struct ReapedChild {
+    pid: Pid,
+    exit: oneshot::Receiver<Option<ExitStatus>>,
+    /// The exit status, once received.
+    exited: Option<Option<ExitStatus>>,
+}
+
+/// Locks the registry of children, to spawn a child and register it before
+/// the reaper can look for children again.
+pub(crate) fn spawning() -> Spawning {
+    if let Err(e) = start_reaper() {
+        warn!("Failed to start the reaper: {e}");
+    }
+    Spawning(CHILDREN.lock().expect("children lock"))
+}
+
+impl Spawning {
+    pub fn manage(mut self, pid: Pid) -> ManagedChild {
+        let _ = self.0.managed.insert(pid);
+        ManagedChild(pid)
+    }
+
+    pub fn reap(mut self, pid: Pid) -> ReapedChild {
+        let (tx, exit) = oneshot::channel();
+        let listener: ExitListener = Box::new(move |exit_status| {
+            let _ = tx.send(exit_status);
+        });
+        let reaped =
+            Reaped::Running { listeners: vec![listener], abandoned: false };
+        let _ = self.0.reaped.insert(pid, reaped);
+        ReapedChild { pid, exit, exited: None }
+    }
+}
+
+impl Drop for ManagedChild {
+    fn drop(&mut self) {
+        let _ = CHILDREN.lock().expect("children lock").managed.remove(&self.0);
+    }
+}
+
+impl ReapedChild {
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Returns the [Pid] until the child is seen to exit, like
+    /// [tokio::process::Child::id].
+    pub fn id(&self) -> Option<Pid> {
+        self.exited.is_none().then_some(self.pid)
+    }
+
+    /// Returns the exit status if the child exited, without waiting.
+    /// Fails with ECHILD if it was reaped by someone else.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if self.exited.is_none() {
+            match self.exit.try_recv() {
+                Ok(exit_status) => self.exited = Some(exit_status),
+                Err(oneshot::error::TryRecvError::Empty) => return Ok(None),
+                Err(oneshot::error::TryRecvError::Closed) => {
+                    self.exited = Some(None)
+                }
+            }
+        }
+        exit_status(self.exited.expect("exited")).map(Some)
+    }
+
+    /// Waits for the child to exit. Fails with ECHILD if it was reaped by
+    /// someone else.
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        if self.exited.is_none() {
+            self.exited = Some((&mut self.exit).await.unwrap_or(None));
+        }
+        exit_status(self.exited.expect("exited"))
+    }
+
+    /// Kills the child with SIGKILL, unless it was reaped already, so that
+    /// its pid can't have been reused.
+    pub fn kill(&self) -> io::Result<()> {
+        let children = CHILDREN.lock().expect("children lock");
+        if let Some(Reaped::Running { .. }) = children.reaped.get(&self.pid) {
+            // SAFETY: the child is not reaped while the lock is held.
+            if unsafe { libc::kill(self.pid.as_raw(), libc::SIGKILL) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `listener` with the exit status of the child once it is reaped,
+    /// or right away if it was reaped already. The listener is called with
+    /// the registry of children locked, so it must neither block nor spawn
+    /// children.
+    pub fn on_exit(
+        &self,
+        listener: impl FnOnce(Option<ExitStatus>) + Send + 'static,
+    ) {
+        let mut children = CHILDREN.lock().expect("children lock");
+        match children.reaped.get_mut(&self.pid) {
+            Some(Reaped::Running { listeners, .. }) => {
+                listeners.push(Box::new(listener))
+            }
+            Some(Reaped::Exited(exit_status)) => listener(*exit_status),
+            None => listener(None),
+        }
+    }
+}
+
+impl Drop for ReapedChild {
+    /// A child still running is reaped once it exits all the same.
+    fn drop(&mut self) {
+        let mut children = CHILDREN.lock().expect("children lock");
+        match children.reaped.get_mut(&self.pid) {
+            Some(Reaped::Running { abandoned, .. }) => *abandoned = true,
+            Some(Reaped::Exited(_)) => {
+                let _ = children.reaped.remove(&self.pid);
+            }
+            None => {}
+        }
+    }
+}
+
+fn exit_status(exit_status: Option<ExitStatus>) -> io::Result<ExitStatus> {
+    exit_status.ok_or_else(|| io::Error::from_raw_os_error(libc::ECHILD))
+}
+
+/// Opens a pidfd referring to `pid`, which, unlike the pid, can't come to
+/// refer to another process once it exits.
+pub(crate) fn pidfd_open(pid: Pid) -> io::Result<OwnedFd> {
+    // SAFETY: pidfd_open takes no pointers.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_raw(), 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the fd was just opened, and nothing else owns it.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Reaps the exited children of auraed which are not registered as well,
+/// whenever a child exits, for as long as auraed runs.
+///
+/// Unless auraed is pid 1, it is made a child subreaper first, so orphans are
+/// re-parented to it rather than to init.
+pub(crate) fn start() -> io::Result<()> {
+    if std::process::id() != 1 {
+        // SAFETY: PR_SET_CHILD_SUBREAPER only sets an attribute of auraed.
+        let res =
+            unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    REAP_ORPHANS.store(true, Ordering::Relaxed);
+    info!("Reaping orphaned processes");
+    start_reaper()
+}
+
+/// Starts the reaper, once, on a thread of its own, so that it outlives the
+/// runtime of whichever task spawns the first child.
+fn start_reaper() -> io::Result<()> {
+    static STARTED: OnceCell<()> = OnceCell::new();
+
+    let _ = STARTED.get_or_try_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        // Listened for before returning, so no exit is missed
+        let mut sigchld = {
+            let _entered = runtime.enter();
+            signal(SignalKind::child())?
+        };
+
+        let _ = std::thread::Builder::new().name("reaper".into()).spawn(
+            move || {
+                runtime.block_on(async move {
+                    loop {
+                        reap_children();
+
+                        tokio::select! {
+                            _ = sigchld.recv() => {}
+                            _ = tokio::time::sleep(REAP_INTERVAL) => {}
+                        }
+                    }
+                })
+            },
+        )?;
+        Ok::<_, io::Error>(())
+    })?;
+    Ok(())
+}
+
+/// Reaps the registered children which exited, and the orphans if auraed
+/// reaps them.
+fn reap_children() {
+    let mut children = CHILDREN.lock().expect("children lock");
+    reap_exited(&mut children);
+
+    if REAP_ORPHANS.load(Ordering::Relaxed) {
+        reap_orphans(&children, next_exited);
+    }
+}
+
+/// Reaps the reaped children which exited, and notifies their listeners.
+/// Each is waited on by its pid, so the children waited on by their owner,
+/// or by tokio, are left alone.
+fn reap_exited(children: &mut Children) {
+    children.reaped.retain(|pid, reaped| {
+        let Reaped::Running { listeners, abandoned } = reaped else {
+            return true;
+        };
+
+        let mut status = 0;
+        // SAFETY: waits on a single child, without blocking.
+        let res =
+            unsafe { libc::waitpid(pid.as_raw(), &mut status, libc::WNOHANG) };
+        let exit_status = match res {
+            0 => return true,
+            -1 => {
+                warn!(
+                    "Failed to reap child {pid}: {}",
+                    io::Error::last_os_error()
+                );
+                None
+            }
+            _ => Some(ExitStatus::from_raw(status)),
+        };
+        trace!("Reaped child {pid}");
+
+        for listener in std::mem::take(listeners) {
+            listener(exit_status);
+        }
+        if *abandoned {
+            return false;
+        }
+        *reaped = Reaped::Exited(exit_status);
+        true
+    });
+}
+
+/// Reaps the exited children which are not registered, for as long as the
+/// next exited child, as peeked at by `next_exited`, is one of them. A
+/// managed child which exited holds up the orphans after it until its owner
+/// waits on it. None is being spawned, as the registry is locked.
+fn reap_orphans(
+    children: &Children,
+    mut next_exited: impl FnMut() -> Option<Pid>,
+) {
+    while let Some(pid) = next_exited() {
+        if children.managed.contains(&pid) || children.reaped.contains_key(&pid)
+        {
+            return;
+        }
+
+        let mut status = 0;
+        // SAFETY: waits on a single child, without blocking.
+        let res =
+            unsafe { libc::waitpid(pid.as_raw(), &mut status, libc::WNOHANG) };
+        match res {
+            -1 => {
+                warn!(
+                    "Failed to reap orphan {pid}: {}",
+                    io::Error::last_os_error()
+                );
+                return;
+            }
+            0 => return,
+            _ => trace!("Reaped orphan {pid}"),
+        }
+    }
+}
+
+/// Peeks at the next child of auraed which exited and was not waited on yet,
+/// leaving it to be waited on.
+fn next_exited() -> Option<Pid> {
+    // SAFETY: siginfo_t is plain data, for which zeroes are valid.
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // SAFETY: waitid writes to `info` only, and WNOWAIT leaves the child
+    // waitable.
+    let res = unsafe {
+        libc::waitid(
+            libc::P_ALL,
+            0,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    if res == -1 {
+        let e = io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::ECHILD) {
+            warn!("Failed to look for exited children: {e}");
+        }
+        return None;
+    }
+
+    // SAFETY: waitid succeeded, so `info` holds a SIGCHLD, or zeroes if no
+    // child exited.
+    let pid = unsafe { info.si_pid() };
+    (pid != 0).then(|| Pid::from_raw(pid))
+}
+
+#[cfg(test)]
+// The tests don't reap orphans, so they may spawn children of their own
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn wait_until_exited(pid: Pid) {
+        let process = procfs::process::Process::new(pid.as_raw()).unwrap();
+        while process.stat().unwrap().state != 'Z' {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_reap_orphans_leaves_managed_children_to_their_owner() {
+        let spawning = spawning();
+        let mut orphan = Command::new("true").spawn().unwrap();
+        let orphan_pid = Pid::from_raw(orphan.id() as i32);
+
+        let mut owned = Command::new("true").spawn().unwrap();
+        let owned_pid = Pid::from_raw(owned.id() as i32);
+        let managed = spawning.manage(owned_pid);
+
+        let mut later = Command::new("true").spawn().unwrap();
+        let later_pid = Pid::from_raw(later.id() as i32);
+
+        wait_until_exited(orphan_pid);
+        wait_until_exited(owned_pid);
+        wait_until_exited(later_pid);
+        // Peeks at the children of the test only, as other tests spawn some
+        let mut exited = [orphan_pid, owned_pid, later_pid].into_iter();
+        reap_orphans(&CHILDREN.lock().unwrap(), || exited.next());
+
+        // The orphan was reaped
+        assert_eq!(
+            orphan.try_wait().unwrap_err().raw_os_error(),
+            Some(libc::ECHILD)
+        );
+
+        // The managed child is still there for its owner to wait on, and
+        // holds up the children which exited after it
+        assert!(owned.wait().unwrap().success());
+        drop(managed);
+        assert!(later.wait().unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn test_reaped_child_is_notified_of_its_exit() {
+        let spawning = spawning();
+        let child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+        let mut reaped = spawning.reap(Pid::from_raw(child.id() as i32));
+
+        let exit_status = reaped.wait().await.unwrap();
+        assert_eq!(exit_status.code(), Some(3));
+        assert_eq!(reaped.try_wait().unwrap(), Some(exit_status));
+        assert_eq!(reaped.id(), None);
+
+        // Listeners added after the exit are called right away
+        let (tx, rx) = std::sync::mpsc::channel();
+        reaped.on_exit(move |exit_status| tx.send(exit_status).unwrap());
+        assert_eq!(rx.try_recv().unwrap(), Some(exit_status));
+
+        // Killing a reaped child doesn't signal a reused pid
+        reaped.kill().unwrap();
+    }
+}
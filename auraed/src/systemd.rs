@@ -0,0 +1,272 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Running auraed as a systemd service with `Type=notify`, and with socket
+//! activation, so its sockets are held across restarts. Both are optional,
+//! and only used if systemd set the variables of sd_listen_fds(3) and
+//! sd_notify(3) in the environment of auraed.
+
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use once_cell::sync::OnceCell;
+use std::{
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// The first of the sockets passed by systemd.
+const LISTEN_FDS_START: RawFd = 3;
+
+static NOTIFIER: OnceCell<Option<Notifier>> = OnceCell::new();
+
+/// Sends state changes of auraed to systemd.
+#[derive(Debug)]
+struct Notifier {
+    socket: SocketAddr,
+    /// How often systemd expects `WATCHDOG=1`, if at all.
+    watchdog: Option<Duration>,
+}
+
+/// Takes the sockets systemd passed to auraed, if it was socket activated.
+///
+/// The variables describing the sockets are removed from the environment, so
+/// the nested auraeds and executables auraed starts don't take them as well.
+pub(crate) fn take_listen_fds() -> io::Result<Vec<OwnedFd>> {
+    let fds = listen_fds(|name| std::env::var(name).ok(), LISTEN_FDS_START);
+    for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(name);
+    }
+
+    fds
+}
+
+fn listen_fds(
+    var: impl Fn(&str) -> Option<String>,
+    first: RawFd,
+) -> io::Result<Vec<OwnedFd>> {
+    let (Some(pid), Some(count)) = (var("LISTEN_PID"), var("LISTEN_FDS"))
+    else {
+        return Ok(vec![]);
+    };
+
+    // The sockets were passed to a process which started us
+    if pid.parse::<u32>().ok() != Some(std::process::id()) {
+        return Ok(vec![]);
+    }
+
+    let count: RawFd = count.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("LISTEN_FDS is not a number of sockets: '{count}'"),
+        )
+    })?;
+
+    (first..first + count)
+        .map(|fd| {
+            // SAFETY: systemd passed the fd to auraed, and it is taken once
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            // Keep the processes auraed starts from inheriting the socket
+            let _ =
+                fcntl(fd.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+            Ok(fd)
+        })
+        .collect()
+}
+
+/// Reads where to notify systemd, if at all. The variables are removed from
+/// the environment, so the processes auraed starts don't notify in its name.
+pub(crate) fn init() {
+    let notifier = Notifier::new(|name| std::env::var(name).ok());
+    for name in ["NOTIFY_SOCKET", "WATCHDOG_USEC", "WATCHDOG_PID"] {
+        std::env::remove_var(name);
+    }
+
+    match notifier {
+        Ok(notifier) => {
+            let _ = NOTIFIER.set(notifier);
+        }
+        Err(e) => warn!("Not notifying systemd: {e}"),
+    }
+}
+
+/// Sends `state` to systemd, e.g. `READY=1`. Does nothing unless auraed runs
+/// as a `Type=notify` systemd service.
+pub(crate) fn notify(state: &str) {
+    let Some(Some(notifier)) = NOTIFIER.get() else {
+        return;
+    };
+
+    if let Err(e) = notifier.notify(state) {
+        warn!("Failed to notify systemd of {state}: {e}");
+    }
+}
+
+/// Sends `WATCHDOG=1` to systemd at twice the rate it expects, if its
+/// watchdog is enabled, for as long as auraed runs.
+pub(crate) fn start_watchdog() {
+    let Some(Some(Notifier { watchdog: Some(watchdog), .. })) = NOTIFIER.get()
+    else {
+        return;
+    };
+
+    info!("Notifying the systemd watchdog every {:?}", *watchdog / 2);
+    let mut interval = tokio::time::interval(*watchdog / 2);
+    let _ignored = tokio::spawn(async move {
+        loop {
+            let _ = interval.tick().await;
+            notify("WATCHDOG=1");
+        }
+    });
+}
+
+impl Notifier {
+    fn new(var: impl Fn(&str) -> Option<String>) -> io::Result<Option<Self>> {
+        let Some(socket) = var("NOTIFY_SOCKET") else {
+            return Ok(None);
+        };
+
+        let socket = match socket.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(socket)?,
+        };
+
+        // The watchdog may be meant for a process which started us
+        let watchdog_pid = var("WATCHDOG_PID").map(|pid| pid.parse::<u32>());
+        let watchdog = match watchdog_pid {
+            Some(Ok(pid)) if pid != std::process::id() => None,
+            _ => var("WATCHDOG_USEC")
+                .and_then(|usec| usec.parse().ok())
+                .filter(|usec| *usec > 0)
+                .map(Duration::from_micros),
+        };
+
+        Ok(Some(Self { socket, watchdog }))
+    }
+
+    fn notify(&self, state: &str) -> io::Result<()> {
+        let socket = UnixDatagram::unbound()?;
+        let _ = socket.send_to_addr(state.as_bytes(), &self.socket)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::{adopt_socket_stream, SocketStream};
+    use std::{collections::HashMap, os::fd::IntoRawFd};
+
+    fn env(vars: &[(&str, String)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[tokio::test]
+    async fn test_listen_fds_adopts_unix_and_tcp_sockets() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-systemd-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        let vars = env(&[
+            ("LISTEN_PID", std::process::id().to_string()),
+            ("LISTEN_FDS", "1".into()),
+        ]);
+
+        let unix =
+            std::os::unix::net::UnixListener::bind(dir.join("aurae.sock"))
+                .unwrap();
+        let fds = listen_fds(&vars, unix.into_raw_fd()).unwrap();
+        assert_eq!(fds.len(), 1);
+        let flags = fcntl(fds[0].as_raw_fd(), FcntlArg::F_GETFD).unwrap();
+        assert!(FdFlag::from_bits_truncate(flags).contains(FdFlag::FD_CLOEXEC));
+        let fd = fds.into_iter().next().unwrap();
+        let stream = adopt_socket_stream(fd).unwrap();
+        assert!(matches!(stream, SocketStream::Unix(_)));
+        // Served without TLS, so only with an authorization policy
+        let listener = stream.listener_config().unwrap();
+        assert!(listener.check_authorized(true, false).is_err());
+        assert!(listener.check_authorized(true, true).is_ok());
+
+        let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let fds = listen_fds(&vars, tcp.into_raw_fd()).unwrap();
+        let fd = fds.into_iter().next().unwrap();
+        let stream = adopt_socket_stream(fd).unwrap();
+        assert!(matches!(stream, SocketStream::Tcp(_)));
+        let listener = stream.listener_config().unwrap();
+        assert!(listener.check_authorized(false, false).is_err());
+        assert!(listener.check_authorized(true, false).is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_listen_fds_ignores_sockets_of_another_process() {
+        let fds = listen_fds(
+            env(&[
+                ("LISTEN_PID", (std::process::id() + 1).to_string()),
+                ("LISTEN_FDS", "1".into()),
+            ]),
+            LISTEN_FDS_START,
+        )
+        .unwrap();
+        assert!(fds.is_empty());
+
+        assert!(listen_fds(env(&[]), LISTEN_FDS_START).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_notifier_sends_state_to_notify_socket() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-systemd-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("notify.sock");
+        let socket = UnixDatagram::bind(&path).unwrap();
+
+        let notifier = Notifier::new(env(&[
+            ("NOTIFY_SOCKET", path.display().to_string()),
+            ("WATCHDOG_USEC", "2000000".into()),
+        ]))
+        .unwrap()
+        .expect("notifier");
+        assert_eq!(notifier.watchdog, Some(Duration::from_secs(2)));
+
+        notifier.notify("READY=1").unwrap();
+        let mut buf = [0; 16];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_notifier_ignores_watchdog_of_another_process() {
+        let notifier = Notifier::new(env(&[
+            ("NOTIFY_SOCKET", "@aurae-test".into()),
+            ("WATCHDOG_USEC", "2000000".into()),
+            ("WATCHDOG_PID", (std::process::id() + 1).to_string()),
+        ]))
+        .unwrap()
+        .expect("notifier");
+        assert_eq!(notifier.watchdog, None);
+
+        assert!(Notifier::new(env(&[])).unwrap().is_none());
+    }
+}
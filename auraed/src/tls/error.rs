@@ -0,0 +1,48 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use thiserror::Error;
+use tokio_rustls::rustls::{
+    self, pki_types::pem, server::VerifierBuilderError,
+};
+use x509_certificate::X509CertificateError;
+
+pub(crate) type Result<T> = std::result::Result<T, TlsError>;
+
+#[derive(Debug, Error)]
+pub(crate) enum TlsError {
+    #[error("failed to read '{}': {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("'{}' is not a valid PEM file: {reason:?}", path.display())]
+    InvalidPem { path: PathBuf, reason: pem::Error },
+    #[error("'{}' contains no certificate", path.display())]
+    NoCertificate { path: PathBuf },
+    #[error("'{}' is not a valid X509 certificate: {source}", path.display())]
+    InvalidCertificate { path: PathBuf, source: X509CertificateError },
+    #[error("the certificate '{}' expired at {not_after}", path.display())]
+    Expired { path: PathBuf, not_after: DateTime<Utc> },
+    #[error("the key '{}' does not match the certificate '{}'", key.display(), certificate.display())]
+    KeyMismatch { key: PathBuf, certificate: PathBuf },
+    #[error("'{}' is not a valid CA certificate: {source}", path.display())]
+    InvalidCa { path: PathBuf, source: rustls::Error },
+    #[error("invalid client CA: {0}")]
+    ClientVerifier(#[from] VerifierBuilderError),
+    #[error("invalid TLS material: {0}")]
+    Rustls(#[from] rustls::Error),
+    #[error("auraed does not serve TLS")]
+    NotServed,
+}
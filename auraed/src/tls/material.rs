@@ -0,0 +1,218 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::{Result, TlsError};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_rustls::rustls::{
+    crypto::{self, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    sign::CertifiedKey,
+    Error, InconsistentKeys, RootCertStore, ServerConfig,
+};
+use x509_certificate::X509Certificate;
+
+/// The protocol negotiated with the clients, which only speak gRPC.
+const ALPN_H2: &[u8] = b"h2";
+
+/// The files the TLS material is loaded from.
+#[derive(Debug, Clone)]
+pub(crate) struct TlsPaths {
+    pub server_crt: PathBuf,
+    pub server_key: PathBuf,
+    /// The CA the certificates of the clients are verified with.
+    pub ca_crt: PathBuf,
+}
+
+impl TlsPaths {
+    pub fn iter(&self) -> impl Iterator<Item = &Path> {
+        [&self.server_crt, &self.server_key, &self.ca_crt]
+            .into_iter()
+            .map(PathBuf::as_path)
+    }
+}
+
+/// The contents of the files of the TLS material, to tell whether they
+/// changed since they were loaded.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TlsFiles {
+    server_crt: Vec<u8>,
+    server_key: Vec<u8>,
+    ca_crt: Vec<u8>,
+}
+
+impl TlsFiles {
+    pub fn read(paths: &TlsPaths) -> Result<Self> {
+        let read = |path: &Path| {
+            std::fs::read(path)
+                .map_err(|source| TlsError::Read { path: path.into(), source })
+        };
+
+        Ok(Self {
+            server_crt: read(&paths.server_crt)?,
+            server_key: read(&paths.server_key)?,
+            ca_crt: read(&paths.ca_crt)?,
+        })
+    }
+}
+
+/// Validated TLS material, which new connections are handshaken with.
+#[derive(Debug)]
+pub(crate) struct TlsMaterial {
+    pub files: TlsFiles,
+    pub config: Arc<ServerConfig>,
+    /// When the server certificate expires.
+    pub not_after: DateTime<Utc>,
+}
+
+impl TlsMaterial {
+    pub fn load(paths: &TlsPaths) -> Result<Self> {
+        Self::new(TlsFiles::read(paths)?, paths)
+    }
+
+    /// Validates the material of `files`, read from `paths`: the server
+    /// certificate must not be expired, and the key must match it.
+    pub fn new(files: TlsFiles, paths: &TlsPaths) -> Result<Self> {
+        let provider = Arc::new(crypto::ring::default_provider());
+
+        let certs = certificates(&files.server_crt, &paths.server_crt)?;
+        let leaf =
+            X509Certificate::from_der(certs[0].as_ref()).map_err(|source| {
+                TlsError::InvalidCertificate {
+                    path: paths.server_crt.clone(),
+                    source,
+                }
+            })?;
+        let not_after = leaf.validity_not_after();
+        if not_after <= Utc::now() {
+            return Err(TlsError::Expired {
+                path: paths.server_crt.clone(),
+                not_after,
+            });
+        }
+
+        let key = PrivateKeyDer::from_pem_slice(&files.server_key).map_err(
+            |reason| TlsError::InvalidPem {
+                path: paths.server_key.clone(),
+                reason,
+            },
+        )?;
+        check_keys_match(&certs, &key, &provider).map_err(|e| match e {
+            Error::InconsistentKeys(InconsistentKeys::KeyMismatch) => {
+                TlsError::KeyMismatch {
+                    key: paths.server_key.clone(),
+                    certificate: paths.server_crt.clone(),
+                }
+            }
+            e => e.into(),
+        })?;
+
+        let mut roots = RootCertStore::empty();
+        for ca in certificates(&files.ca_crt, &paths.ca_crt)? {
+            roots.add(ca).map_err(|source| TlsError::InvalidCa {
+                path: paths.ca_crt.clone(),
+                source,
+            })?;
+        }
+        let verifier = WebPkiClientVerifier::builder_with_provider(
+            Arc::new(roots),
+            provider.clone(),
+        )
+        .build()?;
+
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![ALPN_H2.to_vec()];
+
+        Ok(Self { files, config: Arc::new(config), not_after })
+    }
+}
+
+/// The certificates of the PEM file read from `path`, of which there must be
+/// at least one.
+fn certificates(
+    pem: &[u8],
+    path: &Path,
+) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|reason| TlsError::InvalidPem { path: path.into(), reason })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificate { path: path.into() });
+    }
+
+    Ok(certs)
+}
+
+/// Whether the public key of the certificate of `certs` is that of `key`.
+/// Keys whose public key the provider cannot tell are assumed to match.
+fn check_keys_match(
+    certs: &[CertificateDer<'static>],
+    key: &PrivateKeyDer<'static>,
+    provider: &CryptoProvider,
+) -> std::result::Result<(), Error> {
+    let key = provider.key_provider.load_private_key(key.clone_key())?;
+    match CertifiedKey::new(certs.to_vec(), key).keys_match() {
+        Err(Error::InconsistentKeys(InconsistentKeys::Unknown)) => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_paths() -> TlsPaths {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("failed to create test dir");
+
+        TlsPaths {
+            server_crt: dir.join("server.crt"),
+            server_key: dir.join("server.key"),
+            ca_crt: dir.join("ca.crt"),
+        }
+    }
+
+    #[test]
+    fn test_load_fails_for_missing_files() {
+        let paths = test_paths();
+
+        assert!(matches!(
+            TlsMaterial::load(&paths),
+            Err(TlsError::Read { path, .. }) if path == paths.server_crt
+        ));
+    }
+
+    #[test]
+    fn test_load_fails_without_certificate() {
+        let paths = test_paths();
+        for path in paths.iter() {
+            std::fs::write(path, "not a certificate\n")
+                .expect("failed to write test file");
+        }
+
+        let files = TlsFiles::read(&paths).expect("failed to read files");
+        assert_eq!(files, TlsFiles::read(&paths).unwrap());
+        assert!(matches!(
+            TlsMaterial::new(files, &paths),
+            Err(TlsError::NoCertificate { path }) if path == paths.server_crt
+        ));
+    }
+}
@@ -0,0 +1,56 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The TLS material auraed serves its socket and TCP listeners with: the
+//! server certificate and key, and the CA the clients are verified with.
+//!
+//! The material is reloaded from its files as they change, on SIGHUP, and
+//! with the `ReloadTls` call of the observe service, e.g. where watching the
+//! files is unreliable. New connections are handshaken with the material
+//! loaded last, while established connections keep theirs. Material that
+//! fails validation is not loaded, and the current material keeps being
+//! served.
+
+pub(crate) use error::{Result, TlsError};
+pub(crate) use material::TlsPaths;
+pub(crate) use server_tls::{ReloadTls, ServerTls, TlsStatus};
+
+use once_cell::sync::OnceCell;
+
+mod error;
+mod material;
+mod server_tls;
+mod watch;
+
+static SERVER_TLS: OnceCell<ServerTls> = OnceCell::new();
+
+/// Loads the TLS material auraed serves, and reloads it from there on.
+pub(crate) fn init(paths: TlsPaths) -> Result<&'static ServerTls> {
+    let mut initialized = false;
+    let server_tls = SERVER_TLS.get_or_try_init(|| {
+        initialized = true;
+        ServerTls::load(paths)
+    })?;
+    if initialized {
+        watch::start(server_tls);
+    }
+
+    Ok(server_tls)
+}
+
+/// Returns the TLS material auraed serves, if it serves TLS.
+pub(crate) fn get() -> Result<&'static ServerTls> {
+    SERVER_TLS.get().ok_or(TlsError::NotServed)
+}
@@ -0,0 +1,194 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::material::{TlsFiles, TlsMaterial, TlsPaths};
+use super::Result;
+use crate::blocking::{BlockingJob, Pool};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::{rustls::ServerConfig, server::TlsStream, TlsAcceptor};
+use tracing::{debug, error, info};
+
+/// How long a client is given to complete its TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many TLS handshakes of a listener may be in progress at once.
+const MAX_CONCURRENT_HANDSHAKES: usize = 64;
+
+/// The TLS material auraed serves, replaced as it is reloaded.
+#[derive(Debug)]
+pub(crate) struct ServerTls {
+    paths: TlsPaths,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    material: TlsMaterial,
+    loaded_at: SystemTime,
+    reloads: u64,
+    failed_reloads: u64,
+    last_error: Option<String>,
+}
+
+/// The TLS material auraed serves, and how its reloads went.
+#[derive(Debug, Clone)]
+pub(crate) struct TlsStatus {
+    pub not_after: DateTime<Utc>,
+    pub loaded_at: SystemTime,
+    pub reloads: u64,
+    pub failed_reloads: u64,
+    pub last_error: Option<String>,
+}
+
+impl ServerTls {
+    pub fn load(paths: TlsPaths) -> Result<Self> {
+        let material = TlsMaterial::load(&paths)?;
+        info!(
+            "Loaded TLS certificate '{}', valid until {}",
+            paths.server_crt.display(),
+            material.not_after
+        );
+
+        Ok(Self {
+            paths,
+            state: Mutex::new(State {
+                material,
+                loaded_at: SystemTime::now(),
+                reloads: 0,
+                failed_reloads: 0,
+                last_error: None,
+            }),
+        })
+    }
+
+    pub fn paths(&self) -> &TlsPaths {
+        &self.paths
+    }
+
+    /// Reloads the material from its files, unless they are unchanged and
+    /// the reload is not `forced`. Returns whether the material was
+    /// replaced. Material that fails validation is not loaded, and the
+    /// current material keeps being served.
+    pub fn reload(&self, forced: bool) -> Result<bool> {
+        let mut state = self.state.lock().expect("server tls lock");
+
+        let material = TlsFiles::read(&self.paths).and_then(|files| {
+            if !forced && files == state.material.files {
+                return Ok(None);
+            }
+            TlsMaterial::new(files, &self.paths).map(Some)
+        });
+
+        match material {
+            Ok(None) => Ok(false),
+            Ok(Some(material)) => {
+                info!(
+                    "Reloaded TLS certificate '{}', valid until {}",
+                    self.paths.server_crt.display(),
+                    material.not_after
+                );
+                state.material = material;
+                state.loaded_at = SystemTime::now();
+                state.reloads += 1;
+                state.last_error = None;
+                Ok(true)
+            }
+            Err(e) => {
+                error!(
+                    "Failed to reload the TLS material, still serving the certificate valid until {}: {e}",
+                    state.material.not_after
+                );
+                state.failed_reloads += 1;
+                state.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn status(&self) -> TlsStatus {
+        let state = self.state.lock().expect("server tls lock");
+        TlsStatus {
+            not_after: state.material.not_after,
+            loaded_at: state.loaded_at,
+            reloads: state.reloads,
+            failed_reloads: state.failed_reloads,
+            last_error: state.last_error.clone(),
+        }
+    }
+
+    fn config(&self) -> Arc<ServerConfig> {
+        self.state.lock().expect("server tls lock").material.config.clone()
+    }
+
+    /// Handshakes each connection of `incoming` with the material loaded
+    /// last, once it is accepted. The connections whose handshake fails are
+    /// dropped.
+    pub fn accept<I, IO, IE>(
+        &'static self,
+        incoming: I,
+    ) -> impl Stream<Item = std::result::Result<TlsStream<IO>, IE>>
+    where
+        I: Stream<Item = std::result::Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        incoming
+            .map(move |io| async move {
+                let io = match io {
+                    Ok(io) => io,
+                    Err(e) => return Some(Err(e)),
+                };
+
+                let acceptor = TlsAcceptor::from(self.config());
+                match tokio::time::timeout(
+                    HANDSHAKE_TIMEOUT,
+                    acceptor.accept(io),
+                )
+                .await
+                {
+                    Ok(Ok(stream)) => Some(Ok(stream)),
+                    Ok(Err(e)) => {
+                        debug!("TLS handshake failed: {e}");
+                        None
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake timed out");
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(MAX_CONCURRENT_HANDSHAKES)
+            .filter_map(std::future::ready)
+    }
+}
+
+/// Reloads the material of a [ServerTls] on the [Pool::Crypto] pool, as
+/// its certificates are read and verified. See [ServerTls::reload].
+pub(crate) struct ReloadTls {
+    pub server_tls: &'static ServerTls,
+    pub forced: bool,
+}
+
+impl BlockingJob for ReloadTls {
+    type Output = Result<bool>;
+    const POOL: Pool = Pool::Crypto;
+
+    fn run(self) -> Self::Output {
+        self.server_tls.reload(self.forced)
+    }
+}
@@ -0,0 +1,107 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Reloads the TLS material as its files change, and on SIGHUP.
+
+use super::{ReloadTls, ServerTls};
+use crate::blocking;
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use std::collections::BTreeSet;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd};
+use std::path::Path;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::warn;
+
+/// How long the files are left to settle after they change, before they
+/// are reloaded, as a certificate and its key are replaced one at a time.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+pub(super) fn start(server_tls: &'static ServerTls) {
+    reload_on_sighup(server_tls);
+    if let Err(e) = reload_on_change(server_tls) {
+        warn!("failed to watch the TLS files, they are only reloaded on SIGHUP: {e}");
+    }
+}
+
+fn reload_on_sighup(server_tls: &'static ServerTls) {
+    let Ok(mut sighup) = signal(SignalKind::hangup()) else {
+        warn!("failed to listen for SIGHUP, the TLS material won't be reloaded on SIGHUP");
+        return;
+    };
+
+    let _ignored = tokio::spawn(async move {
+        while sighup.recv().await.is_some() {
+            // A failed reload is logged, and the current material kept
+            let _ = blocking::run(ReloadTls { server_tls, forced: true }).await;
+        }
+    });
+}
+
+fn reload_on_change(server_tls: &'static ServerTls) -> io::Result<()> {
+    // The directories are watched rather than the files, which are usually
+    // replaced rather than written to, e.g. by renaming or by swapping the
+    // symbolic link of a mounted secret.
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC)?;
+    let directories: BTreeSet<_> = server_tls
+        .paths()
+        .iter()
+        .filter_map(Path::parent)
+        .map(|dir| match dir.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => dir,
+        })
+        .collect();
+    for directory in directories {
+        let _ = inotify.add_watch(
+            directory,
+            AddWatchFlags::IN_CLOSE_WRITE
+                | AddWatchFlags::IN_MOVED_TO
+                | AddWatchFlags::IN_CREATE
+                | AddWatchFlags::IN_DELETE,
+        )?;
+    }
+
+    let _ = std::thread::Builder::new().name("tls-watcher".into()).spawn(
+        move || loop {
+            if let Err(e) = inotify.read_events() {
+                warn!("stopped watching the TLS files: {e}");
+                return;
+            }
+            while is_readable(&inotify, SETTLE_TIME) {
+                let _ = inotify.read_events();
+            }
+
+            // Unchanged files, e.g. of other changes in their directories,
+            // are not reloaded
+            let _ = server_tls.reload(false);
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Waits up to `timeout` for more events.
+fn is_readable(inotify: &Inotify, timeout: Duration) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd: inotify.as_fd().as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+    // SAFETY: pollfd is valid for the duration of the call.
+    unsafe { libc::poll(&mut pollfd, 1, timeout_ms) > 0 }
+}
@@ -13,9 +13,11 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
+use std::path::PathBuf;
 use thiserror::Error;
 use tonic::Status;
 use tracing::error;
+use validation::ValidationError;
 
 use super::virtual_machine::VmID;
 
@@ -23,6 +25,16 @@ pub(crate) type Result<T> = std::result::Result<T, VmServiceError>;
 
 #[derive(Debug, Error)]
 pub(crate) enum VmServiceError {
+    #[error("vm '{id}' already exists")]
+    VmExists { id: VmID },
+    #[error("vm '{id}' not found")]
+    VmNotFound { id: VmID },
+    #[error("vm '{id}' is already running")]
+    VmAlreadyRunning { id: VmID },
+    #[error("vm '{id}' is not running")]
+    VmNotRunning { id: VmID },
+    #[error("vm '{id}' image '{}' does not exist", path.display())]
+    MissingImage { id: VmID, path: PathBuf },
     #[error("vm '{id}' could not be allocated: {source}")]
     FailedToAllocateError { id: VmID, source: anyhow::Error },
     #[error("vm '{id}' could not be freed: {source}")]
@@ -31,10 +43,8 @@ pub(crate) enum VmServiceError {
     FailedToStartError { id: VmID, source: anyhow::Error },
     #[error("vm '{id}' could not be stopped: {source}")]
     FailedToStopError { id: VmID, source: anyhow::Error },
-    #[error("vm config has no machine specified")]
-    MissingMachineConfig,
-    #[error("vm '{id}' config has no root drive specified")]
-    MissingRootDrive { id: VmID },
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
 }
 
 impl From<VmServiceError> for Status {
@@ -42,12 +52,16 @@ impl From<VmServiceError> for Status {
         let msg = err.to_string();
         error!("{msg}");
         match err {
+            VmServiceError::VmExists { .. } => Status::already_exists(msg),
+            VmServiceError::VmNotFound { .. } => Status::not_found(msg),
             VmServiceError::FailedToAllocateError { .. }
             | VmServiceError::FailedToFreeError { .. }
             | VmServiceError::FailedToStartError { .. }
             | VmServiceError::FailedToStopError { .. } => Status::internal(msg),
-            VmServiceError::MissingMachineConfig { .. }
-            | VmServiceError::MissingRootDrive { .. } => {
+            VmServiceError::VmAlreadyRunning { .. }
+            | VmServiceError::VmNotRunning { .. }
+            | VmServiceError::MissingImage { .. }
+            | VmServiceError::ValidationError(_) => {
                 Status::failed_precondition(msg)
             }
         }
@@ -0,0 +1,256 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::Hypervisor;
+use crate::vms::virtual_machine::VmSpec;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+    process::Command,
+};
+use tonic::async_trait;
+
+const API_SOCKET: &str = "api.sock";
+
+/// Runs virtual machines with [Cloud Hypervisor](https://www.cloudhypervisor.org),
+/// controlled through its API socket.
+#[derive(Debug, Clone)]
+pub(crate) struct CloudHypervisor {
+    binary: PathBuf,
+}
+
+impl CloudHypervisor {
+    pub fn new(binary: impl Into<PathBuf>) -> Self {
+        Self { binary: binary.into() }
+    }
+
+    /// Sends a request without a body to the API of the hypervisor, e.g.
+    /// "vm.power-button".
+    async fn put(&self, state_dir: &Path, endpoint: &str) -> io::Result<()> {
+        let mut stream =
+            UnixStream::connect(state_dir.join(API_SOCKET)).await?;
+        stream
+            .write_all(
+                format!(
+                    "PUT /api/v1/{endpoint} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        let mut status_line = String::new();
+        let _ = BufReader::new(stream).read_line(&mut status_line).await?;
+        match status_line.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "{endpoint} failed: {}",
+                status_line.trim()
+            ))),
+        }
+    }
+}
+
+impl Default for CloudHypervisor {
+    /// Runs the `cloud-hypervisor` binary found in the PATH.
+    fn default() -> Self {
+        Self::new("cloud-hypervisor")
+    }
+}
+
+#[async_trait]
+impl Hypervisor for CloudHypervisor {
+    fn command(&self, spec: &VmSpec, state_dir: &Path) -> io::Result<Command> {
+        // The socket of a previous run would keep the hypervisor from
+        // listening on it again.
+        match std::fs::remove_file(state_dir.join(API_SOCKET)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        let mut command = Command::new(&self.binary);
+        let _ = command
+            .arg("--api-socket")
+            .arg(format!("path={}", state_dir.join(API_SOCKET).display()))
+            .arg("--kernel")
+            .arg(&spec.kernel_image_path)
+            .arg("--cmdline")
+            .arg(spec.kernel_args.join(" "))
+            .arg("--cpus")
+            .arg(format!("boot={}", spec.vcpu_count))
+            .arg("--memory")
+            .arg(format!("size={}M", spec.memory_size))
+            .args(["--console", "tty", "--serial", "off"]);
+
+        if !spec.mounts.is_empty() {
+            let _ = command.arg("--disk").args(spec.mounts.iter().map(|m| {
+                format!(
+                    "path={},readonly={}",
+                    m.host_path.display(),
+                    if m.read_only { "on" } else { "off" }
+                )
+            }));
+        }
+
+        if !spec.net.is_empty() {
+            let _ = command.arg("--net").args(spec.net.iter().map(|n| {
+                format!(
+                    "tap={},ip={},mask={},mac={}",
+                    n.tap, n.ip, n.mask, n.mac
+                )
+            }));
+        }
+
+        Ok(command)
+    }
+
+    async fn power_off(&self, state_dir: &Path) -> io::Result<()> {
+        self.put(state_dir, "vm.power-button").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vms::virtual_machine::{MacAddr, MountSpec, NetSpec};
+    use std::net::Ipv4Addr;
+    use tokio::{io::AsyncReadExt, net::UnixListener};
+
+    fn state_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-vm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).expect("create state dir");
+        dir
+    }
+
+    fn spec() -> VmSpec {
+        VmSpec {
+            memory_size: 512,
+            vcpu_count: 2,
+            kernel_image_path: PathBuf::from("/vm/vmlinux.bin"),
+            kernel_args: vec!["console=hvc0".into(), "root=/dev/vda1".into()],
+            mounts: vec![
+                MountSpec {
+                    host_path: PathBuf::from("/vm/disk.raw"),
+                    read_only: false,
+                },
+                MountSpec {
+                    host_path: PathBuf::from("/vm/data.raw"),
+                    read_only: true,
+                },
+            ],
+            net: vec![NetSpec {
+                tap: "auraed-abc123".into(),
+                ip: Ipv4Addr::new(192, 168, 249, 1),
+                mask: Ipv4Addr::new(255, 255, 255, 0),
+                mac: MacAddr([0x02, 0, 0, 0, 0, 0x01]),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_command_boots_the_spec() {
+        let state_dir = state_dir();
+        let api_socket =
+            format!("path={}", state_dir.join(API_SOCKET).display());
+        let command = CloudHypervisor::default()
+            .command(&spec(), &state_dir)
+            .expect("command");
+        let args: Vec<_> = command
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(command.as_std().get_program(), "cloud-hypervisor");
+        assert_eq!(
+            args,
+            [
+                "--api-socket",
+                api_socket.as_str(),
+                "--kernel",
+                "/vm/vmlinux.bin",
+                "--cmdline",
+                "console=hvc0 root=/dev/vda1",
+                "--cpus",
+                "boot=2",
+                "--memory",
+                "size=512M",
+                "--console",
+                "tty",
+                "--serial",
+                "off",
+                "--disk",
+                "path=/vm/disk.raw,readonly=off",
+                "path=/vm/data.raw,readonly=on",
+                "--net",
+                "tap=auraed-abc123,ip=192.168.249.1,mask=255.255.255.0,mac=02:00:00:00:00:01",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_power_off_presses_the_power_button() {
+        let state_dir = state_dir();
+        let listener =
+            UnixListener::bind(state_dir.join(API_SOCKET)).expect("bind");
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+            let mut request = vec![0; 1024];
+            let n = stream.read(&mut request).await.expect("read");
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .expect("write");
+            String::from_utf8_lossy(&request[..n]).to_string()
+        });
+
+        CloudHypervisor::default()
+            .power_off(&state_dir)
+            .await
+            .expect("power off");
+
+        let request = server.await.expect("server");
+        assert!(
+            request.starts_with("PUT /api/v1/vm.power-button HTTP/1.1\r\n"),
+            "{request}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_power_off_fails_if_the_api_does() {
+        let state_dir = state_dir();
+        let listener =
+            UnixListener::bind(state_dir.join(API_SOCKET)).expect("bind");
+        let _server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+            let mut request = vec![0; 1024];
+            let _ = stream.read(&mut request).await.expect("read");
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\n\r\n")
+                .await
+                .expect("write");
+        });
+
+        let err = CloudHypervisor::default()
+            .power_off(&state_dir)
+            .await
+            .expect_err("power off should fail");
+        assert!(err.to_string().contains("500"), "{err}");
+    }
+}
@@ -0,0 +1,38 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The hypervisors virtual machines run in, each in a process of its own.
+
+pub(crate) use cloud_hypervisor::CloudHypervisor;
+
+use super::virtual_machine::VmSpec;
+use std::{fmt::Debug, io, path::Path};
+use tokio::process::Command;
+use tonic::async_trait;
+
+mod cloud_hypervisor;
+
+/// A hypervisor backend, e.g. [CloudHypervisor].
+#[async_trait]
+pub(crate) trait Hypervisor: Debug + Send + Sync {
+    /// Returns the command that boots the machine of `spec`. The console of
+    /// the guest must be written to its stdout, and any control socket kept
+    /// in `state_dir`.
+    fn command(&self, spec: &VmSpec, state_dir: &Path) -> io::Result<Command>;
+
+    /// Asks the guest of the hypervisor running from `state_dir` to power
+    /// off. The hypervisor is expected to exit once it has.
+    async fn power_off(&self, state_dir: &Path) -> io::Result<()>;
+}
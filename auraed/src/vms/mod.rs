@@ -14,9 +14,11 @@
 \* -------------------------------------------------------------------------- */
 
 mod error;
-mod manager;
+mod hypervisor;
+mod validation;
 mod virtual_machine;
 mod virtual_machines;
 mod vm_service;
 
+pub(crate) use hypervisor::CloudHypervisor;
 pub(crate) use vm_service::VmService;
@@ -0,0 +1,312 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use super::virtual_machine::{MountSpec, VmID};
+use proto::vms::{
+    DriveMount, RootDrive, VirtualMachine, VmServiceAllocateRequest,
+    VmServiceFreeRequest, VmServiceStartRequest, VmServiceStatusRequest,
+    VmServiceStopRequest,
+};
+use std::path::PathBuf;
+use validation::{ValidatedType, ValidationError};
+use validation_macros::ValidatedType;
+
+/// The maximum number of vCPUs of a VM, as supported by Cloud Hypervisor.
+const MAX_VCPU_COUNT: u32 = 254;
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVmServiceAllocateRequest {
+    #[field_type(Option<VirtualMachine>)]
+    pub machine: ValidatedVirtualMachine,
+}
+
+impl VmServiceAllocateRequestTypeValidator
+    for VmServiceAllocateRequestValidator
+{
+    fn validate_machine(
+        machine: Option<VirtualMachine>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<ValidatedVirtualMachine, ValidationError> {
+        let machine = validation::required(machine, field_name, parent_name)?;
+
+        ValidatedVirtualMachine::validate(
+            machine,
+            Some(&validation::field_name(field_name, parent_name)),
+        )
+    }
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVirtualMachine {
+    #[field_type(String)]
+    #[validate(create)]
+    pub id: VmID,
+    #[field_type(u32)]
+    pub mem_size_mb: u32,
+    #[field_type(u32)]
+    pub vcpu_count: u32,
+    #[field_type(String)]
+    pub kernel_img_path: PathBuf,
+    #[validate(none)]
+    pub kernel_args: Vec<String>,
+    #[field_type(Option<RootDrive>)]
+    pub root_drive: MountSpec,
+    #[field_type(Vec<DriveMount>)]
+    pub drive_mounts: Vec<MountSpec>,
+    /// Ignored, as the address is only known once the VM is started.
+    #[allow(unused)]
+    #[validate(none)]
+    pub auraed_address: String,
+}
+
+impl VirtualMachineTypeValidator for VirtualMachineValidator {
+    fn validate_mem_size_mb(
+        mem_size_mb: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<u32, ValidationError> {
+        validation::minimum_value(
+            mem_size_mb,
+            1,
+            "MiB",
+            field_name,
+            parent_name,
+        )?;
+        Ok(mem_size_mb)
+    }
+
+    fn validate_vcpu_count(
+        vcpu_count: u32,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<u32, ValidationError> {
+        validation::minimum_value(
+            vcpu_count,
+            1,
+            "vCPUs",
+            field_name,
+            parent_name,
+        )?;
+        validation::maximum_value(
+            vcpu_count,
+            MAX_VCPU_COUNT,
+            "vCPUs",
+            field_name,
+            parent_name,
+        )?;
+        Ok(vcpu_count)
+    }
+
+    fn validate_kernel_img_path(
+        kernel_img_path: String,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<PathBuf, ValidationError> {
+        validate_image_path(kernel_img_path, field_name, parent_name)
+    }
+
+    fn validate_root_drive(
+        root_drive: Option<RootDrive>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<MountSpec, ValidationError> {
+        let root_drive =
+            validation::required(root_drive, field_name, parent_name)?;
+        let field_name = validation::field_name(field_name, parent_name);
+
+        Ok(MountSpec {
+            host_path: validate_image_path(
+                root_drive.image_path,
+                "image_path",
+                Some(&field_name),
+            )?,
+            read_only: root_drive.read_only,
+        })
+    }
+
+    fn validate_drive_mounts(
+        drive_mounts: Vec<DriveMount>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Vec<MountSpec>, ValidationError> {
+        drive_mounts
+            .into_iter()
+            .enumerate()
+            .map(|(i, mount)| {
+                let field_name = validation::field_name(
+                    &format!("{field_name}[{i}]"),
+                    parent_name,
+                );
+                Ok(MountSpec {
+                    host_path: validate_image_path(
+                        mount.image_path,
+                        "image_path",
+                        Some(&field_name),
+                    )?,
+                    read_only: mount.read_only,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Images are opened by the hypervisor, so their path must not depend on
+/// its working directory.
+fn validate_image_path(
+    path: String,
+    field_name: &str,
+    parent_name: Option<&str>,
+) -> Result<PathBuf, ValidationError> {
+    let path =
+        validation::required_not_empty(Some(path), field_name, parent_name)?;
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return Err(ValidationError::Invalid {
+            field: validation::field_name(field_name, parent_name),
+        });
+    }
+    Ok(path)
+}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVmServiceFreeRequest {
+    #[field_type(String)]
+    #[validate]
+    pub vm_id: VmID,
+}
+
+impl VmServiceFreeRequestTypeValidator for VmServiceFreeRequestValidator {}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVmServiceStartRequest {
+    #[field_type(String)]
+    #[validate]
+    pub vm_id: VmID,
+}
+
+impl VmServiceStartRequestTypeValidator for VmServiceStartRequestValidator {}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVmServiceStopRequest {
+    #[field_type(String)]
+    #[validate]
+    pub vm_id: VmID,
+}
+
+impl VmServiceStopRequestTypeValidator for VmServiceStopRequestValidator {}
+
+#[derive(Debug, ValidatedType)]
+pub struct ValidatedVmServiceStatusRequest {
+    #[field_type(String)]
+    #[validate]
+    pub vm_id: VmID,
+}
+
+impl VmServiceStatusRequestTypeValidator for VmServiceStatusRequestValidator {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> VirtualMachine {
+        VirtualMachine {
+            id: "my-vm".into(),
+            mem_size_mb: 1024,
+            vcpu_count: 2,
+            kernel_img_path: "/var/lib/aurae/vm/kernel/vmlinux.bin".into(),
+            kernel_args: vec!["console=hvc0".into()],
+            root_drive: Some(RootDrive {
+                image_path: "/var/lib/aurae/vm/image/disk.raw".into(),
+                read_only: false,
+            }),
+            drive_mounts: vec![DriveMount {
+                image_path: "/var/lib/aurae/vm/image/data.raw".into(),
+                vm_path: "/data".into(),
+                fs_type: "ext4".into(),
+                read_only: true,
+            }],
+            auraed_address: String::new(),
+        }
+    }
+
+    fn validate(
+        machine: VirtualMachine,
+    ) -> Result<ValidatedVmServiceAllocateRequest, ValidationError> {
+        ValidatedVmServiceAllocateRequest::validate(
+            VmServiceAllocateRequest { machine: Some(machine) },
+            None,
+        )
+    }
+
+    #[test]
+    fn test_allocate_request_is_valid() {
+        let request = validate(machine()).expect("valid request");
+        assert_eq!(request.machine.id, VmID::new("my-vm"));
+        assert_eq!(
+            request.machine.root_drive.host_path,
+            PathBuf::from("/var/lib/aurae/vm/image/disk.raw")
+        );
+        assert!(request.machine.drive_mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_allocate_request_requires_a_machine() {
+        assert!(matches!(
+            ValidatedVmServiceAllocateRequest::validate(
+                VmServiceAllocateRequest { machine: None },
+                None,
+            ),
+            Err(ValidationError::Required { .. })
+        ));
+    }
+
+    #[test]
+    fn test_allocate_request_requires_a_root_drive() {
+        let err = validate(VirtualMachine { root_drive: None, ..machine() })
+            .expect_err("root drive is required");
+        assert_eq!(err.get_field(), "machine.root_drive");
+    }
+
+    #[test]
+    fn test_allocate_request_rejects_invalid_resources() {
+        let err = validate(VirtualMachine { vcpu_count: 0, ..machine() })
+            .expect_err("vcpus are required");
+        assert_eq!(err.get_field(), "machine.vcpu_count");
+
+        let err = validate(VirtualMachine { vcpu_count: 255, ..machine() })
+            .expect_err("too many vcpus");
+        assert_eq!(err.get_field(), "machine.vcpu_count");
+
+        let err = validate(VirtualMachine { mem_size_mb: 0, ..machine() })
+            .expect_err("memory is required");
+        assert_eq!(err.get_field(), "machine.mem_size_mb");
+    }
+
+    #[test]
+    fn test_allocate_request_requires_absolute_image_paths() {
+        let err = validate(VirtualMachine {
+            kernel_img_path: "vmlinux.bin".into(),
+            ..machine()
+        })
+        .expect_err("relative kernel path");
+        assert_eq!(err.get_field(), "machine.kernel_img_path");
+
+        let mut relative_mount = machine();
+        relative_mount.drive_mounts[0].image_path = "data.raw".into();
+        let err = validate(relative_mount).expect_err("relative mount path");
+        assert_eq!(err.get_field(), "machine.drive_mounts[0].image_path");
+    }
+}
@@ -12,37 +12,60 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-use crate::vms::manager::Manager;
-use anyhow::anyhow;
-use net_util::MacAddr;
+
+use super::hypervisor::Hypervisor;
+use crate::logging::log_channel::LogChannel;
+use crate::reaper::{self, ManagedChild};
+use nix::unistd::Pid;
 use std::{
     fmt::{self, Display},
+    io,
     net::{Ipv4Addr, SocketAddr},
     path::PathBuf,
-    sync::{Arc, Mutex},
-};
-#[cfg(target_arch = "x86_64")]
-use vmm::vm_config::DebugConsoleConfig;
-use vmm::{
-    api::ApiAction,
-    vm::VmState,
-    vm_config::{
-        default_console, default_serial, CpuFeatures, CpusConfig,
-        HotplugMethod, MemoryConfig, PayloadConfig, RngConfig, VhostMode,
-        DEFAULT_DISK_NUM_QUEUES, DEFAULT_DISK_QUEUE_SIZE,
-        DEFAULT_MAX_PHYS_BITS, DEFAULT_NET_NUM_QUEUES, DEFAULT_NET_QUEUE_SIZE,
-    },
+    process::Stdio,
+    time::Duration,
 };
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Child;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use validation::{ValidatedField, ValidationError};
+
+/// The number of lines of each output channel retained, to replay to those
+/// who start reading the console late.
+const LOG_HISTORY_LINES: usize = 1000;
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub struct VmID(String);
 
 impl VmID {
+    #[cfg(test)]
     pub fn new(id: impl Into<String>) -> Self {
         Self(id.into())
     }
 }
 
+impl ValidatedField<String> for VmID {
+    fn validate(
+        input: Option<String>,
+        field_name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let input =
+            validation::required_not_empty(input, field_name, parent_name)?;
+
+        // The id names the state directory of the VM
+        validation::allow_regex(
+            &input,
+            &validation::DOMAIN_NAME_LABEL_REGEX,
+            field_name,
+            parent_name,
+        )?;
+
+        Ok(Self(input))
+    }
+}
+
 impl Display for VmID {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -59,101 +82,12 @@ pub struct VmSpec {
     pub net: Vec<NetSpec>,
 }
 
-impl From<VmSpec> for vmm::vm_config::VmConfig {
-    fn from(spec: VmSpec) -> Self {
-        vmm::vm_config::VmConfig {
-            cpus: CpusConfig {
-                boot_vcpus: spec.vcpu_count as u8,
-                max_vcpus: spec.vcpu_count as u8,
-                topology: None,
-                kvm_hyperv: false,
-                max_phys_bits: DEFAULT_MAX_PHYS_BITS,
-                affinity: None,
-                features: CpuFeatures::default(),
-            },
-            memory: MemoryConfig {
-                size: (spec.memory_size << 20) as u64,
-                mergeable: false,
-                hotplug_method: HotplugMethod::default(),
-                hotplug_size: None,
-                hotplugged_size: None,
-                shared: false,
-                hugepages: false,
-                hugepage_size: None,
-                prefault: false,
-                zones: None,
-                thp: false,
-            },
-            payload: Some(PayloadConfig {
-                firmware: None,
-                kernel: Some(spec.kernel_image_path),
-                cmdline: Some(spec.kernel_args.join(" ")),
-                initramfs: None,
-            }),
-            rate_limit_groups: None,
-            disks: Some(spec.mounts.into_iter().map(Into::into).collect()),
-            net: Some(spec.net.into_iter().map(Into::into).collect()),
-            rng: RngConfig::default(),
-            balloon: None,
-            fs: None,
-            pmem: None,
-            serial: default_serial(),
-            console: default_console(),
-            #[cfg(target_arch = "x86_64")]
-            debug_console: DebugConsoleConfig::default(),
-            devices: None,
-            user_devices: None,
-            vdpa: None,
-            vsock: None,
-            pvpanic: false,
-            iommu: false,
-            #[cfg(target_arch = "x86_64")]
-            sgx_epc: None,
-            numa: None,
-            watchdog: false,
-            pci_segments: None,
-            platform: None,
-            tpm: None,
-            preserved_fds: None,
-            landlock_enable: false,
-            landlock_rules: None,
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct NetSpec {
-    pub tap: Option<String>,
+    pub tap: String,
     pub ip: Ipv4Addr,
     pub mask: Ipv4Addr,
     pub mac: MacAddr,
-    pub host_mac: Option<MacAddr>,
-}
-
-impl From<NetSpec> for vmm::vm_config::NetConfig {
-    fn from(spec: NetSpec) -> Self {
-        vmm::vm_config::NetConfig {
-            tap: spec.tap,
-            ip: spec.ip,
-            mask: spec.mask,
-            mac: spec.mac,
-            host_mac: spec.host_mac,
-            mtu: None,
-            iommu: false,
-            num_queues: DEFAULT_NET_NUM_QUEUES,
-            queue_size: DEFAULT_NET_QUEUE_SIZE,
-            vhost_user: false,
-            vhost_socket: None,
-            vhost_mode: VhostMode::default(),
-            id: None,
-            fds: None,
-            rate_limiter_config: None,
-            pci_segment: 0,
-            offload_tso: false,
-            offload_ufo: false,
-            offload_csum: false,
-        }
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -162,181 +96,159 @@ pub struct MountSpec {
     pub read_only: bool,
 }
 
-impl From<MountSpec> for vmm::vm_config::DiskConfig {
-    fn from(spec: MountSpec) -> Self {
-        vmm::vm_config::DiskConfig {
-            path: Some(spec.host_path),
-            readonly: spec.read_only,
-            direct: false,
-            iommu: false,
-            num_queues: DEFAULT_DISK_NUM_QUEUES,
-            queue_size: DEFAULT_DISK_QUEUE_SIZE,
-            vhost_user: false,
-            vhost_socket: None,
-            rate_limit_group: None,
-            rate_limiter_config: None,
-            id: None,
-            disable_io_uring: false,
-            disable_aio: false,
-            pci_segment: 0,
-            serial: None,
-            queue_affinity: None,
-        }
+/// The MAC address of a network device of a VM.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    /// A random, locally administered, unicast address.
+    pub fn local_random() -> Self {
+        let mut bytes = [0; 6];
+        bytes.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..6]);
+        bytes[0] = (bytes[0] & 0xfe) | 0x02;
+        Self(bytes)
     }
 }
 
-#[derive(Clone)]
-pub struct VirtualMachine {
-    pub id: VmID,
-    pub vm: VmSpec,
-    pub status: VmStatus,
-    manager: Arc<Mutex<Manager>>,
-}
-
-impl fmt::Debug for VirtualMachine {
+impl Display for MacAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "VirtualMachine {{ id: {:?}, vm: {:?} }}", self.id, self.vm)
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct VmStatus(VmState);
+#[derive(Debug)]
+pub struct VirtualMachine {
+    pub id: VmID,
+    pub spec: VmSpec,
+    /// Where the hypervisor keeps the state of the VM, e.g. its API socket.
+    pub state_dir: PathBuf,
+    /// The console of the VM.
+    pub stdout: LogChannel,
+    /// The output of the hypervisor itself.
+    pub stderr: LogChannel,
+    state: VmState,
+}
 
-impl Display for VmStatus {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.0)
-    }
+#[derive(Debug)]
+enum VmState {
+    Created,
+    Running {
+        child: Child,
+        pid: i32,
+        /// Leaves `child` for us to wait on, rather than the reaper.
+        #[allow(unused)]
+        managed: ManagedChild,
+        stdout: JoinHandle<()>,
+        stderr: JoinHandle<()>,
+    },
+    Shutdown,
 }
 
 impl VirtualMachine {
-    pub fn new(id: VmID, spec: VmSpec) -> Result<Self, anyhow::Error> {
-        let mut manager = Manager::new();
-        manager.start()?;
-
-        if let Some(sender) = &manager.sender {
-            vmm::api::VmCreate
-                .send(
-                    manager.events.try_clone()?,
-                    sender.clone(),
-                    Box::new(spec.clone().into()),
-                )
-                .expect("Failed to send create request");
-        } else {
-            return Err(anyhow!("Virtual machine manager not initialized"));
-        }
-
-        Ok(VirtualMachine {
-            id,
-            vm: spec,
-            status: VmStatus(VmState::Created),
-            manager: Arc::new(Mutex::new(manager)),
-        })
+    pub fn new(id: VmID, spec: VmSpec, state_dir: PathBuf) -> Self {
+        let stdout = LogChannel::new(format!("{id}::stdout"))
+            .with_history(LOG_HISTORY_LINES);
+        let stderr = LogChannel::new(format!("{id}::stderr"))
+            .with_history(LOG_HISTORY_LINES);
+        Self { id, spec, state_dir, stdout, stderr, state: VmState::Created }
     }
 
-    pub fn start(&mut self) -> Result<(), anyhow::Error> {
-        if let VmState::Running = self.status.0 {
-            return Err(anyhow!("Virtual machine already running"));
-        }
-        let manager = self
-            .manager
-            .lock()
-            .map_err(|_| anyhow!("Failed to aquire lock for vm manager"))?;
-
-        if let Some(sender) = &manager.sender {
-            let _ = vmm::api::VmBoot
-                .send(manager.events.try_clone()?, sender.clone(), ())
-                .map_err(|e| anyhow!("Failed to send start request: {e}"))?;
-            self.status = VmStatus(VmState::Running);
-        } else {
-            return Err(anyhow!("Virtual machine manager not initialized"))?;
+    /// Starts the hypervisor running the VM, and returns its pid.
+    /// The hypervisor of a previous start must have been stopped.
+    pub fn start(&mut self, hypervisor: &dyn Hypervisor) -> io::Result<i32> {
+        if let VmState::Running { .. } = self.state {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "hypervisor already started",
+            ));
         }
 
-        // Update the VM with the network device information if it wasn't provided
-        if self.vm.net.is_empty() {
-            if let Some(net) = &self.info()?.net {
-                self.vm.net = net
-                    .iter()
-                    .map(|n| NetSpec {
-                        tap: n.tap.clone(),
-                        ip: n.ip,
-                        mask: n.mask,
-                        mac: n.mac,
-                        host_mac: n.host_mac,
-                    })
-                    .collect();
-            }
-        }
+        let mut command = hypervisor.command(&self.spec, &self.state_dir)?;
+        let _ = command
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
 
-        Ok(())
-    }
+        let spawning = reaper::spawning();
+        #[allow(clippy::disallowed_methods)] // spawned under spawning
+        let mut child = command.spawn()?;
+        let pid = child.id().expect("spawned child") as i32;
+        let managed = spawning.manage(Pid::from_raw(pid));
 
-    pub fn stop(&mut self) -> Result<(), anyhow::Error> {
-        if let VmState::Shutdown = self.status.0 {
-            return Err(anyhow!("Virtual machine already stopped"));
-        }
-        let manager = self
-            .manager
-            .lock()
-            .map_err(|_| anyhow!("Failed to aquire lock for vm manager"))?;
-
-        if let Some(sender) = &manager.sender {
-            let _ = vmm::api::VmShutdown
-                .send(manager.events.try_clone()?, sender.clone(), ())
-                .map_err(|e| anyhow!("Failed to send stop request: {e}"))?;
-            self.status = VmStatus(VmState::Shutdown);
-        } else {
-            return Err(anyhow!("Virtual machine manager not initialized"));
-        }
+        let stdout = forward(child.stdout.take(), self.stdout.clone());
+        let stderr = forward(child.stderr.take(), self.stderr.clone());
 
-        Ok(())
+        self.state = VmState::Running { child, pid, managed, stdout, stderr };
+        Ok(pid)
     }
 
-    pub fn delete(&mut self) -> Result<(), anyhow::Error> {
-        if self.status.0 != VmState::Shutdown {
-            self.stop()?;
+    /// Asks the guest to power off, and kills the hypervisor if it has not
+    /// exited within `timeout`. Returns the pid the hypervisor had, if it
+    /// was started.
+    pub async fn stop(
+        &mut self,
+        hypervisor: &dyn Hypervisor,
+        timeout: Duration,
+    ) -> io::Result<Option<i32>> {
+        let VmState::Running { child, pid, stdout, stderr, .. } =
+            &mut self.state
+        else {
+            return Ok(None);
         };
-        let manager = self
-            .manager
-            .lock()
-            .map_err(|_| anyhow!("Failed to aquire lock for vm manager"))?;
-
-        if let Some(sender) = &manager.sender {
-            let _ = vmm::api::VmDelete
-                .send(manager.events.try_clone()?, sender.clone(), ())
-                .map_err(|e| anyhow!("Failed to send destroy request: {e}"))?;
-            return Ok(());
+
+        if child.try_wait()?.is_none() {
+            if let Err(e) = hypervisor.power_off(&self.state_dir).await {
+                warn!("failed to power off vm '{}': {e}", self.id);
+            }
+            if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+                warn!(
+                    "vm '{}' did not power off within {timeout:?}, killing its hypervisor",
+                    self.id
+                );
+                child.kill().await?;
+            }
         }
-        Err(anyhow!("Virtual machine manager not initialized"))
+        let _ = tokio::join!(stdout, stderr);
+
+        let pid = *pid;
+        self.state = VmState::Shutdown;
+        Ok(Some(pid))
     }
 
-    fn info(&self) -> Result<vmm::vm_config::VmConfig, anyhow::Error> {
-        let manager = self
-            .manager
-            .lock()
-            .map_err(|_| anyhow!("Failed to aquire lock for vm manager"))?;
-
-        if let Some(sender) = &manager.sender {
-            let res = vmm::api::VmInfo
-                .send(manager.events.try_clone()?, sender.clone(), ())
-                .map_err(|e| anyhow!("Failed to send info request: {e}"))?;
-            let config = *res.config;
-            return Ok(config.clone());
-        }
-        Err(anyhow!("Virtual machine manager not initialized"))
+    /// Returns true if the hypervisor has been started and has not exited.
+    pub fn is_running(&mut self) -> io::Result<bool> {
+        Ok(match &mut self.state {
+            VmState::Running { child, .. } => child.try_wait()?.is_none(),
+            VmState::Created | VmState::Shutdown => false,
+        })
     }
 
-    /// Get a reference to the address of the TAP device for this VM
-    pub fn tap(&self) -> Option<SocketAddr> {
-        let manager = self.manager.lock().ok()?;
+    /// The pid of the hypervisor, if it is running.
+    pub fn pid(&mut self) -> io::Result<Option<i32>> {
+        let running = self.is_running()?;
+        Ok(match self.state {
+            VmState::Running { pid, .. } if running => Some(pid),
+            _ => None,
+        })
+    }
 
-        // Retrieve config from the VMM
-        let res = vmm::api::VmInfo
-            .send(manager.events.try_clone().ok()?, manager.sender.clone()?, ())
-            .ok()?;
-        let config = *res.config;
-        let net = config.net.clone()?;
+    /// "Created" until the VM is started, "Running" until its hypervisor
+    /// exits, and "Shutdown" thereafter.
+    pub fn status(&mut self) -> io::Result<&'static str> {
+        let running = self.is_running()?;
+        Ok(match self.state {
+            VmState::Created => "Created",
+            VmState::Running { .. } if running => "Running",
+            VmState::Running { .. } | VmState::Shutdown => "Shutdown",
+        })
+    }
 
-        let iface = net.first()?.tap.clone()?;
+    /// The address auraed serves on in the guest, through the first tap
+    /// device of the VM.
+    pub fn tap(&self) -> Option<SocketAddr> {
+        let iface = &self.spec.net.first()?.tap;
         let scope_id = nix::net::if_::if_nametoindex(iface.as_str()).ok()?;
 
         // TODO: Make this somehow configurable
@@ -347,21 +259,50 @@ impl VirtualMachine {
     }
 }
 
+/// Sends the lines read from `output` to `log_channel`.
+fn forward(
+    output: Option<impl AsyncRead + Unpin + Send + 'static>,
+    log_channel: LogChannel,
+) -> JoinHandle<()> {
+    let output = output.expect("piped output");
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(output).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log_channel.send(line);
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{net::Ipv4Addr, path::PathBuf};
-
-    use net_util::MacAddr;
+    use super::*;
+    use std::path::Path;
+    use tokio::process::Command;
+    use tonic::async_trait;
+
+    /// Boots nothing: echoes a line, and then ignores the power button.
+    #[derive(Debug)]
+    struct FakeHypervisor;
+
+    #[async_trait]
+    impl Hypervisor for FakeHypervisor {
+        fn command(
+            &self,
+            _spec: &VmSpec,
+            _state_dir: &Path,
+        ) -> io::Result<Command> {
+            let mut command = Command::new("sh");
+            let _ = command.args(["-c", "echo booted; exec sleep 30"]);
+            Ok(command)
+        }
 
-    use crate::vms::virtual_machine::{
-        MountSpec, NetSpec, VirtualMachine, VmID, VmSpec,
-    };
+        async fn power_off(&self, _state_dir: &Path) -> io::Result<()> {
+            Err(io::Error::other("no power button"))
+        }
+    }
 
-    #[test]
-    #[ignore]
-    fn test_create_vm() {
-        let id = VmID::new("test_vm");
-        let spec = VmSpec {
+    fn spec() -> VmSpec {
+        VmSpec {
             memory_size: 1024,
             vcpu_count: 4,
             kernel_image_path: PathBuf::from(
@@ -376,24 +317,84 @@ mod tests {
                 read_only: false,
             }],
             net: vec![NetSpec {
-                tap: Some("tap0".to_string()),
+                tap: "tap0".to_string(),
                 ip: Ipv4Addr::new(192, 168, 249, 1),
                 mask: Ipv4Addr::new(255, 255, 255, 255),
                 mac: MacAddr::local_random(),
-                host_mac: None,
             }],
-        };
+        }
+    }
+
+    #[test]
+    fn test_vm_id_must_be_a_domain_name_label() {
+        assert!(VmID::validate(Some("my-vm".into()), "id", None).is_ok());
+        assert!(matches!(
+            VmID::validate(Some(String::new()), "id", None),
+            Err(ValidationError::Required { .. })
+        ));
+        assert!(matches!(
+            VmID::validate(Some("../my-vm".into()), "id", None),
+            Err(ValidationError::AllowRegexViolation { .. })
+        ));
+    }
 
-        let mut vm = VirtualMachine::new(id.clone(), spec).unwrap();
+    #[test]
+    fn test_local_random_mac_is_locally_administered_unicast() {
+        let mac = MacAddr::local_random();
+        assert_eq!(mac.0[0] & 0x03, 0x02);
+
+        let formatted = mac.to_string();
+        assert_eq!(formatted.len(), 17);
+        assert_eq!(formatted.split(':').count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_stop_kills_a_hypervisor_which_does_not_power_off() {
+        let mut vm = VirtualMachine::new(
+            VmID::new("test-vm"),
+            spec(),
+            std::env::temp_dir(),
+        );
+        let console = vm.stdout.subscribe("test");
+        assert_eq!(vm.status().unwrap(), "Created");
+
+        let pid = vm.start(&FakeHypervisor).expect("start");
+        assert_eq!(vm.status().unwrap(), "Running");
+        assert_eq!(vm.pid().unwrap(), Some(pid));
+        assert!(vm.start(&FakeHypervisor).is_err());
+
+        let stopped = vm
+            .stop(&FakeHypervisor, Duration::from_millis(100))
+            .await
+            .expect("stop");
+        assert_eq!(stopped, Some(pid));
+        assert_eq!(vm.status().unwrap(), "Shutdown");
+        assert_eq!(vm.pid().unwrap(), None);
+
+        let mut console = console;
+        assert_eq!(console.recv().await.expect("console").line, "booted");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_create_vm() {
+        let hypervisor = crate::vms::hypervisor::CloudHypervisor::default();
+        let id = VmID::new("test-vm");
+        let state_dir = std::env::temp_dir()
+            .join(format!("ae-test-vm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&state_dir).unwrap();
+
+        let mut vm = VirtualMachine::new(id.clone(), spec(), state_dir);
         assert_eq!(vm.id, id);
 
-        assert!(vm.start().is_ok(), "{:?}", vm);
+        assert!(vm.start(&hypervisor).is_ok(), "{:?}", vm);
 
         // Give the VM some time to boot
-        std::thread::sleep(std::time::Duration::from_secs(10));
-        assert!(vm.stop().is_ok(), "{:?}", vm);
-
-        std::thread::sleep(std::time::Duration::from_secs(5));
-        assert!(vm.delete().is_ok(), "{:?}", vm);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(
+            vm.stop(&hypervisor, Duration::from_secs(10)).await.is_ok(),
+            "{:?}",
+            vm
+        );
     }
 }
@@ -12,131 +12,279 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-use std::{collections::HashMap, net::Ipv4Addr};
 
-use anyhow::anyhow;
-use net_util::MacAddr;
-use tracing::error;
-use vmm_sys_util::{rand, signal::block_signal};
+use std::{collections::HashMap, net::Ipv4Addr, path::PathBuf};
 
-use super::virtual_machine::{NetSpec, VirtualMachine, VmID, VmSpec};
+use anyhow::Context;
+use tracing::warn;
+
+use super::{
+    error::{Result, VmServiceError},
+    virtual_machine::{MacAddr, NetSpec, VirtualMachine, VmID, VmSpec},
+};
 
 type Cache = HashMap<VmID, VirtualMachine>;
 
 /// The in-memory cache of virtual machines ([VirtualMachine]) created with Aurae.
 #[derive(Debug)]
 pub struct VirtualMachines {
+    /// The parent of the state directory of each virtual machine.
+    vms_dir: PathBuf,
     cache: Cache,
-}
-
-impl Default for VirtualMachines {
-    fn default() -> Self {
-        Self::new()
-    }
+    /// The virtual machines taken out of the cache to be stopped, with the
+    /// IP addresses they hold until they are restored or released.
+    taken: HashMap<VmID, Vec<Ipv4Addr>>,
 }
 
 impl VirtualMachines {
-    /// Create a new instance of the virtual machines cache.
-    pub fn new() -> Self {
-        unsafe {
-            let _ = libc::signal(libc::SIGCHLD, libc::SIG_IGN);
-        }
-
-        // Mask the signals handled by the Cloud Hyupervisor VMM so they only run on the dedicated signal handling thread
-        for sig in &vmm::vm::Vm::HANDLED_SIGNALS {
-            if let Err(e) = block_signal(*sig) {
-                error!("Error blocking signals: {e}");
-            }
-        }
-
-        for sig in &vmm::Vmm::HANDLED_SIGNALS {
-            if let Err(e) = block_signal(*sig) {
-                error!("Error blocking signals: {e}");
-            }
-        }
-
-        Self { cache: Cache::new() }
+    /// Create a new instance of the virtual machines cache, keeping the state
+    /// of each virtual machine in a directory of `vms_dir`.
+    pub fn new(vms_dir: PathBuf) -> Self {
+        Self { vms_dir, cache: Cache::new(), taken: HashMap::new() }
     }
 
-    /// Allocate an IP address for a new virtual machine
+    /// Allocate an IP address for a new virtual machine: the lowest one not
+    /// used by another.
     ///
     /// Use the hard-coded Cloud Hypervisor default address as starting IP
     /// https://github.com/cloud-hypervisor/cloud-hypervisor/blob/165c2c476f752909aba41d4e319f12ade20b72d3/vmm/src/vm_config.rs#L313-L319
-    fn allocate_ip(&self) -> Ipv4Addr {
-        Ipv4Addr::new(192, 168, 249, self.cache.len() as u8 + 1)
+    fn allocate_ip(&self) -> Option<Ipv4Addr> {
+        (1..=254).map(|host| Ipv4Addr::new(192, 168, 249, host)).find(|ip| {
+            !self
+                .cache
+                .values()
+                .flat_map(|vm| vm.spec.net.iter().map(|n| &n.ip))
+                .chain(self.taken.values().flatten())
+                .any(|taken| taken == ip)
+        })
     }
 
-    /// Create a new virtual machine
+    /// Create a new virtual machine, and its state directory
     pub fn create(
         &mut self,
         id: VmID,
         mut spec: VmSpec,
-    ) -> Result<VirtualMachine, anyhow::Error> {
-        if let Some(vm) = self.cache.get(&id) {
-            return Err(anyhow!(
-                "Virtual machine with ID '{:?}' already exists: {:?}",
-                &id,
-                vm.vm,
-            ));
+    ) -> Result<&mut VirtualMachine> {
+        if self.cache.contains_key(&id) || self.taken.contains_key(&id) {
+            return Err(VmServiceError::VmExists { id });
         }
 
         // Populate the default network configuration if it's empty
         if spec.net.is_empty() {
+            let Some(ip) = self.allocate_ip() else {
+                return Err(VmServiceError::FailedToAllocateError {
+                    id,
+                    source: anyhow::anyhow!("no IP address left"),
+                });
+            };
+            let suffix = uuid::Uuid::new_v4().simple().to_string();
             spec.net.push(NetSpec {
-                tap: Some(format!(
-                    "auraed-{}",
-                    rand::rand_alphanumerics(6).into_string().map_err(
-                        |_| anyhow!("Error generating TAP device name")
-                    )?,
-                )),
-                ip: self.allocate_ip(),
+                tap: format!("auraed-{}", &suffix[..6]),
+                ip,
                 mask: Ipv4Addr::new(255, 255, 255, 0),
                 mac: MacAddr::local_random(),
-                host_mac: None,
             });
         }
 
-        let vm = VirtualMachine::new(id.clone(), spec)?;
-        let _ = self.cache.insert(id, vm.clone()).is_none();
+        let state_dir = self.vms_dir.join(id.to_string());
+        std::fs::create_dir_all(&state_dir)
+            .with_context(|| {
+                format!("failed to create '{}'", state_dir.display())
+            })
+            .map_err(|e| VmServiceError::FailedToAllocateError {
+                id: id.clone(),
+                source: e,
+            })?;
+
+        let vm = VirtualMachine::new(id.clone(), spec, state_dir);
+        Ok(self.cache.entry(id).or_insert(vm))
+    }
+
+    /// Get a virtual machine by its ID
+    pub fn get_mut(&mut self, id: &VmID) -> Result<&mut VirtualMachine> {
+        self.cache
+            .get_mut(id)
+            .ok_or_else(|| VmServiceError::VmNotFound { id: id.clone() })
+    }
+
+    /// Removes a virtual machine from the cache, so that it can be stopped
+    /// without holding the cache. Its ID and IP addresses stay taken until
+    /// [VirtualMachines::restore] or [VirtualMachines::release].
+    pub fn take(&mut self, id: &VmID) -> Result<VirtualMachine> {
+        let vm = self
+            .cache
+            .remove(id)
+            .ok_or_else(|| VmServiceError::VmNotFound { id: id.clone() })?;
+
+        let ips = vm.spec.net.iter().map(|n| n.ip).collect();
+        let _ = self.taken.insert(id.clone(), ips);
         Ok(vm)
     }
 
-    /// Stop a virtual machine by its ID
-    pub fn stop(&mut self, id: &VmID) -> Result<(), anyhow::Error> {
-        if let Some(vm) = self.cache.get_mut(id) {
-            vm.stop()?;
-            Ok(())
-        } else {
-            Err(anyhow!("Virtual machine with ID '{:?}' not found", id))
-        }
+    /// Puts back a virtual machine taken with [VirtualMachines::take].
+    pub fn restore(&mut self, vm: VirtualMachine) {
+        let _ = self.taken.remove(&vm.id);
+        let _ = self.cache.insert(vm.id.clone(), vm);
+    }
+
+    /// Frees the ID and IP addresses of a virtual machine taken with
+    /// [VirtualMachines::take], once it is deleted.
+    pub fn release(&mut self, id: &VmID) {
+        let _ = self.taken.remove(id);
     }
 
-    /// Start a virtual machine by its ID, returning the addres of its TAP device
-    pub fn start(&mut self, id: &VmID) -> Result<String, anyhow::Error> {
-        if let Some(vm) = self.cache.get_mut(id) {
-            vm.start()?;
-            match vm.tap() {
-                Some(tap) => Ok(tap.to_string()),
-                None => Ok("".into()),
+    /// Deletes a stopped virtual machine, removing its state directory and
+    /// tap devices.
+    pub async fn delete(vm: &VirtualMachine) -> anyhow::Result<()> {
+        delete_taps(vm.spec.net.iter().map(|n| n.tap.as_str())).await?;
+
+        match std::fs::remove_dir_all(&vm.state_dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow::Error::new(e).context(format!(
+                    "failed to remove '{}'",
+                    vm.state_dir.display()
+                )))
             }
-        } else {
-            Err(anyhow!("Virtual machine with ID '{:?}' not found", id))
+            _ => Ok(()),
+        }
+    }
+
+    /// List all virtual machines
+    pub fn list(&mut self) -> impl Iterator<Item = &mut VirtualMachine> {
+        self.cache.values_mut()
+    }
+}
+
+/// Deletes the tap devices named `taps` which exist.
+async fn delete_taps<'a>(
+    taps: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<()> {
+    let taps: Vec<_> = taps
+        .filter_map(|tap| {
+            nix::net::if_::if_nametoindex(tap).ok().map(|index| (tap, index))
+        })
+        .collect();
+    if taps.is_empty() {
+        return Ok(());
+    }
+
+    let (connection, handle, _) = rtnetlink::new_connection()?;
+    let _ignored = tokio::spawn(connection);
+    for (tap, index) in taps {
+        if let Err(e) = handle.link().del(index).execute().await {
+            warn!("failed to delete tap device '{tap}': {e}");
         }
     }
+    Ok(())
+}
 
-    /// Delete a virtual machine by its ID
-    pub fn delete(&mut self, id: &VmID) -> Result<(), anyhow::Error> {
-        if let Some(vm) = self.cache.get_mut(id) {
-            vm.delete()?;
-            let _ = self.cache.remove(id);
-            Ok(())
-        } else {
-            Err(anyhow!("Virtual machine with ID '{:?}' not found", id))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn spec() -> VmSpec {
+        VmSpec {
+            memory_size: 512,
+            vcpu_count: 1,
+            kernel_image_path: PathBuf::from("/vm/vmlinux.bin"),
+            kernel_args: vec![],
+            mounts: vec![],
+            net: vec![],
         }
     }
 
-    /// List all virtual machines
-    pub fn list(&self) -> Vec<VirtualMachine> {
-        self.cache.values().cloned().collect()
+    fn vms_dir() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ae-test-vms-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_create_allocates_the_lowest_free_ip() {
+        let vms_dir = vms_dir();
+        let mut vms = VirtualMachines::new(vms_dir.clone());
+
+        for id in ["vm-1", "vm-2", "vm-3"] {
+            let _ = vms.create(VmID::new(id), spec()).expect("create");
+        }
+
+        // The IP of a VM being stopped is not reused
+        let vm = vms.take(&VmID::new("vm-2")).expect("take");
+        let other = vms.create(VmID::new("vm-5"), spec()).expect("create");
+        assert_eq!(other.spec.net[0].ip, Ipv4Addr::new(192, 168, 249, 4));
+        VirtualMachines::delete(&vm).await.expect("delete");
+        vms.release(&vm.id);
+
+        let vm = vms.create(VmID::new("vm-4"), spec()).expect("create");
+        assert_eq!(vm.spec.net[0].ip, Ipv4Addr::new(192, 168, 249, 2));
+        assert!(vm.spec.net[0].tap.starts_with("auraed-"));
+        assert_eq!(vm.spec.net[0].tap.len(), "auraed-".len() + 6);
+        assert_eq!(vm.state_dir, vms_dir.join("vm-4"));
+        assert!(Path::new(&vm.state_dir).is_dir());
+
+        assert!(matches!(
+            vms.create(VmID::new("vm-4"), spec()),
+            Err(VmServiceError::VmExists { .. })
+        ));
+
+        std::fs::remove_dir_all(vms_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_the_state_dir() {
+        let vms_dir = vms_dir();
+        let mut vms = VirtualMachines::new(vms_dir.clone());
+        let id = VmID::new("my-vm");
+
+        let state_dir =
+            vms.create(id.clone(), spec()).unwrap().state_dir.clone();
+        std::fs::write(state_dir.join("api.sock"), "").unwrap();
+        let vm = vms.take(&id).expect("take");
+
+        // The ID of a VM being stopped is not reused
+        assert!(matches!(
+            vms.create(id.clone(), spec()),
+            Err(VmServiceError::VmExists { .. })
+        ));
+
+        VirtualMachines::delete(&vm).await.expect("delete");
+        vms.release(&id);
+
+        assert!(!state_dir.exists());
+        assert!(matches!(
+            vms.get_mut(&id),
+            Err(VmServiceError::VmNotFound { .. })
+        ));
+        assert!(matches!(
+            vms.take(&id),
+            Err(VmServiceError::VmNotFound { .. })
+        ));
+
+        std::fs::remove_dir_all(vms_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_the_tap_devices() {
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+
+        let vms_dir = vms_dir();
+        let mut vms = VirtualMachines::new(vms_dir.clone());
+        let id = VmID::new("my-vm");
+        let _ = vms.create(id.clone(), spec()).expect("create");
+        let vm = vms.take(&id).expect("take");
+        let tap = vm.spec.net[0].tap.clone();
+
+        // Stands in for the tap device the hypervisor creates
+        let (connection, handle, _) = rtnetlink::new_connection().unwrap();
+        let _ignored = tokio::spawn(connection);
+        handle.link().add().dummy(tap.clone()).execute().await.unwrap();
+        assert!(nix::net::if_::if_nametoindex(tap.as_str()).is_ok());
+
+        VirtualMachines::delete(&vm).await.expect("delete");
+        assert!(nix::net::if_::if_nametoindex(tap.as_str()).is_err());
+
+        std::fs::remove_dir_all(vms_dir).unwrap();
     }
 }
@@ -13,181 +13,309 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-use proto::vms::{
-    vm_service_server, VirtualMachineSummary, VmServiceAllocateRequest,
-    VmServiceAllocateResponse, VmServiceFreeRequest, VmServiceFreeResponse,
-    VmServiceListRequest, VmServiceListResponse, VmServiceStartRequest,
-    VmServiceStartResponse, VmServiceStopRequest, VmServiceStopResponse,
+use proto::{
+    observe::LogChannelType,
+    vms::{
+        vm_service_server, VirtualMachineSummary, VmServiceAllocateRequest,
+        VmServiceAllocateResponse, VmServiceFreeRequest, VmServiceFreeResponse,
+        VmServiceListRequest, VmServiceListResponse, VmServiceStartRequest,
+        VmServiceStartResponse, VmServiceStatusRequest,
+        VmServiceStatusResponse, VmServiceStopRequest, VmServiceStopResponse,
+    },
 };
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 use tonic::{Request, Response, Status};
+use tracing::warn;
+use validation::ValidatedType;
 
 use super::{
     error::{Result, VmServiceError},
-    virtual_machine::{MountSpec, VmID, VmSpec},
+    hypervisor::Hypervisor,
+    validation::{
+        ValidatedVirtualMachine, ValidatedVmServiceAllocateRequest,
+        ValidatedVmServiceFreeRequest, ValidatedVmServiceStartRequest,
+        ValidatedVmServiceStatusRequest, ValidatedVmServiceStopRequest,
+    },
+    virtual_machine::{VirtualMachine, VmSpec},
     virtual_machines::VirtualMachines,
 };
+use crate::logging::log_registry::{LogKey, LogRegistry};
+
+/// How long the guest of a VM is given to power off when it is stopped,
+/// before its hypervisor is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// VmService struct manages the lifecycle of virtual machines.
 #[derive(Debug, Clone)]
 pub struct VmService {
     vms: Arc<Mutex<VirtualMachines>>,
-    // TODO: ObserveService
+    hypervisor: Arc<dyn Hypervisor>,
 }
 
 impl VmService {
-    /// Allocates a new instance of VmService.
-    pub fn new() -> Self {
-        Self { vms: Default::default() }
+    /// Allocates a new instance of VmService, running VMs in `hypervisor`
+    /// with their state in a directory of `vms_dir`.
+    pub fn new(vms_dir: PathBuf, hypervisor: Arc<dyn Hypervisor>) -> Self {
+        Self {
+            vms: Arc::new(Mutex::new(VirtualMachines::new(vms_dir))),
+            hypervisor,
+        }
     }
 
-    // TODO: validate requestts
     /// Allocates a new VM based on the provided request.
     ///
     /// # Arguments
-    /// * `request` - A (currently unvalidated) request to allocate a VM
+    /// * `request` - A validated request to allocate a VM
     ///
     /// # Returns
     /// A result containing the VmServiceAllocateResponse or an error.
     #[tracing::instrument(skip(self))]
     async fn allocate(
         &self,
-        request: VmServiceAllocateRequest,
+        request: ValidatedVmServiceAllocateRequest,
     ) -> Result<VmServiceAllocateResponse> {
-        let mut vms = self.vms.lock().await;
-
-        let Some(vm) = request.machine else {
-            return Err(VmServiceError::MissingMachineConfig {});
-        };
+        let ValidatedVmServiceAllocateRequest {
+            machine:
+                ValidatedVirtualMachine {
+                    id,
+                    mem_size_mb,
+                    vcpu_count,
+                    kernel_img_path,
+                    kernel_args,
+                    root_drive,
+                    drive_mounts,
+                    auraed_address: _,
+                },
+        } = request;
 
-        let id = VmID::new(vm.id);
-        let Some(root_drive) = vm.root_drive else {
-            return Err(VmServiceError::MissingRootDrive { id: id.clone() });
-        };
+        let mut mounts = vec![root_drive];
+        mounts.extend(drive_mounts);
 
-        let mut mounts = vec![MountSpec {
-            host_path: PathBuf::from(root_drive.image_path.as_str()),
-            read_only: root_drive.read_only,
-        }];
-        mounts.extend(vm.drive_mounts.into_iter().map(|m| MountSpec {
-            host_path: PathBuf::from(m.image_path.as_str()),
-            read_only: m.read_only,
-        }));
+        // Fail now rather than when the VM is started
+        for path in std::iter::once(&kernel_img_path)
+            .chain(mounts.iter().map(|m| &m.host_path))
+        {
+            if !path.is_file() {
+                return Err(VmServiceError::MissingImage {
+                    id,
+                    path: path.clone(),
+                });
+            }
+        }
 
         let spec = VmSpec {
-            memory_size: vm.mem_size_mb,
-            vcpu_count: vm.vcpu_count,
-            kernel_image_path: PathBuf::from(vm.kernel_img_path.as_str()),
-            kernel_args: vm.kernel_args,
+            memory_size: mem_size_mb,
+            vcpu_count,
+            kernel_image_path: kernel_img_path,
+            kernel_args,
             mounts,
             net: vec![],
         };
 
-        let vm = vms.create(id.clone(), spec).map_err(|e| {
-            VmServiceError::FailedToAllocateError { id, source: e }
-        })?;
+        let mut vms = self.vms.lock().await;
+        let vm = vms.create(id, spec)?;
 
         Ok(VmServiceAllocateResponse { vm_id: vm.id.to_string() })
     }
 
-    /// Frees a VM
+    /// Frees a VM, stopping it first if it is running.
     ///
     /// # Arguments
-    /// * `request` - An (unvalidated) request to free a VM
+    /// * `request` - A validated request to free a VM
     ///
     /// # Returns
     /// A result containing VmServiceFreeResponse or an error.
     #[tracing::instrument(skip(self))]
     async fn free(
         &self,
-        request: VmServiceFreeRequest,
+        request: ValidatedVmServiceFreeRequest,
     ) -> Result<VmServiceFreeResponse> {
-        let id = VmID::new(request.vm_id);
+        let ValidatedVmServiceFreeRequest { vm_id: id } = request;
 
-        let mut vms = self.vms.lock().await;
-        vms.delete(&id)
-            .map_err(|e| VmServiceError::FailedToFreeError { id, source: e })?;
+        // Taken out of the cache, with its ID reserved, so that it is
+        // stopped without holding the cache
+        let mut vm = self.vms.lock().await.take(&id)?;
+        let freed = match self.stop_vm(&mut vm).await {
+            Ok(()) => VirtualMachines::delete(&vm).await,
+            Err(e) => Err(e),
+        };
 
-        Ok(VmServiceFreeResponse {})
+        let mut vms = self.vms.lock().await;
+        match freed {
+            Ok(()) => {
+                vms.release(&id);
+                Ok(VmServiceFreeResponse {})
+            }
+            Err(e) => {
+                // Left allocated, to be freed again
+                vms.restore(vm);
+                Err(VmServiceError::FailedToFreeError { id, source: e })
+            }
+        }
     }
 
     /// Starts a VM
     ///
     /// # Arguments
-    /// * `request` - An (unvalidated) request to start a VM
+    /// * `request` - A validated request to start a VM
     ///
     /// # Returns
     /// A result containing VmServiceStartResponse or an error.
     #[tracing::instrument(skip(self))]
     async fn start(
         &self,
-        request: VmServiceStartRequest,
+        request: ValidatedVmServiceStartRequest,
     ) -> Result<VmServiceStartResponse> {
-        let id = VmID::new(request.vm_id);
+        let ValidatedVmServiceStartRequest { vm_id: id } = request;
+        let start_error = |source| VmServiceError::FailedToStartError {
+            id: id.clone(),
+            source,
+        };
 
         let mut vms = self.vms.lock().await;
-        let addr = vms.start(&id).map_err(|e| {
-            VmServiceError::FailedToStartError { id, source: e }
-        })?;
+        let vm = vms.get_mut(&id)?;
+        if vm.is_running().map_err(|e| start_error(e.into()))? {
+            return Err(VmServiceError::VmAlreadyRunning { id });
+        }
+
+        // Clean up after the hypervisor of a previous start, which exited
+        // on its own.
+        self.stop_vm(vm).await.map_err(start_error)?;
 
-        Ok(VmServiceStartResponse { auraed_address: addr })
+        let pid = vm
+            .start(self.hypervisor.as_ref())
+            .map_err(|e| start_error(e.into()))?;
+        register_channels(vm, pid);
+
+        Ok(VmServiceStartResponse {
+            auraed_address: vm.tap().map(|t| t.to_string()).unwrap_or_default(),
+        })
     }
 
     /// Stops a VM
     ///
     /// # Arguments
-    /// * `request` - An (unvalidated) request to stop a VM
+    /// * `request` - A validated request to stop a VM
     ///
     /// # Returns
     /// A result containing VmServiceStopResponse or an error.
     #[tracing::instrument(skip(self))]
     async fn stop(
         &self,
-        request: VmServiceStopRequest,
+        request: ValidatedVmServiceStopRequest,
     ) -> Result<VmServiceStopResponse> {
-        let id = VmID::new(request.vm_id);
+        let ValidatedVmServiceStopRequest { vm_id: id } = request;
 
-        let mut vms = self.vms.lock().await;
-        vms.stop(&id)
+        let mut vm = {
+            let mut vms = self.vms.lock().await;
+            let vm = vms.get_mut(&id)?;
+            let is_running = vm.is_running().map_err(|e| {
+                VmServiceError::FailedToStopError {
+                    id: id.clone(),
+                    source: e.into(),
+                }
+            })?;
+            if !is_running {
+                return Err(VmServiceError::VmNotRunning { id });
+            }
+
+            // Taken out of the cache, with its ID reserved, so that it is
+            // stopped without holding the cache
+            vms.take(&id)?
+        };
+
+        let stopped = self.stop_vm(&mut vm).await;
+        self.vms.lock().await.restore(vm);
+        stopped
             .map_err(|e| VmServiceError::FailedToStopError { id, source: e })?;
 
         Ok(VmServiceStopResponse {})
     }
 
+    /// Get the status of a VM
+    ///
+    /// # Returns
+    /// A result containing VmServiceStatusResponse or an error.
+    #[tracing::instrument(skip(self))]
+    async fn status(
+        &self,
+        request: ValidatedVmServiceStatusRequest,
+    ) -> Result<VmServiceStatusResponse> {
+        let ValidatedVmServiceStatusRequest { vm_id: id } = request;
+
+        let mut vms = self.vms.lock().await;
+        let vm = vms.get_mut(&id)?;
+        Ok(VmServiceStatusResponse { machine: Some(summary(vm)) })
+    }
+
     /// List VMs
     ///
     /// # Returns
     /// A result containing VmServiceListResponse or an error.
     #[tracing::instrument(skip(self))]
     async fn list(&self) -> Result<VmServiceListResponse> {
-        let vms = self.vms.lock().await;
+        let mut vms = self.vms.lock().await;
         Ok(VmServiceListResponse {
-            machines: vms
-                .list()
-                .iter()
-                .map(|m| VirtualMachineSummary {
-                    id: m.id.to_string(),
-                    mem_size_mb: m.vm.memory_size,
-                    vcpu_count: m.vm.vcpu_count,
-                    kernel_img_path: m
-                        .vm
-                        .kernel_image_path
-                        .to_string_lossy()
-                        .to_string(),
-                    root_dir_path: m.vm.mounts[0]
-                        .host_path
-                        .to_string_lossy()
-                        .to_string(),
-                    auraed_address: m
-                        .tap()
-                        .map(|t| t.to_string())
-                        .unwrap_or_default(),
-                    status: m.status.to_string(),
-                })
-                .collect(),
+            machines: vms.list().map(summary).collect(),
         })
     }
+
+    /// Stops the hypervisor of `vm` if it was started, and deregisters its
+    /// console from the [LogRegistry].
+    async fn stop_vm(&self, vm: &mut VirtualMachine) -> anyhow::Result<()> {
+        if let Some(pid) =
+            vm.stop(self.hypervisor.as_ref(), STOP_TIMEOUT).await?
+        {
+            deregister_channels(vm, pid);
+        }
+        Ok(())
+    }
+}
+
+/// Makes the output of the hypervisor of `vm` observable by its `pid`.
+fn register_channels(vm: &VirtualMachine, pid: i32) {
+    let registry = LogRegistry::global();
+    for (channel_type, channel) in [
+        (LogChannelType::Stdout, &vm.stdout),
+        (LogChannelType::Stderr, &vm.stderr),
+    ] {
+        registry.register(LogKey::process(pid, channel_type), channel.clone());
+    }
+}
+
+fn deregister_channels(vm: &VirtualMachine, pid: i32) {
+    let registry = LogRegistry::global();
+    for (channel_type, channel) in [
+        (LogChannelType::Stdout, &vm.stdout),
+        (LogChannelType::Stderr, &vm.stderr),
+    ] {
+        registry.deregister(LogKey::process(pid, channel_type), channel);
+    }
+}
+
+fn summary(vm: &mut VirtualMachine) -> VirtualMachineSummary {
+    let status = vm.status().unwrap_or_else(|e| {
+        warn!("failed to get the status of vm '{}': {e}", vm.id);
+        "Unknown"
+    });
+    VirtualMachineSummary {
+        id: vm.id.to_string(),
+        status: status.to_string(),
+        mem_size_mb: vm.spec.memory_size,
+        vcpu_count: vm.spec.vcpu_count,
+        kernel_img_path: vm
+            .spec
+            .kernel_image_path
+            .to_string_lossy()
+            .to_string(),
+        root_dir_path: vm.spec.mounts[0]
+            .host_path
+            .to_string_lossy()
+            .to_string(),
+        auraed_address: vm.tap().map(|t| t.to_string()).unwrap_or_default(),
+        pid: vm.pid().ok().flatten().unwrap_or_default(),
+    }
 }
 
 #[tonic::async_trait]
@@ -196,34 +324,51 @@ impl vm_service_server::VmService for VmService {
         &self,
         request: Request<VmServiceAllocateRequest>,
     ) -> std::result::Result<Response<VmServiceAllocateResponse>, Status> {
-        let req = request.into_inner();
-        // TODO: validate the request
-        Ok(Response::new(self.allocate(req).await?))
+        let request = request.into_inner();
+        let request =
+            ValidatedVmServiceAllocateRequest::validate(request, None)?;
+        Ok(Response::new(self.allocate(request).await?))
     }
 
     async fn free(
         &self,
         request: Request<VmServiceFreeRequest>,
     ) -> std::result::Result<Response<VmServiceFreeResponse>, Status> {
-        let req = request.into_inner();
-        // TODO: validate request
-        Ok(Response::new(self.free(req).await?))
+        let request = request.into_inner();
+        let request = ValidatedVmServiceFreeRequest::validate(request, None)?;
+
+        // Detached, so a client that disconnects mid-free can't leave the VM
+        // taken out of the cache, with its ID and IPs reserved.
+        let service = self.clone();
+        let free = tokio::spawn(async move { service.free(request).await });
+        let response =
+            free.await.map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(response))
     }
 
     async fn start(
         &self,
         request: Request<VmServiceStartRequest>,
     ) -> std::result::Result<Response<VmServiceStartResponse>, Status> {
-        let req = request.into_inner();
-        Ok(Response::new(self.start(req).await?))
+        let request = request.into_inner();
+        let request = ValidatedVmServiceStartRequest::validate(request, None)?;
+        Ok(Response::new(self.start(request).await?))
     }
 
     async fn stop(
         &self,
         request: Request<VmServiceStopRequest>,
     ) -> std::result::Result<Response<VmServiceStopResponse>, Status> {
-        let req = request.into_inner();
-        Ok(Response::new(self.stop(req).await?))
+        let request = request.into_inner();
+        let request = ValidatedVmServiceStopRequest::validate(request, None)?;
+
+        // Detached, so a client that disconnects mid-stop can't leave the VM
+        // taken out of the cache, with its ID and IPs reserved.
+        let service = self.clone();
+        let stop = tokio::spawn(async move { service.stop(request).await });
+        let response =
+            stop.await.map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(response))
     }
 
     async fn list(
@@ -232,4 +377,13 @@ impl vm_service_server::VmService for VmService {
     ) -> std::result::Result<Response<VmServiceListResponse>, Status> {
         Ok(Response::new(self.list().await?))
     }
+
+    async fn status(
+        &self,
+        request: Request<VmServiceStatusRequest>,
+    ) -> std::result::Result<Response<VmServiceStatusResponse>, Status> {
+        let request = request.into_inner();
+        let request = ValidatedVmServiceStatusRequest::validate(request, None)?;
+        Ok(Response::new(self.status(request).await?))
+    }
 }
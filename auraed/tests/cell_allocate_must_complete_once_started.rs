@@ -0,0 +1,68 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::CellServiceAllocateRequestBuilder;
+use proto::cells::{CellServiceFreeRequest, CellServiceListRequest};
+use std::path::Path;
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_complete_once_started() {
+    skip_if_not_root!("cell_allocate_must_complete_once_started");
+    skip_if_seccomp!("cell_allocate_must_complete_once_started");
+
+    let client = common::auraed_client().await;
+
+    // Make sure auraed is up before timing the allocates
+    let _ = retry!(client.list(CellServiceListRequest {}).await).unwrap();
+
+    // Dropped at increasing points of the allocate
+    for delay_ms in [0, 1, 2, 5, 10, 20] {
+        let request = CellServiceAllocateRequestBuilder::new().build();
+        let cell_name = request.cell.as_ref().unwrap().name.clone();
+
+        let _ = tokio::time::timeout(
+            Duration::from_millis(delay_ms),
+            client.allocate(request.clone()),
+        )
+        .await;
+
+        // A dropped allocate either never started, or ran to completion,
+        // so the cell can be allocated again: an orphaned cgroup would be
+        // rejected as not being a cell.
+        let _ = client
+            .allocate(request)
+            .await
+            .expect("allocate after a dropped allocate must succeed");
+
+        let cgroup = Path::new("/sys/fs/cgroup").join(&cell_name);
+        assert!(cgroup.exists(), "{cgroup:?}");
+
+        let _ = client
+            .free(CellServiceFreeRequest {
+                cell_name,
+                force: true,
+                recursive: false,
+                timeout_ms: 0,
+            })
+            .await
+            .expect("failed to free");
+        assert!(!cgroup.exists(), "{cgroup:?} after {delay_ms}ms");
+    }
+}
@@ -0,0 +1,132 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{free, CellServiceAllocateRequestBuilder};
+use proto::cells::{CellServiceAllocateRequest, CpuController};
+use test_helpers::*;
+
+mod common;
+
+/// Returns `request` with its cell given the cpu `weight`, and `update`.
+fn with_cpu_weight(
+    request: &CellServiceAllocateRequest,
+    weight: u64,
+    update: bool,
+) -> CellServiceAllocateRequest {
+    let mut request = request.clone();
+    request.cell.as_mut().unwrap().cpu =
+        Some(CpuController { weight: Some(weight), ..Default::default() });
+    request.update = update;
+    request
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_only_fail_for_conflicting_specs() {
+    skip_if_not_root!("cell_allocate_must_only_fail_for_conflicting_specs");
+    skip_if_seccomp!("cell_allocate_must_only_fail_for_conflicting_specs");
+
+    let client = common::auraed_client().await;
+
+    let request = with_cpu_weight(
+        &CellServiceAllocateRequestBuilder::new().build(),
+        100,
+        false,
+    );
+    let allocated =
+        retry!(client.allocate(request.clone()).await).unwrap().into_inner();
+    assert!(!allocated.existed);
+
+    // The same spec again is a success
+    let again = client
+        .allocate(request.clone())
+        .await
+        .expect("identical allocate must succeed")
+        .into_inner();
+    assert!(again.existed);
+    assert_eq!(again.cell_name, allocated.cell_name);
+
+    // A different spec is a conflict, naming the differing fields
+    let status = client
+        .allocate(with_cpu_weight(&request, 200, false))
+        .await
+        .expect_err("conflicting allocate must fail");
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    assert!(
+        status.message().contains("cpu.weight: 100 -> 200"),
+        "{}",
+        status.message()
+    );
+
+    // Unless the cell is to be updated
+    let cpu_weight =
+        format!("/sys/fs/cgroup/{}/cpu.weight", allocated.cell_name);
+    let weight = std::fs::read_to_string(&cpu_weight).ok();
+
+    let updated = client
+        .allocate(with_cpu_weight(&request, 200, true))
+        .await
+        .expect("update must succeed")
+        .into_inner();
+    assert!(updated.existed);
+
+    if allocated.cgroup_v2 {
+        assert_ne!(std::fs::read_to_string(&cpu_weight).ok(), weight);
+    }
+
+    // The updated spec is the spec of the cell now
+    let again = client
+        .allocate(with_cpu_weight(&request, 200, false))
+        .await
+        .expect("allocate with the updated spec must succeed")
+        .into_inner();
+    assert!(again.existed);
+
+    // Changes that can not be applied in place are rejected
+    let mut isolated = with_cpu_weight(&request, 200, true);
+    isolated.cell.as_mut().unwrap().isolate_network = true;
+    let status = client
+        .allocate(isolated)
+        .await
+        .expect_err("immutable change must fail");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        status.message().contains("isolate_network: false -> true"),
+        "{}",
+        status.message()
+    );
+
+    free(&client, allocated.cell_name).await;
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_allocate_concurrent_duplicates_once() {
+    skip_if_not_root!("cell_allocate_must_allocate_concurrent_duplicates_once");
+    skip_if_seccomp!("cell_allocate_must_allocate_concurrent_duplicates_once");
+
+    let client = common::auraed_client().await;
+
+    let request = CellServiceAllocateRequestBuilder::new().build();
+    let (first, second) = tokio::join!(
+        async { retry!(client.allocate(request.clone()).await) },
+        async { retry!(client.allocate(request.clone()).await) },
+    );
+    let first = first.expect("first allocate must succeed").into_inner();
+    let second = second.expect("second allocate must succeed").into_inner();
+
+    assert_eq!(first.cell_name, second.cell_name);
+    assert!(first.existed != second.existed, "{first:?} {second:?}");
+
+    free(&client, first.cell_name).await;
+}
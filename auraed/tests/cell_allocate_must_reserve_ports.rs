@@ -0,0 +1,125 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use client::{ErrorDetails, Resource};
+use common::cells::{
+    free, CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceListPortReservationsRequest, PortReservation};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+async fn reservations(client: &client::Client) -> Vec<PortReservation> {
+    client
+        .list_port_reservations(CellServiceListPortReservationsRequest {})
+        .await
+        .expect("failed to list port reservations")
+        .into_inner()
+        .reservations
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_reserve_ports() {
+    skip_if_not_root!("cell_allocate_must_reserve_ports");
+    skip_if_seccomp!("cell_allocate_must_reserve_ports");
+
+    let client = common::auraed_client().await;
+
+    let owner = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .reserve_port(48443)
+                    .reserve_port(48080)
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let reserved = reservations(&client).await;
+    for port in [48080, 48443] {
+        assert!(
+            reserved
+                .contains(&PortReservation { port, cell_name: owner.clone() }),
+            "{reserved:?}"
+        );
+    }
+
+    // Another cell can not reserve a reserved port
+    let status = client
+        .allocate(
+            CellServiceAllocateRequestBuilder::new()
+                .reserve_port(48080)
+                .build(),
+        )
+        .await
+        .expect_err("allocate of a reserved port must fail");
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    assert!(status.message().contains(&owner), "{}", status.message());
+    assert_eq!(
+        ErrorDetails::from_status(&status),
+        Some(ErrorDetails::AlreadyExists { resource: Resource::Port(48080) })
+    );
+
+    // The executables of the owner are told its ports
+    let output = format!("/tmp/ae-ports-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(owner.clone())
+                    .command(format!(
+                        "echo $AURAE_PORTS > {output}.tmp \
+                        && mv {output}.tmp {output}"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let ports = loop {
+        if let Ok(ports) = tokio::fs::read_to_string(&output).await {
+            break ports;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(ports.trim(), "48080,48443");
+    let _ = tokio::fs::remove_file(output).await;
+
+    // The ports are released with the cell
+    free(&client, owner).await;
+
+    let other = client
+        .allocate(
+            CellServiceAllocateRequestBuilder::new()
+                .reserve_port(48080)
+                .build(),
+        )
+        .await
+        .expect("allocate of a released port must succeed")
+        .into_inner()
+        .cell_name;
+    free(&client, other).await;
+}
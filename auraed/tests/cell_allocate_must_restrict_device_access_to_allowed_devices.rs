@@ -0,0 +1,107 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, DeviceRule};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+fn char_device(major: u32, minor: u32, access: &str) -> DeviceRule {
+    DeviceRule {
+        device_type: "c".into(),
+        major: Some(major),
+        minor: Some(minor),
+        access: access.into(),
+    }
+}
+
+/// Allocates a cell allowing /dev/null, and /dev/zero if `allow_zero`,
+/// and returns what a process in it reports when reading /dev/zero.
+async fn read_zero_in_cell(
+    client: &client::Client,
+    allow_zero: bool,
+) -> String {
+    let mut request = CellServiceAllocateRequestBuilder::new();
+    let _ = request.allow_device(char_device(1, 3, "rw"));
+    if allow_zero {
+        let _ = request.allow_device(char_device(1, 5, "r"));
+    }
+
+    let cell_name = retry!(client.allocate(request.build()).await)
+        .unwrap()
+        .into_inner()
+        .cell_name;
+
+    // The cell shares the host's filesystem
+    let output = format!("/tmp/ae-devices-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .command(format!(
+                        "if head -c 1 /dev/zero > /dev/null; \
+                         then echo opened; else echo denied; fi > {output}"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let report = loop {
+        let report =
+            tokio::fs::read_to_string(&output).await.unwrap_or_default();
+        if report.ends_with('\n') {
+            break report;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+    let _ = tokio::fs::remove_file(output).await;
+
+    report.trim().to_string()
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_restrict_device_access_to_allowed_devices() {
+    skip_if_not_root!(
+        "cell_allocate_must_restrict_device_access_to_allowed_devices"
+    );
+    skip_if_seccomp!(
+        "cell_allocate_must_restrict_device_access_to_allowed_devices"
+    );
+
+    let client = common::auraed_client().await;
+
+    assert_eq!(read_zero_in_cell(&client, true).await, "opened");
+    assert_eq!(read_zero_in_cell(&client, false).await, "denied");
+}
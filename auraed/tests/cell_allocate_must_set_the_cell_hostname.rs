@@ -0,0 +1,115 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::CellServiceFreeRequest;
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Allocates a cell with `request`, and returns its name with the hostname
+/// a process in it reports.
+async fn hostname_in_cell(
+    client: &client::Client,
+    request: &mut CellServiceAllocateRequestBuilder,
+) -> (String, String) {
+    let cell_name = retry!(client.allocate(request.build()).await)
+        .unwrap()
+        .into_inner()
+        .cell_name;
+
+    // The cell shares the host's filesystem
+    let output = format!("/tmp/ae-hostname-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .command(format!(
+                        "uname -n > {output}.tmp && mv {output}.tmp {output}"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let hostname = loop {
+        if let Ok(hostname) = tokio::fs::read_to_string(&output).await {
+            break hostname;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name: cell_name.clone(),
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+    let _ = tokio::fs::remove_file(output).await;
+
+    (cell_name, hostname.trim().to_string())
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_allocate_must_set_the_cell_hostname() {
+    skip_if_not_root!("cell_allocate_must_set_the_cell_hostname");
+    skip_if_seccomp!("cell_allocate_must_set_the_cell_hostname");
+
+    let client = common::auraed_client().await;
+
+    let (_, hostname) = hostname_in_cell(
+        &client,
+        CellServiceAllocateRequestBuilder::new()
+            .isolate_uts()
+            .hostname("ae-host.example".into()),
+    )
+    .await;
+    assert_eq!(hostname, "ae-host.example");
+
+    // Without a hostname, the cell is named after itself
+    let (cell_name, hostname) = hostname_in_cell(
+        &client,
+        CellServiceAllocateRequestBuilder::new().isolate_uts(),
+    )
+    .await;
+    assert_eq!(hostname, cell_name);
+
+    // The host's hostname is left alone
+    let host_hostname =
+        std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap();
+    assert_ne!(host_hostname.trim(), "ae-host.example");
+
+    // An invalid hostname is rejected
+    let status = client
+        .allocate(
+            CellServiceAllocateRequestBuilder::new()
+                .hostname("not_a_hostname".into())
+                .build(),
+        )
+        .await
+        .expect_err("invalid hostname must be rejected");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
@@ -0,0 +1,98 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::CellServiceAllocateRequestBuilder;
+use proto::cells::{CellServiceFreeRequest, CellServiceListRequest};
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_free_must_free_nested_cells_only_if_recursive() {
+    skip_if_not_root!("cell_free_must_free_nested_cells_only_if_recursive");
+    skip_if_seccomp!("cell_free_must_free_nested_cells_only_if_recursive");
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Allocate a nested cell, and a cell nested in that one
+    let nested_cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .parent_cell_name(cell_name.clone())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let _double_nested_cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .parent_cell_name(nested_cell_name.clone())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Freeing the cell without recursive must fail, naming the nested cell
+    let status = client
+        .free(CellServiceFreeRequest {
+            cell_name: cell_name.clone(),
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect_err("free without recursive must fail");
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains(&nested_cell_name));
+
+    // Freeing the cell recursively frees all of its nested cells
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name: cell_name.clone(),
+            force: false,
+            recursive: true,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free recursively");
+
+    let list_response = retry!(client.list(CellServiceListRequest {}).await)
+        .unwrap()
+        .into_inner();
+    assert!(list_response.cells.iter().all(|node| node
+        .cell
+        .as_ref()
+        .unwrap()
+        .name
+        != cell_name));
+}
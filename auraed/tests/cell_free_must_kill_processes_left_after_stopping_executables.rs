@@ -0,0 +1,105 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceStopRequest};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Leaves a grandchild running once the executable is stopped, as it is in
+/// a new session and does not have the executable id in its environment.
+const LINGERING: &str = "(env -i setsid sleep 100 &); sleep 100";
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_free_must_kill_processes_left_after_stopping_executables() {
+    skip_if_not_root!(
+        "cell_free_must_kill_processes_left_after_stopping_executables"
+    );
+    skip_if_seccomp!(
+        "cell_free_must_kill_processes_left_after_stopping_executables"
+    );
+
+    let client = common::auraed_client().await;
+
+    // A cell with a lingering grandchild must be freed once it is killed
+    let cell_name = allocate_and_stop(&client, LINGERING).await;
+    let response = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 200,
+        })
+        .await
+        .expect("failed to free")
+        .into_inner();
+    assert!(response.escalated);
+
+    // A cell whose processes all exited must be freed without killing any
+    let cell_name = allocate_and_stop(&client, "sleep 100").await;
+    let response = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free")
+        .into_inner();
+    assert!(!response.escalated);
+}
+
+/// Allocates a cell, and starts and stops an executable running `command`
+/// in it. Returns the name of the cell.
+async fn allocate_and_stop(client: &client::Client, command: &str) -> String {
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let executable_name = format!("ae-lingering-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(command.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let _ = client
+        .stop(CellServiceStopRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name,
+            ..Default::default()
+        })
+        .await
+        .expect("failed to stop");
+
+    cell_name
+}
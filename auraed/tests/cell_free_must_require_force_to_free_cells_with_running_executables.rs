@@ -0,0 +1,107 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceListExecutablesRequest};
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_free_must_require_force_to_free_cells_with_running_executables() {
+    skip_if_not_root!(
+        "cell_free_must_require_force_to_free_cells_with_running_executables"
+    );
+    skip_if_seccomp!(
+        "cell_free_must_require_force_to_free_cells_with_running_executables"
+    );
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Allocate a nested cell
+    let nested_cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .parent_cell_name(cell_name.clone())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Start an executable in the nested cell
+    let executable_name = format!("ae-sleeper-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(nested_cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    // Freeing the parent cell without force must fail
+    let status = client
+        .free(CellServiceFreeRequest {
+            cell_name: cell_name.clone(),
+            force: false,
+            recursive: true,
+            timeout_ms: 0,
+        })
+        .await
+        .expect_err("free without force must fail");
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains(&nested_cell_name));
+    assert!(status.message().contains(&executable_name));
+
+    // The executable must be untouched
+    let executable_names = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(nested_cell_name),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .executable_names;
+    assert_eq!(executable_names, vec![executable_name]);
+
+    // Freeing with force stops the executable and frees the cells
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: true,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to force free");
+}
@@ -0,0 +1,166 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    CellDefinition, CellServiceExportRequest, CellServiceFreeRequest,
+    CellServiceImportRequest, CellServiceListExecutablesRequest,
+    CellsDocument, ExecutableDefinition, ImportAction, ImportChange,
+};
+use test_helpers::*;
+
+mod common;
+
+/// The action and the executable of each change, in order.
+fn actions(changes: &[ImportChange]) -> Vec<(ImportAction, Option<&str>)> {
+    changes
+        .iter()
+        .map(|change| (change.action(), change.executable_name.as_deref()))
+        .collect()
+}
+
+async fn running(client: &client::Client, cell_name: &str) -> Vec<String> {
+    client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.into()),
+        })
+        .await
+        .expect("failed to list executables")
+        .into_inner()
+        .executable_names
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_import_must_only_change_what_differs_from_the_document() {
+    skip_if_not_root!(
+        "cell_import_must_only_change_what_differs_from_the_document"
+    );
+    skip_if_seccomp!(
+        "cell_import_must_only_change_what_differs_from_the_document"
+    );
+
+    let client = common::auraed_client().await;
+
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let start = CellServiceStartRequestBuilder::new()
+        .cell_name(cell_name.clone())
+        .build();
+    let _ = retry!(client.start(start.clone()).await).unwrap();
+
+    // The cell is exported with its executable, as it was started
+    let document = client
+        .export(CellServiceExportRequest {})
+        .await
+        .expect("failed to export")
+        .into_inner()
+        .document
+        .expect("document");
+    let mut definition = document
+        .cells
+        .into_iter()
+        .find(|x| x.cell.as_ref().map(|x| &x.name) == Some(&cell_name))
+        .expect("exported cell");
+    assert_eq!(
+        definition.executables,
+        [ExecutableDefinition {
+            executable: start.executable.clone(),
+            uid: None,
+            gid: None,
+        }]
+    );
+
+    // Importing the export again changes nothing
+    let import = |definitions: Vec<CellDefinition>, dry_run: bool| {
+        CellServiceImportRequest {
+            document: Some(CellsDocument { cells: definitions }),
+            dry_run,
+            prune: false,
+        }
+    };
+    let changes = client
+        .import(import(vec![definition.clone()], false))
+        .await
+        .expect("failed to import")
+        .into_inner()
+        .changes;
+    let executable_name = &start.executable.as_ref().unwrap().name;
+    assert_eq!(
+        actions(&changes),
+        [
+            (ImportAction::Skip, None),
+            (ImportAction::Skip, Some(executable_name.as_str()))
+        ]
+    );
+
+    // A conflicting cell is left as it is, but its missing executables
+    // are started
+    let added = CellServiceStartRequestBuilder::new()
+        .cell_name(cell_name.clone())
+        .build();
+    let added_name = &added.executable.as_ref().unwrap().name;
+    definition.cell.as_mut().unwrap().isolate_network = true;
+    definition.executables.push(ExecutableDefinition {
+        executable: added.executable.clone(),
+        uid: None,
+        gid: None,
+    });
+
+    let changes = client
+        .import(import(vec![definition.clone()], true))
+        .await
+        .expect("failed to dry run the import")
+        .into_inner()
+        .changes;
+    let expected = [
+        (ImportAction::Conflict, None),
+        (ImportAction::Skip, Some(executable_name.as_str())),
+        (ImportAction::Start, Some(added_name.as_str())),
+    ];
+    assert_eq!(actions(&changes), expected);
+    assert!(
+        changes[0].reason.contains("isolate_network: false -> true"),
+        "{}",
+        changes[0].reason
+    );
+    assert!(!running(&client, &cell_name).await.contains(added_name));
+
+    let changes = client
+        .import(import(vec![definition], false))
+        .await
+        .expect("failed to import")
+        .into_inner()
+        .changes;
+    assert_eq!(actions(&changes), expected);
+    assert!(running(&client, &cell_name).await.contains(added_name));
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
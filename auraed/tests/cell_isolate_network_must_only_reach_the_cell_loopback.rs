@@ -0,0 +1,88 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::CellServiceAllocateRequestBuilder;
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceNetCheckRequest, NetCheckAttempt,
+    NetCheckProtocol,
+};
+use test_helpers::*;
+
+mod common;
+
+async fn net_check(
+    client: &client::Client,
+    cell_name: &str,
+    target_address: &str,
+) -> NetCheckAttempt {
+    let response = retry!(
+        client
+            .net_check(CellServiceNetCheckRequest {
+                cell_name: Some(cell_name.into()),
+                target_address: target_address.into(),
+                protocol: NetCheckProtocol::Tcp.into(),
+                timeout_ms: 500,
+                count: 1,
+                tls_server_name: None,
+            })
+            .await
+    )
+    .unwrap()
+    .into_inner();
+
+    response.attempts.into_iter().next().expect("attempt")
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_isolate_network_must_only_reach_the_cell_loopback() {
+    skip_if_not_root!("cell_isolate_network_must_only_reach_the_cell_loopback");
+    skip_if_seccomp!("cell_isolate_network_must_only_reach_the_cell_loopback");
+
+    let client = common::auraed_client().await;
+
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .isolate_network()
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // The loopback of the cell is up: the connection reaches its network
+    // stack, which refuses it as nothing listens on the port
+    let attempt = net_check(&client, &cell_name, "127.0.0.1:1").await;
+    assert!(!attempt.success);
+    assert_eq!(attempt.errno, Some(libc::ECONNREFUSED), "{attempt:?}");
+
+    // Nothing outside of the cell is routable
+    let attempt = net_check(&client, &cell_name, "192.0.2.1:80").await;
+    assert!(!attempt.success);
+    assert_eq!(attempt.errno, Some(libc::ENETUNREACH), "{attempt:?}");
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free cell");
+}
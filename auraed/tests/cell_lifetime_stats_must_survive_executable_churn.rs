@@ -0,0 +1,127 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceListRequest, CellServiceStatsRequest,
+    CellServiceStopRequest, LifetimeStats,
+};
+use test_helpers::*;
+
+mod common;
+
+async fn lifetime_stats(
+    client: &client::Client,
+    cell_name: &str,
+) -> LifetimeStats {
+    client
+        .stats(CellServiceStatsRequest { cell_name: cell_name.into() })
+        .await
+        .expect("failed to get stats")
+        .into_inner()
+        .lifetime_stats
+        .expect("lifetime stats")
+}
+
+async fn free(client: &client::Client, cell_name: &str) {
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name: cell_name.into(),
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_lifetime_stats_must_survive_executable_churn() {
+    skip_if_not_root!("cell_lifetime_stats_must_survive_executable_churn");
+    skip_if_seccomp!("cell_lifetime_stats_must_survive_executable_churn");
+
+    let client = common::auraed_client().await;
+
+    let allocate = CellServiceAllocateRequestBuilder::new().build();
+    let cell_name = retry!(client.allocate(allocate.clone()).await)
+        .unwrap()
+        .into_inner()
+        .cell_name;
+
+    // The same executable is started, and stopped, twice
+    let executable_name = format!("ae-churn-{}", uuid::Uuid::new_v4());
+    for _ in 0..2 {
+        let _ = retry!(
+            client
+                .start(
+                    CellServiceStartRequestBuilder::new()
+                        .cell_name(cell_name.clone())
+                        .executable_name(executable_name.clone())
+                        .command("tail -f /dev/null".into())
+                        .build(),
+                )
+                .await
+        )
+        .unwrap();
+
+        let _ = client
+            .stop(CellServiceStopRequest {
+                cell_name: Some(cell_name.clone()),
+                executable_name: executable_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .expect("failed to stop");
+    }
+
+    let stats = lifetime_stats(&client, &cell_name).await;
+    assert_eq!(stats.executables_started, 2);
+    assert_eq!(stats.executables_stopped, 2);
+    assert_eq!(stats.restarts.len(), 1);
+    assert_eq!(stats.restarts[0].executable_name, executable_name);
+    assert_eq!(stats.restarts[0].restarts, 1);
+    assert!(stats.restarts[0].last_started_ms > 0);
+    // Sampled as the executables stopped
+    assert!(stats.sampled_at_ms > 0);
+
+    // List reports the same counters
+    let listed = client
+        .list(CellServiceListRequest {})
+        .await
+        .expect("failed to list")
+        .into_inner()
+        .cells
+        .into_iter()
+        .find(|x| x.cell.as_ref().map(|x| &x.name) == Some(&cell_name))
+        .expect("listed cell")
+        .lifetime_stats
+        .expect("lifetime stats");
+    assert_eq!(listed.executables_started, 2);
+    assert_eq!(listed.restarts, stats.restarts);
+
+    // A cell allocated again under the same name starts over
+    free(&client, &cell_name).await;
+    let _ = client.allocate(allocate).await.expect("failed to reallocate");
+
+    let stats = lifetime_stats(&client, &cell_name).await;
+    assert_eq!(stats.executables_started, 0);
+    assert_eq!(stats.executables_stopped, 0);
+    assert!(stats.restarts.is_empty());
+
+    free(&client, &cell_name).await;
+}
@@ -17,6 +17,7 @@ use common::cells::CellServiceAllocateRequestBuilder;
 use pretty_assertions::assert_eq;
 use proto::cells::{
     Cell, CellGraphNode, CellServiceListRequest, CellServiceListResponse,
+    NestedAuraedHealth,
 };
 use test_helpers::*;
 
@@ -74,9 +75,15 @@ async fn cells_list_must_list_allocated_cells_recursively() {
     .cell_name;
 
     // List all cells
-    let list_response = retry!(client.list(CellServiceListRequest {}).await)
-        .unwrap()
-        .into_inner();
+    let mut list_response =
+        retry!(client.list(CellServiceListRequest {}).await)
+            .unwrap()
+            .into_inner();
+
+    // The nested auraeds of all cells are serving. Their pids and sockets,
+    // and the usage of the cells, differ between runs, so they are left out
+    // of the comparison.
+    take_serving_nested_auraeds(&mut list_response.cells);
 
     // The expected response
     let mut expected = CellServiceListResponse {
@@ -87,10 +94,21 @@ async fn cells_list_must_list_allocated_cells_recursively() {
                     cpu: None,
                     cpuset: None,
                     memory: None,
+                    device_allow: vec![],
                     isolate_process: false,
                     isolate_network: false,
+                    isolate_uts: false,
+                    hostname: None,
+                    variables: Default::default(),
+                    reserved_ports: Default::default(),
+                    default_uid: None,
+                    default_gid: None,
+                    userns: None,
+                    per_executable_accounting: false,
                 }),
                 children: vec![],
+                nested_auraed: None,
+                lifetime_stats: None,
             },
             CellGraphNode {
                 cell: Some(Cell {
@@ -98,8 +116,17 @@ async fn cells_list_must_list_allocated_cells_recursively() {
                     cpu: None,
                     cpuset: None,
                     memory: None,
+                    device_allow: vec![],
                     isolate_process: false,
                     isolate_network: false,
+                    isolate_uts: false,
+                    hostname: None,
+                    variables: Default::default(),
+                    reserved_ports: Default::default(),
+                    default_uid: None,
+                    default_gid: None,
+                    userns: None,
+                    per_executable_accounting: false,
                 }),
                 children: vec![CellGraphNode {
                     cell: Some(Cell {
@@ -107,8 +134,17 @@ async fn cells_list_must_list_allocated_cells_recursively() {
                         cpu: None,
                         cpuset: None,
                         memory: None,
+                        device_allow: vec![],
                         isolate_process: false,
                         isolate_network: false,
+                        isolate_uts: false,
+                        hostname: None,
+                        variables: Default::default(),
+                        reserved_ports: Default::default(),
+                        default_uid: None,
+                        default_gid: None,
+                        userns: None,
+                        per_executable_accounting: false,
                     }),
                     children: vec![CellGraphNode {
                         cell: Some(Cell {
@@ -116,12 +152,27 @@ async fn cells_list_must_list_allocated_cells_recursively() {
                             cpu: None,
                             cpuset: None,
                             memory: None,
+                            device_allow: vec![],
                             isolate_process: false,
                             isolate_network: false,
+                            isolate_uts: false,
+                            hostname: None,
+                            variables: Default::default(),
+                            reserved_ports: Default::default(),
+                            default_uid: None,
+                            default_gid: None,
+                            userns: None,
+                            per_executable_accounting: false,
                         }),
                         children: vec![],
+                        nested_auraed: None,
+                        lifetime_stats: None,
                     }],
+                    nested_auraed: None,
+                    lifetime_stats: None,
                 }],
+                nested_auraed: None,
+                lifetime_stats: None,
             },
         ],
     };
@@ -132,4 +183,19 @@ async fn cells_list_must_list_allocated_cells_recursively() {
         expected.cells.swap(0, 1);
         assert_eq!(list_response, expected);
     }
-}
\ No newline at end of file
+}
+
+fn take_serving_nested_auraeds(nodes: &mut [CellGraphNode]) {
+    for node in nodes {
+        let nested_auraed =
+            node.nested_auraed.take().expect("cell has a nested auraed");
+        assert_eq!(nested_auraed.health(), NestedAuraedHealth::Serving);
+        assert!(nested_auraed.pid > 0);
+
+        let lifetime_stats =
+            node.lifetime_stats.take().expect("cell has lifetime stats");
+        assert_eq!(lifetime_stats.executables_started, 0);
+
+        take_serving_nested_auraeds(&mut node.children);
+    }
+}
@@ -0,0 +1,122 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::CellServiceAllocateRequestBuilder;
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceNetCheckRequest, NetCheckProtocol,
+};
+use test_helpers::*;
+use tokio::net::TcpListener;
+
+mod common;
+
+fn net_check_request(
+    cell_name: Option<String>,
+    target_address: String,
+) -> CellServiceNetCheckRequest {
+    CellServiceNetCheckRequest {
+        cell_name,
+        target_address,
+        protocol: NetCheckProtocol::Tcp.into(),
+        timeout_ms: 500,
+        count: 1,
+        tls_server_name: None,
+    }
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_net_check_must_check_from_within_the_cell_network_namespace() {
+    skip_if_not_root!(
+        "cell_net_check_must_check_from_within_the_cell_network_namespace"
+    );
+    skip_if_seccomp!(
+        "cell_net_check_must_check_from_within_the_cell_network_namespace"
+    );
+
+    let client = common::auraed_client().await;
+
+    // Listen on the loopback of the host's network namespace
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_address = listener.local_addr().unwrap().to_string();
+
+    // From the host, the listener is reachable
+    let response = retry!(
+        client.net_check(net_check_request(None, target_address.clone())).await
+    )
+    .unwrap()
+    .into_inner();
+    assert_eq!(response.resolved_addresses, vec![target_address.clone()]);
+    assert!(response.attempts[0].success, "{:?}", response.attempts[0]);
+
+    // From a cell sharing the host's network namespace, it is reachable too
+    let shared_cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let response = retry!(
+        client
+            .net_check(net_check_request(
+                Some(shared_cell_name.clone()),
+                target_address.clone(),
+            ))
+            .await
+    )
+    .unwrap()
+    .into_inner();
+    assert!(response.attempts[0].success, "{:?}", response.attempts[0]);
+
+    // From a cell with its own network namespace, the host's loopback
+    // is not reachable
+    let isolated_cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .isolate_network()
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let response = retry!(
+        client
+            .net_check(net_check_request(
+                Some(isolated_cell_name.clone()),
+                target_address.clone(),
+            ))
+            .await
+    )
+    .unwrap()
+    .into_inner();
+    assert!(!response.attempts[0].success);
+    assert!(response.attempts[0].errno.is_some());
+
+    for cell_name in [shared_cell_name, isolated_cell_name] {
+        let _ = client
+            .free(CellServiceFreeRequest {
+                cell_name,
+                force: false,
+                recursive: false,
+                timeout_ms: 0,
+            })
+            .await
+            .expect("failed to free cell");
+    }
+}
@@ -0,0 +1,127 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceListExecutablesRequest,
+    CellServiceQuarantineRequest, CellServiceUnquarantineRequest,
+};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Returns the number of lines printed to `path` so far
+async fn printed_lines(path: &str) -> usize {
+    tokio::fs::read_to_string(path).await.unwrap_or_default().lines().count()
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_quarantine_must_freeze_and_resume_an_executable() {
+    skip_if_not_root!("cell_quarantine_must_freeze_and_resume_an_executable");
+    skip_if_seccomp!("cell_quarantine_must_freeze_and_resume_an_executable");
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Start a printer in the cell, which shares the host's filesystem
+    let output = format!("/tmp/ae-printer-{}", uuid::Uuid::new_v4());
+    let executable_name = format!("ae-printer-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(format!(
+                        "while true; do echo line >> {output}; sleep 0.05; done"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(printed_lines(&output).await > 0, "printer did not print");
+
+    // Quarantine the printer
+    let _ = client
+        .quarantine(CellServiceQuarantineRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name: executable_name.clone(),
+        })
+        .await
+        .expect("failed to quarantine");
+
+    let response = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.executable_names, vec![executable_name.clone()]);
+    assert_eq!(
+        response.quarantined_executable_names,
+        vec![executable_name.clone()]
+    );
+
+    // The printer must be silent while quarantined
+    let quarantined_lines = printed_lines(&output).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(printed_lines(&output).await, quarantined_lines);
+
+    // Unquarantine the printer, and its output must resume
+    let _ = client
+        .unquarantine(CellServiceUnquarantineRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name: executable_name.clone(),
+        })
+        .await
+        .expect("failed to unquarantine");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(printed_lines(&output).await > quarantined_lines);
+
+    let response = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.quarantined_executable_names.is_empty());
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+    let _ = tokio::fs::remove_file(output).await;
+}
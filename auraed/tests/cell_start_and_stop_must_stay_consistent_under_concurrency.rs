@@ -0,0 +1,135 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use futures_util::future::join_all;
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceListExecutablesRequest,
+    CellServiceStopRequest,
+};
+use std::time::Duration;
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+const CELLS: usize = 4;
+const PAIRS: usize = 100;
+
+/// Long enough for every pair to complete on a loaded machine, and short
+/// enough to fail on a deadlock rather than hang.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_and_stop_must_stay_consistent_under_concurrency() {
+    skip_if_not_root!(
+        "cell_start_and_stop_must_stay_consistent_under_concurrency"
+    );
+    skip_if_seccomp!(
+        "cell_start_and_stop_must_stay_consistent_under_concurrency"
+    );
+
+    let client = common::auraed_client().await;
+
+    let mut cell_names = vec![];
+    for _ in 0..CELLS {
+        let cell_name = retry!(
+            client
+                .allocate(CellServiceAllocateRequestBuilder::new().build())
+                .await
+        )
+        .unwrap()
+        .into_inner()
+        .cell_name;
+        cell_names.push(cell_name);
+    }
+
+    // Each pair starts an executable and stops it, with the pairs of all
+    // cells running concurrently
+    let pairs = (0..PAIRS).map(|i| {
+        let client = &client;
+        let cell_name = cell_names[i % CELLS].clone();
+        async move {
+            let executable_name = format!("ae-stress-{i}");
+            let _ = client
+                .start(
+                    CellServiceStartRequestBuilder::new()
+                        .cell_name(cell_name.clone())
+                        .executable_name(executable_name.clone())
+                        .build(),
+                )
+                .await
+                .unwrap_or_else(|e| panic!("failed to start {i}: {e:?}"));
+            let _ = client
+                .stop(CellServiceStopRequest {
+                    cell_name: Some(cell_name),
+                    executable_name,
+                    ..Default::default()
+                })
+                .await
+                .unwrap_or_else(|e| panic!("failed to stop {i}: {e:?}"));
+        }
+    });
+    let _ = tokio::time::timeout(TIMEOUT, join_all(pairs))
+        .await
+        .expect("start and stop pairs deadlocked");
+
+    // Of concurrent starts of the same name, exactly one wins
+    let contenders = (0..8).map(|_| {
+        client.start(
+            CellServiceStartRequestBuilder::new()
+                .cell_name(cell_names[0].clone())
+                .executable_name("ae-contended".into())
+                .build(),
+        )
+    });
+    let results = tokio::time::timeout(TIMEOUT, join_all(contenders))
+        .await
+        .expect("concurrent starts deadlocked");
+    assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+    for status in results.iter().filter_map(|res| res.as_ref().err()) {
+        assert_eq!(status.code(), Code::AlreadyExists, "{status:?}");
+    }
+
+    // No executable is lost or left behind
+    for (i, cell_name) in cell_names.iter().enumerate() {
+        let executable_names = client
+            .list_executables(CellServiceListExecutablesRequest {
+                cell_name: Some(cell_name.clone()),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .executable_names;
+        let expected: Vec<String> =
+            if i == 0 { vec!["ae-contended".into()] } else { vec![] };
+        assert_eq!(executable_names, expected);
+    }
+
+    for cell_name in cell_names {
+        let _ = client
+            .free(CellServiceFreeRequest {
+                cell_name,
+                force: true,
+                recursive: false,
+                timeout_ms: 0,
+            })
+            .await
+            .expect("failed to free");
+    }
+}
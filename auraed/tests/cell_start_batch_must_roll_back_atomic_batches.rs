@@ -0,0 +1,126 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder, DAEMON,
+};
+use proto::cells::{
+    executable_start_result::Outcome, CellServiceFreeRequest,
+    CellServiceListExecutablesRequest, CellServiceStartBatchRequest,
+    Executable,
+};
+use test_helpers::*;
+
+mod common;
+
+fn executable(name: &str, forbidden_daemon: bool) -> Executable {
+    let mut builder = CellServiceStartRequestBuilder::new();
+    let _ = builder.cell_name(String::new()).executable_name(name.into());
+    if forbidden_daemon {
+        let _ = builder.command(DAEMON.into()).forbid_daemonize();
+    }
+    builder.build().executable.expect("executable")
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_batch_must_roll_back_atomic_batches() {
+    skip_if_not_root!("cell_start_batch_must_roll_back_atomic_batches");
+    skip_if_seccomp!("cell_start_batch_must_roll_back_atomic_batches");
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let id = uuid::Uuid::new_v4();
+    let first = format!("ae-first-{id}");
+    let failing = format!("ae-failing-{id}");
+    let last = format!("ae-last-{id}");
+    let batch = |atomic| CellServiceStartBatchRequest {
+        cell_name: Some(cell_name.clone()),
+        executables: vec![
+            executable(&first, false),
+            executable(&failing, true),
+            executable(&last, false),
+        ],
+        uid: None,
+        gid: None,
+        atomic,
+        ready_after_ms: 0,
+    };
+
+    // Names must be unique within the batch
+    let mut duplicates = batch(true);
+    duplicates.executables[2].name = first.clone();
+    let status = client
+        .start_batch(duplicates)
+        .await
+        .expect_err("duplicate names must fail the batch");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    // An atomic batch starts none if one fails
+    let results = retry!(client.start_batch(batch(true)).await)
+        .unwrap()
+        .into_inner()
+        .results;
+    assert_eq!(results.len(), 3);
+    assert!(results
+        .iter()
+        .all(|result| matches!(result.outcome, Some(Outcome::Error(_)))));
+
+    let running = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .executable_names;
+    assert!(running.is_empty(), "{running:?}");
+
+    // Otherwise, the others keep running
+    let results = client.start_batch(batch(false)).await.unwrap().into_inner();
+    let outcomes: Vec<_> = results
+        .results
+        .into_iter()
+        .map(|result| matches!(result.outcome, Some(Outcome::Pid(_))))
+        .collect();
+    assert_eq!(outcomes, vec![true, false, true]);
+
+    let running = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .executable_names;
+    assert_eq!(running, vec![first, last]);
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -0,0 +1,124 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    executable_start_result::Outcome, CellServiceFreeRequest,
+    CellServiceStartBatchRequest, Executable,
+};
+use test_helpers::*;
+
+mod common;
+
+fn executable(name: &str, command: &str, depends_on: &[&str]) -> Executable {
+    let mut builder = CellServiceStartRequestBuilder::new();
+    let _ = builder
+        .cell_name(String::new())
+        .executable_name(name.into())
+        .command(command.into());
+    let mut executable = builder.build().executable.expect("executable");
+    executable.depends_on = depends_on.iter().map(|x| x.to_string()).collect();
+    executable
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_batch_must_start_dependencies_first() {
+    skip_if_not_root!("cell_start_batch_must_start_dependencies_first");
+    skip_if_seccomp!("cell_start_batch_must_start_dependencies_first");
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let id = uuid::Uuid::new_v4();
+    let app = format!("ae-app-{id}");
+    let db = format!("ae-db-{id}");
+    let batch = |executables| CellServiceStartBatchRequest {
+        cell_name: Some(cell_name.clone()),
+        executables,
+        uid: None,
+        gid: None,
+        atomic: false,
+        ready_after_ms: 200,
+    };
+
+    // Cycles are rejected before any executable starts
+    let status = client
+        .start_batch(batch(vec![
+            executable(&app, "tail -f /dev/null", &[&db]),
+            executable(&db, "tail -f /dev/null", &[&app]),
+        ]))
+        .await
+        .expect_err("a cycle must fail the batch");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(
+        status.message().contains(&format!("{app} -> {db} -> {app}")),
+        "{}",
+        status.message()
+    );
+
+    // The dependency starts first, even if listed last
+    let response = retry!(
+        client
+            .start_batch(batch(vec![
+                executable(&app, "tail -f /dev/null", &[&db]),
+                executable(&db, "tail -f /dev/null", &[]),
+            ]))
+            .await
+    )
+    .unwrap()
+    .into_inner();
+    assert_eq!(response.start_order, [db.clone(), app.clone()]);
+    assert!(response
+        .results
+        .iter()
+        .all(|result| matches!(result.outcome, Some(Outcome::Pid(_)))));
+
+    // A dependency that exits before it is ready aborts its dependents
+    let app = format!("ae-app-exiting-{id}");
+    let db = format!("ae-db-exiting-{id}");
+    let response = client
+        .start_batch(batch(vec![
+            executable(&db, "exit 1", &[]),
+            executable(&app, "tail -f /dev/null", &[&db]),
+        ]))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.start_order, [db.clone()]);
+    let Some(Outcome::Error(error)) = &response.results[1].outcome else {
+        panic!("dependent must not start: {:?}", response.results[1]);
+    };
+    assert!(error.contains(&format!("dependency '{db}'")), "{error}");
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
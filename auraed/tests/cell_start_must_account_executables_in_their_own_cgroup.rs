@@ -0,0 +1,118 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    CellServiceFreeRequest, CellServiceListExecutablesRequest,
+    CellServiceStopRequest, KillMode,
+};
+use std::path::Path;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_account_executables_in_their_own_cgroup() {
+    skip_if_not_root!(
+        "cell_start_must_account_executables_in_their_own_cgroup"
+    );
+    skip_if_seccomp!("cell_start_must_account_executables_in_their_own_cgroup");
+    if !Path::new("/sys/fs/cgroup/cgroup.controllers").exists() {
+        skip!(
+            "{} requires cgroup v2. Skipping test.",
+            "cell_start_must_account_executables_in_their_own_cgroup"
+        );
+    }
+
+    let client = common::auraed_client().await;
+
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .per_executable_accounting()
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let executable_name = format!("ae-sleeper-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    // The executable runs in a leaf of its own, next to the nested auraed
+    let leaf = Path::new("/sys/fs/cgroup")
+        .join(&cell_name)
+        .join(format!("_{executable_name}"));
+    let procs = std::fs::read_to_string(leaf.join("cgroup.procs"))
+        .expect("failed to read the cgroup of the executable");
+    assert_eq!(procs.lines().count(), 1, "{procs}");
+
+    let listed = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .expect("failed to list executables")
+        .into_inner();
+    let status = listed
+        .executables
+        .iter()
+        .find(|status| status.name == executable_name)
+        .expect("executable is listed");
+    assert!(
+        status.memory.as_ref().is_some_and(|memory| memory.current.is_some()),
+        "{status:?}"
+    );
+    assert!(
+        status.cpu.as_ref().is_some_and(|cpu| cpu.usage_usec.is_some()),
+        "{status:?}"
+    );
+
+    // The leaf is removed once the executable stops
+    let _ = client
+        .stop(CellServiceStopRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name,
+            kill_mode: KillMode::ProcessGroup.into(),
+        })
+        .await
+        .expect("failed to stop");
+    assert!(!leaf.exists());
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -0,0 +1,115 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceUpdateRequest};
+use std::path::Path;
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+fn read(info: &Path, file: &str) -> String {
+    std::fs::read_to_string(info.join(file))
+        .unwrap_or_else(|e| panic!("failed to read {file}: {e}"))
+        .trim()
+        .to_string()
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_expose_the_cell_info_to_executables() {
+    skip_if_not_root!("cell_start_must_expose_the_cell_info_to_executables");
+    skip_if_seccomp!("cell_start_must_expose_the_cell_info_to_executables");
+
+    let client = common::auraed_client().await;
+
+    let allocated = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .variable("team".into(), "storage".into())
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner();
+    let cell_name = allocated.cell_name;
+
+    // The cell shares the host's filesystem
+    let output = format!("/tmp/ae-cell-info-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .command(format!(
+                        "echo $AURAE_CELL_INFO > {output}.tmp \
+                        && mv {output}.tmp {output}"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let info = loop {
+        if let Ok(info) = tokio::fs::read_to_string(&output).await {
+            break info;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    let info = Path::new(info.trim()).to_path_buf();
+    assert!(info.ends_with(format!("cells/{cell_name}/info")), "{info:?}");
+
+    assert_eq!(read(&info, "cell_name"), cell_name);
+    assert_eq!(read(&info, "memory.max"), "max");
+    let variables: serde_json::Value =
+        serde_json::from_str(&read(&info, "variables.json")).unwrap();
+    assert_eq!(variables, serde_json::json!({ "team": "storage" }));
+
+    // Updates are reflected
+    if allocated.cgroup_v2 {
+        let _ = client
+            .update(CellServiceUpdateRequest {
+                cell_name: cell_name.clone(),
+                memory_max: Some(256 << 20),
+                ..Default::default()
+            })
+            .await
+            .expect("failed to update");
+        assert_eq!(read(&info, "memory.max"), (256 << 20).to_string());
+    }
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+    let _ = tokio::fs::remove_file(output).await;
+
+    // The info is removed with the cell
+    assert!(!info.exists());
+}
@@ -0,0 +1,101 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceListRequest};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Double forks processes which exit, orphaning them to the pid 1 of the
+/// pid namespace of the cell, i.e. its nested auraed.
+const DOUBLE_FORKS: &str =
+    "for i in 1 2 3 4 5; do sh -c 'true &'; done; sleep 10";
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_reap_processes_orphaned_in_an_isolated_cell() {
+    skip_if_not_root!(
+        "cell_start_must_reap_processes_orphaned_in_an_isolated_cell"
+    );
+    skip_if_seccomp!(
+        "cell_start_must_reap_processes_orphaned_in_an_isolated_cell"
+    );
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell, whose nested auraed is pid 1 of a new pid namespace
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .isolate_process()
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .command(DOUBLE_FORKS.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let cells = client
+        .list(CellServiceListRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .cells;
+    let nested_auraed_pid = cells
+        .iter()
+        .find(|node| node.cell.as_ref().unwrap().name == cell_name)
+        .and_then(|node| node.nested_auraed.as_ref())
+        .expect("cell has a nested auraed")
+        .pid;
+
+    // No orphan is left as a zombie
+    let zombies: Vec<_> = procfs::process::all_processes()
+        .unwrap()
+        .filter_map(|process| process.ok()?.stat().ok())
+        .filter(|stat| stat.ppid == nested_auraed_pid && stat.state == 'Z')
+        .map(|stat| stat.pid)
+        .collect();
+    assert_eq!(zombies, Vec::<i32>::new());
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -0,0 +1,120 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    free, CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{IdMapping, UserNamespace};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Starts an executable in the cell, as `uid` if set, and returns the uid and
+/// gid it reports in a file, which the cell shares with the host.
+async fn run(
+    client: &client::Client,
+    cell_name: &str,
+    uid: Option<u32>,
+) -> String {
+    let output = format!("/tmp/ae-ids-{}", uuid::Uuid::new_v4());
+    let mut request = CellServiceStartRequestBuilder::new();
+    let _ = request.cell_name(cell_name.into()).command(format!(
+        "echo $(id -u) $(id -g) > {output}.tmp && mv {output}.tmp {output}"
+    ));
+    if let Some(uid) = uid {
+        let _ = request.uid(uid).gid(uid);
+    }
+    let _ = retry!(client.start(request.build()).await).unwrap();
+
+    let mut attempts = 0;
+    let written = loop {
+        if let Ok(written) = tokio::fs::read_to_string(&output).await {
+            break written;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    let _ = tokio::fs::remove_file(output).await;
+    written.trim().to_string()
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_run_as_the_default_ids_of_the_cell() {
+    skip_if_not_root!("cell_start_must_run_as_the_default_ids_of_the_cell");
+    skip_if_seccomp!("cell_start_must_run_as_the_default_ids_of_the_cell");
+
+    let client = common::auraed_client().await;
+
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .default_ids(65534, 65534)
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    assert_eq!(run(&client, &cell_name, None).await, "65534 65534");
+    // The ids of the start request override those of the cell
+    assert_eq!(run(&client, &cell_name, Some(0)).await, "0 0");
+
+    free(&client, cell_name).await;
+
+    // A user namespace needs both maps
+    let mapping =
+        || IdMapping { container_id: 0, host_id: 100000, size: 65536 };
+    let status = client
+        .allocate(
+            CellServiceAllocateRequestBuilder::new()
+                .userns(UserNamespace {
+                    uid_map: vec![mapping()],
+                    gid_map: vec![],
+                })
+                .build(),
+        )
+        .await
+        .expect_err("a user namespace without a gid map must be rejected");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(status.message().contains("userns.gid_map"), "{status:?}");
+
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .userns(UserNamespace {
+                        uid_map: vec![mapping()],
+                        gid_map: vec![mapping()],
+                    })
+                    .default_ids(1000, 1000)
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // The ids are those of the namespace
+    assert_eq!(run(&client, &cell_name, None).await, "1000 1000");
+
+    free(&client, cell_name).await;
+}
@@ -0,0 +1,108 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::CellServiceFreeRequest;
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_substitute_cell_variables() {
+    skip_if_not_root!("cell_start_must_substitute_cell_variables");
+    skip_if_seccomp!("cell_start_must_substitute_cell_variables");
+
+    let client = common::auraed_client().await;
+
+    let cell_name = retry!(
+        client
+            .allocate(
+                CellServiceAllocateRequestBuilder::new()
+                    .variable("greeting".into(), "hello".into())
+                    .build()
+            )
+            .await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // The cell shares the host's filesystem
+    let output = format!("/tmp/ae-variables-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .command(format!(
+                        "echo ${{greeting}} ${{cell_name}} '$${{greeting}}' \
+                        > {output}.tmp && mv {output}.tmp {output}"
+                    ))
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let echoed = loop {
+        if let Ok(echoed) = tokio::fs::read_to_string(&output).await {
+            break echoed;
+        }
+        attempts += 1;
+        assert!(attempts < 50, "executable did not report");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(echoed.trim(), format!("hello {cell_name} ${{greeting}}"));
+
+    // Nothing is started if a variable is unresolved
+    let status = client
+        .start(
+            CellServiceStartRequestBuilder::new()
+                .cell_name(cell_name.clone())
+                .command("echo ${greeting} ${user} ${host}".into())
+                .build(),
+        )
+        .await
+        .expect_err("unresolved variables must fail the start");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    assert!(status.message().contains("user, host"), "{status:?}");
+
+    // The built-in variables can not be overridden
+    let status = client
+        .allocate(
+            CellServiceAllocateRequestBuilder::new()
+                .variable("cell_name".into(), "other".into())
+                .build(),
+        )
+        .await
+        .expect_err("built-in variables must be rejected");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+    let _ = tokio::fs::remove_file(output).await;
+}
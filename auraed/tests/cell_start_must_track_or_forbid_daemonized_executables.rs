@@ -0,0 +1,102 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder, DAEMON,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceListExecutablesRequest};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_start_must_track_or_forbid_daemonized_executables() {
+    skip_if_not_root!("cell_start_must_track_or_forbid_daemonized_executables");
+    skip_if_seccomp!("cell_start_must_track_or_forbid_daemonized_executables");
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Start a daemon, which must keep running once its parent has exited
+    let executable_name = format!("ae-daemon-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(DAEMON.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let response = client
+        .list_executables(CellServiceListExecutablesRequest {
+            cell_name: Some(cell_name.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.executable_names, vec![executable_name]);
+
+    // Start a daemon which forbids daemonizing, which must fail
+    let status = client
+        .start(
+            CellServiceStartRequestBuilder::new()
+                .cell_name(cell_name.clone())
+                .command(DAEMON.into())
+                .forbid_daemonize()
+                .build(),
+        )
+        .await
+        .expect_err("daemonizing must fail the start");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    // One which exits before the grace period without daemonizing starts
+    let response = client
+        .start(
+            CellServiceStartRequestBuilder::new()
+                .cell_name(cell_name.clone())
+                .command("exit 0".into())
+                .forbid_daemonize()
+                .build(),
+        )
+        .await
+        .expect("exiting early must not fail the start")
+        .into_inner();
+    assert!(response.pid > 0);
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -0,0 +1,138 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{
+    CellServiceAllocateRequest, CellServiceFreeRequest, CellServiceStopRequest,
+    CellServiceStopResponse, KillMode,
+};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Leaves a grandchild in the process group of the executable, and a
+/// daemon in a session of its own, neither of which has the executable id in
+/// its environment.
+const DOUBLE_FORK: &str =
+    "(env -i sleep 100 &); (env -i setsid sleep 100 &); exec sleep 100";
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_stop_must_kill_the_processes_of_the_kill_mode() {
+    skip_if_not_root!("cell_stop_must_kill_the_processes_of_the_kill_mode");
+    skip_if_seccomp!("cell_stop_must_kill_the_processes_of_the_kill_mode");
+
+    let client = common::auraed_client().await;
+
+    let cell = CellServiceAllocateRequestBuilder::new().build();
+    let accounted = CellServiceAllocateRequestBuilder::new()
+        .per_executable_accounting()
+        .build();
+
+    // Killing the process orphans both the grandchild and the daemon
+    let (cell_name, response) = stop(&client, &cell, KillMode::Process).await;
+    let response = response.expect("failed to stop");
+    assert_eq!(response.orphan_pids.len(), 2, "{response:?}");
+    assert!(free(&client, cell_name).await);
+
+    // Killing the process group orphans the daemon only
+    let (cell_name, response) =
+        stop(&client, &cell, KillMode::ProcessGroup).await;
+    let response = response.expect("failed to stop");
+    assert_eq!(response.orphan_pids.len(), 1, "{response:?}");
+    assert!(free(&client, cell_name).await);
+
+    // Killing the leaf cgroup of the executable leaves no process for the
+    // cell to kill when freed
+    let (cell_name, response) =
+        stop(&client, &accounted, KillMode::Cgroup).await;
+    let response = response.expect("failed to stop");
+    assert!(response.orphan_pids.is_empty(), "{response:?}");
+    assert!(!free(&client, cell_name).await);
+
+    // Without per executable accounting, the executable has no leaf
+    let (cell_name, response) = stop(&client, &cell, KillMode::Cgroup).await;
+    let status = response.expect_err("stopped an executable without a leaf");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    let _ = free(&client, cell_name).await;
+
+    // The cgroup can only be killed for an executable in a cell
+    let status = client
+        .stop(CellServiceStopRequest {
+            cell_name: None,
+            executable_name: format!("ae-double-fork-{}", uuid::Uuid::new_v4()),
+            kill_mode: KillMode::Cgroup.into(),
+        })
+        .await
+        .expect_err("stopped the cgroup of an executable outside of a cell");
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+}
+
+/// Allocates a cell, and starts an executable which double-forks in it,
+/// and stops it with `kill_mode`. Returns the name of the cell.
+async fn stop(
+    client: &client::Client,
+    cell: &CellServiceAllocateRequest,
+    kill_mode: KillMode,
+) -> (String, Result<CellServiceStopResponse, tonic::Status>) {
+    let cell_name = retry!(client.allocate(cell.clone()).await)
+        .unwrap()
+        .into_inner()
+        .cell_name;
+
+    let executable_name = format!("ae-double-fork-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(DOUBLE_FORK.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let response = client
+        .stop(CellServiceStopRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name,
+            kill_mode: kill_mode.into(),
+        })
+        .await
+        .map(|response| response.into_inner());
+
+    (cell_name, response)
+}
+
+/// Frees the cell, returning true if processes were left to be killed.
+async fn free(client: &client::Client, cell_name: String) -> bool {
+    client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 200,
+        })
+        .await
+        .expect("failed to free")
+        .into_inner()
+        .escalated
+}
@@ -0,0 +1,118 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceUpdateRequest};
+use test_helpers::*;
+
+mod common;
+
+fn read(cgroup: &str, file: &str) -> String {
+    std::fs::read_to_string(format!("{cgroup}/{file}"))
+        .unwrap_or_else(|e| panic!("failed to read {file}: {e}"))
+        .trim()
+        .to_string()
+}
+
+#[test_helpers_macros::shared_runtime_test]
+async fn cell_update_must_write_cgroup_values_to_a_running_cell() {
+    skip_if_not_root!("cell_update_must_write_cgroup_values_to_a_running_cell");
+    skip_if_seccomp!("cell_update_must_write_cgroup_values_to_a_running_cell");
+
+    let client = common::auraed_client().await;
+
+    let allocated = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner();
+    let cell_name = allocated.cell_name;
+
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .build()
+            )
+            .await
+    )
+    .unwrap();
+
+    let update = CellServiceUpdateRequest {
+        cell_name: cell_name.clone(),
+        memory_max: Some(256 << 20),
+        pids_max: Some(64),
+        ..Default::default()
+    };
+
+    if !allocated.cgroup_v2 {
+        let status = client
+            .update(update)
+            .await
+            .expect_err("update must be unsupported on cgroup v1");
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    } else {
+        let cgroup = format!("/sys/fs/cgroup/{cell_name}");
+        let cpu_max = read(&cgroup, "cpu.max");
+
+        let response = client
+            .update(update.clone())
+            .await
+            .expect("failed to update")
+            .into_inner();
+        assert!(response.memory_events.is_some());
+
+        // The values in the request are written, and the others left alone
+        assert_eq!(read(&cgroup, "memory.max"), (256 << 20).to_string());
+        assert_eq!(read(&cgroup, "pids.max"), "64");
+        assert_eq!(read(&cgroup, "cpu.max"), cpu_max);
+
+        // The executable keeps running
+        assert_ne!(read(&cgroup, "pids.current"), "0");
+
+        // A frozen cell is not updated
+        std::fs::write(format!("{cgroup}/cgroup.freeze"), "1").unwrap();
+        let status = client
+            .update(update)
+            .await
+            .expect_err("update of a frozen cell must fail");
+        std::fs::write(format!("{cgroup}/cgroup.freeze"), "0").unwrap();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    // A cell that does not exist is not found
+    let status = client
+        .update(CellServiceUpdateRequest {
+            cell_name: format!("ae-missing-{}", uuid::Uuid::new_v4()),
+            pids_max: Some(64),
+            ..Default::default()
+        })
+        .await
+        .expect_err("update of a missing cell must fail");
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -14,9 +14,29 @@
 \* -------------------------------------------------------------------------- */
 #![allow(unused)]
 
+use client::{cells::cell_service::CellServiceClient, Client};
 use proto::cells::{
-    Cell, CellServiceAllocateRequest, CellServiceStartRequest, Executable,
+    Cell, CellServiceAllocateRequest, CellServiceFreeRequest,
+    CellServiceStartRequest, DeviceRule, Executable, UserNamespace,
 };
+use std::collections::HashMap;
+
+/// A command which forks a process which forks `sleep` into a new session,
+/// and exits, leaving it running as a daemon.
+pub const DAEMON: &str = "(setsid sleep 10 &); exit 0";
+
+/// Frees the cell `cell_name`, killing the processes left in it.
+pub async fn free(client: &Client, cell_name: String) {
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: true,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
 
 fn generate_cell_name(parent_name: Option<&str>) -> String {
     if let Some(parent_name) = parent_name {
@@ -29,11 +49,34 @@ fn generate_cell_name(parent_name: Option<&str>) -> String {
 struct CellBuilder {
     parent: Option<String>,
     isolate_process: bool,
+    isolate_network: bool,
+    isolate_uts: bool,
+    hostname: Option<String>,
+    device_allow: Vec<DeviceRule>,
+    variables: HashMap<String, String>,
+    reserved_ports: Vec<u32>,
+    default_uid: Option<u32>,
+    default_gid: Option<u32>,
+    userns: Option<UserNamespace>,
+    per_executable_accounting: bool,
 }
 
 impl CellBuilder {
     pub fn new() -> Self {
-        Self { parent: None, isolate_process: false }
+        Self {
+            parent: None,
+            isolate_process: false,
+            isolate_network: false,
+            isolate_uts: false,
+            hostname: None,
+            device_allow: vec![],
+            variables: HashMap::new(),
+            reserved_ports: vec![],
+            default_uid: None,
+            default_gid: None,
+            userns: None,
+            per_executable_accounting: false,
+        }
     }
 
     pub fn parent_cell_name(&mut self, parent_cell_name: String) -> &mut Self {
@@ -46,6 +89,52 @@ impl CellBuilder {
         self
     }
 
+    pub fn isolate_network(&mut self) -> &mut Self {
+        self.isolate_network = true;
+        self
+    }
+
+    pub fn isolate_uts(&mut self) -> &mut Self {
+        self.isolate_uts = true;
+        self
+    }
+
+    pub fn hostname(&mut self, hostname: String) -> &mut Self {
+        self.hostname = Some(hostname);
+        self
+    }
+
+    pub fn allow_device(&mut self, rule: DeviceRule) -> &mut Self {
+        self.device_allow.push(rule);
+        self
+    }
+
+    pub fn variable(&mut self, name: String, value: String) -> &mut Self {
+        let _ = self.variables.insert(name, value);
+        self
+    }
+
+    pub fn reserve_port(&mut self, port: u32) -> &mut Self {
+        self.reserved_ports.push(port);
+        self
+    }
+
+    pub fn default_ids(&mut self, uid: u32, gid: u32) -> &mut Self {
+        self.default_uid = Some(uid);
+        self.default_gid = Some(gid);
+        self
+    }
+
+    pub fn userns(&mut self, userns: UserNamespace) -> &mut Self {
+        self.userns = Some(userns);
+        self
+    }
+
+    pub fn per_executable_accounting(&mut self) -> &mut Self {
+        self.per_executable_accounting = true;
+        self
+    }
+
     pub fn build(&self) -> Cell {
         let cell_name = generate_cell_name(self.parent.as_deref());
         Cell {
@@ -53,8 +142,17 @@ impl CellBuilder {
             cpu: None,
             cpuset: None,
             memory: None,
-            isolate_network: false,
+            device_allow: self.device_allow.clone(),
+            isolate_network: self.isolate_network,
             isolate_process: self.isolate_process,
+            isolate_uts: self.isolate_uts,
+            hostname: self.hostname.clone(),
+            variables: self.variables.clone(),
+            reserved_ports: self.reserved_ports.clone(),
+            default_uid: self.default_uid,
+            default_gid: self.default_gid,
+            userns: self.userns.clone(),
+            per_executable_accounting: self.per_executable_accounting,
         }
     }
 }
@@ -78,8 +176,56 @@ impl CellServiceAllocateRequestBuilder {
         self
     }
 
+    pub fn isolate_network(&mut self) -> &mut Self {
+        let _ = self.cell_builder.isolate_network();
+        self
+    }
+
+    pub fn isolate_uts(&mut self) -> &mut Self {
+        let _ = self.cell_builder.isolate_uts();
+        self
+    }
+
+    pub fn hostname(&mut self, hostname: String) -> &mut Self {
+        let _ = self.cell_builder.hostname(hostname);
+        self
+    }
+
+    pub fn allow_device(&mut self, rule: DeviceRule) -> &mut Self {
+        let _ = self.cell_builder.allow_device(rule);
+        self
+    }
+
+    pub fn variable(&mut self, name: String, value: String) -> &mut Self {
+        let _ = self.cell_builder.variable(name, value);
+        self
+    }
+
+    pub fn reserve_port(&mut self, port: u32) -> &mut Self {
+        let _ = self.cell_builder.reserve_port(port);
+        self
+    }
+
+    pub fn default_ids(&mut self, uid: u32, gid: u32) -> &mut Self {
+        let _ = self.cell_builder.default_ids(uid, gid);
+        self
+    }
+
+    pub fn userns(&mut self, userns: UserNamespace) -> &mut Self {
+        let _ = self.cell_builder.userns(userns);
+        self
+    }
+
+    pub fn per_executable_accounting(&mut self) -> &mut Self {
+        let _ = self.cell_builder.per_executable_accounting();
+        self
+    }
+
     pub fn build(&self) -> CellServiceAllocateRequest {
-        CellServiceAllocateRequest { cell: Some(self.cell_builder.build()) }
+        CellServiceAllocateRequest {
+            cell: Some(self.cell_builder.build()),
+            update: false,
+        }
     }
 }
 
@@ -87,6 +233,7 @@ struct ExecutableBuilder {
     name: String,
     command: String,
     description: String,
+    forbid_daemonize: bool,
 }
 
 impl ExecutableBuilder {
@@ -95,6 +242,7 @@ impl ExecutableBuilder {
             name: format!("ae-sleeper-{}", uuid::Uuid::new_v4()),
             command: "tail -f /dev/null".to_string(),
             description: String::from("description"),
+            forbid_daemonize: false,
         }
     }
 
@@ -103,11 +251,31 @@ impl ExecutableBuilder {
         self
     }
 
+    pub fn command(&mut self, command: String) -> &mut Self {
+        self.command = command;
+        self
+    }
+
+    pub fn forbid_daemonize(&mut self) -> &mut Self {
+        self.forbid_daemonize = true;
+        self
+    }
+
     pub fn build(&self) -> Executable {
         Executable {
             name: self.name.clone(),
             command: self.command.clone(),
             description: self.description.clone(),
+            forbid_daemonize: self.forbid_daemonize,
+            disable_log_redaction: false,
+            seccomp_profile: String::new(),
+            no_new_privs: false,
+            capabilities: None,
+            rootfs: String::new(),
+            output_limit: None,
+            mounts: vec![],
+            depends_on: vec![],
+            readiness_probe: None,
         }
     }
 }
@@ -139,6 +307,16 @@ impl CellServiceStartRequestBuilder {
         self
     }
 
+    pub fn command(&mut self, command: String) -> &mut Self {
+        let _ = self.executable_builder.command(command);
+        self
+    }
+
+    pub fn forbid_daemonize(&mut self) -> &mut Self {
+        let _ = self.executable_builder.forbid_daemonize();
+        self
+    }
+
     pub fn uid(&mut self, uid: u32) -> &mut Self {
         self.uid = Some(uid);
         self
@@ -18,14 +18,17 @@ use backoff::{
     ExponentialBackoffBuilder, SystemClock,
 };
 use client::{
-    AuraeConfig, AuraeSocket, AuthConfig, Client, ClientError, SystemConfig,
+    grpc::health::health::HealthClient, AuraeConfig, AuraeSocket, AuthConfig,
+    Client, SystemConfig,
 };
 use once_cell::sync::Lazy;
+use proto::grpc::health::HealthCheckRequest;
 use std::{future::Future, net::SocketAddr, time::Duration};
 use tokio::sync::OnceCell;
 
 pub mod cells;
 pub mod observe;
+pub mod pods;
 
 #[macro_export]
 macro_rules! retry {
@@ -76,9 +79,11 @@ async fn run_auraed() -> Client {
             ca_crt: "/etc/aurae/pki/ca.crt".to_string(),
             client_crt: "/etc/aurae/pki/_signed.client.nova.crt".to_string(),
             client_key: "/etc/aurae/pki/client.nova.key".to_string(),
+            ..Default::default()
         },
         system: SystemConfig {
             socket: AuraeSocket::Path(socket.clone().into()),
+            discovery: None,
         },
     };
 
@@ -90,22 +95,24 @@ async fn run_auraed() -> Client {
         auraed::run(runtime, Some(socket), false, false).await.unwrap()
     });
 
+    let client =
+        Client::new(client_config).await.expect("failed to create client");
     let mut retry_strategy = default_retry_strategy();
 
+    // The client connects on its first call, once auraed listens
     loop {
-        match Client::new(client_config.clone()).await {
-            Ok(client) => break Ok(client),
-            e @ Err(ClientError::ConnectionError(_)) => {
-                if let Some(delay) = retry_strategy.next_backoff() {
-                    tokio::time::sleep(delay).await
-                } else {
-                    break e;
-                }
+        let res =
+            client.check(HealthCheckRequest { service: String::new() }).await;
+        match res {
+            Err(e) if e.code() == tonic::Code::Unavailable => {
+                let Some(delay) = retry_strategy.next_backoff() else {
+                    panic!("failed to connect to auraed: {e}");
+                };
+                tokio::time::sleep(delay).await
             }
-            e => break e,
+            _ => break client,
         }
     }
-    .expect("failed to create client")
 }
 
 static CLIENT: OnceCell<Client> = OnceCell::const_new();
@@ -130,8 +137,12 @@ pub async fn remote_auraed_client(
             ca_crt: "/etc/aurae/pki/ca.crt".to_string(),
             client_crt: "/etc/aurae/pki/_signed.client.nova.crt".to_string(),
             client_key: "/etc/aurae/pki/client.nova.key".to_string(),
+            ..Default::default()
+        },
+        system: SystemConfig {
+            socket: AuraeSocket::Addr(addr),
+            discovery: None,
         },
-        system: SystemConfig { socket: AuraeSocket::Addr(addr) },
     };
     Client::new(client_config.clone()).await
 }
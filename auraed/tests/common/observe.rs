@@ -46,13 +46,23 @@ pub async fn intercept_posix_signals_stream(
     intercepted
 }
 
+/// Whether `signals` holds `signal` sent to the process `process_id`.
+pub fn contains_signal(
+    signals: &[Signal],
+    process_id: i32,
+    signal: i32,
+) -> bool {
+    signals.iter().any(|s| s.process_id == process_id && s.signal == signal)
+}
+
 pub(crate) struct GetPosixSignalsStreamRequestBuilder {
     workload: Option<Workload>,
+    include_unmanaged: bool,
 }
 
 impl GetPosixSignalsStreamRequestBuilder {
     pub fn new() -> Self {
-        Self { workload: None }
+        Self { workload: None, include_unmanaged: false }
     }
 
     pub fn cell_workload(&mut self, name: String) -> &mut Self {
@@ -63,7 +73,15 @@ impl GetPosixSignalsStreamRequestBuilder {
         self
     }
 
+    pub fn include_unmanaged(&mut self) -> &mut Self {
+        self.include_unmanaged = true;
+        self
+    }
+
     pub fn build(&self) -> GetPosixSignalsStreamRequest {
-        GetPosixSignalsStreamRequest { workload: self.workload.clone() }
+        GetPosixSignalsStreamRequest {
+            workload: self.workload.clone(),
+            include_unmanaged: self.include_unmanaged,
+        }
     }
 }
\ No newline at end of file
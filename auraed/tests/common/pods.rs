@@ -12,64 +12,52 @@
  * Copyright 2022 - 2024, the aurae contributors                              *
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
-use std::sync::{
-    mpsc::{channel, Sender},
-    Arc,
-};
+#![allow(unused)]
 
-use hypervisor::Hypervisor;
-use libc::EFD_NONBLOCK;
-use vmm::{api::ApiRequest, VmmThreadHandle};
-use vmm_sys_util::eventfd::EventFd;
+use proto::cri::{
+    LinuxPodSandboxConfig, PodSandboxConfig, PodSandboxMetadata, PortMapping,
+    RunPodSandboxRequest,
+};
+use std::collections::HashMap;
 
-pub struct Manager {
-    pub events: EventFd,
-    pub sender: Option<Sender<ApiRequest>>,
-    hypervisor: Arc<dyn Hypervisor>,
-    debug: EventFd,
-    vmm_thread: Option<VmmThreadHandle>,
+pub struct RunPodSandboxRequestBuilder {
+    name: String,
+    labels: HashMap<String, String>,
+    port_mappings: Vec<PortMapping>,
 }
 
-impl Manager {
+impl RunPodSandboxRequestBuilder {
     pub fn new() -> Self {
-        let debug =
-            EventFd::new(EFD_NONBLOCK).expect("Failed to create event monitor");
-        let api_evt =
-            EventFd::new(EFD_NONBLOCK).expect("Failed to create API eventfd");
-
-        let hypervisor =
-            hypervisor::new().expect("Failed to instantiate hypervisor");
-
         Self {
-            hypervisor,
-            debug,
-            sender: None,
-            events: api_evt,
-            vmm_thread: None,
+            name: format!("ae-e2e-pod-{}", uuid::Uuid::new_v4()),
+            labels: HashMap::new(),
+            port_mappings: vec![],
         }
     }
 
-    pub fn start(&mut self) -> Result<(), anyhow::Error> {
-        let (sender, receiver) = channel();
-        self.sender = Some(sender.clone());
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        let _ = self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn port_mapping(mut self, port_mapping: PortMapping) -> Self {
+        self.port_mappings.push(port_mapping);
+        self
+    }
 
-        let version =
-            vmm::VmmVersionInfo::new("auraed", env!("CARGO_PKG_VERSION"));
-        self.vmm_thread = Some(
-            vmm::start_vmm_thread(
-                version,
-                &None,
-                None,
-                self.events.try_clone()?,
-                sender,
-                receiver,
-                self.debug.try_clone()?,
-                &seccompiler::SeccompAction::Allow,
-                self.hypervisor.clone(),
-                false
-            )
-            .expect("Failed to start VMM thread"),
-        );
-        Ok(())
+    pub fn build(&self) -> RunPodSandboxRequest {
+        RunPodSandboxRequest {
+            config: Some(PodSandboxConfig {
+                metadata: Some(PodSandboxMetadata {
+                    name: self.name.clone(),
+                    ..Default::default()
+                }),
+                linux: Some(LinuxPodSandboxConfig::default()),
+                labels: self.labels.clone(),
+                port_mappings: self.port_mappings.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
     }
 }
@@ -0,0 +1,150 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cells::cell_service::CellServiceClient;
+use client::grpc::health::health::HealthClient;
+use client::observe::observe_service::ObserveServiceClient;
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use proto::cells::{CellServiceFreeRequest, CellServiceStopRequest};
+use proto::grpc::health::HealthCheckRequest;
+use proto::observe::GetBlockingPoolStatsRequest;
+use std::time::{Duration, Instant};
+use test_helpers::*;
+
+mod common;
+
+/// Leaves a grandchild running once the executable is stopped, which the
+/// free of its cell waits for on the mount-ops pool until it times out.
+const LINGERING: &str = "(env -i setsid sleep 100 &); sleep 100";
+
+/// More cells than the mount-ops pool frees at once, by default 4.
+const CELLS: usize = 8;
+
+const FREE_TIMEOUT_MS: u32 = 2_000;
+
+/// How long a health check or a stats request may take while the pool is
+/// saturated, far below the time the frees block for.
+const MAX_LATENCY: Duration = Duration::from_millis(250);
+
+#[test_helpers_macros::shared_runtime_test]
+async fn observe_get_blocking_pool_stats_must_report_load_without_delaying_requests(
+) {
+    skip_if_not_root!(
+        "observe_get_blocking_pool_stats_must_report_load_without_delaying_requests"
+    );
+    skip_if_seccomp!(
+        "observe_get_blocking_pool_stats_must_report_load_without_delaying_requests"
+    );
+
+    let client = common::auraed_client().await;
+
+    let cell_names = futures::future::join_all(
+        (0..CELLS).map(|_| allocate_and_stop(&client, LINGERING)),
+    )
+    .await;
+
+    let frees: Vec<_> = cell_names
+        .into_iter()
+        .map(|cell_name| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                client
+                    .free(CellServiceFreeRequest {
+                        cell_name,
+                        force: false,
+                        recursive: false,
+                        timeout_ms: FREE_TIMEOUT_MS,
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let pools = client
+        .get_blocking_pool_stats(GetBlockingPoolStatsRequest {})
+        .await
+        .expect("failed to get blocking pool stats")
+        .into_inner()
+        .pools;
+    let mount_ops = pools
+        .iter()
+        .find(|pool| pool.name == "mount-ops")
+        .expect("no mount-ops pool");
+    assert_eq!(mount_ops.running, mount_ops.size);
+    assert!(mount_ops.queued > 0, "{mount_ops:?}");
+
+    // Requests which need no blocking work are served while it is queued
+    for _ in 0..10 {
+        let start = Instant::now();
+        let _ = client
+            .check(HealthCheckRequest { service: String::new() })
+            .await
+            .expect("failed to check health");
+        assert!(start.elapsed() < MAX_LATENCY, "{:?}", start.elapsed());
+
+        let start = Instant::now();
+        let _ = client
+            .get_blocking_pool_stats(GetBlockingPoolStatsRequest {})
+            .await
+            .expect("failed to get blocking pool stats");
+        assert!(start.elapsed() < MAX_LATENCY, "{:?}", start.elapsed());
+    }
+
+    for free in frees {
+        let response = free.await.unwrap().expect("failed to free");
+        assert!(response.into_inner().escalated);
+    }
+}
+
+/// Allocates a cell, and starts and stops an executable running `command`
+/// in it. Returns the name of the cell.
+async fn allocate_and_stop(client: &client::Client, command: &str) -> String {
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    let executable_name = format!("ae-lingering-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(command.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let _ = client
+        .stop(CellServiceStopRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name,
+            ..Default::default()
+        })
+        .await
+        .expect("failed to stop");
+
+    cell_name
+}
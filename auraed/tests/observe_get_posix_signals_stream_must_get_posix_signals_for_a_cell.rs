@@ -18,10 +18,11 @@ use common::{
         CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
     },
     observe::{
-        intercept_posix_signals_stream, GetPosixSignalsStreamRequestBuilder,
+        contains_signal, intercept_posix_signals_stream,
+        GetPosixSignalsStreamRequestBuilder,
     },
 };
-use proto::{cells::CellServiceStopRequest, observe::Signal};
+use proto::cells::CellServiceStopRequest;
 use std::time::Duration;
 use test_helpers::*;
 
@@ -98,6 +99,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_cell() {
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell1_name.clone()),
                 executable_name: exe1_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -108,6 +110,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_cell() {
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell2_name.clone()),
                 executable_name: exe2_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -118,14 +121,10 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_cell() {
     let guard = intercepted_signals.lock().await;
 
     // Assert we intercepted the signal for the executable in the first cell
-    let expected = Signal { process_id: pid1, signal: 9 };
     assert!(
-        guard.contains(&expected),
-        "signal not found\nexpected: {expected:#?}\nintercepted: {guard:#?}",
+        contains_signal(&guard, pid1, 9),
+        "signal not found\nexpected: SIGKILL to {pid1}\nintercepted: {guard:#?}",
     );
     // Assert we did NOT intercept the signal for the executable in the second cell
-    assert!(
-        !guard.contains(&Signal { process_id: pid2, signal: 9 }),
-        "unexpected signal intercepted"
-    );
+    assert!(!contains_signal(&guard, pid2, 9), "unexpected signal intercepted");
 }
\ No newline at end of file
@@ -18,10 +18,11 @@ use common::{
         CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
     },
     observe::{
-        intercept_posix_signals_stream, GetPosixSignalsStreamRequestBuilder,
+        contains_signal, intercept_posix_signals_stream,
+        GetPosixSignalsStreamRequestBuilder,
     },
 };
-use proto::{cells::CellServiceStopRequest, observe::Signal};
+use proto::cells::CellServiceStopRequest;
 use std::time::Duration;
 use test_helpers::*;
 
@@ -129,6 +130,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_nested_cel
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell1_name.clone()),
                 executable_name: exe1_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -139,6 +141,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_nested_cel
             .stop(CellServiceStopRequest {
                 cell_name: Some(nested_cell_name.clone()),
                 executable_name: nested_exe_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -149,6 +152,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_nested_cel
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell2_name.clone()),
                 executable_name: exe2_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -159,19 +163,12 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_a_nested_cel
     let guard = intercepted_signals.lock().await;
 
     // Assert we intercepted the signal for the executable in the nested cell
-    let expected = Signal { process_id: nested_pid, signal: 9 };
     assert!(
-        guard.contains(&expected),
-        "signal not found\nexpected: {expected:#?}\nintercepted: {guard:#?}",
+        contains_signal(&guard, nested_pid, 9),
+        "signal not found\nexpected: SIGKILL to {nested_pid}\nintercepted: {guard:#?}",
     );
     // Assert we did NOT intercept the signal for the executable in the first (parent) cell
-    assert!(
-        !guard.contains(&Signal { process_id: pid1, signal: 9 }),
-        "unexpected signal intercepted"
-    );
+    assert!(!contains_signal(&guard, pid1, 9), "unexpected signal intercepted");
     // Assert we did NOT intercept the signal for the executable in the second cell
-    assert!(
-        !guard.contains(&Signal { process_id: pid2, signal: 9 }),
-        "unexpected signal intercepted"
-    );
+    assert!(!contains_signal(&guard, pid2, 9), "unexpected signal intercepted");
 }
\ No newline at end of file
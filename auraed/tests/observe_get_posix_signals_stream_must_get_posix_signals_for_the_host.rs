@@ -18,10 +18,11 @@ use common::{
         CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
     },
     observe::{
-        intercept_posix_signals_stream, GetPosixSignalsStreamRequestBuilder,
+        contains_signal, intercept_posix_signals_stream,
+        GetPosixSignalsStreamRequestBuilder,
     },
 };
-use proto::{cells::CellServiceStopRequest, observe::Signal};
+use proto::cells::CellServiceStopRequest;
 use std::time::Duration;
 use test_helpers::*;
 
@@ -72,6 +73,7 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_the_host() {
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell_name.clone()),
                 executable_name: exe_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -81,9 +83,16 @@ async fn observe_get_posix_signal_stream_must_get_posix_signals_for_the_host() {
 
     // Assert we intercepted the signal
     let guard = intercepted_signals.lock().await;
-    let expected = Signal { process_id: pid, signal: 9 };
     assert!(
-        guard.contains(&expected),
-        "signal not found\nexpected: {expected:#?}\nintercepted: {guard:#?}",
+        contains_signal(&guard, pid, 9),
+        "signal not found\nexpected: SIGKILL to {pid}\nintercepted: {guard:#?}",
+    );
+
+    // Assert the signal is labelled with the executable and its cell
+    assert!(
+        guard.iter().any(|signal| signal.process_id == pid
+            && signal.executable_name == exe_name
+            && signal.workload.as_ref().is_some_and(|w| w.id == cell_name)),
+        "signal not labelled\nintercepted: {guard:#?}",
     );
 }
\ No newline at end of file
@@ -18,10 +18,11 @@ use common::{
         CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
     },
     observe::{
-        intercept_posix_signals_stream, GetPosixSignalsStreamRequestBuilder,
+        contains_signal, intercept_posix_signals_stream,
+        GetPosixSignalsStreamRequestBuilder,
     },
 };
-use proto::{cells::CellServiceStopRequest, observe::Signal};
+use proto::cells::CellServiceStopRequest;
 use std::time::Duration;
 use test_helpers::*;
 
@@ -79,6 +80,7 @@ async fn observe_get_posix_signal_stream_must_map_host_pids_to_namespace_pids()
             .stop(CellServiceStopRequest {
                 cell_name: Some(cell_name.clone()),
                 executable_name: exe_name.clone(),
+                ..Default::default()
             })
             .await
     );
@@ -88,9 +90,8 @@ async fn observe_get_posix_signal_stream_must_map_host_pids_to_namespace_pids()
 
     // Assert we intercepted the signal
     let guard = intercepted_signals.lock().await;
-    let expected = Signal { process_id: nspid, signal: 9 };
     assert!(
-        guard.contains(&expected),
-        "signal not found\nexpected: {expected:#?}\nintercepted: {guard:#?}",
+        contains_signal(&guard, nspid, 9),
+        "signal not found\nexpected: SIGKILL to {nspid}\nintercepted: {guard:#?}",
     );
 }
\ No newline at end of file
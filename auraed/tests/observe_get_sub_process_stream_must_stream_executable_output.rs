@@ -0,0 +1,138 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::{
+    cells::cell_service::CellServiceClient,
+    observe::observe_service::ObserveServiceClient,
+};
+use common::cells::{
+    CellServiceAllocateRequestBuilder, CellServiceStartRequestBuilder,
+};
+use futures_util::StreamExt;
+use proto::{
+    cells::{CellServiceFreeRequest, CellServiceStopRequest},
+    observe::{GetSubProcessStreamRequest, LogChannelType},
+};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+/// Writes a line to stdout and stderr every 100ms.
+const CHATTY: &str = "while true; do echo out; echo err >&2; sleep 0.1; done";
+
+#[test_helpers_macros::shared_runtime_test]
+async fn observe_get_sub_process_stream_must_stream_executable_output() {
+    skip_if_not_root!(
+        "observe_get_sub_process_stream_must_stream_executable_output"
+    );
+    skip_if_seccomp!(
+        "observe_get_sub_process_stream_must_stream_executable_output"
+    );
+
+    let client = common::auraed_client().await;
+
+    // Allocate a cell
+    let cell_name = retry!(
+        client.allocate(CellServiceAllocateRequestBuilder::new().build()).await
+    )
+    .unwrap()
+    .into_inner()
+    .cell_name;
+
+    // Subscribing to an executable that doesn't exist must fail right away
+    let status = client
+        .get_sub_process_stream(GetSubProcessStreamRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name: "ae-missing".into(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the executable must not be found");
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    // Start an executable that writes to stdout and stderr
+    let executable_name = format!("ae-chatty-{}", uuid::Uuid::new_v4());
+    let _ = retry!(
+        client
+            .start(
+                CellServiceStartRequestBuilder::new()
+                    .cell_name(cell_name.clone())
+                    .executable_name(executable_name.clone())
+                    .command(CHATTY.into())
+                    .build(),
+            )
+            .await
+    )
+    .unwrap();
+
+    let mut stream = client
+        .get_sub_process_stream(GetSubProcessStreamRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name: executable_name.clone(),
+            ..Default::default()
+        })
+        .await
+        .expect("stream")
+        .into_inner();
+
+    // Both stdout and stderr must be streamed
+    let mut seen = vec![];
+    while seen.len() < 2 {
+        let response =
+            tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .expect("timed out waiting for output")
+                .expect("stream ended")
+                .expect("response");
+        let channel_type = response.channel_type();
+        let line = response.item.expect("log item").line;
+        match channel_type {
+            LogChannelType::Stdout => assert_eq!(line, "out"),
+            LogChannelType::Stderr => assert_eq!(line, "err"),
+            LogChannelType::Unspecified => panic!("unspecified channel type"),
+        }
+        if !seen.contains(&channel_type) {
+            seen.push(channel_type);
+        }
+    }
+
+    // Stopping the executable must end the stream
+    let _ = client
+        .stop(CellServiceStopRequest {
+            cell_name: Some(cell_name.clone()),
+            executable_name,
+            ..Default::default()
+        })
+        .await
+        .expect("failed to stop");
+
+    let ended = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(response) = stream.next().await {
+            let _ = response.expect("response");
+        }
+    })
+    .await;
+    assert!(ended.is_ok(), "the stream must end once the executable stops");
+
+    let _ = client
+        .free(CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        })
+        .await
+        .expect("failed to free");
+}
@@ -0,0 +1,126 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cri::{
+    labels::LabelSelector, runtime_service::RuntimeServiceClient,
+};
+use common::pods::RunPodSandboxRequestBuilder;
+use proto::cri::{
+    ListPodSandboxRequest, PodSandboxFilter, RemovePodSandboxRequest,
+};
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn pod_sandbox_list_must_select_by_labels() {
+    skip_if_not_root!("pod_sandbox_list_must_select_by_labels");
+    skip_if_seccomp!("pod_sandbox_list_must_select_by_labels");
+
+    let client = common::auraed_client().await;
+
+    // Labels are validated, unlike annotations
+    let err = RuntimeServiceClient::run_pod_sandbox(
+        &client,
+        RunPodSandboxRequestBuilder::new().label("app", "not a value").build(),
+    )
+    .await
+    .expect_err("the label must be invalid");
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    // Unique, as other tests run pods meanwhile
+    let run = uuid::Uuid::new_v4().to_string();
+    let mut sandbox_ids = vec![];
+    for tier in ["front", "db"] {
+        let sandbox_id = retry!(
+            RuntimeServiceClient::run_pod_sandbox(
+                &client,
+                RunPodSandboxRequestBuilder::new()
+                    .label("run", &run)
+                    .label("tier", tier)
+                    .build()
+            )
+            .await
+        )
+        .unwrap()
+        .into_inner()
+        .pod_sandbox_id;
+        sandbox_ids.push(sandbox_id);
+    }
+
+    let list = |selector: String| {
+        let client = client.clone();
+        async move {
+            let selector: LabelSelector = selector.parse().unwrap();
+            RuntimeServiceClient::list_pod_sandbox(
+                &client,
+                ListPodSandboxRequest {
+                    filter: Some(PodSandboxFilter {
+                        label_selector: selector.to_filter(),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .await
+            .map(|res| {
+                let mut ids: Vec<String> = res
+                    .into_inner()
+                    .items
+                    .into_iter()
+                    .map(|item| item.id)
+                    .collect();
+                ids.sort();
+                ids
+            })
+        }
+    };
+
+    let mut both = sandbox_ids.clone();
+    both.sort();
+    assert_eq!(list(format!("run={run}")).await.unwrap(), both);
+    assert_eq!(
+        list(format!("run={run},tier=front")).await.unwrap(),
+        vec![sandbox_ids[0].clone()]
+    );
+    assert_eq!(
+        list(format!("run={run},tier!=front")).await.unwrap(),
+        vec![sandbox_ids[1].clone()]
+    );
+
+    // A malformed selector is an invalid argument
+    let err = RuntimeServiceClient::list_pod_sandbox(
+        &client,
+        ListPodSandboxRequest {
+            filter: Some(PodSandboxFilter {
+                label_selector: [("tier!!".to_string(), "db".to_string())]
+                    .into(),
+                ..Default::default()
+            }),
+        },
+    )
+    .await
+    .expect_err("the selector must be malformed");
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    for sandbox_id in sandbox_ids {
+        let _ = RuntimeServiceClient::remove_pod_sandbox(
+            &client,
+            RemovePodSandboxRequest { pod_sandbox_id: sandbox_id },
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use auraed::AuraedRuntime;
+use client::cri::runtime_service::RuntimeServiceClient;
+use common::pods::RunPodSandboxRequestBuilder;
+use proto::cri::{PodSandboxStatusRequest, RemovePodSandboxRequest};
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn pod_sandbox_remove_must_tear_down_running_sandboxes() {
+    skip_if_not_root!("pod_sandbox_remove_must_tear_down_running_sandboxes");
+    skip_if_seccomp!("pod_sandbox_remove_must_tear_down_running_sandboxes");
+
+    let client = common::auraed_client().await;
+
+    let sandbox_id = retry!(
+        RuntimeServiceClient::run_pod_sandbox(
+            &client,
+            RunPodSandboxRequestBuilder::new().build()
+        )
+        .await
+    )
+    .unwrap()
+    .into_inner()
+    .pod_sandbox_id;
+
+    let runtime_dir = AuraedRuntime::default().runtime_dir;
+    let pod_dir = runtime_dir.join("pods").join(&sandbox_id);
+    let bundle_dir = runtime_dir.join("bundles").join(&sandbox_id);
+    assert!(pod_dir.exists());
+    assert!(bundle_dir.exists());
+
+    // Removing the running sandbox kills its init container
+    let _ = RuntimeServiceClient::remove_pod_sandbox(
+        &client,
+        RemovePodSandboxRequest { pod_sandbox_id: sandbox_id.clone() },
+    )
+    .await
+    .expect("failed to remove a running sandbox");
+
+    assert!(!pod_dir.exists());
+    assert!(!bundle_dir.exists());
+    let status = RuntimeServiceClient::pod_sandbox_status(
+        &client,
+        PodSandboxStatusRequest {
+            pod_sandbox_id: sandbox_id.clone(),
+            verbose: false,
+        },
+    )
+    .await
+    .expect_err("a removed sandbox must be gone");
+    assert_eq!(status.code(), Code::NotFound);
+
+    // Removing it again fails, as it is unknown
+    let status = RuntimeServiceClient::remove_pod_sandbox(
+        &client,
+        RemovePodSandboxRequest { pod_sandbox_id: sandbox_id },
+    )
+    .await
+    .expect_err("removing a sandbox twice must fail");
+    assert_eq!(status.code(), Code::NotFound);
+}
@@ -0,0 +1,138 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+use client::cri::runtime_service::RuntimeServiceClient;
+use common::pods::RunPodSandboxRequestBuilder;
+use proto::cri::{
+    PodSandboxStatusRequest, PortMapping, RemovePodSandboxRequest,
+    StopPodSandboxRequest,
+};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use test_helpers::*;
+use tonic::Code;
+
+mod common;
+
+const CONTAINER_PORT: u16 = 8080;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn pod_sandbox_run_must_publish_its_ports() {
+    skip_if_not_root!("pod_sandbox_run_must_publish_its_ports");
+    skip_if_seccomp!("pod_sandbox_run_must_publish_its_ports");
+
+    let client = common::auraed_client().await;
+
+    // A host port no one else has
+    let host_port =
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let port_mapping = PortMapping {
+        container_port: CONTAINER_PORT.into(),
+        host_port: host_port.into(),
+        host_ip: "127.0.0.1".into(),
+        ..Default::default()
+    };
+
+    let sandbox_id = retry!(
+        RuntimeServiceClient::run_pod_sandbox(
+            &client,
+            RunPodSandboxRequestBuilder::new()
+                .port_mapping(port_mapping.clone())
+                .build()
+        )
+        .await
+    )
+    .unwrap()
+    .into_inner()
+    .pod_sandbox_id;
+
+    let status = |sandbox_id: String| {
+        let client = client.clone();
+        async move {
+            RuntimeServiceClient::pod_sandbox_status(
+                &client,
+                PodSandboxStatusRequest {
+                    pod_sandbox_id: sandbox_id,
+                    verbose: true,
+                },
+            )
+            .await
+            .unwrap()
+            .into_inner()
+            .info
+        }
+    };
+    let info = status(sandbox_id.clone()).await;
+    assert_eq!(
+        info["ports"],
+        format!("127.0.0.1:{host_port}->{CONTAINER_PORT}/tcp")
+    );
+
+    // Another pod can't publish the same host port
+    let err = RuntimeServiceClient::run_pod_sandbox(
+        &client,
+        RunPodSandboxRequestBuilder::new().port_mapping(port_mapping).build(),
+    )
+    .await
+    .expect_err("the host port must be in use");
+    assert_eq!(err.code(), Code::AlreadyExists);
+
+    // Connections to the host port reach the container port of the pod
+    let netns = File::open(format!("/proc/{}/ns/net", info["pid"])).unwrap();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let server = std::thread::spawn(move || {
+        nix::sched::setns(&netns, nix::sched::CloneFlags::CLONE_NEWNET)
+            .expect("failed to enter the network namespace of the pod");
+        let listener = TcpListener::bind(("0.0.0.0", CONTAINER_PORT)).unwrap();
+        ready_tx.send(()).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"hello from the pod").unwrap();
+    });
+    ready_rx.recv().unwrap();
+
+    let mut received = String::new();
+    let _ = TcpStream::connect(("127.0.0.1", host_port))
+        .unwrap()
+        .read_to_string(&mut received)
+        .unwrap();
+    assert_eq!(received, "hello from the pod");
+    server.join().unwrap();
+
+    // Stopping the pod frees its host port
+    let _ = RuntimeServiceClient::stop_pod_sandbox(
+        &client,
+        StopPodSandboxRequest { pod_sandbox_id: sandbox_id.clone() },
+    )
+    .await
+    .unwrap();
+    assert!(!status(sandbox_id.clone()).await.contains_key("ports"));
+    // The forward is told to stop, but may not have freed the port just yet
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while TcpListener::bind(("127.0.0.1", host_port)).is_err() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the host port must be free once the pod is stopped");
+
+    let _ = RuntimeServiceClient::remove_pod_sandbox(
+        &client,
+        RemovePodSandboxRequest { pod_sandbox_id: sandbox_id },
+    )
+    .await
+    .unwrap();
+}
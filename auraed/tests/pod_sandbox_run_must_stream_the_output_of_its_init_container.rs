@@ -0,0 +1,75 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cri::runtime_service::RuntimeServiceClient;
+use client::observe::observe_service::ObserveServiceClient;
+use common::pods::RunPodSandboxRequestBuilder;
+use futures::StreamExt;
+use proto::cri::RemovePodSandboxRequest;
+use proto::observe::{GetSubProcessStreamRequest, LogChannelType};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn pod_sandbox_run_must_stream_the_output_of_its_init_container() {
+    skip_if_not_root!(
+        "pod_sandbox_run_must_stream_the_output_of_its_init_container"
+    );
+    skip_if_seccomp!(
+        "pod_sandbox_run_must_stream_the_output_of_its_init_container"
+    );
+
+    let client = common::auraed_client().await;
+
+    let sandbox_id = retry!(
+        RuntimeServiceClient::run_pod_sandbox(
+            &client,
+            RunPodSandboxRequestBuilder::new().build()
+        )
+        .await
+    )
+    .unwrap()
+    .into_inner()
+    .pod_sandbox_id;
+
+    // The banner the auraed of the init container prints as it starts is
+    // retained for those who subscribe later
+    let mut stream = client
+        .get_sub_process_stream(GetSubProcessStreamRequest {
+            pod_sandbox_id: sandbox_id.clone(),
+            channel_type: LogChannelType::Stdout.into(),
+            since: Some(0),
+            ..Default::default()
+        })
+        .await
+        .expect("stream")
+        .into_inner();
+    let response = tokio::time::timeout(Duration::from_secs(10), stream.next())
+        .await
+        .expect("timed out waiting for output")
+        .expect("stream ended")
+        .expect("response");
+    assert_eq!(response.channel_type(), LogChannelType::Stdout);
+    let item = response.item.expect("log item");
+    assert_eq!(item.channel, format!("{sandbox_id}::stdout"));
+
+    let _ = RuntimeServiceClient::remove_pod_sandbox(
+        &client,
+        RemovePodSandboxRequest { pod_sandbox_id: sandbox_id },
+    )
+    .await
+    .unwrap();
+}
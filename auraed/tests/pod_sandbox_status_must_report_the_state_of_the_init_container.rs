@@ -0,0 +1,119 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+use client::cri::runtime_service::RuntimeServiceClient;
+use common::pods::RunPodSandboxRequestBuilder;
+use proto::cri::{
+    ListPodSandboxRequest, PodSandboxFilter, PodSandboxState,
+    PodSandboxStateValue, PodSandboxStatusRequest, RemovePodSandboxRequest,
+    StopPodSandboxRequest,
+};
+use std::time::Duration;
+use test_helpers::*;
+
+mod common;
+
+#[test_helpers_macros::shared_runtime_test]
+async fn pod_sandbox_status_must_report_the_state_of_the_init_container() {
+    skip_if_not_root!(
+        "pod_sandbox_status_must_report_the_state_of_the_init_container"
+    );
+    skip_if_seccomp!(
+        "pod_sandbox_status_must_report_the_state_of_the_init_container"
+    );
+
+    let client = common::auraed_client().await;
+
+    let sandbox_id = retry!(
+        RuntimeServiceClient::run_pod_sandbox(
+            &client,
+            RunPodSandboxRequestBuilder::new().build()
+        )
+        .await
+    )
+    .unwrap()
+    .into_inner()
+    .pod_sandbox_id;
+
+    let response = RuntimeServiceClient::pod_sandbox_status(
+        &client,
+        PodSandboxStatusRequest {
+            pod_sandbox_id: sandbox_id.clone(),
+            verbose: true,
+        },
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let status = response.status.expect("status of the sandbox");
+    assert_eq!(status.id, sandbox_id);
+    assert_eq!(status.state(), PodSandboxState::SandboxReady);
+    assert!(status.created_at > 0);
+    assert!(response.info["bundle"].ends_with(&sandbox_id));
+    assert!(response.info.contains_key("pid"));
+
+    // Listed as ready
+    let list = |state: PodSandboxState| {
+        let client = client.clone();
+        let sandbox_id = sandbox_id.clone();
+        async move {
+            RuntimeServiceClient::list_pod_sandbox(
+                &client,
+                ListPodSandboxRequest {
+                    filter: Some(PodSandboxFilter {
+                        id: sandbox_id,
+                        state: Some(PodSandboxStateValue {
+                            state: state.into(),
+                        }),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .await
+            .unwrap()
+            .into_inner()
+            .items
+        }
+    };
+    assert_eq!(list(PodSandboxState::SandboxReady).await.len(), 1);
+    assert!(list(PodSandboxState::SandboxNotready).await.is_empty());
+
+    // Not ready once its init container is stopped
+    let _ = RuntimeServiceClient::stop_pod_sandbox(
+        &client,
+        StopPodSandboxRequest { pod_sandbox_id: sandbox_id.clone() },
+    )
+    .await
+    .unwrap();
+    // The init container is killed, but may not have exited just yet
+    let stopped = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let stopped = list(PodSandboxState::SandboxNotready).await;
+            if !stopped.is_empty() {
+                break stopped;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the sandbox must be listed as not ready");
+    assert_eq!(stopped[0].id, sandbox_id);
+
+    let _ = RuntimeServiceClient::remove_pod_sandbox(
+        &client,
+        RemovePodSandboxRequest { pod_sandbox_id: sandbox_id },
+    )
+    .await
+    .unwrap();
+}
@@ -130,6 +130,7 @@ async fn vms_with_auraed() {
         CellServiceStopRequest {
             cell_name: Some(cell_name.clone()),
             executable_name: "sleeper".into(),
+            ..Default::default()
         },
     )
     .await
@@ -137,7 +138,12 @@ async fn vms_with_auraed() {
 
     CellServiceClient::free(
         &remote_client,
-        CellServiceFreeRequest { cell_name },
+        CellServiceFreeRequest {
+            cell_name,
+            force: false,
+            recursive: false,
+            timeout_ms: 0,
+        },
     )
     .await
     .expect("failed to free cell");
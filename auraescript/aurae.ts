@@ -50,3 +50,12 @@ export function createClient(opts: CreateClient = { kind: "default" }): Promise<
     // @ts-ignore
     return Deno.core.ops.as__client_new(config);
 }
+
+/** Thrown by the streaming calls of a client when auraed fails them. */
+export interface GrpcError extends Error {
+    /** The gRPC status code, e.g. "NOT_FOUND". */
+    code: string;
+}
+
+// @ts-ignore
+export const GrpcError: { new(message: string): GrpcError } = globalThis.GrpcError;
@@ -17,16 +17,16 @@ use std::io::Write;
 use std::path::PathBuf;
 
 fn main() {
-    generate_aurae_ts();
+    generate_ts("aurae.ts", include_str!("./aurae.ts"));
+    generate_ts("helpers.ts", include_str!("./helpers.ts"));
 }
 
-fn generate_aurae_ts() {
+fn generate_ts(file_name: &str, contents: &str) {
     // Currently nothing is generated.
-    // We are only copying the contents of helpers.ts to the gen directory.
+    // We are only copying the hand written modules to the gen directory,
+    // next to the modules generated from the protos, which they import.
     // If we do generate code in the future, we won't need to change all the imports.
 
-    let aurae = include_str!("./aurae.ts");
-
     let gen_dir = match std::env::var("CARGO_MANIFEST_DIR") {
         Ok(out_dir) => {
             let mut out_dir = PathBuf::from(out_dir);
@@ -38,7 +38,7 @@ fn generate_aurae_ts() {
 
     let ts_path = {
         let mut out_dir = gen_dir;
-        out_dir.push("aurae.ts");
+        out_dir.push(file_name);
         out_dir
     };
 
@@ -51,6 +51,6 @@ fn generate_aurae_ts() {
             panic!("Failed to create or overwrite {ts_path:?}")
         });
 
-    write!(ts, "{aurae}")
+    write!(ts, "{contents}")
         .unwrap_or_else(|_| panic!("Could not write to {ts_path:?}"));
 }
\ No newline at end of file
@@ -0,0 +1,330 @@
+// Helpers for the common workflows of AuraeScript, on top of the generated
+// clients. They fill in defaults, and reject obvious mistakes before a
+// request is sent to auraed.
+//
+//     import * as helpers from "../auraescript/gen/helpers.ts";
+//
+//     await helpers.withCell("my-cell", async (cell) => {
+//         await cell.start("echo hello");
+//     });
+
+import * as cellsApi from "./cells.ts";
+import * as cri from "./cri.ts";
+
+/** Thrown by the helpers, before calling auraed, for an invalid argument. */
+export class ValidationError extends Error {
+    constructor(message: string) {
+        super(message);
+        this.name = "ValidationError";
+    }
+}
+
+// The same rules auraed applies to the name of a cell: up to 8 levels of
+// nested cells separated by '/', each a domain name label (RFC 1123).
+const CELL_NAME_MAX_LENGTH = 255;
+const CELL_NAME_MAX_DEPTH = 8;
+const DOMAIN_NAME_LABEL_REGEX = /^(?=.{1,63}$)(?![-])[a-zA-Z0-9-]+(?<![-])$/;
+
+/**
+ * Throws a {@link ValidationError} unless `name` is a valid cell name, e.g.
+ * "my-cell" or "my-cell/nested-cell".
+ */
+export function validateCellName(name: string): void {
+    const trimmed = name.replace(/^\/+|\/+$/g, "");
+    if (trimmed.length === 0) {
+        throw new ValidationError("cell name must not be empty");
+    }
+    if (new TextEncoder().encode(trimmed).length > CELL_NAME_MAX_LENGTH) {
+        throw new ValidationError(
+            `cell name '${name}' must be at most ${CELL_NAME_MAX_LENGTH} bytes`,
+        );
+    }
+    const components = trimmed.split("/");
+    if (components.length > CELL_NAME_MAX_DEPTH) {
+        throw new ValidationError(
+            `cell name '${name}' must be at most ${CELL_NAME_MAX_DEPTH} levels`,
+        );
+    }
+    for (const component of components) {
+        if (!DOMAIN_NAME_LABEL_REGEX.test(component)) {
+            throw new ValidationError(
+                `cell name '${name}' must be made of letters, digits and '-', ` +
+                `not starting or ending with '-', up to 63 characters per level`,
+            );
+        }
+    }
+}
+
+/** Throws a {@link ValidationError} if `command` is empty. */
+export function validateCommand(command: string): void {
+    if (command.trim().length === 0) {
+        throw new ValidationError("command must not be empty");
+    }
+}
+
+/** The name of an executable running `command`: its program's basename. */
+function defaultExecutableName(command: string): string {
+    const program = command.trim().split(/\s+/)[0];
+    return program.split("/").pop() || program;
+}
+
+/** Options shared by all the helpers. */
+export interface ClientOptions {
+    /** The client from `createClient`. The default client if unset. */
+    client?: number;
+}
+
+/** The resources and isolation of a cell. */
+export interface CellOptions extends ClientOptions {
+    /** The weight of the cell for CPU time, from 1 to 10000. */
+    cpuWeight?: number;
+    /** The CPU time the cell may use per period (100ms), in microseconds. */
+    cpuMax?: number;
+    /** The memory the cell may use, in bytes. */
+    memoryMax?: number;
+    /** Unshare the pid, ipc, uts and mount namespaces. Default: false. */
+    isolateProcess?: boolean;
+    /** Unshare the net namespace. Default: false. */
+    isolateNetwork?: boolean;
+}
+
+/** Options for starting an executable. */
+export interface StartOptions {
+    /** The name of the executable. Default: the basename of the program. */
+    name?: string;
+    /** A description of the executable. Default: the command. */
+    description?: string;
+    /** The user to run the executable as. Default: the user of auraed. */
+    uid?: number;
+    /** The group to run the executable as. Default: the group of auraed. */
+    gid?: number;
+}
+
+/** Options for {@link Cell.free}. */
+export interface FreeOptions {
+    /** Kill the executables still running in the cell. Default: true. */
+    force?: boolean;
+    /** Free the nested cells of the cell first. Default: true. */
+    recursive?: boolean;
+}
+
+/** An executable started in a cell. */
+export interface Executable {
+    /** The cell the executable runs in. */
+    cell: Cell;
+    /** The name of the executable, unique within its cell. */
+    name: string;
+    /** The process id of the executable, as seen by auraed. */
+    pid: number;
+    /** Stops the executable. */
+    stop(): Promise<void>;
+}
+
+/** An allocated cell. */
+export class Cell {
+    readonly name: string;
+    readonly client: number | undefined;
+
+    constructor(name: string, client?: number) {
+        validateCellName(name);
+        this.name = name;
+        this.client = client;
+    }
+
+    /** Starts `command` in the cell, run with `sh -c`. */
+    async start(command: string, opts: StartOptions = {}): Promise<Executable> {
+        validateCommand(command);
+        const name = opts.name ?? defaultExecutableName(command);
+        const service = new cellsApi.CellServiceClient(this.client);
+        const started = await service.start(<cellsApi.CellServiceStartRequest>{
+            cellName: this.name,
+            executable: cellsApi.Executable.fromPartial({
+                name,
+                command,
+                description: opts.description ?? command,
+            }),
+            uid: opts.uid,
+            gid: opts.gid,
+        });
+        return {
+            cell: this,
+            name,
+            pid: started.pid,
+            stop: () => this.stop(name),
+        };
+    }
+
+    /** Stops the executable `name` of the cell. */
+    async stop(name: string): Promise<void> {
+        const service = new cellsApi.CellServiceClient(this.client);
+        await service.stop(<cellsApi.CellServiceStopRequest>{
+            cellName: this.name,
+            executableName: name,
+        });
+    }
+
+    /**
+     * Frees the cell. By default its executables are killed and its nested
+     * cells freed with it.
+     */
+    async free(opts: FreeOptions = {}): Promise<void> {
+        const service = new cellsApi.CellServiceClient(this.client);
+        await service.free(cellsApi.CellServiceFreeRequest.fromPartial({
+            cellName: this.name,
+            force: opts.force ?? true,
+            recursive: opts.recursive ?? true,
+        }));
+    }
+}
+
+/** Helpers for cells. */
+export const cells = {
+    /** Allocates the cell `name`. */
+    async allocate(name: string, opts: CellOptions = {}): Promise<Cell> {
+        validateCellName(name);
+        const service = new cellsApi.CellServiceClient(opts.client);
+        await service.allocate(<cellsApi.CellServiceAllocateRequest>{
+            cell: cellsApi.Cell.fromPartial({
+                name,
+                cpu: opts.cpuWeight === undefined && opts.cpuMax === undefined
+                    ? undefined
+                    : cellsApi.CpuController.fromPartial({
+                        weight: opts.cpuWeight,
+                        max: opts.cpuMax,
+                    }),
+                memory: opts.memoryMax === undefined
+                    ? undefined
+                    : cellsApi.MemoryController.fromPartial({
+                        max: opts.memoryMax,
+                    }),
+                isolateProcess: opts.isolateProcess ?? false,
+                isolateNetwork: opts.isolateNetwork ?? false,
+            }),
+        });
+        return new Cell(name, opts.client);
+    },
+
+    /**
+     * Allocates the cell `name` and starts `command` in it. The cell is
+     * freed again if the command fails to start.
+     */
+    async run(
+        name: string,
+        command: string,
+        opts: CellOptions & StartOptions = {},
+    ): Promise<Executable> {
+        validateCommand(command);
+        const cell = await cells.allocate(name, opts);
+        try {
+            return await cell.start(command, opts);
+        } catch (e) {
+            await cell.free().catch(() => {});
+            throw e;
+        }
+    },
+};
+
+/**
+ * Allocates the cell `name`, and calls `fn` with it. The cell is freed once
+ * `fn` returns, or throws, killing the executables still running in it.
+ */
+export async function withCell<T>(
+    name: string,
+    fn: (cell: Cell) => Promise<T>,
+    opts: CellOptions = {},
+): Promise<T> {
+    const cell = await cells.allocate(name, opts);
+    try {
+        return await fn(cell);
+    } finally {
+        await cell.free();
+    }
+}
+
+/** Options for {@link pods.runImage}. */
+export interface RunImageOptions extends ClientOptions {
+    /** The name of the pod. Default: derived from the image. */
+    name?: string;
+    /** The command of the container. Default: the entrypoint of the image. */
+    command?: string[];
+    /** The arguments of the command. Default: the cmd of the image. */
+    args?: string[];
+    /** The environment of the container. */
+    env?: Record<string, string>;
+    /** The hostname of the pod. Default: the name of the pod. */
+    hostname?: string;
+}
+
+/** A container running in its own pod sandbox. */
+export interface Pod {
+    /** The id of the pod sandbox. */
+    podSandboxId: string;
+    /** The id of the container. */
+    containerId: string;
+    /** Stops the pod sandbox, and removes it. */
+    remove(): Promise<void>;
+}
+
+/** The name of a pod running `image`, e.g. "nginx" for "docker.io/nginx:1". */
+function defaultPodName(image: string): string {
+    const repository = image.split("@")[0].split("/").pop() ?? image;
+    return repository.split(":")[0];
+}
+
+/** Helpers for pods, through the CRI runtime service of auraed. */
+export const pods = {
+    /**
+     * Runs `image` as a single container in a new pod sandbox. The sandbox
+     * is removed again if the container fails to start.
+     */
+    async runImage(image: string, opts: RunImageOptions = {}): Promise<Pod> {
+        if (image.trim().length === 0) {
+            throw new ValidationError("image must not be empty");
+        }
+        const name = opts.name ?? defaultPodName(image);
+
+        const runtime = new cri.RuntimeServiceClient(opts.client);
+        const sandboxConfig = cri.PodSandboxConfig.fromPartial({
+            metadata: cri.PodSandboxMetadata.fromPartial({ name }),
+            hostname: opts.hostname ?? name,
+            linux: cri.LinuxPodSandboxConfig.fromPartial({}),
+        });
+        const { podSandboxId } = await runtime.runPodSandbox(
+            cri.RunPodSandboxRequest.fromPartial({ config: sandboxConfig }),
+        );
+
+        const remove = async () => {
+            await runtime.stopPodSandbox(
+                cri.StopPodSandboxRequest.fromPartial({ podSandboxId }),
+            );
+            await runtime.removePodSandbox(
+                cri.RemovePodSandboxRequest.fromPartial({ podSandboxId }),
+            );
+        };
+
+        try {
+            const { containerId } = await runtime.createContainer(
+                cri.CreateContainerRequest.fromPartial({
+                    podSandboxId,
+                    sandboxConfig,
+                    config: cri.ContainerConfig.fromPartial({
+                        metadata: cri.ContainerMetadata.fromPartial({ name }),
+                        image: cri.ImageSpec.fromPartial({ image }),
+                        command: opts.command ?? [],
+                        args: opts.args ?? [],
+                        envs: Object.entries(opts.env ?? {}).map(
+                            ([key, value]) => cri.KeyValue.fromPartial({ key, value }),
+                        ),
+                    }),
+                }),
+            );
+            await runtime.startContainer(
+                cri.StartContainerRequest.fromPartial({ containerId }),
+            );
+            return { podSandboxId, containerId, remove };
+        } catch (e) {
+            await remove().catch(() => {});
+            throw e;
+        }
+    },
+};
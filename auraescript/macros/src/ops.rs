@@ -69,8 +69,9 @@ pub(crate) fn ops_generator(input: TokenStream) -> TokenStream {
             let client_ident =
                 Ident::new(&format!("{}Client", s.name()), file_path_span);
 
-            // TODO: support streaming
+            // TODO: support client streaming
             let methods = s.method.iter().filter(|m| !m.client_streaming() && !m.server_streaming());
+            let streaming_methods = s.method.iter().filter(|m| !m.client_streaming() && m.server_streaming());
 
             let op_idents = methods.clone()
                 .map(|m| {
@@ -80,8 +81,28 @@ pub(crate) fn ops_generator(input: TokenStream) -> TokenStream {
                     )
                 });
 
+            let get_client = quote! {
+                let client = match client_rid {
+                    None => ::deno_core::RcRef::new(::client::Client::default().await
+                        .map_err(|e| ::deno_error::JsErrorBox::generic(
+                            format!("Failed to create default client: {:?}",e.to_string()))
+                        )?),
+                    Some(client_rid) => {
+                        let as_client = {
+                            let op_state = &op_state.borrow();
+                            let rt = &op_state.resource_table; // get `ResourceTable` from JsRuntime `OpState`
+                            rt.get::<crate::builtin::auraescript_client::AuraeScriptClient>(client_rid) // get `Client` from its rid
+                        .map_err(|e| ::deno_error::JsErrorBox::generic(
+                                    format!("Failed to get client: {:?}",e.to_string()))) // fix client error
+                                ?.clone()
+                        };
+                        ::deno_core::RcRef::map(as_client, |v| &v.0)
+                    }
+                };
+            };
+
             // generate a fn for each deno op
-            let op_functions: Vec<proc_macro2::TokenStream> = methods
+            let mut op_functions: Vec<proc_macro2::TokenStream> = methods
                 .zip(op_idents.clone())
                 .map(|(m, op_ident)| {
                     let input_type = proto_reader::helpers::to_unqualified_type(m.input_type());
@@ -102,23 +123,7 @@ pub(crate) fn ops_generator(input: TokenStream) -> TokenStream {
                             ::proto::#module::#output_type,
                             ::deno_error::JsErrorBox
                         > {
-                            let client = match client_rid {
-                                None => ::deno_core::RcRef::new(::client::Client::default().await
-                                    .map_err(|e| ::deno_error::JsErrorBox::generic(
-                                        format!("Failed to create default client: {:?}",e.to_string()))
-                                    )?),
-                                Some(client_rid) => {
-                                    let as_client = {
-                                        let op_state = &op_state.borrow();
-                                        let rt = &op_state.resource_table; // get `ResourceTable` from JsRuntime `OpState`
-                                        rt.get::<crate::builtin::auraescript_client::AuraeScriptClient>(client_rid) // get `Client` from its rid
-                                    .map_err(|e| ::deno_error::JsErrorBox::generic(
-                                                format!("Failed to get client: {:?}",e.to_string()))) // fix client error
-                                            ?.clone()
-                                    };
-                                    ::deno_core::RcRef::map(as_client, |v| &v.0)
-                                }
-                            };
+                            #get_client
                             let res = ::client::#module::#service_name_in_snake_case::#client_ident::#name(
                                 &(*client),
                                 req
@@ -134,12 +139,80 @@ pub(crate) fn ops_generator(input: TokenStream) -> TokenStream {
                 })
                 .collect();
 
+            let streaming_op_idents: Vec<(Ident, Ident)> = streaming_methods.clone()
+                .map(|m| {
+                    let op_name = op_name(&module, s.name(), m.name());
+                    (
+                        Ident::new(&op_name, file_path_span),
+                        Ident::new(&next_op_name(&op_name), file_path_span),
+                    )
+                })
+                .collect();
+
+            // generate a pair of deno ops for each server streaming method: one
+            // to make the call, which returns the stream as a resource, and one
+            // to pull the next message from that resource
+            op_functions.extend(streaming_methods
+                .zip(streaming_op_idents.iter())
+                .map(|(m, (op_ident, next_op_ident))| {
+                    let input_type = proto_reader::helpers::to_unqualified_type(m.input_type());
+                    let input_type = Ident::new(input_type, file_path_span);
+                    let output_type = proto_reader::helpers::to_unqualified_type(m.output_type());
+                    let output_type = Ident::new(output_type, file_path_span);
+                    let name = Ident::new(&m.name().to_snake_case(), file_path_span);
+
+                    quote! {
+                        #[::deno_core::op2(async)]
+                        #[smi]
+                        pub(crate) async fn #op_ident(
+                            op_state: Rc<RefCell<OpState>>, // Auto filled by deno macro, call from typescript ignoring this parameter
+                            #[smi] client_rid: Option<::deno_core::ResourceId>,
+                            #[serde] req: ::proto::#module::#input_type,
+                        ) -> std::result::Result<
+                            ::deno_core::ResourceId,
+                            ::deno_error::JsErrorBox
+                        > {
+                            #get_client
+                            let res = ::client::#module::#service_name_in_snake_case::#client_ident::#name(
+                                &(*client),
+                                req
+                            ).await.map_err(|e| ::deno_error::JsErrorBox::from_err(
+                                crate::builtin::stream::GrpcError::from(e)))?;
+
+                            let stream = crate::builtin::stream::AuraeScriptStream::new(res.into_inner());
+                            Ok(op_state.borrow_mut().resource_table.add(stream))
+                        }
+
+                        #[::deno_core::op2(async)]
+                        #[serde]
+                        pub(crate) async fn #next_op_ident(
+                            op_state: Rc<RefCell<OpState>>, // Auto filled by deno macro, call from typescript ignoring this parameter
+                            #[smi] stream_rid: ::deno_core::ResourceId,
+                        ) -> std::result::Result<
+                            Option<::proto::#module::#output_type>,
+                            ::deno_error::JsErrorBox
+                        > {
+                            let stream = op_state
+                                .borrow()
+                                .resource_table
+                                .get::<crate::builtin::stream::AuraeScriptStream<::proto::#module::#output_type>>(stream_rid)
+                                .map_err(|e| ::deno_error::JsErrorBox::generic(
+                                    format!("Failed to get stream: {:?}", e.to_string())))?;
+
+                            stream.next().await.map_err(::deno_error::JsErrorBox::from_err)
+                        }
+                    }
+                }));
+
             // generate a OpDecl for each function for conveniently adding to the deno runtime
-            let op_decls: Vec<proc_macro2::TokenStream> = op_idents.map(|op_ident| {
-                quote! {
-                    #op_ident()
-                }
-            }).collect();
+            let op_decls: Vec<proc_macro2::TokenStream> = op_idents
+                .chain(streaming_op_idents.into_iter().flat_map(|(op_ident, next_op_ident)| [op_ident, next_op_ident]))
+                .map(|op_ident| {
+                    quote! {
+                        #op_ident()
+                    }
+                })
+                .collect();
 
             (op_functions, op_decls)
         })
@@ -267,6 +340,34 @@ export class {service_name}Client implements {service_name} {{
         let output_type =
             proto_reader::helpers::to_unqualified_type(m.output_type());
 
+        // server streaming methods are async generators, pulling a message
+        // from the stream each time the caller asks for the next one
+        if m.server_streaming() && !m.client_streaming() {
+            let next_op_name = next_op_name(&op_name);
+            ts_funcs.push_str(&format!(
+                r#"
+async *{fn_name}(request: {input_type}): AsyncIterable<{output_type}> {{
+    // @ts-ignore
+    const stream: number = await Deno.core.ops.{op_name}(this.client, request);
+    try {{
+        while (true) {{
+            // @ts-ignore
+            const response = await Deno.core.ops.{next_op_name}(stream);
+            if (response === null) {{
+                return;
+            }}
+            yield response;
+        }}
+    }} finally {{
+        // @ts-ignore
+        Deno.core.tryClose(stream);
+    }}
+}}
+        "#
+            ));
+            return;
+        }
+
         ts_funcs.push_str(&format!(
             r#"
 {fn_name}(request: {input_type}): Promise<{output_type}> {{
@@ -299,3 +400,8 @@ fn op_name(module: &Path, service_name: &str, method_name: &str) -> String {
         method_name.to_snake_case()
     )
 }
+
+/// Example `ae__observe__observe_service__watch_events__next`
+fn next_op_name(op_name: &str) -> String {
+    format!("{op_name}__next")
+}
@@ -22,6 +22,7 @@
 //! lives in this module.
 
 pub(crate) mod auraescript_client;
+pub(crate) mod stream;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
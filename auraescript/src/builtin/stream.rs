@@ -0,0 +1,93 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Server-streaming calls from AuraeScript.
+//!
+//! A stream is held as a resource of the runtime, and its messages are pulled
+//! one at a time as the script iterates over it. Nothing is read ahead of the
+//! script, which leaves the flow control to HTTP/2. Closing the resource
+//! (the script breaking out of its loop, or exiting) drops the stream, which
+//! cancels the call.
+
+use deno_core::{AsyncRefCell, CancelFuture, CancelHandle, RcRef, Resource};
+use std::{borrow::Cow, rc::Rc};
+use tonic::{Code, Status, Streaming};
+
+/// The error thrown in AuraeScript when a streaming call fails, as a
+/// `GrpcError` carrying the gRPC status code (e.g. "NOT_FOUND") in its `code`.
+#[derive(Debug, thiserror::Error, deno_error::JsError)]
+#[class("GrpcError")]
+#[error("{message}")]
+pub(crate) struct GrpcError {
+    #[property]
+    code: String,
+    message: String,
+}
+
+impl From<Status> for GrpcError {
+    fn from(status: Status) -> Self {
+        let code = status.code();
+        let message = match status.message() {
+            "" => code.description().to_string(),
+            message => message.to_string(),
+        };
+        Self { code: code_name(code), message }
+    }
+}
+
+/// The canonical name of a status code, as in the gRPC specification.
+fn code_name(code: Code) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{code:?}").char_indices() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+/// The response stream of a server-streaming call.
+pub(crate) struct AuraeScriptStream<T> {
+    stream: AsyncRefCell<Streaming<T>>,
+    cancel: CancelHandle,
+}
+
+impl<T: 'static> AuraeScriptStream<T> {
+    pub(crate) fn new(stream: Streaming<T>) -> Self {
+        Self { stream: AsyncRefCell::new(stream), cancel: CancelHandle::new() }
+    }
+
+    /// Waits for the next message. Returns `None` once the stream ends, or
+    /// when the resource is closed while waiting.
+    pub(crate) async fn next(self: Rc<Self>) -> Result<Option<T>, GrpcError> {
+        let mut stream = RcRef::map(&self, |s| &s.stream).borrow_mut().await;
+        let cancel = RcRef::map(&self, |s| &s.cancel);
+        match stream.message().or_cancel(cancel).await {
+            Ok(message) => Ok(message?),
+            Err(_canceled) => Ok(None),
+        }
+    }
+}
+
+impl<T: 'static> Resource for AuraeScriptStream<T> {
+    fn name(&self) -> Cow<str> {
+        "auraeScriptStream".into()
+    }
+
+    fn close(self: Rc<Self>) {
+        self.cancel.cancel()
+    }
+}
@@ -72,7 +72,11 @@ mod health;
 mod observe;
 mod vms;
 
-deno_core::extension!(auraescript, ops_fn = stdlib);
+deno_core::extension!(
+    auraescript,
+    ops_fn = stdlib,
+    js = [dir "src/builtin", "errors.js"]
+);
 
 pub fn runtime(
     main_module: Url,
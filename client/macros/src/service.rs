@@ -17,12 +17,16 @@ use proc_macro::TokenStream;
 use proc_macro2::Ident;
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
-use syn::{parse_macro_input, Lit, Path, Token};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, parse_macro_input, Lit, Path, Token};
 
 struct ServiceInput {
     file_path: Lit,
     module: Path,
     service_name: Ident,
+    /// The rpcs which may be retried, as they have the same effect however
+    /// many times they are called.
+    idempotent: Vec<Ident>,
 }
 
 impl Parse for ServiceInput {
@@ -33,12 +37,30 @@ impl Parse for ServiceInput {
         let _: Token![,] = input.parse()?;
         let service_name = input.parse()?;
 
-        Ok(Self { file_path, module, service_name })
+        let mut idempotent = vec![];
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let keyword: Ident = input.parse()?;
+            if keyword != "idempotent" {
+                return Err(syn::Error::new(
+                    keyword.span(),
+                    "expected idempotent(<rpc>, ...)",
+                ));
+            }
+            let rpcs;
+            let _ = parenthesized!(rpcs in input);
+            idempotent =
+                Punctuated::<Ident, Token![,]>::parse_terminated(&rpcs)?
+                    .into_iter()
+                    .collect();
+            let _: Option<Token![,]> = input.parse()?;
+        }
+
+        Ok(Self { file_path, module, service_name, idempotent })
     }
 }
 
 pub(crate) fn service(input: TokenStream) -> TokenStream {
-    let ServiceInput { file_path, module, service_name } =
+    let ServiceInput { file_path, module, service_name, idempotent } =
         parse_macro_input!(input as ServiceInput);
 
     let (_, proto) = proto_reader::parse(&file_path);
@@ -50,6 +72,13 @@ pub(crate) fn service(input: TokenStream) -> TokenStream {
         .find(|x| matches!(x.name(), n if service_name == n))
         .expect("failed to find service");
 
+    for rpc in &idempotent {
+        assert!(
+            service.method.iter().any(|m| rpc == m.name()),
+            "{service_name} has no rpc {rpc}"
+        );
+    }
+
     let client_namespace = Ident::new(
         &format!("{}_client", service_name.to_string().to_snake_case()),
         service_name.span(),
@@ -77,7 +106,17 @@ pub(crate) fn service(input: TokenStream) -> TokenStream {
                     todo!("bidirectional streaming")
                 }
                 (true, false) => {
-                    todo!("client streaming")
+                    quote! {
+                        async fn #name(
+                            &self,
+                            req: impl ::tonic::IntoStreamingRequest<
+                                Message = ::proto::#module::#input_type
+                            > + Send + 'static
+                        ) -> Result<
+                            ::tonic::Response<::proto::#module::#output_type>,
+                            ::tonic::Status
+                        >
+                    }
                 },
                 (false, true) => {
                     quote! {
@@ -107,15 +146,39 @@ pub(crate) fn service(input: TokenStream) -> TokenStream {
         },
     ).collect();
 
+    // Unary calls may be retried, streaming calls never are
     let rpc_implementations: Vec<_> = rpc_signatures
         .iter()
         .zip(fn_name_idents)
-        .map(|(signature, name)| {
-            quote! {
-                #signature {
-                    let mut client = ::proto::#module::#client_namespace::#client_ident::new(self.channel.clone());
+        .zip(&service.method)
+        .map(|((signature, name), m)| {
+            let call = quote! {
+                |channel, req| async move {
+                    let mut client = ::proto::#module::#client_namespace::#client_ident::new(channel);
                     client.#name(req).await
                 }
+            };
+
+            if m.client_streaming.unwrap_or(false) {
+                quote! {
+                    #signature {
+                        let req = ::tonic::IntoStreamingRequest::into_streaming_request(req);
+                        self.call_streaming(req, #call).await
+                    }
+                }
+            } else if m.server_streaming.unwrap_or(false) {
+                quote! {
+                    #signature {
+                        self.call_streaming(::tonic::Request::new(req), #call).await
+                    }
+                }
+            } else {
+                let idempotent = idempotent.iter().any(|rpc| rpc == m.name());
+                quote! {
+                    #signature {
+                        self.call(#idempotent, req, #call).await
+                    }
+                }
             }
         }).collect();
 
@@ -13,4 +13,9 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-macros::service!("../api/v0/cells/cells.proto", cells, CellService);
\ No newline at end of file
+macros::service!(
+    "../api/v0/cells/cells.proto",
+    cells,
+    CellService,
+    idempotent(Export, List, ListExecutables, Stats)
+);
\ No newline at end of file
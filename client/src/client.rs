@@ -18,12 +18,20 @@
 //! Manages authenticating with remote Aurae instances, as well as searching
 //! the local filesystem for configuration and authentication material.
 
-use crate::config::{AuraeConfig, CertMaterial, ClientCertDetails};
-use crate::AuraeSocket;
+use crate::config::{AuraeConfig, ClientCertDetails};
+use crate::connection::{ConnectionState, Disconnected, RetryPolicy};
+use crate::node::Nodes;
+use crate::{tls, AuraeSocket, AuthConfig};
 use hyper_util::rt::TokioIo;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use tokio::net::{TcpStream, UnixStream};
-use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity, Uri};
+use tokio::sync::watch;
+use tonic::transport::{Channel, ClientTlsConfig, Uri};
 use tower::service_fn;
 
 const KNOWN_IGNORED_SOCKET_ADDR: &str = "hxxp://null";
@@ -36,16 +44,38 @@ pub enum ClientError {
     #[error(transparent)]
     ConnectionError(#[from] tonic::transport::Error),
     #[error(transparent)]
+    Disconnected(#[from] Disconnected),
+    #[error("cannot connect to socket '{}': {source}", path.display())]
+    Socket { path: PathBuf, source: std::io::Error },
+    #[error("node '{node}' not found, known nodes: [{}]", known.join(", "))]
+    NodeNotFound { node: String, known: Vec<String> },
+    #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
 /// Instance of a single client for an Aurae consumer.
 #[derive(Debug, Clone)]
 pub struct Client {
+    /// Replaced when the TLS material is reloaded.
+    pub(crate) transport: Arc<RwLock<Transport>>,
+    /// How long before the client certificate expires to warn about it.
+    expiry_warning: Duration,
+    /// How long each call is given, if limited.
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) state: Arc<watch::Sender<ConnectionState>>,
+    /// Resolves the names of the nodes other clients are made for.
+    pub(crate) nodes: Nodes,
+}
+
+/// The channel of a [Client], and the certificate it authenticates with.
+#[derive(Debug)]
+pub(crate) struct Transport {
     /// The channel used for gRPC connections before encryption is handled.
     pub(crate) channel: Channel,
-    #[allow(unused)]
-    client_cert_details: Option<ClientCertDetails>,
+    /// The TLS config of the channel, for the clients of other nodes.
+    pub(crate) tls_config: Option<ClientTlsConfig>,
+    pub(crate) client_cert_details: Option<ClientCertDetails>,
 }
 
 impl Client {
@@ -55,40 +85,107 @@ impl Client {
 
     /// Create a new Client.
     ///
+    /// A unix socket without TLS (a unix:// socket) is connected to without
+    /// certificates. It must exist and be writable by the user.
+    ///
+    /// Warns if the client certificate expires within the expiry warning of
+    /// the [crate::AuthConfig], and watches the certificates for changes if it
+    /// has a reload interval.
+    ///
+    /// The names of nodes are resolved with the discovery service of the
+    /// discovery socket of the [crate::SystemConfig], if it has one.
+    ///
     /// Note: A new client is required for every independent execution of this process.
     pub async fn new(
         AuraeConfig { auth, system }: AuraeConfig,
     ) -> Result<Self> {
-        let cert_material = auth.to_cert_material().await?;
-        let client_cert_details =
-            Some(cert_material.get_client_cert_details()?);
+        let client = Self::connect_to(system.socket, auth.clone()).await?;
+        match system.discovery {
+            Some(discovery) => {
+                let discovery = Self::connect_to(discovery, auth).await?;
+                Ok(client.with_discovery(discovery))
+            }
+            None => Ok(client),
+        }
+    }
 
-        let CertMaterial { server_root_ca_cert, client_cert, client_key } =
-            cert_material;
+    async fn connect_to(socket: AuraeSocket, auth: AuthConfig) -> Result<Self> {
+        if let AuraeSocket::Unix(path) = &socket {
+            check_unix_socket(path)?;
+            return Self::connect(socket, None, None);
+        }
 
-        let tls_config = ClientTlsConfig::new()
-            // TODO: get this from the config or the cert information somehow
-            .domain_name("server.unsafe.aurae.io")
-            .ca_certificate(Certificate::from_pem(server_root_ca_cert))
-            .identity(Identity::from_pem(client_cert, client_key));
+        let (tls_config, client_cert_details) = tls::load(&auth).await?;
+        tls::warn_expiring(&client_cert_details, auth.expiry_warning);
 
-        let channel =
-            Self::connect_chan(system.socket.clone(), Some(tls_config)).await?;
-        Ok(Self { channel, client_cert_details })
+        let mut client = Self::connect(
+            socket.clone(),
+            Some(tls_config),
+            Some(client_cert_details),
+        )?;
+        client.expiry_warning = auth.expiry_warning;
+
+        if let Some(interval) = auth.reload_interval {
+            tls::watch(&client, socket, auth, interval);
+        }
+
+        Ok(client)
     }
 
     /// Create a new Client without TLS, remote server should also expect no TLS.
     ///
     /// Note: A new client is required for every independent execution of this process.
     pub async fn new_no_tls(socket: AuraeSocket) -> Result<Self> {
-        let channel = Self::connect_chan(socket, None).await?;
-        let client_cert_details = None;
-        Ok(Self { channel, client_cert_details })
+        Self::connect(socket, None, None)
+    }
+
+    pub(crate) fn connect(
+        socket: AuraeSocket,
+        tls_config: Option<ClientTlsConfig>,
+        client_cert_details: Option<ClientCertDetails>,
+    ) -> Result<Self> {
+        let state = Arc::new(watch::channel(ConnectionState::Idle).0);
+        let channel =
+            Self::connect_chan(socket, tls_config.clone(), state.clone())?;
+
+        Ok(Self {
+            transport: Arc::new(RwLock::new(Transport {
+                channel,
+                tls_config,
+                client_cert_details,
+            })),
+            expiry_warning: Duration::ZERO,
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            state,
+            nodes: Nodes::default(),
+        })
+    }
+
+    /// Details of the client certificate, as last loaded. None without TLS.
+    pub fn client_cert_details(&self) -> Option<ClientCertDetails> {
+        self.transport.read().expect("poisoned").client_cert_details.clone()
+    }
+
+    /// When the client certificate expires, if within the expiry warning of
+    /// the [crate::AuthConfig] from now.
+    pub fn cert_expiring(&self) -> Option<SystemTime> {
+        let transport = self.transport.read().expect("poisoned");
+        let details = transport.client_cert_details.as_ref()?;
+        tls::expiring(details, self.expiry_warning)
+    }
+
+    /// The channel for new calls.
+    pub(crate) fn channel(&self) -> Channel {
+        self.transport.read().expect("poisoned").channel.clone()
     }
 
-    async fn connect_chan(
+    /// The channel connects on the first call, and reconnects on the next
+    /// call once the connection is lost, rather than failing for good.
+    pub(crate) fn connect_chan(
         socket: AuraeSocket,
         tls_config: Option<ClientTlsConfig>,
+        state: Arc<watch::Sender<ConnectionState>>,
     ) -> Result<Channel> {
         let endpoint = match tls_config {
             None => Channel::from_static(KNOWN_IGNORED_SOCKET_ADDR),
@@ -101,33 +198,167 @@ impl Client {
         // If the system socket looks like a SocketAddr, bind to it directly.  Otherwise,
         // connect as a UNIX socket (assume it's a file path).
         let channel = match socket {
-            AuraeSocket::Path(path) => {
-                endpoint
-                    .connect_with_connector(service_fn({
-                        move |_: Uri| {
-                            let path = path.clone();
-                            async move {
-                                Ok::<_, std::io::Error>(TokioIo::new(
-                                    UnixStream::connect(path).await?,
-                                ))
-                            }
-                        }
-                    }))
-                    .await
-            }
-            AuraeSocket::Addr(addr) => {
-                endpoint
-                    .connect_with_connector(service_fn({
-                        move |_: Uri| async move {
-                            Ok::<_, std::io::Error>(TokioIo::new(
-                                TcpStream::connect(addr).await?,
-                            ))
-                        }
-                    }))
-                    .await
-            }
-        }?;
+            AuraeSocket::Path(path) | AuraeSocket::Unix(path) => endpoint
+                .connect_with_connector_lazy(service_fn(move |_: Uri| {
+                    let path = path.clone();
+                    let state = state.clone();
+                    async move {
+                        let stream =
+                            observe_connect(&state, connect_unix(&path))
+                                .await?;
+                        Ok::<_, std::io::Error>(TokioIo::new(stream))
+                    }
+                })),
+            AuraeSocket::Addr(addr) => endpoint.connect_with_connector_lazy(
+                service_fn(move |_: Uri| {
+                    let state = state.clone();
+                    async move {
+                        let stream =
+                            observe_connect(&state, TcpStream::connect(addr))
+                                .await?;
+                        Ok::<_, std::io::Error>(TokioIo::new(stream))
+                    }
+                }),
+            ),
+        };
 
         Ok(channel)
     }
 }
+
+/// Publishes the state of the connection as it is established.
+async fn observe_connect<S>(
+    state: &watch::Sender<ConnectionState>,
+    connect: impl std::future::Future<Output = std::io::Result<S>>,
+) -> std::io::Result<S> {
+    let _ = state.send_replace(ConnectionState::Connecting);
+    let res = connect.await;
+    let _ = state.send_replace(match res {
+        Ok(_) => ConnectionState::Ready,
+        Err(_) => ConnectionState::TransientFailure,
+    });
+    res
+}
+
+/// Checks that the user may connect to the unix socket at `path`, unless it
+/// is in the abstract namespace.
+fn check_unix_socket(path: &Path) -> Result<()> {
+    if path.to_str().is_some_and(|path| path.starts_with('@')) {
+        return Ok(());
+    }
+
+    let error = |source| ClientError::Socket { path: path.into(), source };
+    let metadata = std::fs::metadata(path).map_err(error)?;
+    if !metadata.file_type().is_socket() {
+        return Err(error(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "not a socket",
+        )));
+    }
+
+    // Connecting takes write permission on the socket
+    nix::unistd::access(path, nix::unistd::AccessFlags::W_OK)
+        .map_err(|e| error(e.into()))
+}
+
+/// Connects to the unix socket at `path`, or in the abstract namespace if
+/// `path` starts with '@'. Errors name the path.
+async fn connect_unix(path: &Path) -> std::io::Result<UnixStream> {
+    let Some(name) = path.to_str().and_then(|path| path.strip_prefix('@'))
+    else {
+        return UnixStream::connect(path).await.map_err(|e| {
+            std::io::Error::new(e.kind(), format!("{}: {e}", path.display()))
+        });
+    };
+
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+    let stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+    stream.set_nonblocking(true)?;
+    UnixStream::from_std(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::health::health::HealthClient;
+    use crate::SystemConfig;
+    use proto::grpc::health::{
+        health_check_response::ServingStatus,
+        health_server::{Health, HealthServer},
+        HealthCheckRequest, HealthCheckResponse,
+    };
+    use tokio_stream::wrappers::{ReceiverStream, UnixListenerStream};
+    use tonic::{Request, Response, Status};
+
+    struct Serving;
+
+    #[tonic::async_trait]
+    impl Health for Serving {
+        async fn check(
+            &self,
+            _: Request<HealthCheckRequest>,
+        ) -> std::result::Result<Response<HealthCheckResponse>, Status>
+        {
+            Ok(Response::new(HealthCheckResponse {
+                status: ServingStatus::Serving as i32,
+            }))
+        }
+
+        type WatchStream =
+            ReceiverStream<std::result::Result<HealthCheckResponse, Status>>;
+
+        async fn watch(
+            &self,
+            _: Request<HealthCheckRequest>,
+        ) -> std::result::Result<Response<Self::WatchStream>, Status> {
+            Err(Status::unimplemented("watch"))
+        }
+    }
+
+    fn unix_config(path: &Path) -> AuraeConfig {
+        AuraeConfig {
+            auth: Default::default(),
+            system: SystemConfig {
+                socket: AuraeSocket::Unix(path.into()),
+                discovery: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_without_tls() {
+        let path = std::env::temp_dir()
+            .join(format!("ae-test-client-unix-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let _server = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(HealthServer::new(Serving))
+                .serve_with_incoming(UnixListenerStream::new(listener)),
+        );
+
+        let client = Client::new(unix_config(&path)).await.unwrap();
+        let response = client
+            .check(HealthCheckRequest { service: String::new() })
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().status(), ServingStatus::Serving);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_unix_socket_is_named() {
+        let path = std::env::temp_dir().join("ae-test-client-missing.sock");
+
+        let Err(e) = Client::new(unix_config(&path)).await else {
+            panic!("expected an error");
+        };
+        assert!(
+            matches!(&e, ClientError::Socket { path: p, source }
+                if *p == path && source.kind() == std::io::ErrorKind::NotFound),
+            "{e:?}"
+        );
+        assert!(e.to_string().contains(&*path.to_string_lossy()));
+    }
+}
@@ -15,13 +15,18 @@
 
 use crate::config::cert_material::CertMaterial;
 use serde::Deserialize;
+use std::time::Duration;
 
 /// Authentication material for an AuraeScript client.
 ///
 /// This material is read from disk many times during runtime.
 /// Changing this material during a process will impact the currently
 /// running process.
+///
+/// Not needed to connect to a unix socket without TLS, and may be left out of
+/// the config then.
 #[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct AuthConfig {
     /// The same CA certificate the server has.
     pub ca_crt: String,
@@ -29,10 +34,52 @@ pub struct AuthConfig {
     pub client_crt: String,
     /// The client secret key.
     pub client_key: String,
+    /// Seconds between checks of the files above for changes, after which
+    /// the client reloads them. The files are not watched when unset.
+    #[serde(with = "secs_option")]
+    pub reload_interval: Option<Duration>,
+    /// Seconds before the client certificate expires from which the client
+    /// warns about it.
+    #[serde(with = "secs")]
+    pub expiry_warning: Duration,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            ca_crt: String::new(),
+            client_crt: String::new(),
+            client_key: String::new(),
+            reload_interval: None,
+            expiry_warning: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
 }
 
 impl AuthConfig {
     pub async fn to_cert_material(&self) -> anyhow::Result<CertMaterial> {
         CertMaterial::from_config(self).await
     }
-}
\ No newline at end of file
+}
+/// Deserializes a [Duration] from a number of seconds.
+mod secs {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+mod secs_option {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
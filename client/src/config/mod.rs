@@ -44,6 +44,7 @@ mod x509_details;
 #[derive(Debug, Clone, Deserialize)]
 pub struct AuraeConfig {
     /// Authentication material
+    #[serde(default)]
     pub auth: AuthConfig,
     /// System configuration
     pub system: SystemConfig,
@@ -125,8 +126,10 @@ impl AuraeConfig {
             client_key.into(),
             socket.into(),
         );
-        let auth = AuthConfig { ca_crt, client_crt, client_key };
-        let system = SystemConfig { socket: AuraeSocket::Path(socket.into()) };
+        let auth =
+            AuthConfig { ca_crt, client_crt, client_key, ..Default::default() };
+        let Ok(socket) = socket.parse();
+        let system = SystemConfig { socket, discovery: None };
         Self { auth, system }
     }
 }
@@ -159,6 +162,67 @@ socket = "#;
         )
     }
 
+    #[test]
+    fn can_parse_toml_config_unix_socket_without_auth() {
+        let input = r#"
+[system]
+socket = "unix:///run/aurae/local.sock""#;
+        let config = AuraeConfig::parse_from_toml(input).unwrap();
+        assert!(
+            matches!(config.system.socket, AuraeSocket::Unix(path) if Some("/run/aurae/local.sock") == path.to_str())
+        )
+    }
+
+    #[test]
+    fn can_parse_toml_config_reload_interval_and_expiry_warning() {
+        let input = r#"
+[auth]
+ca_crt = "~/.aurae/pki/ca.crt"
+client_crt = "~/.aurae/pki/_signed.client.nova.crt"
+client_key = "~/.aurae/pki/client.nova.key"
+reload_interval = 60
+expiry_warning = 3600
+
+[system]
+socket = "/var/run/aurae/aurae.sock""#;
+        let config = AuraeConfig::parse_from_toml(input).unwrap();
+        assert_eq!(
+            config.auth.reload_interval,
+            Some(std::time::Duration::from_secs(60))
+        );
+        assert_eq!(
+            config.auth.expiry_warning,
+            std::time::Duration::from_secs(3600)
+        );
+
+        let config =
+            AuraeConfig::parse_from_toml(&get_input("/run/aurae.sock"))
+                .unwrap();
+        assert_eq!(config.auth.reload_interval, None);
+        assert_eq!(
+            config.auth.expiry_warning,
+            AuthConfig::default().expiry_warning
+        );
+    }
+
+    #[test]
+    fn can_parse_toml_config_discovery() {
+        let input = r#"
+[system]
+socket = "unix:///run/aurae/local.sock"
+discovery = "[fe80::2]:8080""#;
+        let config = AuraeConfig::parse_from_toml(input).unwrap();
+        assert!(matches!(
+            config.system.discovery,
+            Some(AuraeSocket::Addr(addr)) if addr.port() == 8080
+        ));
+
+        let config =
+            AuraeConfig::parse_from_toml(&get_input("/run/aurae.sock"))
+                .unwrap();
+        assert!(config.system.discovery.is_none());
+    }
+
     #[test]
     fn can_parse_toml_config_socket_ipv6_with_scope_id() {
         let input = get_input("[fe80::2%4]:8080");
@@ -15,9 +15,11 @@
 
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer};
+use std::convert::Infallible;
 use std::fmt::Formatter;
 use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 /// The system configuration for AuraeScript.
 ///
@@ -25,21 +27,46 @@ use std::path::PathBuf;
 #[derive(Debug, Clone, Deserialize)]
 pub struct SystemConfig {
     /// Socket to connect the client to.  Can be a path (unix socket) or a network socket address.
+    /// A path starting with '@' names a unix socket in the abstract namespace.
     ///
     /// When deserializing from a string, the deserializer will try to parse a valid value in the following order:
     /// - IpV6 with scope id (e.g., "[fe80::2%4]:8080")
     /// - IpV6 without scope id (e.g., "[fe80::2]:8080")
     /// - IpV4 (e.g., "127.0.0.1:8080")
+    /// - A path after "unix://", for a unix socket without TLS
     /// - Otherwise a path
     ///
     /// scope id must be a valid u32, otherwise it will be assumed a path
     pub socket: AuraeSocket,
+    /// Socket of the auraed whose discovery service resolves the names of
+    /// the nodes, parsed as the socket is. Defaults to the socket.
+    #[serde(default)]
+    pub discovery: Option<AuraeSocket>,
 }
 
 #[derive(Debug, Clone)]
 pub enum AuraeSocket {
     Path(PathBuf),
     Addr(SocketAddr),
+    /// A unix socket served without TLS, as by `auraed --listen unix:<path>`,
+    /// so no certificates are needed to connect to it.
+    Unix(PathBuf),
+}
+
+impl FromStr for AuraeSocket {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if let Ok(addr) = s.parse::<SocketAddrV6>() {
+            AuraeSocket::Addr(addr.into())
+        } else if let Ok(addr) = s.parse::<SocketAddrV4>() {
+            AuraeSocket::Addr(addr.into())
+        } else if let Some(path) = s.strip_prefix("unix://") {
+            AuraeSocket::Unix(path.into())
+        } else {
+            AuraeSocket::Path(s.into())
+        })
+    }
 }
 
 impl<'de> Deserialize<'de> for AuraeSocket {
@@ -57,7 +84,9 @@ impl<'de> Visitor<'de> for AuraeSocketVisitor {
     type Value = AuraeSocket;
 
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-        formatter.write_str("a path (unix socket) or a network socket address")
+        formatter.write_str(
+            "a path (unix socket), a unix:// URI or a network socket address",
+        )
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -78,13 +107,8 @@ impl<'de> Visitor<'de> for AuraeSocketVisitor {
     where
         E: Error,
     {
-        if let Ok(addr) = v.parse::<SocketAddrV6>() {
-            Ok(AuraeSocket::Addr(addr.into()))
-        } else if let Ok(addr) = v.parse::<SocketAddrV4>() {
-            Ok(AuraeSocket::Addr(addr.into()))
-        } else {
-            Ok(AuraeSocket::Path(v.into()))
-        }
+        let Ok(socket) = v.parse();
+        Ok(socket)
     }
 }
 
@@ -107,6 +131,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_parse_aurae_socket_unix_uri() {
+        let visitor = AuraeSocketVisitor {};
+
+        let res = visitor
+            .visit_str::<toml::de::Error>("unix:///run/aurae/local.sock")
+            .unwrap();
+
+        assert!(
+            matches!(res, AuraeSocket::Unix(path) if Some("/run/aurae/local.sock") == path.to_str())
+        );
+    }
+
     #[test]
     fn can_parse_aurae_socket_ipv6() {
         let visitor = AuraeSocketVisitor {};
@@ -45,6 +45,7 @@
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
+use std::time::SystemTime;
 use x509_certificate::X509Certificate;
 
 /// An in-memory representation of an X509 identity, and its metadata.
@@ -58,6 +59,8 @@ pub struct X509Details {
     pub sha256_fingerprint: String,
     /// From the SSL spec, the algorithm used for encryption.
     pub key_algorithm: String,
+    /// From the SSL spec, the time after which the certificate is invalid.
+    pub not_after: SystemTime,
     // Force instantiation through function
     phantom_data: PhantomData<()>,
 }
@@ -89,6 +92,7 @@ pub(crate) fn new_x509_details(
         issuer_common_name,
         sha256_fingerprint: format!("{sha256_fingerprint:?}"),
         key_algorithm,
+        not_after: x509.validity_not_after().into(),
         phantom_data: PhantomData,
     })
 }
\ No newline at end of file
@@ -0,0 +1,322 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Keeps calls to auraed going through short outages. The channel of a
+//! [Client] connects lazily, and reconnects on the next call once the
+//! connection is lost. Idempotent unary calls are retried with exponential
+//! backoff while auraed is unavailable, and all calls may be given a
+//! deadline.
+
+use crate::Client;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Response, Status};
+
+/// The state of the connection of a [Client] to auraed, as in the gRPC
+/// connectivity semantics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No call was made yet, so no connection was attempted.
+    Idle,
+    /// Connecting to auraed.
+    Connecting,
+    /// Connected to auraed.
+    Ready,
+    /// The last connection attempt failed, or the connection was lost. The
+    /// next call reconnects.
+    TransientFailure,
+}
+
+/// How idempotent unary calls are retried while auraed is unavailable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of attempts of a call, including the first.
+    pub max_attempts: u32,
+    /// The backoff before the first retry.
+    pub initial_backoff: Duration,
+    /// The backoff is multiplied by this after each retry.
+    pub multiplier: f64,
+    /// The backoff does not grow past this.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// The backoff after `attempt` failed, counting from 1. Between half
+    /// of the exponential backoff and all of it, so that clients which
+    /// failed together don't retry together.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self
+            .initial_backoff
+            .mul_f64(self.multiplier.powi(exponent).min(u32::MAX as f64))
+            .min(self.max_backoff);
+
+        let jitter =
+            RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff / 2 + (backoff / 2).mul_f64(jitter)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The connection to auraed was lost, or could not be established, so the
+/// call did not complete. Streaming calls are not retried, and fail with
+/// the [Status] this is made from instead.
+#[derive(Debug, Error)]
+#[error("disconnected from auraed: {}", .0.message())]
+pub struct Disconnected(pub Status);
+
+impl TryFrom<Status> for Disconnected {
+    type Error = Status;
+
+    /// Returns the status back if it was returned by auraed, rather than
+    /// caused by the connection.
+    fn try_from(status: Status) -> Result<Self, Self::Error> {
+        if is_disconnected(&status) {
+            Ok(Self(status))
+        } else {
+            Err(status)
+        }
+    }
+}
+
+/// Returns true if the call failed for the connection to auraed, rather
+/// than with a status auraed returned, UNAVAILABLE included. The channel
+/// makes those statuses from the error of the connection, which it keeps as
+/// their source.
+fn is_disconnected(status: &Status) -> bool {
+    status.code() == Code::Unavailable
+        && std::error::Error::source(status).is_some()
+}
+
+impl Client {
+    /// Gives each call `timeout` to complete, retries included, after which
+    /// it fails with [Code::DeadlineExceeded]. auraed is sent the deadline
+    /// as well. A streaming call is given `timeout` to open its stream.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries idempotent unary calls as in `retry_policy`.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Watches the state of the connection to auraed, e.g. to show that the
+    /// client is reconnecting.
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Makes a unary call, retried if `idempotent` as in the retry policy.
+    #[doc(hidden)]
+    pub async fn call<T, R, F, Fut>(
+        &self,
+        idempotent: bool,
+        req: T,
+        call: F,
+    ) -> Result<Response<R>, Status>
+    where
+        T: Clone,
+        F: Fn(Channel, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let max_attempts =
+            if idempotent { self.retry_policy.max_attempts } else { 1 };
+
+        let mut attempt = 1;
+        loop {
+            let res = self
+                .call_once(deadline, Request::new(req.clone()), &call)
+                .await;
+
+            match res {
+                Err(e)
+                    if e.code() == Code::Unavailable
+                        && attempt < max_attempts =>
+                {
+                    let backoff = self.retry_policy.backoff(attempt);
+                    if deadline.is_some_and(|d| Instant::now() + backoff >= d) {
+                        return Err(e);
+                    }
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Makes a call which is never retried, such as a streaming call.
+    #[doc(hidden)]
+    pub async fn call_streaming<T, R, F, Fut>(
+        &self,
+        req: Request<T>,
+        call: F,
+    ) -> Result<Response<R>, Status>
+    where
+        F: Fn(Channel, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.call_once(deadline, req, &call).await
+    }
+
+    async fn call_once<T, R, F, Fut>(
+        &self,
+        deadline: Option<Instant>,
+        mut req: Request<T>,
+        call: &F,
+    ) -> Result<Response<R>, Status>
+    where
+        F: Fn(Channel, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        let res = match deadline {
+            None => call(self.channel(), req).await,
+            Some(deadline) => {
+                req.set_timeout(
+                    deadline.saturating_duration_since(Instant::now()),
+                );
+                let res = tokio::time::timeout_at(
+                    deadline,
+                    call(self.channel(), req),
+                )
+                .await;
+
+                // The channel cancels the call itself once the timeout sent
+                // to auraed expires
+                match res {
+                    Ok(Err(e))
+                        if e.code() == Code::Cancelled
+                            && Instant::now() >= deadline =>
+                    {
+                        Err(Status::deadline_exceeded("deadline exceeded"))
+                    }
+                    Ok(res) => res,
+                    Err(_) => {
+                        Err(Status::deadline_exceeded("deadline exceeded"))
+                    }
+                }
+            }
+        };
+
+        if matches!(&res, Err(e) if is_disconnected(e)) {
+            let _ = self.state.send_replace(ConnectionState::TransientFailure);
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cells::cell_service::CellServiceClient;
+    use crate::AuraeSocket;
+    use proto::cells::CellServiceListRequest;
+
+    fn socket_path() -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("ae-test-client-{}.sock", std::process::id()))
+    }
+
+    #[test]
+    fn test_backoff_grows_to_max_backoff_with_jitter() {
+        let policy = RetryPolicy::default();
+        for attempt in 1..10 {
+            let backoff = policy.backoff(attempt);
+            let exponential = (policy.initial_backoff * 2u32.pow(attempt - 1))
+                .min(policy.max_backoff);
+            assert!(backoff >= exponential / 2, "{attempt}: {backoff:?}");
+            assert!(backoff <= exponential, "{attempt}: {backoff:?}");
+        }
+
+        assert!(policy.backoff(u32::MAX) <= policy.max_backoff);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_call_is_retried_while_unavailable() {
+        let path = socket_path().with_extension("missing");
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(1),
+        };
+        let client = Client::new_no_tls(AuraeSocket::Path(path))
+            .await
+            .expect("client connects lazily")
+            .with_retry_policy(policy);
+        let state = client.connection_state();
+        assert_eq!(*state.borrow(), ConnectionState::Idle);
+
+        let started = Instant::now();
+        let status = client.list(CellServiceListRequest {}).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(Disconnected::try_from(status).is_ok());
+        assert_eq!(*state.borrow(), ConnectionState::TransientFailure);
+
+        // Backoffs of at least 50ms and 100ms
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_unavailable_returned_by_auraed_is_not_a_disconnect() {
+        let status = Status::unavailable("auraed is shutting down");
+        assert!(Disconnected::try_from(status).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_call_fails_past_timeout() {
+        // A socket which is never answered
+        let path = socket_path();
+        let _ = std::fs::remove_file(&path);
+        let _listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let client = Client::new_no_tls(AuraeSocket::Path(path.clone()))
+            .await
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+
+        let status = client.list(CellServiceListRequest {}).await.unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
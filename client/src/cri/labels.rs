@@ -0,0 +1,277 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! The labels of pod sandboxes, and the selectors listing pod sandboxes by
+//! their labels.
+//!
+//! A selector is a comma-separated list of `key=value` and `key!=value`
+//! terms, all of which the labels of a pod sandbox must match. The
+//! `label_selector` of a CRI filter is a map, which only holds equalities,
+//! so a `key!=value` term is the `key!` entry of the map. `!` is not valid
+//! in a label key, so the two can't be confused.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The longest name of a label key, and the longest label value.
+const MAX_NAME_LEN: usize = 63;
+/// The longest prefix of a label key, a DNS subdomain.
+const MAX_PREFIX_LEN: usize = 253;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum LabelError {
+    #[error("invalid label key '{key}': {reason}")]
+    InvalidKey { key: String, reason: &'static str },
+    #[error("invalid value '{value}' of label '{key}': {reason}")]
+    InvalidValue { key: String, value: String, reason: &'static str },
+    #[error(
+        "invalid selector term '{term}': expected key=value or key!=value"
+    )]
+    InvalidTerm { term: String },
+    #[error("label '{key}' is selected by more than one {op} term")]
+    RepeatedTerm { key: String, op: &'static str },
+}
+
+/// Validates a label as Kubernetes does. The key is a name, optionally
+/// prefixed by a DNS subdomain and a `/`. The value is empty, or a name.
+/// A name is at most 63 alphanumerics, `-`, `_` and `.`, beginning and
+/// ending with an alphanumeric.
+pub fn validate_label(key: &str, value: &str) -> Result<(), LabelError> {
+    let invalid_key =
+        |reason| LabelError::InvalidKey { key: key.to_string(), reason };
+
+    let (prefix, name) = match key.split_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    if let Some(prefix) = prefix {
+        if prefix.is_empty() || prefix.len() > MAX_PREFIX_LEN {
+            return Err(invalid_key("the prefix must be 1 to 253 characters"));
+        }
+        if !prefix.split('.').all(is_dns_label) {
+            return Err(invalid_key("the prefix must be a DNS subdomain"));
+        }
+    }
+    validate_name(name).map_err(invalid_key)?;
+
+    if !value.is_empty() {
+        validate_name(value).map_err(|reason| LabelError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err("must be 1 to 63 characters");
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !name.ends_with(|c: char| c.is_ascii_alphanumeric())
+    {
+        return Err("must begin and end with an alphanumeric");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err("must only have alphanumerics, '-', '_' and '.'");
+    }
+    Ok(())
+}
+
+fn is_dns_label(label: &str) -> bool {
+    !label.is_empty()
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// The labels pod sandboxes are listed by. The empty selector selects every
+/// pod sandbox.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    equal: HashMap<String, String>,
+    not_equal: HashMap<String, String>,
+}
+
+impl LabelSelector {
+    /// The selector of the `label_selector` of a CRI filter.
+    pub fn from_filter(
+        label_selector: &HashMap<String, String>,
+    ) -> Result<Self, LabelError> {
+        let mut selector = Self::default();
+        for (key, value) in label_selector {
+            match key.strip_suffix('!') {
+                Some(key) => selector.not_equal(key, value)?,
+                None => selector.equal(key, value)?,
+            }
+        }
+        Ok(selector)
+    }
+
+    /// The `label_selector` of a CRI filter for the selector.
+    pub fn to_filter(&self) -> HashMap<String, String> {
+        self.not_equal
+            .iter()
+            .map(|(key, value)| (format!("{key}!"), value.clone()))
+            .chain(self.equal.clone())
+            .collect()
+    }
+
+    /// Whether `labels` match each of the terms of the selector. A label
+    /// which is not set is not equal to any value.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.equal.iter().all(|(key, value)| labels.get(key) == Some(value))
+            && self
+                .not_equal
+                .iter()
+                .all(|(key, value)| labels.get(key) != Some(value))
+    }
+
+    fn equal(&mut self, key: &str, value: &str) -> Result<(), LabelError> {
+        validate_label(key, value)?;
+        match self.equal.insert(key.to_string(), value.to_string()) {
+            Some(_) => {
+                Err(LabelError::RepeatedTerm { key: key.to_string(), op: "=" })
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn not_equal(&mut self, key: &str, value: &str) -> Result<(), LabelError> {
+        validate_label(key, value)?;
+        match self.not_equal.insert(key.to_string(), value.to_string()) {
+            Some(_) => {
+                Err(LabelError::RepeatedTerm { key: key.to_string(), op: "!=" })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = LabelError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let mut parsed = Self::default();
+        for term in selector.split(',').map(str::trim) {
+            if term.is_empty() && selector.trim().is_empty() {
+                continue;
+            }
+            if let Some((key, value)) = term.split_once("!=") {
+                parsed.not_equal(key.trim(), value.trim())?;
+            } else if let Some((key, value)) = term.split_once('=') {
+                parsed.equal(key.trim(), value.trim())?;
+            } else {
+                return Err(LabelError::InvalidTerm { term: term.to_string() });
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(labels: &[(&str, &str)]) -> HashMap<String, String> {
+        labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let selector: LabelSelector =
+            "app=web, tier!=db,aurae.io/team=".parse().unwrap();
+        assert_eq!(
+            selector.equal,
+            labels(&[("app", "web"), ("aurae.io/team", "")])
+        );
+        assert_eq!(selector.not_equal, labels(&[("tier", "db")]));
+
+        assert_eq!("".parse(), Ok(LabelSelector::default()));
+        assert_eq!(" ".parse(), Ok(LabelSelector::default()));
+    }
+
+    #[test]
+    fn test_parse_malformed_selector() {
+        for selector in [
+            "app",
+            "app=web,",
+            ",app=web",
+            "app=web,,tier=db",
+            "=web",
+            "app==web",
+            "app=web=db",
+            "-app=web",
+            "my app=web",
+            "Aurae.io/app=web",
+            "app=web,app=db",
+            "tier!=db,tier!=cache",
+        ] {
+            assert!(
+                selector.parse::<LabelSelector>().is_err(),
+                "'{selector}' must be malformed"
+            );
+        }
+        assert_eq!(
+            "app".parse::<LabelSelector>(),
+            Err(LabelError::InvalidTerm { term: "app".into() })
+        );
+    }
+
+    #[test]
+    fn test_selector_matches_labels() {
+        let selector: LabelSelector = "app=web,tier!=db".parse().unwrap();
+        assert!(selector.matches(&labels(&[("app", "web"), ("tier", "front")])));
+        assert!(selector.matches(&labels(&[("app", "web")])));
+        assert!(!selector.matches(&labels(&[("app", "web"), ("tier", "db")])));
+        assert!(!selector.matches(&labels(&[("app", "api")])));
+        assert!(!selector.matches(&labels(&[])));
+
+        assert!(LabelSelector::default().matches(&labels(&[])));
+    }
+
+    #[test]
+    fn test_selector_round_trips_through_filters() {
+        let selector: LabelSelector = "app=web,tier!=db".parse().unwrap();
+        let filter = selector.to_filter();
+        assert_eq!(filter, labels(&[("app", "web"), ("tier!", "db")]));
+        assert_eq!(LabelSelector::from_filter(&filter), Ok(selector));
+
+        assert!(
+            LabelSelector::from_filter(&labels(&[("tier!!", "db")])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_label() {
+        assert!(validate_label("app", "web").is_ok());
+        assert!(validate_label("app.kubernetes.io/name", "my_app-1.0").is_ok());
+        assert!(validate_label("app", "").is_ok());
+
+        assert!(validate_label("", "web").is_err());
+        assert!(validate_label("/app", "web").is_err());
+        assert!(validate_label("a/b/c", "web").is_err());
+        assert!(validate_label(&"a".repeat(64), "web").is_err());
+        assert!(validate_label("app", &"a".repeat(64)).is_err());
+        assert!(validate_label("app", "web!").is_err());
+    }
+}
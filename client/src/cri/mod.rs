@@ -43,4 +43,5 @@
 \* -------------------------------------------------------------------------- */
 
 pub mod image_service;
+pub mod labels;
 pub mod runtime_service;
\ No newline at end of file
@@ -16,5 +16,6 @@
 macros::service!(
     "../api/v0/discovery/discovery.proto",
     discovery,
-    DiscoveryService
+    DiscoveryService,
+    idempotent(Discover, Register, Deregister)
 );
\ No newline at end of file
@@ -0,0 +1,187 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Structured details of the errors auraed returns. They are carried in the
+//! metadata of the [Status], next to its code and message, so clients can
+//! tell errors apart without parsing the message.
+
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::Status;
+
+/// The kind of error, one of the [ErrorDetails] variants.
+const KIND_KEY: &str = "aurae-error";
+/// The [Resource] of [ErrorDetails::NotFound] and
+/// [ErrorDetails::AlreadyExists].
+const RESOURCE_KEY: &str = "aurae-error-resource";
+/// The field of [ErrorDetails::InvalidSpec].
+const FIELD_KEY: &str = "aurae-error-field";
+/// The reason of [ErrorDetails::InvalidSpec].
+const REASON_KEY: &str = "aurae-error-reason";
+/// The cell of [ErrorDetails::CgroupIo].
+const CELL_KEY: &str = "aurae-error-cell";
+
+/// A resource of auraed, named in [ErrorDetails].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resource {
+    Cell(String),
+    Executable(String),
+    Port(u16),
+}
+
+impl Resource {
+    fn encode(&self) -> String {
+        match self {
+            Self::Cell(name) => format!("cell:{name}"),
+            Self::Executable(name) => format!("executable:{name}"),
+            Self::Port(port) => format!("port:{port}"),
+        }
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let (kind, name) = value.split_once(':')?;
+        match kind {
+            "cell" => Some(Self::Cell(name.to_string())),
+            "executable" => Some(Self::Executable(name.to_string())),
+            "port" => name.parse().ok().map(Self::Port),
+            _ => None,
+        }
+    }
+}
+
+/// The structured details of an error returned by auraed.
+///
+/// Decode them from a [Status] with [ErrorDetails::from_status]. Errors
+/// without details decode to `None`, and should be told apart by their
+/// code alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorDetails {
+    /// The resource does not exist.
+    NotFound { resource: Resource },
+    /// The resource exists, or is taken by another cell.
+    AlreadyExists { resource: Resource },
+    /// A field of the request is invalid, for `reason`.
+    InvalidSpec { field: String, reason: String },
+    /// Reading or writing the cgroup of the cell failed.
+    CgroupIo { cell_name: String },
+}
+
+impl ErrorDetails {
+    /// Returns `status` carrying the details. Details with values that
+    /// can't be sent as metadata are left out.
+    pub fn attach(&self, mut status: Status) -> Status {
+        let entries: Result<Vec<_>, _> = self
+            .entries()
+            .into_iter()
+            .map(|(key, value)| {
+                MetadataValue::try_from(value).map(|value| (key, value))
+            })
+            .collect();
+        let Ok(entries) = entries else {
+            return status;
+        };
+
+        let metadata = status.metadata_mut();
+        for (key, value) in entries {
+            let _ = metadata.insert(key, value);
+        }
+
+        status
+    }
+
+    /// Decodes the details `status` carries, if any.
+    pub fn from_status(status: &Status) -> Option<Self> {
+        let metadata = status.metadata();
+        let resource = || Resource::decode(get(metadata, RESOURCE_KEY)?);
+
+        let details = match get(metadata, KIND_KEY)? {
+            "not-found" => Self::NotFound { resource: resource()? },
+            "already-exists" => Self::AlreadyExists { resource: resource()? },
+            "invalid-spec" => Self::InvalidSpec {
+                field: get(metadata, FIELD_KEY)?.to_string(),
+                reason: get(metadata, REASON_KEY)?.to_string(),
+            },
+            "cgroup-io" => Self::CgroupIo {
+                cell_name: get(metadata, CELL_KEY)?.to_string(),
+            },
+            _ => return None,
+        };
+
+        Some(details)
+    }
+
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::NotFound { resource } => vec![
+                (KIND_KEY, "not-found".into()),
+                (RESOURCE_KEY, resource.encode()),
+            ],
+            Self::AlreadyExists { resource } => vec![
+                (KIND_KEY, "already-exists".into()),
+                (RESOURCE_KEY, resource.encode()),
+            ],
+            Self::InvalidSpec { field, reason } => vec![
+                (KIND_KEY, "invalid-spec".into()),
+                (FIELD_KEY, field.clone()),
+                (REASON_KEY, reason.clone()),
+            ],
+            Self::CgroupIo { cell_name } => vec![
+                (KIND_KEY, "cgroup-io".into()),
+                (CELL_KEY, cell_name.clone()),
+            ],
+        }
+    }
+}
+
+fn get<'a>(metadata: &'a MetadataMap, key: &str) -> Option<&'a str> {
+    metadata.get(key)?.to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_details_round_trip() {
+        for details in [
+            ErrorDetails::NotFound {
+                resource: Resource::Cell("parent/child".into()),
+            },
+            ErrorDetails::AlreadyExists {
+                resource: Resource::Executable("sleeper".into()),
+            },
+            ErrorDetails::AlreadyExists { resource: Resource::Port(8080) },
+            ErrorDetails::InvalidSpec {
+                field: "cell.reserved_ports[0]".into(),
+                reason: "invalid".into(),
+            },
+            ErrorDetails::CgroupIo { cell_name: "cell".into() },
+        ] {
+            let status = details.attach(Status::internal("error"));
+            assert_eq!(ErrorDetails::from_status(&status), Some(details));
+        }
+    }
+
+    #[test]
+    fn test_status_without_details() {
+        assert_eq!(ErrorDetails::from_status(&Status::internal("error")), None);
+
+        // Details that can't be sent are left out
+        let status = ErrorDetails::NotFound {
+            resource: Resource::Executable("sleeper\n".into()),
+        }
+        .attach(Status::not_found("error"));
+        assert!(status.metadata().is_empty());
+    }
+}
@@ -13,4 +13,9 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-macros::service!("../api/grpc/health/v1/health.proto", grpc::health, Health);
\ No newline at end of file
+macros::service!(
+    "../api/grpc/health/v1/health.proto",
+    grpc::health,
+    Health,
+    idempotent(Check)
+);
\ No newline at end of file
@@ -13,13 +13,22 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 pub use crate::client::{Client, ClientError};
-pub use config::{AuraeConfig, AuraeSocket, AuthConfig, SystemConfig};
+pub use crate::connection::{ConnectionState, Disconnected, RetryPolicy};
+pub use crate::error_details::{ErrorDetails, Resource};
+pub use crate::node::DEFAULT_NODE_TTL;
+pub use config::{
+    AuraeConfig, AuraeSocket, AuthConfig, ClientCertDetails, SystemConfig,
+};
 
 pub mod cells;
 mod client;
 mod config;
+mod connection;
 pub mod cri;
 pub mod discovery;
+mod error_details;
 pub mod grpc;
+mod node;
 pub mod observe;
+mod tls;
 pub mod vms;
\ No newline at end of file
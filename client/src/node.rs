@@ -0,0 +1,278 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Points a [Client] at another node of the cluster by name. The address of
+//! the node is resolved with the discovery service of an auraed the node
+//! registers with, and cached for a while, so that each call for the same
+//! node does not resolve it again.
+
+use crate::discovery::discovery_service::DiscoveryServiceClient;
+use crate::{AuraeSocket, Client, ClientError, Disconnected};
+use proto::discovery::DiscoverRequest;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the address of a node is cached for, by default.
+pub const DEFAULT_NODE_TTL: Duration = Duration::from_secs(30);
+
+/// Resolves the names of nodes to their addresses, for [Client::with_node].
+#[derive(Debug, Clone)]
+pub(crate) struct Nodes {
+    /// The client of the auraed to resolve the names with. The client
+    /// pointed at another node resolves them itself if [None].
+    pub(crate) discovery: Option<Arc<Client>>,
+    /// How long a resolved address is used for before it is resolved again.
+    pub(crate) ttl: Duration,
+    /// The addresses of the nodes last discovered, and when.
+    cache: Arc<Mutex<HashMap<String, (SocketAddr, Instant)>>>,
+}
+
+impl Default for Nodes {
+    fn default() -> Self {
+        Self { discovery: None, ttl: DEFAULT_NODE_TTL, cache: Arc::default() }
+    }
+}
+
+impl Nodes {
+    /// The address of the node `name`, from the cache unless it expired.
+    /// Otherwise every node discovered is cached again.
+    async fn resolve(
+        &self,
+        client: &Client,
+        name: &str,
+    ) -> Result<SocketAddr, ClientError> {
+        {
+            let cache = self.cache.lock().expect("poisoned");
+            if let Some((addr, resolved)) = cache.get(name) {
+                if resolved.elapsed() < self.ttl {
+                    return Ok(*addr);
+                }
+            }
+        }
+
+        let discovery = self.discovery.as_deref().unwrap_or(client);
+        let peers = discovery
+            .discover(DiscoverRequest {})
+            .await
+            .map_err(|status| match Disconnected::try_from(status) {
+                Ok(disconnected) => disconnected.into(),
+                Err(status) => ClientError::Other(anyhow::anyhow!(
+                    "failed to discover the nodes: {}",
+                    status.message()
+                )),
+            })?
+            .into_inner()
+            .peers;
+
+        let now = Instant::now();
+        let mut cache = self.cache.lock().expect("poisoned");
+        cache.clear();
+        for peer in peers {
+            // A peer with an address we can't dial is left unknown
+            if let Ok(addr) = peer.address.parse() {
+                let _ = cache.insert(peer.name, (addr, now));
+            }
+        }
+
+        match cache.get(name) {
+            Some((addr, _)) => Ok(*addr),
+            None => {
+                let mut known: Vec<_> = cache.keys().cloned().collect();
+                known.sort();
+                Err(ClientError::NodeNotFound { node: name.into(), known })
+            }
+        }
+    }
+}
+
+impl Client {
+    /// A client of the node `name`, as registered with the discovery
+    /// service. It dials the node with the TLS material this client has
+    /// at the time, and has the same timeout and retry policy.
+    ///
+    /// Fails with [ClientError::NodeNotFound] if no node by that name is
+    /// registered.
+    pub async fn with_node(&self, name: &str) -> Result<Self, ClientError> {
+        let addr = self.nodes.resolve(self, name).await?;
+
+        let (tls_config, client_cert_details) = {
+            let transport = self.transport.read().expect("poisoned");
+            (
+                transport.tls_config.clone(),
+                transport.client_cert_details.clone(),
+            )
+        };
+
+        let mut client = Self::connect(
+            AuraeSocket::Addr(addr),
+            tls_config,
+            client_cert_details,
+        )?;
+        client.expiry_warning = self.expiry_warning;
+        client.timeout = self.timeout;
+        client.retry_policy = self.retry_policy;
+        // The client of the node resolves names as this client does
+        client.nodes = Nodes {
+            discovery: self
+                .nodes
+                .discovery
+                .clone()
+                .or_else(|| Some(Arc::new(self.clone()))),
+            ..self.nodes.clone()
+        };
+        Ok(client)
+    }
+
+    /// Resolves the names of nodes with the discovery service of the
+    /// auraed of `discovery`, rather than with that of this client.
+    pub fn with_discovery(mut self, discovery: Client) -> Self {
+        self.nodes.discovery = Some(Arc::new(discovery));
+        self
+    }
+
+    /// Caches the address of each node for `ttl` once resolved, rather
+    /// than for [DEFAULT_NODE_TTL].
+    pub fn with_node_ttl(mut self, ttl: Duration) -> Self {
+        self.nodes.ttl = ttl;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::discovery::{
+        discovery_service_server::{DiscoveryService, DiscoveryServiceServer},
+        DeregisterRequest, DeregisterResponse, DiscoverResponse, Peer,
+        RegisterRequest, RegisterResponse, WatchPeersRequest,
+        WatchPeersResponse,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_stream::wrappers::{ReceiverStream, UnixListenerStream};
+    use tonic::{Request, Response, Status};
+
+    /// Discovers the same peers, counting how often.
+    struct Peers {
+        peers: Vec<Peer>,
+        discovered: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl DiscoveryService for Peers {
+        async fn discover(
+            &self,
+            _: Request<DiscoverRequest>,
+        ) -> Result<Response<DiscoverResponse>, Status> {
+            let _ = self.discovered.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(DiscoverResponse {
+                healthy: true,
+                peers: self.peers.clone(),
+                ..Default::default()
+            }))
+        }
+
+        async fn register(
+            &self,
+            _: Request<RegisterRequest>,
+        ) -> Result<Response<RegisterResponse>, Status> {
+            Err(Status::unimplemented("register"))
+        }
+
+        async fn deregister(
+            &self,
+            _: Request<DeregisterRequest>,
+        ) -> Result<Response<DeregisterResponse>, Status> {
+            Err(Status::unimplemented("deregister"))
+        }
+
+        type WatchPeersStream =
+            ReceiverStream<Result<WatchPeersResponse, Status>>;
+
+        async fn watch_peers(
+            &self,
+            _: Request<WatchPeersRequest>,
+        ) -> Result<Response<Self::WatchPeersStream>, Status> {
+            Err(Status::unimplemented("watch_peers"))
+        }
+    }
+
+    fn peer(name: &str, address: &str) -> Peer {
+        Peer {
+            name: name.into(),
+            address: address.into(),
+            ..Default::default()
+        }
+    }
+
+    async fn serve(name: &str, peers: Vec<Peer>) -> (Client, Arc<AtomicUsize>) {
+        let path = std::env::temp_dir().join(format!(
+            "ae-test-client-node-{name}-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let discovered = Arc::new(AtomicUsize::new(0));
+        let _server = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(DiscoveryServiceServer::new(Peers {
+                    peers,
+                    discovered: discovered.clone(),
+                }))
+                .serve_with_incoming(UnixListenerStream::new(listener)),
+        );
+
+        let client = Client::new_no_tls(AuraeSocket::Unix(path)).await.unwrap();
+        (client, discovered)
+    }
+
+    #[tokio::test]
+    async fn test_with_node_caches_the_address() {
+        let (client, discovered) =
+            serve("cache", vec![peer("alpha", "[::1]:8080")]).await;
+
+        let _ = client.with_node("alpha").await.unwrap();
+        let _ = client.with_node("alpha").await.unwrap();
+        assert_eq!(discovered.load(Ordering::SeqCst), 1);
+
+        // Resolved again once expired
+        let client = client.with_node_ttl(Duration::ZERO);
+        let _ = client.with_node("alpha").await.unwrap();
+        assert_eq!(discovered.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_with_unknown_node_lists_the_known_nodes() {
+        let (client, _) = serve(
+            "unknown",
+            vec![
+                peer("beta", "10.0.0.2:8080"),
+                peer("alpha", "10.0.0.1:8080"),
+                peer("gamma", "not an address"),
+            ],
+        )
+        .await;
+
+        let Err(e) = client.with_node("delta").await else {
+            panic!("expected an error");
+        };
+        assert!(
+            matches!(&e, ClientError::NodeNotFound { node, known }
+                if node == "delta" && *known == ["alpha", "beta"]),
+            "{e:?}"
+        );
+    }
+}
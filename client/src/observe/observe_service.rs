@@ -13,4 +13,15 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-macros::service!("../api/v0/observe/observe.proto", observe, ObserveService);
\ No newline at end of file
+macros::service!(
+    "../api/v0/observe/observe.proto",
+    observe,
+    ObserveService,
+    idempotent(
+        GetLogLevel,
+        GetTlsStatus,
+        GetRedactionStats,
+        GetBlockingPoolStats,
+        ReloadTls
+    )
+);
\ No newline at end of file
@@ -0,0 +1,223 @@
+/* -------------------------------------------------------------------------- *\
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ * -------------------------------------------------------------------------- *
+ * Copyright 2022 - 2024, the aurae contributors                              *
+ * SPDX-License-Identifier: Apache-2.0                                        *
+\* -------------------------------------------------------------------------- */
+
+//! Keeps the TLS material of a [Client] current. The client certificate,
+//! its key and the CA certificate are reloaded when the files change, so
+//! that certificates may be rotated under a running client. Calls made
+//! after a reload use a new channel, while calls in flight finish on the
+//! old one. Material which fails to load is ignored, and the client keeps
+//! using what it had.
+
+use crate::client::Transport;
+use crate::config::{AuthConfig, CertMaterial, ClientCertDetails};
+use crate::{AuraeSocket, Client};
+use anyhow::Context;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+use tracing::warn;
+
+/// The TLS config of a client, and details of the certificate in it.
+pub(crate) async fn load(
+    auth: &AuthConfig,
+) -> anyhow::Result<(ClientTlsConfig, ClientCertDetails)> {
+    let cert_material = auth.to_cert_material().await?;
+    let client_cert_details = cert_material.get_client_cert_details()?;
+
+    let CertMaterial { server_root_ca_cert, client_cert, client_key } =
+        cert_material;
+
+    let tls_config = ClientTlsConfig::new()
+        // TODO: get this from the config or the cert information somehow
+        .domain_name("server.unsafe.aurae.io")
+        .ca_certificate(Certificate::from_pem(server_root_ca_cert))
+        .identity(Identity::from_pem(client_cert, client_key));
+
+    Ok((tls_config, client_cert_details))
+}
+
+/// When the client certificate expires, if within `window` from now.
+pub(crate) fn expiring(
+    details: &ClientCertDetails,
+    window: Duration,
+) -> Option<SystemTime> {
+    let now = SystemTime::now();
+    (details.not_after <= now + window).then_some(details.not_after)
+}
+
+/// Warns if the client certificate expires within `window`.
+pub(crate) fn warn_expiring(details: &ClientCertDetails, window: Duration) {
+    let Some(not_after) = expiring(details, window) else {
+        return;
+    };
+
+    let name = &details.subject_common_name;
+    match not_after.duration_since(SystemTime::now()) {
+        Ok(left) => {
+            warn!("client certificate '{name}' expires in {}s", left.as_secs())
+        }
+        Err(_) => warn!("client certificate '{name}' expired"),
+    }
+}
+
+/// The modification times and lengths of the files, to notice changes.
+fn stamp(auth: &AuthConfig) -> Vec<Option<(SystemTime, u64)>> {
+    [&auth.ca_crt, &auth.client_crt, &auth.client_key]
+        .into_iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(Path::new(path)).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+/// Checks the files in `auth` for changes every `interval`, and reloads
+/// them into `client` when they do. Stops once every clone of `client` is
+/// dropped.
+pub(crate) fn watch(
+    client: &Client,
+    socket: AuraeSocket,
+    auth: AuthConfig,
+    interval: Duration,
+) {
+    let transport = Arc::downgrade(&client.transport);
+    let state = client.state.clone();
+    let mut last = stamp(&auth);
+
+    let _ignored = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            if transport.strong_count() == 0 {
+                return;
+            }
+
+            let current = stamp(&auth);
+            if current == last {
+                continue;
+            }
+            // A failed reload is not retried until the files change again,
+            // such as when the rest of the material is written
+            last = current;
+
+            let res = async {
+                let (tls_config, client_cert_details) = load(&auth).await?;
+                let channel = Client::connect_chan(
+                    socket.clone(),
+                    Some(tls_config.clone()),
+                    state.clone(),
+                )
+                .context("failed to configure TLS")?;
+                anyhow::Ok((channel, tls_config, client_cert_details))
+            }
+            .await;
+
+            let Some(transport) = transport.upgrade() else {
+                return;
+            };
+            match res {
+                Ok((channel, tls_config, client_cert_details)) => {
+                    warn_expiring(&client_cert_details, auth.expiry_warning);
+                    *transport.write().expect("poisoned") = Transport {
+                        channel,
+                        tls_config: Some(tls_config),
+                        client_cert_details: Some(client_cert_details),
+                    };
+                }
+                Err(e) => warn!(
+                    "failed to reload TLS material, keeping the previous: {e:#}"
+                ),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuraeConfig, SystemConfig};
+    use std::path::PathBuf;
+
+    const PKI: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/pki");
+
+    fn auth(dir: &Path, name: &str) -> AuthConfig {
+        AuthConfig {
+            ca_crt: format!("{}/ca.crt", dir.display()),
+            client_crt: format!("{}/_signed.client.{name}.crt", dir.display()),
+            client_key: format!("{}/client.{name}.key", dir.display()),
+            ..Default::default()
+        }
+    }
+
+    fn common_name(client: &Client) -> String {
+        client.client_cert_details().unwrap().subject_common_name.clone()
+    }
+
+    #[tokio::test]
+    async fn test_expiring_within_window() {
+        let (_, details) = load(&auth(Path::new(PKI), "alice")).await.unwrap();
+        assert_eq!(
+            expiring(&details, AuthConfig::default().expiry_warning),
+            None
+        );
+
+        let century = Duration::from_secs(200 * 365 * 24 * 60 * 60);
+        assert_eq!(expiring(&details, century), Some(details.not_after));
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_previous_material_on_failure() {
+        let dir = std::env::temp_dir()
+            .join(format!("ae-test-client-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let copy = |from: &str, to: &str| {
+            std::fs::copy(PathBuf::from(PKI).join(from), dir.join(to)).unwrap();
+        };
+        copy("ca.crt", "ca.crt");
+        copy("_signed.client.alice.crt", "_signed.client.current.crt");
+        copy("client.alice.key", "client.current.key");
+
+        let config = AuraeConfig {
+            auth: AuthConfig {
+                reload_interval: Some(Duration::from_millis(20)),
+                ..auth(&dir, "current")
+            },
+            system: SystemConfig {
+                socket: AuraeSocket::Path(dir.join("missing.sock")),
+                discovery: None,
+            },
+        };
+        let client = Client::new(config).await.unwrap();
+        assert_eq!(common_name(&client), "alice.unsafe.aurae.io");
+        assert_eq!(client.cert_expiring(), None);
+
+        copy("_signed.client.bob.crt", "_signed.client.current.crt");
+        copy("client.bob.key", "client.current.key");
+        for _ in 0..250 {
+            if common_name(&client) == "bob.unsafe.aurae.io" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(common_name(&client), "bob.unsafe.aurae.io");
+
+        std::fs::write(dir.join("_signed.client.current.crt"), "garbage")
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(common_name(&client), "bob.unsafe.aurae.io");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
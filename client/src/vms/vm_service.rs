@@ -13,4 +13,9 @@
  * SPDX-License-Identifier: Apache-2.0                                        *
 \* -------------------------------------------------------------------------- */
 
-macros::service!("../api/v0/vms/vms.proto", vms, VmService);
\ No newline at end of file
+macros::service!(
+    "../api/v0/vms/vms.proto",
+    vms,
+    VmService,
+    idempotent(List, Status)
+);
\ No newline at end of file
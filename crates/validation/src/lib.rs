@@ -150,9 +150,47 @@ impl ValidationError {
     }
 }
 
+impl ValidationError {
+    /// Why the field is invalid, without the field.
+    pub fn reason(&self) -> String {
+        match self {
+            Self::Required { .. } => String::from("required"),
+            Self::Minimum { minimum, units, .. } => {
+                format!("minimum {minimum} {units}").trim_end().to_string()
+            }
+            Self::Maximum { maximum, units, .. } => {
+                format!("maximum {maximum} {units}").trim_end().to_string()
+            }
+            #[cfg(feature = "regex")]
+            Self::AllowRegexViolation { pattern, .. } => {
+                format!("does not match {pattern}")
+            }
+            Self::Invalid { .. } => String::from("invalid"),
+        }
+    }
+}
+
+/// Carries the field and reason as the `invalid-spec` details that
+/// `client::ErrorDetails` decodes.
 #[cfg(feature = "tonic")]
 impl From<ValidationError> for tonic::Status {
     fn from(e: ValidationError) -> Self {
-        Self::failed_precondition(e.to_string())
+        let mut status = Self::failed_precondition(e.to_string());
+
+        let entries = [
+            ("aurae-error", "invalid-spec".parse()),
+            ("aurae-error-field", e.get_field().parse()),
+            ("aurae-error-reason", e.reason().parse()),
+        ];
+        if entries.iter().all(|(_, value)| value.is_ok()) {
+            let metadata = status.metadata_mut();
+            for (key, value) in entries {
+                if let Ok(value) = value {
+                    let _ = metadata.insert(key, value);
+                }
+            }
+        }
+
+        status
     }
 }
\ No newline at end of file
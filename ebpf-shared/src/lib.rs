@@ -28,6 +28,10 @@ pub struct Signal {
     pub cgroup_id: u64,
     pub signum: i32,
     pub pid: i32,
+    /// The thread group of the task that generated the signal.
+    pub sender_pid: i32,
+    /// The si_code of the signal, e.g. SI_USER for kill(2).
+    pub code: i32,
 }
 
 impl HasCgroup for Signal {
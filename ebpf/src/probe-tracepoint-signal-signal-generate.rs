@@ -60,6 +60,7 @@ static mut SIGNALS: PerfEventArray<Signal> = PerfEventArray::<Signal>::new(0);
 //      - 5.4  https://github.com/torvalds/linux/blob/v5.4/include/trace/events/signal.h
 //      - 5.0  https://github.com/torvalds/linux/blob/v5.0/include/trace/events/signal.h
 const SIGNAL_OFFSET: usize = 8;
+const CODE_OFFSET: usize = 16;
 const PID_OFFSET: usize = 36;
 
 #[tracepoint(name = "signal_signal_generate", category = "signal")]
@@ -78,6 +79,13 @@ fn try_signals(ctx: TracePointContext) -> Result<u32, u32> {
         }
     };
 
+    let code: i32 = unsafe {
+        match ctx.read_at(CODE_OFFSET) {
+            Ok(s) => s,
+            Err(errn) => return Err(errn as u32),
+        }
+    };
+
     let pid: i32 = unsafe {
         match ctx.read_at(PID_OFFSET) {
             Ok(s) => s,
@@ -85,9 +93,11 @@ fn try_signals(ctx: TracePointContext) -> Result<u32, u32> {
         }
     };
 
+    // The tracepoint fires in the context of the task generating the signal
     let cgroup_id = unsafe { helpers::bpf_get_current_cgroup_id() };
+    let sender_pid = (helpers::bpf_get_current_pid_tgid() >> 32) as i32;
 
-    let s = Signal { cgroup_id, signum, pid };
+    let s = Signal { cgroup_id, signum, pid, sender_pid, code };
     unsafe {
         SIGNALS.output(&ctx, &s, 0);
     }
@@ -0,0 +1,59 @@
+#!/usr/bin/env auraescript
+/* -------------------------------------------------------------------------- *\
+ *        Apache 2.0 License Copyright © 2022-2023 The Aurae Authors          *
+ *                                                                            *
+ *                +--------------------------------------------+              *
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ *                                                                            *
+ * -------------------------------------------------------------------------- *
+ *                                                                            *
+ *   Licensed under the Apache License, Version 2.0 (the "License");          *
+ *   you may not use this file except in compliance with the License.         *
+ *   You may obtain a copy of the License at                                  *
+ *                                                                            *
+ *       http://www.apache.org/licenses/LICENSE-2.0                           *
+ *                                                                            *
+ *   Unless required by applicable law or agreed to in writing, software      *
+ *   distributed under the License is distributed on an "AS IS" BASIS,        *
+ *   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. *
+ *   See the License for the specific language governing permissions and      *
+ *   limitations under the License.                                           *
+ *                                                                            *
+\* -------------------------------------------------------------------------- */
+import * as helpers from "../auraescript/gen/helpers.ts";
+
+// [ Run ]
+// Allocates the cell, and starts the executable in it
+let echo = await helpers.cells.run("ae-helpers-cell", "echo 'hello world'", {
+    cpuWeight: 100,
+});
+console.log(`started ${echo.name} (pid ${echo.pid})`);
+await echo.cell.free();
+
+// [ With Cell ]
+// The cell is freed once the callback returns, or throws
+await helpers.withCell("ae-helpers-cell", async (cell) => {
+    let sleep = await cell.start("sleep 60", { name: "sleeper" });
+    console.log(`started ${sleep.name} (pid ${sleep.pid})`);
+    await sleep.stop();
+}, { memoryMax: 64 * 1024 * 1024 });
+
+// [ Validation ]
+// Invalid arguments are rejected before calling auraed
+try {
+    await helpers.cells.run("-not-a-cell-", "echo unreachable");
+} catch (e) {
+    if (e instanceof helpers.ValidationError) {
+        console.log(e.message);
+    } else {
+        throw e;
+    }
+}
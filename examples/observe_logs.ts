@@ -0,0 +1,92 @@
+#!/usr/bin/env auraescript
+/* -------------------------------------------------------------------------- *\
+ *        Apache 2.0 License Copyright © 2022-2023 The Aurae Authors          *
+ *                                                                            *
+ *                +--------------------------------------------+              *
+ *                |   █████╗ ██╗   ██╗██████╗  █████╗ ███████╗ |              *
+ *                |  ██╔══██╗██║   ██║██╔══██╗██╔══██╗██╔════╝ |              *
+ *                |  ███████║██║   ██║██████╔╝███████║█████╗   |              *
+ *                |  ██╔══██║██║   ██║██╔══██╗██╔══██║██╔══╝   |              *
+ *                |  ██║  ██║╚██████╔╝██║  ██║██║  ██║███████╗ |              *
+ *                |  ╚═╝  ╚═╝ ╚═════╝ ╚═╝  ╚═╝╚═╝  ╚═╝╚══════╝ |              *
+ *                +--------------------------------------------+              *
+ *                                                                            *
+ *                         Distributed Systems Runtime                        *
+ *                                                                            *
+ * -------------------------------------------------------------------------- *
+ *                                                                            *
+ *   Licensed under the Apache License, Version 2.0 (the "License");          *
+ *   you may not use this file except in compliance with the License.         *
+ *   You may obtain a copy of the License at                                  *
+ *                                                                            *
+ *       http://www.apache.org/licenses/LICENSE-2.0                           *
+ *                                                                            *
+ *   Unless required by applicable law or agreed to in writing, software      *
+ *   distributed under the License is distributed on an "AS IS" BASIS,        *
+ *   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. *
+ *   See the License for the specific language governing permissions and      *
+ *   limitations under the License.                                           *
+ *                                                                            *
+\* -------------------------------------------------------------------------- */
+import * as cells from "../auraescript/gen/cells.ts";
+import * as observe from "../auraescript/gen/observe.ts";
+import * as aurae from "../auraescript/gen/aurae.ts";
+
+let client = await aurae.createClient();
+
+let cellService = new cells.CellServiceClient(client);
+let observeService = new observe.ObserveServiceClient(client);
+let cellName = "ae-logs-cell";
+let executableName = "ae-logs-clock";
+
+// [ Allocate ]
+await cellService.allocate(<cells.CellServiceAllocateRequest>{
+    cell: cells.Cell.fromPartial({
+        name: cellName,
+    })
+});
+
+// [ Start ]
+await cellService.start(<cells.CellServiceStartRequest>{
+    cellName,
+    executable: cells.Executable.fromPartial({
+        command: "while true; do date; sleep 1; done",
+        description: "outputs the time every second",
+        name: executableName,
+    })
+});
+
+// [ Tail ]
+// The stream is pulled a line at a time, and breaking out of the loop
+// cancels it.
+let lines = 0;
+try {
+    for await (const response of observeService.getSubProcessStream(<observe.GetSubProcessStreamRequest>{
+        channelType: observe.LogChannelType.LOG_CHANNEL_TYPE_UNSPECIFIED,
+        cellName,
+        executableName,
+        since: 0, // replay the output from the start
+    })) {
+        console.log(response.item?.line);
+        if (++lines == 5) {
+            break;
+        }
+    }
+} catch (e) {
+    if (e instanceof aurae.GrpcError) {
+        console.log(`failed to tail ${executableName}: ${e.code}: ${e.message}`);
+    } else {
+        throw e;
+    }
+}
+
+// [ Stop ]
+await cellService.stop(<cells.CellServiceStopRequest>{
+    cellName,
+    executableName,
+});
+
+// [ Free ]
+await cellService.free(<cells.CellServiceFreeRequest>{
+    cellName
+});
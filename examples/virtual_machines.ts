@@ -41,7 +41,7 @@ function wait(ms) {
 const client = await aurae.createClient();
 const vmService = new vms.VmServiceClient(client);
 
-let vm = await vmService.create(<vms.VmServiceCreateRequest>{
+let vm = await vmService.allocate(<vms.VmServiceAllocateRequest>{
     machine: vms.VirtualMachine.fromPartial({
         id: "ae-sleeper-vm",
         vcpuCount: 2,
@@ -53,7 +53,7 @@ let vm = await vmService.create(<vms.VmServiceCreateRequest>{
         }),
     })
 });
-console.log('Created VM:', vm)
+console.log('Allocated VM:', vm)
 
 // Start and list the VMs
 let created = await vmService.start(<vms.VmServiceStartRequest>{ vmId: "ae-sleeper-vm" });
@@ -65,11 +65,11 @@ wait(5000);
 let machines = await vmService.list(<vms.VmServiceListRequest>{});
 console.log('Listed VMs:', machines)
 
-// Stop the VM
+// Stop the VM, waiting for it to power off
 await vmService.stop(<vms.VmServiceStopRequest>{ vmId: "ae-sleeper-vm" });
 
-// Wait 5s for the Vm to stop
-wait(5000);
+let status = await vmService.status(<vms.VmServiceStatusRequest>{ vmId: "ae-sleeper-vm" });
+console.log('Stopped VM:', status)
 
 // Free the VM
 await vmService.free(<vms.VmServiceFreeRequest>{ vmId: "ae-sleeper-vm" });